@@ -0,0 +1,291 @@
+use chrono::NaiveDate;
+use rust_decimal::Decimal;
+use serde::Deserialize;
+
+/// A bank statement exported as CSV, whose rows replace the forecast up to
+/// the last day it covers
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct Statement {
+    pub file: String,
+    // Account the statement is for
+    pub account: String,
+    // Headings of the columns to read
+    #[serde(default)]
+    pub columns: Columns,
+    // How the dates are written, in chrono's format, e.g. `%d/%m/%Y`
+    #[serde(default = "default_date_format")]
+    pub date_format: String,
+    // Where each row's money came from or went to: the account of the first
+    // rule whose text its description contains, ignoring case
+    #[serde(default)]
+    pub rules: Vec<Rule>,
+    // Account for rows no rule matches
+    #[serde(default = "default_otherwise")]
+    pub otherwise: String,
+}
+
+/// What a generator actually paid on a date, which the forecast uses instead
+/// of what it would have paid, e.g. `{generator: energy_bill, date: 2025-02-01, amount: 243.17}`
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct Override {
+    // Name of the generator
+    pub generator: String,
+    pub date: NaiveDate,
+    pub amount: Decimal,
+}
+
+/// Headings of a statement's columns. Money in and out is either one signed
+/// `amount` column or separate `debit` (out) and `credit` (in) columns.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct Columns {
+    #[serde(default = "default_date_column")]
+    pub date: String,
+    #[serde(default = "default_description_column")]
+    pub description: String,
+    #[serde(default = "default_amount_column")]
+    pub amount: String,
+    #[serde(default)]
+    pub debit: Option<String>,
+    #[serde(default)]
+    pub credit: Option<String>,
+    // Running balance after each row, if the statement has one
+    #[serde(default)]
+    pub balance: Option<String>,
+}
+
+impl Default for Columns {
+    fn default() -> Self {
+        Columns {
+            date: default_date_column(),
+            description: default_description_column(),
+            amount: default_amount_column(),
+            debit: None,
+            credit: None,
+            balance: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct Rule {
+    pub contains: String,
+    pub account: String,
+}
+
+fn default_date_format() -> String {
+    "%Y-%m-%d".to_string()
+}
+
+fn default_otherwise() -> String {
+    "uncategorised".to_string()
+}
+
+fn default_date_column() -> String {
+    "Date".to_string()
+}
+
+fn default_description_column() -> String {
+    "Description".to_string()
+}
+
+fn default_amount_column() -> String {
+    "Amount".to_string()
+}
+
+/// A line of a statement
+#[derive(Debug, Clone, PartialEq)]
+pub struct Row {
+    pub date: NaiveDate,
+    pub description: String,
+    // Paid into the statement's account; negative if paid out
+    pub amount: Decimal,
+    pub balance: Option<Decimal>,
+    // Account the money came from or went to
+    pub counterpart: String,
+}
+
+/// The fields of a CSV line, which may be quoted to hold commas, with `""`
+/// for a quote inside quotes
+fn fields(line: &str) -> Vec<String> {
+    let mut fields = vec![String::new()];
+    let mut quoted = false;
+    let mut chars = line.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted && chars.peek() == Some(&'"') => {
+                chars.next();
+                fields.last_mut().unwrap().push('"');
+            }
+            '"' => quoted = !quoted,
+            ',' if !quoted => fields.push(String::new()),
+            c => fields.last_mut().unwrap().push(c),
+        }
+    }
+    fields.into_iter().map(|field| field.trim().to_string()).collect()
+}
+
+/// An amount as banks write them, e.g. `£1,234.50` or `-12.00`; blank is zero
+fn amount(text: &str) -> Option<Decimal> {
+    let digits: String = text.chars().filter(|c| c.is_ascii_digit() || *c == '.' || *c == '-').collect();
+    if digits.is_empty() { Some(Decimal::ZERO) } else { digits.parse().ok() }
+}
+
+/// The rows of `statement` from its CSV `text`, in date order, skipping `#`
+/// comments and taking the first other line as the headings. Statements
+/// listed newest first are turned round, so each day's last row is its latest.
+pub fn parse(statement: &Statement, text: &str) -> Result<Vec<Row>, String> {
+    let mut lines = text.lines().enumerate().filter(|(_, line)| !line.trim().is_empty() && !line.trim().starts_with('#'));
+    let headings = lines.next().map(|(_, line)| fields(line)).unwrap_or_default();
+    let column = |heading: &str| {
+        headings.iter().position(|h| h.eq_ignore_ascii_case(heading)).ok_or_else(|| format!("No column headed '{heading}'"))
+    };
+    let columns = &statement.columns;
+    let date_column = column(&columns.date)?;
+    let description_column = column(&columns.description)?;
+    let money_columns = match (&columns.debit, &columns.credit) {
+        (None, None) => (Some(column(&columns.amount)?), None, None),
+        (debit, credit) => (None, debit.as_deref().map(column).transpose()?, credit.as_deref().map(column).transpose()?),
+    };
+    let balance_column = columns.balance.as_deref().map(column).transpose()?;
+
+    let mut rows = Vec::new();
+    for (number, line) in lines {
+        let fields = fields(line);
+        let field = |index: usize| fields.get(index).map(String::as_str).unwrap_or_default();
+        let money = |index: Option<usize>| index.map(|index| amount_or_error(field(index), number)).transpose().map(Option::unwrap_or_default);
+        let date = NaiveDate::parse_from_str(field(date_column), &statement.date_format)
+            .map_err(|_| format!("Line {}: '{}' is not a date like {}", number + 1, field(date_column), statement.date_format))?;
+        let (signed, debit, credit) = money_columns;
+        // A debit column may hold its amounts as positive or negative numbers; they're paid out either way
+        let amount = money(signed)? - money(debit)?.abs() + money(credit)?;
+        let balance = balance_column.map(|index| amount_or_error(field(index), number)).transpose()?;
+        let description = field(description_column).to_string();
+        let counterpart = statement
+            .rules
+            .iter()
+            .find(|rule| description.to_lowercase().contains(&rule.contains.to_lowercase()))
+            .map_or(&statement.otherwise, |rule| &rule.account)
+            .clone();
+        rows.push(Row { date, description, amount, balance, counterpart });
+    }
+    if rows.first().zip(rows.last()).is_some_and(|(first, last)| first.date > last.date) {
+        rows.reverse();
+    }
+    rows.sort_by_key(|row| row.date);
+    Ok(rows)
+}
+
+fn amount_or_error(text: &str, number: usize) -> Result<Decimal, String> {
+    amount(text).ok_or_else(|| format!("Line {}: '{text}' is not an amount", number + 1))
+}
+
+/// Each day's balances from the day after `start` up to the last row of any
+/// statement, from `opening` with each row after `start` paid between its
+/// statement's account and its counterpart. Where a statement gives running
+/// balances, its account is set to each day's last one, and whatever that
+/// changes is put down to `adjustments`, as if the opening balance were wrong.
+pub fn history(
+    opening: &crate::balances::Balances,
+    start: NaiveDate,
+    statements: &[(&Statement, Vec<Row>)],
+    adjustments: &str,
+) -> crate::History {
+    let mut balances = opening.to_map();
+    for (statement, rows) in statements {
+        balances.entry(statement.account.clone()).or_insert(Decimal::ZERO);
+        for row in rows {
+            balances.entry(row.counterpart.clone()).or_insert(Decimal::ZERO);
+        }
+    }
+    balances.entry(adjustments.to_string()).or_insert(Decimal::ZERO);
+    let Some(end) = statements.iter().filter_map(|(_, rows)| rows.last()).map(|row| row.date).max() else {
+        return Vec::new();
+    };
+    let mut balances = crate::balances::Balances::from(balances);
+    let mut history = Vec::new();
+    for date in start.iter_days().skip(1).take_while(|date| *date <= end) {
+        for (statement, rows) in statements {
+            let today: Vec<&Row> = rows.iter().filter(|row| row.date == date).collect();
+            for row in &today {
+                crate::post(&mut balances, &row.counterpart, &statement.account, row.amount);
+            }
+            if let Some(balance) = today.last().and_then(|row| row.balance) {
+                let difference = balance - balances[&statement.account];
+                crate::post(&mut balances, adjustments, &statement.account, difference);
+            }
+        }
+        history.push((date, balances.clone()));
+    }
+    history
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::balances::Balances;
+    use rust_decimal_macros::dec;
+
+    fn statement(columns: Columns, date_format: &str) -> Statement {
+        Statement {
+            file: "statement.csv".to_string(),
+            account: "main".to_string(),
+            columns,
+            date_format: date_format.to_string(),
+            rules: vec![Rule { contains: "tesco".to_string(), account: "groceries_expenditure".to_string() }],
+            otherwise: default_otherwise(),
+        }
+    }
+
+    #[test]
+    fn test_parse() {
+        let date = |d| NaiveDate::from_ymd_opt(2025, 1, d).unwrap();
+        // Newest first, with separate money in and out columns
+        let columns = Columns {
+            debit: Some("Paid out".to_string()),
+            credit: Some("Paid in".to_string()),
+            balance: Some("Balance".to_string()),
+            ..Columns::default()
+        };
+        let text = "\
+Date,Description,Paid out,Paid in,Balance
+03/01/2025,\"TESCO STORES, LEEDS\",\"£1,020.50\",,\"£979.50\"
+02/01/2025,SALARY,,2000.00,2000.00
+";
+        let rows = parse(&statement(columns, "%d/%m/%Y"), text).unwrap();
+        assert_eq!(
+            rows,
+            vec![
+                Row { date: date(2), description: "SALARY".to_string(), amount: dec!(2000.00), balance: Some(dec!(2000.00)), counterpart: "uncategorised".to_string() },
+                Row {
+                    date: date(3),
+                    description: "TESCO STORES, LEEDS".to_string(),
+                    amount: dec!(-1020.50),
+                    balance: Some(dec!(979.50)),
+                    counterpart: "groceries_expenditure".to_string()
+                },
+            ]
+        );
+        assert_eq!(fields("a,\"say \"\"hi\"\"\",c"), vec!["a", "say \"hi\"", "c"]);
+
+        let plain = statement(Columns::default(), "%Y-%m-%d");
+        assert_eq!(parse(&plain, "Date,Description,Amount\n2025-01-02,Tesco,-10\n").unwrap()[0].amount, dec!(-10));
+        assert_eq!(parse(&plain, "Date,Amount\n"), Err("No column headed 'Description'".to_string()));
+        assert_eq!(parse(&plain, "Date,Description,Amount\nsoon,x,1\n"), Err("Line 2: 'soon' is not a date like %Y-%m-%d".to_string()));
+    }
+
+    #[test]
+    fn test_history() {
+        let date = |d| NaiveDate::from_ymd_opt(2025, 1, d).unwrap();
+        let row = |d, amount, balance| Row { date: date(d), description: String::new(), amount, balance, counterpart: "salary_income".to_string() };
+        let current = statement(Columns::default(), "%Y-%m-%d");
+        // The statement says main held 50 more than the config thought
+        let rows = vec![row(1, dec!(999), None), row(2, dec!(100), None), row(4, dec!(200), Some(dec!(1350)))];
+        let opening = Balances::from_iter([("main".to_string(), dec!(1000)), ("opening_balances".to_string(), dec!(-1000))]);
+        let history = history(&opening, date(1), &[(&current, rows)], "opening_balances");
+        let main: Vec<(NaiveDate, Decimal)> = history.iter().map(|(date, balances)| (*date, balances["main"])).collect();
+        assert_eq!(main, vec![(date(2), dec!(1100)), (date(3), dec!(1100)), (date(4), dec!(1350))]);
+        let last = &history.last().unwrap().1;
+        assert_eq!((last["salary_income"], last["opening_balances"]), (dec!(-300), dec!(-1050)));
+        assert_eq!(last.values().sum::<Decimal>(), Decimal::ZERO);
+    }
+}
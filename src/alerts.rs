@@ -0,0 +1,154 @@
+use chrono::NaiveDate;
+use rust_decimal::Decimal;
+use serde::Deserialize;
+
+use crate::balances::Balances;
+use crate::journal::Posting;
+
+/// A balance an account should stay within, e.g. `{account: main, below: 500}`
+/// to warn whenever `main` drops below £500
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct Rule {
+    pub account: String,
+    #[serde(default)]
+    pub below: Option<Decimal>,
+    #[serde(default)]
+    pub above: Option<Decimal>,
+}
+
+impl Rule {
+    fn broken_by(&self, balance: Decimal) -> bool {
+        self.below.is_some_and(|below| balance < below) || self.above.is_some_and(|above| balance > above)
+    }
+
+    /// What the rule warns about, e.g. "below 500"
+    pub fn describe(&self) -> String {
+        let mut parts = Vec::new();
+        if let Some(below) = self.below {
+            parts.push(format!("below {below}"));
+        }
+        if let Some(above) = self.above {
+            parts.push(format!("above {above}"));
+        }
+        parts.join(" or ")
+    }
+}
+
+/// A run of days on which an account broke one of the rules
+#[derive(Debug, Clone, PartialEq)]
+pub struct Breach {
+    pub account: String,
+    pub rule: String,
+    pub from: NaiveDate,
+    // Last day of the breach, or None if it lasts to the end of the forecast
+    pub to: Option<NaiveDate>,
+    // Balance furthest past the limit
+    pub worst: Decimal,
+    // Generators whose postings took the account past the limit on the first day
+    pub causes: Vec<String>,
+}
+
+/// Generators, with their descriptions, that paid out of `account` on `date`,
+/// or into it if `into`
+fn causes(postings: &[Posting], account: &str, date: NaiveDate, into: bool) -> Vec<String> {
+    let mut causes: Vec<String> = Vec::new();
+    for posting in postings.iter().filter(|posting| posting.date == date) {
+        let paid = if into { &posting.to } else { &posting.from };
+        if paid == account && !causes.contains(&posting.source()) {
+            causes.push(posting.source());
+        }
+    }
+    causes
+}
+
+/// Every breach of `rules` over `history`, in date order, with what in
+/// `postings` set each one off
+pub fn breaches(history: &[(NaiveDate, Balances)], rules: &[Rule], postings: &[Posting]) -> Vec<Breach> {
+    let mut breaches = Vec::new();
+    for rule in rules {
+        let mut current: Option<Breach> = None;
+        let mut previous_date = None;
+        for (date, balances) in history {
+            let balance = *balances.get(&rule.account).unwrap_or_else(|| panic!("Alert account '{}' not found", rule.account));
+            match (&mut current, rule.broken_by(balance)) {
+                (Some(breach), true) => {
+                    // Below a floor the lowest balance is worst, above a ceiling the highest
+                    if rule.below.is_some_and(|below| balance < below) {
+                        breach.worst = breach.worst.min(balance);
+                    } else {
+                        breach.worst = breach.worst.max(balance);
+                    }
+                }
+                (None, true) => {
+                    let above = rule.below.is_none_or(|below| balance >= below);
+                    current = Some(Breach {
+                        account: rule.account.clone(),
+                        rule: rule.describe(),
+                        from: *date,
+                        to: None,
+                        worst: balance,
+                        causes: causes(postings, &rule.account, *date, above),
+                    })
+                }
+                (Some(_), false) => {
+                    let mut breach = current.take().unwrap();
+                    breach.to = previous_date;
+                    breaches.push(breach);
+                }
+                (None, false) => {}
+            }
+            previous_date = Some(*date);
+        }
+        breaches.extend(current);
+    }
+    breaches.sort_by_key(|breach| breach.from);
+    breaches
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal_macros::dec;
+
+    #[test]
+    fn test_breaches_are_grouped_into_runs() {
+        let date = |d| NaiveDate::from_ymd_opt(2025, 1, d).unwrap();
+        let history: Vec<(NaiveDate, Balances)> = [900, 400, 300, 600, 450, 100]
+            .iter()
+            .enumerate()
+            .map(|(i, balance)| (date(i as u32 + 1), Balances::from_iter([("main".to_string(), Decimal::from(*balance))])))
+            .collect();
+        let rules = [Rule { account: "main".to_string(), below: Some(dec!(500)), above: None }];
+        let posting = |day, generator: &str, note: Option<&str>, to: &str| Posting {
+            date: date(day),
+            generator: generator.to_string(),
+            description: "expense".to_string(),
+            from: "main".to_string(),
+            to: to.to_string(),
+            amount: dec!(100),
+            received: None,
+            note: note.map(str::to_string),
+        };
+        let postings = [
+            posting(2, "Holiday", Some("Flights to Lisbon"), "holiday_expenditure"),
+            posting(2, "Holiday", Some("Flights to Lisbon"), "holiday_expenditure"),
+            posting(2, "Council tax", None, "council_tax_expenditure"),
+            posting(3, "Gym", None, "gym_expenditure"),
+        ];
+        let breach = |from, to, worst, causes: &[&str]| Breach {
+            account: "main".to_string(),
+            rule: "below 500".to_string(),
+            from: date(from),
+            to,
+            worst,
+            causes: causes.iter().map(|cause| cause.to_string()).collect(),
+        };
+        assert_eq!(
+            breaches(&history, &rules, &postings),
+            vec![
+                breach(2, Some(date(3)), dec!(300), &["Holiday \"Flights to Lisbon\"", "Council tax"]),
+                breach(5, None, dec!(100), &[]),
+            ]
+        );
+    }
+}
@@ -0,0 +1,128 @@
+use rust_decimal::Decimal;
+use serde::Deserialize;
+use std::collections::HashMap;
+
+use crate::balances::Balances;
+
+/// What a generator does when the account it pays from, with its backers,
+/// can't fund a payment without going below its floor
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum InsufficientFunds {
+    /// Make no payment
+    Skip,
+    /// Pay what there is
+    Partial,
+    /// Pay in full regardless
+    Overdraw,
+    /// Stop the forecast
+    Error,
+}
+
+/// Accounts that other accounts fall back on, so a payment that would take
+/// an account below its floor is made good from what backs it, and from what
+/// backs that in turn
+#[derive(Debug, Default)]
+pub struct Backing<'a> {
+    // Account backing each backed account
+    backers: HashMap<&'a str, &'a str>,
+    // Lowest each account is kept at, where that isn't zero
+    floors: HashMap<&'a str, Decimal>,
+}
+
+impl<'a> Backing<'a> {
+    pub fn new(backers: impl IntoIterator<Item = (&'a str, &'a str)>, floors: impl IntoIterator<Item = (&'a str, Decimal)>) -> Self {
+        Backing { backers: backers.into_iter().collect(), floors: floors.into_iter().collect() }
+    }
+
+    pub fn floor(&self, account: &str) -> Decimal {
+        self.floors.get(account).copied().unwrap_or_default()
+    }
+
+    /// `account` followed by the accounts backing it, nearest first, stopping
+    /// before any account already in the chain
+    fn chain<'b>(&'b self, account: &'b str) -> Vec<&'b str> {
+        let mut chain = vec![account];
+        while let Some(&backer) = self.backers.get(chain[chain.len() - 1])
+            && !chain.contains(&backer)
+        {
+            chain.push(backer);
+        }
+        chain
+    }
+
+    /// What `account` holds above its floor, or nothing once it's at or below it
+    fn spare(&self, balances: &Balances, account: &str) -> Decimal {
+        (balances[account] - self.floor(account)).max(Decimal::ZERO)
+    }
+
+    /// Most that can be paid out of `account` without taking it below its
+    /// floor, counting what the accounts backing it can spare
+    pub fn available(&self, balances: &Balances, account: &str) -> Decimal {
+        let chain = self.chain(account);
+        let backed: Decimal = chain[1..].iter().map(|backer| self.spare(balances, backer)).sum();
+        (balances[account] - self.floor(account) + backed).max(Decimal::ZERO)
+    }
+
+    /// Transfers, as (from, to, amount), that bring `account` back up to its
+    /// floor: each backer gives what it can spare until the shortfall is met,
+    /// and money drawn from further down the chain passes through the
+    /// accounts in between. Empty when it's unbacked or not short.
+    pub fn cover<'b>(&'b self, balances: &Balances, account: &'b str) -> Vec<(&'b str, &'b str, Decimal)> {
+        let chain = self.chain(account);
+        let mut short = self.floor(account) - balances[account];
+        if chain.len() < 2 || short <= Decimal::ZERO {
+            return Vec::new();
+        }
+        let mut taken = Vec::new();
+        for backer in &chain[1..] {
+            let take = self.spare(balances, backer).min(short);
+            taken.push(take);
+            short -= take;
+        }
+        // Pass money up the chain from the furthest backer drawn on, so no account in between dips below its floor
+        let mut transfers = Vec::new();
+        let mut passing = Decimal::ZERO;
+        for link in (1..chain.len()).rev() {
+            passing += taken[link - 1];
+            if passing > Decimal::ZERO {
+                transfers.push((chain[link], chain[link - 1], passing));
+            }
+        }
+        transfers
+    }
+
+    /// Each account backing itself, directly or further down its chain
+    pub fn cycles(&self) -> Vec<&'a str> {
+        let mut cycles: Vec<&'a str> =
+            self.backers.keys().copied().filter(|account| self.chain(account).last().and_then(|last| self.backers.get(last)) == Some(account)).collect();
+        cycles.sort();
+        cycles
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal_macros::dec;
+
+    #[test]
+    fn test_cover() {
+        let balances = Balances::from_iter([
+            ("main".to_string(), dec!(-150)),
+            ("savings".to_string(), dec!(120)),
+            ("emergency".to_string(), dec!(1000)),
+        ]);
+        let backing = Backing::new([("main", "savings"), ("savings", "emergency")], [("main", dec!(50)), ("savings", dec!(100))]);
+        // Savings can spare 20 of the 200 main is short; emergency finds the rest, passing it through savings
+        assert_eq!(backing.cover(&balances, "main"), vec![("emergency", "savings", dec!(180)), ("savings", "main", dec!(200))]);
+        assert_eq!(backing.available(&balances, "main"), dec!(820));
+        assert_eq!(backing.cover(&balances, "emergency"), vec![]);
+
+        let short = Balances::from_iter([("main".to_string(), dec!(-10)), ("savings".to_string(), dec!(4))]);
+        let backing = Backing::new([("main", "savings")], []);
+        assert_eq!(backing.cover(&short, "main"), vec![("savings", "main", dec!(4))]);
+        assert_eq!(backing.cycles(), Vec::<&str>::new());
+        assert_eq!(Backing::new([("main", "savings"), ("savings", "main")], []).cycles(), vec!["main", "savings"]);
+    }
+}
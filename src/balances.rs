@@ -0,0 +1,111 @@
+use rust_decimal::Decimal;
+use std::collections::HashMap;
+use std::ops::Index;
+use std::sync::Arc;
+
+/// Account names interned in sorted order, shared by every day's balances
+#[derive(Debug, PartialEq)]
+pub struct Accounts {
+    names: Vec<String>,
+    indices: HashMap<String, usize>,
+}
+
+/// Balances of a fixed set of accounts, kept as amounts in the order of the
+/// accounts' interned names, so a day's balances are copied without copying
+/// the names. Accounts are looked up as they are in a map of name to balance.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Balances {
+    accounts: Arc<Accounts>,
+    amounts: Vec<Decimal>,
+}
+
+impl Balances {
+    /// The balance of `account`, if it's one of these accounts
+    pub fn get(&self, account: &str) -> Option<&Decimal> {
+        self.accounts.indices.get(account).map(|&index| &self.amounts[index])
+    }
+
+    pub fn get_mut(&mut self, account: &str) -> Option<&mut Decimal> {
+        self.accounts.indices.get(account).map(|&index| &mut self.amounts[index])
+    }
+
+    pub fn contains_key(&self, account: &str) -> bool {
+        self.accounts.indices.contains_key(account)
+    }
+
+    /// Each account with its balance, in order of name
+    pub fn iter(&self) -> impl Iterator<Item = (&String, &Decimal)> {
+        self.accounts.names.iter().zip(&self.amounts)
+    }
+
+    pub fn keys(&self) -> impl Iterator<Item = &String> {
+        self.accounts.names.iter()
+    }
+
+    pub fn values(&self) -> impl Iterator<Item = &Decimal> {
+        self.amounts.iter()
+    }
+
+    pub fn len(&self) -> usize {
+        self.amounts.len()
+    }
+
+    /// These balances as a map of account name to balance
+    pub fn to_map(&self) -> HashMap<String, Decimal> {
+        self.iter().map(|(account, balance)| (account.clone(), *balance)).collect()
+    }
+}
+
+impl Index<&str> for Balances {
+    type Output = Decimal;
+
+    fn index(&self, account: &str) -> &Decimal {
+        self.get(account).unwrap_or_else(|| panic!("Account '{account}' not found"))
+    }
+}
+
+impl FromIterator<(String, Decimal)> for Balances {
+    fn from_iter<I: IntoIterator<Item = (String, Decimal)>>(balances: I) -> Self {
+        let mut balances: Vec<(String, Decimal)> = balances.into_iter().collect();
+        balances.sort_by(|a, b| a.0.cmp(&b.0));
+        balances.dedup_by(|later, earlier| later.0 == earlier.0);
+        let (names, amounts): (Vec<String>, Vec<Decimal>) = balances.into_iter().unzip();
+        let indices = names.iter().enumerate().map(|(index, name)| (name.clone(), index)).collect();
+        Balances { accounts: Arc::new(Accounts { names, indices }), amounts }
+    }
+}
+
+impl From<HashMap<String, Decimal>> for Balances {
+    fn from(balances: HashMap<String, Decimal>) -> Self {
+        balances.into_iter().collect()
+    }
+}
+
+impl<'a> IntoIterator for &'a Balances {
+    type Item = (&'a String, &'a Decimal);
+    type IntoIter = std::iter::Zip<std::slice::Iter<'a, String>, std::slice::Iter<'a, Decimal>>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.accounts.names.iter().zip(&self.amounts)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal_macros::dec;
+
+    #[test]
+    fn test_balances() {
+        let mut balances = Balances::from(HashMap::from([("savings".to_string(), dec!(50)), ("main".to_string(), dec!(100))]));
+        assert_eq!(balances.keys().collect::<Vec<_>>(), ["main", "savings"]);
+        assert_eq!(balances["savings"], dec!(50));
+        assert_eq!(balances.get("mortgage"), None);
+        let day_before = balances.clone();
+        *balances.get_mut("main").unwrap() -= dec!(30);
+        assert_eq!((balances["main"], day_before["main"]), (dec!(70), dec!(100)));
+        // Copies share the interned names
+        assert!(Arc::ptr_eq(&balances.accounts, &day_before.accounts));
+        assert_eq!(balances.to_map(), HashMap::from([("main".to_string(), dec!(70)), ("savings".to_string(), dec!(50))]));
+    }
+}
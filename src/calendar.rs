@@ -0,0 +1,331 @@
+use chrono::{Datelike, NaiveDate, Weekday};
+use serde::Deserialize;
+
+use crate::schedule::Schedule;
+
+/// Days that payments can be made on. Business days are weekdays that
+/// aren't one of the calendar's holidays.
+pub trait Calendar {
+    /// Holidays falling in `year`, in date order
+    fn holidays(&self, year: i32) -> Vec<NaiveDate>;
+
+    fn is_business_day(&self, date: NaiveDate) -> bool {
+        !is_weekend(date) && !self.holidays(date.year()).contains(&date)
+    }
+}
+
+/// Which built-in set of public holidays counts as non-business days
+#[derive(Debug, Clone, Copy, PartialEq, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum HolidayCalendar {
+    /// England and Wales bank holidays
+    #[default]
+    Uk,
+    /// US Federal Reserve bank holidays
+    Us,
+    /// Days the euro area's TARGET payment system is closed
+    Target,
+    /// Weekends only, so the config's `holidays` are the only other non-business days
+    #[serde(alias = "custom")]
+    None,
+}
+
+impl Calendar for HolidayCalendar {
+    fn holidays(&self, year: i32) -> Vec<NaiveDate> {
+        match self {
+            HolidayCalendar::Uk => uk_bank_holidays(year),
+            HolidayCalendar::Us => us_bank_holidays(year),
+            HolidayCalendar::Target => target_holidays(year),
+            HolidayCalendar::None => Vec::new(),
+        }
+    }
+}
+
+/// A calendar with extra non-business days on top, such as those supplied in the config
+pub struct WithExtra<'a, C> {
+    pub base: C,
+    pub extra: &'a [NaiveDate],
+}
+
+impl<C: Calendar> Calendar for WithExtra<'_, C> {
+    fn holidays(&self, year: i32) -> Vec<NaiveDate> {
+        let mut holidays = self.base.holidays(year);
+        holidays.extend(self.extra.iter().filter(|date| date.year() == year));
+        holidays.sort();
+        holidays.dedup();
+        holidays
+    }
+}
+
+/// What to do with a payment that falls on a non-business day
+#[derive(Debug, Clone, Copy, PartialEq, Default, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Adjust {
+    /// Pay on the scheduled day regardless
+    #[default]
+    None,
+    NextBusinessDay,
+    PreviousBusinessDay,
+}
+
+impl Adjust {
+    /// The date a payment scheduled for `date` is actually made
+    pub fn roll(self, date: NaiveDate, calendar: &impl Calendar) -> NaiveDate {
+        let mut date = date;
+        match self {
+            Adjust::None => {}
+            Adjust::NextBusinessDay => {
+                while !calendar.is_business_day(date) {
+                    date = date.succ_opt().unwrap();
+                }
+            }
+            Adjust::PreviousBusinessDay => {
+                while !calendar.is_business_day(date) {
+                    date = date.pred_opt().unwrap();
+                }
+            }
+        }
+        date
+    }
+
+    /// Does a generator on `schedule` pay out on `date` once rolled? Only a
+    /// business day can be paid on, and it takes the payments scheduled for it
+    /// and for the run of non-business days just before it (or after it, when
+    /// rolling back), however long that run is.
+    pub fn fires(self, schedule: &Schedule, date: NaiveDate, origin: NaiveDate, calendar: &impl Calendar) -> bool {
+        let step = match self {
+            Adjust::None => return schedule.fires_on(date, origin),
+            Adjust::NextBusinessDay => chrono::Duration::days(-1),
+            Adjust::PreviousBusinessDay => chrono::Duration::days(1),
+        };
+        let mut scheduled = date;
+        while self.roll(scheduled, calendar) == date {
+            if schedule.fires_on(scheduled, origin) {
+                return true;
+            }
+            scheduled += step;
+        }
+        false
+    }
+}
+
+fn is_weekend(date: NaiveDate) -> bool {
+    matches!(date.weekday(), Weekday::Sat | Weekday::Sun)
+}
+
+/// Easter Sunday by the anonymous Gregorian algorithm
+fn easter_sunday(year: i32) -> NaiveDate {
+    let a = year % 19;
+    let b = year / 100;
+    let c = year % 100;
+    let d = b / 4;
+    let e = b % 4;
+    let f = (b + 8) / 25;
+    let g = (b - f + 1) / 3;
+    let h = (19 * a + b - d - g + 15) % 30;
+    let i = c / 4;
+    let k = c % 4;
+    let l = (32 + 2 * e + 2 * i - h - k) % 7;
+    let m = (a + 11 * h + 22 * l) / 451;
+    let month = (h + l - 7 * m + 114) / 31;
+    let day = (h + l - 7 * m + 114) % 31 + 1;
+    NaiveDate::from_ymd_opt(year, month as u32, day as u32).unwrap()
+}
+
+fn first_monday(year: i32, month: u32) -> NaiveDate {
+    NaiveDate::from_weekday_of_month_opt(year, month, Weekday::Mon, 1).unwrap()
+}
+
+fn nth_weekday(year: i32, month: u32, weekday: Weekday, n: u8) -> NaiveDate {
+    NaiveDate::from_weekday_of_month_opt(year, month, weekday, n).unwrap()
+}
+
+fn last_monday(year: i32, month: u32) -> NaiveDate {
+    NaiveDate::from_weekday_of_month_opt(year, month, Weekday::Mon, 5)
+        .or_else(|| NaiveDate::from_weekday_of_month_opt(year, month, Weekday::Mon, 4))
+        .unwrap()
+}
+
+/// Regular England and Wales bank holidays for a year, with weekend
+/// substitute days. One-off holidays (jubilees, coronations) aren't included;
+/// add them to `holidays` in the config.
+pub fn uk_bank_holidays(year: i32) -> Vec<NaiveDate> {
+    let easter = easter_sunday(year);
+    let mut holidays = vec![
+        easter - chrono::Duration::days(2),
+        easter + chrono::Duration::days(1),
+        first_monday(year, 5),
+        last_monday(year, 5),
+        last_monday(year, 8),
+    ];
+    for (month, day) in [(1, 1), (12, 25), (12, 26)] {
+        let mut date = NaiveDate::from_ymd_opt(year, month, day).unwrap();
+        while is_weekend(date) || holidays.contains(&date) {
+            date = date.succ_opt().unwrap();
+        }
+        holidays.push(date);
+    }
+    holidays.sort();
+    holidays
+}
+
+/// US Federal Reserve bank holidays for a year. A holiday on a Sunday is
+/// observed on the Monday; one on a Saturday isn't moved, as the Federal
+/// Reserve stays open the Friday before.
+pub fn us_bank_holidays(year: i32) -> Vec<NaiveDate> {
+    let fixed = |month, day| {
+        let date = NaiveDate::from_ymd_opt(year, month, day).unwrap();
+        if date.weekday() == Weekday::Sun { date.succ_opt().unwrap() } else { date }
+    };
+    let mut holidays = vec![
+        fixed(1, 1),
+        nth_weekday(year, 1, Weekday::Mon, 3),
+        nth_weekday(year, 2, Weekday::Mon, 3),
+        last_monday(year, 5),
+        fixed(7, 4),
+        first_monday(year, 9),
+        nth_weekday(year, 10, Weekday::Mon, 2),
+        fixed(11, 11),
+        nth_weekday(year, 11, Weekday::Thu, 4),
+        fixed(12, 25),
+    ];
+    // Juneteenth, a Federal Reserve holiday from 2022
+    if year >= 2022 {
+        holidays.push(fixed(6, 19));
+    }
+    holidays.sort();
+    holidays
+}
+
+/// Days the TARGET euro payment system is closed in a year. There are no
+/// substitute days for holidays at weekends.
+pub fn target_holidays(year: i32) -> Vec<NaiveDate> {
+    let easter = easter_sunday(year);
+    let date = |month, day| NaiveDate::from_ymd_opt(year, month, day).unwrap();
+    vec![
+        date(1, 1),
+        easter - chrono::Duration::days(2),
+        easter + chrono::Duration::days(1),
+        date(5, 1),
+        date(12, 25),
+        date(12, 26),
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(y: i32, m: u32, d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(y, m, d).unwrap()
+    }
+
+    #[test]
+    fn test_uk_bank_holidays_2025() {
+        assert_eq!(
+            uk_bank_holidays(2025),
+            vec![
+                date(2025, 1, 1),
+                date(2025, 4, 18),
+                date(2025, 4, 21),
+                date(2025, 5, 5),
+                date(2025, 5, 26),
+                date(2025, 8, 25),
+                date(2025, 12, 25),
+                date(2025, 12, 26),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_uk_christmas_substitute_days() {
+        // Christmas 2027 is a Saturday: substitutes on Monday 27th and Tuesday 28th
+        let holidays = uk_bank_holidays(2027);
+        assert!(holidays.contains(&date(2027, 12, 27)));
+        assert!(holidays.contains(&date(2027, 12, 28)));
+        // New Year's Day 2028 is a Saturday
+        assert!(uk_bank_holidays(2028).contains(&date(2028, 1, 3)));
+        assert_eq!(easter_sunday(2026), date(2026, 4, 5));
+    }
+
+    #[test]
+    fn test_roll_to_business_day() {
+        let calendar = HolidayCalendar::Uk;
+        // Christmas 2025 is a Thursday and Boxing Day a Friday
+        assert_eq!(Adjust::NextBusinessDay.roll(date(2025, 12, 25), &calendar), date(2025, 12, 29));
+        assert_eq!(Adjust::PreviousBusinessDay.roll(date(2025, 12, 25), &calendar), date(2025, 12, 24));
+        assert_eq!(Adjust::None.roll(date(2025, 12, 25), &calendar), date(2025, 12, 25));
+        let weekends_only = HolidayCalendar::None;
+        assert_eq!(Adjust::NextBusinessDay.roll(date(2025, 12, 25), &weekends_only), date(2025, 12, 25));
+    }
+
+    #[test]
+    fn test_custom_holidays() {
+        let extra = [date(2025, 3, 3)];
+        let calendar = WithExtra { base: HolidayCalendar::Uk, extra: &extra };
+        assert!(!calendar.is_business_day(date(2025, 3, 3)));
+        assert_eq!(Adjust::NextBusinessDay.roll(date(2025, 3, 1), &calendar), date(2025, 3, 4));
+
+        // A payment due at the start of nine days off is made the day after, however long the break
+        let extra: Vec<NaiveDate> = (10..=18).map(|day| date(2025, 3, day)).collect();
+        let calendar = WithExtra { base: HolidayCalendar::Uk, extra: &extra };
+        let schedule = Schedule::monthly(10);
+        let paid: Vec<NaiveDate> = date(2025, 3, 1)
+            .iter_days()
+            .take(31)
+            .filter(|day| Adjust::NextBusinessDay.fires(&schedule, *day, date(2025, 1, 1), &calendar))
+            .collect();
+        assert_eq!(paid, vec![date(2025, 3, 19)]);
+        assert!(Adjust::PreviousBusinessDay.fires(&Schedule::monthly(18), date(2025, 3, 7), date(2025, 1, 1), &calendar));
+    }
+
+    #[test]
+    fn test_adjusted_firing() {
+        let calendar = HolidayCalendar::Uk;
+        let schedule = Schedule::monthly(25);
+        let origin = date(2025, 1, 1);
+        // 25 May 2025 is a Sunday and 26 May is the spring bank holiday
+        assert!(!Adjust::NextBusinessDay.fires(&schedule, date(2025, 5, 25), origin, &calendar));
+        assert!(!Adjust::NextBusinessDay.fires(&schedule, date(2025, 5, 26), origin, &calendar));
+        assert!(Adjust::NextBusinessDay.fires(&schedule, date(2025, 5, 27), origin, &calendar));
+        assert!(Adjust::PreviousBusinessDay.fires(&schedule, date(2025, 5, 23), origin, &calendar));
+        assert!(Adjust::NextBusinessDay.fires(&schedule, date(2025, 6, 25), origin, &calendar));
+    }
+
+    #[test]
+    fn test_us_bank_holidays() {
+        assert_eq!(
+            us_bank_holidays(2025),
+            vec![
+                date(2025, 1, 1),
+                date(2025, 1, 20),
+                date(2025, 2, 17),
+                date(2025, 5, 26),
+                date(2025, 6, 19),
+                date(2025, 7, 4),
+                date(2025, 9, 1),
+                date(2025, 10, 13),
+                date(2025, 11, 11),
+                date(2025, 11, 27),
+                date(2025, 12, 25),
+            ]
+        );
+        // Independence Day 2027 is a Sunday, observed on Monday; Christmas 2027 is a Saturday and isn't moved
+        let holidays = us_bank_holidays(2027);
+        assert!(holidays.contains(&date(2027, 7, 5)));
+        assert!(holidays.contains(&date(2027, 12, 25)));
+        assert!(!holidays.contains(&date(2027, 12, 24)));
+        assert!(!us_bank_holidays(2021).contains(&date(2021, 6, 18)));
+    }
+
+    #[test]
+    fn test_target_holidays() {
+        let calendar = HolidayCalendar::Target;
+        // Labour Day 2025 is a Thursday; the UK's early May bank holiday isn't a TARGET holiday
+        assert!(!calendar.is_business_day(date(2025, 5, 1)));
+        assert!(calendar.is_business_day(date(2025, 5, 5)));
+        assert_eq!(Adjust::NextBusinessDay.roll(date(2025, 4, 18), &calendar), date(2025, 4, 22));
+        // Christmas 2027 falls at a weekend with no substitute days
+        assert_eq!(Adjust::NextBusinessDay.roll(date(2027, 12, 25), &calendar), date(2027, 12, 27));
+    }
+}
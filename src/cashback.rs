@@ -0,0 +1,52 @@
+use chrono::{Datelike, NaiveDate};
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+use serde::{Deserialize, Serialize};
+
+use crate::rounding::Rounding;
+
+/// Spending a cashback generator has already paid out on, and what it has
+/// earned in the current calendar year
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Rewards {
+    counted: Decimal,
+    year: i32,
+    earned: Decimal,
+}
+
+impl Rewards {
+    /// Start counting spending from a total of `spent` so far
+    pub fn new(spent: Decimal) -> Self {
+        Rewards { counted: spent, ..Default::default() }
+    }
+
+    /// Cashback due on `date` at `percentage` of the spending since the last
+    /// payment, given the running total `spent`, keeping within `cap` for the year
+    pub fn earn(&mut self, date: NaiveDate, spent: Decimal, percentage: Decimal, cap: Option<Decimal>, rounding: Rounding) -> Decimal {
+        if date.year() != self.year {
+            self.year = date.year();
+            self.earned = Decimal::ZERO;
+        }
+        let due = rounding.round((spent - self.counted) * percentage / dec!(100));
+        let due = cap.map_or(due, |cap| due.min(cap - self.earned)).max(Decimal::ZERO);
+        self.counted = spent;
+        self.earned += due;
+        due
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cashback_capped_each_year() {
+        let date = |y, m| NaiveDate::from_ymd_opt(y, m, 1).unwrap();
+        let mut rewards = Rewards::new(dec!(100));
+        assert_eq!(rewards.earn(date(2025, 2), dec!(1100), dec!(1), Some(dec!(15)), Rounding::default()), dec!(10.00));
+        assert_eq!(rewards.earn(date(2025, 3), dec!(2100), dec!(1), Some(dec!(15)), Rounding::default()), dec!(5.00));
+        assert_eq!(rewards.earn(date(2025, 4), dec!(3100), dec!(1), Some(dec!(15)), Rounding::default()), dec!(0));
+        // Spending over the cap isn't carried into the next year
+        assert_eq!(rewards.earn(date(2026, 1), dec!(3600), dec!(1), Some(dec!(15)), Rounding::default()), dec!(5.00));
+    }
+}
@@ -0,0 +1,68 @@
+use chrono::NaiveDate;
+use rust_decimal::Decimal;
+use serde::Deserialize;
+
+use crate::balances::Balances;
+
+/// A balance an account is expected to end a day with, e.g. one read off a
+/// statement: `{date: 2026-01-01, account: main, equals: 12500, tolerance: 50}`
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct Check {
+    pub date: NaiveDate,
+    pub account: String,
+    pub equals: Decimal,
+    // How far either way the forecast balance may be and still pass
+    #[serde(default)]
+    pub tolerance: Decimal,
+}
+
+/// How a check turns out against the forecast
+#[derive(Debug, Clone, PartialEq)]
+pub struct Outcome {
+    pub check: Check,
+    // Forecast balance at the end of the check's date, or None if the
+    // forecast doesn't keep one for that day
+    pub balance: Option<Decimal>,
+}
+
+impl Outcome {
+    /// How far the forecast balance is from the expected one
+    pub fn difference(&self) -> Option<Decimal> {
+        self.balance.map(|balance| balance - self.check.equals)
+    }
+
+    pub fn passed(&self) -> bool {
+        self.difference().is_some_and(|difference| difference.abs() <= self.check.tolerance)
+    }
+}
+
+/// Each of `checks` against the balances in `history`, in date order
+pub fn outcomes(history: &[(NaiveDate, Balances)], checks: &[Check]) -> Vec<Outcome> {
+    let mut outcomes: Vec<Outcome> = checks
+        .iter()
+        .map(|check| {
+            let balances = history.iter().find(|(date, _)| *date == check.date).map(|(_, balances)| balances);
+            let balance = balances.map(|balances| *balances.get(&check.account).unwrap_or_else(|| panic!("Check account '{}' not found", check.account)));
+            Outcome { check: check.clone(), balance }
+        })
+        .collect();
+    outcomes.sort_by_key(|outcome| outcome.check.date);
+    outcomes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal_macros::dec;
+
+    #[test]
+    fn test_outcomes() {
+        let date = |d| NaiveDate::from_ymd_opt(2026, 1, d).unwrap();
+        let history: Vec<(NaiveDate, Balances)> =
+            [dec!(12480), dec!(12600)].iter().enumerate().map(|(i, main)| (date(i as u32 + 1), Balances::from_iter([("main".to_string(), *main)]))).collect();
+        let check = |d, equals, tolerance| Check { date: date(d), account: "main".to_string(), equals, tolerance };
+        let outcomes = outcomes(&history, &[check(2, dec!(12500), dec!(50)), check(1, dec!(12500), dec!(50)), check(9, dec!(0), dec!(0))]);
+        let results: Vec<(NaiveDate, Option<Decimal>, bool)> = outcomes.iter().map(|outcome| (outcome.check.date, outcome.difference(), outcome.passed())).collect();
+        assert_eq!(results, vec![(date(1), Some(dec!(-20)), true), (date(2), Some(dec!(100)), false), (date(9), None, false)]);
+    }
+}
@@ -0,0 +1,334 @@
+use chrono::{Datelike, Month, Months, NaiveDate};
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+use serde::Deserialize;
+
+use crate::inflation::inflated;
+use crate::rounding::Rounding;
+use crate::schedule::{DayOfMonth, Schedule};
+use crate::{Generator, GeneratorEntry};
+
+/// A child whose costs are projected from their date of birth
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct Child {
+    pub name: String,
+    pub birth_date: NaiveDate,
+    // Age-linked costs, or a typical set of UK costs if None
+    #[serde(default)]
+    pub costs: Option<Vec<AgeCost>>,
+    // Annual percentage increase applied to costs from the start of the forecast
+    #[serde(default = "default_inflation")]
+    pub inflation: Decimal,
+    #[serde(default = "crate::default_main")]
+    pub from: String,
+    // Termly university payments, replacing the typical monthly contribution
+    #[serde(default)]
+    pub university: Option<University>,
+}
+
+/// Fees and maintenance paid each term while a child is at university,
+/// priced at the start of the forecast
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct University {
+    pub termly_amount: Decimal,
+    // Starts the first autumn term after this birthday
+    #[serde(default = "default_university_age")]
+    pub start_age: u32,
+    #[serde(default = "default_university_years")]
+    pub years: u32,
+    // Months payments are due in, in order through the academic year
+    #[serde(default = "default_terms")]
+    pub terms: Vec<Month>,
+}
+
+/// A cost that applies between two ages, priced at the start of the forecast
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct AgeCost {
+    pub name: String,
+    // Age the cost starts at, and the age it stops at
+    pub from_age: u32,
+    pub to_age: u32,
+    pub amount: Decimal,
+    #[serde(alias = "day")]
+    pub schedule: Schedule,
+}
+
+/// One stage of a cost that changes as a child grows, e.g. nursery fees,
+/// starting on a date or at an age and lasting until the next stage starts
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct Phase {
+    // What the stage is, e.g. `free hours`, shown with its payments
+    #[serde(default)]
+    pub name: Option<String>,
+    // Day the stage starts, or the child's age when it does
+    #[serde(default)]
+    pub from: Option<NaiveDate>,
+    #[serde(default)]
+    pub from_age: Option<u32>,
+    pub amount: Decimal,
+}
+
+impl Phase {
+    /// The day the stage starts, for a child born on `birth_date`
+    pub fn starts(&self, birth_date: Option<NaiveDate>) -> Option<NaiveDate> {
+        match (self.from, self.from_age, birth_date) {
+            (Some(from), None, _) => Some(from),
+            (None, Some(age), Some(birth_date)) => birth_date.checked_add_months(Months::new(age * 12)),
+            _ => None,
+        }
+    }
+
+    /// Why the stage's start can't be worked out, if it can't
+    pub fn problem(&self, birth_date: Option<NaiveDate>) -> Option<String> {
+        match (self.from, self.from_age, birth_date) {
+            (None, None, _) => Some("needs either from or from_age".to_string()),
+            (Some(_), Some(_), _) => Some("has both from and from_age; give one".to_string()),
+            (None, Some(age), None) => Some(format!("starts at age {age}, but the generator has no birth_date")),
+            _ => None,
+        }
+    }
+}
+
+/// The stage of `phases` in force on `date`: the last to have started by then
+pub fn phase_on(phases: &[Phase], birth_date: Option<NaiveDate>, date: NaiveDate) -> Option<&Phase> {
+    phases
+        .iter()
+        .filter_map(|phase| Some((phase.starts(birth_date)?, phase)))
+        .filter(|(starts, _)| *starts <= date)
+        .max_by_key(|(starts, _)| *starts)
+        .map(|(_, phase)| phase)
+}
+
+fn default_inflation() -> Decimal {
+    dec!(3)
+}
+
+fn default_university_age() -> u32 {
+    18
+}
+
+fn default_university_years() -> u32 {
+    3
+}
+
+fn default_terms() -> Vec<Month> {
+    vec![Month::September, Month::January, Month::April]
+}
+
+/// Typical costs for a child in England: nursery until school, wraparound
+/// care through primary school, school trips and a university contribution
+pub fn default_costs() -> Vec<AgeCost> {
+    let monthly = || Schedule::Monthly { day: DayOfMonth::Day(1) };
+    vec![
+        AgeCost { name: "nursery".to_string(), from_age: 1, to_age: 4, amount: dec!(1100), schedule: monthly() },
+        AgeCost { name: "wraparound care".to_string(), from_age: 4, to_age: 11, amount: dec!(250), schedule: monthly() },
+        AgeCost {
+            name: "school trips".to_string(),
+            from_age: 5,
+            to_age: 18,
+            amount: dec!(300),
+            schedule: Schedule::Annually { month: Month::June, day: DayOfMonth::Day(1) },
+        },
+        AgeCost { name: "university".to_string(), from_age: 18, to_age: 21, amount: dec!(500), schedule: monthly() },
+    ]
+}
+
+/// An expense generator for each year of age each of the child's costs
+/// applies, with the amount inflated to that year from `start` and rounded by `rounding`
+pub fn generators(child: &Child, start: NaiveDate, rounding: Rounding) -> Vec<GeneratorEntry> {
+    let mut costs = child.costs.clone().unwrap_or_else(default_costs);
+    if child.costs.is_none() && child.university.is_some() {
+        costs.retain(|cost| cost.name != "university");
+    }
+    let birthday = |age: u32| child.birth_date.checked_add_months(Months::new(age * 12)).expect("Birthday out of range");
+    let mut generators = Vec::new();
+    for cost in &costs {
+        for age in cost.from_age..cost.to_age {
+            let (begins, ends) = (birthday(age), birthday(age + 1).pred_opt().unwrap());
+            generators.push(GeneratorEntry {
+                name: Some(format!("{} {}", child.name, cost.name)),
+                start_date: Some(begins),
+                end_date: Some(ends),
+                // Already inflated at the child's own rate
+                inflation: Some(Decimal::ZERO),
+                ..Generator::Expense {
+                    amount: inflated(cost.amount, child.inflation, start, begins, rounding),
+                    schedule: cost.schedule.clone(),
+                    category: "children".to_string(),
+                    from: child.from.clone(),
+                    subscription: Some(false),
+                    price_rise: None,
+                    variation: Decimal::ZERO,
+                }
+                .into()
+            });
+        }
+    }
+    for (date, amount) in university_payments(child, start, rounding) {
+        let month = Month::try_from(date.month() as u8).unwrap();
+        generators.push(GeneratorEntry {
+            name: Some(format!("{} university", child.name)),
+            start_date: Some(date),
+            end_date: Some(date),
+            inflation: Some(Decimal::ZERO),
+            ..Generator::Expense {
+                amount,
+                schedule: Schedule::Annually { month, day: DayOfMonth::Day(1) },
+                category: "children".to_string(),
+                from: child.from.clone(),
+                subscription: Some(false),
+                price_rise: None,
+                variation: Decimal::ZERO,
+            }
+            .into()
+        });
+    }
+    generators
+}
+
+/// Each termly university payment for the child, inflated from `start`
+pub fn university_payments(child: &Child, start: NaiveDate, rounding: Rounding) -> Vec<(NaiveDate, Decimal)> {
+    let Some(university) = &child.university else {
+        return Vec::new();
+    };
+    let Some(first_term) = university.terms.first() else {
+        return Vec::new();
+    };
+    let birthday = child.birth_date.checked_add_months(Months::new(university.start_age * 12)).expect("Birthday out of range");
+    let first_term = first_term.number_from_month();
+    let first_year = if birthday.month() <= first_term { birthday.year() } else { birthday.year() + 1 };
+    let mut payments = Vec::new();
+    for year in first_year..first_year + university.years as i32 {
+        for term in &university.terms {
+            let month = term.number_from_month();
+            let date = NaiveDate::from_ymd_opt(if month >= first_term { year } else { year + 1 }, month, 1).unwrap();
+            payments.push((date, inflated(university.termly_amount, child.inflation, start, date, rounding)));
+        }
+    }
+    payments
+}
+
+/// The least saved each month from `start` that has enough put by for every
+/// payment when it falls due, ignoring any interest earned on the savings
+pub fn monthly_saving_needed(payments: &[(NaiveDate, Decimal)], start: NaiveDate) -> Decimal {
+    let mut needed = Decimal::ZERO;
+    let mut cumulative = Decimal::ZERO;
+    for (date, amount) in payments {
+        cumulative += amount;
+        let months = crate::report::months_between(start, *date).max(1);
+        needed = needed.max(cumulative / Decimal::from(months));
+    }
+    needed.round_dp(2)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(y: i32, m: u32, d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(y, m, d).unwrap()
+    }
+
+    #[test]
+    fn test_one_generator_per_year_of_age_with_inflation() {
+        let child = Child {
+            name: "Sam".to_string(),
+            birth_date: date(2024, 3, 10),
+            costs: Some(vec![AgeCost {
+                name: "nursery".to_string(),
+                from_age: 1,
+                to_age: 3,
+                amount: dec!(1000),
+                schedule: Schedule::monthly(1),
+            }]),
+            inflation: dec!(10),
+            from: "main".to_string(),
+            university: None,
+        };
+        let generators = generators(&child, date(2025, 1, 1), Rounding::default());
+        assert_eq!(generators.len(), 2);
+        assert_eq!(generators[0].name.as_deref(), Some("Sam nursery"));
+        assert_eq!((generators[0].start_date, generators[0].end_date), (Some(date(2025, 3, 10)), Some(date(2026, 3, 9))));
+        assert_eq!((generators[1].start_date, generators[1].end_date), (Some(date(2026, 3, 10)), Some(date(2027, 3, 9))));
+        let amounts: Vec<Decimal> = generators
+            .iter()
+            .map(|entry| match &entry.generator {
+                Generator::Expense { amount, .. } => *amount,
+                _ => panic!("Expected an expense"),
+            })
+            .collect();
+        assert_eq!(amounts, vec![dec!(1000.00), dec!(1100.00)]);
+    }
+
+    #[test]
+    fn test_default_costs_cover_childhood() {
+        let child = Child {
+            name: "Alex".to_string(),
+            birth_date: date(2020, 2, 29),
+            costs: None,
+            inflation: Decimal::ZERO,
+            from: "main".to_string(),
+            university: None,
+        };
+        let generators = generators(&child, date(2025, 1, 1), Rounding::default());
+        // 3 years of nursery, 7 of wraparound care, 13 of school trips and 3 of university
+        assert_eq!(generators.len(), 26);
+        // A leap day birthday falls on 28 February in other years
+        assert_eq!(generators[0].start_date, Some(date(2021, 2, 28)));
+        assert_eq!(generators.last().unwrap().end_date, Some(date(2041, 2, 27)));
+    }
+
+    #[test]
+    fn test_university_payments_and_saving() {
+        let child = Child {
+            name: "Alex".to_string(),
+            birth_date: date(2007, 10, 20),
+            costs: None,
+            inflation: dec!(10),
+            from: "main".to_string(),
+            university: Some(University {
+                termly_amount: dec!(3000),
+                start_age: 18,
+                years: 1,
+                terms: default_terms(),
+            }),
+        };
+        let start = date(2024, 9, 1);
+        // Turns 18 after the autumn term has started, so goes up in 2026
+        let payments = university_payments(&child, start, Rounding::default());
+        assert_eq!(
+            payments,
+            vec![(date(2026, 9, 1), dec!(3630.00)), (date(2027, 1, 1), dec!(3630.00)), (date(2027, 4, 1), dec!(3630.00))]
+        );
+        // The first term needs £3,630 in 24 months; all three need £10,890 in 31
+        assert_eq!(monthly_saving_needed(&payments, start), dec!(351.29));
+        // The dedicated plan replaces the default monthly university contribution
+        let generators = generators(&child, start, Rounding::default());
+        assert_eq!(generators.iter().filter(|entry| entry.name.as_deref() == Some("Alex university")).count(), 3);
+        assert_eq!(generators.len(), 3 + 7 + 13 + 3);
+    }
+
+    #[test]
+    fn test_phase_on() {
+        let phases: Vec<Phase> = serde_yaml::from_str(
+            "
+- {name: nursery, from_age: 1, amount: 1100}
+- {name: wraparound, from_age: 4, amount: 250}
+- {name: free hours, from: 2026-09-01, amount: 600}
+- {from_age: 11, amount: 0}
+",
+        )
+        .unwrap();
+        let born = Some(date(2024, 3, 10));
+        let name = |on| phase_on(&phases, born, on).map(|phase| (phase.name.as_deref(), phase.amount));
+        assert_eq!(name(date(2025, 3, 9)), None);
+        assert_eq!(name(date(2025, 3, 10)), Some((Some("nursery"), dec!(1100))));
+        assert_eq!(name(date(2026, 9, 1)), Some((Some("free hours"), dec!(600))));
+        assert_eq!(name(date(2028, 3, 10)), Some((Some("wraparound"), dec!(250))));
+        assert_eq!(name(date(2040, 1, 1)), Some((None, dec!(0))));
+        // Stages by age need to know when the child was born
+        assert_eq!(phase_on(&phases, None, date(2027, 1, 1)).map(|phase| phase.amount), Some(dec!(600)));
+        assert_eq!(phases[0].problem(None), Some("starts at age 1, but the generator has no birth_date".to_string()));
+        assert_eq!(phases[0].problem(born), None);
+    }
+}
@@ -0,0 +1,58 @@
+use chrono::{Month, NaiveDate};
+use rust_decimal::Decimal;
+use serde::Deserialize;
+use std::collections::HashMap;
+
+use crate::rounding::Rounding;
+
+/// A price that rises each year by an inflation index plus a fixed
+/// percentage, as UK broadband and mobile contracts do mid-contract
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct PriceRise {
+    // Name of the index in `inflation_indices`
+    #[serde(default = "default_index")]
+    pub index: String,
+    // Percentage added to the index, e.g. 3.9 for CPI+3.9%
+    #[serde(default)]
+    pub plus: Decimal,
+    // Month the rise takes effect each year, usually the contract anniversary or April
+    pub month: Month,
+}
+
+fn default_index() -> String {
+    "cpi".to_string()
+}
+
+impl PriceRise {
+    /// Percentage the price rises by each year, given the assumed annual
+    /// rate of each index
+    pub fn percent(&self, indices: &HashMap<String, Decimal>) -> Result<Decimal, String> {
+        let index = indices.get(&self.index).ok_or_else(|| format!("Inflation index '{}' not found in inflation_indices", self.index))?;
+        Ok(*index + self.plus)
+    }
+
+    /// The price on `date` of something costing `amount` when the forecast starts
+    pub fn amount_on(
+        &self, amount: Decimal, indices: &HashMap<String, Decimal>, start: NaiveDate, date: NaiveDate, rounding: Rounding,
+    ) -> Result<Decimal, String> {
+        Ok(crate::salary::amount_on(amount, self.percent(indices)?, self.month, &[], start, date, rounding))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal_macros::dec;
+
+    #[test]
+    fn test_cpi_plus_rise_each_april() {
+        let date = |y, m, d| NaiveDate::from_ymd_opt(y, m, d).unwrap();
+        let rise = PriceRise { index: "cpi".to_string(), plus: dec!(3.9), month: Month::April };
+        let indices = HashMap::from([("cpi".to_string(), dec!(3.1))]);
+        assert_eq!(rise.percent(&indices), Ok(dec!(7.0)));
+        let start = date(2025, 1, 1);
+        assert_eq!(rise.amount_on(dec!(40), &indices, start, date(2025, 3, 31), Rounding::default()), Ok(dec!(40)));
+        assert_eq!(rise.amount_on(dec!(40), &indices, start, date(2025, 4, 1), Rounding::default()), Ok(dec!(42.80)));
+        assert_eq!(rise.amount_on(dec!(40), &indices, start, date(2026, 4, 1), Rounding::default()), Ok(dec!(45.80)));
+    }
+}
@@ -0,0 +1,64 @@
+use chrono::{Datelike, NaiveDate};
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+
+use crate::rounding::Rounding;
+
+/// Month the council tax year starts in, and the bill rises
+const YEAR_START: u32 = 4;
+
+/// Calendar year the council tax year `date` falls in started
+fn tax_year(date: NaiveDate) -> i32 {
+    if date.month() >= YEAR_START { date.year() } else { date.year() - 1 }
+}
+
+/// The instalment due in the month of `date`, or None if nothing is collected
+/// that month. The bill is `annual_amount` for the year the forecast starts
+/// in, rising by `annual_increase_percent` each April after, and collected in
+/// `instalments` monthly payments from April. Instalments are rounded, and the
+/// first makes up the difference.
+pub fn instalment(
+    annual_amount: Decimal,
+    annual_increase_percent: Decimal,
+    instalments: u32,
+    start: NaiveDate,
+    date: NaiveDate,
+    rounding: Rounding,
+) -> Option<Decimal> {
+    let number = (date.month() + 12 - YEAR_START) % 12;
+    if number >= instalments {
+        return None;
+    }
+    let growth = (tax_year(start)..tax_year(date)).fold(Decimal::ONE, |acc, _| acc * (Decimal::ONE + annual_increase_percent / dec!(100)));
+    let bill = rounding.round(annual_amount * growth);
+    let each = rounding.round(bill / Decimal::from(instalments));
+    Some(if number == 0 { bill - each * Decimal::from(instalments - 1) } else { each })
+}
+
+/// Why `instalments` can't be collected within a year, if they can't
+pub fn instalments_problem(instalments: u32) -> Option<String> {
+    (!(1..=12).contains(&instalments)).then(|| format!("instalments is {instalments}, which isn't from 1 to 12"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_instalment() {
+        let date = |y, m| NaiveDate::from_ymd_opt(y, m, 1).unwrap();
+        let start = date(2025, 2);
+        let due = |d, instalments| instalment(dec!(1234.56), dec!(5), instalments, start, d, Rounding::default());
+        // Up to March the bill is the one for the year started in April 2024
+        assert_eq!(due(date(2025, 2), 10), None);
+        assert_eq!(due(date(2025, 1), 10), Some(dec!(123.46)));
+        assert_eq!(due(date(2025, 4), 10), Some(dec!(129.62)));
+        assert_eq!(due(date(2025, 5), 10), Some(dec!(129.63)));
+        assert_eq!(due(date(2026, 1), 10), Some(dec!(129.63)));
+        assert_eq!(due(date(2026, 2), 10), None);
+        assert_eq!(due(date(2026, 2), 12), Some(dec!(108.02)));
+        assert_eq!(due(date(2026, 4), 10), Some(dec!(136.11)));
+        assert_eq!(instalments_problem(10), None);
+        assert_eq!(instalments_problem(0), Some("instalments is 0, which isn't from 1 to 12".to_string()));
+    }
+}
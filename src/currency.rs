@@ -0,0 +1,191 @@
+use chrono::NaiveDate;
+use rust_decimal::Decimal;
+use serde::Deserialize;
+use std::collections::{BTreeMap, HashMap};
+
+use crate::balances::Balances;
+use crate::rounding::{Rounding, Rules};
+
+/// What one unit of a currency is worth in the base currency from a date
+/// on, e.g. `{currency: EUR, from: 2025-01-01, rate: 0.85}`
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct ExchangeRate {
+    pub currency: String,
+    pub from: NaiveDate,
+    pub rate: Decimal,
+}
+
+/// The currency each account is held in and the rates between them. Every
+/// account is in the base currency unless it says otherwise.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Currencies<'a> {
+    base: &'a str,
+    // Currency of each account not held in the base currency
+    accounts: HashMap<&'a str, &'a str>,
+    rates: &'a [ExchangeRate],
+    // How amounts in each currency and account are rounded
+    rounding: &'a Rules,
+}
+
+/// Balances held in one currency on a day, and what they're worth in the base currency
+#[derive(Debug, Clone, PartialEq)]
+pub struct CurrencyTotal {
+    pub currency: String,
+    pub total: Decimal,
+    pub in_base: Decimal,
+}
+
+impl<'a> Currencies<'a> {
+    pub fn new(base: &'a str, accounts: impl IntoIterator<Item = (&'a str, &'a str)>, rates: &'a [ExchangeRate], rounding: &'a Rules) -> Self {
+        let accounts = accounts.into_iter().filter(|(_, currency)| *currency != base).collect();
+        Currencies { base, accounts, rates, rounding }
+    }
+
+    pub fn base(&self) -> &str {
+        self.base
+    }
+
+    /// Whether any account is held in a currency other than the base one
+    pub fn mixed(&self) -> bool {
+        !self.accounts.is_empty()
+    }
+
+    pub fn currency(&self, account: &str) -> &str {
+        self.accounts.get(account).copied().unwrap_or(self.base)
+    }
+
+    /// How amounts paid to or worked out on `account` are rounded
+    pub fn rounding(&self, account: &str) -> Rounding {
+        self.rounding.for_account(account, self.currency(account))
+    }
+
+    /// Currencies accounts are held in that no rate is given for, with the
+    /// accounts held in them
+    pub fn missing_rates(&self) -> Vec<(String, Vec<String>)> {
+        let mut missing: BTreeMap<&str, Vec<String>> = BTreeMap::new();
+        for (account, currency) in &self.accounts {
+            if !self.rates.iter().any(|rate| rate.currency == *currency) {
+                missing.entry(currency).or_default().push(account.to_string());
+            }
+        }
+        missing
+            .into_iter()
+            .map(|(currency, mut accounts)| {
+                accounts.sort();
+                (currency.to_string(), accounts)
+            })
+            .collect()
+    }
+
+    /// Value of one unit of `currency` in the base currency on `date`: the
+    /// latest rate given from that date or before, or the earliest rate for
+    /// dates before any is given
+    fn rate(&self, currency: &str, date: NaiveDate) -> Decimal {
+        if currency == self.base {
+            return Decimal::ONE;
+        }
+        let rates = self.rates.iter().filter(|rate| rate.currency == currency);
+        rates
+            .clone()
+            .filter(|rate| rate.from <= date)
+            .max_by_key(|rate| rate.from)
+            .or_else(|| rates.min_by_key(|rate| rate.from))
+            .unwrap_or_else(|| panic!("No exchange rate for {currency}"))
+            .rate
+    }
+
+    /// What `amount` paid out of `from` comes to on arriving in `to` on
+    /// `date`, rounded as `to` rounds amounts
+    pub fn convert(&self, amount: Decimal, from: &str, to: &str, date: NaiveDate) -> Decimal {
+        let (from_currency, to_currency) = (self.currency(from), self.currency(to));
+        if from_currency == to_currency {
+            return amount;
+        }
+        self.rounding(to).round(amount * self.rate(from_currency, date) / self.rate(to_currency, date))
+    }
+
+    /// The balance of `account` in the base currency on `date`
+    pub fn in_base(&self, account: &str, balance: Decimal, date: NaiveDate) -> Decimal {
+        match self.currency(account) {
+            currency if currency == self.base => balance,
+            currency => self.rounding.for_currency(self.base).round(balance * self.rate(currency, date)),
+        }
+    }
+
+    /// Total of `balances` in the base currency on `date`
+    pub fn total<'b>(&self, balances: impl IntoIterator<Item = (&'b String, &'b Decimal)>, date: NaiveDate) -> Decimal {
+        balances.into_iter().map(|(account, balance)| self.in_base(account, *balance, date)).sum()
+    }
+
+    /// Every day of `history` with each balance in the base currency at that day's rate
+    pub fn history_in_base(&self, history: &crate::History) -> crate::History {
+        if !self.mixed() {
+            return history.clone();
+        }
+        history
+            .iter()
+            .map(|(date, balances)| {
+                (*date, balances.iter().map(|(account, balance)| (account.clone(), self.in_base(account, *balance, *date))).collect())
+            })
+            .collect()
+    }
+
+    /// Total of `accounts` in each currency they're held in on `date`, with
+    /// what that's worth in the base currency, base currency first
+    pub fn totals(&self, balances: &Balances, accounts: &[String], date: NaiveDate) -> Vec<CurrencyTotal> {
+        let mut totals: BTreeMap<(bool, &str), Decimal> = BTreeMap::new();
+        for account in accounts {
+            let currency = self.currency(account);
+            *totals.entry((currency != self.base, currency)).or_default() += balances.get(account).copied().unwrap_or_default();
+        }
+        totals
+            .into_iter()
+            .map(|((_, currency), total)| CurrencyTotal {
+                currency: currency.to_string(),
+                total,
+                in_base: self.rounding.for_currency(self.base).round(total * self.rate(currency, date)),
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal_macros::dec;
+
+    #[test]
+    fn test_convert() {
+        let date = |m, d| NaiveDate::from_ymd_opt(2025, m, d).unwrap();
+        let rates = [
+            ExchangeRate { currency: "EUR".to_string(), from: date(1, 1), rate: dec!(0.85) },
+            ExchangeRate { currency: "EUR".to_string(), from: date(7, 1), rate: dec!(0.80) },
+            ExchangeRate { currency: "USD".to_string(), from: date(1, 1), rate: dec!(0.75) },
+        ];
+        let rounding = Rules::default();
+        let currencies = Currencies::new("GBP", [("euro_savings", "EUR"), ("dollars", "USD"), ("main", "GBP")], &rates, &rounding);
+        assert!(currencies.mixed());
+        assert_eq!(currencies.currency("main"), "GBP");
+        assert_eq!(currencies.convert(dec!(100), "main", "euro_savings", date(3, 1)), dec!(117.65));
+        assert_eq!(currencies.convert(dec!(100), "euro_savings", "main", date(7, 1)), dec!(80.00));
+        // Before the first rate is given, it's the one used
+        assert_eq!(currencies.convert(dec!(100), "euro_savings", "main", NaiveDate::from_ymd_opt(2024, 6, 1).unwrap()), dec!(85.00));
+        assert_eq!(currencies.convert(dec!(100), "dollars", "euro_savings", date(3, 1)), dec!(88.24));
+        assert_eq!(currencies.convert(dec!(100), "main", "groceries_expenditure", date(3, 1)), dec!(100));
+
+        let balances = Balances::from_iter([("main".to_string(), dec!(500)), ("euro_savings".to_string(), dec!(1000)), ("mortgage".to_string(), dec!(-2000))]);
+        assert_eq!(currencies.total(&balances, date(3, 1)), dec!(-650.00));
+        let accounts = ["euro_savings", "main", "mortgage"].map(String::from);
+        assert_eq!(
+            currencies.totals(&balances, &accounts, date(8, 1)),
+            vec![
+                CurrencyTotal { currency: "GBP".to_string(), total: dec!(-1500), in_base: dec!(-1500) },
+                CurrencyTotal { currency: "EUR".to_string(), total: dec!(1000), in_base: dec!(800.00) },
+            ]
+        );
+
+        let unpriced = Currencies::new("GBP", [("yen", "JPY"), ("euro_savings", "EUR")], &rates[..1], &rounding);
+        assert_eq!(unpriced.missing_rates(), vec![("JPY".to_string(), vec!["yen".to_string()])]);
+        assert!(!Currencies::new("GBP", [("main", "GBP")], &[], &rounding).mixed());
+    }
+}
@@ -0,0 +1,109 @@
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+
+/// The parts of a debt a payment can go towards
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Component {
+    Fees,
+    Interest,
+    Principal,
+}
+
+pub fn default_allocation() -> Vec<Component> {
+    vec![Component::Fees, Component::Interest, Component::Principal]
+}
+
+/// How a payment (or a run of payments) was split across a debt's components
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct Allocation {
+    pub fees: Decimal,
+    pub interest: Decimal,
+    pub principal: Decimal,
+}
+
+impl Allocation {
+    pub fn total(&self) -> Decimal {
+        self.fees + self.interest + self.principal
+    }
+
+    fn add(&mut self, other: &Allocation) {
+        self.fees += other.fees;
+        self.interest += other.interest;
+        self.principal += other.principal;
+    }
+}
+
+/// Charges on a debt that have been added to its balance but not yet paid.
+/// Whatever else is owed is principal.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Debt {
+    pub fees: Decimal,
+    pub interest: Decimal,
+    // Principal left unpaid at the last payment, which interest is charged on
+    pub carried_principal: Decimal,
+    // Running total of everything paid so far
+    pub paid: Allocation,
+}
+
+impl Debt {
+    /// Principal part of a total outstanding balance of `owed`
+    pub fn principal(&self, owed: Decimal) -> Decimal {
+        (owed - self.fees - self.interest).max(Decimal::ZERO)
+    }
+
+    /// Split `payment` across the components in `order`, paying off the
+    /// unpaid fees and interest as it goes. Principal takes no more than
+    /// `principal`; anything left over after the last component is unused.
+    pub fn allocate(&mut self, payment: Decimal, principal: Decimal, order: &[Component]) -> Allocation {
+        let mut remaining = payment;
+        let mut allocation = Allocation::default();
+        for component in order {
+            let (owed, paid) = match component {
+                Component::Fees => (&mut self.fees, &mut allocation.fees),
+                Component::Interest => (&mut self.interest, &mut allocation.interest),
+                Component::Principal => {
+                    let amount = remaining.min(principal - allocation.principal);
+                    allocation.principal += amount;
+                    remaining -= amount;
+                    continue;
+                }
+            };
+            let amount = remaining.min(*owed);
+            *owed -= amount;
+            *paid += amount;
+            remaining -= amount;
+        }
+        self.paid.add(&allocation);
+        allocation
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal_macros::dec;
+
+    fn debt() -> Debt {
+        Debt { fees: dec!(12), interest: dec!(30), ..Default::default() }
+    }
+
+    #[test]
+    fn test_allocation_in_default_order() {
+        let mut debt = debt();
+        let allocation = debt.allocate(dec!(50), dec!(1000), &default_allocation());
+        assert_eq!(allocation, Allocation { fees: dec!(12), interest: dec!(30), principal: dec!(8) });
+        assert_eq!((debt.fees, debt.interest), (Decimal::ZERO, Decimal::ZERO));
+    }
+
+    #[test]
+    fn test_partial_payment_principal_first() {
+        let mut debt = debt();
+        let order = [Component::Principal, Component::Interest, Component::Fees];
+        let allocation = debt.allocate(dec!(50), dec!(40), &order);
+        assert_eq!(allocation, Allocation { fees: dec!(0), interest: dec!(10), principal: dec!(40) });
+        assert_eq!((debt.fees, debt.interest), (dec!(12), dec!(20)));
+        assert_eq!(debt.paid.total(), dec!(50));
+        assert_eq!(debt.principal(dec!(100)), dec!(68));
+    }
+}
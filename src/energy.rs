@@ -0,0 +1,73 @@
+use chrono::{Datelike, Months, NaiveDate};
+use rust_decimal::Decimal;
+use serde::Deserialize;
+
+use crate::rounding::Rounding;
+
+/// New prices from a given date, e.g. a price cap change or a switch of
+/// supplier; anything not given carries on as before
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct TariffChange {
+    pub from: NaiveDate,
+    #[serde(default)]
+    pub standing_charge: Option<Decimal>,
+    #[serde(default)]
+    pub unit_rate: Option<Decimal>,
+}
+
+/// The standing charge per day and unit rate per kWh in force on `date`
+pub fn tariff_on(
+    standing_charge: Decimal,
+    unit_rate: Decimal,
+    changes: &[TariffChange],
+    date: NaiveDate,
+) -> (Decimal, Decimal) {
+    let mut changes: Vec<&TariffChange> = changes.iter().filter(|change| change.from <= date).collect();
+    changes.sort_by_key(|change| change.from);
+    changes.into_iter().fold((standing_charge, unit_rate), |(standing_charge, unit_rate), change| {
+        (change.standing_charge.unwrap_or(standing_charge), change.unit_rate.unwrap_or(unit_rate))
+    })
+}
+
+/// The bill paid on `date` for the calendar month before it: a standing
+/// charge for each day plus that month's usage from `usage` (kWh for January
+/// to December), at the prices in force on the first of the month
+pub fn bill(
+    standing_charge: Decimal,
+    unit_rate: Decimal,
+    changes: &[TariffChange],
+    usage: &[Decimal],
+    date: NaiveDate,
+    rounding: Rounding,
+) -> Decimal {
+    assert_eq!(usage.len(), 12, "Energy usage needs one figure for each month, January first");
+    let month_start = |date: NaiveDate| date.with_day(1).expect("Every month has a first day");
+    let billed = month_start(date) - Months::new(1);
+    let days = (month_start(date) - billed).num_days();
+    let (standing_charge, unit_rate) = tariff_on(standing_charge, unit_rate, changes, billed);
+    rounding.round(standing_charge * Decimal::from(days) + unit_rate * usage[billed.month0() as usize])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal_macros::dec;
+
+    #[test]
+    fn test_bill_for_previous_month() {
+        let date = |y, m, d| NaiveDate::from_ymd_opt(y, m, d).unwrap();
+        let usage: Vec<Decimal> = (1..=12).map(|month| Decimal::from(month * 10)).collect();
+        let changes = vec![
+            TariffChange { from: date(2025, 4, 1), standing_charge: None, unit_rate: Some(dec!(0.30)) },
+            TariffChange { from: date(2025, 1, 1), standing_charge: Some(dec!(0.50)), unit_rate: None },
+        ];
+        // January: 31 days at 50p and 10kWh at 25p, billed in February
+        assert_eq!(bill(dec!(0.40), dec!(0.25), &changes, &usage, date(2025, 2, 15), Rounding::default()), dec!(18.00));
+        // March is still at the old unit rate, though billed after the change
+        assert_eq!(bill(dec!(0.40), dec!(0.25), &changes, &usage, date(2025, 4, 15), Rounding::default()), dec!(23.00));
+        // April is the first month at the new unit rate: 30 days at 50p and 40kWh at 30p
+        assert_eq!(bill(dec!(0.40), dec!(0.25), &changes, &usage, date(2025, 5, 1), Rounding::default()), dec!(27.00));
+        // December 2024 is before any change
+        assert_eq!(tariff_on(dec!(0.40), dec!(0.25), &changes, date(2024, 12, 31)), (dec!(0.40), dec!(0.25)));
+    }
+}
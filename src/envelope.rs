@@ -0,0 +1,108 @@
+use chrono::{Datelike, NaiveDate};
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+use serde::Deserialize;
+
+use crate::balances::Balances;
+use crate::journal::Posting;
+
+/// What becomes of what's left in an envelope when the next allocation is made
+#[derive(Debug, Clone, Copy, PartialEq, Default, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Leftover {
+    /// Kept in the envelope, on top of the new allocation
+    #[default]
+    Rollover,
+    /// Settled with the account the allocation comes from, so the envelope
+    /// starts again from nothing: anything unspent goes back and any
+    /// overspending is made good
+    Reset,
+}
+
+/// An envelope's allocation and spending over a month
+#[derive(Debug, Clone, PartialEq)]
+pub struct Usage {
+    pub envelope: String,
+    // (year, month)
+    pub month: (i32, u32),
+    pub allocated: Decimal,
+    // Paid out of the envelope, less anything paid back into it
+    pub spent: Decimal,
+    // Balance at the end of the month; below zero once overspent
+    pub left: Decimal,
+}
+
+impl Usage {
+    /// Percentage of the month's allocation spent, if anything was allocated
+    pub fn used(&self) -> Option<Decimal> {
+        (!self.allocated.is_zero()).then(|| (self.spent * dec!(100) / self.allocated).round_dp(1))
+    }
+}
+
+/// Each calendar month's usage of each of `envelopes`, given as the label of
+/// the generator filling it and its account. Allocations are the generator's
+/// own postings into the envelope; everything else in or out is spending.
+pub fn usage(envelopes: &[(String, String)], postings: &[Posting], history: &[(NaiveDate, Balances)]) -> Vec<Usage> {
+    let month_of = |date: NaiveDate| (date.year(), date.month());
+    let mut usages = Vec::new();
+    for (i, (date, balances)) in history.iter().enumerate() {
+        if history.get(i + 1).is_some_and(|(next, _)| month_of(*next) == month_of(*date)) {
+            continue;
+        }
+        let month = month_of(*date);
+        for (generator, account) in envelopes {
+            let mut usage = Usage {
+                envelope: account.clone(),
+                month,
+                allocated: Decimal::ZERO,
+                spent: Decimal::ZERO,
+                left: balances.get(account).copied().unwrap_or_default(),
+            };
+            for posting in postings.iter().filter(|posting| month_of(posting.date) == month) {
+                match (posting.generator == *generator, posting.from == *account, posting.to == *account) {
+                    (true, _, true) if posting.description == "allocation" => usage.allocated += posting.amount,
+                    (true, _, _) => {}
+                    (false, true, _) => usage.spent += posting.amount,
+                    (false, _, true) => usage.spent -= posting.amount,
+                    _ => {}
+                }
+            }
+            usages.push(usage);
+        }
+    }
+    usages
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_usage() {
+        let day = |m, d| NaiveDate::from_ymd_opt(2025, m, d).unwrap();
+        let posting = |date, generator: &str, description: &str, from: &str, to: &str, amount| Posting {
+            date,
+            generator: generator.to_string(),
+            description: description.to_string(),
+            from: from.to_string(),
+            to: to.to_string(),
+            amount,
+            received: None,
+            note: None,
+        };
+        let postings = [
+            posting(day(1, 1), "Food", "allocation", "main", "groceries", dec!(400)),
+            posting(day(1, 10), "Shop", "expense", "groceries", "food_expenditure", dec!(150)),
+            posting(day(1, 20), "Shop", "expense", "groceries", "food_expenditure", dec!(150)),
+            posting(day(2, 1), "Food", "envelope reset", "groceries", "main", dec!(100)),
+            posting(day(2, 1), "Food", "allocation", "main", "groceries", dec!(400)),
+            posting(day(2, 10), "Shop", "expense", "groceries", "food_expenditure", dec!(500)),
+        ];
+        let balances = |groceries| Balances::from_iter([("groceries".to_string(), groceries)]);
+        let history = vec![(day(1, 31), balances(dec!(100))), (day(2, 28), balances(dec!(-100)))];
+        let usages = usage(&[("Food".to_string(), "groceries".to_string())], &postings, &history);
+        let usage = |month, allocated, spent, left| Usage { envelope: "groceries".to_string(), month: (2025, month), allocated, spent, left };
+        assert_eq!(usages, vec![usage(1, dec!(400), dec!(300), dec!(100)), usage(2, dec!(400), dec!(500), dec!(-100))]);
+        assert_eq!(usages[1].used(), Some(dec!(125.0)));
+    }
+}
@@ -0,0 +1,321 @@
+use chrono::{Datelike, Months, NaiveDate};
+use rust_decimal::Decimal;
+use std::collections::HashSet;
+
+use crate::History;
+use crate::journal::Posting;
+
+/// Bars a sparkline is drawn with, lowest first
+const BARS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+
+/// A key pressed while exploring
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Key {
+    Left,
+    Right,
+    Up,
+    Down,
+    PreviousMonth,
+    NextMonth,
+    Toggle,
+    Quit,
+}
+
+/// The keys in `bytes` read from a terminal in raw mode, ignoring any that do nothing here
+pub fn keys(bytes: &[u8]) -> Vec<Key> {
+    let mut keys = Vec::new();
+    let mut rest = bytes;
+    while let Some((&first, after)) = rest.split_first() {
+        rest = after;
+        let key = match first {
+            0x1b => match rest {
+                [b'[', b'D', after @ ..] => Some((Key::Left, after)),
+                [b'[', b'C', after @ ..] => Some((Key::Right, after)),
+                [b'[', b'A', after @ ..] => Some((Key::Up, after)),
+                [b'[', b'B', after @ ..] => Some((Key::Down, after)),
+                [b'[', b'5', b'~', after @ ..] => Some((Key::PreviousMonth, after)),
+                [b'[', b'6', b'~', after @ ..] => Some((Key::NextMonth, after)),
+                // Any other control sequence, such as Home or Delete, is dropped up to its final byte
+                [b'[', after @ ..] => {
+                    let end = after.iter().position(|byte| (0x40..=0x7e).contains(byte)).map_or(after.len(), |end| end + 1);
+                    rest = &after[end..];
+                    None
+                }
+                // Escape on its own
+                _ => Some((Key::Quit, rest)),
+            },
+            b'h' => Some((Key::Left, rest)),
+            b'l' => Some((Key::Right, rest)),
+            b'k' => Some((Key::Up, rest)),
+            b'j' => Some((Key::Down, rest)),
+            b'[' => Some((Key::PreviousMonth, rest)),
+            b']' => Some((Key::NextMonth, rest)),
+            b' ' => Some((Key::Toggle, rest)),
+            b'q' | 0x03 => Some((Key::Quit, rest)),
+            _ => None,
+        };
+        if let Some((key, after)) = key {
+            keys.push(key);
+            rest = after;
+        }
+    }
+    keys
+}
+
+/// A sparkline of `values`, its lowest value drawn with the lowest bar and its highest with the highest
+pub fn sparkline(values: &[Decimal]) -> String {
+    let (Some(low), Some(high)) = (values.iter().min(), values.iter().max()) else {
+        return String::new();
+    };
+    let steps = Decimal::from(BARS.len() - 1);
+    values
+        .iter()
+        .map(|value| match high - low {
+            range if range.is_zero() => BARS[0],
+            range => BARS[usize::try_from(((value - low) * steps / range).round()).unwrap_or(0)],
+        })
+        .collect()
+}
+
+/// Where the explorer is in a forecast, and which accounts it's graphing
+pub struct Explorer<'a> {
+    history: &'a History,
+    postings: &'a [Posting],
+    accounts: Vec<String>,
+    shown: HashSet<String>,
+    currency_symbol: &'a str,
+    // Index of the selected day in `history`
+    day: usize,
+    // Index of the selected account in `accounts`
+    account: usize,
+}
+
+impl<'a> Explorer<'a> {
+    /// An explorer of `history` starting on its first day, listing `accounts`
+    /// and graphing those in `shown`
+    pub fn new(history: &'a History, postings: &'a [Posting], accounts: Vec<String>, shown: HashSet<String>, currency_symbol: &'a str) -> Self {
+        Explorer { history, postings, accounts, shown, currency_symbol, day: 0, account: 0 }
+    }
+
+    fn date(&self) -> NaiveDate {
+        self.history[self.day].0
+    }
+
+    /// Index of the first day of the history on or after `date`, or of its last day
+    fn day_from(&self, date: NaiveDate) -> usize {
+        self.history.partition_point(|(day, _)| *day < date).min(self.history.len() - 1)
+    }
+
+    /// Act on `key`, returning whether to carry on exploring
+    pub fn press(&mut self, key: Key) -> bool {
+        let last = self.history.len() - 1;
+        match key {
+            Key::Left => self.day = self.day.saturating_sub(1),
+            Key::Right => self.day = (self.day + 1).min(last),
+            Key::Up => self.account = self.account.saturating_sub(1),
+            Key::Down => self.account = (self.account + 1).min(self.accounts.len().saturating_sub(1)),
+            Key::PreviousMonth => self.day = self.day_from(self.date().checked_sub_months(Months::new(1)).unwrap_or(self.history[0].0)),
+            Key::NextMonth => self.day = self.day_from(self.date().checked_add_months(Months::new(1)).unwrap_or(self.history[last].0)),
+            Key::Toggle => {
+                if let Some(account) = self.accounts.get(self.account)
+                    && !self.shown.remove(account)
+                {
+                    self.shown.insert(account.clone());
+                }
+            }
+            Key::Quit => return false,
+        }
+        true
+    }
+
+    /// The screen as lines, fitted to `width` columns and `height` rows
+    pub fn render(&self, width: usize, height: usize) -> Vec<String> {
+        let date = self.date();
+        let mut lines = vec![
+            format!("Balances on {date} (day {} of {})", self.day + 1, self.history.len()),
+            "←/→ day  [/] month  ↑/↓ account  space graph or hide  q quit".to_string(),
+            String::new(),
+        ];
+        // Days of the selected month the sparklines cover
+        let month: Vec<usize> =
+            (0..self.history.len()).filter(|&day| (self.history[day].0.year(), self.history[day].0.month()) == (date.year(), date.month())).collect();
+        let name_width = self.accounts.iter().map(|account| account.chars().count()).max().unwrap_or(0);
+        let balances = &self.history[self.day].1;
+        for (index, account) in self.accounts.iter().enumerate() {
+            let cursor = if index == self.account { '>' } else { ' ' };
+            let shown = self.shown.contains(account);
+            let balance = format!("{}{:.2}", self.currency_symbol, balances.get(account).copied().unwrap_or_default());
+            let mut line = format!("{cursor} [{}] {account:<name_width$} {balance:>14}", if shown { 'x' } else { ' ' });
+            if shown {
+                let values: Vec<Decimal> = month.iter().map(|&day| self.history[day].1.get(account).copied().unwrap_or_default()).collect();
+                line = format!("{line}  {}", sparkline(&values));
+            }
+            lines.push(line);
+        }
+        // Points at the selected day in the sparklines
+        let offset = name_width + 23 + month.iter().position(|&day| day == self.day).unwrap_or(0);
+        lines.push(format!("{:offset$}^ {date}", ""));
+        lines.push(String::new());
+
+        let postings: Vec<&Posting> = self.postings.iter().filter(|posting| posting.date == date).collect();
+        if postings.is_empty() {
+            lines.push(format!("No transactions on {date}"));
+        } else {
+            lines.push(format!("Transactions on {date}:"));
+            let room = height.saturating_sub(lines.len());
+            for posting in postings.iter().take(if postings.len() > room { room.saturating_sub(1) } else { room }) {
+                lines.push(format!(
+                    "  {} ({}): {} -> {} {}{:.2}",
+                    posting.source(),
+                    posting.description,
+                    posting.from,
+                    posting.to,
+                    self.currency_symbol,
+                    posting.amount
+                ));
+            }
+            if postings.len() > room {
+                lines.push(format!("  ... and {} more", postings.len() - room.saturating_sub(1)));
+            }
+        }
+        lines.truncate(height);
+        lines.iter().map(|line| line.chars().take(width).collect()).collect()
+    }
+}
+
+/// Run `stty` on the terminal with `args`, returning what it prints
+#[cfg(unix)]
+fn stty(args: &[&str]) -> std::io::Result<String> {
+    use std::process::{Command, Stdio};
+    let tty = std::fs::File::open("/dev/tty")?;
+    let output = Command::new("stty").args(args).stdin(Stdio::from(tty)).output()?;
+    match output.status.success() {
+        true => Ok(String::from_utf8_lossy(&output.stdout).trim().to_string()),
+        false => Err(std::io::Error::other(String::from_utf8_lossy(&output.stderr).trim().to_string())),
+    }
+}
+
+/// The terminal in raw mode on the alternate screen, put back as it was
+/// when dropped, whether exploring ends normally, with an error or a panic
+#[cfg(unix)]
+struct RawTerminal {
+    // Settings to restore, as `stty -g` prints them
+    saved: String,
+}
+
+#[cfg(unix)]
+impl RawTerminal {
+    fn enter() -> std::io::Result<Self> {
+        use std::io::Write;
+        let saved = stty(&["-g"])?;
+        let terminal = RawTerminal { saved };
+        stty(&["raw", "-echo"])?;
+        // Draw on the alternate screen, so the shell's scrollback is left as it was
+        let mut stdout = std::io::stdout();
+        write!(stdout, "\x1b[?1049h\x1b[?25l")?;
+        stdout.flush()?;
+        Ok(terminal)
+    }
+}
+
+#[cfg(unix)]
+impl Drop for RawTerminal {
+    fn drop(&mut self) {
+        use std::io::Write;
+        // Nothing more can be done about a terminal that can't be put back
+        let mut stdout = std::io::stdout();
+        let _ = write!(stdout, "\x1b[?25h\x1b[?1049l").and_then(|()| stdout.flush());
+        let _ = stty(&[&self.saved]);
+    }
+}
+
+/// Explore the forecast on the terminal until it's quit, redrawing on every key
+#[cfg(unix)]
+pub fn run(mut explorer: Explorer) -> std::io::Result<()> {
+    use std::io::{IsTerminal, Read, Write};
+    if !std::io::stdin().is_terminal() || !std::io::stdout().is_terminal() {
+        return Err(std::io::Error::other("explore needs a terminal"));
+    }
+    let _terminal = RawTerminal::enter()?;
+    let mut stdout = std::io::stdout();
+    let mut buffer = [0; 64];
+    loop {
+        let size = stty(&["size"]).unwrap_or_default();
+        let (height, width) = size
+            .split_once(' ')
+            .and_then(|(rows, columns)| Some((rows.parse().ok()?, columns.parse().ok()?)))
+            // A terminal that doesn't know its size says it's 0 by 0
+            .filter(|&(rows, columns)| rows > 0 && columns > 0)
+            .unwrap_or((24, 80));
+        write!(stdout, "\x1b[H\x1b[2J{}", explorer.render(width, height).join("\r\n"))?;
+        stdout.flush()?;
+        let read = std::io::stdin().read(&mut buffer)?;
+        if read == 0 || !keys(&buffer[..read]).into_iter().all(|key| explorer.press(key)) {
+            return Ok(());
+        }
+    }
+}
+
+/// Exploring drives the terminal with `stty`, which only Unix has
+#[cfg(not(unix))]
+pub fn run(_explorer: Explorer) -> std::io::Result<()> {
+    Err(std::io::Error::other("explore needs a Unix terminal"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::balances::Balances;
+    use rust_decimal_macros::dec;
+
+    #[test]
+    fn test_keys() {
+        assert_eq!(keys(b"\x1b[C\x1b[Dhl"), vec![Key::Right, Key::Left, Key::Left, Key::Right]);
+        assert_eq!(keys(b"\x1b[5~]x \x1b[Bq"), vec![Key::PreviousMonth, Key::NextMonth, Key::Toggle, Key::Down, Key::Quit]);
+        assert_eq!(keys(b"\x1b"), vec![Key::Quit]);
+        // Home, End and Delete do nothing, rather than counting as [
+        assert_eq!(keys(b"\x1b[H\x1b[F\x1b[3~\x1b[1;5Ch"), vec![Key::Left]);
+        assert_eq!(sparkline(&[dec!(0), dec!(35), dec!(70), dec!(70)]), "▁▅██");
+        assert_eq!(sparkline(&[dec!(5), dec!(5)]), "▁▁");
+    }
+
+    #[test]
+    fn test_explorer() {
+        let start = NaiveDate::from_ymd_opt(2025, 1, 30).unwrap();
+        let history: History = (0..40i64)
+            .map(|day| {
+                let balances = Balances::from_iter([("main".to_string(), Decimal::from(100 + day)), ("savings".to_string(), dec!(50))]);
+                (start + chrono::Duration::days(day), balances)
+            })
+            .collect();
+        let posting = |date| Posting {
+            date,
+            generator: "Salary".to_string(),
+            description: "salary".to_string(),
+            from: "income".to_string(),
+            to: "main".to_string(),
+            amount: dec!(1),
+            received: None,
+            note: None,
+        };
+        let postings = vec![posting(start), posting(start + chrono::Duration::days(3))];
+        let accounts = vec!["main".to_string(), "savings".to_string()];
+        let mut explorer = Explorer::new(&history, &postings, accounts, HashSet::from(["main".to_string()]), "£");
+
+        let screen = explorer.render(80, 24);
+        assert_eq!(screen[0], "Balances on 2025-01-30 (day 1 of 40)");
+        assert_eq!(screen[3], "> [x] main           £100.00  ▁█");
+        assert_eq!(screen[4], "  [ ] savings         £50.00");
+        assert_eq!(screen[5], format!("{:30}^ 2025-01-30", ""));
+        assert_eq!(screen[7..], ["Transactions on 2025-01-30:", "  Salary (salary): income -> main £1.00"]);
+
+        assert!(explorer.press(Key::NextMonth));
+        assert_eq!(explorer.date(), NaiveDate::from_ymd_opt(2025, 2, 28).unwrap());
+        assert!(explorer.press(Key::Down) && explorer.press(Key::Toggle) && explorer.press(Key::PreviousMonth));
+        let screen = explorer.render(30, 7);
+        assert_eq!(screen[0], "Balances on 2025-01-30 (day 1 ");
+        assert_eq!(screen[4], "> [x] savings         £50.00  ");
+        assert_eq!(screen.len(), 7);
+        assert!(!explorer.press(Key::Quit));
+    }
+}
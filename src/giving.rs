@@ -0,0 +1,164 @@
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+use serde::Deserialize;
+
+use crate::GeneratorEntry;
+use crate::rounding::Rounding;
+
+/// What a percentage tithe is worked out on
+#[derive(Debug, Clone, Copy, PartialEq, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TitheBasis {
+    /// Salary paid since the last tithe
+    #[default]
+    Salary,
+    /// Salary plus rent received since the last tithe
+    Income,
+}
+
+impl TitheBasis {
+    /// Types of income generator the basis covers
+    fn kinds(&self) -> &'static [&'static str] {
+        match self {
+            TitheBasis::Salary => &["salary"],
+            TitheBasis::Income => &["salary", "rent"],
+        }
+    }
+}
+
+/// A payment of income, to tell which tithes it counts towards
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Income<'a> {
+    // Label of the generator paying it
+    pub generator: &'a str,
+    // Its type, e.g. `salary`
+    pub kind: &'a str,
+    // Income account it's paid from
+    pub account: &'a str,
+}
+
+/// Whether a tithe counts `income`: if it gives `of`, when one of those names
+/// the income's generator, generator type or income account (ignoring case),
+/// and otherwise when its `basis` covers that type of generator
+pub fn counts(of: Option<&[String]>, basis: TitheBasis, income: &Income) -> bool {
+    match of {
+        Some(of) => of.iter().any(|name| {
+            [income.generator, income.kind, income.account].iter().any(|source| source.eq_ignore_ascii_case(name))
+        }),
+        None => basis.kinds().contains(&income.kind),
+    }
+}
+
+/// Alternative ways of giving to forecast side by side with the config as written
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct TitheComparison {
+    pub strategies: Vec<TitheStrategy>,
+    // Account the giving is paid into, used to total what each strategy gives
+    #[serde(default = "crate::default_charity")]
+    pub account: String,
+    // Whether donations are made under Gift Aid, so the charity reclaims basic rate tax
+    #[serde(default)]
+    pub gift_aid: bool,
+    // Whether the donor pays higher rate tax and so can reclaim the difference
+    #[serde(default)]
+    pub higher_rate_taxpayer: bool,
+}
+
+/// A named set of generators that replaces every tithe in the config
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct TitheStrategy {
+    pub name: String,
+    pub generators: Vec<GeneratorEntry>,
+}
+
+/// A charity receiving a share of a tithe, as a fixed amount or a percentage
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct Recipient {
+    pub name: String,
+    // Account the recipient's giving is tracked in, created if not listed in the config
+    pub account: String,
+    // Fixed amount from each tithe payment
+    #[serde(default)]
+    pub amount: Option<Decimal>,
+    // Percentage of what's left of the payment once fixed amounts are given
+    #[serde(default)]
+    pub percentage: Option<Decimal>,
+}
+
+/// Split a giving budget between `recipients`: fixed amounts first, in order,
+/// as far as the budget goes, then percentages of what remains. Returns each
+/// recipient's share and whatever is left unallocated.
+pub fn allocate(budget: Decimal, recipients: &[Recipient], rounding: Rounding) -> (Vec<Decimal>, Decimal) {
+    let mut remaining = budget;
+    let mut shares: Vec<Decimal> = recipients
+        .iter()
+        .map(|recipient| {
+            let share = recipient.amount.unwrap_or(Decimal::ZERO).min(remaining).max(Decimal::ZERO);
+            remaining -= share;
+            share
+        })
+        .collect();
+    let after_fixed = remaining;
+    for (share, recipient) in shares.iter_mut().zip(recipients) {
+        if let Some(percentage) = recipient.percentage {
+            let part = rounding.round(after_fixed * percentage / dec!(100)).min(remaining);
+            *share += part;
+            remaining -= part;
+        }
+    }
+    (shares, remaining)
+}
+
+/// Basic rate tax the charity reclaims on a Gift Aid donation of `given`,
+/// i.e. 20% of the grossed-up donation
+pub fn gift_aid(given: Decimal) -> Decimal {
+    (given * dec!(0.25)).round_dp(2)
+}
+
+/// What a higher rate taxpayer reclaims on Gift Aid donations of `given`:
+/// the 20% difference between higher and basic rate on the grossed-up donation
+pub fn higher_rate_relief(given: Decimal) -> Decimal {
+    (given * dec!(1.25) * dec!(0.20)).round_dp(2)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_gift_aid() {
+        // £80 given is £100 gross: the charity reclaims £20 and a higher rate taxpayer £20
+        assert_eq!(gift_aid(dec!(80)), dec!(20.00));
+        assert_eq!(higher_rate_relief(dec!(80)), dec!(20.00));
+        assert_eq!(gift_aid(dec!(33.33)), dec!(8.33));
+    }
+
+    #[test]
+    fn test_counts() {
+        let salary = Income { generator: "Day job", kind: "salary", account: "salary_income" };
+        let freelance = Income { generator: "Freelance", kind: "salary", account: "freelance_income" };
+        let rent = Income { generator: "rent (generator 3)", kind: "rent", account: "rental_income" };
+        assert!(counts(None, TitheBasis::Salary, &freelance));
+        assert!(!counts(None, TitheBasis::Salary, &rent));
+        assert!(counts(None, TitheBasis::Income, &rent));
+        let of = ["freelance".to_string(), "rental_income".to_string()];
+        assert!(!counts(Some(&of), TitheBasis::Salary, &salary));
+        assert!(counts(Some(&of), TitheBasis::Salary, &freelance));
+        assert!(counts(Some(&of), TitheBasis::Salary, &rent));
+        assert!(counts(Some(&["SALARY".to_string()]), TitheBasis::Salary, &freelance));
+    }
+
+    #[test]
+    fn test_allocate_fixed_then_percentages() {
+        let recipient = |amount, percentage| Recipient {
+            name: "charity".to_string(),
+            account: "charity".to_string(),
+            amount,
+            percentage,
+        };
+        let recipients = [recipient(None, Some(dec!(60))), recipient(Some(dec!(100)), None), recipient(None, Some(dec!(40)))];
+        assert_eq!(allocate(dec!(300), &recipients, Rounding::default()), (vec![dec!(120.00), dec!(100), dec!(80.00)], dec!(0.00)));
+        // Fixed amounts can use up a small budget
+        assert_eq!(allocate(dec!(60), &recipients, Rounding::default()), (vec![dec!(0), dec!(60), dec!(0)], dec!(0)));
+    }
+}
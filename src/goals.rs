@@ -0,0 +1,195 @@
+use chrono::NaiveDate;
+use rust_decimal::Decimal;
+use serde::Deserialize;
+use std::collections::HashMap;
+
+use crate::balances::Balances;
+
+/// A balance to reach in an account by a date, e.g. a house deposit
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct Goal {
+    pub account: String,
+    pub target: Decimal,
+    pub by: NaiveDate,
+    #[serde(default)]
+    pub name: Option<String>,
+    // When saving towards the goal starts, for the planned path to the target;
+    // defaults to the start of the forecast
+    #[serde(default)]
+    pub from: Option<NaiveDate>,
+    // CSV file of recorded balances, a date and balance per line, e.g. a balance
+    // CSV saved by an earlier run; `#` lines and a header are skipped
+    #[serde(default)]
+    pub actuals: Option<String>,
+}
+
+impl Goal {
+    /// The goal's name, or its account if it has none
+    pub fn label(&self) -> &str {
+        self.name.as_deref().unwrap_or(&self.account)
+    }
+}
+
+/// How a goal turns out over the forecast
+#[derive(Debug, Clone, PartialEq)]
+pub struct Progress {
+    pub goal: Goal,
+    // First day the account holds the target, if it ever does
+    pub reached: Option<NaiveDate>,
+    // Balance on the goal's date, or at the end of the forecast if that comes first
+    pub balance_by: Decimal,
+}
+
+impl Progress {
+    pub fn met(&self) -> bool {
+        self.balance_by >= self.goal.target
+    }
+
+    /// How far short of the target the account is on the goal's date
+    pub fn shortfall(&self) -> Decimal {
+        (self.goal.target - self.balance_by).max(Decimal::ZERO)
+    }
+}
+
+/// A day on a goal's burn-up chart
+#[derive(Debug, Clone, PartialEq)]
+pub struct PlanRow {
+    pub date: NaiveDate,
+    // Balance the plan expects, rising in a straight line to the target by the goal's date
+    pub planned: Decimal,
+    pub forecast: Option<Decimal>,
+    pub actual: Option<Decimal>,
+}
+
+impl PlanRow {
+    /// How far the forecast is below the plan, if it is
+    pub fn behind(&self) -> Option<Decimal> {
+        self.forecast.map(|forecast| self.planned - forecast).filter(|gap| *gap > Decimal::ZERO)
+    }
+}
+
+/// Dated balances from CSV `text`, skipping `#` comments and a header line
+pub fn parse_actuals(text: &str) -> Result<Vec<(NaiveDate, Decimal)>, String> {
+    let mut actuals = Vec::new();
+    for (number, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let (date, balance) = line.split_once(',').ok_or_else(|| format!("Line {}: expected a date and a balance", number + 1))?;
+        let Ok(date) = date.trim().parse::<NaiveDate>() else {
+            if actuals.is_empty() {
+                continue; // The header
+            }
+            return Err(format!("Line {}: '{}' is not a date", number + 1, date.trim()));
+        };
+        let balance = balance.trim().parse().map_err(|_| format!("Line {}: '{}' is not a balance", number + 1, balance.trim()))?;
+        actuals.push((date, balance));
+    }
+    actuals.sort();
+    Ok(actuals)
+}
+
+/// The plan for `goal` against the forecast in `history` and recorded
+/// `actuals`, for every day either covers from the plan's start. The plan
+/// starts from the last actual balance on or before its start, or else the
+/// forecast's, and holds at the target after the goal's date.
+pub fn plan(history: &[(NaiveDate, Balances)], goal: &Goal, actuals: &[(NaiveDate, Decimal)]) -> Vec<PlanRow> {
+    let forecast: HashMap<NaiveDate, Decimal> =
+        history.iter().filter_map(|(date, balances)| balances.get(&goal.account).map(|balance| (*date, *balance))).collect();
+    let Some(from) = goal.from.or_else(|| history.first().map(|(date, _)| *date)) else {
+        return Vec::new();
+    };
+    let mut dates: Vec<NaiveDate> = forecast.keys().chain(actuals.iter().map(|(date, _)| date)).copied().filter(|date| *date >= from).collect();
+    dates.sort();
+    dates.dedup();
+    let opening = actuals
+        .iter()
+        .rev()
+        .find(|(date, _)| *date <= from)
+        .map(|(_, balance)| *balance)
+        .or_else(|| dates.iter().find_map(|date| forecast.get(date).copied()))
+        .unwrap_or_default();
+    let days = (goal.by - from).num_days().max(1);
+    dates
+        .into_iter()
+        .map(|date| {
+            let elapsed = (date - from).num_days().min(days);
+            PlanRow {
+                date,
+                planned: (opening + (goal.target - opening) * Decimal::from(elapsed) / Decimal::from(days)).round_dp(2),
+                forecast: forecast.get(&date).copied(),
+                actual: actuals.iter().find(|(day, _)| *day == date).map(|(_, balance)| *balance),
+            }
+        })
+        .collect()
+}
+
+/// Track `goal` through `history`
+pub fn progress(history: &[(NaiveDate, Balances)], goal: &Goal) -> Progress {
+    let balance = |balances: &Balances| {
+        *balances.get(&goal.account).unwrap_or_else(|| panic!("Goal account '{}' not found", goal.account))
+    };
+    let reached = history.iter().find(|(_, balances)| balance(balances) >= goal.target).map(|(date, _)| *date);
+    let balance_by = history
+        .iter()
+        .take_while(|(date, _)| *date <= goal.by)
+        .last()
+        .map_or(Decimal::ZERO, |(_, balances)| balance(balances));
+    Progress { goal: goal.clone(), reached, balance_by }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal_macros::dec;
+
+    #[test]
+    fn test_progress() {
+        let date = |m| NaiveDate::from_ymd_opt(2025, m, 1).unwrap();
+        let history: Vec<(NaiveDate, Balances)> = (1..=6)
+            .map(|m| (date(m), Balances::from_iter([("deposit".to_string(), Decimal::from(m * 1000))])))
+            .collect();
+        let goal = |target, by| Goal { account: "deposit".to_string(), target, by, name: None, from: None, actuals: None };
+
+        let met = progress(&history, &goal(dec!(3000), date(4)));
+        assert_eq!((met.met(), met.reached, met.shortfall()), (true, Some(date(3)), Decimal::ZERO));
+
+        let late = progress(&history, &goal(dec!(5000), date(3)));
+        assert_eq!((late.met(), late.reached, late.shortfall()), (false, Some(date(5)), dec!(2000)));
+
+        let never = progress(&history, &goal(dec!(10000), date(12)));
+        assert_eq!((never.reached, never.balance_by, never.shortfall()), (None, dec!(6000), dec!(4000)));
+    }
+
+    #[test]
+    fn test_plan() {
+        let date = |m, d| NaiveDate::from_ymd_opt(2025, m, d).unwrap();
+        // The forecast saves 1000 a month from 1000 in April
+        let history: Vec<(NaiveDate, Balances)> = (4..=8)
+            .map(|m| (date(m, 1), Balances::from_iter([("deposit".to_string(), Decimal::from((m - 3) * 1000))])))
+            .collect();
+        let actuals = parse_actuals("# Recorded\nDate,Balance\n2025-02-01,0\n2025-03-01,400.50\n").unwrap();
+        assert_eq!(actuals, vec![(date(2, 1), dec!(0)), (date(3, 1), dec!(400.50))]);
+        // From nothing in February to 6000 by the end of July
+        let goal = Goal {
+            account: "deposit".to_string(),
+            target: dec!(6000),
+            by: date(8, 1),
+            name: None,
+            from: Some(date(2, 1)),
+            actuals: None,
+        };
+        let rows = plan(&history, &goal, &actuals);
+        let planned: Vec<(NaiveDate, Decimal)> = rows.iter().map(|row| (row.date, row.planned)).collect();
+        assert_eq!(planned[..2], [(date(2, 1), dec!(0)), (date(3, 1), dec!(928.18))]);
+        assert_eq!(rows[1].actual, Some(dec!(400.50)));
+        assert_eq!(rows[1].forecast, None);
+        // The forecast starts April about 1000 behind and stays there, while the plan holds at the target in August
+        let behind: Vec<Option<Decimal>> = rows.iter().map(PlanRow::behind).collect();
+        assert_eq!(behind, vec![None, None, Some(dec!(955.80)), Some(dec!(950.28)), Some(dec!(977.90)), Some(dec!(972.38)), Some(dec!(1000))]);
+        assert_eq!(rows.last().unwrap().planned, dec!(6000));
+
+        assert_eq!(parse_actuals("2025-01-01,10\nsoon,20\n"), Err("Line 2: 'soon' is not a date".to_string()));
+    }
+}
@@ -0,0 +1,106 @@
+use rust_decimal::Decimal;
+use std::collections::BTreeMap;
+
+use crate::balances::Balances;
+
+/// Separates the levels of a nested account name, e.g. `expenses:utilities:gas`
+const SEPARATOR: char = ':';
+
+/// The accounts `account` is nested under, outermost first, e.g. `expenses`
+/// and `expenses:utilities` for `expenses:utilities:gas`
+pub fn parents(account: &str) -> Vec<&str> {
+    account.match_indices(SEPARATOR).map(|(index, _)| &account[..index]).collect()
+}
+
+/// Whether any of the accounts in `balances` is nested under another
+pub fn nested(balances: &Balances) -> bool {
+    balances.iter().any(|(account, _)| account.contains(SEPARATOR))
+}
+
+/// `balances` with a total for each account that others are nested under:
+/// its own balance, if it has one, and the balances of everything under it
+pub fn with_parents(balances: &Balances) -> Balances {
+    let mut totals: BTreeMap<String, Decimal> = balances.iter().map(|(account, balance)| (account.clone(), *balance)).collect();
+    for (account, balance) in balances.iter() {
+        for parent in parents(account) {
+            *totals.entry(parent.to_string()).or_default() += balance;
+        }
+    }
+    totals.into_iter().collect()
+}
+
+/// A line of the account tree, with the account's total at the start and end of the forecast
+#[derive(Debug, Clone, PartialEq)]
+pub struct Node {
+    pub account: String,
+    // How many accounts it's nested under
+    pub depth: usize,
+    pub opening: Decimal,
+    pub closing: Decimal,
+}
+
+impl Node {
+    /// The last part of the account's name, which is all a tree needs to show
+    pub fn name(&self) -> &str {
+        self.account.rsplit(SEPARATOR).next().unwrap_or(&self.account)
+    }
+}
+
+/// The nested accounts and those they're nested under, each followed by
+/// what's under it, with the totals from `with_parents`
+pub fn tree(opening: &Balances, closing: &Balances) -> Vec<Node> {
+    let (opening, closing) = (with_parents(opening), with_parents(closing));
+    let mut accounts: Vec<&String> = closing
+        .iter()
+        .map(|(account, _)| account)
+        .filter(|account| account.contains(SEPARATOR) || closing.iter().any(|(other, _)| parents(other).contains(&account.as_str())))
+        .collect();
+    // Sorting on the parts of the names keeps everything under an account together
+    accounts.sort_by_key(|account| account.split(SEPARATOR).collect::<Vec<_>>());
+    accounts
+        .into_iter()
+        .map(|account| Node {
+            account: account.clone(),
+            depth: parents(account).len(),
+            opening: opening.get(account).copied().unwrap_or_default(),
+            closing: closing[account.as_str()],
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal_macros::dec;
+
+    #[test]
+    fn test_tree() {
+        assert_eq!(parents("expenses:utilities:gas"), vec!["expenses", "expenses:utilities"]);
+        assert_eq!(parents("main"), Vec::<&str>::new());
+        let balances = |gas, water| {
+            Balances::from_iter([
+                ("main".to_string(), dec!(1000)),
+                ("expenses:utilities:gas".to_string(), gas),
+                ("expenses:utilities:water".to_string(), water),
+                ("expenses:food".to_string(), dec!(50)),
+                ("expenses-other".to_string(), dec!(5)),
+            ])
+        };
+        let (opening, closing) = (balances(dec!(0), dec!(0)), balances(dec!(80), dec!(30)));
+        assert!(nested(&opening));
+        assert_eq!(with_parents(&closing)["expenses"], dec!(160));
+        let node = |account: &str, depth, opening, closing| Node { account: account.to_string(), depth, opening, closing };
+        let tree = tree(&opening, &closing);
+        assert_eq!(
+            tree,
+            vec![
+                node("expenses", 0, dec!(50), dec!(160)),
+                node("expenses:food", 1, dec!(50), dec!(50)),
+                node("expenses:utilities", 1, dec!(0), dec!(110)),
+                node("expenses:utilities:gas", 2, dec!(0), dec!(80)),
+                node("expenses:utilities:water", 2, dec!(0), dec!(30)),
+            ]
+        );
+        assert_eq!(tree[3].name(), "gas");
+    }
+}
@@ -0,0 +1,154 @@
+use chrono::{DateTime, NaiveDate, Utc};
+use rust_decimal::Decimal;
+
+use crate::journal::Posting;
+
+/// A generator's payments on a day, as a calendar event
+#[derive(Debug, Clone, PartialEq)]
+pub struct Event {
+    pub date: NaiveDate,
+    pub generator: String,
+    pub summary: String,
+    // One line for each posting
+    pub description: Vec<String>,
+}
+
+/// One all-day event for each run of a generator's postings on a day, titled with
+/// the generator and its description, and what it pays into or out of
+/// `main`, or what it moves if it doesn't touch `main`
+pub fn events(postings: &[Posting], main: &str, currency_symbol: &str) -> Vec<Event> {
+    // Each event, with what it pays into `main` if it touches it, and the total it moves
+    let mut events: Vec<(Event, Option<Decimal>, Decimal)> = Vec::new();
+    for posting in postings {
+        // A generator's postings on a day come together, so two generators with the same label stay apart
+        let index = match events.last() {
+            Some((event, _, _)) if event.date == posting.date && event.generator == posting.generator => events.len() - 1,
+            _ => {
+                let event = Event { date: posting.date, generator: posting.generator.clone(), summary: posting.source(), description: Vec::new() };
+                events.push((event, None, Decimal::ZERO));
+                events.len() - 1
+            }
+        };
+        let (event, to_main, moved) = &mut events[index];
+        event.description.push(format!(
+            "{}: {currency_symbol}{:.2} from {} to {}",
+            posting.description, posting.amount, posting.from, posting.to
+        ));
+        if posting.to == main {
+            *to_main = Some(to_main.unwrap_or_default() + posting.amount);
+        }
+        if posting.from == main {
+            *to_main = Some(to_main.unwrap_or_default() - posting.amount);
+        }
+        *moved += posting.amount;
+    }
+    events
+        .into_iter()
+        .map(|(event, to_main, moved)| {
+            let amount = match to_main {
+                Some(to_main) => {
+                    let sign = if to_main < Decimal::ZERO { "-" } else { "+" };
+                    format!("{sign}{currency_symbol}{:.2}", to_main.abs())
+                }
+                None => format!("{currency_symbol}{moved:.2}"),
+            };
+            Event { summary: format!("{} {amount}", event.summary), ..event }
+        })
+        .collect()
+}
+
+/// `text` escaped for an iCalendar property value
+fn escape(text: &str) -> String {
+    text.replace('\\', "\\\\").replace(';', "\\;").replace(',', "\\,").replace('\n', "\\n")
+}
+
+/// `line` folded so no line is longer than 75 bytes, as iCalendar requires
+fn fold(line: &str) -> String {
+    let mut folded = String::new();
+    let mut length = 0;
+    for c in line.chars() {
+        if length + c.len_utf8() > 75 {
+            folded.push_str("\r\n ");
+            length = 1;
+        }
+        folded.push(c);
+        length += c.len_utf8();
+    }
+    folded
+}
+
+/// An iCalendar file of `events`, stamped with `generated`
+pub fn calendar(events: &[Event], generated: DateTime<Utc>) -> String {
+    let mut lines = vec!["BEGIN:VCALENDAR".to_string(), "VERSION:2.0".to_string(), "PRODID:-//cash-forecast//EN".to_string()];
+    for (number, event) in events.iter().enumerate() {
+        lines.push("BEGIN:VEVENT".to_string());
+        // Made from the date and generator, so importing a later run updates the events rather than adding copies,
+        // and numbered after the first when generators with the same label post on the same day
+        let uid = format!("{}-{}", event.date.format("%Y%m%d"), crate::stamp::config_hash(&event.generator));
+        let repeat = events[..number].iter().filter(|other| other.date == event.date && other.generator == event.generator).count();
+        match repeat {
+            0 => lines.push(format!("UID:{uid}@cash-forecast")),
+            _ => lines.push(format!("UID:{uid}-{}@cash-forecast", repeat + 1)),
+        }
+        lines.push(format!("DTSTAMP:{}", generated.format("%Y%m%dT%H%M%SZ")));
+        lines.push(format!("DTSTART;VALUE=DATE:{}", event.date.format("%Y%m%d")));
+        lines.push(format!("DTEND;VALUE=DATE:{}", event.date.succ_opt().unwrap_or(event.date).format("%Y%m%d")));
+        lines.push(format!("SUMMARY:{}", escape(&event.summary)));
+        lines.push(format!("DESCRIPTION:{}", escape(&event.description.join("\n"))));
+        lines.push("TRANSP:TRANSPARENT".to_string());
+        lines.push("END:VEVENT".to_string());
+    }
+    lines.push("END:VCALENDAR".to_string());
+    lines.iter().map(|line| fold(line) + "\r\n").collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal_macros::dec;
+
+    #[test]
+    fn test_events() {
+        let date = NaiveDate::from_ymd_opt(2025, 1, 28).unwrap();
+        let posting = |generator: &str, description: &str, from: &str, to: &str, amount| Posting {
+            date,
+            generator: generator.to_string(),
+            description: description.to_string(),
+            from: from.to_string(),
+            to: to.to_string(),
+            amount,
+            received: None,
+            note: None,
+        };
+        let postings = [
+            posting("Salary", "income tax", "salary_income", "income_tax", dec!(500)),
+            posting("Salary", "net pay", "salary_income", "main", dec!(2500)),
+            posting("Mortgage", "mortgage payment", "main", "mortgage", dec!(800)),
+            Posting { note: Some("Rainy day fund".to_string()), ..posting("Save", "transfer", "current", "savings", dec!(100)) },
+        ];
+        let events = events(&postings, "main", "£");
+        let summaries: Vec<&str> = events.iter().map(|event| event.summary.as_str()).collect();
+        assert_eq!(summaries, vec!["Salary +£2500.00", "Mortgage -£800.00", "Save \"Rainy day fund\" £100.00"]);
+        assert_eq!(events[0].description, vec!["income tax: £500.00 from salary_income to income_tax", "net pay: £2500.00 from salary_income to main"]);
+
+        let generated = date.and_hms_opt(9, 30, 0).unwrap().and_utc();
+        let ics = calendar(&events[1..2], generated);
+        assert_eq!(
+            ics,
+            "BEGIN:VCALENDAR\r\nVERSION:2.0\r\nPRODID:-//cash-forecast//EN\r\nBEGIN:VEVENT\r\nUID:20250128-c6b125d592b149cf@cash-forecast\r\n\
+             DTSTAMP:20250128T093000Z\r\nDTSTART;VALUE=DATE:20250128\r\nDTEND;VALUE=DATE:20250129\r\nSUMMARY:Mortgage -£800.00\r\n\
+             DESCRIPTION:mortgage payment: £800.00 from main to mortgage\r\nTRANSP:TRANSPARENT\r\nEND:VEVENT\r\nEND:VCALENDAR\r\n"
+        );
+
+        // Two generators both called Mortgage pay on the same day, with another in between
+        let paid = [postings[2].clone(), postings[3].clone(), postings[2].clone()];
+        let repeated = super::events(&paid, "main", "£");
+        assert_eq!(repeated.len(), 3);
+        let uids: Vec<String> = calendar(&repeated, generated).lines().filter(|line| line.starts_with("UID:")).map(str::to_string).collect();
+        assert_eq!(uids[0], "UID:20250128-c6b125d592b149cf@cash-forecast");
+        assert_eq!(uids[2], "UID:20250128-c6b125d592b149cf-2@cash-forecast");
+        assert_eq!(escape("a, b; c\nd"), "a\\, b\\; c\\nd");
+        let folded = fold(&"x".repeat(80));
+        assert_eq!(folded, format!("{}\r\n {}", "x".repeat(75), "x".repeat(5)));
+    }
+}
@@ -0,0 +1,134 @@
+use serde_yaml::{Mapping, Value};
+use std::path::{Component, Path, PathBuf};
+
+/// The config document `text`, read from `path`, with the files its
+/// `include` list names merged in beneath it, e.g. `include: [mortgage.yaml,
+/// bills.yaml]`. Included files are read with `read`, relative to the file
+/// including them, and may include others in turn. Lists such as
+/// `generators` are joined, included files' first; mappings such as
+/// `accounts` are merged, with a later file's entry replacing an earlier
+/// one's; and any other setting is taken from the last file to give it.
+/// A document without includes is returned as it is.
+pub fn resolve(path: &Path, text: &str, read: &dyn Fn(&Path) -> Result<String, String>) -> Result<String, String> {
+    let document: Value = serde_yaml::from_str(text).map_err(|e| e.to_string())?;
+    if document.get("include").is_none() {
+        return Ok(text.to_string());
+    }
+    let merged = expand(path, document, read, &mut vec![normal(path)])?;
+    serde_yaml::to_string(&merged).map_err(|e| e.to_string())
+}
+
+/// `document` with its includes merged in, where `within` are the files
+/// being included, outermost first, so a file including itself is caught
+fn expand(path: &Path, mut document: Value, read: &dyn Fn(&Path) -> Result<String, String>, within: &mut Vec<PathBuf>) -> Result<Value, String> {
+    let Some(includes) = document.as_mapping_mut().and_then(|mapping| mapping.remove("include")) else {
+        return Ok(document);
+    };
+    let files: Vec<String> =
+        serde_yaml::from_value(includes).map_err(|_| format!("{}: include must be a list of files", path.display()))?;
+    let mut merged = Value::Mapping(Mapping::new());
+    for file in files {
+        let included = normal(&path.parent().unwrap_or(Path::new("")).join(&file));
+        if within.contains(&included) {
+            return Err(format!("{} includes {file}, which includes it in turn", path.display()));
+        }
+        let text = read(&included).map_err(|e| format!("Can't read {}, included by {}: {e}", included.display(), path.display()))?;
+        let value: Value = serde_yaml::from_str(&text).map_err(|e| format!("{}: {e}", included.display()))?;
+        within.push(included.clone());
+        let value = expand(&included, value, read, within)?;
+        within.pop();
+        merge(&mut merged, value);
+    }
+    merge(&mut merged, document);
+    Ok(merged)
+}
+
+/// `path` without `.` and with each `..` taking off the directory before it,
+/// so the same file reached by different routes is seen to be the same
+fn normal(path: &Path) -> PathBuf {
+    let mut normal = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir if normal.file_name().is_some() => {
+                normal.pop();
+            }
+            component => normal.push(component),
+        }
+    }
+    normal
+}
+
+/// Lay the settings of `over` on top of `base`
+fn merge(base: &mut Value, over: Value) {
+    let (Some(base), Value::Mapping(over)) = (base.as_mapping_mut(), over) else {
+        return;
+    };
+    for (key, value) in over {
+        match (base.get_mut(&key), value) {
+            (Some(Value::Sequence(earlier)), Value::Sequence(later)) => earlier.extend(later),
+            (Some(Value::Mapping(earlier)), Value::Mapping(later)) => earlier.extend(later),
+            (_, value) => {
+                base.insert(key, value);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    #[test]
+    fn test_resolve() {
+        let files = HashMap::from([
+            (
+                "shared/mortgage.yaml",
+                "
+include: [rates.yaml]
+generators:
+  - type: mortgage
+    name: Mortgage
+    deduction_amount: 1000
+    day: 1
+accounts:
+  mortgage: -200000
+  main: 500
+",
+            ),
+            ("shared/rates.yaml", "inflation_rate: 2.5\n"),
+            ("bills.yaml", "generators:\n  - {type: expense, name: Council tax, amount: 150, day: 1, category: bills}\n"),
+            ("loop.yaml", "include: [shared/../config.yaml]\n"),
+        ]);
+        let read = |path: &Path| files.get(path.to_str().unwrap()).map(|text| text.to_string()).ok_or("not found".to_string());
+        let config = "
+include: [shared/mortgage.yaml, bills.yaml]
+inflation_rate: 3
+generators:
+  - {type: salary, name: Salary, amount: 3000, day: 25}
+accounts:
+  main: 1000
+";
+        let merged: Value = serde_yaml::from_str(&resolve(Path::new("config.yaml"), config, &read).unwrap()).unwrap();
+        let names: Vec<&str> = merged["generators"].as_sequence().unwrap().iter().map(|generator| generator["name"].as_str().unwrap()).collect();
+        assert_eq!(names, vec!["Mortgage", "Council tax", "Salary"]);
+        // The including file's own settings win
+        assert_eq!(merged["accounts"]["main"].as_i64(), Some(1000));
+        assert_eq!(merged["accounts"]["mortgage"].as_i64(), Some(-200000));
+        assert_eq!(merged["inflation_rate"].as_f64(), Some(3.0));
+        assert!(merged.get("include").is_none());
+
+        let plain = "accounts:\n  main: 1000.10\n";
+        assert_eq!(resolve(Path::new("config.yaml"), plain, &read), Ok(plain.to_string()));
+        assert_eq!(
+            resolve(Path::new("config.yaml"), "include: [missing.yaml]\n", &read),
+            Err("Can't read missing.yaml, included by config.yaml: not found".to_string())
+        );
+        assert_eq!(
+            resolve(Path::new("config.yaml"), "include: [loop.yaml]\n", &read),
+            Err("loop.yaml includes shared/../config.yaml, which includes it in turn".to_string())
+        );
+        assert_eq!(resolve(Path::new("config.yaml"), "include: bills.yaml\n", &read), Err("config.yaml: include must be a list of files".to_string()));
+    }
+}
@@ -0,0 +1,28 @@
+use chrono::NaiveDate;
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+
+use crate::rounding::Rounding;
+
+/// `amount` at the start of the forecast, inflated to `date` at `inflation`
+/// percent for each full year since `start`
+pub fn inflated(amount: Decimal, inflation: Decimal, start: NaiveDate, date: NaiveDate, rounding: Rounding) -> Decimal {
+    let years = date.years_since(start).unwrap_or(0);
+    let growth = (0..years).fold(Decimal::ONE, |acc, _| acc * (Decimal::ONE + inflation / dec!(100)));
+    rounding.round(amount * growth)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_inflated_on_each_anniversary() {
+        let date = |y, m, d| NaiveDate::from_ymd_opt(y, m, d).unwrap();
+        let start = date(2025, 3, 15);
+        assert_eq!(inflated(dec!(100), dec!(5), start, date(2026, 3, 14), Rounding::default()), dec!(100));
+        assert_eq!(inflated(dec!(100), dec!(5), start, date(2026, 3, 15), Rounding::default()), dec!(105.00));
+        assert_eq!(inflated(dec!(100), dec!(5), start, date(2027, 3, 15), Rounding::default()), dec!(110.25));
+        assert_eq!(inflated(dec!(100), dec!(5), start, date(2024, 1, 1), Rounding::default()), dec!(100));
+    }
+}
@@ -0,0 +1,233 @@
+use chrono::{Datelike, NaiveDate};
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+
+/// How the interest due on a payment date is worked out. Also accepted as
+/// `compounding: monthly_snapshot` or `compounding: daily_accrual`.
+#[derive(Debug, Clone, Copy, PartialEq, Default, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum InterestMethod {
+    /// Annual rate divided across the year's payments, applied to the balance on the payment day
+    #[default]
+    #[serde(alias = "monthly_snapshot")]
+    BalanceOnDay,
+    /// Annual rate applied to the average of the daily balances since the last payment,
+    /// for the number of days in that period. This is the same as accruing a day's
+    /// interest on each day's balance and posting the total on the payment day.
+    #[serde(alias = "daily_accrual")]
+    AverageDailyBalance,
+}
+
+/// A rate that applies from `from` until the next period starts
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct RatePeriod {
+    pub from: NaiveDate,
+    pub rate: Decimal,
+}
+
+/// The annual percentage rate in force on `date`: the latest period in
+/// `schedule` that has started, or `rate` if none has
+pub fn rate_on(rate: Decimal, schedule: &[RatePeriod], date: NaiveDate) -> Decimal {
+    schedule
+        .iter()
+        .filter(|period| period.from <= date)
+        .max_by_key(|period| period.from)
+        .map_or(rate, |period| period.rate)
+}
+
+/// `margin` over the reference rate `base` from `reference_rates` on `date`,
+/// or just `margin` if it tracks nothing. A reference rate is zero before its
+/// first period starts.
+pub fn tracking(margin: Decimal, base: Option<&str>, reference_rates: &HashMap<String, Vec<RatePeriod>>, date: NaiveDate) -> Decimal {
+    let Some(base) = base else {
+        return margin;
+    };
+    let schedule = reference_rates.get(base).unwrap_or_else(|| panic!("Reference rate '{base}' not found in reference_rates"));
+    margin + rate_on(Decimal::ZERO, schedule, date)
+}
+
+/// A rate that applies to the part of a balance up to `upto`, above the tier before
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct Tier {
+    // Top of the tier; None for the top tier
+    pub upto: Option<Decimal>,
+    // The margin over `base` if it's given
+    #[serde(alias = "margin")]
+    pub rate: Decimal,
+    // Reference rate from the config's `reference_rates` the tier's rate tracks
+    #[serde(default)]
+    pub base: Option<String>,
+}
+
+/// Why `tiers` can't be followed, if they can't: none given, or a tier whose
+/// top isn't above the one before, or one after the open-ended top tier
+pub fn tiers_problem(tiers: &[Tier]) -> Option<String> {
+    // Top of the tier before; None once past the top tier
+    let mut lower = Some(Decimal::ZERO);
+    for (number, tier) in tiers.iter().enumerate() {
+        let Some(below) = lower else {
+            return Some(format!("tier {} comes after a tier with no upto", number + 1));
+        };
+        match tier.upto {
+            Some(upto) if upto <= below => return Some(format!("tier {} is up to {upto}, which isn't above {below}", number + 1)),
+            upto => lower = upto,
+        }
+    }
+    tiers.is_empty().then(|| "no tiers are given".to_string())
+}
+
+/// A year's interest on `balance` at the rate of each of `tiers` on the part
+/// of the balance in that tier, and none above the last tier's top; nothing
+/// is paid on a balance below zero. None if it's too large for a Decimal.
+pub fn tiered_annual_interest(balance: Decimal, tiers: &[Tier]) -> Option<Decimal> {
+    let mut interest = Decimal::ZERO;
+    let mut lower = Decimal::ZERO;
+    for tier in tiers {
+        let upper = tier.upto.map_or(balance, |upto| upto.min(balance));
+        if upper > lower {
+            interest = interest.checked_add((upper - lower).checked_mul(tier.rate)? / dec!(100))?;
+        }
+        lower = lower.max(upper);
+    }
+    Some(interest)
+}
+
+/// Running record of an interest generator's account between payments
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct InterestAccrual {
+    balance_sum: Decimal,
+    days: u32,
+    // Interest paid to the account in each (year, month), negative for interest charged on a debt
+    pub paid: BTreeMap<(i32, u32), Decimal>,
+}
+
+impl InterestAccrual {
+    /// Note the balance at the point the generator runs on a simulated day
+    pub fn record(&mut self, balance: Decimal) {
+        self.balance_sum += balance;
+        self.days += 1;
+    }
+
+    /// Note interest paid to the account on `date`
+    pub fn pay(&mut self, date: NaiveDate, interest: Decimal) {
+        *self.paid.entry((date.year(), date.month())).or_default() += interest;
+    }
+
+    /// Start a new period once interest has been paid
+    pub fn reset(&mut self) {
+        self.balance_sum = Decimal::ZERO;
+        self.days = 0;
+    }
+
+    fn average(&self) -> Decimal {
+        if self.days == 0 {
+            Decimal::ZERO
+        } else {
+            self.balance_sum / Decimal::from(self.days)
+        }
+    }
+}
+
+/// Unrounded interest due today, for an annual percentage `rate` paid
+/// `periods_per_year` times a year, or None if it's too large for a Decimal
+pub fn interest_due(
+    method: InterestMethod,
+    balance: Decimal,
+    rate: Decimal,
+    periods_per_year: Decimal,
+    accrual: &InterestAccrual,
+) -> Option<Decimal> {
+    match method {
+        InterestMethod::BalanceOnDay => balance.checked_mul(rate / periods_per_year / dec!(100)),
+        InterestMethod::AverageDailyBalance => {
+            Some(accrual.average().checked_mul(rate / dec!(100))?.checked_mul(Decimal::from(accrual.days))? / dec!(365))
+        }
+    }
+}
+
+/// Unrounded interest due today on a balance earning `tiers`, paid
+/// `periods_per_year` times a year, or None if it's too large for a Decimal
+pub fn tiered_interest_due(
+    method: InterestMethod,
+    balance: Decimal,
+    tiers: &[Tier],
+    periods_per_year: Decimal,
+    accrual: &InterestAccrual,
+) -> Option<Decimal> {
+    match method {
+        InterestMethod::BalanceOnDay => tiered_annual_interest(balance, tiers)?.checked_div(periods_per_year),
+        InterestMethod::AverageDailyBalance => {
+            Some(tiered_annual_interest(accrual.average(), tiers)?.checked_mul(Decimal::from(accrual.days))? / dec!(365))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rate_on() {
+        let date = |y, m, d| NaiveDate::from_ymd_opt(y, m, d).unwrap();
+        let schedule = vec![
+            RatePeriod { from: date(2027, 3, 1), rate: dec!(6.5) },
+            RatePeriod { from: date(2025, 1, 1), rate: dec!(5.0) },
+        ];
+        assert_eq!(rate_on(dec!(4), &schedule, date(2024, 12, 31)), dec!(4));
+        assert_eq!(rate_on(dec!(4), &schedule, date(2025, 1, 1)), dec!(5.0));
+        assert_eq!(rate_on(dec!(4), &schedule, date(2027, 2, 28)), dec!(5.0));
+        assert_eq!(rate_on(dec!(4), &schedule, date(2027, 3, 1)), dec!(6.5));
+        assert_eq!(rate_on(dec!(4), &[], date(2027, 3, 1)), dec!(4));
+
+        let reference_rates = HashMap::from([("boe_base".to_string(), schedule)]);
+        assert_eq!(tracking(dec!(1.5), Some("boe_base"), &reference_rates, date(2027, 3, 1)), dec!(8.0));
+        assert_eq!(tracking(dec!(1.5), Some("boe_base"), &reference_rates, date(2024, 6, 1)), dec!(1.5));
+        assert_eq!(tracking(dec!(1.5), None, &reference_rates, date(2027, 3, 1)), dec!(1.5));
+    }
+
+    #[test]
+    fn test_tiered_interest() {
+        let tiers = vec![Tier { upto: Some(dec!(5000)), rate: dec!(5), base: None }, Tier { upto: None, rate: dec!(2), base: None }];
+        assert_eq!(tiered_annual_interest(dec!(3000), &tiers), Some(dec!(150)));
+        // 5% of the first 5,000 and 2% of the 7,000 above it
+        assert_eq!(tiered_annual_interest(dec!(12000), &tiers), Some(dec!(390)));
+        assert_eq!(tiered_annual_interest(dec!(-100), &tiers), Some(Decimal::ZERO));
+        assert_eq!(tiered_interest_due(InterestMethod::BalanceOnDay, dec!(12000), &tiers, dec!(12), &InterestAccrual::default()), Some(dec!(32.5)));
+        // Nothing is paid above a capped top tier
+        assert_eq!(tiered_annual_interest(dec!(9000), &tiers[..1]), Some(dec!(250)));
+        assert_eq!(tiered_annual_interest(Decimal::MAX, &tiers), None);
+        assert_eq!(tiers_problem(&tiers), None);
+        assert_eq!(tiers_problem(&[]), Some("no tiers are given".to_string()));
+        let backwards = vec![tiers[1].clone(), tiers[0].clone()];
+        assert_eq!(tiers_problem(&backwards), Some("tier 2 comes after a tier with no upto".to_string()));
+        let overlapping = vec![tiers[0].clone(), Tier { upto: Some(dec!(1000)), rate: dec!(1), base: None }];
+        assert_eq!(tiers_problem(&overlapping), Some("tier 2 is up to 1000, which isn't above 5000".to_string()));
+    }
+
+    #[test]
+    fn test_balance_on_day_ignores_history() {
+        let mut accrual = InterestAccrual::default();
+        accrual.record(dec!(1));
+        let interest = interest_due(InterestMethod::BalanceOnDay, dec!(1200), dec!(6), dec!(12), &accrual);
+        assert_eq!(interest, Some(dec!(6)));
+        assert_eq!(interest_due(InterestMethod::BalanceOnDay, Decimal::MAX, dec!(1200), dec!(1), &accrual), None);
+    }
+
+    #[test]
+    fn test_average_daily_balance() {
+        let mut accrual = InterestAccrual::default();
+        for _ in 0..10 {
+            accrual.record(dec!(1000));
+        }
+        for _ in 0..20 {
+            accrual.record(dec!(4000));
+        }
+        // Average of 3000 over 30 days at 7.3% a year
+        let interest = interest_due(InterestMethod::AverageDailyBalance, dec!(4000), dec!(7.3), dec!(12), &accrual);
+        assert_eq!(interest, Some(dec!(18)));
+        accrual.reset();
+        assert_eq!(interest_due(InterestMethod::AverageDailyBalance, dec!(4000), dec!(7.3), dec!(12), &accrual), Some(Decimal::ZERO));
+    }
+}
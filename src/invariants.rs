@@ -0,0 +1,90 @@
+use chrono::NaiveDate;
+use rust_decimal::Decimal;
+use std::fmt;
+
+use crate::balances::Balances;
+use crate::currency::Currencies;
+
+/// A day on which the forecast broke one of its invariants
+#[derive(Debug, Clone, PartialEq)]
+pub struct Violation {
+    pub date: NaiveDate,
+    pub problem: String,
+}
+
+impl fmt::Display for Violation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.date, self.problem)
+    }
+}
+
+/// What every day of a forecast has to satisfy, whatever its generators do
+pub struct Invariants<'a> {
+    // Balances are added up in the base currency
+    pub currencies: &'a Currencies<'a>,
+    // Liabilities that may never go above zero, i.e. into credit
+    pub liabilities: Vec<String>,
+    // Largest balance, either way, held precisely, beyond which growth could overflow
+    pub limit: Decimal,
+}
+
+impl Invariants<'_> {
+    /// Everything wrong with a day's `balances`: not summing to zero, a
+    /// liability in credit, or a balance beyond the limit
+    pub fn violations(&self, date: NaiveDate, balances: &Balances) -> Vec<Violation> {
+        let mut problems = Vec::new();
+        let total = self.currencies.total(balances.iter(), date);
+        if !total.is_zero() {
+            problems.push(format!("balances sum to {total}, not zero"));
+        }
+        for account in &self.liabilities {
+            if let Some(balance) = balances.get(account)
+                && *balance > Decimal::ZERO
+            {
+                problems.push(format!("liability {account} is in credit at {balance}"));
+            }
+        }
+        for (account, balance) in balances.iter().filter(|(_, balance)| balance.abs() > self.limit) {
+            problems.push(format!("{account} is {balance}, beyond the {} that can be held precisely", self.limit));
+        }
+        problems.into_iter().map(|problem| Violation { date, problem }).collect()
+    }
+}
+
+/// Every day in `history` that breaks `invariants`, in date order
+pub fn verify_invariants(history: &[(NaiveDate, Balances)], invariants: &Invariants) -> Vec<Violation> {
+    history.iter().flat_map(|(date, balances)| invariants.violations(*date, balances)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rounding::Rules;
+    use rust_decimal_macros::dec;
+
+    #[test]
+    fn test_verify_invariants() {
+        let rules = Rules::default();
+        let currencies = Currencies::new("GBP", [], &[], &rules);
+        let invariants = Invariants { currencies: &currencies, liabilities: vec!["card".to_string()], limit: dec!(1000) };
+        let day = |d| NaiveDate::from_ymd_opt(2025, 1, d).unwrap();
+        let balances = |main, card, opening| {
+            Balances::from_iter([("main".to_string(), main), ("card".to_string(), card), ("opening_balances".to_string(), opening)])
+        };
+        let history = vec![
+            (day(1), balances(dec!(100), dec!(-50), dec!(-50))),
+            (day(2), balances(dec!(100), dec!(10), dec!(-100))),
+            (day(3), balances(dec!(1500), dec!(-50), dec!(-1450))),
+        ];
+        let violations: Vec<String> = verify_invariants(&history, &invariants).iter().map(Violation::to_string).collect();
+        assert_eq!(
+            violations,
+            vec![
+                "2025-01-02: balances sum to 10, not zero",
+                "2025-01-02: liability card is in credit at 10",
+                "2025-01-03: main is 1500, beyond the 1000 that can be held precisely",
+                "2025-01-03: opening_balances is -1450, beyond the 1000 that can be held precisely",
+            ]
+        );
+    }
+}
@@ -0,0 +1,59 @@
+use rust_decimal::prelude::{FromPrimitive, ToPrimitive};
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+use serde::{Deserialize, Serialize};
+
+/// Growth per period that compounds to `annual_return` percent over a year
+/// of `periods_per_year` periods
+pub fn growth_rate(annual_return: Decimal, periods_per_year: Decimal) -> Decimal {
+    let annual = (Decimal::ONE + annual_return / dec!(100)).to_f64().unwrap_or(1.0);
+    let periods = periods_per_year.to_f64().unwrap_or(12.0);
+    Decimal::from_f64(annual.powf(1.0 / periods) - 1.0).unwrap_or_default().round_dp(10)
+}
+
+/// What has been paid into an investment in the current tax year, against
+/// an allowance such as the £20,000 ISA limit
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Contributions {
+    tax_year: i32,
+    made: Decimal,
+}
+
+impl Contributions {
+    /// Pay in up to `amount` in `tax_year` without going over `cap` for the
+    /// year, returning how much was paid in
+    pub fn take(&mut self, tax_year: i32, amount: Decimal, cap: Option<Decimal>) -> Decimal {
+        if tax_year != self.tax_year {
+            self.tax_year = tax_year;
+            self.made = Decimal::ZERO;
+        }
+        let taken = cap.map_or(amount, |cap| amount.min(cap - self.made)).max(Decimal::ZERO);
+        self.made += taken;
+        taken
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_contributions_capped_per_tax_year() {
+        let date = |y, m, d| crate::tax::uk::tax_year(chrono::NaiveDate::from_ymd_opt(y, m, d).unwrap());
+        let mut contributions = Contributions::default();
+        assert_eq!(contributions.take(date(2025, 4, 6), dec!(15000), Some(dec!(20000))), dec!(15000));
+        assert_eq!(contributions.take(date(2026, 1, 6), dec!(15000), Some(dec!(20000))), dec!(5000));
+        assert_eq!(contributions.take(date(2026, 4, 5), dec!(100), Some(dec!(20000))), dec!(0));
+        // The allowance is fresh from 6 April
+        assert_eq!(contributions.take(date(2026, 4, 6), dec!(100), Some(dec!(20000))), dec!(100));
+        assert_eq!(contributions.take(date(2026, 5, 6), dec!(50000), None), dec!(50000));
+    }
+
+    #[test]
+    fn test_growth_compounds_to_annual_return() {
+        let rate = growth_rate(dec!(5), dec!(12));
+        let year = (0..12).fold(dec!(1000), |balance, _| balance * (Decimal::ONE + rate));
+        assert_eq!(year.round_dp(2), dec!(1050.00));
+        assert_eq!(growth_rate(Decimal::ZERO, dec!(12)), Decimal::ZERO);
+    }
+}
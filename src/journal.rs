@@ -0,0 +1,274 @@
+use chrono::NaiveDate;
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use std::ops::{Deref, DerefMut};
+
+use crate::backing::{Backing, InsufficientFunds};
+use crate::balances::Balances;
+use crate::currency::Currencies;
+
+/// Money moved from one account to another by a generator
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Posting {
+    pub date: NaiveDate,
+    pub generator: String,
+    pub description: String,
+    pub from: String,
+    pub to: String,
+    pub amount: Decimal,
+    // What `to` received in its own currency, where that isn't `from`'s
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub received: Option<Decimal>,
+    // Description the config gives the generator, if any
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub note: Option<String>,
+}
+
+impl Posting {
+    /// What the posting paid into `to`
+    pub fn received(&self) -> Decimal {
+        self.received.unwrap_or(self.amount)
+    }
+
+    /// The generator that made the posting, with its description if it has one
+    pub fn source(&self) -> String {
+        match &self.note {
+            Some(note) => format!("{} \"{note}\"", self.generator),
+            None => self.generator.clone(),
+        }
+    }
+}
+
+/// A day's balances, recording each posting made to them
+pub struct Ledger<'a> {
+    pub balances: Balances,
+    pub postings: Vec<Posting>,
+    date: NaiveDate,
+    // Generator the postings are being made for, and its description
+    generator: String,
+    note: Option<String>,
+    // Payments between accounts in different currencies are converted at the day's rate
+    currencies: &'a Currencies<'a>,
+    // Payments that take a backed account below its floor are made good from its backers
+    backing: &'a Backing<'a>,
+    // Account the generator pays from, and what it does when that can't fund a payment
+    funding: Option<(String, InsufficientFunds)>,
+    // Amount the generator's main payment is made for, whatever it works out
+    pinned: Option<Decimal>,
+}
+
+impl<'a> Ledger<'a> {
+    pub fn new(balances: Balances, date: NaiveDate, currencies: &'a Currencies<'a>, backing: &'a Backing<'a>) -> Self {
+        Ledger { balances, postings: Vec::new(), date, generator: String::new(), note: None, currencies, backing, funding: None, pinned: None }
+    }
+
+    /// Put the postings that follow down to `generator`, described by `note`
+    pub fn start(&mut self, generator: String, note: Option<String>) {
+        self.generator = generator;
+        self.note = note;
+        self.funding = None;
+    }
+
+    /// Have payments out of `account` that it can't fund dealt with by
+    /// `policy`, until the next generator starts
+    pub fn fund_from(&mut self, account: &str, policy: InsufficientFunds) {
+        self.funding = Some((account.to_string(), policy));
+    }
+
+    /// Make the generator's main payment `amount`, whatever it would have
+    /// been, such as to put in what a bill actually came to
+    pub fn pin(&mut self, amount: Decimal) {
+        self.pinned = Some(amount);
+    }
+
+    /// The pinned amount in place of `amount`, if there is one
+    pub fn pinned_or(&mut self, amount: Decimal) -> Decimal {
+        self.pinned.take().unwrap_or(amount)
+    }
+
+    /// The pinned amount, if the generator made no payment for it
+    pub fn unpin(&mut self) -> Option<Decimal> {
+        self.pinned.take()
+    }
+
+    /// Move `amount` from `from` to `to`, noting it in the journal unless it's
+    /// nothing, then cover any shortfall it leaves in `from` from its backers.
+    /// Returns what was moved, which is less than `amount` if `from` can't fund
+    /// it all, or fails if its policy is then to stop the forecast
+    pub fn post(&mut self, from: &str, to: &str, mut amount: Decimal, description: &str) -> Result<Decimal, String> {
+        if let Some((account, policy)) = &self.funding
+            && account == from
+            && amount > Decimal::ZERO
+        {
+            let available = self.backing.available(&self.balances, from);
+            if amount > available {
+                match policy {
+                    InsufficientFunds::Skip => {
+                        crate::log::warn(format_args!(
+                            "{}: skipped paying {amount} out of {from} on {}, which has only {available} available",
+                            self.generator, self.date
+                        ));
+                        return Ok(Decimal::ZERO);
+                    }
+                    InsufficientFunds::Partial => {
+                        crate::log::warn(format_args!("{}: paid only {available} of {amount} out of {from} on {}", self.generator, self.date));
+                        amount = available;
+                    }
+                    InsufficientFunds::Overdraw => {}
+                    InsufficientFunds::Error => {
+                        return Err(format!(
+                            "{}: can't pay {amount} out of {from} on {}, which has only {available} available",
+                            self.generator, self.date
+                        ));
+                    }
+                }
+            }
+        }
+        self.record(from, to, amount, description);
+        let backing = self.backing;
+        for (backer, backed, shortfall) in backing.cover(&self.balances, from) {
+            self.record(backer, backed, shortfall, "cover shortfall");
+        }
+        Ok(amount)
+    }
+
+    fn record(&mut self, from: &str, to: &str, amount: Decimal, description: &str) {
+        let received = self.currencies.convert(amount, from, to, self.date);
+        crate::post_converted(&mut self.balances, from, to, amount, received);
+        if !amount.is_zero() {
+            self.postings.push(Posting {
+                date: self.date,
+                generator: self.generator.clone(),
+                description: description.to_string(),
+                from: from.to_string(),
+                to: to.to_string(),
+                amount,
+                received: (received != amount).then_some(received),
+                note: self.note.clone(),
+            });
+        }
+    }
+}
+
+impl Deref for Ledger<'_> {
+    type Target = Balances;
+
+    fn deref(&self) -> &Self::Target {
+        &self.balances
+    }
+}
+
+impl DerefMut for Ledger<'_> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.balances
+    }
+}
+
+/// Each day's balances from the day after `start` to `end`, made by applying
+/// the postings in `plan` to the `opening` balances rather than running the
+/// generators. Amounts are taken as planned, so a change to one posting isn't
+/// passed on to later ones worked out from balances, such as interest.
+pub fn execute(
+    opening: &Balances,
+    start: NaiveDate,
+    end: NaiveDate,
+    plan: &[Posting],
+) -> Result<crate::History, String> {
+    if let Some(posting) = plan.iter().find(|posting| posting.date <= start || posting.date > end) {
+        return Err(format!("{} posting on {} is outside the forecast, {start} to {end}", posting.generator, posting.date));
+    }
+    if let Some(account) = plan.iter().flat_map(|posting| [&posting.from, &posting.to]).find(|account| !opening.contains_key(account)) {
+        return Err(format!("Plan posts to unknown account '{account}'"));
+    }
+    let mut postings: Vec<&Posting> = plan.iter().collect();
+    // Stable, so a day's postings keep their order
+    postings.sort_by_key(|posting| posting.date);
+    let mut postings = postings.into_iter().peekable();
+    let mut balances = opening.clone();
+    let mut history = Vec::new();
+    for date in start.iter_days().skip(1).take_while(|date| *date <= end) {
+        while let Some(posting) = postings.next_if(|posting| posting.date == date) {
+            crate::post_converted(&mut balances, &posting.from, &posting.to, posting.amount, posting.received());
+        }
+        history.push((date, balances.clone()));
+    }
+    Ok(history)
+}
+
+/// The postings as CSV lines, with a header
+pub fn csv(postings: &[Posting]) -> Vec<String> {
+    // Generator names and descriptions are free text, so quote them
+    let quote = |text: &str| format!("\"{}\"", text.replace('"', "\"\""));
+    std::iter::once("Date,Generator,Description,From,To,Amount,Note".to_string())
+        .chain(postings.iter().map(|posting| {
+            format!(
+                "{},{},{},{},{},{},{}",
+                posting.date.format("%Y-%m-%d"),
+                quote(&posting.generator),
+                quote(&posting.description),
+                posting.from,
+                posting.to,
+                posting.amount,
+                posting.note.as_deref().map(quote).unwrap_or_default()
+            )
+        }))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal_macros::dec;
+
+    #[test]
+    fn test_execute() {
+        let date = |d| NaiveDate::from_ymd_opt(2025, 3, d).unwrap();
+        let opening = Balances::from_iter([("main".to_string(), dec!(100)), ("savings".to_string(), dec!(-100))]);
+        let posting = |d, amount| Posting {
+            date: date(d),
+            generator: "Save".to_string(),
+            description: "transfer".to_string(),
+            from: "main".to_string(),
+            to: "savings".to_string(),
+            amount,
+            received: None,
+            note: None,
+        };
+        let history = execute(&opening, date(1), date(4), &[posting(3, dec!(30)), posting(2, dec!(10))]).unwrap();
+        let main: Vec<(NaiveDate, Decimal)> = history.iter().map(|(date, balances)| (*date, balances["main"])).collect();
+        assert_eq!(main, vec![(date(2), dec!(90)), (date(3), dec!(60)), (date(4), dec!(60))]);
+
+        assert_eq!(
+            execute(&opening, date(1), date(4), &[posting(5, dec!(1))]),
+            Err("Save posting on 2025-03-05 is outside the forecast, 2025-03-01 to 2025-03-04".to_string())
+        );
+        let stray = Posting { to: "holiday".to_string(), ..posting(2, dec!(1)) };
+        assert_eq!(execute(&opening, date(1), date(4), &[stray]), Err("Plan posts to unknown account 'holiday'".to_string()));
+    }
+
+    #[test]
+    fn test_ledger() {
+        let date = NaiveDate::from_ymd_opt(2025, 3, 1).unwrap();
+        let balances = Balances::from_iter([("main".to_string(), dec!(100)), ("savings".to_string(), dec!(-100))]);
+        let rounding = crate::rounding::Rules::default();
+        let currencies = Currencies::new("GBP", [], &[], &rounding);
+        let backing = Backing::default();
+        let mut ledger = Ledger::new(balances, date, &currencies, &backing);
+        ledger.start("Save \"rainy day\"".to_string(), None);
+        ledger.post("main", "savings", dec!(40), "transfer").unwrap();
+        ledger.post("main", "savings", Decimal::ZERO, "transfer").unwrap();
+        ledger.start("Holiday".to_string(), Some("Flights to Lisbon".to_string()));
+        ledger.post("main", "savings", dec!(25), "transfer").unwrap();
+        assert_eq!(ledger["main"], dec!(35));
+        assert_eq!(ledger.postings.len(), 2);
+        assert_eq!(
+            csv(&ledger.postings),
+            vec![
+                "Date,Generator,Description,From,To,Amount,Note",
+                "2025-03-01,\"Save \"\"rainy day\"\"\",\"transfer\",main,savings,40,",
+                "2025-03-01,\"Holiday\",\"transfer\",main,savings,25,\"Flights to Lisbon\""
+            ]
+        );
+        assert_eq!(ledger.postings[1].source(), "Holiday \"Flights to Lisbon\"");
+    }
+}
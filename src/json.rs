@@ -0,0 +1,167 @@
+use chrono::{NaiveDate, NaiveDateTime};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+
+use crate::balances::Balances;
+use crate::journal::Posting;
+use crate::stamp::RunStamp;
+
+/// Version of the document's layout, raised whenever a field is renamed,
+/// removed or changes meaning, so tools reading it can tell
+pub const SCHEMA_VERSION: u32 = 1;
+
+/// The whole forecast for other tools to read. Amounts are strings, so they
+/// come through to the penny rather than as floating point.
+#[derive(Debug, Serialize)]
+pub struct Document<'a> {
+    pub schema_version: u32,
+    pub metadata: Metadata,
+    // Every account, sorted by name
+    pub accounts: Vec<String>,
+    pub opening_balances: BTreeMap<&'a str, Decimal>,
+    // Every account's balance at the end of each day of the forecast
+    pub days: Vec<Day<'a>>,
+    pub postings: &'a [Posting],
+}
+
+/// What produced the document, and over what horizon
+#[derive(Debug, Serialize)]
+pub struct Metadata {
+    // Null for a run `--as-of` a date, so its output is the same every time
+    pub generated_at: Option<NaiveDateTime>,
+    pub as_of: Option<NaiveDate>,
+    pub version: String,
+    pub config_file: String,
+    pub config_hash: String,
+    pub currency_symbol: String,
+    pub start: NaiveDate,
+    pub end: NaiveDate,
+    pub assumptions: Vec<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Day<'a> {
+    pub date: NaiveDate,
+    pub balances: BTreeMap<&'a str, Decimal>,
+}
+
+/// The parts of a document an earlier run wrote that are read back
+#[derive(Deserialize)]
+struct Saved {
+    schema_version: u32,
+    days: Vec<SavedDay>,
+}
+
+#[derive(Deserialize)]
+struct SavedDay {
+    date: NaiveDate,
+    balances: HashMap<String, Decimal>,
+}
+
+/// The balances each day of a forecast saved as a JSON document
+pub fn read_history(text: &str) -> Result<crate::History, String> {
+    let saved: Saved = serde_json::from_str(text).map_err(|e| e.to_string())?;
+    if saved.schema_version != SCHEMA_VERSION {
+        return Err(format!("Schema version {} can't be read; this version reads {SCHEMA_VERSION}", saved.schema_version));
+    }
+    Ok(saved.days.into_iter().map(|day| (day.date, day.balances.into())).collect())
+}
+
+fn sorted(balances: &Balances) -> BTreeMap<&str, Decimal> {
+    balances.iter().map(|(account, balance)| (account.as_str(), *balance)).collect()
+}
+
+/// The forecast from `opening` balances through `history`, with the postings
+/// that made it, described by `stamp`
+pub fn document<'a>(
+    stamp: &RunStamp,
+    currency_symbol: &str,
+    opening: &'a Balances,
+    history: &'a crate::History,
+    postings: &'a [Posting],
+) -> Document<'a> {
+    let mut accounts: Vec<String> = opening.keys().chain(history.iter().flat_map(|(_, balances)| balances.keys())).cloned().collect();
+    accounts.sort();
+    accounts.dedup();
+    Document {
+        schema_version: SCHEMA_VERSION,
+        metadata: Metadata {
+            generated_at: stamp.as_of.is_none().then_some(stamp.run_at),
+            as_of: stamp.as_of,
+            version: stamp.version.clone(),
+            config_file: stamp.config_file.clone(),
+            config_hash: stamp.config_hash.clone(),
+            currency_symbol: currency_symbol.to_string(),
+            start: stamp.start,
+            end: stamp.end,
+            assumptions: stamp.assumptions.clone(),
+        },
+        accounts,
+        opening_balances: sorted(opening),
+        days: history.iter().map(|(date, balances)| Day { date: *date, balances: sorted(balances) }).collect(),
+        postings,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal_macros::dec;
+
+    #[test]
+    fn test_document() {
+        let date = |d| NaiveDate::from_ymd_opt(2025, 1, d).unwrap();
+        let stamp = RunStamp {
+            run_at: date(3).and_hms_opt(9, 30, 0).unwrap(),
+            as_of: Some(date(1)),
+            config_file: "config.yaml".to_string(),
+            config_hash: "af63dc4c8601ec8c".to_string(),
+            version: "0.1.0".to_string(),
+            start: date(1),
+            end: date(2),
+            assumptions: vec!["inflation of 2.5%".to_string()],
+        };
+        let opening = Balances::from_iter([("savings".to_string(), dec!(-100)), ("main".to_string(), dec!(100))]);
+        let history = vec![(date(2), Balances::from_iter([("savings".to_string(), dec!(-60)), ("main".to_string(), dec!(60))]))];
+        let postings = [Posting {
+            date: date(2),
+            generator: "Save".to_string(),
+            description: "transfer".to_string(),
+            from: "main".to_string(),
+            to: "savings".to_string(),
+            amount: dec!(40),
+            received: None,
+            note: None,
+        }];
+        let json = serde_json::to_value(document(&stamp, "£", &opening, &history, &postings)).unwrap();
+        let expected = serde_json::json!({
+            "schema_version": 1,
+            "metadata": {
+                "generated_at": null,
+                "as_of": "2025-01-01",
+                "version": "0.1.0",
+                "config_file": "config.yaml",
+                "config_hash": "af63dc4c8601ec8c",
+                "currency_symbol": "£",
+                "start": "2025-01-01",
+                "end": "2025-01-02",
+                "assumptions": ["inflation of 2.5%"]
+            },
+            "accounts": ["main", "savings"],
+            "opening_balances": {"main": "100", "savings": "-100"},
+            "days": [{"date": "2025-01-02", "balances": {"main": "60", "savings": "-60"}}],
+            "postings": [
+                {"date": "2025-01-02", "generator": "Save", "description": "transfer", "from": "main", "to": "savings", "amount": "40"}
+            ]
+        });
+        assert_eq!(json, expected);
+        let live = RunStamp { as_of: None, ..stamp.clone() };
+        let json_live = serde_json::to_value(document(&live, "£", &opening, &history, &postings)).unwrap();
+        assert_eq!(json_live["metadata"]["generated_at"], "2025-01-03T09:30:00");
+
+        let history = read_history(&json.to_string()).unwrap();
+        assert_eq!(history, vec![(date(2), Balances::from_iter([("savings".to_string(), dec!(-60)), ("main".to_string(), dec!(60))]))]);
+        assert!(read_history(&json.to_string().replace("\"schema_version\":1", "\"schema_version\":2")).is_err());
+    }
+}
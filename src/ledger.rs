@@ -0,0 +1,261 @@
+use chrono::{Datelike, NaiveDate};
+use rust_decimal::Decimal;
+use serde::Deserialize;
+use std::collections::HashMap;
+
+use crate::balances::Balances;
+use crate::journal::Posting;
+
+/// Where opening balances come from
+pub const OPENING_BALANCES: &str = "Equity:Opening Balances";
+
+/// Top-level account types in plain-text accounting, declared in the config
+/// as `asset`, `liability`, `income` or `expense`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+pub enum AccountType {
+    #[serde(rename = "asset")]
+    Assets,
+    #[serde(rename = "liability")]
+    Liabilities,
+    #[serde(rename = "income")]
+    Income,
+    #[serde(rename = "expense")]
+    Expenses,
+}
+
+impl AccountType {
+    /// The type as written in the config, e.g. `liability`
+    pub fn singular(self) -> &'static str {
+        match self {
+            AccountType::Assets => "asset",
+            AccountType::Liabilities => "liability",
+            AccountType::Income => "income",
+            AccountType::Expenses => "expense",
+        }
+    }
+
+    /// The full name of `account` under this type, e.g. `Assets:main`
+    pub fn name(self, account: &str) -> String {
+        format!("{self:?}:{account}")
+    }
+}
+
+/// `amount` in `currency_symbol`, quoting a symbol ledger wouldn't read as a commodity
+fn amount(amount: Decimal, currency_symbol: &str) -> String {
+    let symbol = if currency_symbol.chars().any(|c| c.is_whitespace() || c.is_ascii_digit() || "-.,;\"".contains(c)) {
+        format!("\"{}\"", currency_symbol.replace('"', ""))
+    } else {
+        currency_symbol.to_string()
+    };
+    let sign = if amount < Decimal::ZERO { "-" } else { "" };
+    format!("{sign}{symbol}{:.2}", amount.abs())
+}
+
+/// A ledger-cli/hledger journal of the forecast: the opening balances on
+/// `start`, then every posting, with accounts named by `names`. `header` lines
+/// go at the top as comments.
+pub fn journal(
+    header: &[String],
+    start: NaiveDate,
+    opening: &[(String, Decimal)],
+    postings: &[Posting],
+    names: &HashMap<String, String>,
+    currency_symbol: &str,
+) -> String {
+    let name = |account: &str| names.get(account).cloned().unwrap_or_else(|| AccountType::Assets.name(account));
+    let mut lines: Vec<String> = header.iter().map(|line| format!("; {line}")).collect();
+    lines.push(String::new());
+    lines.push(format!("{} Opening balances", start.format("%Y-%m-%d")));
+    for (account, balance) in opening.iter().filter(|(_, balance)| !balance.is_zero()) {
+        lines.push(format!("    {}  {}", name(account), amount(*balance, currency_symbol)));
+    }
+    lines.push(format!("    {OPENING_BALANCES}"));
+    for posting in postings {
+        lines.push(String::new());
+        lines.push(format!("{} {} | {}", posting.date.format("%Y-%m-%d"), posting.source(), posting.description));
+        lines.push(format!("    {}  {}", name(&posting.to), amount(posting.amount, currency_symbol)));
+        lines.push(format!("    {}", name(&posting.from)));
+    }
+    lines.push(String::new());
+    lines.join("\n")
+}
+
+/// Commodity code for a currency symbol, as beancount needs one
+pub fn commodity(currency_symbol: &str) -> String {
+    match currency_symbol.trim() {
+        "£" => "GBP".to_string(),
+        "$" => "USD".to_string(),
+        "€" => "EUR".to_string(),
+        "¥" => "JPY".to_string(),
+        "₹" => "INR".to_string(),
+        symbol => {
+            let code: String = symbol.chars().filter(char::is_ascii_alphabetic).collect::<String>().to_uppercase();
+            if code.len() >= 2 { code } else { "CASH".to_string() }
+        }
+    }
+}
+
+/// `name` as beancount accepts it: each part capitalised, words joined by
+/// dashes, e.g. `Liabilities:Credit-Card`
+fn beancount_account(name: &str) -> String {
+    name.split(':')
+        .map(|part| {
+            part.split(|c: char| !c.is_ascii_alphanumeric())
+                .filter(|word| !word.is_empty())
+                .map(|word| word[..1].to_uppercase() + &word[1..])
+                .collect::<Vec<_>>()
+                .join("-")
+        })
+        .collect::<Vec<_>>()
+        .join(":")
+}
+
+/// A beancount ledger of the forecast: an `open` for every account, the
+/// opening balances on `start`, every posting, and an assertion of each
+/// asset and liability's balance at the start of every month in `history`
+pub fn beancount(
+    header: &[String],
+    start: NaiveDate,
+    opening: &[(String, Decimal)],
+    postings: &[Posting],
+    history: &[(NaiveDate, Balances)],
+    names: &HashMap<String, String>,
+    commodity: &str,
+) -> String {
+    let name = |account: &str| beancount_account(&names.get(account).cloned().unwrap_or_else(|| AccountType::Assets.name(account)));
+    let quote = |text: &str| format!("\"{}\"", text.replace('"', "'"));
+    let mut lines: Vec<String> = header.iter().map(|line| format!("; {line}")).collect();
+    lines.push(format!("option \"operating_currency\" \"{commodity}\""));
+    lines.push(String::new());
+    let mut accounts: Vec<String> = opening.iter().map(|(account, _)| name(account)).collect();
+    accounts.extend(postings.iter().flat_map(|posting| [name(&posting.from), name(&posting.to)]));
+    accounts.push(beancount_account(OPENING_BALANCES));
+    accounts.sort();
+    accounts.dedup();
+    for account in &accounts {
+        lines.push(format!("{} open {account} {commodity}", start.format("%Y-%m-%d")));
+    }
+    lines.push(String::new());
+    lines.push(format!("{} * \"Opening balances\"", start.format("%Y-%m-%d")));
+    for (account, balance) in opening.iter().filter(|(_, balance)| !balance.is_zero()) {
+        lines.push(format!("  {}  {balance:.2} {commodity}", name(account)));
+    }
+    lines.push(format!("  {}", beancount_account(OPENING_BALANCES)));
+
+    // Assertions are checked before the day's postings, so a month's closing balance is asserted on the 1st of the next
+    let mut assertions = history.iter().filter_map(|(date, balances)| {
+        let next = date.succ_opt()?;
+        (next.day() == 1).then_some((next, balances))
+    });
+    let mut next_assertion = assertions.next();
+    let mut postings = postings.iter().peekable();
+    loop {
+        let assert_first = match (&next_assertion, postings.peek()) {
+            (Some((date, _)), Some(posting)) => *date <= posting.date,
+            (Some(_), None) => true,
+            (None, Some(_)) => false,
+            (None, None) => break,
+        };
+        lines.push(String::new());
+        if assert_first {
+            let (date, balances) = next_assertion.take().unwrap();
+            let mut held: Vec<(String, Decimal)> = balances
+                .iter()
+                .map(|(account, balance)| (name(account), *balance))
+                .filter(|(account, _)| account.starts_with("Assets:") || account.starts_with("Liabilities:"))
+                .collect();
+            held.sort();
+            for (account, balance) in held {
+                lines.push(format!("{} balance {account}  {balance:.2} {commodity}", date.format("%Y-%m-%d")));
+            }
+            next_assertion = assertions.next();
+        } else {
+            let posting = postings.next().unwrap();
+            lines.push(format!("{} * {} {}", posting.date.format("%Y-%m-%d"), quote(&posting.source()), quote(&posting.description)));
+            lines.push(format!("  {}  {:.2} {commodity}", name(&posting.to), posting.amount));
+            lines.push(format!("  {}", name(&posting.from)));
+        }
+    }
+    lines.push(String::new());
+    lines.join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal_macros::dec;
+
+    #[test]
+    fn test_journal() {
+        let date = |d| NaiveDate::from_ymd_opt(2025, 1, d).unwrap();
+        let names = HashMap::from([
+            ("salary_income".to_string(), AccountType::Income.name("salary_income")),
+            ("mortgage".to_string(), AccountType::Liabilities.name("mortgage")),
+        ]);
+        let opening = [("main".to_string(), dec!(1000)), ("mortgage".to_string(), dec!(-90000)), ("savings".to_string(), Decimal::ZERO)];
+        let postings = [Posting {
+            date: date(28),
+            generator: "Salary".to_string(),
+            description: "net pay".to_string(),
+            from: "salary_income".to_string(),
+            to: "main".to_string(),
+            amount: dec!(2500),
+            received: None,
+            note: None,
+        }];
+        let expected = "\
+; Forecast from 2025-01-01
+
+2025-01-01 Opening balances
+    Assets:main  £1000.00
+    Liabilities:mortgage  -£90000.00
+    Equity:Opening Balances
+
+2025-01-28 Salary | net pay
+    Assets:main  £2500.00
+    Income:salary_income
+";
+        let header = ["Forecast from 2025-01-01".to_string()];
+        assert_eq!(journal(&header, date(1), &opening, &postings, &names, "£"), expected);
+        assert_eq!(amount(dec!(-12.5), "CHF "), "-\"CHF \"12.50");
+    }
+
+    #[test]
+    fn test_beancount() {
+        let date = |m, d| NaiveDate::from_ymd_opt(2025, m, d).unwrap();
+        let names = HashMap::from([("salary_income".to_string(), AccountType::Income.name("salary_income"))]);
+        let opening = [("main".to_string(), dec!(1000)), ("salary_income".to_string(), Decimal::ZERO)];
+        let postings = [Posting {
+            date: date(1, 31),
+            generator: "Salary".to_string(),
+            description: "net pay".to_string(),
+            from: "salary_income".to_string(),
+            to: "main".to_string(),
+            amount: dec!(2500),
+            received: None,
+            note: None,
+        }];
+        let balances = |main| Balances::from_iter([("main".to_string(), main), ("salary_income".to_string(), dec!(1000) - main)]);
+        let history = [(date(1, 30), balances(dec!(1000))), (date(1, 31), balances(dec!(3500))), (date(2, 1), balances(dec!(3500)))];
+        let expected = "\
+option \"operating_currency\" \"GBP\"
+
+2025-01-01 open Assets:Main GBP
+2025-01-01 open Equity:Opening-Balances GBP
+2025-01-01 open Income:Salary-Income GBP
+
+2025-01-01 * \"Opening balances\"
+  Assets:Main  1000.00 GBP
+  Equity:Opening-Balances
+
+2025-01-31 * \"Salary\" \"net pay\"
+  Assets:Main  2500.00 GBP
+  Income:Salary-Income
+
+2025-02-01 balance Assets:Main  3500.00 GBP
+";
+        assert_eq!(beancount(&[], date(1, 1), &opening, &postings, &history, &names, &commodity("£")), expected);
+        assert_eq!(commodity("CHF "), "CHF");
+        assert_eq!(beancount_account("Liabilities:credit card_2"), "Liabilities:Credit-Card-2");
+    }
+}
@@ -0,0 +1,60 @@
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+
+use crate::rounding::Rounding;
+
+/// Level payment that repays `principal` over `payments` instalments at
+/// `rate_per_payment` (as a fraction, not a percentage), rounded to pence
+pub fn level_payment(principal: Decimal, rate_per_payment: Decimal, payments: u32, rounding: Rounding) -> Decimal {
+    if payments == 0 {
+        return principal;
+    }
+    if rate_per_payment.is_zero() {
+        return rounding.round(principal / Decimal::from(payments));
+    }
+    let growth = (0..payments).fold(Decimal::ONE, |acc, _| acc * (Decimal::ONE + rate_per_payment));
+    rounding.round(principal * rate_per_payment * growth / (growth - Decimal::ONE))
+}
+
+/// Interest and principal parts of a payment of `payment` against an
+/// outstanding `balance` (a positive amount owed). The final payment clears
+/// whatever is left, so it may be smaller or slightly larger than the rest.
+pub fn split_payment(balance: Decimal, rate_per_payment: Decimal, payment: Decimal, rounding: Rounding) -> (Decimal, Decimal) {
+    if balance <= Decimal::ZERO {
+        return (Decimal::ZERO, Decimal::ZERO);
+    }
+    let interest = rounding.round(balance * rate_per_payment);
+    let principal = (payment - interest).max(Decimal::ZERO);
+    let principal = if balance - principal < dec!(1.00) { balance } else { principal };
+    (interest, principal)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_level_payment() {
+        assert_eq!(level_payment(dec!(10000), dec!(0.005), 12, Rounding::default()), dec!(860.66));
+        assert_eq!(level_payment(dec!(1200), Decimal::ZERO, 12, Rounding::default()), dec!(100.00));
+    }
+
+    #[test]
+    fn test_schedule_repays_in_full() {
+        let rate = dec!(0.005);
+        let payment = level_payment(dec!(10000), rate, 12, Rounding::default());
+        let mut balance = dec!(10000);
+        let mut total_interest = Decimal::ZERO;
+        for n in 0..12 {
+            let (interest, principal) = split_payment(balance, rate, payment, Rounding::default());
+            if n == 0 {
+                assert_eq!((interest, principal), (dec!(50.00), dec!(810.66)));
+            }
+            balance -= principal;
+            total_interest += interest;
+        }
+        assert_eq!(balance, Decimal::ZERO);
+        assert_eq!(total_interest, dec!(327.96));
+        assert_eq!(split_payment(balance, rate, payment, Rounding::default()), (Decimal::ZERO, Decimal::ZERO));
+    }
+}
@@ -0,0 +1,72 @@
+use std::fmt::Arguments;
+use std::sync::atomic::{AtomicU8, Ordering};
+
+/// How much the forecast says on stderr about what it's doing, chosen with
+/// `-q`, `-v` or `-vv`; errors are always shown
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Verbosity {
+    /// Nothing but errors, and no balances on stdout either
+    Quiet,
+    /// Nothing but errors
+    Normal,
+    /// Warnings, such as payments skipped or cut short for lack of funds, and progress
+    Verbose,
+    /// Everything, down to each generator as it fires
+    Debug,
+}
+
+impl Verbosity {
+    /// The verbosity for `verbose` `-v` flags, or Quiet for `-q`
+    pub fn from_flags(verbose: u8, quiet: bool) -> Self {
+        match (quiet, verbose) {
+            (true, _) => Verbosity::Quiet,
+            (false, 0) => Verbosity::Normal,
+            (false, 1) => Verbosity::Verbose,
+            (false, _) => Verbosity::Debug,
+        }
+    }
+}
+
+/// Shared by every thread, so runs in parallel log the same way
+static VERBOSITY: AtomicU8 = AtomicU8::new(Verbosity::Normal as u8);
+
+pub fn set_verbosity(verbosity: Verbosity) {
+    VERBOSITY.store(verbosity as u8, Ordering::Relaxed);
+}
+
+/// Whether messages at `verbosity` are shown
+pub fn enabled(verbosity: Verbosity) -> bool {
+    VERBOSITY.load(Ordering::Relaxed) >= verbosity as u8
+}
+
+pub fn warn(message: Arguments) {
+    if enabled(Verbosity::Verbose) {
+        eprintln!("warning: {message}");
+    }
+}
+
+pub fn info(message: Arguments) {
+    if enabled(Verbosity::Verbose) {
+        eprintln!("info: {message}");
+    }
+}
+
+pub fn debug(message: Arguments) {
+    if enabled(Verbosity::Debug) {
+        eprintln!("debug: {message}");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_flags() {
+        assert_eq!(Verbosity::from_flags(0, false), Verbosity::Normal);
+        assert_eq!(Verbosity::from_flags(1, false), Verbosity::Verbose);
+        assert_eq!(Verbosity::from_flags(3, false), Verbosity::Debug);
+        assert_eq!(Verbosity::from_flags(0, true), Verbosity::Quiet);
+        assert!(Verbosity::Debug > Verbosity::Verbose && Verbosity::Normal > Verbosity::Quiet);
+    }
+}
@@ -1,33 +1,461 @@
 use chrono::{Datelike, Month};
+use clap::{Parser, Subcommand, ValueEnum};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use rust_decimal::prelude::ToPrimitive;
 use rust_decimal::Decimal;
 use rust_decimal_macros::dec;
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 use std::fs;
 
+mod actuals;
+mod alerts;
+mod backing;
+mod balances;
+mod calendar;
+mod cashback;
+mod checks;
+mod children;
+mod contract;
+mod council_tax;
+mod currency;
+mod debt;
+mod energy;
+mod envelope;
+mod explore;
+mod giving;
+mod goals;
+mod hierarchy;
+mod ics;
+mod include;
+mod inflation;
+mod invariants;
+mod interest;
+mod investment;
+mod journal;
+mod json;
+mod ledger;
+mod log;
+mod loan;
+mod monte_carlo;
+mod overdraft;
+mod overpayment;
+mod parallel;
+mod parquet;
+mod report;
+mod rounding;
+mod salary;
+mod scenario;
+mod schedule;
+mod serve;
+mod share;
+mod share_scheme;
+mod snapshot;
+mod solve;
+mod stamp;
+mod svg;
+mod tax;
+mod validate;
+mod variables;
+mod volatile;
+mod xlsx;
+
+use calendar::{Adjust, HolidayCalendar, WithExtra};
+use debt::{Component, Debt};
+use giving::{TitheBasis, TitheComparison};
+use interest::{InterestAccrual, InterestMethod, RatePeriod};
+use schedule::{DayOfMonth, Schedule};
+
 const MAIN_ACCOUNT: &str = "main";
 const SALARY_INCOME: &str = "salary_income";
 const MORTGAGE_INCOME: &str = "mortgage_income";
 const MORTGAGE_ACCOUNT: &str = "mortgage";
 const OPENING_BALANCES: &str = "opening_balances";
 const CHARITY_EXPENDITURE: &str = "charity_expenditure";
+const RENTAL_INCOME: &str = "rental_income";
+const DIVIDEND_INCOME: &str = "dividend_income";
+const SAVINGS_INTEREST: &str = "savings_interest";
+const GIFT_AID: &str = "gift_aid";
+const GIFT_AID_INCOME: &str = "gift_aid_income";
+const EVENTS_EXPENDITURE: &str = "events_expenditure";
+const COUNCIL_TAX_EXPENDITURE: &str = "council_tax_expenditure";
+const LOAN_INTEREST: &str = "loan_interest";
+const CARD_INTEREST: &str = "card_interest";
+const DEBT_FEES: &str = "debt_fees";
+const INCOME_TAX: &str = "income_tax";
+const NATIONAL_INSURANCE: &str = "national_insurance";
+const STUDENT_LOAN: &str = "student_loan_repayments";
+const ENERGY_CATEGORY: &str = "energy";
+const INVESTMENT_GROWTH: &str = "investment_growth";
+const CASHBACK_INCOME: &str = "cashback_income";
+const SHARE_SCHEME_GAINS: &str = "share_scheme_gains";
+const CAPITAL_GAINS_TAX: &str = "capital_gains_tax";
+const ASSET_REVALUATION: &str = "asset_revaluation";
+const OVERDRAFT_INTEREST: &str = "overdraft_interest";
+// Gains and losses in the base currency on accounts held in other currencies as exchange rates move
+const EXCHANGE_GAINS: &str = "exchange_gains";
+/// Largest balance, in either direction, the engine will carry. Decimal holds
+/// about 28 significant digits, so this leaves ten decimal places for rates to
+/// be applied without losing pennies and keeps a day's growth far from overflow.
+const MAX_BALANCE: Decimal = dec!(1_000_000_000_000_000_000);
+/// Longest forecast that can be run: a century of days
+const MAX_HORIZON_DAYS: i64 = 36525;
+// Days forecast when the config gives no horizon of its own
+const DEFAULT_HORIZON_DAYS: i32 = 6000;
 
-#[derive(Debug, Deserialize, PartialEq)]
+#[derive(Debug, Deserialize, PartialEq, Clone)]
 struct Config {
-    generators: Vec<Generator>,
+    generators: Vec<GeneratorEntry>,
     accounts: std::collections::HashMap<String, Decimal>,
     #[serde(default = "default_currency_symbol")]
     currency_symbol: String,
     #[serde(default = "default_start_date")]
     start_date: chrono::NaiveDate,
+    // If set, uncertain generators are sampled with this seed; otherwise they use expected values
+    #[serde(default)]
+    seed: Option<u64>,
+    // Public holidays that, with weekends, make a day a non-business day
+    #[serde(default)]
+    holiday_calendar: HolidayCalendar,
+    // Extra non-business days on top of the holiday calendar, or the only ones with `holiday_calendar: custom`
+    #[serde(default)]
+    holidays: Vec<chrono::NaiveDate>,
+    // Account standing in for each role, e.g. `main: current_account`. A role
+    // name used anywhere an account is expected, including the defaults, means
+    // the account given here.
+    #[serde(default)]
+    roles: std::collections::HashMap<String, String>,
+    // Country whose tax, National Insurance and student loan rules apply to gross salaries, and those rules by tax year
+    #[serde(default)]
+    tax: tax::TaxSettings,
+    // Children whose typical costs are added as generators
+    #[serde(default)]
+    children: Vec<children::Child>,
+    // Where each account is held, keyed by account name, shown in reports
+    #[serde(default)]
+    account_details: std::collections::HashMap<String, AccountDetails>,
+    // Giving strategies to compare against the tithes in `generators`
+    #[serde(default)]
+    tithe_comparison: Option<TitheComparison>,
+    // Assumed annual percentage rate of each inflation index, e.g. `cpi: 3.0`
+    #[serde(default)]
+    inflation_indices: std::collections::HashMap<String, Decimal>,
+    // Named rates that interest rates can track, each a schedule of the
+    // percentage from each date, e.g. `boe_base: [{from: 2025-01-01, rate: 4.75}]`
+    #[serde(default)]
+    reference_rates: std::collections::HashMap<String, Vec<RatePeriod>>,
+    // Annual percentage that expense and salary amounts rise by, unless a generator says otherwise
+    #[serde(default)]
+    inflation_rate: Decimal,
+    // Overdraft terms for accounts allowed to go negative, keyed by account name
+    #[serde(default)]
+    overdrafts: std::collections::HashMap<String, overdraft::Overdraft>,
+    // Balances to warn about, e.g. `- {account: main, below: 500}`
+    #[serde(default)]
+    warn_if: Vec<alerts::Rule>,
+    // Balances to save up to by a date, e.g. `- {account: house_deposit, target: 40000, by: 2028-06-01}`
+    #[serde(default)]
+    goals: Vec<goals::Goal>,
+    // Balances the forecast should end a day with, to test it against known
+    // figures, e.g. `- {date: 2026-01-01, account: main, equals: 12500, tolerance: 50}`
+    #[serde(default)]
+    checks: Vec<checks::Check>,
+    // What each account is, keyed by account name: asset, liability, income or expense.
+    // Generators are checked against these, and they group accounts in reports.
+    #[serde(default)]
+    account_types: std::collections::HashMap<String, ledger::AccountType>,
+    // Accounts to chart, each on its own and all together; every asset and liability if not given
+    #[serde(default)]
+    plot_accounts: Option<Vec<String>>,
+    // Date the forecast is run as of, which `--as-of` overrides; it is also the start date if none is given
+    #[serde(default)]
+    as_of: Option<chrono::NaiveDate>,
+    // Variations on this config to forecast alongside it and compare
+    #[serde(default)]
+    scenarios: Vec<scenario::Scenario>,
+    // Named numbers, or expressions of those before them, that any amount can
+    // be written in terms of, e.g. `net_salary: 3200` and `amount: "net_salary * 0.1"`
+    #[serde(default)]
+    variables: std::collections::HashMap<String, Decimal>,
+    // Last day of the forecast; otherwise it runs for `years` and `months` from the
+    // start, or for `DEFAULT_HORIZON_DAYS` if neither is given
+    #[serde(default)]
+    end_date: Option<chrono::NaiveDate>,
+    #[serde(default)]
+    years: Option<u32>,
+    #[serde(default)]
+    months: Option<u32>,
+    // Liability to forecast until it's paid off, e.g. `mortgage`, stopping at the
+    // horizon above, or at `MAX_HORIZON_DAYS` if none is given
+    #[serde(default)]
+    until_paid_off: Option<String>,
+    // Bank statements whose rows replace the forecast up to the last day they
+    // cover, so it carries on from the real balances then
+    #[serde(default)]
+    actuals: Vec<actuals::Statement>,
+    // What named generators actually paid on particular dates, used instead of
+    // their usual amounts, e.g. `- {generator: energy_bill, date: 2025-02-01, amount: 243.17}`
+    #[serde(default)]
+    overrides: Vec<actuals::Override>,
+    // Currency the reports total everything in, and that accounts are held in
+    // unless their `account_details` give another
+    #[serde(default = "default_base_currency")]
+    base_currency: String,
+    // What a unit of each other currency is worth in the base currency from
+    // each date, e.g. `- {currency: EUR, from: 2025-01-01, rate: 0.85}`
+    #[serde(default)]
+    exchange_rates: Vec<currency::ExchangeRate>,
+    // How generators round the amounts they work out, such as interest: half_up,
+    // half_even or truncate, to `places` decimal places, overridable per currency and account
+    #[serde(default)]
+    rounding: rounding::Rules,
+    // Days whose balances are kept for the reports: daily, or month_end to save
+    // memory over long horizons and many runs, at the cost of anything looking
+    // for a day's balance, such as the lowest, only seeing month ends
+    #[serde(default)]
+    history: Sampling,
+    // Engine state to start from instead of a fresh one, set when resuming from a snapshot
+    #[serde(skip)]
+    opening_state: Option<State>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            generators: Vec::new(),
+            accounts: std::collections::HashMap::new(),
+            currency_symbol: default_currency_symbol(),
+            start_date: default_start_date(),
+            seed: None,
+            holiday_calendar: HolidayCalendar::default(),
+            holidays: Vec::new(),
+            roles: std::collections::HashMap::new(),
+            tax: tax::TaxSettings::default(),
+            children: Vec::new(),
+            account_details: std::collections::HashMap::new(),
+            tithe_comparison: None,
+            inflation_indices: std::collections::HashMap::new(),
+            reference_rates: std::collections::HashMap::new(),
+            inflation_rate: Decimal::ZERO,
+            overdrafts: std::collections::HashMap::new(),
+            warn_if: Vec::new(),
+            goals: Vec::new(),
+            checks: Vec::new(),
+            account_types: std::collections::HashMap::new(),
+            plot_accounts: None,
+            as_of: None,
+            scenarios: Vec::new(),
+            variables: std::collections::HashMap::new(),
+            end_date: None,
+            years: None,
+            months: None,
+            until_paid_off: None,
+            actuals: Vec::new(),
+            overrides: Vec::new(),
+            base_currency: default_base_currency(),
+            exchange_rates: Vec::new(),
+            rounding: rounding::Rules::default(),
+            history: Sampling::default(),
+            opening_state: None,
+        }
+    }
+}
+
+impl Config {
+    /// The account playing `role`, which is the role's own name unless mapped in `roles`
+    fn role(&self, role: &str) -> String {
+        self.roles.get(role).cloned().unwrap_or_else(|| role.to_string())
+    }
+
+    /// The currency each account is held in and the rates between them
+    fn currencies(&self) -> currency::Currencies<'_> {
+        let held = self.account_details.iter().filter_map(|(account, details)| Some((account.as_str(), details.currency.as_deref()?)));
+        currency::Currencies::new(&self.base_currency, held, &self.exchange_rates, &self.rounding)
+    }
+
+    fn backing(&self) -> backing::Backing<'_> {
+        let backers = self.account_details.iter().filter_map(|(account, details)| Some((account.as_str(), details.backed_by.as_deref()?)));
+        let floors = self.account_details.iter().filter_map(|(account, details)| Some((account.as_str(), details.floor?)));
+        backing::Backing::new(backers, floors)
+    }
+
+    /// Add the generators for each child's costs
+    fn expand_children(&mut self) {
+        let currencies = self.currencies();
+        let generators: Vec<GeneratorEntry> =
+            self.children.iter().flat_map(|child| children::generators(child, self.start_date, currencies.rounding(&child.from))).collect();
+        self.generators.extend(generators);
+    }
+
+    /// Replace role names in generators with the accounts they stand for
+    fn resolve_roles(&mut self) {
+        let roles = self.roles.clone();
+        let resolve = |account: &mut String| {
+            if let Some(resolved) = roles.get(account.as_str()) {
+                *account = resolved.clone();
+            }
+        };
+        for entry in &mut self.generators {
+            entry.generator.accounts_mut().into_iter().for_each(resolve);
+            entry.balance_account.iter_mut().for_each(resolve);
+        }
+        self.overdrafts = std::mem::take(&mut self.overdrafts)
+            .into_iter()
+            .map(|(mut account, mut overdraft)| {
+                resolve(&mut account);
+                resolve(&mut overdraft.interest_account);
+                resolve(&mut overdraft.fee_account);
+                (account, overdraft)
+            })
+            .collect();
+        for details in self.account_details.values_mut() {
+            details.backed_by.iter_mut().for_each(resolve);
+        }
+        for rule in &mut self.warn_if {
+            resolve(&mut rule.account);
+        }
+        for goal in &mut self.goals {
+            resolve(&mut goal.account);
+        }
+        for check in &mut self.checks {
+            resolve(&mut check.account);
+        }
+        if let Some(comparison) = &mut self.tithe_comparison {
+            resolve(&mut comparison.account);
+            for strategy in &mut comparison.strategies {
+                for entry in &mut strategy.generators {
+                    entry.generator.accounts_mut().into_iter().for_each(resolve);
+                    entry.balance_account.iter_mut().for_each(resolve);
+                }
+            }
+        }
+    }
+}
+
+/// A generator as written in the config: what it does, plus options that
+/// apply to every kind of generator
+#[derive(Debug, Deserialize, PartialEq, Clone)]
+struct GeneratorEntry {
+    #[serde(flatten)]
+    generator: Generator,
+    // Human readable label, e.g. the name of a subscription service
+    #[serde(default)]
+    name: Option<String>,
+    // What the generator is for, e.g. `Flights for the Lisbon trip`, shown with
+    // its postings, validation problems and alerts
+    #[serde(default)]
+    description: Option<String>,
+    // Where to move a payment that falls on a weekend or holiday
+    #[serde(default)]
+    adjust: Adjust,
+    // First and last days the generator runs on; it runs throughout the forecast if None
+    #[serde(default)]
+    start_date: Option<chrono::NaiveDate>,
+    #[serde(default)]
+    end_date: Option<chrono::NaiveDate>,
+    // Months the generator doesn't run in, e.g. `[2026-08, 2026-09]` for a payment
+    // holiday, or `[february, march]` for council tax that isn't collected then
+    #[serde(default)]
+    skip: Vec<schedule::SkippedMonth>,
+    // Annual inflation for an expense or salary amount instead of the config's `inflation_rate`
+    #[serde(default)]
+    inflation: Option<Decimal>,
+    // Inflation index from `inflation_indices` to follow instead, e.g. `cpi`
+    #[serde(default)]
+    index: Option<String>,
+    // Limits a transfer or expense must leave `balance_account` within, or it is skipped
+    #[serde(default)]
+    only_if_balance_above: Option<Decimal>,
+    #[serde(default)]
+    only_if_balance_below: Option<Decimal>,
+    // Account whose balance the limits apply to; the paying account if None
+    #[serde(default)]
+    balance_account: Option<String>,
+    // How sure the figures are, `high`, `medium` or `low`: tagged generators are listed
+    // in the assumption register, and low ones are varied more widely under Monte Carlo
+    #[serde(default)]
+    confidence: Option<monte_carlo::Confidence>,
+    // What to do when the `from` account can't fund a payment: skip, partial,
+    // overdraw or error. Mortgages pay what they can unless told otherwise;
+    // everything else overdraws.
+    #[serde(default)]
+    on_insufficient_funds: Option<backing::InsufficientFunds>,
+}
+
+impl GeneratorEntry {
+    /// The generator's name, or its type and place in the config if it has none
+    fn label(&self, index: usize) -> String {
+        self.name.clone().unwrap_or_else(|| format!("{} (generator {})", self.generator.kind(), index + 1))
+    }
+
+    /// The generator's label, followed by its description if it has one
+    fn title(&self, index: usize) -> String {
+        match &self.description {
+            Some(description) => format!("{} \"{description}\"", self.label(index)),
+            None => self.label(index),
+        }
+    }
+
+    fn active_on(&self, date: chrono::NaiveDate) -> bool {
+        self.start_date.is_none_or(|start| date >= start)
+            && self.end_date.is_none_or(|end| date <= end)
+            && !self.skip.iter().any(|month| month.contains(date))
+    }
+
+    /// Annual percentage the generator's amount rises by: its index's rate,
+    /// its own rate, or the config's, in that order
+    fn inflation(&self, config: &Config) -> Result<Decimal, String> {
+        match &self.index {
+            Some(index) => config.inflation_indices.get(index).copied().ok_or_else(|| format!("Inflation index '{index}' not found in inflation_indices")),
+            None => Ok(self.inflation.unwrap_or(config.inflation_rate)),
+        }
+    }
+
+    /// Whether paying `amount` from `from` to `to` would leave the balance
+    /// the generator watches within its limits
+    fn allows(&self, balances: &balances::Balances, from: &str, to: &str, amount: Decimal) -> bool {
+        let account = self.balance_account.as_deref().unwrap_or(from);
+        let balance = *balances.get(account).unwrap_or_else(|| panic!("Account '{account}' not found"));
+        let after = if account == from {
+            balance - amount
+        } else if account == to {
+            balance + amount
+        } else {
+            balance
+        };
+        self.only_if_balance_above.is_none_or(|above| after >= above) && self.only_if_balance_below.is_none_or(|below| after <= below)
+    }
+}
+
+impl From<Generator> for GeneratorEntry {
+    fn from(generator: Generator) -> Self {
+        GeneratorEntry {
+            generator,
+            name: None,
+            description: None,
+            adjust: Adjust::default(),
+            start_date: None,
+            end_date: None,
+            skip: Vec::new(),
+            inflation: None,
+            index: None,
+            only_if_balance_above: None,
+            only_if_balance_below: None,
+            balance_account: None,
+            confidence: None,
+            on_insufficient_funds: None,
+        }
+    }
 }
 
-#[derive(Debug, Deserialize, PartialEq)]
+#[derive(Debug, Deserialize, PartialEq, Clone)]
 #[serde(tag = "type", rename_all = "lowercase")]
 enum Generator {
     #[serde(rename = "mortgage")]
     Mortgage {
         deduction_amount: Decimal,
-        deduction_day: u32,
+        #[serde(alias = "deduction_day")]
+        schedule: Schedule,
         #[serde(default = "default_main")]
         from: String,
         #[serde(default = "default_mortgage")]
@@ -35,1156 +463,9376 @@ enum Generator {
     },
     #[serde(rename = "interest")]
     Interest {
+        // Annual percentage rate, used until the first `rate_schedule` period starts;
+        // the margin over `base` if it's given
+        #[serde(default, alias = "margin")]
         rate: Decimal,
-        day: u32,
+        // Dated rate changes, e.g. a fixed deal ending and reverting to a variable rate
+        #[serde(default)]
+        rate_schedule: Vec<RatePeriod>,
+        // Reference rate from `reference_rates` the rate tracks, e.g. `boe_base`
+        #[serde(default)]
+        base: Option<String>,
+        #[serde(alias = "day")]
+        schedule: Schedule,
         #[serde(default = "default_mortgage")]
         account: String,
         #[serde(default = "default_mortgage_income")]
         income_account: String,
-        // Month interest is paid annually, or if None then interest is paid monthly
+        // Month interest is paid annually, or if None then interest is paid on the schedule
         month: Option<Month>,
+        #[serde(default, alias = "compounding")]
+        method: InterestMethod,
+        // Standard deviation, in percentage points, of a change to the rate for a
+        // whole run, drawn when the config has a seed
+        #[serde(default)]
+        rate_volatility: Decimal,
+    },
+    // Interest paid on savings at tiered rates, e.g. 5% on the first £5,000 and 2% above
+    #[serde(rename = "savings_interest")]
+    SavingsInterest {
+        // Rates on each band of the balance, lowest first, e.g. `[{upto: 5000, rate: 5}, {rate: 2}]`
+        tiers: Vec<interest::Tier>,
+        #[serde(alias = "day")]
+        schedule: Schedule,
+        account: String,
+        #[serde(default = "default_savings_interest")]
+        income_account: String,
+        #[serde(default, alias = "compounding")]
+        method: InterestMethod,
+        // Other taxable income a year, which sets the Personal Savings Allowance the
+        // interest is compared with; the config's gross salaries if not given
+        #[serde(default)]
+        other_income: Option<Decimal>,
     },
     #[serde(rename = "salary")]
     Salary {
+        // Net pay per payment; ignored if `gross_annual` is given
+        #[serde(default)]
         amount: Decimal,
-        day: u32,
+        #[serde(alias = "day")]
+        schedule: Schedule,
         #[serde(default = "default_main")]
         to: String,
+        #[serde(default = "default_salary_income")]
+        income_account: String,
+        // Percentage pay rise applied each year in `increase_month`
+        #[serde(default)]
+        annual_increase_percent: Decimal,
+        #[serde(default = "default_increase_month")]
+        increase_month: Month,
+        // Dated changes to a new amount, e.g. a promotion
+        #[serde(default)]
+        raises: Vec<salary::Raise>,
+        // Gross annual salary to work out net pay from; raises are then gross annual amounts too
+        #[serde(default)]
+        gross_annual: Option<Decimal>,
+        #[serde(default)]
+        student_loans: Vec<tax::StudentLoanPlan>,
+        #[serde(default = "default_income_tax")]
+        tax_account: String,
+        #[serde(default = "default_national_insurance")]
+        ni_account: String,
+        #[serde(default = "default_student_loan")]
+        student_loan_account: String,
+        // Percentage of gross pay given to charity through payroll, before income tax
+        #[serde(default)]
+        payroll_giving_percent: Decimal,
+        #[serde(default = "default_charity")]
+        payroll_giving_account: String,
     },
     #[serde(rename = "transfer")]
     Transfer {
+        // Fixed amount to move; ignored if `percentage` is given
+        #[serde(default)]
         amount: Decimal,
-        day: u32,
+        #[serde(alias = "day")]
+        schedule: Schedule,
         #[serde(default = "default_main")]
         from: String,
         #[serde(default = "default_main")]
         to: String,
+        // Percentage to move instead of an amount, of `of`: `{balance: main}` or
+        // `{income: [salary]}`; last month's salary and rent if not given
+        #[serde(default)]
+        percentage: Option<Decimal>,
+        #[serde(default)]
+        of: Option<share::ShareOf>,
     },
     #[serde(rename = "tithe")]
     Tithe {
         percentage: Decimal,
-        day: u32,
+        #[serde(default)]
+        basis: TitheBasis,
+        #[serde(alias = "day")]
+        schedule: Schedule,
         #[serde(default = "default_main")]
         from: String,
+        // Receives whatever isn't allocated to `recipients`
         #[serde(default = "default_charity")]
         to: String,
+        // Named recipients sharing the giving, each with its own account
+        #[serde(default)]
+        recipients: Vec<giving::Recipient>,
+        // Income generators, by name or type, or income accounts to tithe on,
+        // e.g. `[salary, freelance]`, in place of `basis`
+        #[serde(default)]
+        of: Option<Vec<String>>,
+        // Whether the giving is made under Gift Aid, so the charities reclaim
+        // basic rate tax on it; the uplift is recorded but never leaves `from`
+        #[serde(default)]
+        gift_aid: bool,
+        // Tracks the 25% uplift the charities reclaim, so the giving can be grossed up
+        #[serde(default = "default_gift_aid")]
+        gift_aid_account: String,
+        // The tax reclaimed from HMRC that pays for the uplift
+        #[serde(default = "default_gift_aid_income")]
+        gift_aid_income_account: String,
+    },
+    #[serde(rename = "rent")]
+    Rent {
+        amount: Decimal,
+        #[serde(alias = "day")]
+        schedule: Schedule,
+        #[serde(default = "default_main")]
+        to: String,
+        #[serde(default = "default_rental_income")]
+        income_account: String,
+        // Chance (0 to 1) that a given payment is lost entirely because the property is empty
+        #[serde(default)]
+        void_probability: Decimal,
+        // Chance (0 to 1) that a payment which is received arrives late
+        #[serde(default)]
+        late_probability: Decimal,
+        // How many days late a late payment arrives
+        #[serde(default)]
+        late_days: DelayRange,
+        // Percentage the rent rises by each year
+        #[serde(default)]
+        growth_percent: Decimal,
+        // Whether to set property income tax aside from the rent received, into `tax_account`
+        #[serde(default)]
+        taxed: bool,
+        #[serde(default = "default_income_tax")]
+        tax_account: String,
+        // Other taxable income a year the rent is taxed on top of; the config's gross salaries if not given
+        #[serde(default)]
+        other_income: Option<Decimal>,
+    },
+    // Dividends from shares held outside a tax-free wrapper
+    #[serde(rename = "dividend")]
+    Dividend {
+        // Each payment, at the start of the forecast
+        amount: Decimal,
+        #[serde(alias = "day")]
+        schedule: Schedule,
+        // Percentage the payments grow by each year
+        #[serde(default)]
+        growth_percent: Decimal,
+        #[serde(default = "default_main")]
+        to: String,
+        #[serde(default = "default_dividend_income")]
+        income_account: String,
+        // Whether to set dividend tax aside from each payment, into `tax_account`
+        #[serde(default)]
+        taxed: bool,
+        #[serde(default = "default_income_tax")]
+        tax_account: String,
+        // Other taxable income a year the dividends are taxed on top of; the config's gross salaries if not given
+        #[serde(default)]
+        other_income: Option<Decimal>,
+    },
+    #[serde(rename = "expense")]
+    Expense {
+        amount: Decimal,
+        #[serde(alias = "day")]
+        schedule: Schedule,
+        // Spending category, paid into an automatically created `<category>_expenditure` account
+        category: String,
+        #[serde(default = "default_main")]
+        from: String,
+        // Whether this is a cancellable subscription; defaults to true in the `subscriptions` category
+        #[serde(default)]
+        subscription: Option<bool>,
+        // Annual rise in the amount, e.g. CPI+3.9% each April
+        #[serde(default)]
+        price_rise: Option<contract::PriceRise>,
+        // Standard deviation of each payment as a percentage of the amount, for
+        // irregular spending; payments vary only when the config has a seed
+        #[serde(default)]
+        variation: Decimal,
+    },
+    // A gas or electricity bill worked out from the tariff and how much is used each month
+    #[serde(rename = "energy")]
+    Energy {
+        // Charge per day, whatever is used
+        standing_charge: Decimal,
+        // Price per kWh
+        unit_rate: Decimal,
+        // kWh used in each month, January to December
+        usage: Vec<Decimal>,
+        // Dated changes to the standing charge or unit rate, e.g. each new price cap
+        #[serde(default)]
+        tariff_changes: Vec<energy::TariffChange>,
+        // When each month's bill is paid, for the month before
+        #[serde(alias = "day")]
+        schedule: Schedule,
+        #[serde(default = "default_energy_category")]
+        category: String,
+        #[serde(default = "default_main")]
+        from: String,
+    },
+    // A cost that comes at random, such as a car repair. Under Monte Carlo it
+    // happens by chance, costing between `min` and `max` each time; otherwise
+    // its expected cost is charged each time the schedule comes round
+    #[serde(rename = "random_expense")]
+    RandomExpense {
+        min: Decimal,
+        max: Decimal,
+        // How many times a year it's expected to happen, e.g. 1.5
+        times_per_year: Decimal,
+        #[serde(alias = "day")]
+        schedule: Schedule,
+        // Spending category, paid into an automatically created `<category>_expenditure` account
+        category: String,
+        #[serde(default = "default_main")]
+        from: String,
+    },
+    // A cost paid in stages, e.g. childcare going from nursery to free hours to
+    // wraparound care to nothing, each stage paid until the next one starts
+    #[serde(rename = "phased_expense")]
+    PhasedExpense {
+        phases: Vec<children::Phase>,
+        // Needed for stages that start at an age rather than on a date
+        #[serde(default)]
+        birth_date: Option<chrono::NaiveDate>,
+        #[serde(alias = "day")]
+        schedule: Schedule,
+        #[serde(default = "default_children_category")]
+        category: String,
+        #[serde(default = "default_main")]
+        from: String,
+    },
+    // A bill that varies predictably with the month, e.g. heating costing more in winter
+    #[serde(rename = "seasonal_expense")]
+    SeasonalExpense {
+        // Amount paid in each month, e.g. `{jan: 220, feb: 210, aug: 90}`; nothing is paid in a month left out
+        amounts: std::collections::HashMap<Month, Decimal>,
+        #[serde(alias = "day")]
+        schedule: Schedule,
+        // Spending category, paid into an automatically created `<category>_expenditure` account
+        category: String,
+        #[serde(default = "default_main")]
+        from: String,
+    },
+    #[serde(rename = "loan")]
+    Loan {
+        // Liability account holding the outstanding balance as a negative amount
+        account: String,
+        // Amount originally borrowed, which with the rate and term fixes the payment
+        principal: Decimal,
+        // Annual percentage rate
+        rate: Decimal,
+        term_months: u32,
+        #[serde(alias = "day")]
+        schedule: Schedule,
+        #[serde(default = "default_main")]
+        from: String,
+        #[serde(default = "default_loan_interest")]
+        interest_account: String,
+        // Fee added to the debt with each payment
+        #[serde(default)]
+        fee: Decimal,
+        #[serde(default = "default_debt_fees")]
+        fee_account: String,
+        // Order payments are applied to fees, interest and principal
+        #[serde(default = "debt::default_allocation")]
+        allocation: Vec<Component>,
+    },
+    #[serde(rename = "credit_card")]
+    CreditCard {
+        // Card account, negative while money is owed; purchases are other generators paying from it
+        account: String,
+        // When statements are cut
+        #[serde(alias = "statement_day")]
+        schedule: Schedule,
+        // Days after the statement that the payment is taken
+        payment_delay_days: u32,
+        #[serde(default)]
+        repayment: CardRepayment,
+        // Minimum payment as a percentage of the statement balance...
+        #[serde(default = "default_minimum_percent")]
+        minimum_percent: Decimal,
+        // ...but never less than this
+        #[serde(default = "default_minimum_amount")]
+        minimum_amount: Decimal,
+        // Annual percentage rate charged on balances carried past the payment date
+        rate: Decimal,
+        #[serde(default = "default_main")]
+        from: String,
+        #[serde(default = "default_card_interest")]
+        interest_account: String,
+        // Fee added to the card with each statement
+        #[serde(default)]
+        fee: Decimal,
+        #[serde(default = "default_debt_fees")]
+        fee_account: String,
+        // Order payments are applied to fees, interest and principal
+        #[serde(default = "debt::default_allocation")]
+        allocation: Vec<Component>,
+    },
+    // Extra payments off a mortgage, within the lender's annual overpayment limit
+    #[serde(rename = "overpayment")]
+    Overpayment {
+        // Fixed amount to overpay each time
+        #[serde(default)]
+        amount: Option<Decimal>,
+        // Alternatively, overpay whatever is in the `from` account above this
+        #[serde(default)]
+        sweep_above: Option<Decimal>,
+        #[serde(alias = "day")]
+        schedule: Schedule,
+        #[serde(default = "default_main")]
+        from: String,
+        #[serde(default = "default_mortgage")]
+        to: String,
+        // Percentage of the balance at the start of each calendar year that may be overpaid
+        #[serde(default = "default_overpayment_cap_percent")]
+        annual_cap_percent: Decimal,
+        // Where anything over the limit goes instead; if None it stays in `from`
+        #[serde(default)]
+        overflow_to: Option<String>,
+    },
+    // Regular contributions into an account that grows at an expected annual return
+    #[serde(rename = "investment")]
+    Investment {
+        amount: Decimal,
+        #[serde(alias = "day")]
+        schedule: Schedule,
+        #[serde(default = "default_main")]
+        from: String,
+        // The investment account, which also receives its growth
+        to: String,
+        // Expected annual percentage return, compounded each time the generator runs
+        #[serde(default)]
+        annual_return: Decimal,
+        // Standard deviation of the return, drawn afresh each tax year when the config has a seed
+        #[serde(default)]
+        return_volatility: Decimal,
+        #[serde(default = "default_investment_growth")]
+        growth_account: String,
+        // Most that can be paid in each tax year, e.g. 20000 for an ISA
+        #[serde(default)]
+        annual_cap: Option<Decimal>,
+        // Tax-free allowance that caps contributions instead, `isa` or `pension`, taken
+        // from the tax rules for each year
+        #[serde(default)]
+        allowance: Option<tax::Allowance>,
+        // Where contributions over the cap go instead; if None they stay in `from`
+        #[serde(default)]
+        overflow_to: Option<String>,
+    },
+    // A percentage of spending in some expense categories paid back, e.g. card cashback
+    #[serde(rename = "cashback")]
+    Cashback {
+        percentage: Decimal,
+        // Expense categories the cashback is earned on
+        categories: Vec<String>,
+        // When the cashback earned since the last payment is paid
+        #[serde(alias = "day")]
+        schedule: Schedule,
+        #[serde(default = "default_main")]
+        to: String,
+        #[serde(default = "default_cashback_income")]
+        income_account: String,
+        // Most that can be earned in a calendar year
+        #[serde(default)]
+        annual_cap: Option<Decimal>,
+    },
+    // A save-as-you-earn share option contract: monthly savings, then shares
+    // bought at the option price and sold when it matures
+    #[serde(rename = "saye")]
+    Saye {
+        // Saved each month from net pay
+        amount: Decimal,
+        #[serde(alias = "day")]
+        schedule: Schedule,
+        // When the contract starts; it matures `term_months` later
+        start: chrono::NaiveDate,
+        #[serde(default = "default_saye_term_months")]
+        term_months: u32,
+        // Price per share the option lets the savings buy at
+        option_price: Decimal,
+        // Expected share price at maturity, used in the forecast
+        share_price: Decimal,
+        // Other share prices to report the outcome at
+        #[serde(default)]
+        scenarios: Vec<share_scheme::ShareScenario>,
+        #[serde(default = "default_main")]
+        from: String,
+        // Account holding the savings until maturity
+        account: String,
+        // Where the savings and any gain are paid at maturity
+        #[serde(default = "default_main")]
+        to: String,
+        #[serde(default = "default_share_scheme_gains")]
+        gain_account: String,
+        #[serde(default = "default_capital_gains_tax")]
+        tax_account: String,
+    },
+    // A regular saver: a fixed rate account taking up to a set amount a month,
+    // paying its interest at maturity, when everything in it is paid out
+    #[serde(rename = "regular_saver")]
+    RegularSaver {
+        // Most the account takes in a month
+        monthly_cap: Decimal,
+        // Paid in each month, up to the cap; the cap if not given
+        #[serde(default)]
+        amount: Option<Decimal>,
+        // Fixed annual rate, earned on each day's balance
+        rate: Decimal,
+        #[serde(alias = "day")]
+        schedule: Schedule,
+        // When the account opens; it matures `term_months` later. Interest is
+        // only counted from the forecast's start for an account already open.
+        start: chrono::NaiveDate,
+        #[serde(default = "default_regular_saver_term_months")]
+        term_months: u32,
+        #[serde(default = "default_main")]
+        from: String,
+        // The saver's own account, created if it isn't configured
+        account: String,
+        // Where the savings and interest are paid at maturity
+        #[serde(default = "default_main")]
+        to: String,
+        #[serde(default = "default_savings_interest")]
+        income_account: String,
+    },
+    // A holding such as crypto whose value swings day to day, bought on a schedule
+    #[serde(rename = "volatile_asset")]
+    VolatileAsset {
+        // The holding's account, valued at what it would sell for
+        account: String,
+        // Bought each time the schedule fires
+        #[serde(default)]
+        amount: Decimal,
+        #[serde(alias = "day")]
+        schedule: Schedule,
+        #[serde(default = "default_main")]
+        from: String,
+        // Expected annual percentage return and its standard deviation; returns
+        // are sampled when the config has a seed, otherwise the mean is used
+        #[serde(default)]
+        mean_return: Decimal,
+        #[serde(default)]
+        volatility: Decimal,
+        // Sudden falls in value to stress test against
+        #[serde(default)]
+        drawdowns: Vec<volatile::Drawdown>,
+        #[serde(default = "default_asset_revaluation")]
+        revaluation_account: String,
+    },
+    // Keeps an account near a target by sweeping the excess to savings and
+    // drawing back from savings when it runs low
+    #[serde(rename = "sweep")]
+    Sweep {
+        #[serde(alias = "day")]
+        schedule: Schedule,
+        #[serde(default = "default_main")]
+        account: String,
+        savings: String,
+        // Anything above this is moved to savings
+        target: Decimal,
+        // If the account is below this, it is topped back up to it from whatever savings hold
+        #[serde(default)]
+        floor: Option<Decimal>,
+    },
+    // A budget envelope: an account filled with an allocation on schedule, for
+    // the spending of a category to be paid from by giving it as their `from`
+    #[serde(rename = "envelope")]
+    Envelope {
+        amount: Decimal,
+        #[serde(alias = "day")]
+        schedule: Schedule,
+        #[serde(default = "default_main")]
+        from: String,
+        // The envelope's account, created if it isn't configured
+        to: String,
+        // Whether what's left carries over to the next allocation (`rollover`)
+        // or is settled with `from` first (`reset`)
+        #[serde(default)]
+        leftover: envelope::Leftover,
+    },
+    // A switch to a new mortgage deal on a given date
+    #[serde(rename = "remortgage")]
+    Remortgage {
+        date: chrono::NaiveDate,
+        #[serde(default = "default_mortgage")]
+        account: String,
+        // New annual rate for interest charged on the account from this date
+        #[serde(default)]
+        rate: Option<Decimal>,
+        // New amount for mortgage payments into the account from this date
+        #[serde(default)]
+        payment: Option<Decimal>,
+        // Arrangement fee added to the balance on the day
+        #[serde(default)]
+        fee: Decimal,
+        #[serde(default = "default_debt_fees")]
+        fee_account: String,
+    },
+    // Council tax: a bill for each year from April, collected in monthly
+    // instalments from April, usually ten so February and March are free
+    #[serde(rename = "council_tax")]
+    CouncilTax {
+        // The bill for the council tax year the forecast starts in
+        annual_amount: Decimal,
+        // Day of the month each instalment is collected
+        day: DayOfMonth,
+        #[serde(default = "default_council_tax_instalments")]
+        instalments: u32,
+        // Percentage the bill rises by each April
+        #[serde(default)]
+        annual_increase_percent: Decimal,
+        #[serde(default = "default_main")]
+        from: String,
+        #[serde(default = "default_council_tax_expenditure")]
+        to: String,
+    },
+    #[serde(rename = "annual_events")]
+    AnnualEvents {
+        events: Vec<AnnualEvent>,
+        // Day of the month each event is charged, unless the event gives its own
+        #[serde(default = "default_event_day")]
+        day: DayOfMonth,
+        #[serde(default = "default_main")]
+        from: String,
+        #[serde(default = "default_events_expenditure")]
+        to: String,
     },
 }
 
-fn default_currency_symbol() -> String {
-    "£".to_string()
+impl Generator {
+    /// The schedule of a generator that does nothing except when it comes due;
+    /// None for those that also act in between, such as accruing interest
+    fn due_schedule(&self) -> Option<&Schedule> {
+        match self {
+            Generator::Mortgage { schedule, .. }
+            | Generator::Salary { schedule, .. }
+            | Generator::Transfer { schedule, .. }
+            | Generator::Tithe { schedule, .. }
+            | Generator::Rent { schedule, .. }
+            | Generator::Dividend { schedule, .. }
+            | Generator::Expense { schedule, .. }
+            | Generator::Energy { schedule, .. }
+            | Generator::SeasonalExpense { schedule, .. }
+            | Generator::RandomExpense { schedule, .. }
+            | Generator::PhasedExpense { schedule, .. }
+            | Generator::Loan { schedule, .. }
+            | Generator::CreditCard { schedule, .. }
+            | Generator::Overpayment { schedule, .. }
+            | Generator::Investment { schedule, .. }
+            | Generator::Sweep { schedule, .. }
+            | Generator::Envelope { schedule, .. } => Some(schedule),
+            Generator::Interest { .. }
+            | Generator::SavingsInterest { .. }
+            | Generator::Cashback { .. }
+            | Generator::Saye { .. }
+            | Generator::RegularSaver { .. }
+            | Generator::VolatileAsset { .. }
+            | Generator::Remortgage { .. }
+            | Generator::CouncilTax { .. }
+            | Generator::AnnualEvents { .. } => None,
+        }
+    }
+
+    /// The schedule the generator runs on, if it has one
+    fn schedule(&self) -> Option<&Schedule> {
+        match self {
+            Generator::Interest { schedule, .. }
+            | Generator::SavingsInterest { schedule, .. }
+            | Generator::Cashback { schedule, .. }
+            | Generator::Saye { schedule, .. }
+            | Generator::RegularSaver { schedule, .. }
+            | Generator::VolatileAsset { schedule, .. } => Some(schedule),
+            Generator::Remortgage { .. } | Generator::CouncilTax { .. } | Generator::AnnualEvents { .. } => None,
+            _ => self.due_schedule(),
+        }
+    }
+
+    /// Each percentage or probability the generator is given, with the field
+    /// naming it; none of them make sense below zero
+    fn percentages(&self) -> Vec<(&'static str, Decimal)> {
+        match self {
+            Generator::Salary { annual_increase_percent, payroll_giving_percent, .. } => {
+                vec![("annual_increase_percent", *annual_increase_percent), ("payroll_giving_percent", *payroll_giving_percent)]
+            }
+            Generator::Tithe { percentage, recipients, .. } => [("percentage", *percentage)]
+                .into_iter()
+                .chain(recipients.iter().filter_map(|recipient| recipient.percentage.map(|percentage| ("percentage", percentage))))
+                .collect(),
+            Generator::Rent { void_probability, late_probability, .. } => {
+                vec![("void_probability", *void_probability), ("late_probability", *late_probability)]
+            }
+            Generator::Transfer { percentage, .. } => percentage.iter().map(|percentage| ("percentage", *percentage)).collect(),
+            Generator::Expense { variation, .. } => vec![("variation", *variation)],
+            Generator::RandomExpense { times_per_year, .. } => vec![("times_per_year", *times_per_year)],
+            Generator::CreditCard { minimum_percent, .. } => vec![("minimum_percent", *minimum_percent)],
+            Generator::Overpayment { annual_cap_percent, .. } => vec![("annual_cap_percent", *annual_cap_percent)],
+            Generator::Cashback { percentage, .. } => vec![("percentage", *percentage)],
+            Generator::CouncilTax { annual_increase_percent, .. } => vec![("annual_increase_percent", *annual_increase_percent)],
+            // A margin over a reference rate can be below zero
+            Generator::SavingsInterest { tiers, .. } => tiers.iter().filter(|tier| tier.base.is_none()).map(|tier| ("tiers", tier.rate)).collect(),
+            Generator::RegularSaver { rate, .. } => vec![("rate", *rate)],
+            _ => Vec::new(),
+        }
+    }
+
+    /// Fields of `wiring` naming accounts the generator never posts to as
+    /// configured, such as the tax accounts of a salary given as net pay
+    fn unused_accounts(&self) -> &'static [&'static str] {
+        match self {
+            Generator::Salary { gross_annual: None, .. } => {
+                &["tax_account", "ni_account", "student_loan_account", "payroll_giving_account"]
+            }
+            Generator::Loan { fee, .. } | Generator::CreditCard { fee, .. } | Generator::Remortgage { fee, .. } if fee.is_zero() => {
+                &["fee_account"]
+            }
+            Generator::Rent { taxed: false, .. } | Generator::Dividend { taxed: false, .. } => &["tax_account"],
+            Generator::Tithe { gift_aid: false, .. } => &["gift_aid_account", "gift_aid_income_account"],
+            _ => &[],
+        }
+    }
+
+    /// What the generator does when it can't fund a payment, unless the config says
+    fn on_insufficient_funds(&self) -> backing::InsufficientFunds {
+        match self {
+            Generator::Mortgage { .. } | Generator::Loan { .. } | Generator::Overpayment { .. } => backing::InsufficientFunds::Partial,
+            _ => backing::InsufficientFunds::Overdraw,
+        }
+    }
+
+    /// Each account the generator uses, with the field naming it and the
+    /// types of account that make sense there
+    fn wiring(&self) -> Vec<(&String, &'static str, &'static [ledger::AccountType])> {
+        use ledger::AccountType::{Assets, Expenses, Income, Liabilities};
+        const HELD: &[ledger::AccountType] = &[Assets, Liabilities];
+        const ASSET: &[ledger::AccountType] = &[Assets];
+        const LIABILITY: &[ledger::AccountType] = &[Liabilities];
+        const INCOME: &[ledger::AccountType] = &[Income];
+        const EXPENSE: &[ledger::AccountType] = &[Expenses];
+        match self {
+            Generator::Mortgage { from, to, .. } => vec![(from, "from", HELD), (to, "to", LIABILITY)],
+            // Interest on a debt is charged from the income account, so it may be tracking either
+            Generator::Interest { account, income_account, .. } => {
+                vec![(account, "account", HELD), (income_account, "income_account", &[Income, Expenses])]
+            }
+            Generator::Salary { to, income_account, tax_account, ni_account, student_loan_account, payroll_giving_account, .. } => vec![
+                (to, "to", HELD),
+                (income_account, "income_account", INCOME),
+                (tax_account, "tax_account", EXPENSE),
+                (ni_account, "ni_account", EXPENSE),
+                (student_loan_account, "student_loan_account", EXPENSE),
+                (payroll_giving_account, "payroll_giving_account", EXPENSE),
+            ],
+            Generator::Transfer { from, to, of, .. } => {
+                let mut wiring = vec![(from, "from", HELD), (to, "to", HELD)];
+                if let Some(share::ShareOf::Balance { balance: account }) = of {
+                    wiring.push((account, "of", HELD));
+                }
+                wiring
+            }
+            Generator::Tithe { from, to, recipients, gift_aid_account, gift_aid_income_account, .. } => [
+                (from, "from", HELD),
+                (to, "to", EXPENSE),
+                (gift_aid_account, "gift_aid_account", EXPENSE),
+                (gift_aid_income_account, "gift_aid_income_account", INCOME),
+            ]
+            .into_iter()
+            .chain(recipients.iter().map(|recipient| (&recipient.account, "recipient account", EXPENSE)))
+            .collect(),
+            Generator::Rent { to, income_account, tax_account, .. } | Generator::Dividend { to, income_account, tax_account, .. } => {
+                vec![(to, "to", HELD), (income_account, "income_account", INCOME), (tax_account, "tax_account", EXPENSE)]
+            }
+            Generator::Cashback { to, income_account, .. } => vec![(to, "to", HELD), (income_account, "income_account", INCOME)],
+            Generator::SavingsInterest { account, income_account, .. } => {
+                vec![(account, "account", ASSET), (income_account, "income_account", INCOME)]
+            }
+            Generator::Expense { from, .. }
+            | Generator::Energy { from, .. }
+            | Generator::SeasonalExpense { from, .. }
+            | Generator::RandomExpense { from, .. }
+            | Generator::PhasedExpense { from, .. } => vec![(from, "from", HELD)],
+            Generator::Loan { account, from, interest_account, fee_account, .. }
+            | Generator::CreditCard { account, from, interest_account, fee_account, .. } => vec![
+                (account, "account", LIABILITY),
+                (from, "from", HELD),
+                (interest_account, "interest_account", EXPENSE),
+                (fee_account, "fee_account", EXPENSE),
+            ],
+            Generator::Overpayment { from, to, overflow_to, .. } => [(from, "from", HELD), (to, "to", LIABILITY)]
+                .into_iter()
+                .chain(overflow_to.iter().map(|account| (account, "overflow_to", HELD)))
+                .collect(),
+            Generator::Investment { from, to, growth_account, overflow_to, .. } => {
+                [(from, "from", HELD), (to, "to", ASSET), (growth_account, "growth_account", INCOME)]
+                    .into_iter()
+                    .chain(overflow_to.iter().map(|account| (account, "overflow_to", HELD)))
+                    .collect()
+            }
+            Generator::Saye { from, account, to, gain_account, tax_account, .. } => vec![
+                (from, "from", HELD),
+                (account, "account", ASSET),
+                (to, "to", HELD),
+                (gain_account, "gain_account", INCOME),
+                (tax_account, "tax_account", EXPENSE),
+            ],
+            Generator::RegularSaver { from, account, to, income_account, .. } => vec![
+                (from, "from", HELD),
+                (account, "account", ASSET),
+                (to, "to", HELD),
+                (income_account, "income_account", INCOME),
+            ],
+            Generator::VolatileAsset { account, from, revaluation_account, .. } => {
+                vec![(account, "account", ASSET), (from, "from", HELD), (revaluation_account, "revaluation_account", INCOME)]
+            }
+            Generator::Sweep { account, savings, .. } => vec![(account, "account", HELD), (savings, "savings", HELD)],
+            Generator::Envelope { from, to, .. } => vec![(from, "from", HELD), (to, "to", ASSET)],
+            Generator::Remortgage { account, fee_account, .. } => vec![(account, "account", LIABILITY), (fee_account, "fee_account", EXPENSE)],
+            Generator::CouncilTax { from, to, .. } | Generator::AnnualEvents { from, to, .. } => {
+                vec![(from, "from", HELD), (to, "to", EXPENSE)]
+            }
+        }
+    }
+
+    /// The generator's type as written in the config
+    fn kind(&self) -> &'static str {
+        match self {
+            Generator::Mortgage { .. } => "mortgage",
+            Generator::Interest { .. } => "interest",
+            Generator::SavingsInterest { .. } => "savings_interest",
+            Generator::Salary { .. } => "salary",
+            Generator::Transfer { .. } => "transfer",
+            Generator::Tithe { .. } => "tithe",
+            Generator::Rent { .. } => "rent",
+            Generator::Dividend { .. } => "dividend",
+            Generator::Expense { .. } => "expense",
+            Generator::Energy { .. } => "energy",
+            Generator::SeasonalExpense { .. } => "seasonal_expense",
+            Generator::RandomExpense { .. } => "random_expense",
+            Generator::PhasedExpense { .. } => "phased_expense",
+            Generator::Loan { .. } => "loan",
+            Generator::CreditCard { .. } => "credit_card",
+            Generator::Overpayment { .. } => "overpayment",
+            Generator::Investment { .. } => "investment",
+            Generator::Cashback { .. } => "cashback",
+            Generator::Saye { .. } => "saye",
+            Generator::RegularSaver { .. } => "regular_saver",
+            Generator::VolatileAsset { .. } => "volatile_asset",
+            Generator::Sweep { .. } => "sweep",
+            Generator::Envelope { .. } => "envelope",
+            Generator::Remortgage { .. } => "remortgage",
+            Generator::CouncilTax { .. } => "council_tax",
+            Generator::AnnualEvents { .. } => "annual_events",
+        }
+    }
+
+    /// Every account the generator names, apart from those derived from an expense category
+    fn accounts_mut(&mut self) -> Vec<&mut String> {
+        match self {
+            Generator::Mortgage { from, to, .. } | Generator::CouncilTax { from, to, .. } | Generator::AnnualEvents { from, to, .. } => {
+                vec![from, to]
+            }
+            Generator::Transfer { from, to, of, .. } => {
+                let mut accounts = vec![from, to];
+                if let Some(share::ShareOf::Balance { balance: account }) = of {
+                    accounts.push(account);
+                }
+                accounts
+            }
+            Generator::Tithe { from, to, recipients, gift_aid_account, gift_aid_income_account, .. } => {
+                let mut accounts = vec![from, to, gift_aid_account, gift_aid_income_account];
+                accounts.extend(recipients.iter_mut().map(|recipient| &mut recipient.account));
+                accounts
+            }
+            Generator::Interest { account, income_account, .. } | Generator::SavingsInterest { account, income_account, .. } => {
+                vec![account, income_account]
+            }
+            Generator::Salary {
+                to, income_account, tax_account, ni_account, student_loan_account, payroll_giving_account, ..
+            } => {
+                vec![to, income_account, tax_account, ni_account, student_loan_account, payroll_giving_account]
+            }
+            Generator::Rent { to, income_account, tax_account, .. } | Generator::Dividend { to, income_account, tax_account, .. } => {
+                vec![to, income_account, tax_account]
+            }
+            Generator::Cashback { to, income_account, .. } => vec![to, income_account],
+            Generator::Expense { from, .. }
+            | Generator::Energy { from, .. }
+            | Generator::SeasonalExpense { from, .. }
+            | Generator::RandomExpense { from, .. }
+            | Generator::PhasedExpense { from, .. } => vec![from],
+            Generator::Loan { account, from, interest_account, fee_account, .. }
+            | Generator::CreditCard { account, from, interest_account, fee_account, .. } => {
+                vec![account, from, interest_account, fee_account]
+            }
+            Generator::Remortgage { account, fee_account, .. } => vec![account, fee_account],
+            Generator::Overpayment { from, to, overflow_to, .. } => {
+                let mut accounts = vec![from, to];
+                accounts.extend(overflow_to.as_mut());
+                accounts
+            }
+            Generator::Sweep { account, savings, .. } => vec![account, savings],
+            Generator::Envelope { from, to, .. } => vec![from, to],
+            Generator::VolatileAsset { account, from, revaluation_account, .. } => vec![account, from, revaluation_account],
+            Generator::RegularSaver { from, account, to, income_account, .. } => vec![from, account, to, income_account],
+            Generator::Saye { from, account, to, gain_account, tax_account, .. } => {
+                vec![from, account, to, gain_account, tax_account]
+            }
+            Generator::Investment { from, to, growth_account, overflow_to, .. } => {
+                let mut accounts = vec![from, to, growth_account];
+                accounts.extend(overflow_to.as_mut());
+                accounts
+            }
+        }
+    }
 }
 
-fn default_start_date() -> chrono::NaiveDate {
-    chrono::NaiveDate::from_ymd_opt(2025, 1, 1).unwrap()
+/// A lump of spending that recurs once a year, such as a summer holiday or Christmas
+#[derive(Debug, Deserialize, PartialEq, Clone)]
+struct AnnualEvent {
+    name: String,
+    month: Month,
+    amount: Decimal,
+    day: Option<DayOfMonth>,
 }
 
-fn default_main() -> String {
-    MAIN_ACCOUNT.to_string()
+/// How much of each credit card statement is paid off
+#[derive(Debug, Clone, Copy, PartialEq, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum CardRepayment {
+    #[default]
+    Full,
+    Minimum,
 }
 
-fn default_mortgage() -> String {
-    MORTGAGE_ACCOUNT.to_string()
+/// A uniformly distributed delay in days, inclusive at both ends
+#[derive(Debug, Deserialize, PartialEq, Clone, Default)]
+struct DelayRange {
+    min: u32,
+    max: u32,
 }
 
-fn default_mortgage_income() -> String {
-    MORTGAGE_INCOME.to_string()
+impl DelayRange {
+    fn expected(&self) -> u32 {
+        (self.min + self.max).div_ceil(2)
+    }
+
+    fn sample(&self, rng: &mut StdRng) -> u32 {
+        rng.gen_range(self.min..=self.max.max(self.min))
+    }
 }
 
-fn default_charity() -> String {
-    CHARITY_EXPENDITURE.to_string()
+/// Notes and references that say where an account actually lives, so the
+/// reports double as an inventory of accounts
+#[derive(Debug, Deserialize, PartialEq, Clone, Default)]
+struct AccountDetails {
+    // Bank or provider
+    institution: Option<String>,
+    sort_code: Option<String>,
+    // Last four digits of the account or card number
+    last4: Option<String>,
+    url: Option<String>,
+    notes: Option<String>,
+    // Currency the account is held in, e.g. EUR, if not the base currency
+    currency: Option<String>,
+    // Account that makes good any payment taking this one below its floor, e.g.
+    // `savings` for `main`, and which may be backed by another in turn
+    #[serde(default)]
+    backed_by: Option<String>,
+    // Lowest the balance is kept at when it's backed, and the least it keeps back
+    // when backing another; zero if not given
+    #[serde(default)]
+    floor: Option<Decimal>,
+    // Whether a liability may be paid into credit, e.g. a card refunded after
+    // it's cleared; otherwise `--check` treats that as a mistake
+    #[serde(default)]
+    allow_credit: bool,
 }
 
-fn main() {
-    // Load config from YAML
-    // read from actual.yaml if it exists, otherwise from config.yaml
-    let config_file = if fs::metadata("actual.yaml").is_ok() {
-        "actual.yaml"
-    } else {
-        "config.yaml"
-    };
-    let yaml = fs::read_to_string(config_file).expect("Failed to read config file");
-    let config: Config = match serde_yaml::from_str(&yaml) {
-        Ok(cfg) => cfg,
-        Err(e) => {
-            eprintln!("YAML parsing error: {e}");
-            std::process::exit(1);
-        }
-    };
+impl AccountDetails {
+    /// Institution, sort code and masked account number, where known
+    fn reference(&self) -> String {
+        let last4 = self.last4.as_ref().map(|digits| format!("****{digits}"));
+        [self.institution.clone(), self.sort_code.clone(), last4]
+            .into_iter()
+            .flatten()
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+}
 
-    // Work out balances before running
-    let accounts_with_defaults = add_default_accounts(&config.accounts);
-    let balances = add_opening_balances(&accounts_with_defaults);
+/// End-of-day balances for every simulated day the config's `history` keeps
+type History = Vec<(chrono::NaiveDate, balances::Balances)>;
 
-    let history = run(&config, balances, 6000);
-    
-    // Print the history of balances
-    for (date, balances) in &history {
-        if date.day() == 1 {
-            println!("\nBalances on {date}:");
-            for (name, balance) in balances {
-                print_balance_named(name, *date, *balance, &config.currency_symbol); 
-            }
+/// Which days' balances a forecast's history keeps. The forecast is still
+/// worked out day by day either way.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum Sampling {
+    #[default]
+    Daily,
+    /// The last day of each month, and the last day of the forecast
+    MonthEnd,
+}
+
+impl Sampling {
+    fn keeps(self, date: chrono::NaiveDate) -> bool {
+        match self {
+            Sampling::Daily => true,
+            Sampling::MonthEnd => date.succ_opt().is_none_or(|next| next.day() == 1),
         }
     }
-    
-    // Create plots for mortgage balance over time
-    create_mortgage_plots(&history, &config.currency_symbol);
 }
 
-fn run(
-    config: &Config,
-    balances: std::collections::HashMap<String, Decimal>,
-    days_to_run: i32
-) -> Vec<(chrono::NaiveDate, std::collections::HashMap<String, Decimal>)> {
-    let mut balances = balances;
-    let mut date: chrono::NaiveDate = config.start_date;
-    let mut history = Vec::new();
-    let mut total_salary_since_last_tithe = Decimal::ZERO;
+/// Everything carried from one simulated day to the next apart from balances.
+/// It's saved in snapshots, all but the random number generator, which is
+/// seeded afresh from the config on resuming.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+struct State {
+    // Income each tithe generator has yet to give on, keyed by generator index
+    tithes: std::collections::HashMap<usize, Decimal>,
+    // Income counted by each percentage transfer this month and last, keyed by generator index
+    shares: std::collections::HashMap<usize, share::MonthlyIncome>,
+    // Postings scheduled by a generator to land on a later date, e.g. late rent
+    pending: Vec<PendingPosting>,
+    // Source of randomness for uncertain generators; None means use expected values
+    #[serde(skip)]
+    rng: Option<StdRng>,
+    // Interest generators' running balances between payments, keyed by generator index
+    interest: std::collections::HashMap<usize, InterestAccrual>,
+    // Unpaid charges and payment totals of loan and credit card generators, keyed by generator index
+    debts: std::collections::HashMap<usize, Debt>,
+    // What's left of each overpayment generator's annual limit, keyed by generator index
+    overpayments: std::collections::HashMap<usize, overpayment::Allowance>,
+    // What each investment generator has paid in this tax year, keyed by generator index
+    investments: std::collections::HashMap<usize, investment::Contributions>,
+    // Each investment generator's sampled return and the tax year it's for, keyed by generator index
+    investment_returns: std::collections::HashMap<usize, (i32, Decimal)>,
+    // Change to each interest generator's rate drawn for the run, keyed by generator index
+    rate_shifts: std::collections::HashMap<usize, Decimal>,
+    // Spending each cashback generator has paid out on, keyed by generator index
+    cashback: std::collections::HashMap<usize, cashback::Rewards>,
+    // Each overdrawn account's interest and days overdrawn this month, keyed by account
+    overdrafts: std::collections::HashMap<String, overdraft::Usage>,
+    // Account, day and balance each time an account went past a hard overdraft limit
+    overdraft_breaches: Vec<(String, chrono::NaiveDate, Decimal)>,
+    // Every posting made; only the day's during a day, so the state stays cheap to copy
+    #[serde(skip)]
+    journal: Vec<journal::Posting>,
+    // First day each generator's schedule came due, keyed by generator index
+    first_due: std::collections::HashMap<usize, chrono::NaiveDate>,
+    // What each regular saver has paid in this calendar month and the (year, month) it's for, keyed by generator index
+    saver_deposits: std::collections::HashMap<usize, ((i32, u32), Decimal)>,
+}
 
-    for _ in 0..days_to_run {
-        date = date + chrono::Duration::days(1);
-        let (new_balances, new_total_salary) = compute_next_day_balances(config, &balances, date, total_salary_since_last_tithe);
-        balances = new_balances;
-        total_salary_since_last_tithe = new_total_salary;
-        history.push((date, balances.clone()));
+impl State {
+    fn new(config: &Config) -> Self {
+        let rng = config.seed.map(StdRng::seed_from_u64);
+        match &config.opening_state {
+            Some(state) => State { rng, journal: Vec::new(), ..state.clone() },
+            None => State { rng, ..Default::default() },
+        }
     }
-    history
 }
 
-fn add_opening_balances(
-    balances: &std::collections::HashMap<String, Decimal>,
-) -> std::collections::HashMap<String, Decimal> {
-    let mut new_balances = balances.clone();
-    let opening_balance: Decimal = new_balances.values().sum();
-    new_balances.insert(OPENING_BALANCES.to_string(), -opening_balance);
-    new_balances
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct PendingPosting {
+    date: chrono::NaiveDate,
+    from: String,
+    to: String,
+    amount: Decimal,
+    // Type of generator whose income this is, which counts towards tithes when it lands
+    income: Option<String>,
+    // Generator that made the posting, and what it's for, for the journal
+    generator: String,
+    description: String,
+    #[serde(default)]
+    note: Option<String>,
 }
 
-fn add_default_accounts(
-    balances: &std::collections::HashMap<String, Decimal>,
-) -> std::collections::HashMap<String, Decimal> {
-    let mut new_balances = balances.clone();
-    if !new_balances.contains_key(SALARY_INCOME) {
+fn default_currency_symbol() -> String {
+    "£".to_string()
+}
+
+fn default_base_currency() -> String {
+    "GBP".to_string()
+}
+
+/// Start date for a config that doesn't give one and isn't run with an
+/// as-of date. Fixed rather than today, so runs are repeatable.
+fn default_start_date() -> chrono::NaiveDate {
+    chrono::NaiveDate::from_ymd_opt(2025, 1, 1).unwrap()
+}
+
+fn default_main() -> String {
+    MAIN_ACCOUNT.to_string()
+}
+
+fn default_mortgage() -> String {
+    MORTGAGE_ACCOUNT.to_string()
+}
+
+fn default_salary_income() -> String {
+    SALARY_INCOME.to_string()
+}
+
+fn default_income_tax() -> String {
+    INCOME_TAX.to_string()
+}
+
+fn default_national_insurance() -> String {
+    NATIONAL_INSURANCE.to_string()
+}
+
+fn default_student_loan() -> String {
+    STUDENT_LOAN.to_string()
+}
+
+fn default_increase_month() -> Month {
+    Month::January
+}
+
+fn default_mortgage_income() -> String {
+    MORTGAGE_INCOME.to_string()
+}
+
+fn default_savings_interest() -> String {
+    SAVINGS_INTEREST.to_string()
+}
+
+fn default_charity() -> String {
+    CHARITY_EXPENDITURE.to_string()
+}
+
+fn default_rental_income() -> String {
+    RENTAL_INCOME.to_string()
+}
+
+fn default_gift_aid() -> String {
+    GIFT_AID.to_string()
+}
+
+fn default_gift_aid_income() -> String {
+    GIFT_AID_INCOME.to_string()
+}
+
+fn default_dividend_income() -> String {
+    DIVIDEND_INCOME.to_string()
+}
+
+fn default_loan_interest() -> String {
+    LOAN_INTEREST.to_string()
+}
+
+fn default_card_interest() -> String {
+    CARD_INTEREST.to_string()
+}
+
+fn default_debt_fees() -> String {
+    DEBT_FEES.to_string()
+}
+
+fn default_minimum_percent() -> Decimal {
+    dec!(3)
+}
+
+fn default_minimum_amount() -> Decimal {
+    dec!(5)
+}
+
+fn default_overpayment_cap_percent() -> Decimal {
+    dec!(10)
+}
+
+fn default_events_expenditure() -> String {
+    EVENTS_EXPENDITURE.to_string()
+}
+
+fn default_energy_category() -> String {
+    ENERGY_CATEGORY.to_string()
+}
+
+fn default_children_category() -> String {
+    "children".to_string()
+}
+
+fn default_investment_growth() -> String {
+    INVESTMENT_GROWTH.to_string()
+}
+
+fn default_cashback_income() -> String {
+    CASHBACK_INCOME.to_string()
+}
+
+fn default_saye_term_months() -> u32 {
+    36
+}
+
+fn default_regular_saver_term_months() -> u32 {
+    12
+}
+
+fn default_share_scheme_gains() -> String {
+    SHARE_SCHEME_GAINS.to_string()
+}
+
+fn default_capital_gains_tax() -> String {
+    CAPITAL_GAINS_TAX.to_string()
+}
+
+fn default_asset_revaluation() -> String {
+    ASSET_REVALUATION.to_string()
+}
+
+fn default_overdraft_interest() -> String {
+    OVERDRAFT_INTEREST.to_string()
+}
+
+fn default_council_tax_expenditure() -> String {
+    COUNCIL_TAX_EXPENDITURE.to_string()
+}
+
+fn default_council_tax_instalments() -> u32 {
+    10
+}
+
+fn default_event_day() -> DayOfMonth {
+    DayOfMonth::Day(1)
+}
+
+#[derive(Parser)]
+#[command(name = "cash-forecast", version, about = "Forecast account balances from actual.yaml or config.yaml")]
+struct Cli {
+    #[command(subcommand)]
+    command: Option<Command>,
+    /// Run as if on this date (YYYY-MM-DD), so the same config gives identical output whenever it's run
+    #[arg(long = "as-of", global = true)]
+    as_of: Option<chrono::NaiveDate>,
+    /// Carry on from the balances and engine state in a snapshot file, as
+    /// written by the snapshot subcommand, instead of from the config's start
+    #[arg(long, global = true)]
+    resume: Option<String>,
+    /// List every posting the forecast makes, day by day, after the balances
+    #[arg(long = "show-transactions")]
+    show_transactions: bool,
+    /// How to write the forecast: as text and charts, or as a single JSON
+    /// document of every day's balances and every posting on stdout
+    #[arg(long, value_enum, default_value_t = OutputFormat::Text)]
+    format: OutputFormat,
+    /// Last day of the forecast (YYYY-MM-DD), instead of the config's horizon
+    #[arg(long = "end-date")]
+    end_date: Option<chrono::NaiveDate>,
+    /// Years to forecast for, instead of the config's horizon; may be combined with --months
+    #[arg(long)]
+    years: Option<u32>,
+    /// Months to forecast for, instead of the config's horizon; may be combined with --years
+    #[arg(long)]
+    months: Option<u32>,
+    /// Forecast until this liability is paid off, e.g. mortgage
+    #[arg(long = "until-paid-off")]
+    until_paid_off: Option<String>,
+    /// Say on stderr what the forecast is doing: -v for warnings, such as
+    /// payments skipped for lack of funds, and -vv for each generator as it fires
+    #[arg(short, long, action = clap::ArgAction::Count, global = true)]
+    verbose: u8,
+    /// Don't print the balances, only the reports and where they're saved
+    #[arg(short, long, global = true, conflicts_with = "verbose")]
+    quiet: bool,
+    /// Check every simulated day against the engine's invariants, and fail if
+    /// any is broken: balances summing to zero, no liability in credit unless
+    /// its account_details allow_credit, and no balance too large to hold precisely
+    #[arg(long, global = true)]
+    check: bool,
+}
+
+#[derive(Clone, Copy, PartialEq, ValueEnum)]
+enum OutputFormat {
+    Text,
+    Json,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Re-run the forecast without the named generators and show how the key figures change
+    Impact {
+        /// Name of a generator to leave out (not case sensitive); may be repeated
+        #[arg(long = "remove", required = true)]
+        remove: Vec<String>,
+    },
+    /// Find the smallest amount for a generator, or interest rate for an interest
+    /// generator, that gets an account to a balance by a date
+    Solve {
+        /// Name of the generator to adjust (not case sensitive)
+        #[arg(long)]
+        generator: String,
+        /// Account whose balance is the target
+        #[arg(long)]
+        account: String,
+        /// Balance to reach; leave out to solve for paying off a liability
+        #[arg(long, default_value_t = Decimal::ZERO)]
+        target: Decimal,
+        /// Date to reach it by (YYYY-MM-DD)
+        #[arg(long)]
+        by: chrono::NaiveDate,
+        /// Largest amount or rate to try
+        #[arg(long, default_value_t = dec!(100000))]
+        max: Decimal,
+    },
+    /// Re-run the forecast with a generator's amount, or an interest generator's
+    /// rate, stepped across a range and show how the key figures change
+    Sweep {
+        /// Name of the generator to adjust (not case sensitive)
+        #[arg(long)]
+        generator: String,
+        /// First value to try
+        #[arg(long)]
+        from: Decimal,
+        /// Last value to try
+        #[arg(long)]
+        to: Decimal,
+        /// Step between values
+        #[arg(long)]
+        step: Decimal,
+    },
+    /// Re-run the forecast for every combination of values of one or two
+    /// generators' amounts or rates, and show a chosen figure for each as a grid
+    Matrix {
+        /// A generator to adjust and the values to try, as NAME=FROM:TO:STEP, e.g.
+        /// `Overpay=0:500:100`; give once or twice
+        #[arg(long = "vary", required = true, value_parser = parse_vary)]
+        vary: Vec<Vary>,
+        #[arg(long, value_enum, default_value_t = MatrixMetric::LowestCash)]
+        metric: MatrixMetric,
+        /// Liability whose payoff date to show; the first if not given
+        #[arg(long)]
+        account: Option<String>,
+    },
+    /// Run the forecast many times with uncertain inputs sampled, and show the
+    /// 10th, 50th and 90th percentile of each account's balance
+    MonteCarlo {
+        /// How many runs, seeded one after another from the config's seed (or 0)
+        #[arg(long, default_value_t = 500)]
+        runs: u64,
+    },
+    /// Apply a plan of postings, such as an edited /tmp/journal.json, to the
+    /// opening balances instead of running the generators, and show how the key
+    /// figures differ from the config's forecast
+    Replay {
+        /// JSON file of postings, as saved to /tmp/journal.json
+        #[arg(long)]
+        plan: String,
+    },
+    /// Write the forecast's postings out for other tools
+    Export {
+        #[arg(long, value_enum, default_value_t = ExportFormat::Ledger)]
+        format: ExportFormat,
+        /// File to write to, or for parquet the directory to write history.parquet and journal.parquet
+        /// to; /tmp/forecast.ledger, /tmp/forecast.beancount, /tmp/forecast.xlsx or /tmp if not given
+        #[arg(long)]
+        output: Option<String>,
+        /// Commodity code for beancount, e.g. GBP; worked out from the currency symbol if not given
+        #[arg(long)]
+        commodity: Option<String>,
+    },
+    /// Check the config for mistakes without running the forecast, listing
+    /// every problem found with its line rather than stopping at the first
+    Validate,
+    /// Compare the balances the config's bank statements give with those an
+    /// earlier run forecast, month by month, to see where the forecast drifts
+    Reconcile {
+        /// JSON document of the earlier forecast, as written by --format json
+        #[arg(long)]
+        forecast: String,
+    },
+    /// Write an iCalendar file of the payments generators make over the coming
+    /// months, with amounts in the event titles, to overlay on a calendar
+    Calendar {
+        /// How many months of payments to include
+        #[arg(long, default_value_t = 12)]
+        months: u32,
+        /// File to write to; /tmp/forecast.ics if not given
+        #[arg(long)]
+        output: Option<String>,
+    },
+    /// Run the forecast to a date and save every balance and the engine's
+    /// state at the end of it, for a later run to --resume from
+    Snapshot {
+        /// Date to save the state at (YYYY-MM-DD)
+        #[arg(long)]
+        on: chrono::NaiveDate,
+        /// File to write to; /tmp/snapshot.yaml if not given
+        #[arg(long)]
+        output: Option<String>,
+    },
+    /// Serve the report, its charts and the forecast as JSON on localhost,
+    /// running the forecast again whenever the config changes
+    Serve {
+        /// Port to listen on
+        #[arg(long, default_value_t = 8080)]
+        port: u16,
+    },
+    /// Explore the forecast on the terminal: step through days and months,
+    /// graph accounts and see each day's transactions
+    Explore,
+}
+
+/// A generator and the values a matrix run gives it
+#[derive(Debug, Clone, PartialEq)]
+struct Vary {
+    generator: String,
+    from: Decimal,
+    to: Decimal,
+    step: Decimal,
+}
+
+fn parse_vary(text: &str) -> Result<Vary, String> {
+    let (generator, range) = text.rsplit_once('=').ok_or("Expected NAME=FROM:TO:STEP")?;
+    let values: Vec<Decimal> = range
+        .split(':')
+        .map(|value| value.trim().parse().map_err(|_| format!("'{value}' is not a number")))
+        .collect::<Result<_, _>>()?;
+    match values[..] {
+        [from, to, step] => Ok(Vary { generator: generator.trim().to_string(), from, to, step }),
+        _ => Err("Expected NAME=FROM:TO:STEP".to_string()),
+    }
+}
+
+/// The figure a matrix run shows for each combination
+#[derive(Clone, Copy, PartialEq, ValueEnum)]
+enum MatrixMetric {
+    /// Lowest balance of the main account over the forecast
+    LowestCash,
+    /// Main account's balance at the horizon
+    CashAtHorizon,
+    /// Total of the configured accounts at the horizon
+    NetWorth,
+    /// Date a liability is paid off
+    Payoff,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum ExportFormat {
+    /// A plain-text accounting journal, read by ledger-cli and hledger
+    Ledger,
+    /// A beancount ledger with monthly balance assertions, e.g. for browsing in Fava
+    Beancount,
+    /// An Excel workbook with a monthly summary, the transactions, and a sheet of balances for each account
+    Xlsx,
+    /// Parquet tables of every day's balances and of the transactions, e.g. for querying with DuckDB
+    Parquet,
+}
+
+/// What `result` holds, or else its error printed and the process ended
+fn or_exit<T>(result: Result<T, String>) -> T {
+    result.unwrap_or_else(|e| {
+        eprintln!("{e}");
+        std::process::exit(1);
+    })
+}
+
+fn main() {
+    let cli = Cli::parse();
+    log::set_verbosity(log::Verbosity::from_flags(cli.verbose, cli.quiet));
+    if cli.format == OutputFormat::Json && cli.command.is_some() {
+        eprintln!("--format json writes the forecast itself, not a subcommand's results");
+        std::process::exit(1);
+    }
+
+    // Load config from YAML
+    // read from actual.yaml if it exists, otherwise from config.yaml
+    let config_file = if fs::metadata("actual.yaml").is_ok() {
+        "actual.yaml"
+    } else {
+        "config.yaml"
+    };
+    if let Some(Command::Serve { port }) = &cli.command {
+        let args = forecast_args(&cli);
+        let watched = || watched_files(config_file, cli.resume.as_deref());
+        if let Err(e) = serve::serve(*port, |version| serve_report(&args, version), watched) {
+            eprintln!("Can't serve on port {port}: {e}");
+            std::process::exit(1);
+        }
+        return;
+    }
+    let text = fs::read_to_string(config_file).expect("Failed to read config file");
+    let read = |path: &std::path::Path| fs::read_to_string(path).map_err(|e| e.to_string());
+    let yaml = match include::resolve(std::path::Path::new(config_file), &text, &read) {
+        Ok(yaml) => yaml,
+        Err(e) => {
+            eprintln!("{e}");
+            std::process::exit(1);
+        }
+    };
+    if let Some(Command::Validate) = &cli.command {
+        let mut problems = validate_config(&yaml);
+        // Lines in the merged document don't point into any one file
+        if yaml != text {
+            problems.iter_mut().for_each(|problem| problem.line = None);
+        }
+        problems.sort_by_key(|problem| problem.line.unwrap_or(usize::MAX));
+        if problems.is_empty() {
+            println!("{config_file} is valid");
+            return;
+        }
+        for problem in &problems {
+            println!("{config_file}: {problem}");
+        }
+        println!("{} problem(s) found", problems.len());
+        std::process::exit(1);
+    }
+    let mut config: Config = match read_config(&yaml) {
+        Ok(cfg) => cfg,
+        Err(e) => {
+            eprintln!("YAML parsing error: {e}");
+            std::process::exit(1);
+        }
+    };
+
+    let as_of = cli.as_of.or(config.as_of);
+    let start_given = start_date_given(&yaml);
+    if !start_given && let Some(as_of) = as_of {
+        config.start_date = as_of;
+    }
+    if cli.end_date.is_some() || cli.years.is_some() || cli.months.is_some() {
+        (config.end_date, config.years, config.months) = (cli.end_date, cli.years, cli.months);
+    }
+    if cli.until_paid_off.is_some() {
+        config.until_paid_off = cli.until_paid_off.clone();
+    }
+    config.expand_children();
+    config.resolve_roles();
+    if let Some(file) = &cli.resume {
+        let resumed = fs::read_to_string(file).map_err(|e| e.to_string()).and_then(|text| snapshot::read(&text)).and_then(|snapshot| resume(&mut config, snapshot));
+        if let Err(e) = resumed {
+            eprintln!("Can't resume from {file}: {e}");
+            std::process::exit(1);
+        }
+    }
+    let actual_history = match splice_actuals(&mut config, as_of) {
+        Ok(history) => history,
+        Err(e) => {
+            eprintln!("{e}");
+            std::process::exit(1);
+        }
+    };
+    let scenarios = match scenario_configs(&yaml, &config) {
+        Ok(scenarios) => scenarios,
+        Err(e) => {
+            eprintln!("{e}");
+            std::process::exit(1);
+        }
+    };
+    let actuals = match goal_actuals(&config) {
+        Ok(actuals) => actuals,
+        Err(e) => {
+            eprintln!("{e}");
+            std::process::exit(1);
+        }
+    };
+
+    if let Err(problems) = check_currencies(&config) {
+        for problem in problems {
+            eprintln!("{problem}");
+        }
+        std::process::exit(1);
+    }
+
+    // Work out balances before running
+    let balances = all_accounts(&config);
+
+    // A generator wired to an account or index that doesn't exist can't run at all
+    let missing = missing_references(&config, &balances);
+    if !missing.is_empty() {
+        for (_, _, problem) in missing {
+            eprintln!("{problem}");
+        }
+        std::process::exit(1);
+    }
+
+    if let Err(problems) = check_account_types(&config, &balances) {
+        for problem in problems {
+            eprintln!("{problem}");
+        }
+        std::process::exit(1);
+    }
+    let plotted = match plot_accounts(&config, &balances) {
+        Ok(plotted) => plotted,
+        Err(e) => {
+            eprintln!("{e}");
+            std::process::exit(1);
+        }
+    };
+
+    let days_to_run = match horizon_days(&config).and_then(|days| match &config.until_paid_off {
+        Some(account) => paid_off_days(&config, &balances, account, days),
+        None => Ok(days),
+    }) {
+        Ok(days) => days,
+        Err(e) => {
+            eprintln!("{e}");
+            std::process::exit(1);
+        }
+    };
+    log::info(format_args!(
+        "Forecasting {days_to_run} days from {} with {} generators, from {config_file}",
+        config.start_date,
+        config.generators.len()
+    ));
+    let forecast = || or_exit(run_checked(&config, &balances, days_to_run, cli.check));
+    if let Some(Command::Impact { remove }) = &cli.command {
+        let without = match without_generators(&config, remove) {
+            Ok(without) => without,
+            Err(missing) => {
+                eprintln!("No generator named: {}", missing.join(", "));
+                std::process::exit(1);
+            }
+        };
+        let before = key_metrics(&config, &balances, &or_exit(run(&config, balances.clone(), days_to_run)));
+        let after = key_metrics(&without, &balances, &or_exit(run(&without, balances.clone(), days_to_run)));
+        println!("Without {}:", remove.join(", "));
+        report::print_impact(&before, &after, &config.currency_symbol);
+        return;
+    }
+    if let Some(Command::Solve { generator, account, target, by, max }) = &cli.command {
+        let goal = goals::Goal { account: account.clone(), target: *target, by: *by, name: None, from: None, actuals: None };
+        match solve_for(&config, &balances, generator, &goal, *max) {
+            Ok(found) => {
+                let show = |value| show_parameter(&config, generator, value);
+                report::print_solution(&goal, generator, found.map(show), &show(*max), &config.currency_symbol);
+            }
+            Err(e) => {
+                eprintln!("{e}");
+                std::process::exit(1);
+            }
+        }
+        return;
+    }
+    let stamp = stamp::RunStamp {
+        run_at: chrono::Local::now().naive_local(),
+        as_of,
+        config_file: config_file.to_string(),
+        config_hash: stamp::config_hash(&yaml),
+        version: env!("CARGO_PKG_VERSION").to_string(),
+        start: config.start_date,
+        end: config.start_date + chrono::Duration::days(days_to_run as i64),
+        assumptions: {
+            let mut assumptions = match &cli.command {
+                // The runs are sampled even when the config has no seed
+                Some(Command::MonteCarlo { runs }) => {
+                    let sampled = Config { seed: Some(config.seed.unwrap_or(0)), ..config.clone() };
+                    let mut assumptions = assumptions(&sampled);
+                    assumptions.push(format!("{runs} runs, each seeded one higher than the last"));
+                    assumptions
+                }
+                _ => assumptions(&config),
+            };
+            if let Some(file) = &cli.resume {
+                assumptions.push(format!("balances and engine state resumed from {file}"));
+            } else if !start_given {
+                assumptions.push(format!("start date of {} as none is configured", config.start_date));
+            }
+            assumptions
+        },
+    };
+    if cli.format == OutputFormat::Json {
+        let (history, final_state) = forecast();
+        let document = json::document(&stamp, &config.currency_symbol, &balances, &history, &final_state.journal);
+        match serde_json::to_string_pretty(&document) {
+            Ok(json) => println!("{json}"),
+            Err(e) => {
+                eprintln!("Error writing JSON: {e}");
+                std::process::exit(1);
+            }
+        }
+        return;
+    }
+    if let Some(Command::Explore) = &cli.command {
+        let (history, final_state) = forecast();
+        let accounts = account_order(&config, &balances);
+        let shown = config.accounts.keys().cloned().collect();
+        let explorer = explore::Explorer::new(&history, &final_state.journal, accounts, shown, &config.currency_symbol);
+        if let Err(e) = explore::run(explorer) {
+            eprintln!("Can't explore the forecast: {e}");
+            std::process::exit(1);
+        }
+        return;
+    }
+    for line in stamp.lines() {
+        println!("{line}");
+    }
+    if let Some(Command::Sweep { generator, from, to, step }) = &cli.command {
+        let rows = match sensitivity(&config, &balances, days_to_run, generator, (*from, *to, *step)) {
+            Ok(rows) => rows,
+            Err(e) => {
+                eprintln!("{e}");
+                std::process::exit(1);
+            }
+        };
+        let main = config.role(MAIN_ACCOUNT);
+        report::print_sensitivity(&rows, &main, |value| show_parameter(&config, generator, value), &config.currency_symbol);
+        create_sensitivity_plot(generator, &report::sensitivity_csv(&rows, generator, &main), &stamp, &config.currency_symbol);
+        return;
+    }
+    if let Some(Command::Replay { plan }) = &cli.command {
+        let replayed = fs::read_to_string(plan)
+            .map_err(|e| format!("Can't read plan {plan}: {e}"))
+            .and_then(|json| serde_json::from_str::<Vec<journal::Posting>>(&json).map_err(|e| format!("Plan {plan}: {e}")))
+            .and_then(|postings| journal::execute(&balances, config.start_date, stamp.end, &postings));
+        match replayed {
+            Ok(history) => {
+                let before = key_metrics(&config, &balances, &or_exit(run(&config, balances.clone(), days_to_run)));
+                println!("With the postings in {plan}:");
+                report::print_impact(&before, &key_metrics(&config, &balances, &history), &config.currency_symbol);
+            }
+            Err(e) => {
+                eprintln!("{e}");
+                std::process::exit(1);
+            }
+        }
+        return;
+    }
+    if let Some(Command::Export { format, output, commodity }) = &cli.command {
+        let (history, final_state) = forecast();
+        let opening: Vec<(String, Decimal)> = account_order(&config, &balances).into_iter().map(|account| (account.clone(), balances[&account])).collect();
+        let names = ledger_account_names(&config, &balances);
+        let file = |default: &str| output.clone().unwrap_or_else(|| default.to_string());
+        // What each file holds, where it goes, and its contents
+        let files: Vec<(&str, String, Vec<u8>)> = match format {
+            ExportFormat::Ledger => vec![(
+                "Ledger",
+                file("/tmp/forecast.ledger"),
+                ledger::journal(&stamp.lines(), config.start_date, &opening, &final_state.journal, &names, &config.currency_symbol).into_bytes(),
+            )],
+            ExportFormat::Beancount => {
+                let commodity = commodity.clone().unwrap_or_else(|| ledger::commodity(&config.currency_symbol));
+                let text = ledger::beancount(&stamp.lines(), config.start_date, &opening, &final_state.journal, &history, &names, &commodity);
+                vec![("Ledger", file("/tmp/forecast.beancount"), text.into_bytes())]
+            }
+            ExportFormat::Xlsx => {
+                let sheets = workbook_sheets(&config, &balances, &history, &final_state.journal);
+                vec![("Workbook", file("/tmp/forecast.xlsx"), xlsx::workbook(&sheets, &config.currency_symbol))]
+            }
+            ExportFormat::Parquet => {
+                let directory = std::path::PathBuf::from(file("/tmp"));
+                let path = |name: &str| directory.join(name).display().to_string();
+                let created_by = format!("cashflow {}", env!("CARGO_PKG_VERSION"));
+                let history = history_columns(&account_order(&config, &balances), &history);
+                vec![
+                    ("History", path("history.parquet"), parquet::table(&history, &created_by)),
+                    ("Journal", path("journal.parquet"), parquet::table(&journal_columns(&final_state.journal), &created_by)),
+                ]
+            }
+        };
+        for (saved, output, bytes) in files {
+            match std::fs::write(&output, bytes) {
+                Ok(()) => println!("{saved} saved to '{output}'"),
+                Err(e) => {
+                    eprintln!("Error writing {output}: {e}");
+                    std::process::exit(1);
+                }
+            }
+        }
+        return;
+    }
+    if let Some(Command::Calendar { months, output }) = &cli.command {
+        // The coming months are counted from the date the forecast is as of, or else from today
+        let from = as_of.unwrap_or_else(|| chrono::Local::now().date_naive());
+        let Some(until) = from.checked_add_months(chrono::Months::new(*months)) else {
+            eprintln!("A calendar of {months} months is too long");
+            std::process::exit(1);
+        };
+        let days = (until - config.start_date).num_days().clamp(1, MAX_HORIZON_DAYS);
+        let (_, final_state) = or_exit(run_with_state(&config, balances.clone(), days as i32));
+        let payments: Vec<journal::Posting> = calendar_postings(&config, &final_state.journal)
+            .into_iter()
+            .filter(|posting| (from..=until).contains(&posting.date))
+            .collect();
+        let events = ics::events(&payments, &config.role(MAIN_ACCOUNT), &config.currency_symbol);
+        let output = output.as_deref().unwrap_or("/tmp/forecast.ics");
+        let generated = as_of.map_or_else(chrono::Utc::now, |date| date.and_time(chrono::NaiveTime::MIN).and_utc());
+        match std::fs::write(output, ics::calendar(&events, generated)) {
+            Ok(()) => println!("{} payments from {from} up to {until} saved to '{output}'", events.len()),
+            Err(e) => {
+                eprintln!("Error writing {output}: {e}");
+                std::process::exit(1);
+            }
+        }
+        return;
+    }
+    if let Some(Command::Snapshot { on, output }) = &cli.command {
+        let days = (*on - config.start_date).num_days();
+        if !(1..=MAX_HORIZON_DAYS).contains(&days) {
+            eprintln!("The snapshot date must be after the forecast starts on {} and within {MAX_HORIZON_DAYS} days of it", config.start_date);
+            std::process::exit(1);
+        }
+        let (history, final_state) = or_exit(run_with_state(&config, balances.clone(), days as i32));
+        let (date, balances) = history.last().expect("Forecast ran for at least a day");
+        let output = output.as_deref().unwrap_or("/tmp/snapshot.yaml");
+        match snapshot::Snapshot::new(*date, generator_labels(&config), balances, final_state).to_yaml().and_then(|yaml| fs::write(output, yaml).map_err(|e| e.to_string())) {
+            Ok(()) => println!("Balances and state at the end of {date} saved to '{output}'"),
+            Err(e) => {
+                eprintln!("Error writing {output}: {e}");
+                std::process::exit(1);
+            }
+        }
+        return;
+    }
+    if let Some(Command::Reconcile { forecast }) = &cli.command {
+        if actual_history.is_empty() {
+            eprintln!("Nothing to reconcile: the config's actuals give no statement rows after {}", config.start_date);
+            std::process::exit(1);
+        }
+        let previous = match fs::read_to_string(forecast).map_err(|e| e.to_string()).and_then(|text| json::read_history(&text)) {
+            Ok(previous) => previous,
+            Err(e) => {
+                eprintln!("Can't read the forecast in {forecast}: {e}");
+                std::process::exit(1);
+            }
+        };
+        let mut accounts: Vec<String> = config.actuals.iter().map(|statement| statement.account.clone()).collect();
+        accounts.sort();
+        accounts.dedup();
+        let variances = report::variances(&previous, &actual_history, &accounts);
+        report::print_variances(&variances, &config.currency_symbol);
+        create_reconciliation_plot(&accounts, &previous, &actual_history, &variances, &stamp, &config.currency_symbol);
+        return;
+    }
+    let register = assumption_register(&config);
+    report::print_assumption_register(&register);
+    if let Some(Command::Matrix { vary, metric, account }) = &cli.command {
+        match matrix(&config, &balances, days_to_run, vary, *metric, account.as_deref()) {
+            Ok(grid) => {
+                report::print_matrix(&grid, &config.currency_symbol);
+                create_matrix_plot(&grid, *metric != MatrixMetric::Payoff, &stamp, &config.currency_symbol);
+            }
+            Err(e) => {
+                eprintln!("{e}");
+                std::process::exit(1);
+            }
+        }
+        return;
+    }
+    if let Some(Command::MonteCarlo { runs }) = &cli.command {
+        let bands = or_exit(simulate(&config, &balances, days_to_run, *runs));
+        for (account, bands) in &bands {
+            let yearly: Vec<_> = bands.iter().filter(|(date, _)| date.month() == 1).copied().collect();
+            report::print_bands(account, &yearly, &config.currency_symbol);
+            create_bands_plot(account, bands, &stamp, &config.currency_symbol);
+        }
+        return;
+    }
+    // The days taken from statements are checked as they stand
+    let currencies = config.currencies();
+    if cli.check
+        && let Some(violation) = invariants::verify_invariants(&actual_history, &engine_invariants(&config, &balances, &currencies)).first()
+    {
+        eprintln!("Invariant broken in the actuals on {violation}");
+        std::process::exit(1);
+    }
+    let (history, final_state) = forecast();
+    let history = [actual_history, history].concat();
+    
+    // Print the history of balances
+    let mut order = account_order(&config, &balances);
+    order.push(OPENING_BALANCES.to_string());
+    for (date, balances) in history.iter().filter(|_| !cli.quiet) {
+        if date.day() == 1 {
+            println!("\nBalances on {date}:");
+            for (name, balance) in order.iter().filter_map(|name| Some((name, balances.get(name)?))) {
+                let symbol = match currencies.currency(name) {
+                    currency if currency == currencies.base() => config.currency_symbol.clone(),
+                    currency => format!("{currency} "),
+                };
+                print_balance_named(name, *date, *balance, &symbol); 
+            }
+        }
+    }
+    
+    if cli.show_transactions {
+        report::print_journal(&final_state.journal, &config.currency_symbol);
+    }
+    if let Err(e) = std::fs::write("/tmp/journal.csv", [stamp.csv(), journal::csv(&final_state.journal)].concat().join("\n")) {
+        eprintln!("Error creating CSV file: {}", e);
+    } else {
+        println!("Journal saved to '/tmp/journal.csv'");
+    }
+    match serde_json::to_string_pretty(&final_state.journal) {
+        Ok(json) => match std::fs::write("/tmp/journal.json", json) {
+            Ok(()) => println!("Journal saved to '/tmp/journal.json'"),
+            Err(e) => eprintln!("Error creating JSON file: {}", e),
+        },
+        Err(e) => eprintln!("Error creating JSON file: {}", e),
+    }
+
+    let names = ledger_account_names(&config, &balances);
+    let types: Vec<(String, String)> = account_order(&config, &balances)
+        .into_iter()
+        .map(|account| {
+            let kind = names[&account].split(':').next().unwrap_or_default().to_string();
+            (account, kind)
+        })
+        .collect();
+    let monthly_summary = report::monthly_summary(&history, &final_state.journal, &types);
+    report::print_monthly_summary(&monthly_summary, &config.currency_symbol);
+
+    let calendar_years = year_totals(&config, &final_state.journal, |date| date.year().to_string());
+    report::print_year_totals("Calendar years", &calendar_years, &config.currency_symbol);
+    let jurisdiction = config.tax.jurisdiction();
+    let tax_years = year_totals(&config, &final_state.journal, |date| tax_year_label(jurisdiction.tax_year(date)));
+    report::print_year_totals("Tax years", &tax_years, &config.currency_symbol);
+    report::print_savings_allowance(&or_exit(savings_allowance_use(&config, &final_state.journal)), &config.currency_symbol);
+
+    let breaches = alerts::breaches(&history, &config.warn_if, &final_state.journal);
+    report::print_alerts(&breaches, &config.currency_symbol);
+    let goals: Vec<goals::Progress> = config.goals.iter().map(|goal| goals::progress(&history, goal)).collect();
+    report::print_goals(&goals, &config.currency_symbol);
+    let checks = checks::outcomes(&history, &config.checks);
+    report::print_checks(&checks, &config.currency_symbol);
+    let plans: Vec<(goals::Goal, Vec<goals::PlanRow>)> =
+        config.goals.iter().zip(&actuals).map(|(goal, actuals)| (goal.clone(), goals::plan(&history, goal, actuals))).collect();
+    report::print_plans(&plans, &config.currency_symbol);
+    let inventory = account_inventory(&config, &balances, &history);
+    report::print_account_inventory(&inventory, &config.currency_symbol);
+    let tree = history.last().map(|(_, closing)| hierarchy::tree(&balances, closing)).unwrap_or_default();
+    report::print_account_tree(&tree, &config.currency_symbol);
+    if let Err(e) = std::fs::write("/tmp/accounts.csv", [stamp.csv(), report::account_inventory_csv(&inventory)].concat().join("\n")) {
+        eprintln!("Error creating CSV file: {}", e);
+    } else {
+        println!("Account inventory saved to '/tmp/accounts.csv'");
+    }
+    report::print_category_summary(&history, &balances, &categories(&config.generators), &config.currency_symbol);
+    report::print_giving_summary(&history, &balances, &giving_accounts(&config), &config.currency_symbol);
+    report::print_envelope_usage(&envelope::usage(&envelopes(&config), &final_state.journal, &history), &config.currency_symbol);
+    report::print_subscription_audit(&or_exit(subscription_audit(&config, &balances, days_to_run, &history)), &config.currency_symbol);
+    report::print_debt_allocations(&debt_allocations(&config, &final_state), &config.currency_symbol);
+    report::print_overdraft_breaches(&final_state.overdraft_breaches, &config.overdrafts, &config.currency_symbol);
+    report::print_payroll_giving(&payroll_giving_comparison(&config), &config.currency_symbol);
+    report::print_university_plans(&university_plans(&config), &config.currency_symbol);
+    report::print_share_scheme_outcomes(&share_scheme_outcomes(&config), &config.currency_symbol);
+    let payoffs = liability_payoffs(&config, &balances, &history, &final_state);
+    report::print_liability_payoffs(&payoffs, &config.currency_symbol);
+    report::print_overpayment_impact(&or_exit(overpayment_impact(&config, &balances, days_to_run, &history, &final_state)), &config.currency_symbol);
+    if let Some(comparison) = &config.tithe_comparison {
+        report::print_giving_comparison(&or_exit(giving_comparison(&config, comparison, &balances, days_to_run)), &config.currency_symbol);
+    }
+    let mut scenario_histories = Vec::new();
+    let scenario_runs = parallel::map(&scenarios, |(name, varied)| {
+        let varied_balances = all_accounts(varied);
+        let varied_history = run(varied, varied_balances.clone(), days_to_run).map_err(|e| format!("Scenario {name}: {e}"))?;
+        Ok((varied_balances, varied_history))
+    });
+    let scenario_runs = or_exit(scenario_runs.into_iter().collect::<Result<Vec<_>, String>>());
+    for ((name, varied), (varied_balances, varied_history)) in scenarios.iter().zip(scenario_runs) {
+        println!("\nScenario {name}:");
+        report::print_impact(
+            &key_metrics(&config, &balances, &history),
+            &key_metrics(varied, &varied_balances, &varied_history),
+            &config.currency_symbol,
+        );
+        scenario_histories.push((name.clone(), varied_history));
+    }
+
+    // Chart each liability's balance over time, with its amortization schedule
+    let tables_html = [
+        report::alerts_html(&breaches, &config.currency_symbol),
+        report::goals_html(&goals, &config.currency_symbol),
+        report::checks_html(&checks, &config.currency_symbol),
+        report::assumption_register_html(&register),
+        report::liability_payoffs_html(&payoffs, &config.currency_symbol),
+        report::account_inventory_html(&inventory, &config.currency_symbol),
+        report::account_tree_html(&tree, &config.currency_symbol),
+        report::year_totals_html(&tax_years, &config.currency_symbol),
+        report::monthly_summary_html(&monthly_summary, &config.currency_symbol),
+    ]
+    .join("\n");
+    let goals_for = |account: &str| -> Vec<goals::Goal> {
+        config.goals.iter().filter(|goal| goal.account == account).cloned().collect()
+    };
+    let overlay = |account: &str| Overlay {
+        goals: goals_for(account),
+        markers: posting_markers(&final_state.journal, account, &config.currency_symbol),
+        monthly_lows: true,
+    };
+    let liabilities = liability_accounts(&config, &balances);
+    for account in &liabilities {
+        let schedule = report::amortization(&history, &balances, account, &interest_charged(&config, &final_state, account));
+        let payments = report::payment_schedule(&final_state.journal, &balances, account);
+        let path = format!("/tmp/{account}_payments.csv");
+        if let Err(e) = std::fs::write(&path, [stamp.csv(), report::payment_schedule_csv(&payments)].concat().join("\n")) {
+            eprintln!("Error creating CSV file: {}", e);
+        } else {
+            println!("{account} payment schedule saved to '{path}'");
+        }
+        let tables_html = format!("{tables_html}\n{}", report::payment_schedule_html(&payments, &config.currency_symbol));
+        create_liability_plots(account, &history, &schedule, &stamp, &tables_html, &overlay(account), &config.currency_symbol);
+    }
+    // Chart every other account with a goal, with its target line
+    let mut goal_accounts: Vec<&String> = config.goals.iter().map(|goal| &goal.account).filter(|account| !liabilities.contains(account)).collect();
+    goal_accounts.sort();
+    goal_accounts.dedup();
+    for account in &goal_accounts {
+        create_balance_plot(account, &history, &stamp, &tables_html, &overlay(account), &config.currency_symbol);
+    }
+    // Chart the rest of the accounts to plot on their own, then all of them
+    // together, with accounts that others are nested under charted as their totals
+    let grouped: History;
+    let plot_history = if hierarchy::nested(&balances) {
+        grouped = history.iter().map(|(date, balances)| (*date, hierarchy::with_parents(balances))).collect();
+        &grouped
+    } else {
+        &history
+    };
+    for account in plotted.iter().filter(|account| !liabilities.contains(account) && !goal_accounts.contains(account)) {
+        create_balance_plot(account, plot_history, &stamp, &tables_html, &overlay(account), &config.currency_symbol);
+    }
+    create_accounts_plot(&plotted, plot_history, &stamp, &tables_html, &config.currency_symbol);
+    let names = ledger_account_names(&config, &balances);
+    let of_type = |kind: ledger::AccountType| -> Vec<String> {
+        names.iter().filter(|(account, name)| **name == kind.name(account)).map(|(account, _)| account.clone()).collect()
+    };
+    let (assets, liabilities) = (of_type(ledger::AccountType::Assets), of_type(ledger::AccountType::Liabilities));
+    let worth = report::net_worth(&currencies.history_in_base(&history), &assets, &liabilities);
+    if currencies.mixed() && let Some((date, last)) = history.last() {
+        let totals = currencies.totals(last, &[assets, liabilities].concat(), *date);
+        report::print_currency_totals(*date, &totals, currencies.base(), &config.currency_symbol);
+    }
+    create_net_worth_plot(&worth, &stamp, &tables_html, &config.currency_symbol);
+    for (goal, rows) in &plans {
+        create_plan_plot(goal, rows, &stamp, &config.currency_symbol);
+    }
+    if !scenario_histories.is_empty() {
+        create_scenario_plot(&config.role(MAIN_ACCOUNT), &history, &scenario_histories, &stamp, &config.currency_symbol);
+    }
+}
+
+/// Each day's balance of each of `accounts` over `history`, a row for each, as Parquet columns
+fn history_columns(accounts: &[String], history: &History) -> Vec<(&'static str, parquet::Column)> {
+    let rows = history.iter().flat_map(|(date, balances)| accounts.iter().map(move |account| (*date, account, balances[account.as_str()])));
+    let (mut dates, mut names, mut amounts) = (Vec::new(), Vec::new(), Vec::new());
+    for (date, account, balance) in rows {
+        dates.push(date);
+        names.push(account.clone());
+        amounts.push(balance.to_f64().unwrap_or_default());
+    }
+    vec![("date", parquet::Column::Date(dates)), ("account", parquet::Column::Text(names)), ("balance", parquet::Column::Number(amounts))]
+}
+
+/// `postings`, a row for each, as Parquet columns
+fn journal_columns(postings: &[journal::Posting]) -> Vec<(&'static str, parquet::Column)> {
+    let text = |field: fn(&journal::Posting) -> &String| parquet::Column::Text(postings.iter().map(|posting| field(posting).clone()).collect());
+    vec![
+        ("date", parquet::Column::Date(postings.iter().map(|posting| posting.date).collect())),
+        ("generator", text(|posting| &posting.generator)),
+        ("description", text(|posting| &posting.description)),
+        ("from", text(|posting| &posting.from)),
+        ("to", text(|posting| &posting.to)),
+        ("amount", parquet::Column::Number(postings.iter().map(|posting| posting.amount.to_f64().unwrap_or_default()).collect())),
+        ("note", parquet::Column::OptionalText(postings.iter().map(|posting| posting.note.clone()).collect())),
+    ]
+}
+
+/// The forecast as worksheets: the monthly summary, with each month's net
+/// worked out by formula, every transaction, and each account's balance by day
+fn workbook_sheets(config: &Config, balances: &balances::Balances, history: &History, postings: &[journal::Posting]) -> Vec<xlsx::Sheet> {
+    use xlsx::Cell;
+    let heading = |headings: &[&str]| headings.iter().map(|heading| Cell::Heading(heading.to_string())).collect::<Vec<_>>();
+    let order = account_order(config, balances);
+    let names = ledger_account_names(config, balances);
+    let types: Vec<(String, String)> =
+        order.iter().map(|account| (account.clone(), names[account].split(':').next().unwrap_or_default().to_string())).collect();
+
+    let mut summary = vec![heading(&["Month", "Account", "In", "Out", "Net", "Closing", "Lowest", "On"])];
+    for month in report::monthly_summary(history, postings, &types) {
+        let (year, number) = month.month;
+        let totals = month.types.iter().map(|flows| (format!("Total {}", flows.name), flows));
+        for (name, flows) in month.accounts.iter().map(|flows| (flows.name.clone(), flows)).chain(totals) {
+            let row = summary.len() + 1;
+            summary.push(vec![
+                Cell::Text(format!("{year}-{number:02}")),
+                Cell::Text(name),
+                Cell::Money(flows.inflows),
+                Cell::Money(flows.outflows),
+                Cell::Formula(format!("C{row}-D{row}")),
+                Cell::Money(flows.closing),
+                Cell::Money(flows.lowest),
+                Cell::Date(flows.lowest_on),
+            ]);
+        }
+    }
+    let mut transactions = vec![heading(&["Date", "Generator", "Description", "From", "To", "Amount", "Note"])];
+    transactions.extend(postings.iter().map(|posting| {
+        vec![
+            Cell::Date(posting.date),
+            Cell::Text(posting.generator.clone()),
+            Cell::Text(posting.description.clone()),
+            Cell::Text(posting.from.clone()),
+            Cell::Text(posting.to.clone()),
+            Cell::Money(posting.amount),
+            posting.note.clone().map_or(Cell::Empty, Cell::Text),
+        ]
+    }));
+    let mut sheets = vec![
+        xlsx::Sheet { name: "Monthly summary".to_string(), rows: summary },
+        xlsx::Sheet { name: "Transactions".to_string(), rows: transactions },
+    ];
+    for account in &order {
+        let mut rows = vec![heading(&["Date", "Balance", "Change"]), vec![Cell::Date(config.start_date), Cell::Money(balances[account.as_str()])]];
+        for (date, day) in history {
+            let row = rows.len() + 1;
+            rows.push(vec![Cell::Date(*date), Cell::Money(day[account.as_str()]), Cell::Formula(format!("B{row}-B{}", row - 1))]);
+        }
+        sheets.push(xlsx::Sheet { name: account.clone(), rows });
+    }
+    sheets
+}
+
+fn run(
+    config: &Config,
+    balances: impl Into<balances::Balances>,
+    days_to_run: i32
+) -> Result<History, String> {
+    Ok(run_with_state(config, balances, days_to_run)?.0)
+}
+
+/// Run the simulation, also returning the engine state at the end
+fn run_with_state(
+    config: &Config,
+    balances: impl Into<balances::Balances>,
+    days_to_run: i32
+) -> Result<(History, State), String> {
+    run_between(config, balances.into(), State::new(config), config.start_date, days_to_run, |_, _, _| {})
+}
+
+/// Run the simulation like `run_with_state`, and if `check` is set, check
+/// every day against the engine's invariants, failing with the first broken
+fn run_checked(config: &Config, balances: &balances::Balances, days_to_run: i32, check: bool) -> Result<(History, State), String> {
+    if !check {
+        return run_with_state(config, balances.clone(), days_to_run);
+    }
+    let currencies = config.currencies();
+    let invariants = engine_invariants(config, balances, &currencies);
+    let mut broken = None;
+    let forecast = run_between(config, balances.clone(), State::new(config), config.start_date, days_to_run, |date, balances, _| {
+        if broken.is_none() {
+            broken = invariants.violations(date, balances).into_iter().next();
+        }
+    })?;
+    broken.map_or(Ok(forecast), |violation| Err(format!("Invariant broken on {violation}")))
+}
+
+/// What every day of a forecast of `config` from `balances` has to satisfy
+fn engine_invariants<'a>(config: &Config, balances: &balances::Balances, currencies: &'a currency::Currencies<'a>) -> invariants::Invariants<'a> {
+    let names = ledger_account_names(config, balances);
+    let mut liabilities: Vec<String> = names
+        .iter()
+        .filter(|(account, name)| {
+            **name == ledger::AccountType::Liabilities.name(account)
+                && !config.account_details.get(*account).is_some_and(|details| details.allow_credit)
+        })
+        .map(|(account, _)| account.clone())
+        .collect();
+    liabilities.sort();
+    invariants::Invariants { currencies, liabilities, limit: MAX_BALANCE }
+}
+
+/// Carry on a forecast from the end of `date`, with the balances and engine
+/// state then, for `days_to_run` days, calling `on_day` at the end of each.
+/// Fails on the first day a balance grows too large to forecast.
+fn run_between(
+    config: &Config,
+    balances: balances::Balances,
+    state: State,
+    date: chrono::NaiveDate,
+    days_to_run: i32,
+    mut on_day: impl FnMut(chrono::NaiveDate, &balances::Balances, &State),
+) -> Result<(History, State), String> {
+    let (mut balances, mut state, mut date) = (balances, state, date);
+    let mut history = Vec::new();
+    let mut journal = Vec::new();
+
+    for day in 1..=days_to_run {
+        date += chrono::Duration::days(1);
+        let (new_balances, new_state) = compute_next_day_balances(config, &balances, date, &state)?;
+        balances = new_balances;
+        state = new_state;
+        journal.append(&mut state.journal);
+        on_day(date, &balances, &state);
+        if day == days_to_run || config.history.keeps(date) {
+            history.push((date, balances.clone()));
+        }
+    }
+    state.journal = journal;
+    Ok((history, state))
+}
+
+/// A forecast kept with the engine's balances and state at every month end,
+/// so a change to its generators can be forecast again from the last month
+/// end before the change makes a difference instead of from the start
+struct Baseline<'a> {
+    config: &'a Config,
+    opening: balances::Balances,
+    history: History,
+    state: State,
+    days_to_run: i32,
+    checkpoints: Vec<(chrono::NaiveDate, balances::Balances, State)>,
+}
+
+impl<'a> Baseline<'a> {
+    fn new(config: &'a Config, opening: balances::Balances, days_to_run: i32) -> Result<Self, String> {
+        let mut checkpoints = Vec::new();
+        let (history, state) = run_between(config, opening.clone(), State::new(config), config.start_date, days_to_run, |date, balances, state| {
+            if date.succ_opt().is_some_and(|next| next.day() == 1) {
+                checkpoints.push((date, balances.clone(), state.clone()));
+            }
+        })?;
+        Ok(Baseline { config, opening, history, state, days_to_run, checkpoints })
+    }
+
+    /// First day the forecast for `changed` could differ from the baseline, or
+    /// None if it can't. Only a generator changed in place, without moving
+    /// when it comes due, is followed to the day it first does; any other
+    /// change is taken to make a difference from the day it starts.
+    fn first_affected(&self, changed: &Config) -> Option<chrono::NaiveDate> {
+        let first_day = self.config.start_date + chrono::Duration::days(1);
+        let without_generators = |config: &Config| Config { generators: Vec::new(), ..config.clone() };
+        if changed.generators.len() != self.config.generators.len() || without_generators(changed) != without_generators(self.config) {
+            return Some(first_day);
+        }
+        self.config
+            .generators
+            .iter()
+            .zip(&changed.generators)
+            .enumerate()
+            .filter(|(_, (old, new))| old != new)
+            .filter_map(|(index, (old, new))| {
+                let same_timing = old.start_date == new.start_date
+                    && old.end_date == new.end_date
+                    && old.skip == new.skip
+                    && old.adjust == new.adjust
+                    && old.generator.due_schedule().is_some()
+                    && old.generator.due_schedule() == new.generator.due_schedule();
+                if same_timing {
+                    // Never due in the baseline means never due at all
+                    self.state.first_due.get(&index).copied()
+                } else {
+                    Some([old.start_date, new.start_date].into_iter().map(|start| start.map_or(first_day, |start| start.max(first_day))).min()?)
+                }
+            })
+            .min()
+    }
+
+    /// The forecast for `changed`, a variation on the baseline's config
+    fn rerun(&self, changed: &Config) -> Result<History, String> {
+        let Some(affected) = self.first_affected(changed) else {
+            return Ok(self.history.clone());
+        };
+        match self.checkpoints.iter().rev().find(|(date, _, _)| *date < affected) {
+            Some((date, balances, state)) => {
+                let days_run = (*date - self.config.start_date).num_days() as i32;
+                let (rest, _) = run_between(changed, balances.clone(), state.clone(), *date, self.days_to_run - days_run, |_, _, _| {})?;
+                let kept = self.history.partition_point(|(day, _)| day <= date);
+                Ok(self.history[..kept].iter().cloned().chain(rest).collect())
+            }
+            None => run(changed, self.opening.clone(), self.days_to_run),
+        }
+    }
+}
+
+/// Every account the forecast for `config` needs, with its opening balance
+fn all_accounts(config: &Config) -> balances::Balances {
+    let accounts_with_defaults = add_default_accounts(&config.accounts);
+    let accounts_with_generators = add_generator_accounts(&accounts_with_defaults, &config.generators);
+    let accounts = add_overdraft_accounts(&accounts_with_generators, &config.overdrafts);
+    let mut balances = add_opening_balances(&accounts);
+    // Opening balances are in the base currency, taking other currencies at the starting rate
+    let currencies = config.currencies();
+    balances.insert(OPENING_BALANCES.to_string(), -currencies.total(&accounts, config.start_date));
+    if currencies.mixed() {
+        balances.entry(EXCHANGE_GAINS.to_string()).or_insert(Decimal::ZERO);
+    }
+    balances.into()
+}
+
+/// Recorded balances for each goal, read from its actuals file if it has one
+fn goal_actuals(config: &Config) -> Result<Vec<Vec<(chrono::NaiveDate, Decimal)>>, String> {
+    config
+        .goals
+        .iter()
+        .map(|goal| match &goal.actuals {
+            Some(path) => {
+                let text = fs::read_to_string(path).map_err(|e| format!("Can't read actuals for {} from {path}: {e}", goal.label()))?;
+                goals::parse_actuals(&text).map_err(|e| format!("Actuals for {} in {path}: {e}", goal.label()))
+            }
+            None => Ok(Vec::new()),
+        })
+        .collect()
+}
+
+/// Read the config's bank statements, up to `until` if given, and move the
+/// config on to start from the balances at the end of the last day they
+/// cover. Returns the balances on each day the statements replace.
+fn splice_actuals(config: &mut Config, until: Option<chrono::NaiveDate>) -> Result<History, String> {
+    if config.actuals.is_empty() {
+        return Ok(Vec::new());
+    }
+    let mut statements = Vec::new();
+    for statement in &config.actuals {
+        let text = fs::read_to_string(&statement.file).map_err(|e| format!("Can't read statement {}: {e}", statement.file))?;
+        let mut rows = actuals::parse(statement, &text).map_err(|e| format!("Statement {}: {e}", statement.file))?;
+        rows.retain(|row| until.is_none_or(|until| row.date <= until));
+        statements.push((statement, rows));
+    }
+    let history = actuals::history(&all_accounts(config), config.start_date, &statements, OPENING_BALANCES);
+    if let Some((date, balances)) = history.last() {
+        config.start_date = *date;
+        config.accounts = balances.iter().filter(|(account, _)| *account != OPENING_BALANCES).map(|(account, balance)| (account.clone(), *balance)).collect();
+    }
+    Ok(history)
+}
+
+/// Label of each of the config's generators, in order
+fn generator_labels(config: &Config) -> Vec<String> {
+    config.generators.iter().enumerate().map(|(index, entry)| entry.label(index)).collect()
+}
+
+/// Carry the config on from `snapshot`, starting on its date from its
+/// balances and engine state
+fn resume(config: &mut Config, snapshot: snapshot::Snapshot<State>) -> Result<(), String> {
+    snapshot.check(&generator_labels(config))?;
+    config.start_date = snapshot.date;
+    config.accounts = snapshot.balances.into_iter().filter(|(account, _)| account != OPENING_BALANCES).collect();
+    config.opening_state = Some(snapshot.state);
+    Ok(())
+}
+
+/// The config document `yaml`, with its variables worked out and put in place
+fn read_config(yaml: &str) -> Result<Config, String> {
+    let mut document: serde_yaml::Value = serde_yaml::from_str(yaml).map_err(|e| e.to_string())?;
+    variables::resolve(&mut document)?;
+    serde_yaml::from_value(document).map_err(|e| e.to_string())
+}
+
+/// The options a served report's forecast is run with, as given on the command line
+fn forecast_args(cli: &Cli) -> Vec<String> {
+    let mut args = Vec::new();
+    let options = [
+        ("--as-of", cli.as_of.map(|date| date.to_string())),
+        ("--resume", cli.resume.clone()),
+        ("--end-date", cli.end_date.map(|date| date.to_string())),
+        ("--years", cli.years.map(|years| years.to_string())),
+        ("--months", cli.months.map(|months| months.to_string())),
+        ("--until-paid-off", cli.until_paid_off.clone()),
+    ];
+    for (option, value) in options {
+        if let Some(value) = value {
+            args.extend([option.to_string(), value]);
+        }
+    }
+    if cli.show_transactions {
+        args.push("--show-transactions".to_string());
+    }
+    if cli.quiet {
+        args.push("--quiet".to_string());
+    }
+    if cli.verbose > 0 {
+        args.push(format!("-{}", "v".repeat(cli.verbose as usize)));
+    }
+    if cli.check {
+        args.push("--check".to_string());
+    }
+    args
+}
+
+/// Run the forecast with `args` as a separate process, once for the text
+/// report and its charts and once for the JSON document, for serving
+fn serve_report(args: &[String], version: u64) -> serve::Report {
+    let run = |extra: &[&str]| {
+        let exe = std::env::current_exe().map_err(|e| e.to_string())?;
+        let output = std::process::Command::new(exe).args(args).args(extra).output().map_err(|e| e.to_string())?;
+        match output.status.success() {
+            true => Ok(String::from_utf8_lossy(&output.stdout).into_owned()),
+            false => Err(String::from_utf8_lossy(&output.stderr).into_owned()),
+        }
+    };
+    match run(&[]) {
+        Ok(text) => serve::Report {
+            version,
+            pages: serve::pages(&text),
+            document: run(&["--format", "json"]).ok().and_then(|json| serde_json::from_str(&json).ok()),
+            text,
+        },
+        Err(e) => serve::Report { version, text: format!("The forecast can't be run: {e}"), ..Default::default() },
+    }
+}
+
+/// The config file, the files it includes and any snapshot resumed from,
+/// whose changes a served report is run again for
+fn watched_files(config_file: &str, resume: Option<&str>) -> Vec<std::path::PathBuf> {
+    let included = std::cell::RefCell::new(Vec::new());
+    let read = |path: &std::path::Path| {
+        included.borrow_mut().push(path.to_path_buf());
+        fs::read_to_string(path).map_err(|e| e.to_string())
+    };
+    if let Ok(text) = fs::read_to_string(config_file) {
+        // Files read before a broken include are still worth watching
+        let _ = include::resolve(std::path::Path::new(config_file), &text, &read);
+    }
+    let mut files = vec![std::path::PathBuf::from(config_file)];
+    files.extend(included.into_inner());
+    files.extend(resume.map(std::path::PathBuf::from));
+    files
+}
+
+/// Each scenario's name and config: the config document `yaml` with the
+/// scenario's changes made, starting when `config` does unless it sets its own start
+fn scenario_configs(yaml: &str, config: &Config) -> Result<Vec<(String, Config)>, String> {
+    if config.scenarios.is_empty() {
+        return Ok(Vec::new());
+    }
+    let base: serde_yaml::Value = serde_yaml::from_str(yaml).map_err(|e| e.to_string())?;
+    config
+        .scenarios
+        .iter()
+        .map(|scenario| {
+            let mut document = scenario::apply(&base, scenario)?;
+            variables::resolve(&mut document).map_err(|e| format!("Scenario {}: {e}", scenario.name))?;
+            let mut varied: Config = serde_yaml::from_value(document).map_err(|e| format!("Scenario {}: {e}", scenario.name))?;
+            if !scenario.set.contains_key("start_date") {
+                varied.start_date = config.start_date;
+            }
+            // Scenarios start from the real or resumed balances too, once they've replaced the config's
+            if (!config.actuals.is_empty() || config.opening_state.is_some()) && !scenario.set.contains_key("accounts") {
+                varied.accounts = config.accounts.clone();
+            }
+            varied.expand_children();
+            varied.resolve_roles();
+            // The resumed state is kept by generator index, so it only carries over to the same generators
+            if generator_labels(&varied) == generator_labels(config) {
+                varied.opening_state = config.opening_state.clone();
+            }
+            Ok((scenario.name.clone(), varied))
+        })
+        .collect()
+}
+
+fn add_opening_balances(
+    balances: &std::collections::HashMap<String, Decimal>,
+) -> std::collections::HashMap<String, Decimal> {
+    let mut new_balances = balances.clone();
+    let opening_balance: Decimal = new_balances.values().sum();
+    new_balances.insert(OPENING_BALANCES.to_string(), -opening_balance);
+    new_balances
+}
+
+fn add_default_accounts(
+    balances: &std::collections::HashMap<String, Decimal>,
+) -> std::collections::HashMap<String, Decimal> {
+    let mut new_balances = balances.clone();
+    if !new_balances.contains_key(SALARY_INCOME) {
         new_balances.insert(SALARY_INCOME.to_string(), Decimal::ZERO);
     }
-    if !new_balances.contains_key(MORTGAGE_INCOME) {
-        new_balances.insert(MORTGAGE_INCOME.to_string(), Decimal::ZERO);
+    if !new_balances.contains_key(MORTGAGE_INCOME) {
+        new_balances.insert(MORTGAGE_INCOME.to_string(), Decimal::ZERO);
+    }
+    if !new_balances.contains_key(CHARITY_EXPENDITURE) {
+        new_balances.insert(CHARITY_EXPENDITURE.to_string(), Decimal::ZERO);
+    }
+    new_balances
+}
+
+/// Add the accounts overdraft interest and fees are charged to
+fn add_overdraft_accounts(
+    balances: &std::collections::HashMap<String, Decimal>,
+    overdrafts: &std::collections::HashMap<String, overdraft::Overdraft>,
+) -> std::collections::HashMap<String, Decimal> {
+    let mut new_balances = balances.clone();
+    for overdraft in overdrafts.values() {
+        new_balances.entry(overdraft.interest_account.clone()).or_insert(Decimal::ZERO);
+        new_balances.entry(overdraft.fee_account.clone()).or_insert(Decimal::ZERO);
+    }
+    new_balances
+}
+
+/// Whether the config text sets `start_date` itself rather than leaving it to the default
+fn start_date_given(yaml: &str) -> bool {
+    serde_yaml::from_str::<serde_yaml::Value>(yaml).is_ok_and(|value| value.get("start_date").is_some())
+}
+
+/// The account an expense category is paid into
+fn category_account(category: &str) -> String {
+    format!("{category}_expenditure")
+}
+
+/// Each distinct expense category with its account, sorted by name
+fn categories(generators: &[GeneratorEntry]) -> Vec<(String, String)> {
+    let mut categories: Vec<(String, String)> = generators
+        .iter()
+        .filter_map(|entry| match &entry.generator {
+            Generator::Expense { category, .. }
+            | Generator::Energy { category, .. }
+            | Generator::SeasonalExpense { category, .. }
+            | Generator::RandomExpense { category, .. }
+            | Generator::PhasedExpense { category, .. } => Some((category.clone(), category_account(category))),
+            _ => None,
+        })
+        .collect();
+    categories.sort();
+    categories.dedup();
+    categories
+}
+
+/// Annualised cost of every subscription, most expensive first, with how much
+/// better off the paying account would be at the end of the forecast if it
+/// were cancelled
+fn subscription_audit(
+    config: &Config,
+    balances: &balances::Balances,
+    days_to_run: i32,
+    history: &[(chrono::NaiveDate, balances::Balances)],
+) -> Result<Vec<report::SubscriptionCost>, String> {
+    let Some((_, baseline)) = history.last() else {
+        return Ok(Vec::new());
+    };
+    let mut costs: Vec<report::SubscriptionCost> = config
+        .generators
+        .iter()
+        .enumerate()
+        .filter_map(|(index, entry)| match &entry.generator {
+            Generator::Expense { amount, schedule, category, from, subscription, .. } if subscription.unwrap_or(category == "subscriptions") => {
+                let mut without = config.clone();
+                without.generators.remove(index);
+                let cancelled = match run(&without, balances.clone(), days_to_run) {
+                    Ok(cancelled) => cancelled,
+                    Err(e) => return Some(Err(e)),
+                };
+                let final_balance = cancelled.last().map(|(_, balances)| balances[from]).unwrap_or(baseline[from]);
+                Some(Ok(report::SubscriptionCost {
+                    name: entry.name.clone().unwrap_or_else(|| format!("{category} {amount}")),
+                    annual_cost: (*amount * schedule.periods_per_year()).round_dp(2),
+                    cancel_impact: final_balance - baseline[from],
+                }))
+            }
+            _ => None,
+        })
+        .collect::<Result<_, String>>()?;
+    costs.sort_by(|a, b| b.annual_cost.cmp(&a.annual_cost).then_with(|| a.name.cmp(&b.name)));
+    Ok(costs)
+}
+
+/// Run the forecast as configured and then with each giving strategy in
+/// place of its tithes, totalling what was given and the tax relief on it
+fn giving_comparison(
+    config: &Config,
+    comparison: &TitheComparison,
+    balances: &balances::Balances,
+    days_to_run: i32,
+) -> Result<Vec<report::GivingOutcome>, String> {
+    let mut scenarios = vec![("current".to_string(), config.clone())];
+    for strategy in &comparison.strategies {
+        let mut scenario = config.clone();
+        scenario.generators.retain(|entry| !matches!(entry.generator, Generator::Tithe { .. }));
+        scenario.generators.extend(strategy.generators.iter().cloned());
+        scenarios.push((strategy.name.clone(), scenario));
+    }
+    scenarios
+        .into_iter()
+        .map(|(name, scenario)| {
+            let history = run(&scenario, balances.clone(), days_to_run)?;
+            let (_, last) = history.last().expect("Forecast has no days");
+            let given = last[&comparison.account] - balances[&comparison.account];
+            let gift_aid = if comparison.gift_aid { giving::gift_aid(given) } else { Decimal::ZERO };
+            let donor_relief = if comparison.gift_aid && comparison.higher_rate_taxpayer {
+                giving::higher_rate_relief(given)
+            } else {
+                Decimal::ZERO
+            };
+            Ok(report::GivingOutcome {
+                name,
+                given,
+                gift_aid,
+                donor_relief,
+                final_balance: last[&config.role(MAIN_ACCOUNT)],
+                payoff_dates: report::payoff_dates(&history, balances, &[OPENING_BALANCES]),
+            })
+        })
+        .collect()
+}
+
+/// The label of each envelope generator, with its envelope's account
+fn envelopes(config: &Config) -> Vec<(String, String)> {
+    config
+        .generators
+        .iter()
+        .enumerate()
+        .filter_map(|(index, entry)| match &entry.generator {
+            Generator::Envelope { to, .. } => Some((entry.label(index), to.clone())),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Each account giving is paid into, labelled by recipient name, or by
+/// generator name or account for a tithe's own account
+fn giving_accounts(config: &Config) -> Vec<(String, String)> {
+    let mut accounts: Vec<(String, String)> = Vec::new();
+    let mut add = |name: String, account: &String| {
+        if !accounts.iter().any(|(_, existing)| existing == account) {
+            accounts.push((name, account.clone()));
+        }
+    };
+    for entry in &config.generators {
+        if let Generator::Tithe { to, recipients, .. } = &entry.generator {
+            for recipient in recipients {
+                add(recipient.name.clone(), &recipient.account);
+            }
+            add(entry.name.clone().unwrap_or_else(|| to.clone()), to);
+        }
+    }
+    accounts
+}
+
+/// Every configured account with its balance at the start and end of the
+/// forecast and any details given for it, sorted by name
+fn account_inventory(
+    config: &Config,
+    balances: &balances::Balances,
+    history: &[(chrono::NaiveDate, balances::Balances)],
+) -> Vec<report::AccountSummary> {
+    // Listed as the other outputs list them, with accounts only described in details last
+    let order = account_order(config, balances);
+    let mut names: Vec<(usize, &String)> = config
+        .accounts
+        .keys()
+        .chain(config.account_details.keys())
+        .map(|name| (order.iter().position(|account| account == name).unwrap_or(order.len()), name))
+        .collect();
+    names.sort();
+    names.dedup();
+    let closing = history.last().map(|(_, balances)| balances).unwrap_or(balances);
+    names
+        .into_iter()
+        .map(|(_, name)| report::AccountSummary {
+            name: name.clone(),
+            opening: balances.get(name).copied().unwrap_or_default(),
+            closing: closing.get(name).copied().unwrap_or_default(),
+            details: config.account_details.get(name).cloned().unwrap_or_default(),
+        })
+        .collect()
+}
+
+/// The rates and modelling choices a forecast rests on, for the output header
+fn assumptions(config: &Config) -> Vec<String> {
+    let mut assumptions = Vec::new();
+    if !config.actuals.is_empty() {
+        let files: Vec<&str> = config.actuals.iter().map(|statement| statement.file.as_str()).collect();
+        assumptions.push(format!("actual balances up to {} from {}", config.start_date, files.join(", ")));
+    }
+    if config.currencies().mixed() {
+        let mut rates: Vec<&currency::ExchangeRate> = config.exchange_rates.iter().collect();
+        rates.sort_by(|a, b| (&a.currency, a.from).cmp(&(&b.currency, b.from)));
+        let rates: Vec<String> = rates.iter().map(|rate| format!("{} at {} from {}", rate.currency, rate.rate, rate.from)).collect();
+        assumptions.push(format!("exchange rates in {}: {}", config.base_currency, rates.join(", ")));
+    }
+    if !config.overrides.is_empty() {
+        let overrides: Vec<String> = config.overrides.iter().map(|pinned| format!("{} on {}", pinned.generator, pinned.date)).collect();
+        assumptions.push(format!("actual amounts for {}", overrides.join(", ")));
+    }
+    let mut reference_rates: Vec<_> = config.reference_rates.iter().collect();
+    reference_rates.sort_by_key(|(name, _)| *name);
+    for (name, schedule) in reference_rates {
+        let mut schedule = schedule.clone();
+        schedule.sort_by_key(|period| period.from);
+        let periods: Vec<String> = schedule.iter().map(|period| format!("{}% from {}", period.rate, period.from)).collect();
+        assumptions.push(format!("{name} at {}", periods.join(", ")));
+    }
+    for entry in &config.generators {
+        match &entry.generator {
+            Generator::Interest { rate, rate_schedule, base, account, .. } => {
+                let mut text = format!("interest on {account} at {rate}%");
+                if let Some(base) = base {
+                    text.push_str(&format!(" over {base}"));
+                }
+                for period in rate_schedule {
+                    text.push_str(&format!(", {}% from {}", period.rate, period.from));
+                }
+                assumptions.push(text);
+            }
+            Generator::SavingsInterest { tiers, account, .. } => {
+                let tiers: Vec<String> = tiers
+                    .iter()
+                    .map(|tier| {
+                        let rate = match &tier.base {
+                            Some(base) => format!("{}% over {base}", tier.rate),
+                            None => format!("{}%", tier.rate),
+                        };
+                        match tier.upto {
+                            Some(upto) => format!("{rate} up to {upto}"),
+                            None => format!("{rate} above"),
+                        }
+                    })
+                    .collect();
+                assumptions.push(format!("savings interest on {account} at {}", tiers.join(", ")));
+            }
+            Generator::RegularSaver { account, rate, start, term_months, .. } => {
+                assumptions.push(format!("regular saver {account} at {rate}% from {start}, maturing {}", saye_maturity(*start, *term_months)));
+            }
+            Generator::Salary { to, annual_increase_percent, increase_month, .. } if !annual_increase_percent.is_zero() => {
+                assumptions.push(format!("salary into {to} rising {annual_increase_percent}% each {}", increase_month.name()));
+            }
+            Generator::CouncilTax { annual_increase_percent, .. } if !annual_increase_percent.is_zero() => {
+                assumptions.push(format!("council tax rising {annual_increase_percent}% each April"));
+            }
+            Generator::VolatileAsset { account, mean_return, volatility, drawdowns, .. } => {
+                let mut text = format!("{account} returning {mean_return}% a year, volatility {volatility}%");
+                for drawdown in drawdowns {
+                    text.push_str(&format!(", falling {}% on {}", drawdown.percent, drawdown.date));
+                }
+                assumptions.push(text);
+            }
+            Generator::Loan { account, rate, .. } => assumptions.push(format!("loan {account} at {rate}%")),
+            Generator::CreditCard { account, rate, .. } => assumptions.push(format!("card {account} at {rate}%")),
+            Generator::Remortgage { date, account, rate, payment, .. } => {
+                let mut text = format!("{account} remortgaged on {date}");
+                if let Some(rate) = rate {
+                    text.push_str(&format!(" at {rate}%"));
+                }
+                if let Some(payment) = payment {
+                    text.push_str(&format!(", paying {payment}"));
+                }
+                assumptions.push(text);
+            }
+            _ => {}
+        }
+    }
+    if !config.inflation_rate.is_zero() {
+        assumptions.push(format!("inflation at {}%", config.inflation_rate));
+    }
+    let mut indices: Vec<_> = config.inflation_indices.iter().collect();
+    indices.sort();
+    for (index, rate) in indices {
+        assumptions.push(format!("{index} inflation at {rate}%"));
+    }
+    assumptions.push(match config.seed {
+        Some(seed) => format!("uncertain income sampled with seed {seed}"),
+        None => "uncertain income at expected values".to_string(),
+    });
+    assumptions
+}
+
+/// What each child's university costs come to and the monthly saving from
+/// the start of the forecast that would cover them
+fn university_plans(config: &Config) -> Vec<report::UniversityPlan> {
+    config
+        .children
+        .iter()
+        .filter_map(|child| {
+            let payments = children::university_payments(child, config.start_date, config.currencies().rounding(&child.from));
+            let (first_payment, _) = *payments.first()?;
+            Some(report::UniversityPlan {
+                child: child.name.clone(),
+                first_payment,
+                total_cost: payments.iter().map(|(_, amount)| amount).sum(),
+                monthly_saving: children::monthly_saving_needed(&payments, config.start_date),
+            })
+        })
+        .collect()
+}
+
+/// For each salary giving through payroll, what that giving costs against
+/// giving the charity the same amount with Gift Aid, in the first year
+fn payroll_giving_comparison(config: &Config) -> Vec<report::PayrollGivingCost> {
+    config
+        .generators
+        .iter()
+        .filter_map(|entry| match &entry.generator {
+            Generator::Salary { gross_annual: Some(gross), payroll_giving_percent, to, .. } if !payroll_giving_percent.is_zero() => {
+                let to_charity = (*gross * *payroll_giving_percent / dec!(100)).round_dp(2);
+                let jurisdiction = config.tax.jurisdiction();
+                Some(report::PayrollGivingCost {
+                    salary: entry.name.clone().unwrap_or_else(|| to.clone()),
+                    to_charity,
+                    payroll: to_charity - jurisdiction.payroll_giving_relief(config.start_date, *gross, to_charity).round_dp(2),
+                    gift_aid: to_charity - giving::gift_aid(to_charity * dec!(0.8))
+                        - jurisdiction.gift_aid_relief(config.start_date, *gross, to_charity).round_dp(2),
+                })
+            }
+            _ => None,
+        })
+        .collect()
+}
+
+/// The config without the generators named in `names`, ignoring case, or
+/// the names that don't match any generator
+fn without_generators(config: &Config, names: &[String]) -> Result<Config, Vec<String>> {
+    let named = |entry: &GeneratorEntry, name: &String| entry.name.as_ref().is_some_and(|n| n.eq_ignore_ascii_case(name));
+    let missing: Vec<String> = names
+        .iter()
+        .filter(|name| !config.generators.iter().any(|entry| named(entry, name)))
+        .cloned()
+        .collect();
+    if !missing.is_empty() {
+        return Err(missing);
+    }
+    let mut without = config.clone();
+    without.generators.retain(|entry| !names.iter().any(|name| named(entry, name)));
+    Ok(without)
+}
+
+/// `config` with the figure `solve` and `sensitivity` adjust in the generator
+/// called `name` set to `value`: the amount paid, the rate of an interest
+/// generator, or the percentage of a percentage transfer
+fn with_parameter(config: &Config, name: &str, value: Decimal) -> Result<Config, String> {
+    let mut adjusted = config.clone();
+    let entry = adjusted
+        .generators
+        .iter_mut()
+        .find(|entry| entry.name.as_ref().is_some_and(|n| n.eq_ignore_ascii_case(name)))
+        .ok_or_else(|| format!("No generator named: {name}"))?;
+    match &mut entry.generator {
+        Generator::Transfer { percentage: Some(percentage), .. } => *percentage = value,
+        Generator::Transfer { amount, .. }
+        | Generator::Rent { amount, .. }
+        | Generator::Dividend { amount, .. }
+        | Generator::Expense { amount, .. }
+        | Generator::Envelope { amount, .. }
+        | Generator::Investment { amount, .. }
+        | Generator::Saye { amount, .. }
+        | Generator::VolatileAsset { amount, .. } => *amount = value,
+        Generator::Overpayment { amount, sweep_above, .. } => {
+            *amount = Some(value);
+            *sweep_above = None;
+        }
+        Generator::Interest { rate, rate_schedule, .. } => {
+            *rate = value;
+            rate_schedule.clear();
+        }
+        _ => return Err(format!("Generator {name} has no amount or rate to solve for")),
+    }
+    Ok(adjusted)
+}
+
+/// A value of the figure adjusted in the generator called `name`, as a
+/// percentage for an interest rate or percentage transfer and otherwise as an amount
+fn show_parameter(config: &Config, name: &str, value: Decimal) -> String {
+    let rate = config.generators.iter().any(|entry| {
+        entry.name.as_ref().is_some_and(|n| n.eq_ignore_ascii_case(name))
+            && matches!(entry.generator, Generator::Interest { .. } | Generator::Transfer { percentage: Some(_), .. })
+    });
+    if rate { format!("{value:.2}%") } else { format!("{}{value:.2}", config.currency_symbol) }
+}
+
+/// Each value from `from` up to `to` in steps of `step`
+fn steps(from: Decimal, to: Decimal, step: Decimal) -> Result<Vec<Decimal>, String> {
+    if step <= Decimal::ZERO || from > to {
+        return Err(format!("Can't step from {from} to {to} by {step}"));
+    }
+    Ok(std::iter::successors(Some(from), |value| Some(value + step)).take_while(|value| *value <= to).collect())
+}
+
+/// Lowest balance `account` has, from its opening balance to the end of `history`
+fn lowest_balance(balances: &balances::Balances, history: &History, account: &str) -> Decimal {
+    history
+        .iter()
+        .filter_map(|(_, balances)| balances.get(account).copied())
+        .chain(balances.get(account).copied())
+        .min()
+        .unwrap_or_default()
+}
+
+/// Key figures with the generator called `name` set to each value from
+/// `from` up to `to` in steps of `step`, and the lowest main balance on the way
+fn sensitivity(
+    config: &Config,
+    balances: &balances::Balances,
+    days_to_run: i32,
+    name: &str,
+    (from, to, step): (Decimal, Decimal, Decimal),
+) -> Result<Vec<report::SensitivityRow>, String> {
+    let values = steps(from, to, step)?;
+    with_parameter(config, name, from)?;
+    let main = config.role(MAIN_ACCOUNT);
+    let baseline = Baseline::new(config, balances.clone(), days_to_run)?;
+    parallel::map(&values, |&value| {
+        let adjusted = with_parameter(config, name, value).expect("Generator checked above");
+        let history = baseline.rerun(&adjusted)?;
+        let lowest_main = lowest_balance(balances, &history, &main);
+        Ok(report::SensitivityRow { value, lowest_main, metrics: key_metrics(&adjusted, balances, &history) })
+    })
+    .into_iter()
+    .collect()
+}
+
+/// `metric` for every combination of the values in `vary`, the first
+/// generator's values down the rows and any second's across the columns
+fn matrix(
+    config: &Config,
+    balances: &balances::Balances,
+    days_to_run: i32,
+    vary: &[Vary],
+    metric: MatrixMetric,
+    account: Option<&str>,
+) -> Result<report::Matrix, String> {
+    let (rows, columns) = match vary {
+        [rows] => (rows, None),
+        [rows, columns] => (rows, Some(columns)),
+        _ => return Err("A matrix varies one or two generators".to_string()),
+    };
+    let row_values = steps(rows.from, rows.to, rows.step)?;
+    let column_values = match columns {
+        Some(columns) => steps(columns.from, columns.to, columns.step)?,
+        None => vec![Decimal::ZERO],
+    };
+    let main = config.role(MAIN_ACCOUNT);
+    let liabilities = liability_accounts(config, balances);
+    let payoff = match (metric, account) {
+        (MatrixMetric::Payoff, Some(account)) if !liabilities.iter().any(|liability| liability == account) => {
+            return Err(format!("{account} is not a liability"));
+        }
+        (MatrixMetric::Payoff, account) => {
+            Some(account.map(str::to_string).or_else(|| liabilities.first().cloned()).ok_or("There are no liabilities to pay off")?)
+        }
+        _ => None,
+    };
+    let title = match (metric, &payoff) {
+        (MatrixMetric::LowestCash, _) => format!("Lowest {main}"),
+        (MatrixMetric::CashAtHorizon, _) => format!("{main} at horizon"),
+        (MatrixMetric::NetWorth, _) => "Net worth at horizon".to_string(),
+        (MatrixMetric::Payoff, payoff) => format!("{} paid off", payoff.as_deref().unwrap_or_default()),
+    };
+
+    let adjusted = |row: Decimal, column: Decimal| -> Result<Config, String> {
+        let config = with_parameter(config, &rows.generator, row)?;
+        match columns {
+            Some(columns) => with_parameter(&config, &columns.generator, column),
+            None => Ok(config),
+        }
+    };
+    adjusted(row_values[0], column_values[0])?;
+    let baseline = Baseline::new(config, balances.clone(), days_to_run)?;
+    let combinations: Vec<(Decimal, Decimal)> = row_values.iter().flat_map(|row| column_values.iter().map(|column| (*row, *column))).collect();
+    let results = parallel::map(&combinations, |&(row, column)| {
+        let varied = adjusted(row, column).expect("Generators checked above");
+        let history = baseline.rerun(&varied)?;
+        Ok(match metric {
+            MatrixMetric::LowestCash => report::Metric::Amount(lowest_balance(balances, &history, &main)),
+            _ => key_metrics(&varied, balances, &history)
+                .into_iter()
+                .find(|(name, _)| *name == title)
+                .map_or(report::Metric::Date(None), |(_, metric)| metric),
+        })
+    })
+    .into_iter()
+    .collect::<Result<Vec<_>, String>>()?;
+    let cells = results.chunks(column_values.len()).map(<[report::Metric]>::to_vec).collect();
+    let show = |name: &str, values: &[Decimal]| -> Vec<String> { values.iter().map(|value| show_parameter(config, name, *value)).collect() };
+    Ok(report::Matrix {
+        metric: title.clone(),
+        rows: (rows.generator.clone(), show(&rows.generator, &row_values)),
+        columns: match columns {
+            Some(columns) => (columns.generator.clone(), show(&columns.generator, &column_values)),
+            None => (String::new(), vec![title]),
+        },
+        cells,
+    })
+}
+
+/// Smallest value for the generator called `name`, up to `max`, that meets
+/// `goal`, running the forecast only as far as the goal's date
+fn solve_for(
+    config: &Config,
+    balances: &balances::Balances,
+    name: &str,
+    goal: &goals::Goal,
+    max: Decimal,
+) -> Result<Option<Decimal>, String> {
+    if !balances.contains_key(&goal.account) {
+        return Err(format!("No account named: {}", goal.account));
+    }
+    let days = (goal.by - config.start_date).num_days();
+    if days <= 0 {
+        return Err(format!("{} is not after the start date of {}", goal.by, config.start_date));
+    }
+    check_horizon(days)?;
+    with_parameter(config, name, Decimal::ZERO)?;
+    let baseline = Baseline::new(config, balances.clone(), days as i32)?;
+    // A value too large to forecast with is taken to miss the goal, and its error kept
+    let failed = std::sync::Mutex::new(None);
+    let found = solve::search(Decimal::ZERO, max, parallel::threads(), |value| {
+        let adjusted = with_parameter(config, name, value).expect("Generator checked above");
+        match baseline.rerun(&adjusted) {
+            Ok(history) => goals::progress(&history, goal).met(),
+            Err(e) => {
+                failed.lock().unwrap().get_or_insert(e);
+                false
+            }
+        }
+    });
+    match failed.into_inner().unwrap() {
+        Some(e) if found.is_none() => Err(e),
+        _ => Ok(found),
+    }
+}
+
+/// An account's spread of balances on each day reported on
+type AccountBands = (String, Vec<(chrono::NaiveDate, monte_carlo::Bands)>);
+
+/// The spread of each configured account's balance on the first of every
+/// month over `runs` runs of `days_to_run` days, each with its own seed
+fn simulate(
+    config: &Config,
+    balances: &balances::Balances,
+    days_to_run: i32,
+    runs: u64,
+) -> Result<Vec<AccountBands>, String> {
+    let first_seed = config.seed.unwrap_or(0);
+    let widened = widen_low_confidence(config);
+    let offsets: Vec<u64> = (0..runs).collect();
+    // Each run's seed is fixed by its place in the order, so the runs can be shared across threads
+    let histories: Vec<History> = parallel::map(&offsets, |offset| {
+        let mut sampled = widened.clone();
+        sampled.seed = Some(first_seed + offset);
+        // Keep only the days reported on, so many long runs fit in memory
+        Ok(run(&sampled, balances.clone(), days_to_run)?.into_iter().filter(|(date, _)| date.day() == 1).collect())
+    })
+    .into_iter()
+    .collect::<Result<_, String>>()?;
+    let dates: Vec<chrono::NaiveDate> = histories.first().map(|history| history.iter().map(|(date, _)| *date).collect()).unwrap_or_default();
+    Ok(account_order(config, balances)
+        .into_iter()
+        .filter(|account| config.accounts.contains_key(account))
+        .map(|account| {
+            let bands = monte_carlo::bands(&histories, &account, &dates);
+            (account, bands)
+        })
+        .collect())
+}
+
+/// `config` with the spread of every low-confidence generator's sampled
+/// figures widened, so Monte Carlo runs reflect how unsure they are
+fn widen_low_confidence(config: &Config) -> Config {
+    let mut widened = config.clone();
+    for entry in widened.generators.iter_mut().filter(|entry| entry.confidence == Some(monte_carlo::Confidence::Low)) {
+        match &mut entry.generator {
+            Generator::Expense { variation, .. } => *variation = monte_carlo::widen(*variation, dec!(20)),
+            Generator::Interest { rate_volatility, .. } => *rate_volatility = monte_carlo::widen(*rate_volatility, Decimal::ONE),
+            Generator::Investment { return_volatility, .. } => *return_volatility = monte_carlo::widen(*return_volatility, dec!(10)),
+            Generator::VolatileAsset { volatility, .. } => *volatility = monte_carlo::widen(*volatility, dec!(20)),
+            _ => {}
+        }
+    }
+    widened
+}
+
+/// Every generator tagged with a confidence, least sure first, with the
+/// spread Monte Carlo samples its figures with
+fn assumption_register(config: &Config) -> Vec<report::Assumption> {
+    let widened = widen_low_confidence(config);
+    let mut register: Vec<report::Assumption> = widened
+        .generators
+        .iter()
+        .enumerate()
+        .filter_map(|(index, entry)| {
+            let spread = match &entry.generator {
+                Generator::Expense { variation, .. } if !variation.is_zero() => Some(format!("amount ±{variation}%")),
+                Generator::Interest { rate_volatility, .. } if !rate_volatility.is_zero() => Some(format!("rate ±{rate_volatility} points")),
+                Generator::Investment { return_volatility, .. } if !return_volatility.is_zero() => {
+                    Some(format!("return ±{return_volatility} points"))
+                }
+                Generator::VolatileAsset { volatility, .. } if !volatility.is_zero() => Some(format!("return ±{volatility} points")),
+                Generator::RandomExpense { min, max, times_per_year, .. } => Some(format!("{times_per_year} times a year at {min} to {max}")),
+                _ => None,
+            };
+            Some(report::Assumption {
+                confidence: entry.confidence?,
+                generator: entry.title(index),
+                spread,
+            })
+        })
+        .collect();
+    register.sort_by_key(|assumption| assumption.confidence);
+    register
+}
+
+/// Headline figures for comparing forecasts: the main account and net worth
+/// of the configured accounts at the horizon, and when each liability is paid off
+fn key_metrics(
+    config: &Config,
+    balances: &balances::Balances,
+    history: &[(chrono::NaiveDate, balances::Balances)],
+) -> Vec<(String, report::Metric)> {
+    let (date, last) = history.last().map_or((config.start_date, balances), |(date, balances)| (*date, balances));
+    let main = config.role(MAIN_ACCOUNT);
+    let currencies = config.currencies();
+    let net_worth = config
+        .accounts
+        .keys()
+        .filter(|account| *account != OPENING_BALANCES)
+        .filter_map(|account| last.get(account).map(|balance| currencies.in_base(account, *balance, date)))
+        .sum();
+    let mut metrics = vec![
+        (format!("{main} at horizon"), report::Metric::Amount(last.get(&main).copied().unwrap_or_default())),
+        ("Net worth at horizon".to_string(), report::Metric::Amount(net_worth)),
+    ];
+    let payoff_dates = report::payoff_dates(history, balances, &[OPENING_BALANCES]);
+    for account in liability_accounts(config, balances) {
+        let paid_off = payoff_dates.iter().find(|(name, _)| *name == account).and_then(|(_, date)| *date);
+        metrics.push((format!("{account} paid off"), report::Metric::Date(paid_off)));
+    }
+    metrics
+}
+
+/// Accounts that start in debt and are paid down by a mortgage or
+/// overpayment or have interest charged on them, sorted by name
+fn liability_accounts(config: &Config, balances: &balances::Balances) -> Vec<String> {
+    let mut accounts: Vec<String> = config
+        .generators
+        .iter()
+        .filter_map(|entry| match &entry.generator {
+            Generator::Mortgage { to, .. } | Generator::Overpayment { to, .. } => Some(to.clone()),
+            Generator::Interest { account, .. } => Some(account.clone()),
+            _ => None,
+        })
+        .filter(|account| balances.get(account).is_some_and(|balance| *balance < Decimal::ZERO))
+        .collect();
+    accounts.sort();
+    accounts.dedup();
+    accounts
+}
+
+/// Pay, tax, interest and giving in `postings`, totalled by the year
+/// `year_of` names for each date
+fn year_totals(config: &Config, postings: &[journal::Posting], year_of: impl Fn(chrono::NaiveDate) -> String) -> Vec<report::YearTotals> {
+    let generators: std::collections::HashMap<String, &Generator> =
+        config.generators.iter().enumerate().map(|(index, entry)| (entry.label(index), &entry.generator)).collect();
+    let mut years: std::collections::BTreeMap<String, report::YearTotals> = std::collections::BTreeMap::new();
+    for posting in postings {
+        let year = year_of(posting.date);
+        let totals = years.entry(year.clone()).or_insert_with(|| report::YearTotals { year, ..Default::default() });
+        let amount = posting.amount;
+        match (generators.get(&posting.generator), posting.description.as_str()) {
+            (Some(Generator::Salary { .. }), description) => {
+                totals.gross_salary += amount;
+                match description {
+                    "income tax" => totals.income_tax += amount,
+                    "national insurance" => totals.national_insurance += amount,
+                    "payroll giving" => totals.payroll_giving += amount,
+                    "net pay" => totals.net_salary += amount,
+                    _ => {}
+                }
+            }
+            // Charged on a debt as a negative amount, and paid on savings as a positive one
+            (Some(Generator::Interest { .. }), _) if amount < Decimal::ZERO => totals.interest_paid -= amount,
+            (Some(Generator::Interest { .. } | Generator::SavingsInterest { .. }), _) | (Some(Generator::RegularSaver { .. }), "interest") => {
+                totals.interest_earned += amount
+            }
+            (Some(Generator::Loan { .. } | Generator::CreditCard { .. }), "interest") | (None, "overdraft interest") => {
+                totals.interest_paid += amount
+            }
+            (Some(Generator::Tithe { .. }), "gift aid") => totals.gift_aid += amount,
+            (Some(Generator::Tithe { .. }), _) => totals.given += amount,
+            _ => {}
+        }
+    }
+    years.into_values().collect()
+}
+
+/// A tax year named by the calendar year it starts in, as its span, e.g. "2025/26"
+fn tax_year_label(year: i32) -> String {
+    format!("{year}/{:02}", (year + 1) % 100)
+}
+
+/// Interest earned in each tax year against the Personal Savings Allowance,
+/// for a config with savings interest. The allowance is set by a savings
+/// interest generator's `other_income`, or the gross salaries when the
+/// year's first interest is paid.
+fn savings_allowance_use(config: &Config, postings: &[journal::Posting]) -> Result<Vec<report::SavingsAllowanceUse>, String> {
+    let savings: Vec<&Option<Decimal>> = config
+        .generators
+        .iter()
+        .filter_map(|entry| match &entry.generator {
+            Generator::SavingsInterest { other_income, .. } => Some(other_income),
+            _ => None,
+        })
+        .collect();
+    if savings.is_empty() {
+        return Ok(Vec::new());
+    }
+    let earning: Vec<String> = config
+        .generators
+        .iter()
+        .enumerate()
+        .filter(|(_, entry)| {
+            matches!(entry.generator, Generator::Interest { .. } | Generator::SavingsInterest { .. } | Generator::RegularSaver { .. })
+        })
+        .map(|(index, entry)| entry.label(index))
+        .collect();
+    let jurisdiction = config.tax.jurisdiction();
+    // The first day interest is paid in each tax year, and the year's total
+    let mut years: std::collections::BTreeMap<i32, (chrono::NaiveDate, Decimal)> = std::collections::BTreeMap::new();
+    let earned = |posting: &&journal::Posting| posting.description == "interest" && posting.amount > Decimal::ZERO && earning.contains(&posting.generator);
+    for posting in postings.iter().filter(earned) {
+        years.entry(jurisdiction.tax_year(posting.date)).or_insert((posting.date, Decimal::ZERO)).1 += posting.amount;
+    }
+    let other_income = savings.into_iter().find_map(|other_income| *other_income);
+    years
+        .into_iter()
+        .map(|(year, (date, interest))| {
+            let income = other_income.map_or_else(|| gross_salaries(config, date), Ok)?;
+            Ok(report::SavingsAllowanceUse { year: tax_year_label(year), interest, allowance: jurisdiction.savings_allowance(date, income) })
+        })
+        .collect()
+}
+
+/// Every account a generator posts to or keeps its balance in that isn't
+/// among `balances`, and every inflation index it follows that isn't in the
+/// config, with the generator's index and the field naming it
+fn missing_references(config: &Config, balances: &balances::Balances) -> Vec<(usize, &'static str, String)> {
+    let mut missing = Vec::new();
+    for (index, entry) in config.generators.iter().enumerate() {
+        let label = entry.title(index);
+        let unused = entry.generator.unused_accounts();
+        for (account, field, _) in entry.generator.wiring() {
+            if !balances.contains_key(account) && !unused.contains(&field) {
+                missing.push((index, field, format!("{label}: {field} is {account}, which is not an account")));
+            }
+        }
+        if let Some(account) = &entry.balance_account
+            && !balances.contains_key(account)
+        {
+            missing.push((index, "balance_account", format!("{label}: balance_account is {account}, which is not an account")));
+        }
+        let price_rise = match &entry.generator {
+            Generator::Expense { price_rise: Some(rise), .. } => Some(&rise.index),
+            _ => None,
+        };
+        for (field, index_name) in [("index", entry.index.as_ref()), ("price_rise", price_rise)] {
+            if let Some(index_name) = index_name
+                && !config.inflation_indices.contains_key(index_name)
+            {
+                missing.push((index, field, format!("{label}: {field} follows {index_name}, which is not one of the inflation_indices")));
+            }
+        }
+    }
+    missing
+}
+
+/// Every way a generator uses an account whose declared type doesn't suit
+/// it, and every declared account that doesn't exist
+fn check_account_types(config: &Config, balances: &balances::Balances) -> Result<(), Vec<String>> {
+    let mut problems: Vec<String> = config
+        .account_types
+        .keys()
+        .filter(|account| !balances.contains_key(account))
+        .map(|account| format!("account_types names no account called {account}"))
+        .collect();
+    problems.sort();
+    for (index, entry) in config.generators.iter().enumerate() {
+        for (account, field, expected) in entry.generator.wiring() {
+            if let Some(kind) = config.account_types.get(account)
+                && !expected.contains(kind)
+            {
+                let expected: Vec<&str> = expected.iter().map(|kind| kind.singular()).collect();
+                problems.push(format!(
+                    "{}: {field} is {account}, declared as {}, but must be {}",
+                    entry.title(index),
+                    kind.singular(),
+                    expected.join(" or ")
+                ));
+            }
+        }
+    }
+    if problems.is_empty() { Ok(()) } else { Err(problems) }
+}
+
+/// Every currency an account is held in that no exchange rate is given for
+fn check_currencies(config: &Config) -> Result<(), Vec<String>> {
+    let problems: Vec<String> = config
+        .currencies()
+        .missing_rates()
+        .into_iter()
+        .map(|(currency, accounts)| format!("exchange_rates gives no rate for {currency}, the currency of {}", accounts.join(", ")))
+        .collect();
+    if problems.is_empty() { Ok(()) } else { Err(problems) }
+}
+
+/// Everything wrong with the config text, each at its line where that can be
+/// found: repeated accounts, fields that aren't read or can't be, accounts no
+/// generator can post to, impossible days, negative percentages and named
+/// generators given twice over the same dates
+fn validate_config(yaml: &str) -> Vec<validate::Problem> {
+    use validate::Problem;
+    let duplicates = validate::duplicate_keys(yaml, "accounts");
+    let mut problems: Vec<Problem> =
+        duplicates.iter().map(|(line, name)| Problem::new(Some(*line), format!("account {name} is listed more than once"))).collect();
+    // A repeated key stops the YAML being read at all, so the repeats are
+    // blanked out, keeping the line numbers, to check the rest
+    let text: String = yaml
+        .lines()
+        .enumerate()
+        .map(|(i, line)| if duplicates.iter().any(|(repeat, _)| *repeat == i + 1) { "\n".to_string() } else { format!("{line}\n") })
+        .collect();
+    let yaml = text.as_str();
+    let mut value: serde_yaml::Value = match serde_yaml::from_str(yaml) {
+        Ok(value) => value,
+        Err(e) => {
+            problems.push(Problem::new(None, e.to_string()));
+            return problems;
+        }
+    };
+    // Working out the variables only fills in values, so every line stays where it is
+    if let Err(e) = variables::resolve(&mut value) {
+        problems.push(Problem::new(validate::section_line(yaml, "variables"), e));
+        return problems;
+    }
+    let Some(mapping) = value.as_mapping() else {
+        problems.push(Problem::new(None, "the config must be a mapping of settings"));
+        return problems;
+    };
+
+    // Each generator is read on its own, so a mistake in one doesn't hide the rest
+    let lines = validate::item_lines(yaml, "generators");
+    let field_line = |index: usize, keys: &[&str]| {
+        let from = *lines.get(index)?;
+        let next = lines.get(index + 1).copied();
+        keys.iter()
+            .filter_map(|key| validate::key_line(yaml, from, key))
+            .filter(|line| next.is_none_or(|next| *line < next))
+            .min()
+            .or(Some(from))
+    };
+    let generators = value.get("generators").and_then(serde_yaml::Value::as_sequence).cloned().unwrap_or_default();
+    let mut generators_read = true;
+    for (index, item) in generators.iter().enumerate() {
+        let label = match (item.get("name").and_then(serde_yaml::Value::as_str), item.get("type").and_then(serde_yaml::Value::as_str)) {
+            (Some(name), _) => name.to_string(),
+            (None, kind) => format!("{} (generator {})", kind.unwrap_or("generator"), index + 1),
+        };
+        if let Err(e) = serde_yaml::from_value::<GeneratorEntry>(item.clone()) {
+            generators_read = false;
+            problems.push(Problem::new(field_line(index, &[]), format!("{label}: {e}")));
+        }
+        if let Some(fields) = item.as_mapping() {
+            for key in validate::unknown_keys::<GeneratorEntry>(fields) {
+                problems.push(Problem::new(field_line(index, &[&key]), format!("{label}: unknown field {key}")));
+            }
+        }
+    }
+    let mut without_generators = mapping.clone();
+    without_generators.insert("generators".into(), serde_yaml::Value::Sequence(Vec::new()));
+    for key in validate::unknown_keys::<Config>(&without_generators) {
+        problems.push(Problem::new(validate::section_line(yaml, &key), format!("unknown field {key}")));
+    }
+    let mut config: Config = match serde_yaml::from_value(value.clone()) {
+        Ok(config) => config,
+        Err(e) => {
+            if generators_read {
+                problems.push(Problem::new(None, e.to_string()));
+            } else if let Err(e) = serde_yaml::from_value::<Config>(serde_yaml::Value::Mapping(without_generators)) {
+                problems.push(Problem::new(None, e.to_string()));
+            }
+            return problems;
+        }
+    };
+
+    config.expand_children();
+    config.resolve_roles();
+    // Balances can't be added up without a rate for every currency
+    if let Err(found) = check_currencies(&config) {
+        let line = validate::section_line(yaml, "exchange_rates").or_else(|| validate::section_line(yaml, "account_details"));
+        problems.extend(found.into_iter().map(|problem| Problem::new(line, problem)));
+        return problems;
+    }
+    let balances = all_accounts(&config);
+    let missing = missing_references(&config, &balances);
+    for (index, entry) in config.generators.iter().enumerate() {
+        let label = entry.title(index);
+        for (_, field, problem) in missing.iter().filter(|(at, _, _)| *at == index) {
+            problems.push(Problem::new(field_line(index, &[field]), problem.clone()));
+        }
+        if entry.on_insufficient_funds.is_some() && !entry.generator.wiring().iter().any(|(_, field, _)| *field == "from") {
+            let line = field_line(index, &["on_insufficient_funds"]);
+            problems.push(Problem::new(line, format!("{label}: on_insufficient_funds is set, but it has no from account to pay out of")));
+        }
+        let mut days: Vec<String> = entry.generator.schedule().and_then(Schedule::problem).into_iter().collect();
+        if let Generator::AnnualEvents { events, day, .. } = &entry.generator {
+            days.extend(std::iter::once(day).chain(events.iter().filter_map(|event| event.day.as_ref())).filter_map(DayOfMonth::problem));
+        }
+        if let Generator::CouncilTax { day, instalments, .. } = &entry.generator {
+            days.extend(day.problem());
+            if let Some(problem) = council_tax::instalments_problem(*instalments) {
+                problems.push(Problem::new(field_line(index, &["instalments"]), format!("{label}: {problem}")));
+            }
+        }
+        for problem in days {
+            let line = field_line(index, &["schedule", "day", "deduction_day", "statement_day"]);
+            problems.push(Problem::new(line, format!("{label}: {problem}")));
+        }
+        if let Generator::Transfer { percentage: None, of: Some(_), .. } = &entry.generator {
+            problems.push(Problem::new(field_line(index, &["of"]), format!("{label}: of is given without a percentage of it to move")));
+        }
+        if let Generator::Tithe { of: Some(of), .. } = &entry.generator {
+            for name in of {
+                let known = balances.keys().any(|account| account.eq_ignore_ascii_case(name))
+                    || config.generators.iter().enumerate().any(|(other, entry)| {
+                        entry.label(other).eq_ignore_ascii_case(name) || entry.generator.kind().eq_ignore_ascii_case(name)
+                    });
+                if !known {
+                    let line = field_line(index, &["of"]);
+                    problems.push(Problem::new(line, format!("{label}: of names {name}, which is neither a generator nor an account")));
+                }
+            }
+        }
+        if let Generator::PhasedExpense { phases, birth_date, .. } = &entry.generator {
+            for (number, phase) in phases.iter().enumerate() {
+                if let Some(problem) = phase.problem(*birth_date) {
+                    problems.push(Problem::new(field_line(index, &["phases"]), format!("{label}: phase {} {problem}", number + 1)));
+                }
+            }
+        }
+        if let Generator::SavingsInterest { tiers, .. } = &entry.generator
+            && let Some(problem) = interest::tiers_problem(tiers)
+        {
+            problems.push(Problem::new(field_line(index, &["tiers"]), format!("{label}: {problem}")));
+        }
+        let bases: Vec<(&str, &String)> = match &entry.generator {
+            Generator::Interest { base: Some(base), .. } => vec![("base", base)],
+            Generator::SavingsInterest { tiers, .. } => tiers.iter().filter_map(|tier| Some(("tiers", tier.base.as_ref()?))).collect(),
+            _ => Vec::new(),
+        };
+        for (field, base) in bases.into_iter().filter(|(_, base)| !config.reference_rates.contains_key(*base)) {
+            problems.push(Problem::new(field_line(index, &[field]), format!("{label}: base is {base}, which is not in reference_rates")));
+        }
+        if let Generator::RegularSaver { monthly_cap, amount: Some(amount), .. } = &entry.generator
+            && amount > monthly_cap
+        {
+            let line = field_line(index, &["amount"]);
+            problems.push(Problem::new(line, format!("{label}: amount is {amount}, which is above the monthly_cap of {monthly_cap}")));
+        }
+        if let Generator::RandomExpense { min, max, .. } = &entry.generator
+            && max < min
+        {
+            problems.push(Problem::new(field_line(index, &["max"]), format!("{label}: max is {max}, which is below min of {min}")));
+        }
+        for (field, value) in entry.generator.percentages() {
+            let line = field_line(index, &[field]);
+            if value < Decimal::ZERO {
+                problems.push(Problem::new(line, format!("{label}: {field} is {value}, which is negative")));
+            } else if field.ends_with("probability") && value > Decimal::ONE {
+                problems.push(Problem::new(line, format!("{label}: {field} is {value}, but must be between 0 and 1")));
+            }
+        }
+    }
+    // A generator renamed in a later one, e.g. for a new job, should end before it starts
+    for (index, entry) in config.generators.iter().enumerate() {
+        let Some(name) = &entry.name else { continue };
+        for (other_index, other) in config.generators.iter().enumerate().skip(index + 1) {
+            let from = entry.start_date.max(other.start_date);
+            let until = match (entry.end_date, other.end_date) {
+                (Some(end), Some(other_end)) => Some(end.min(other_end)),
+                (end, other_end) => end.or(other_end),
+            };
+            if other.name.as_ref() == Some(name)
+                && other.generator.kind() == entry.generator.kind()
+                && from.zip(until).is_none_or(|(from, until)| from <= until)
+            {
+                let when = from.map_or("from the start".to_string(), |from| format!("from {from}"));
+                problems.push(Problem::new(
+                    lines.get(other_index).copied(),
+                    format!("{name}: generators {} and {} both run {when}", index + 1, other_index + 1),
+                ));
+            }
+        }
+    }
+    if let Err(found) = check_account_types(&config, &balances) {
+        problems.extend(found.into_iter().map(|problem| Problem::new(None, problem)));
+    }
+    if let Err(e) = horizon_days(&config) {
+        let line = ["end_date", "years", "months"].iter().find_map(|key| validate::section_line(yaml, key));
+        problems.push(Problem::new(line, e));
+    }
+    if let Some(account) = &config.until_paid_off
+        && !balances.contains_key(account)
+    {
+        problems.push(Problem::new(validate::section_line(yaml, "until_paid_off"), format!("until_paid_off is {account}, which is not an account")));
+    }
+    let lines = validate::item_lines(yaml, "overrides");
+    for (index, pinned) in config.overrides.iter().enumerate() {
+        let named = config.generators.iter().any(|entry| entry.name.as_ref().is_some_and(|name| name.eq_ignore_ascii_case(&pinned.generator)));
+        if !named {
+            let message = format!("Override {}: generator is {}, which is not the name of a generator", index + 1, pinned.generator);
+            problems.push(Problem::new(lines.get(index).copied(), message));
+        }
+    }
+    let lines = validate::item_lines(yaml, "checks");
+    for (index, check) in config.checks.iter().enumerate().filter(|(_, check)| !balances.contains_key(&check.account)) {
+        problems.push(Problem::new(lines.get(index).copied(), format!("Check {}: account is {}, which is not an account", index + 1, check.account)));
+    }
+    // A reference rate has to be known from the start, as it counts as zero until then
+    let mut reference_rates: Vec<(&String, Option<chrono::NaiveDate>)> =
+        config.reference_rates.iter().map(|(name, schedule)| (name, schedule.iter().map(|period| period.from).min())).collect();
+    reference_rates.sort();
+    for (name, first) in reference_rates.into_iter().filter(|(_, first)| first.is_none_or(|first| first > config.start_date)) {
+        let line = validate::section_line(yaml, "reference_rates");
+        let problem = match first {
+            Some(first) => format!("Reference rate {name} starts on {first}, after the forecast starts on {}", config.start_date),
+            None => format!("Reference rate {name} has no rates"),
+        };
+        problems.push(Problem::new(line, problem));
+    }
+    let mut backed: Vec<(&String, &String)> =
+        config.account_details.iter().filter_map(|(account, details)| Some((account, details.backed_by.as_ref()?))).collect();
+    backed.sort();
+    for (account, backer) in backed.into_iter().filter(|(_, backer)| !balances.contains_key(backer)) {
+        let line = validate::section_line(yaml, "account_details");
+        problems.push(Problem::new(line, format!("{account}: backed_by is {backer}, which is not an account")));
+    }
+    let cycles = config.backing().cycles();
+    if !cycles.is_empty() {
+        let line = validate::section_line(yaml, "account_details");
+        problems.push(Problem::new(line, format!("Accounts back each other in a loop: {}", cycles.join(", "))));
+    }
+    problems
+}
+
+/// Accounts to chart: those in `plot_accounts`, which must all exist or have
+/// accounts nested under them, or otherwise every asset and liability, sorted
+/// by name, each after the accounts it's nested under
+fn plot_accounts(config: &Config, balances: &balances::Balances) -> Result<Vec<String>, String> {
+    match &config.plot_accounts {
+        Some(accounts) => {
+            let grouped = hierarchy::with_parents(balances);
+            let unknown: Vec<&str> = accounts.iter().filter(|account| !grouped.contains_key(account)).map(String::as_str).collect();
+            if unknown.is_empty() {
+                Ok(accounts.clone())
+            } else {
+                Err(format!("No account to plot called: {}", unknown.join(", ")))
+            }
+        }
+        None => {
+            let names = ledger_account_names(config, balances);
+            let mut plotted: Vec<String> = Vec::new();
+            for account in account_order(config, balances)
+                .into_iter()
+                .filter(|account| names[account].starts_with("Assets:") || names[account].starts_with("Liabilities:"))
+            {
+                for parent in hierarchy::parents(&account).into_iter().chain([account.as_str()]) {
+                    if !plotted.iter().any(|plotted| plotted == parent) {
+                        plotted.push(parent.to_string());
+                    }
+                }
+            }
+            Ok(plotted)
+        }
+    }
+}
+
+/// Postings made by generators that pay out on dates, leaving out those that
+/// accrue from day to day such as interest and revaluations, and overdraft charges
+fn calendar_postings(config: &Config, postings: &[journal::Posting]) -> Vec<journal::Posting> {
+    let paying: Vec<String> = config
+        .generators
+        .iter()
+        .enumerate()
+        .filter(|(_, entry)| {
+            !matches!(
+                entry.generator,
+                Generator::Interest { .. } | Generator::SavingsInterest { .. } | Generator::Cashback { .. } | Generator::VolatileAsset { .. }
+            )
+        })
+        .map(|(index, entry)| entry.label(index))
+        .collect();
+    postings.iter().filter(|posting| paying.contains(&posting.generator)).cloned().collect()
+}
+
+/// Plain-text accounting names for every account in `balances`: by the type
+/// declared in the config, or income and expense accounts by the part
+/// generators give them, opening balances as equity, and the rest as assets,
+/// or liabilities if they start in debt
+fn ledger_account_names(
+    config: &Config,
+    balances: &balances::Balances,
+) -> std::collections::HashMap<String, String> {
+    use ledger::AccountType::{Assets, Expenses, Income, Liabilities};
+    // The default accounts every forecast has, whether or not a generator uses them
+    let mut types: std::collections::HashMap<String, ledger::AccountType> = [
+        (SALARY_INCOME, Income),
+        (RENTAL_INCOME, Income),
+        (MORTGAGE_INCOME, Expenses),
+        (CHARITY_EXPENDITURE, Expenses),
+        (EVENTS_EXPENDITURE, Expenses),
+        (EXCHANGE_GAINS, Income),
+    ]
+    .into_iter()
+    .map(|(account, kind)| (account.to_string(), kind))
+    .collect();
+    types.extend(categories(&config.generators).into_iter().map(|(_, account)| (account, Expenses)));
+    for entry in &config.generators {
+        let (income, expenses): (Vec<&String>, Vec<&String>) = match &entry.generator {
+            Generator::Salary { income_account, tax_account, ni_account, student_loan_account, payroll_giving_account, .. } => {
+                (vec![income_account], vec![tax_account, ni_account, student_loan_account, payroll_giving_account])
+            }
+            // Interest charged on a debt is an expense, even though it's drawn from the income account
+            Generator::Interest { account, income_account, .. } => match balances.get(account) {
+                Some(balance) if *balance < Decimal::ZERO => (vec![], vec![income_account]),
+                _ => (vec![income_account], vec![]),
+            },
+            Generator::Rent { income_account, tax_account, .. } | Generator::Dividend { income_account, tax_account, .. } => {
+                (vec![income_account], vec![tax_account])
+            }
+            Generator::Cashback { income_account, .. }
+            | Generator::SavingsInterest { income_account, .. }
+            | Generator::RegularSaver { income_account, .. } => (vec![income_account], vec![]),
+            Generator::Investment { growth_account, .. } => (vec![growth_account], vec![]),
+            Generator::VolatileAsset { revaluation_account, .. } => (vec![revaluation_account], vec![]),
+            Generator::Saye { gain_account, tax_account, .. } => (vec![gain_account], vec![tax_account]),
+            Generator::Tithe { to, recipients, gift_aid_account, gift_aid_income_account, .. } => (
+                vec![gift_aid_income_account],
+                [to, gift_aid_account].into_iter().chain(recipients.iter().map(|recipient| &recipient.account)).collect(),
+            ),
+            Generator::Loan { interest_account, fee_account, .. } | Generator::CreditCard { interest_account, fee_account, .. } => {
+                (vec![], vec![interest_account, fee_account])
+            }
+            Generator::Remortgage { fee_account, .. } => (vec![], vec![fee_account]),
+            Generator::CouncilTax { to, .. } | Generator::AnnualEvents { to, .. } => (vec![], vec![to]),
+            _ => (vec![], vec![]),
+        };
+        types.extend(income.into_iter().map(|account| (account.clone(), Income)));
+        types.extend(expenses.into_iter().map(|account| (account.clone(), Expenses)));
+    }
+    for overdraft in config.overdrafts.values() {
+        types.insert(overdraft.interest_account.clone(), Expenses);
+        types.insert(overdraft.fee_account.clone(), Expenses);
+    }
+    types.extend(config.account_types.iter().map(|(account, kind)| (account.clone(), *kind)));
+    let liabilities = liability_accounts(config, balances);
+    balances
+        .iter()
+        .map(|(account, balance)| {
+            let name = match types.get(account) {
+                _ if account == OPENING_BALANCES => ledger::OPENING_BALANCES.to_string(),
+                Some(kind) => kind.name(account),
+                None if liabilities.contains(account) || *balance < Decimal::ZERO => Liabilities.name(account),
+                None => Assets.name(account),
+            };
+            (account.clone(), name)
+        })
+        .collect()
+}
+
+/// Every account but opening balances in the order the outputs list them:
+/// assets, then liabilities, income and expenses, each by name
+fn account_order(config: &Config, balances: &balances::Balances) -> Vec<String> {
+    use ledger::AccountType::{Assets, Expenses, Income, Liabilities};
+    let names = ledger_account_names(config, balances);
+    let mut accounts: Vec<(usize, String)> = names
+        .iter()
+        .filter(|(account, _)| *account != OPENING_BALANCES)
+        .map(|(account, name)| {
+            let kind = [Assets, Liabilities, Income, Expenses].iter().position(|kind| *name == kind.name(account)).unwrap_or(4);
+            (kind, account.clone())
+        })
+        .collect();
+    accounts.sort();
+    accounts.into_iter().map(|(_, account)| account).collect()
+}
+
+/// Interest charged on `account` in each (year, month), as positive amounts
+fn interest_charged(config: &Config, state: &State, account: &str) -> std::collections::BTreeMap<(i32, u32), Decimal> {
+    let mut charged = std::collections::BTreeMap::new();
+    for (index, entry) in config.generators.iter().enumerate() {
+        if let Generator::Interest { account: charged_to, .. } = &entry.generator
+            && charged_to == account
+            && let Some(accrual) = state.interest.get(&index)
+        {
+            for (month, interest) in &accrual.paid {
+                *charged.entry(*month).or_default() -= *interest;
+            }
+        }
+    }
+    charged
+}
+
+/// For every liability, when it's cleared and the interest charged over the forecast
+fn liability_payoffs(
+    config: &Config,
+    balances: &balances::Balances,
+    history: &[(chrono::NaiveDate, balances::Balances)],
+    state: &State,
+) -> Vec<report::LiabilityPayoff> {
+    let payoff_dates = report::payoff_dates(history, balances, &[OPENING_BALANCES]);
+    liability_accounts(config, balances)
+        .into_iter()
+        .map(|account| {
+            let paid_off = payoff_dates.iter().find(|(name, _)| *name == account).and_then(|(_, date)| *date);
+            report::LiabilityPayoff {
+                total_interest: interest_charged(config, state, &account).values().sum(),
+                account,
+                paid_off,
+                remaining_months: paid_off.map(|date| report::months_between(config.start_date, date)),
+            }
+        })
+        .collect()
+}
+
+/// For each account overpaid, the total overpaid and when it's paid off with
+/// and without the overpayments
+fn overpayment_impact(
+    config: &Config,
+    balances: &balances::Balances,
+    days_to_run: i32,
+    history: &[(chrono::NaiveDate, balances::Balances)],
+    state: &State,
+) -> Result<Vec<report::OverpaymentImpact>, String> {
+    let overpaid: Vec<(usize, &String)> = config
+        .generators
+        .iter()
+        .enumerate()
+        .filter_map(|(index, entry)| match &entry.generator {
+            Generator::Overpayment { to, .. } => Some((index, to)),
+            _ => None,
+        })
+        .collect();
+    if overpaid.is_empty() {
+        return Ok(Vec::new());
+    }
+    let mut without = config.clone();
+    without.generators.retain(|entry| !matches!(entry.generator, Generator::Overpayment { .. }));
+    let without = run(&without, balances.clone(), days_to_run)?;
+    let payoff = |history: &[(chrono::NaiveDate, balances::Balances)], account: &String| {
+        report::payoff_dates(history, balances, &[OPENING_BALANCES])
+            .into_iter()
+            .find(|(name, _)| name == account)
+            .and_then(|(_, date)| date)
+    };
+    let mut accounts: Vec<&String> = overpaid.iter().map(|(_, account)| *account).collect();
+    accounts.sort();
+    accounts.dedup();
+    Ok(accounts
+        .into_iter()
+        .map(|account| report::OverpaymentImpact {
+            account: account.clone(),
+            overpaid: overpaid
+                .iter()
+                .filter(|(_, to)| *to == account)
+                .filter_map(|(index, _)| state.overpayments.get(index))
+                .map(|allowance| allowance.total)
+                .sum(),
+            payoff_with: payoff(history, account),
+            payoff_without: payoff(&without, account),
+        })
+        .collect())
+}
+
+/// What each loan and credit card's payments went towards over the run,
+/// labelled by generator name or account
+fn debt_allocations(config: &Config, state: &State) -> Vec<(String, debt::Allocation)> {
+    config
+        .generators
+        .iter()
+        .enumerate()
+        .filter_map(|(index, entry)| match &entry.generator {
+            Generator::Loan { account, .. } | Generator::CreditCard { account, .. } => {
+                let paid = state.debts.get(&index).map(|debt| debt.paid).unwrap_or_default();
+                Some((entry.name.clone().unwrap_or_else(|| account.clone()), paid))
+            }
+            _ => None,
+        })
+        .collect()
+}
+
+/// Add the income and expenditure accounts generators post to without them
+/// being listed in the config: expense categories, salary, rent and interest
+/// income, giving, and loan and card interest and fees
+fn add_generator_accounts(
+    balances: &std::collections::HashMap<String, Decimal>,
+    generators: &[GeneratorEntry],
+) -> std::collections::HashMap<String, Decimal> {
+    let mut new_balances = balances.clone();
+    for (_, account) in categories(generators) {
+        new_balances.entry(account).or_insert(Decimal::ZERO);
+    }
+    for entry in generators {
+        match &entry.generator {
+            Generator::Salary {
+                income_account, gross_annual, tax_account, ni_account, student_loan_account, payroll_giving_account, ..
+            } => {
+                new_balances.entry(income_account.clone()).or_insert(Decimal::ZERO);
+                if gross_annual.is_some() {
+                    for account in [tax_account, ni_account, student_loan_account, payroll_giving_account] {
+                        new_balances.entry(account.clone()).or_insert(Decimal::ZERO);
+                    }
+                }
+            }
+            Generator::Rent { income_account, taxed, tax_account, .. } | Generator::Dividend { income_account, taxed, tax_account, .. } => {
+                new_balances.entry(income_account.clone()).or_insert(Decimal::ZERO);
+                if *taxed {
+                    new_balances.entry(tax_account.clone()).or_insert(Decimal::ZERO);
+                }
+            }
+            Generator::Interest { income_account, .. }
+            | Generator::SavingsInterest { income_account, .. }
+            | Generator::Cashback { income_account, .. } => {
+                new_balances.entry(income_account.clone()).or_insert(Decimal::ZERO);
+            }
+            Generator::Investment { growth_account, .. } => {
+                new_balances.entry(growth_account.clone()).or_insert(Decimal::ZERO);
+            }
+            Generator::VolatileAsset { revaluation_account, .. } => {
+                new_balances.entry(revaluation_account.clone()).or_insert(Decimal::ZERO);
+            }
+            Generator::Saye { gain_account, tax_account, .. } => {
+                new_balances.entry(gain_account.clone()).or_insert(Decimal::ZERO);
+                new_balances.entry(tax_account.clone()).or_insert(Decimal::ZERO);
+            }
+            Generator::RegularSaver { account, income_account, .. } => {
+                new_balances.entry(account.clone()).or_insert(Decimal::ZERO);
+                new_balances.entry(income_account.clone()).or_insert(Decimal::ZERO);
+            }
+            Generator::Tithe { to, recipients, gift_aid, gift_aid_account, gift_aid_income_account, .. } => {
+                new_balances.entry(to.clone()).or_insert(Decimal::ZERO);
+                for recipient in recipients {
+                    new_balances.entry(recipient.account.clone()).or_insert(Decimal::ZERO);
+                }
+                if *gift_aid {
+                    new_balances.entry(gift_aid_account.clone()).or_insert(Decimal::ZERO);
+                    new_balances.entry(gift_aid_income_account.clone()).or_insert(Decimal::ZERO);
+                }
+            }
+            Generator::CouncilTax { to, .. } | Generator::AnnualEvents { to, .. } | Generator::Envelope { to, .. } => {
+                new_balances.entry(to.clone()).or_insert(Decimal::ZERO);
+            }
+            Generator::Remortgage { fee, fee_account, .. } if *fee > Decimal::ZERO => {
+                new_balances.entry(fee_account.clone()).or_insert(Decimal::ZERO);
+            }
+            _ => {}
+        }
+        if let Generator::Loan { interest_account, fee, fee_account, .. }
+        | Generator::CreditCard { interest_account, fee, fee_account, .. } = &entry.generator
+        {
+            new_balances.entry(interest_account.clone()).or_insert(Decimal::ZERO);
+            if *fee > Decimal::ZERO {
+                new_balances.entry(fee_account.clone()).or_insert(Decimal::ZERO);
+            }
+        }
+    }
+    new_balances
+}
+
+fn compute_next_day_balances(
+    config: &Config,
+    balances: &balances::Balances,
+    date: chrono::NaiveDate,
+    state: &State,
+) -> Result<(balances::Balances, State), String> {
+    let currencies = config.currencies();
+    let rounding = |account: &str| currencies.rounding(account);
+    let backing = config.backing();
+    let mut new_balances = journal::Ledger::new(balances.clone(), date, &currencies, &backing);
+    let mut state = state.clone();
+
+    // Land any postings deferred to today from earlier days
+    let (due, later): (Vec<_>, Vec<_>) = state.pending.drain(..).partition(|posting| posting.date <= date);
+    state.pending = later;
+    for posting in due {
+        if let Some(kind) = posting.income.as_deref() {
+            let income = giving::Income { generator: &posting.generator, kind, account: &posting.from };
+            count_income(config, &mut state, &income, date, posting.amount);
+        }
+        new_balances.start(posting.generator, posting.note);
+        new_balances.post(&posting.from, &posting.to, posting.amount, &posting.description)?;
+    }
+
+    let mut overdrawn: Vec<(&String, &overdraft::Overdraft)> = config.overdrafts.iter().collect();
+    overdrawn.sort_by_key(|(account, _)| *account);
+
+    // Charge last month's overdraft interest and fees on the 1st
+    if date.day() == 1 {
+        for (account, overdraft) in &overdrawn {
+            let (interest, fee) = state.overdrafts.entry(account.to_string()).or_default().settle(overdraft, rounding(account));
+            new_balances.start(format!("{account} overdraft"), None);
+            new_balances.post(account, &overdraft.interest_account, interest, "overdraft interest")?;
+            new_balances.post(account, &overdraft.fee_account, fee, "overdraft fees")?;
+        }
+    }
+
+    let calendar = WithExtra { base: config.holiday_calendar, extra: &config.holidays };
+
+    // For each transaction, apply its effect to the relevant accounts
+    for (index, entry) in config.generators.iter().enumerate() {
+        if !entry.active_on(date) {
+            continue;
+        }
+        let due = std::cell::Cell::new(false);
+        let fires = |schedule: &Schedule| {
+            let fires = entry.adjust.fires(schedule, date, config.start_date, &calendar);
+            due.set(due.get() || fires);
+            fires
+        };
+        new_balances.start(entry.label(index), entry.description.clone());
+        if let Some(pinned) = config
+            .overrides
+            .iter()
+            .find(|pinned| pinned.date == date && entry.name.as_ref().is_some_and(|name| name.eq_ignore_ascii_case(&pinned.generator)))
+        {
+            new_balances.pin(pinned.amount);
+        }
+        let posted = new_balances.postings.len();
+        let policy = entry.on_insufficient_funds.unwrap_or_else(|| entry.generator.on_insufficient_funds());
+        if policy != backing::InsufficientFunds::Overdraw
+            && let Some((from, _, _)) = entry.generator.wiring().into_iter().find(|(_, field, _)| *field == "from")
+        {
+            new_balances.fund_from(from, policy);
+        }
+        match &entry.generator {
+            Generator::Mortgage { deduction_amount, schedule, from, to } => {
+                if fires(schedule) {
+                    let deduction_amount = &remortgage_terms(config, to, date).1.unwrap_or(*deduction_amount);
+                    let to_balance = *new_balances.get(to).expect("to account not found in balances");
+                    assert!(to_balance <= Decimal::ZERO, "Mortgage account must be negative; is {to_balance}");
+                    // Anything short in `from` is left to its on_insufficient_funds policy
+                    let actual_deduction = new_balances.pinned_or((*deduction_amount).min(-to_balance).max(Decimal::ZERO));
+                    assert!(actual_deduction <= *deduction_amount);
+                    assert!(actual_deduction >= Decimal::ZERO, "Mortgage deduction amount must be non-negative; is {actual_deduction}");
+                    new_balances.post(from, to, actual_deduction, "mortgage payment")?;
+                }
+            }
+            Generator::Interest { rate, rate_schedule, base, schedule, account, income_account, month, method, rate_volatility } => {
+                let schedule = interest_schedule(schedule, *month);
+                let shift = *state
+                    .rate_shifts
+                    .entry(index)
+                    .or_insert_with(|| monte_carlo::sample(Decimal::ZERO, *rate_volatility, state.rng.as_mut()));
+                let rate = &remortgage_terms(config, account, date)
+                    .0
+                    .unwrap_or_else(|| interest::tracking(interest::rate_on(*rate, rate_schedule, date), base.as_deref(), &config.reference_rates, date));
+                let rate = &if *rate == Decimal::ZERO { *rate } else { (*rate + shift).max(Decimal::ZERO) };
+                let current_balance = *new_balances.get(account).unwrap();
+                let accrual = state.interest.entry(index).or_default();
+                accrual.record(current_balance);
+                if fires(&schedule) {
+                    if *rate != Decimal::ZERO {
+                        let interest_exact = interest::interest_due(*method, current_balance, *rate, schedule.periods_per_year(), accrual)
+                            .ok_or_else(|| overflow("Interest", account, date))?;
+                        let interest = new_balances.pinned_or(rounding(account).round(interest_exact));
+                        new_balances.post(income_account, account, interest, "interest")?;
+                        accrual.pay(date, interest);
+                    }
+                    accrual.reset();
+                }
+            }
+            Generator::SavingsInterest { tiers, schedule, account, income_account, method, .. } => {
+                let current_balance = new_balances[account.as_str()];
+                let accrual = state.interest.entry(index).or_default();
+                accrual.record(current_balance);
+                if fires(schedule) {
+                    let tiers: Vec<interest::Tier> = tiers
+                        .iter()
+                        .map(|tier| interest::Tier {
+                            rate: interest::tracking(tier.rate, tier.base.as_deref(), &config.reference_rates, date),
+                            base: None,
+                            ..tier.clone()
+                        })
+                        .collect();
+                    let due = interest::tiered_interest_due(*method, current_balance, &tiers, schedule.periods_per_year(), accrual)
+                        .ok_or_else(|| overflow("Interest", account, date))?;
+                    let interest = new_balances.pinned_or(rounding(account).round(due));
+                    if interest > Decimal::ZERO {
+                        new_balances.post(income_account, account, interest, "interest")?;
+                        accrual.pay(date, interest);
+                    }
+                    accrual.reset();
+                }
+            }
+            Generator::Salary {
+                amount, schedule, to, income_account, annual_increase_percent, increase_month, raises, gross_annual, student_loans,
+                tax_account, ni_account, student_loan_account, payroll_giving_percent, payroll_giving_account,
+            } => {
+                if fires(schedule) {
+                    // A salary without its own annual increase keeps pace with inflation
+                    let increase = if annual_increase_percent.is_zero() { entry.inflation(config)? } else { *annual_increase_percent };
+                    let pay = |amount| salary::amount_on(amount, increase, *increase_month, raises, config.start_date, date, rounding(to));
+                    let net = match gross_annual {
+                        None => pay(*amount),
+                        Some(gross_annual) => {
+                            // Spread the year's pay and deductions evenly over its payments
+                            let gross_annual = pay(*gross_annual);
+                            let giving = gross_annual * *payroll_giving_percent / dec!(100);
+                            let jurisdiction = config.tax.jurisdiction();
+                            let mut deductions = jurisdiction.deductions(date, gross_annual, student_loans);
+                            deductions.income_tax -= jurisdiction.payroll_giving_relief(date, gross_annual, giving);
+                            let per_payment = |annual: Decimal| rounding(to).round(annual / schedule.periods_per_year());
+                            let gross = per_payment(gross_annual);
+                            let taken = [
+                                (tax_account, per_payment(deductions.income_tax), "income tax"),
+                                (ni_account, per_payment(deductions.national_insurance), "national insurance"),
+                                (student_loan_account, per_payment(deductions.student_loan), "student loan"),
+                                (payroll_giving_account, per_payment(giving), "payroll giving"),
+                            ];
+                            for (account, deduction, description) in &taken {
+                                new_balances.post(income_account, account, *deduction, description)?;
+                            }
+                            gross - taken.iter().map(|(_, deduction, _)| deduction).sum::<Decimal>()
+                        }
+                    };
+                    let net = new_balances.pinned_or(net);
+                    new_balances.post(income_account, to, net, "net pay")?;
+                    let income = giving::Income { generator: &entry.label(index), kind: "salary", account: income_account };
+                    count_income(config, &mut state, &income, date, net);
+                }
+            }
+            Generator::Transfer { amount, schedule, from, to, percentage, of } => {
+                if fires(schedule) {
+                    let amount = match percentage {
+                        None => *amount,
+                        Some(percentage) => {
+                            let base = match of {
+                                Some(share::ShareOf::Balance { balance: account }) => new_balances[account.as_str()],
+                                _ => state.shares.entry(index).or_default().last_month(date),
+                            };
+                            rounding(from).round(base.max(Decimal::ZERO) * *percentage / dec!(100))
+                        }
+                    };
+                    let amount = new_balances.pinned_or(amount);
+                    if entry.allows(&new_balances, from, to, amount) {
+                        new_balances.post(from, to, amount, "transfer")?;
+                    }
+                }
+            }
+            Generator::Tithe { percentage, schedule, from, to, recipients, gift_aid, gift_aid_account, gift_aid_income_account, .. } => {
+                if fires(schedule) {
+                    // A percentage of the income this tithe covers received since it was last paid
+                    let base = state.tithes.get(&index).copied().unwrap_or_default();
+                    let tithe_amount = new_balances.pinned_or(rounding(from).round(base * *percentage / dec!(100)));
+                    if tithe_amount > Decimal::ZERO {
+                        let (shares, unallocated) = giving::allocate(tithe_amount, recipients, rounding(from));
+                        for (recipient, share) in recipients.iter().zip(shares) {
+                            new_balances.post(from, &recipient.account, share, &format!("tithe to {}", recipient.name))?;
+                        }
+                        new_balances.post(from, to, unallocated, "tithe")?;
+                        if *gift_aid {
+                            new_balances.post(gift_aid_income_account, gift_aid_account, giving::gift_aid(tithe_amount), "gift aid")?;
+                        }
+                        state.tithes.remove(&index);
+                    }
+                }
+            }
+            Generator::Rent {
+                amount, schedule, to, income_account, void_probability, late_probability, late_days, growth_percent, taxed, tax_account, other_income,
+            } => {
+                if fires(schedule) {
+                    let amount = &new_balances.pinned_or(inflation::inflated(*amount, *growth_percent, config.start_date, date, rounding(to)));
+                    let (on_time, late, delay) = match state.rng.as_mut() {
+                        Some(rng) => {
+                            if rng.gen_bool(probability(*void_probability)) {
+                                (Decimal::ZERO, Decimal::ZERO, 0)
+                            } else if rng.gen_bool(probability(*late_probability)) {
+                                (Decimal::ZERO, *amount, late_days.sample(rng))
+                            } else {
+                                (*amount, Decimal::ZERO, 0)
+                            }
+                        }
+                        None => {
+                            // Expected value: the void share is never received and the late share arrives after the mean delay
+                            let received = rounding(to).round(*amount * (Decimal::ONE - *void_probability));
+                            let late = rounding(to).round(received * *late_probability);
+                            (received - late, late, late_days.expected())
+                        }
+                    };
+                    if *taxed && !amount.is_zero() {
+                        // Set aside the year's tax on the rent in proportion to what's received
+                        let annual = *amount * schedule.periods_per_year();
+                        let other_income = other_income.map_or_else(|| gross_salaries(config, date), Ok)?;
+                        let annual_tax = config.tax.jurisdiction().property_income_tax(date, other_income, annual);
+                        new_balances.post(to, tax_account, rounding(to).round((on_time + late) * annual_tax / annual), "property income tax")?;
+                    }
+                    let (on_time, late) = if delay == 0 { (on_time + late, Decimal::ZERO) } else { (on_time, late) };
+                    new_balances.post(income_account, to, on_time, "rent")?;
+                    let income = giving::Income { generator: &entry.label(index), kind: "rent", account: income_account };
+                    count_income(config, &mut state, &income, date, on_time);
+                    if late > Decimal::ZERO {
+                        state.pending.push(PendingPosting {
+                            date: date + chrono::Duration::days(delay as i64),
+                            from: income_account.clone(),
+                            to: to.clone(),
+                            amount: late,
+                            income: Some("rent".to_string()),
+                            generator: entry.label(index),
+                            description: "late rent".to_string(),
+                            note: entry.description.clone(),
+                        });
+                    }
+                }
+            }
+            Generator::Dividend { amount, schedule, growth_percent, to, income_account, taxed, tax_account, other_income } => {
+                if fires(schedule) {
+                    let amount = new_balances.pinned_or(inflation::inflated(*amount, *growth_percent, config.start_date, date, rounding(to)));
+                    new_balances.post(income_account, to, amount, "dividend")?;
+                    if *taxed {
+                        let other_income = other_income.map_or_else(|| gross_salaries(config, date), Ok)?;
+                        let periods = schedule.periods_per_year();
+                        let annual_tax = config.tax.jurisdiction().dividend_tax(date, other_income, amount * periods);
+                        new_balances.post(to, tax_account, rounding(to).round(annual_tax / periods), "dividend tax")?;
+                    }
+                    let income = giving::Income { generator: &entry.label(index), kind: "dividend", account: income_account };
+                    count_income(config, &mut state, &income, date, amount);
+                }
+            }
+            Generator::Expense { amount, schedule, category, from, price_rise, variation, .. } => {
+                if fires(schedule) {
+                    let amount = match price_rise {
+                        Some(rise) => rise.amount_on(*amount, &config.inflation_indices, config.start_date, date, rounding(from))?,
+                        None => inflation::inflated(*amount, entry.inflation(config)?, config.start_date, date, rounding(from)),
+                    };
+                    let amount = &new_balances.pinned_or(
+                        rounding(from)
+                            .round(amount * monte_carlo::sample(Decimal::ONE, *variation / dec!(100), state.rng.as_mut()))
+                            .max(Decimal::ZERO),
+                    );
+                    if entry.allows(&new_balances, from, &category_account(category), *amount) {
+                        new_balances.post(from, &category_account(category), *amount, "expense")?;
+                    }
+                }
+            }
+            Generator::Energy { standing_charge, unit_rate, usage, tariff_changes, schedule, category, from } => {
+                if fires(schedule) {
+                    let bill = new_balances.pinned_or(energy::bill(*standing_charge, *unit_rate, tariff_changes, usage, date, rounding(from)));
+                    new_balances.post(from, &category_account(category), bill, "energy bill")?;
+                }
+            }
+            Generator::RandomExpense { min, max, times_per_year, schedule, category, from } => {
+                if fires(schedule) {
+                    let expected = *times_per_year / schedule.periods_per_year();
+                    let cost = match state.rng.as_mut() {
+                        Some(rng) => {
+                            let times = monte_carlo::occurrences(expected.to_f64().unwrap_or(0.0), rng);
+                            (0..times).map(|_| monte_carlo::uniform(*min, *max, rng)).sum()
+                        }
+                        None => expected * (*min + *max) / Decimal::TWO,
+                    };
+                    let amount = new_balances.pinned_or(inflation::inflated(cost, entry.inflation(config)?, config.start_date, date, rounding(from)));
+                    if amount > Decimal::ZERO && entry.allows(&new_balances, from, &category_account(category), amount) {
+                        new_balances.post(from, &category_account(category), amount, "random expense")?;
+                    }
+                }
+            }
+            Generator::PhasedExpense { phases, birth_date, schedule, category, from } => {
+                if fires(schedule)
+                    && let Some(phase) = children::phase_on(phases, *birth_date, date)
+                {
+                    let amount = new_balances.pinned_or(inflation::inflated(phase.amount, entry.inflation(config)?, config.start_date, date, rounding(from)));
+                    if amount > Decimal::ZERO && entry.allows(&new_balances, from, &category_account(category), amount) {
+                        new_balances.post(from, &category_account(category), amount, phase.name.as_deref().unwrap_or("expense"))?;
+                    }
+                }
+            }
+            Generator::SeasonalExpense { amounts, schedule, category, from } => {
+                let month = Month::try_from(date.month() as u8).expect("Every date has a month");
+                if fires(schedule)
+                    && let Some(amount) = amounts.get(&month)
+                {
+                    let amount = new_balances.pinned_or(inflation::inflated(*amount, entry.inflation(config)?, config.start_date, date, rounding(from)));
+                    if entry.allows(&new_balances, from, &category_account(category), amount) {
+                        new_balances.post(from, &category_account(category), amount, "expense")?;
+                    }
+                }
+            }
+            Generator::Loan {
+                account, principal, rate, term_months, schedule, from, interest_account, fee, fee_account, allocation,
+            } => {
+                if fires(schedule) {
+                    let periods_per_year = schedule.periods_per_year();
+                    let rate_per_payment = *rate / periods_per_year / dec!(100);
+                    let payments = (Decimal::from(*term_months) * periods_per_year / dec!(12)).round().to_u32().unwrap_or(0);
+                    let payment = loan::level_payment(*principal, rate_per_payment, payments, rounding(account));
+                    let debt = state.debts.entry(index).or_default();
+                    let owed = -*new_balances.get(account).expect("Loan account not found");
+                    let (interest, principal_due) = loan::split_payment(debt.principal(owed), rate_per_payment, payment, rounding(account));
+                    // Add today's charges to the debt, then pay what's due as far as its policy allows
+                    new_balances.post(account, interest_account, interest, "interest")?;
+                    debt.interest += interest;
+                    if *fee > Decimal::ZERO && owed > Decimal::ZERO {
+                        new_balances.post(account, fee_account, *fee, "fee")?;
+                        debt.fees += *fee;
+                    }
+                    let due = new_balances.pinned_or(debt.fees + debt.interest + principal_due);
+                    let paid = new_balances.post(from, account, due, "loan payment")?;
+                    debt.allocate(paid, principal_due, allocation);
+                }
+            }
+            Generator::CreditCard {
+                account, schedule, payment_delay_days, repayment, minimum_percent, minimum_amount, rate, from, interest_account,
+                fee, fee_account, allocation,
+            } => {
+                if fires(schedule) {
+                    // Charge interest on principal left unpaid from the last statement, and any fee
+                    let debt = state.debts.entry(index).or_default();
+                    let interest = rounding(account).round(debt.carried_principal * *rate / schedule.periods_per_year() / dec!(100));
+                    new_balances.post(account, interest_account, interest, "interest")?;
+                    debt.interest += interest;
+                    if *fee > Decimal::ZERO {
+                        new_balances.post(account, fee_account, *fee, "fee")?;
+                        debt.fees += *fee;
+                    }
+
+                    let statement_balance = (-new_balances[account]).max(Decimal::ZERO);
+                    let principal = debt.principal(statement_balance);
+                    let payment = new_balances.pinned_or(match repayment {
+                        CardRepayment::Full => statement_balance,
+                        CardRepayment::Minimum => rounding(account)
+                            .round(statement_balance * *minimum_percent / dec!(100))
+                            .max(*minimum_amount)
+                            .min(statement_balance),
+                    });
+                    let allocated = debt.allocate(payment, principal, allocation);
+                    debt.carried_principal = principal - allocated.principal;
+                    if payment > Decimal::ZERO {
+                        state.pending.push(PendingPosting {
+                            date: date + chrono::Duration::days(*payment_delay_days as i64),
+                            from: from.clone(),
+                            to: account.clone(),
+                            amount: payment,
+                            income: None,
+                            generator: entry.label(index),
+                            description: "card payment".to_string(),
+                            note: entry.description.clone(),
+                        });
+                    }
+                }
+            }
+            Generator::Overpayment { amount, sweep_above, schedule, from, to, annual_cap_percent, overflow_to } => {
+                if fires(schedule) {
+                    let balance = *new_balances.get(from).expect("Overpayment 'from' account not found");
+                    let wanted = match (amount, sweep_above) {
+                        (Some(amount), _) => *amount,
+                        (None, Some(threshold)) => balance - *threshold,
+                        (None, None) => Decimal::ZERO,
+                    };
+                    // Anything short in `from` is left to its on_insufficient_funds policy
+                    let wanted = new_balances.pinned_or(wanted.max(Decimal::ZERO));
+                    let owed = -*new_balances.get(to).expect("Overpayment 'to' account not found");
+                    let allowance = state.overpayments.entry(index).or_default();
+                    let overpaid = allowance.take(date, wanted, owed.max(Decimal::ZERO), *annual_cap_percent, rounding(to));
+                    let paid = new_balances.post(from, to, overpaid, "overpayment")?;
+                    allowance.give_back(overpaid - paid);
+                    if let Some(overflow_to) = overflow_to {
+                        new_balances.post(from, overflow_to, wanted - overpaid, "over the overpayment limit")?;
+                    }
+                }
+            }
+            Generator::Investment {
+                amount, schedule, from, to, annual_return, return_volatility, growth_account, annual_cap, allowance, overflow_to,
+            } => {
+                if fires(schedule) {
+                    let jurisdiction = config.tax.jurisdiction();
+                    let tax_year = jurisdiction.tax_year(date);
+                    let annual_return = &match state.investment_returns.get(&index) {
+                        Some((year, sampled)) if *year == tax_year => *sampled,
+                        _ => {
+                            let sampled = monte_carlo::sample(*annual_return, *return_volatility, state.rng.as_mut());
+                            state.investment_returns.insert(index, (tax_year, sampled));
+                            sampled
+                        }
+                    };
+                    // Grow what was already invested, then pay in what the allowance permits
+                    let invested = (*new_balances.get(to).expect("Investment account not found")).max(Decimal::ZERO);
+                    let growth = invested
+                        .checked_mul(investment::growth_rate(*annual_return, schedule.periods_per_year()))
+                        .ok_or_else(|| overflow("Growth", to, date))?;
+                    let growth = rounding(to).round(growth);
+                    new_balances.post(growth_account, to, growth, "growth")?;
+                    let amount = &new_balances.pinned_or(*amount);
+                    let contributed = state.investments.entry(index).or_default().take(tax_year, *amount, annual_cap.or(allowance.map(|allowance| jurisdiction.allowance(date, allowance))));
+                    new_balances.post(from, to, contributed, "contribution")?;
+                    if let Some(overflow_to) = overflow_to {
+                        new_balances.post(from, overflow_to, *amount - contributed, "over the annual cap")?;
+                    }
+                }
+            }
+            Generator::Cashback { percentage, categories, schedule, to, income_account, annual_cap } => {
+                // Spending is counted from the balances the day before the generator first runs
+                let spent = |balances: &balances::Balances| -> Decimal {
+                    categories
+                        .iter()
+                        .map(|category| balances.get(&category_account(category)).expect("Cashback category account not found"))
+                        .sum()
+                };
+                let rewards = state.cashback.entry(index).or_insert_with(|| cashback::Rewards::new(spent(balances)));
+                if fires(schedule) {
+                    let earned = rewards.earn(date, spent(&new_balances), *percentage, *annual_cap, rounding(to));
+                    let earned = new_balances.pinned_or(earned);
+                    new_balances.post(income_account, to, earned, "cashback")?;
+                }
+            }
+            Generator::Sweep { schedule, account, savings, target, floor } => {
+                if fires(schedule) {
+                    let balance = *new_balances.get(account).expect("Sweep account not found");
+                    let saved = (*new_balances.get(savings).expect("Sweep savings account not found")).max(Decimal::ZERO);
+                    if balance > *target {
+                        new_balances.post(account, savings, balance - *target, "sweep to savings")?;
+                    } else if let Some(floor) = floor
+                        && balance < *floor
+                    {
+                        new_balances.post(savings, account, (*floor - balance).min(saved), "top up from savings")?;
+                    }
+                }
+            }
+            Generator::Envelope { amount, schedule, from, to, leftover } => {
+                if fires(schedule) {
+                    if *leftover == envelope::Leftover::Reset {
+                        let left = new_balances[to.as_str()];
+                        if left > Decimal::ZERO {
+                            new_balances.post(to, from, left, "envelope reset")?;
+                        } else if left < Decimal::ZERO {
+                            new_balances.post(from, to, -left, "envelope reset")?;
+                        }
+                    }
+                    let amount = new_balances.pinned_or(inflation::inflated(*amount, entry.inflation(config)?, config.start_date, date, rounding(from)));
+                    if entry.allows(&new_balances, from, to, amount) {
+                        new_balances.post(from, to, amount, "allocation")?;
+                    }
+                }
+            }
+            Generator::VolatileAsset { account, amount, schedule, from, mean_return, volatility, drawdowns, revaluation_account } => {
+                // Revalue what's held each day, then buy more
+                let held = (*new_balances.get(account).expect("Volatile asset account not found")).max(Decimal::ZERO);
+                let daily = volatile::daily_return(*mean_return, *volatility, state.rng.as_mut());
+                let after_drawdowns = held
+                    .checked_mul(Decimal::ONE + daily)
+                    .and_then(|grown| grown.checked_mul(Decimal::ONE - volatile::drawdown_on(drawdowns, date)))
+                    .ok_or_else(|| overflow("Revaluation", account, date))?;
+                new_balances.post(revaluation_account, account, rounding(account).round(after_drawdowns - held), "revaluation")?;
+                if fires(schedule) {
+                    let amount = new_balances.pinned_or(*amount);
+                    new_balances.post(from, account, amount, "purchase")?;
+                }
+            }
+            Generator::Saye {
+                amount, schedule, start, term_months, option_price, share_price, from, account, to, gain_account, tax_account, ..
+            } => {
+                let matures = saye_maturity(*start, *term_months);
+                if date >= *start && date < matures && fires(schedule) {
+                    let amount = new_balances.pinned_or(*amount);
+                    new_balances.post(from, account, amount, "saving")?;
+                }
+                if date == matures {
+                    let savings = *new_balances.get(account).expect("Share scheme account not found");
+                    let outcome = share_scheme::maturity(savings, *option_price, *share_price, config.tax.jurisdiction().as_ref(), date, rounding(to));
+                    new_balances.post(account, to, savings, "savings at maturity")?;
+                    new_balances.post(gain_account, to, outcome.gain, "gain on shares")?;
+                    new_balances.post(to, tax_account, outcome.tax, "capital gains tax")?;
+                }
+            }
+            Generator::RegularSaver { monthly_cap, amount, rate, schedule, start, term_months, from, account, to, income_account } => {
+                let matures = saye_maturity(*start, *term_months);
+                if date >= *start && date < matures {
+                    if fires(schedule) {
+                        // However often it's paid into, no more than the cap goes in each calendar month
+                        let month = (date.year(), date.month());
+                        let deposited = match state.saver_deposits.get(&index) {
+                            Some((paid_in, deposited)) if *paid_in == month => *deposited,
+                            _ => Decimal::ZERO,
+                        };
+                        let deposit = new_balances.pinned_or(amount.unwrap_or(*monthly_cap)).min(*monthly_cap - deposited).max(Decimal::ZERO);
+                        if entry.allows(&new_balances, from, account, deposit) {
+                            let paid = new_balances.post(from, account, deposit, "deposit")?;
+                            state.saver_deposits.insert(index, (month, deposited + paid));
+                        }
+                    }
+                    state.interest.entry(index).or_default().record(new_balances[account.as_str()]);
+                }
+                if date == matures {
+                    // Interest on each day's balance since opening, paid in one go
+                    let accrual = state.interest.entry(index).or_default();
+                    let due = interest::interest_due(InterestMethod::AverageDailyBalance, Decimal::ZERO, *rate, Decimal::ONE, accrual)
+                        .ok_or_else(|| overflow("Interest", account, date))?;
+                    let interest = rounding(account).round(due);
+                    new_balances.post(income_account, account, interest, "interest")?;
+                    accrual.pay(date, interest);
+                    accrual.reset();
+                    let savings = new_balances[account.as_str()];
+                    new_balances.post(account, to, savings, "matured")?;
+                }
+            }
+            Generator::Remortgage { date: switch_date, account, fee, fee_account, .. } => {
+                if *switch_date == date {
+                    let fee = new_balances.pinned_or(*fee);
+                    new_balances.post(account, fee_account, fee, "remortgage fee")?;
+                }
+            }
+            Generator::CouncilTax { annual_amount, day, instalments, annual_increase_percent, from, to } => {
+                if fires(&Schedule::Monthly { day: *day })
+                    && let Some(amount) =
+                        council_tax::instalment(*annual_amount, *annual_increase_percent, *instalments, config.start_date, date, rounding(from))
+                {
+                    let amount = new_balances.pinned_or(amount);
+                    new_balances.post(from, to, amount, "council tax")?;
+                }
+            }
+            Generator::AnnualEvents { events, day, from, to } => {
+                for event in events {
+                    let schedule = Schedule::Annually { month: event.month, day: event.day.unwrap_or(*day) };
+                    if fires(&schedule) {
+                        let amount = new_balances.pinned_or(event.amount);
+                        new_balances.post(from, to, amount, &event.name)?;
+                    }
+                }
+            }
+        }
+        if let Some(amount) = new_balances.unpin() {
+            log::warn(format_args!("{}: paid nothing on {date} for the override of {amount} to replace", entry.label(index)));
+        }
+        if due.get() {
+            state.first_due.entry(index).or_insert(date);
+            if log::enabled(log::Verbosity::Debug) {
+                let postings: Vec<String> = new_balances.postings[posted..]
+                    .iter()
+                    .map(|posting| format!("{} {} from {} to {}", posting.description, posting.amount, posting.from, posting.to))
+                    .collect();
+                log::debug(format_args!("{date}: {} fired: {}", entry.label(index), postings.join(", ")));
+            }
+        }
+    }
+
+    // Accrue overdraft interest on the day's closing balances and charge any daily fee
+    for (account, overdraft) in &overdrawn {
+        let balance = *new_balances.get(account).expect("Overdraft account not found");
+        let (fee, breached) = state.overdrafts.entry(account.to_string()).or_default().record(overdraft, balance);
+        new_balances.start(format!("{account} overdraft"), None);
+        new_balances.post(account, &overdraft.fee_account, fee, "overdraft fee")?;
+        if breached && overdraft.hard_limit {
+            state.overdraft_breaches.push((account.to_string(), date, balance));
+        }
+    }
+
+    check_balance_limits(&new_balances, date)?;
+
+    // Revalue accounts in other currencies at the day's rates, and round away
+    // the pennies lost converting payments between them
+    let unbalanced = currencies.total(&new_balances.balances, date);
+    if currencies.mixed() && !unbalanced.is_zero() {
+        *new_balances.get_mut(EXCHANGE_GAINS).expect("Accounts in other currencies have exchange gains") -= unbalanced;
+    }
+
+    // Balances must sum to zero, in the base currency
+    let total_balance = currencies.total(&new_balances.balances, date);
+    if total_balance != Decimal::ZERO {
+        return Err(format!("Balances do not sum to zero on {date}: {total_balance}"));
+    }
+    state.journal.append(&mut new_balances.postings);
+    Ok((new_balances.balances, state))
+}
+
+/// Days to forecast for: up to `end_date`, or for `years` and `months`, or
+/// otherwise `DEFAULT_HORIZON_DAYS`
+fn horizon_days(config: &Config) -> Result<i32, String> {
+    let end = match (config.end_date, config.years, config.months) {
+        (Some(_), Some(_), _) | (Some(_), _, Some(_)) => return Err("Give either end_date or years and months, not both".to_string()),
+        (Some(end), None, None) => end,
+        (None, None, None) => return Ok(DEFAULT_HORIZON_DAYS),
+        (None, years, months) => {
+            let months = years.unwrap_or(0) * 12 + months.unwrap_or(0);
+            config.start_date.checked_add_months(chrono::Months::new(months)).ok_or_else(|| format!("A forecast of {months} months is too long"))?
+        }
+    };
+    let days = (end - config.start_date).num_days();
+    if days < 1 {
+        return Err(format!("The forecast ends on {end}, which is not after it starts on {}", config.start_date));
+    }
+    check_horizon(days)?;
+    Ok(days as i32)
+}
+
+/// Days until `account`, a liability, is paid off, looking no further than
+/// the config's own horizon, or `MAX_HORIZON_DAYS` if it has none
+fn paid_off_days(config: &Config, balances: &balances::Balances, account: &str, days: i32) -> Result<i32, String> {
+    match balances.get(account) {
+        None => return Err(format!("No account to run until paid off called: {account}")),
+        Some(balance) if *balance >= Decimal::ZERO => return Err(format!("{account} is not in debt at the start")),
+        Some(_) => {}
+    }
+    let given = config.end_date.is_some() || config.years.is_some() || config.months.is_some();
+    let limit = if given { days } else { MAX_HORIZON_DAYS as i32 };
+    let history = run(config, balances.clone(), limit)?;
+    match history.iter().find(|(_, balances)| balances[account] >= Decimal::ZERO) {
+        Some((date, _)) => Ok((*date - config.start_date).num_days() as i32),
+        None => Err(format!("{account} is not paid off by {}", config.start_date + chrono::Duration::days(limit as i64))),
+    }
+}
+
+/// Count `amount` of `income`, received on `date`, towards each tithe and
+/// percentage transfer that covers it
+fn count_income(config: &Config, state: &mut State, income: &giving::Income, date: chrono::NaiveDate, amount: Decimal) {
+    for (index, entry) in config.generators.iter().enumerate() {
+        match &entry.generator {
+            Generator::Tithe { of, basis, .. } if giving::counts(of.as_deref(), *basis, income) => {
+                *state.tithes.entry(index).or_default() += amount;
+            }
+            Generator::Transfer { percentage: Some(_), of, .. } if share::counts(of.as_ref(), income) => {
+                state.shares.entry(index).or_default().add(date, amount);
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Gross pay a year on `date` from every salary given as gross, which
+/// dividends and rent are taxed on top of
+fn gross_salaries(config: &Config, date: chrono::NaiveDate) -> Result<Decimal, String> {
+    let currencies = config.currencies();
+    config
+        .generators
+        .iter()
+        .filter(|entry| entry.active_on(date))
+        .filter_map(|entry| match &entry.generator {
+            Generator::Salary { gross_annual: Some(gross_annual), annual_increase_percent, increase_month, raises, to, .. } => {
+                let increase = if annual_increase_percent.is_zero() { entry.inflation(config) } else { Ok(*annual_increase_percent) };
+                Some(increase.map(|increase| {
+                    salary::amount_on(*gross_annual, increase, *increase_month, raises, config.start_date, date, currencies.rounding(to))
+                }))
+            }
+            _ => None,
+        })
+        .sum()
+}
+
+/// Refuse forecasts longer than `MAX_HORIZON_DAYS`
+fn check_horizon(days: i64) -> Result<(), String> {
+    if days > MAX_HORIZON_DAYS {
+        return Err(format!("A forecast of {days} days is longer than the maximum of {MAX_HORIZON_DAYS}"));
+    }
+    Ok(())
+}
+
+/// Fail on the first account, by name, whose balance on `date` is beyond
+/// `MAX_BALANCE`, before further growth on it can overflow
+fn check_balance_limits(balances: &balances::Balances, date: chrono::NaiveDate) -> Result<(), String> {
+    let mut accounts: Vec<(&String, &Decimal)> = balances.iter().filter(|(_, balance)| balance.abs() > MAX_BALANCE).collect();
+    accounts.sort();
+    match accounts.first() {
+        Some((account, balance)) => Err(format!(
+            "Balance of {account} on {date} is {balance}, beyond the {MAX_BALANCE} the forecast can hold precisely; check its growth rates and the horizon"
+        )),
+        None => Ok(()),
+    }
+}
+
+/// The error for `what` on `account`'s balance on `date` being too large to work out
+fn overflow(what: &str, account: &str, date: chrono::NaiveDate) -> String {
+    format!("{what} on {account} on {date} is too large to work out; check its growth rates and the horizon")
+}
+
+/// The rate and payment set by the latest remortgages of `account` on or
+/// before `date`, if any
+fn remortgage_terms(config: &Config, account: &str, date: chrono::NaiveDate) -> (Option<Decimal>, Option<Decimal>) {
+    let mut switches: Vec<(chrono::NaiveDate, Option<Decimal>, Option<Decimal>)> = config
+        .generators
+        .iter()
+        .filter_map(|entry| match &entry.generator {
+            Generator::Remortgage { date: switch_date, account: switched, rate, payment, .. }
+                if switched == account && *switch_date <= date =>
+            {
+                Some((*switch_date, *rate, *payment))
+            }
+            _ => None,
+        })
+        .collect();
+    switches.sort_by_key(|(switch_date, _, _)| *switch_date);
+    switches
+        .into_iter()
+        .fold((None, None), |(rate, payment), (_, new_rate, new_payment)| (new_rate.or(rate), new_payment.or(payment)))
+}
+
+/// The day a save-as-you-earn contract started on `start` matures
+fn saye_maturity(start: chrono::NaiveDate, term_months: u32) -> chrono::NaiveDate {
+    start.checked_add_months(chrono::Months::new(term_months)).expect("Share scheme maturity out of range")
+}
+
+/// How each save-as-you-earn contract turns out at its expected share price
+/// and at each of its scenarios, assuming every monthly saving is made
+fn share_scheme_outcomes(config: &Config) -> Vec<report::ShareSchemeOutcome> {
+    let mut outcomes = Vec::new();
+    for entry in &config.generators {
+        let Generator::Saye { amount, start, term_months, option_price, share_price, scenarios, account, to, .. } = &entry.generator
+        else {
+            continue;
+        };
+        let savings = *amount * Decimal::from(*term_months);
+        let expected = share_scheme::ShareScenario { name: "expected".to_string(), share_price: *share_price };
+        for scenario in std::iter::once(&expected).chain(scenarios) {
+            let maturity = share_scheme::maturity(
+                savings,
+                *option_price,
+                scenario.share_price,
+                config.tax.jurisdiction().as_ref(),
+                saye_maturity(*start, *term_months),
+                config.currencies().rounding(to),
+            );
+            outcomes.push(report::ShareSchemeOutcome {
+                scheme: entry.name.clone().unwrap_or_else(|| account.clone()),
+                scenario: scenario.name.clone(),
+                matures: saye_maturity(*start, *term_months),
+                savings,
+                share_price: scenario.share_price,
+                maturity,
+            });
+        }
+    }
+    outcomes
+}
+
+/// Move `amount` from one account to another
+fn post(balances: &mut balances::Balances, from: &str, to: &str, amount: Decimal) {
+    post_converted(balances, from, to, amount, amount);
+}
+
+/// Move `amount` out of `from`, paying `received` into `to`; they differ
+/// when the accounts are held in different currencies
+fn post_converted(balances: &mut balances::Balances, from: &str, to: &str, amount: Decimal, received: Decimal) {
+    *balances.get_mut(from).unwrap_or_else(|| panic!("Account '{from}' not found")) -= amount;
+    *balances.get_mut(to).unwrap_or_else(|| panic!("Account '{to}' not found")) += received;
+}
+
+/// Convert a configured probability into something `gen_bool` accepts
+fn probability(p: Decimal) -> f64 {
+    p.to_f64().unwrap_or(0.0).clamp(0.0, 1.0)
+}
+
+/// Interest configured with a `month` is paid annually in that month on the
+/// scheduled day; otherwise it follows its schedule as given.
+fn interest_schedule(schedule: &Schedule, month: Option<Month>) -> Schedule {
+    match (month, schedule) {
+        (Some(month), Schedule::Monthly { day }) => Schedule::Annually { month, day: *day },
+        _ => schedule.clone(),
+    }
+}
+
+
+
+fn print_balance_named(name: &str, date: chrono::NaiveDate, balance: Decimal, currency_symbol: &str) {
+    println!(
+        "{name}: {date} {symbol}{v:.2}",
+        name = name,
+        date = date.format("%Y-%m-%d"),
+        symbol = currency_symbol,
+        v = balance
+    );
+}
+
+fn create_liability_plots(
+    account: &str,
+    history: &[(chrono::NaiveDate, balances::Balances)],
+    schedule: &[report::AmortizationRow],
+    stamp: &stamp::RunStamp,
+    tables_html: &str,
+    overlay: &Overlay,
+    currency_symbol: &str,
+) {
+    let path = format!("/tmp/{account}_amortization.csv");
+    if let Err(e) = std::fs::write(&path, [stamp.csv(), report::amortization_csv(schedule)].concat().join("\n")) {
+        eprintln!("Error creating CSV file: {}", e);
+    } else {
+        println!("{account} amortization schedule saved to '{path}'");
+    }
+    let tables_html = format!("{}\n{tables_html}", report::amortization_html(&report::yearly(schedule), currency_symbol));
+    create_balance_plot(account, history, stamp, &tables_html, overlay, currency_symbol);
+}
+
+/// What's drawn over a chart's first line: a line for each goal, a dot on
+/// each day given a tooltip, and optionally its lowest in each month
+#[derive(Default)]
+struct Overlay {
+    goals: Vec<goals::Goal>,
+    // Tooltips by date, as the chart's dates are written
+    markers: Vec<(String, String)>,
+    // Whether to draw the line's lowest in each month under it, so a dip before payday shows
+    monthly_lows: bool,
+}
+
+/// A tooltip for each day `postings` pay into or out of `account`, listing
+/// them with the generators, and their descriptions, that made them
+fn posting_markers(postings: &[journal::Posting], account: &str, currency_symbol: &str) -> Vec<(String, String)> {
+    let mut markers: Vec<(String, String)> = Vec::new();
+    for posting in postings.iter().filter(|posting| posting.from == account || posting.to == account) {
+        let date = posting.date.format("%Y-%m-%d").to_string();
+        let line = format!("{} ({}): {} -> {} {currency_symbol}{:.2}", posting.source(), posting.description, posting.from, posting.to, posting.amount);
+        match markers.last_mut() {
+            Some((day, text)) if *day == date => {
+                text.push('\n');
+                text.push_str(&line);
+            }
+            _ => markers.push((date, line)),
+        }
+    }
+    markers
+}
+
+/// Save an account's daily balances as CSV and chart them, with a line for
+/// each goal and a dot with a tooltip on each day money moves
+fn create_balance_plot(
+    account: &str,
+    history: &[(chrono::NaiveDate, balances::Balances)],
+    stamp: &stamp::RunStamp,
+    tables_html: &str,
+    overlay: &Overlay,
+    currency_symbol: &str,
+) {
+    // Extract dates and balances
+    let mut csv_lines = vec!["Date,Balance".to_string()];
+    
+    for (date, balances) in history {
+        if let Some(balance) = balances.get(account) {
+            csv_lines.push(format!("{},{}", date.format("%Y-%m-%d"), balance));
+        }
+    }
+    
+    let path = format!("/tmp/{account}_balance.csv");
+    if let Err(e) = std::fs::write(&path, [stamp.csv(), csv_lines.clone()].concat().join("\n")) {
+        eprintln!("Error creating CSV file: {}", e);
+    } else {
+        println!("{account} balance CSV data saved to '{path}'");
+    }
+    
+    // Chart them as an HTML page
+    let chart_lines = [vec![format!("Date,{account} Balance")], csv_lines[1..].to_vec()].concat();
+    let title = format!("{account} Balance Over Time");
+    create_html_chart(&format!("{account}_balance"), &title, &chart_lines, &stamp.html(), tables_html, overlay, currency_symbol);
+}
+
+/// Save the daily balances of `accounts` side by side as CSV, and chart them
+/// on one set of axes
+fn create_accounts_plot(
+    accounts: &[String],
+    history: &[(chrono::NaiveDate, balances::Balances)],
+    stamp: &stamp::RunStamp,
+    tables_html: &str,
+    currency_symbol: &str,
+) {
+    if accounts.is_empty() {
+        return;
+    }
+    let mut csv_lines = vec![format!("Date,{}", accounts.join(","))];
+    for (date, balances) in history {
+        let mut row = vec![date.format("%Y-%m-%d").to_string()];
+        row.extend(accounts.iter().map(|account| balances.get(account).map_or(String::new(), Decimal::to_string)));
+        csv_lines.push(row.join(","));
+    }
+    let path = "/tmp/balances.csv";
+    if let Err(e) = std::fs::write(path, [stamp.csv(), csv_lines.clone()].concat().join("\n")) {
+        eprintln!("Error creating CSV file: {}", e);
+    } else {
+        println!("Account balances CSV data saved to '{path}'");
+    }
+    create_html_chart("balances", "Account Balances Over Time", &csv_lines, &stamp.html(), tables_html, &Overlay::default(), currency_symbol);
+}
+
+/// Save each account's balance as forecast and as the statements have it,
+/// day by day, as CSV and chart them with the month end variances below
+fn create_reconciliation_plot(
+    accounts: &[String],
+    forecast: &History,
+    actual: &History,
+    variances: &[report::Variance],
+    stamp: &stamp::RunStamp,
+    currency_symbol: &str,
+) {
+    let forecast: std::collections::HashMap<chrono::NaiveDate, &balances::Balances> =
+        forecast.iter().map(|(date, balances)| (*date, balances)).collect();
+    let headings: Vec<String> = accounts.iter().flat_map(|account| [format!("{account} forecast"), format!("{account} actual")]).collect();
+    let mut csv_lines = vec![format!("Date,{}", headings.join(","))];
+    for (date, balances) in actual {
+        let mut row = vec![date.format("%Y-%m-%d").to_string()];
+        for account in accounts {
+            let forecast = forecast.get(date).and_then(|balances| balances.get(account));
+            row.push(forecast.map_or(String::new(), Decimal::to_string));
+            row.push(balances.get(account).map_or(String::new(), Decimal::to_string));
+        }
+        csv_lines.push(row.join(","));
+    }
+    let path = "/tmp/reconciliation.csv";
+    if let Err(e) = std::fs::write(path, [stamp.csv(), csv_lines.clone()].concat().join("\n")) {
+        eprintln!("Error creating CSV file: {}", e);
+    } else {
+        println!("Forecast and actual balances saved to '{path}'");
+    }
+    let tables_html = report::variances_html(variances, currency_symbol);
+    create_html_chart("reconciliation", "Forecast Against Actual", &csv_lines, &stamp.html(), &tables_html, &Overlay::default(), currency_symbol);
+}
+
+/// Save the total of the assets, of the liabilities and the difference between
+/// them each day as CSV, and chart them
+fn create_net_worth_plot(worth: &[report::NetWorth], stamp: &stamp::RunStamp, tables_html: &str, currency_symbol: &str) {
+    let csv_lines = report::net_worth_csv(worth);
+    let path = "/tmp/net_worth.csv";
+    if let Err(e) = std::fs::write(path, [stamp.csv(), csv_lines.clone()].concat().join("\n")) {
+        eprintln!("Error creating CSV file: {}", e);
+    } else {
+        println!("Net worth CSV data saved to '{path}'");
+    }
+    create_html_chart("net_worth", "Net Worth Over Time", &csv_lines, &stamp.html(), tables_html, &Overlay::default(), currency_symbol);
+}
+
+/// Save `account`'s daily balances in the base forecast and each scenario
+/// side by side as CSV, and chart them on one set of axes
+fn create_scenario_plot(
+    account: &str,
+    history: &[(chrono::NaiveDate, balances::Balances)],
+    scenarios: &[(String, History)],
+    stamp: &stamp::RunStamp,
+    currency_symbol: &str,
+) {
+    let by_date: Vec<std::collections::HashMap<chrono::NaiveDate, Decimal>> = scenarios
+        .iter()
+        .map(|(_, history)| history.iter().filter_map(|(date, balances)| Some((*date, *balances.get(account)?))).collect())
+        .collect();
+    // Commas in scenario names would split the CSV columns
+    let names: Vec<String> = scenarios.iter().map(|(name, _)| name.replace(',', " ")).collect();
+    let mut csv_lines = vec![format!("Date,Base,{}", names.join(","))];
+    for (date, balances) in history {
+        let mut row = vec![date.format("%Y-%m-%d").to_string(), balances.get(account).map_or(String::new(), Decimal::to_string)];
+        row.extend(by_date.iter().map(|balances| balances.get(date).map_or(String::new(), Decimal::to_string)));
+        csv_lines.push(row.join(","));
+    }
+
+    let path = format!("/tmp/{account}_scenarios.csv");
+    if let Err(e) = std::fs::write(&path, [stamp.csv(), csv_lines.clone()].concat().join("\n")) {
+        eprintln!("Error creating CSV file: {}", e);
+    } else {
+        println!("{account} scenario comparison CSV data saved to '{path}'");
+    }
+    let title = format!("{account} Balance by Scenario");
+    create_html_chart(&format!("{account}_scenarios"), &title, &csv_lines, &stamp.html(), "", &Overlay::default(), currency_symbol);
+}
+
+/// Save the percentile bands of `account`'s balance as CSV and chart them
+fn create_bands_plot(account: &str, bands: &[(chrono::NaiveDate, monte_carlo::Bands)], stamp: &stamp::RunStamp, currency_symbol: &str) {
+    let mut csv_lines = vec!["Date,P10,P50,P90".to_string()];
+    for (date, bands) in bands {
+        csv_lines.push(format!("{},{},{},{}", date.format("%Y-%m-%d"), bands.p10, bands.p50, bands.p90));
+    }
+    let path = format!("/tmp/{account}_bands.csv");
+    if let Err(e) = std::fs::write(&path, [stamp.csv(), csv_lines.clone()].concat().join("\n")) {
+        eprintln!("Error creating CSV file: {}", e);
+    } else {
+        println!("{account} balance bands saved to '{path}'");
+    }
+    let title = format!("{account} Balance Across Runs");
+    create_html_chart(&format!("{account}_bands"), &title, &csv_lines, &stamp.html(), "", &Overlay::default(), currency_symbol);
+}
+
+/// Save how the key figures change with a generator's amount or rate as CSV and chart them
+fn create_sensitivity_plot(generator: &str, csv_lines: &[String], stamp: &stamp::RunStamp, currency_symbol: &str) {
+    let name = format!("{}_sweep", generator.to_lowercase().replace(' ', "_"));
+    let path = format!("/tmp/{name}.csv");
+    if let Err(e) = std::fs::write(&path, [stamp.csv(), csv_lines.to_vec()].concat().join("\n")) {
+        eprintln!("Error creating CSV file: {}", e);
+    } else {
+        println!("{generator} sweep saved to '{path}'");
+    }
+    create_html_chart(&name, &format!("Sensitivity to {generator}"), csv_lines, &stamp.html(), "", &Overlay::default(), currency_symbol);
+}
+
+/// Save a matrix run's grid as CSV, and as an HTML table shaded from the
+/// worst figure in red to the best in green
+fn create_matrix_plot(grid: &report::Matrix, higher_is_better: bool, stamp: &stamp::RunStamp, currency_symbol: &str) {
+    let path = "/tmp/matrix.csv";
+    if let Err(e) = std::fs::write(path, [stamp.csv(), report::matrix_csv(grid)].concat().join("\n")) {
+        eprintln!("Error creating CSV file: {}", e);
+    } else {
+        println!("Matrix saved to '{path}'");
+    }
+    let title = report::html_escape(&format!("{} by {}", grid.metric, [grid.rows.0.as_str(), grid.columns.0.as_str()].join(" and ").trim_end_matches(" and ")));
+    let html_content = format!(
+        r#"<!DOCTYPE html>
+<html>
+<head>
+    <title>{title}</title>
+    <style>
+        body {{ font-family: Arial, sans-serif; margin: 20px; }}
+        h1 {{ text-align: center; }}
+        .stamp {{ color: #666; font-size: small; }}
+        table {{ border-collapse: collapse; margin: 20px auto; }}
+        th, td {{ border: 1px solid #ccc; padding: 4px 8px; text-align: right; }}
+    </style>
+</head>
+<body>
+    {}
+    <h1>{title}</h1>
+    {}
+</body>
+</html>"#,
+        stamp.html(),
+        report::matrix_html(grid, higher_is_better, currency_symbol),
+    );
+    let path = "/tmp/matrix.html";
+    if let Err(e) = std::fs::write(path, html_content) {
+        eprintln!("Error creating HTML file: {}", e);
+    } else {
+        println!("Matrix heatmap saved to '{path}'");
+    }
+}
+
+/// Save a goal's planned path beside the forecast, recorded actuals and how
+/// far the forecast is behind plan as CSV, and chart them as a burn-up
+fn create_plan_plot(goal: &goals::Goal, rows: &[goals::PlanRow], stamp: &stamp::RunStamp, currency_symbol: &str) {
+    let show = |value: Option<Decimal>| value.map_or(String::new(), |value| value.to_string());
+    let csv_lines: Vec<String> = std::iter::once("Date,Planned,Forecast,Actual,Behind plan".to_string())
+        .chain(rows.iter().map(|row| {
+            format!("{},{},{},{},{}", row.date.format("%Y-%m-%d"), row.planned, show(row.forecast), show(row.actual), show(row.behind()))
+        }))
+        .collect();
+    let name = format!("{}_plan", goal.label().to_lowercase().replace(' ', "_"));
+    let path = format!("/tmp/{name}.csv");
+    if let Err(e) = std::fs::write(&path, [stamp.csv(), csv_lines.clone()].concat().join("\n")) {
+        eprintln!("Error creating CSV file: {}", e);
+    } else {
+        println!("{} plan saved to '{path}'", goal.label());
+    }
+    create_html_chart(&name, &format!("{} Against Plan", goal.label()), &csv_lines, &stamp.html(), "", &Overlay::default(), currency_symbol);
+}
+
+/// Chart each column of `csv_lines` after the first, which is along the x
+/// axis, as a line named by its header, saved as `/tmp/{name}.html`
+fn create_html_chart(
+    name: &str,
+    title: &str,
+    csv_lines: &[String],
+    stamp_html: &str,
+    tables_html: &str,
+    overlay: &Overlay,
+    currency_symbol: &str,
+) {
+    let header: Vec<&str> = csv_lines.first().map(|header| header.split(',').collect()).unwrap_or_default();
+    let (x_axis, columns) = header.split_first().map_or(("", &[][..]), |(x_axis, columns)| (*x_axis, columns));
+    let rows: Vec<Vec<&str>> = csv_lines.iter().skip(1).map(|line| line.split(',').collect()).collect();
+    let dates: Vec<String> = rows.iter().map(|row| row[0].to_string()).collect();
+
+    // A line per column, in reds for a column that starts in debt and blues and greens otherwise
+    let colours = ["75, 192, 192", "54, 162, 235", "153, 102, 255", "46, 139, 87", "201, 203, 207"];
+    let debt_colours = ["220, 53, 69", "255, 159, 64", "178, 34, 34", "255, 205, 86"];
+    let (mut assets, mut debts) = (0, 0);
+    let mut series: Vec<svg::Series> = columns
+        .iter()
+        .enumerate()
+        .map(|(i, column)| {
+            let values: Vec<Option<f64>> = rows.iter().map(|row| row.get(i + 1).and_then(|value| value.parse().ok())).collect();
+            let in_debt = values.iter().flatten().next().is_some_and(|value| *value < 0.0);
+            let colour = if in_debt {
+                debts += 1;
+                debt_colours[(debts - 1) % debt_colours.len()]
+            } else {
+                assets += 1;
+                colours[(assets - 1) % colours.len()]
+            };
+            let markers = if i == 0 {
+                overlay.markers.iter().filter_map(|(date, text)| Some((dates.iter().position(|d| d == date)?, text.clone()))).collect()
+            } else {
+                Vec::new()
+            };
+            svg::Series { label: format!("{column} ({currency_symbol})"), colour: colour.to_string(), dashed: false, values, markers }
+        })
+        .collect();
+
+    // A dashed line at each goal's target, running up to the goal's date
+    series.extend(overlay.goals.iter().map(|goal| {
+        let by = goal.by.format("%Y-%m-%d").to_string();
+        let target = goal.target.to_f64().unwrap_or_default();
+        svg::Series {
+            label: format!("{} target ({currency_symbol}{}) by {by}", goal.label(), goal.target),
+            colour: "255, 99, 132".to_string(),
+            dashed: true,
+            values: dates.iter().map(|date| (*date <= by).then_some(target)).collect(),
+            markers: Vec::new(),
+        }
+    }));
+
+    // A dashed step along the first line's lowest in each month, in its colour
+    if overlay.monthly_lows
+        && let Some(first) = series.first()
+    {
+        let month = |date: &String| date.get(..7).unwrap_or(date).to_string();
+        let mut lows: std::collections::HashMap<String, f64> = std::collections::HashMap::new();
+        for (date, value) in dates.iter().zip(&first.values) {
+            if let Some(value) = value {
+                let low = lows.entry(month(date)).or_insert(*value);
+                *low = low.min(*value);
+            }
+        }
+        let lowest = svg::Series {
+            label: format!("Lowest in month ({currency_symbol})"),
+            colour: first.colour.clone(),
+            dashed: true,
+            values: dates.iter().map(|date| lows.get(&month(date)).copied()).collect(),
+            markers: Vec::new(),
+        };
+        series.push(lowest);
+    }
+
+    // Drawn here rather than by a script, so the page works offline
+    let html_content = format!(
+        r#"<!DOCTYPE html>
+<html>
+<head>
+    <title>{}</title>
+    <style>
+        body {{ font-family: Arial, sans-serif; margin: 20px; }}
+        .chart-container {{ width: 90%; margin: 0 auto; }}
+        h1 {{ text-align: center; }}
+        .stamp {{ color: #666; font-size: small; }}
+        table {{ border-collapse: collapse; margin: 20px auto; }}
+        th, td {{ border: 1px solid #ccc; padding: 4px 8px; text-align: left; }}
+    </style>
+</head>
+<body>
+    {}
+    <h1>{}</h1>
+    <div class="chart-container">
+        {}
+    </div>
+    {}
+</body>
+</html>"#,
+        report::html_escape(title),
+        stamp_html,
+        report::html_escape(title),
+        svg::line_chart(&dates, &series, &format!("Balance ({currency_symbol})"), x_axis),
+        tables_html,
+    );
+    
+    let path = format!("/tmp/{name}.html");
+    if let Err(e) = std::fs::write(&path, html_content) {
+        eprintln!("Error creating HTML file: {}", e);
+    } else {
+        println!("{title} chart saved to '{path}'");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal_macros::dec;
+    use std::collections::HashMap;
+
+    fn create_test_accounts_with_main_balance(mortgage_deduction_day: u32, main_balance: Option<Decimal>) -> Config {
+        let main_balance = main_balance.unwrap_or(dec!(10000.00));
+        let accounts = HashMap::from([
+            (MAIN_ACCOUNT.to_string(), main_balance),
+            (MORTGAGE_ACCOUNT.to_string(), dec!(-500000.00)),
+        ]);
+        let accounts_with_defaults = super::add_default_accounts(&accounts);
+        let accounts_with_opening = add_opening_balances(&accounts_with_defaults);
+        Config {
+            generators: vec![
+                Generator::Mortgage {
+                    deduction_amount: dec!(123.45),
+                    schedule: Schedule::monthly(mortgage_deduction_day),
+                    from: MAIN_ACCOUNT.to_string(),
+                    to: MORTGAGE_ACCOUNT.to_string(),
+                }.into(),
+                Generator::Interest {
+                    rate: dec!(5.0), // 5% annual interest rate
+                    schedule: Schedule::monthly(mortgage_deduction_day),
+                    account: MORTGAGE_ACCOUNT.to_string(),
+                    income_account: MORTGAGE_INCOME.to_string(),
+                    month: None, // Monthly interest
+                    method: InterestMethod::BalanceOnDay,
+                    rate_schedule: vec![],
+                    base: None,
+                    rate_volatility: Decimal::ZERO,
+                }.into(),
+                Generator::Salary {
+                    amount: dec!(2000.00),
+                    schedule: Schedule::monthly(6),
+                    to: MAIN_ACCOUNT.to_string(),
+                    income_account: SALARY_INCOME.to_string(),
+                    annual_increase_percent: Decimal::ZERO,
+                    increase_month: Month::January,
+                    raises: vec![],
+                    gross_annual: None,
+                    student_loans: vec![],
+                    tax_account: INCOME_TAX.to_string(),
+                    ni_account: NATIONAL_INSURANCE.to_string(),
+                    student_loan_account: STUDENT_LOAN.to_string(),
+                    payroll_giving_percent: Decimal::ZERO,
+                    payroll_giving_account: CHARITY_EXPENDITURE.to_string(),
+                }.into(),
+            ],
+            accounts: accounts_with_opening,
+            currency_symbol: "£".to_string(),
+            start_date: chrono::NaiveDate::from_ymd_opt(2025, 1, 1).unwrap(),
+            ..Default::default()
+        }
+    }
+
+    // For backward compatibility, keep the original function
+    fn create_test_accounts(mortgage_deduction_day: u32) -> Config {
+        create_test_accounts_with_main_balance(mortgage_deduction_day, None)
+    }
+
+    #[test]
+    fn test_config_parsing() {
+        let yaml = r#"
+generators:
+  - type: mortgage
+    deduction_amount: 123.45
+    deduction_day: 1
+  - type: interest
+    rate: 5.0
+    day: 1
+    account: mortgage
+    income_account: mortgage_income
+  - type: salary
+    amount: 2000.00
+    day: 6
+accounts:
+  main: 10000.00
+  mortgage: -500000.00
+currency_symbol: "£"
+start_date: "2025-01-01"
+"#;
+        let original_config: Config = serde_yaml::from_str(yaml).expect("Failed to parse YAML");
+        let mut config = original_config;
+        config.accounts = add_default_accounts(&config.accounts);
+        config.accounts = add_opening_balances(&config.accounts);   
+        
+        let expected = create_test_accounts(1);
+        assert_eq!(
+            config, expected,
+            "Config parsed from YAML does not match expected.\nParsed: {:#?}\nExpected: {:#?}",
+            config, expected
+        );
+        let account = config.accounts.get(MAIN_ACCOUNT).unwrap();
+        assert_eq!(*account, dec!(10000.00));
+        assert_eq!(config.currency_symbol, "£");
+    }
+
+    #[test]
+    fn test_compute_next_day_balances_no_deduction() {
+        let mortgage_deduction_day = 2;
+        let test_day = 5;
+        let next = make_accounts_for_day(mortgage_deduction_day, test_day);
+        assert_eq!(next[MAIN_ACCOUNT], dec!(10000.00));
+    }
+
+    fn make_accounts_for_day(mortgage_deduction_day: u32, test_day: u32) -> balances::Balances {
+        let config = create_test_accounts(mortgage_deduction_day);
+        let (next, _) = compute_next_day_balances(
+            &config,
+            &config.accounts.clone().into(),
+            chrono::NaiveDate::from_ymd_opt(2025, 1, test_day).unwrap(),
+            &State::default(),
+        ).unwrap();
+        next
+    }
+    
+    #[test]
+    fn test_compute_next_day_balances_with_deduction() {
+        let next = make_accounts_for_day(3, 3);
+        assert_eq!(next[MAIN_ACCOUNT], dec!(10000.00) - dec!(123.45));
+    }
+
+    #[test]
+    fn test_compute_next_day_balances_with_salary() {
+        let next= make_accounts_for_day(5, 6);
+        assert_eq!(next[MAIN_ACCOUNT], dec!(10000.00) + dec!(2000.00));
+    }
+
+    #[test]
+    fn test_compute_next_day_balances_with_salary_and_mortgage_same_day() {
+        let mut config = create_test_accounts(7);
+        config.generators.push(Generator::Salary {
+            amount: dec!(1500.00),
+            schedule: Schedule::monthly(7),
+            to: MAIN_ACCOUNT.to_string(),
+            income_account: SALARY_INCOME.to_string(),
+            annual_increase_percent: Decimal::ZERO,
+            increase_month: Month::January,
+            raises: vec![],
+            gross_annual: None,
+            student_loans: vec![],
+            tax_account: INCOME_TAX.to_string(),
+            ni_account: NATIONAL_INSURANCE.to_string(),
+            student_loan_account: STUDENT_LOAN.to_string(),
+            payroll_giving_percent: Decimal::ZERO,
+            payroll_giving_account: CHARITY_EXPENDITURE.to_string(),
+        }.into());
+        let (next, _) = compute_next_day_balances(
+            &config,
+            &config.accounts.clone().into(),
+            chrono::NaiveDate::from_ymd_opt(2025, 1, 7).unwrap(),
+            &State::default(),
+        ).unwrap();
+        assert_eq!(next[MAIN_ACCOUNT], dec!(10000.00) + dec!(1500.00) - dec!(123.45));
+    }
+
+    #[test]
+    fn test_compute_next_day_balances_with_salary_not_on_salary_day() {
+        let mut config = create_test_accounts(10);
+        config.generators.push(Generator::Salary {
+            amount: dec!(1000.00),
+            schedule: Schedule::monthly(15),
+            to: MAIN_ACCOUNT.to_string(),
+            income_account: SALARY_INCOME.to_string(),
+            annual_increase_percent: Decimal::ZERO,
+            increase_month: Month::January,
+            raises: vec![],
+            gross_annual: None,
+            student_loans: vec![],
+            tax_account: INCOME_TAX.to_string(),
+            ni_account: NATIONAL_INSURANCE.to_string(),
+            student_loan_account: STUDENT_LOAN.to_string(),
+            payroll_giving_percent: Decimal::ZERO,
+            payroll_giving_account: CHARITY_EXPENDITURE.to_string(),
+        }.into());
+        let (next, _) = compute_next_day_balances(
+            &config,
+            &config.accounts.clone().into(),
+            chrono::NaiveDate::from_ymd_opt(2025, 1, 15).unwrap(),
+            &State::default(),
+        ).unwrap();
+        assert_eq!(next[MAIN_ACCOUNT], dec!(10000.00) + dec!(1000.00));
+    }
+
+    
+    #[test]
+    fn test_compute_next_day_balances_with_salary_none() {
+        let next = make_accounts_for_day(20, 20);
+        assert_eq!(next[MAIN_ACCOUNT], dec!(10000.00) - dec!(123.45));
+    }
+
+    #[test]
+    fn test_config_parsing_with_salary() {
+        let yaml = r#"
+generators:
+  - type: mortgage
+    deduction_amount: 123.45
+    deduction_day: 1
+    from: main
+    to: mortgage
+  - type: salary
+    amount: 2500.00
+    day: 28
+accounts:
+  main: 10000.00
+currency_symbol: "£"
+start_date: "2025-01-01"
+"#;
+        let config: Config = serde_yaml::from_str(yaml).expect("Failed to parse YAML");
+        assert_eq!(config.generators.len(), 2);
+        let account = config.accounts.get(MAIN_ACCOUNT).unwrap();
+        assert_eq!(*account, dec!(10000.00));
+    }
+
+    #[test]
+    fn test_run_balances_consistency() {
+        let config = create_test_accounts(1);
+        println!("Config: {:#?}", config);
+        let balances = config.accounts.clone();
+        let days = 30; // Run for 30 days
+        let history = super::run(&config, balances, days).unwrap();
+        let final_balances = history.last().expect("History should not be empty").1.clone();
+        // The sum of all balances should be zero (by design)
+        let total: Decimal = final_balances.values().copied().sum();
+        assert_eq!(total, Decimal::ZERO);
+        assert_eq!(final_balances[MAIN_ACCOUNT], dec!(10000.00) + dec!(2000.00));
+        assert_eq!(history.len(), days as usize, "History should have one entry per day");
+    }
+
+    #[test]
+    fn test_run_final_balances_after_salary_and_mortgage() {
+        let config = create_test_accounts(1);
+        let balances = config.accounts.clone();
+        let days = 6; // On day 6, salary is paid
+        let history = super::run(&config, balances, days).unwrap();
+        let final_balances = &history.last().unwrap().1;
+        // Salary should be added on day 6
+        assert_eq!(final_balances[MAIN_ACCOUNT], dec!(10000.00) + dec!(2000.00));
+    }
+
+    #[test]
+    fn test_run_mortgage_deduction_applied() {
+        let config = create_test_accounts(3);
+        let balances = config.accounts.clone();
+        let days = 3; // On day 3, mortgage is deducted
+        let history = super::run(&config, balances, days).unwrap();
+        let final_balances = &history.last().unwrap().1;
+        // Mortgage should be deducted on day 3
+        assert_eq!(final_balances[MAIN_ACCOUNT], dec!(10000.00) - dec!(123.45));
+    }
+
+    #[test]
+    fn test_run_balances_sum_to_zero_each_day() {
+        let config = create_test_accounts(1);
+        let balances = config.accounts.clone();
+        let days = 15;
+        let history = super::run(&config, balances, days).unwrap();
+        for (date, balances) in history {
+            let total: Decimal = balances.values().copied().sum();
+            assert_eq!(total, Decimal::ZERO, "Balances do not sum to zero on {date}");
+        }
+    }
+
+    #[test]
+    fn test_run_salary_paid_on_correct_day() {
+        let config = create_test_accounts(15);
+        let balances = config.accounts.clone();
+        let days = 10;
+        let history = super::run(&config, balances, days).unwrap();
+        // Salary is paid on day 6, so check balance before and after
+        // get the salary day from config
+        assert!(config.generators.len() > 2, "Config should have at least three transactions");
+        assert!(matches!(config.generators[2].generator, Generator::Salary { .. }), "Third transaction should be a Salary transaction");
+        let salary_day = if let Generator::Salary { schedule: Schedule::Monthly { day: DayOfMonth::Day(day) }, .. } = &config.generators[2].generator {
+            *day
+        } else {
+            panic!("Expected third transaction to be a Salary transaction");
+        };
+        assert_eq!(salary_day, 6, "Salary day should be 6");
+        let before = &history[salary_day as usize - 3]; // day 5
+        let after = &history[salary_day as usize - 2];        
+        assert_eq!(before.0, chrono::NaiveDate::from_ymd_opt(2025, 1, 5).unwrap());
+        assert_eq!(after.0, chrono::NaiveDate::from_ymd_opt(2025, 1, 6).unwrap());
+        // Check salary was paid correctly
+        let before_salary  = &before.1[MAIN_ACCOUNT]; // day 5
+        let after_salary = &after.1[MAIN_ACCOUNT];  // day 6
+        // print history
+        println!("History: {:#?}", history);
+        assert_eq!(
+            *after_salary,
+            *before_salary + dec!(2000.00),
+            "Salary not paid correctly on salary day"
+        );
+    }    
+
+
+    #[test]
+    fn test_run_multiple_transactions_same_day() {
+        let mut config = create_test_accounts(3);
+        config.generators.push(Generator::Salary {
+            amount: dec!(500.00),
+            schedule: Schedule::monthly(3),
+            to: MAIN_ACCOUNT.to_string(),
+            income_account: SALARY_INCOME.to_string(),
+            annual_increase_percent: Decimal::ZERO,
+            increase_month: Month::January,
+            raises: vec![],
+            gross_annual: None,
+            student_loans: vec![],
+            tax_account: INCOME_TAX.to_string(),
+            ni_account: NATIONAL_INSURANCE.to_string(),
+            student_loan_account: STUDENT_LOAN.to_string(),
+            payroll_giving_percent: Decimal::ZERO,
+            payroll_giving_account: CHARITY_EXPENDITURE.to_string(),
+        }.into());
+        let balances = config.accounts.clone();
+        let days = 3;
+        let history = super::run(&config, balances, days).unwrap();
+        let final_balances = &history.last().unwrap().1;
+        // On day 3, both mortgage and salary should be applied
+        assert_eq!(final_balances[MAIN_ACCOUNT], dec!(10000.00) - dec!(123.45) + dec!(500.00));
+    }
+
+    #[test]
+    fn test_run_salary_to_different_account() {
+        let mut config = create_test_accounts(6);
+        // Add a new account and pay salary to it
+        let alt_account = "alt_account";
+        let mut accounts = config.accounts.clone();
+        accounts.insert(alt_account.to_string(), dec!(0.00));
+        config.accounts = accounts;
+        config.generators[2] = Generator::Salary {  // Fix: index 2 is the Salary transaction
+            amount: dec!(2000.00),
+            schedule: Schedule::monthly(6),
+            to: alt_account.to_string(),
+            income_account: SALARY_INCOME.to_string(),
+            annual_increase_percent: Decimal::ZERO,
+            increase_month: Month::January,
+            raises: vec![],
+            gross_annual: None,
+            student_loans: vec![],
+            tax_account: INCOME_TAX.to_string(),
+            ni_account: NATIONAL_INSURANCE.to_string(),
+            student_loan_account: STUDENT_LOAN.to_string(),
+            payroll_giving_percent: Decimal::ZERO,
+            payroll_giving_account: CHARITY_EXPENDITURE.to_string(),
+        }.into();
+        
+        let balances = config.accounts.clone();
+        let days = 6;
+        let history = super::run(&config, balances, days).unwrap();
+        let final_balances = &history.last().unwrap().1;
+        
+        assert_eq!(final_balances[alt_account], dec!(2000.00));
+        assert_eq!(final_balances[MAIN_ACCOUNT], dec!(10000.00) -dec!(123.45)); // mortgage deducted
+    }
+
+    #[test]
+    fn test_transfer_between_accounts() {
+        let mut config = create_test_accounts(10); // No mortgage/salary on test day
+        let savings_account = "savings";
+        
+        // Add savings account
+        config.accounts.insert(savings_account.to_string(), dec!(0.00));
+        
+        // Add transfer transaction
+        config.generators.push(Generator::Transfer {
+            amount: dec!(500.00),
+            schedule: Schedule::monthly(5),
+            from: MAIN_ACCOUNT.to_string(),
+            to: savings_account.to_string(),
+            percentage: None,
+            of: None,
+        }.into());
+        
+        let (next, _) = compute_next_day_balances(
+            &config,
+            &config.accounts.clone().into(),
+            chrono::NaiveDate::from_ymd_opt(2025, 1, 5).unwrap(),
+            &State::default(),
+        ).unwrap();
+        
+        assert_eq!(next[MAIN_ACCOUNT], dec!(10000.00) - dec!(500.00));
+        assert_eq!(next[savings_account], dec!(500.00));
+    }
+
+    #[test]
+    fn test_transfer_not_on_transfer_day() {
+        let mut config = create_test_accounts(10);
+        let savings_account = "savings";
+        
+        config.accounts.insert(savings_account.to_string(), dec!(0.00));
+        config.generators.push(Generator::Transfer {
+            amount: dec!(500.00),
+            schedule: Schedule::monthly(7),
+            from: MAIN_ACCOUNT.to_string(),
+            to: savings_account.to_string(),
+            percentage: None,
+            of: None,
+        }.into());
+        
+        let (next, _) = compute_next_day_balances(
+            &config,
+            &config.accounts.clone().into(),
+            chrono::NaiveDate::from_ymd_opt(2025, 1, 5).unwrap(), // Not transfer day
+            &State::default(),
+        ).unwrap();
+        
+        // No transfer should occur
+        assert_eq!(next[MAIN_ACCOUNT], dec!(10000.00));
+        assert_eq!(next[savings_account], dec!(0.00));
+    }
+
+    #[test]
+    fn test_multiple_transfers_same_day() {
+        let mut config = create_test_accounts(10);
+        let savings_account = "savings";
+        let investment_account = "investments";
+        
+        config.accounts.insert(savings_account.to_string(), dec!(0.00));
+        config.accounts.insert(investment_account.to_string(), dec!(0.00));
+        
+        config.generators.push(Generator::Transfer {
+            amount: dec!(300.00),
+            schedule: Schedule::monthly(5),
+            from: MAIN_ACCOUNT.to_string(),
+            to: savings_account.to_string(),
+            percentage: None,
+            of: None,
+        }.into());
+        
+        config.generators.push(Generator::Transfer {
+            amount: dec!(200.00),
+            schedule: Schedule::monthly(5),
+            from: MAIN_ACCOUNT.to_string(),
+            to: investment_account.to_string(),
+            percentage: None,
+            of: None,
+        }.into());
+        
+        let (next, _) = compute_next_day_balances(
+            &config,
+            &config.accounts.clone().into(),
+            chrono::NaiveDate::from_ymd_opt(2025, 1, 5).unwrap(),
+            &State::default(),
+        ).unwrap();
+        
+        assert_eq!(next[MAIN_ACCOUNT], dec!(10000.00) - dec!(300.00) - dec!(200.00));
+        assert_eq!(next[savings_account], dec!(300.00));
+        assert_eq!(next[investment_account], dec!(200.00));
+    }
+
+    #[test]
+    fn test_transfer_with_salary_and_mortgage_same_day() {
+        let mut config = create_test_accounts(7); // Mortgage and salary on day 7
+        let savings_account = "savings";
+        
+        config.accounts.insert(savings_account.to_string(), dec!(0.00));
+        
+        // Change existing transactions to day 7
+        config.generators[0] = Generator::Mortgage {
+            deduction_amount: dec!(123.45),
+            schedule: Schedule::monthly(7),
+            from: MAIN_ACCOUNT.to_string(),
+            to: MORTGAGE_ACCOUNT.to_string(),
+        }.into();
+        config.generators[1] = Generator::Salary {
+            amount: dec!(2000.00),
+            schedule: Schedule::monthly(7),
+            to: MAIN_ACCOUNT.to_string(),
+            income_account: SALARY_INCOME.to_string(),
+            annual_increase_percent: Decimal::ZERO,
+            increase_month: Month::January,
+            raises: vec![],
+            gross_annual: None,
+            student_loans: vec![],
+            tax_account: INCOME_TAX.to_string(),
+            ni_account: NATIONAL_INSURANCE.to_string(),
+            student_loan_account: STUDENT_LOAN.to_string(),
+            payroll_giving_percent: Decimal::ZERO,
+            payroll_giving_account: CHARITY_EXPENDITURE.to_string(),
+        }.into();
+        
+        // Add transfer on same day
+        config.generators.push(Generator::Transfer {
+            amount: dec!(500.00),
+            schedule: Schedule::monthly(7),
+            from: MAIN_ACCOUNT.to_string(),
+            to: savings_account.to_string(),
+            percentage: None,
+            of: None,
+        }.into());
+        
+        let (next, _) = compute_next_day_balances(
+            &config,
+            &config.accounts.clone().into(),
+            chrono::NaiveDate::from_ymd_opt(2025, 1, 7).unwrap(),
+            &State::default(),
+        ).unwrap();
+        
+        // Main account: start + salary - mortgage - transfer
+        assert_eq!(next[MAIN_ACCOUNT], dec!(10000.00) + dec!(2000.00) - dec!(123.45) - dec!(500.00));
+        assert_eq!(next[savings_account], dec!(500.00));
+    }
+
+    #[test]
+    fn test_config_parsing_with_transfer() {
+        let yaml = r#"
+generators:
+  - type: transfer
+    amount: 250.00
+    day: 10
+    from: main
+    to: savings
+accounts:
+  main: 5000.00
+  savings: 0.00
+currency_symbol: "£"
+start_date: "2025-01-01"
+"#;
+        let config: Config = serde_yaml::from_str(yaml).expect("Failed to parse YAML");
+        assert_eq!(config.generators.len(), 1);
+        
+        if let Generator::Transfer { amount, schedule, from, to, percentage, of } = &config.generators[0].generator {
+            assert_eq!(*amount, dec!(250.00));
+            assert_eq!((percentage, of), (&None, &None));
+            assert_eq!(*schedule, Schedule::monthly(10));
+            assert_eq!(from, "main");
+            assert_eq!(to, "savings");
+        } else {
+            panic!("Expected Transfer transaction");
+        }
+    }
+
+    #[test]
+    fn test_weekly_transfer_and_quarterly_interest_schedules() {
+        let yaml = r#"
+generators:
+  - type: transfer
+    amount: 50.00
+    schedule:
+      frequency: weekly
+      weekday: fri
+    from: main
+    to: groceries
+  - type: interest
+    rate: 4.0
+    schedule:
+      frequency: quarterly
+      month: january
+      day: 31
+    account: main
+accounts:
+  main: 1000.00
+  groceries: 0.00
+"#;
+        let mut config: Config = serde_yaml::from_str(yaml).expect("Failed to parse YAML");
+        config.accounts = add_opening_balances(&add_default_accounts(&config.accounts));
+        let balances = config.accounts.clone();
+        let history = super::run(&config, balances, 31).unwrap();
+        let final_balances = &history.last().unwrap().1;
+        // Fridays in January 2025 after the 1st: 3rd, 10th, 17th, 24th, 31st
+        assert_eq!(final_balances["groceries"], dec!(250.00));
+        // Quarterly interest at 4% / 4 on the balance after the last grocery transfer
+        let interest = (dec!(750.00) * dec!(0.01)).round_dp(2);
+        assert_eq!(final_balances[MAIN_ACCOUNT], dec!(750.00) + interest);
+    }
+
+    #[test]
+    fn test_weekday_shorthand_and_fortnightly_schedules() {
+        let yaml = r#"
+start_date: 2025-01-01
+generators:
+  - {type: transfer, name: Allowance, amount: 20.00, day: friday, from: main, to: cash}
+  - {type: transfer, name: Cleaner, amount: 45.00, schedule: {frequency: fortnightly, weekday: tue}, from: main, to: cleaner}
+accounts:
+  main: 1000.00
+  cash: 0.00
+  cleaner: 0.00
+"#;
+        let mut config: Config = serde_yaml::from_str(yaml).expect("Failed to parse YAML");
+        config.accounts = add_opening_balances(&add_default_accounts(&config.accounts));
+        let history = super::run(&config, config.accounts.clone(), 31).unwrap();
+        let final_balances = &history.last().unwrap().1;
+        // Every Friday from the 3rd, and every other Tuesday from the 7th: the 7th and 21st
+        assert_eq!(final_balances["cash"], dec!(100.00));
+        assert_eq!(final_balances["cleaner"], dec!(90.00));
+        // By the 7th, one allowance and the first clean have gone
+        let after_first_week = &history.iter().find(|(date, _)| date.day() == 7).unwrap().1;
+        assert_eq!(after_first_week[MAIN_ACCOUNT], dec!(935.00));
+    }
+
+    #[test]
+    fn test_month_end_day_specifiers() {
+        let yaml = r#"
+generators:
+  - type: salary
+    amount: 2000.00
+    day: last_working_day
+  - type: transfer
+    amount: 100.00
+    day: 31
+    to: bills
+accounts:
+  main: 0.00
+  bills: 0.00
+"#;
+        let mut config: Config = serde_yaml::from_str(yaml).expect("Failed to parse YAML");
+        config.accounts = add_opening_balances(&add_default_accounts(&config.accounts));
+        let balances = config.accounts.clone();
+        let history = super::run(&config, balances, 160).unwrap();
+        let on = |y, m, d| {
+            let date = chrono::NaiveDate::from_ymd_opt(y, m, d).unwrap();
+            history.iter().find(|(day, _)| *day == date).unwrap().1.clone()
+        };
+        // 31 May 2025 is a Saturday so salary lands on Friday 30th
+        assert_eq!(on(2025, 5, 29)[SALARY_INCOME], dec!(-8000.00));
+        assert_eq!(on(2025, 5, 30)[SALARY_INCOME], dec!(-10000.00));
+        // Day 31 bill still fires in February and April
+        assert_eq!(on(2025, 2, 28)["bills"], dec!(200.00));
+        assert_eq!(on(2025, 4, 30)["bills"], dec!(400.00));
+    }
+
+    #[test]
+    fn test_interest_calculation() {
+        let mut config = create_test_accounts(5); // Mortgage on day 5, salary on day 6
+        // Clear existing interest transaction and add a new one for day 10
+        config.generators = vec![
+            Generator::Mortgage {
+                deduction_amount: dec!(123.45),
+                schedule: Schedule::monthly(5),
+                from: MAIN_ACCOUNT.to_string(),
+                to: MORTGAGE_ACCOUNT.to_string(),
+            }.into(),
+            Generator::Interest {
+                rate: dec!(6.0), // 6% annual rate
+                schedule: Schedule::monthly(10),
+                account: MORTGAGE_ACCOUNT.to_string(),
+                income_account: MORTGAGE_INCOME.to_string(),
+                month: None, // Monthly interest
+                method: InterestMethod::BalanceOnDay,
+                rate_schedule: vec![],
+                base: None,
+                rate_volatility: Decimal::ZERO,
+            }.into(),
+        ];
+        
+        let (next, _) = compute_next_day_balances(
+            &config,
+            &config.accounts.clone().into(),
+            chrono::NaiveDate::from_ymd_opt(2025, 1, 10).unwrap(),
+            &State::default(),
+        ).unwrap();
+        
+        // Calculate expected interest: 500000 * (6% / 12 / 100) = 500000 * 0.005 = 2500
+        let expected_interest = dec!(-500000.00) * (dec!(6.0) / dec!(12) / dec!(100));
+        assert_eq!(expected_interest, dec!(-2500.00));
+        
+        // Mortgage balance should increase by interest
+        assert_eq!(next[MORTGAGE_ACCOUNT], dec!(-500000.00) + expected_interest);
+        
+    }
+
+    #[test]
+    fn test_interest_rate_schedule() {
+        let yaml = r#"
+generators:
+  - type: interest
+    day: 1
+    account: mortgage
+    rate_schedule:
+      - from: 2025-01-01
+        rate: 6.0
+      - from: 2025-03-01
+        rate: 3.0
+accounts:
+  mortgage: -100000.00
+"#;
+        let mut config: Config = serde_yaml::from_str(yaml).expect("Failed to parse YAML");
+        config.accounts = add_opening_balances(&add_default_accounts(&config.accounts));
+        let history = super::run(&config, config.accounts.clone(), 90).unwrap();
+        let on = |m, d| {
+            let date = chrono::NaiveDate::from_ymd_opt(2025, m, d).unwrap();
+            history.iter().find(|(day, _)| *day == date).unwrap().1.clone()
+        };
+        // 0.5% a month in February, then 0.25% from March
+        assert_eq!(on(2, 1)[MORTGAGE_ACCOUNT], dec!(-100500.00));
+        assert_eq!(on(3, 1)[MORTGAGE_ACCOUNT], dec!(-100751.25));
+    }
+
+    #[test]
+    fn test_interest_compounding_modes() {
+        let interest_on_feb_1 = |compounding: &str| {
+            let yaml = format!(r#"
+generators:
+  - type: interest
+    rate: 5.0
+    day: 1
+    account: mortgage
+    compounding: {compounding}
+accounts:
+  mortgage: -100000.00
+"#);
+            let mut config: Config = serde_yaml::from_str(&yaml).expect("Failed to parse YAML");
+            config.accounts = add_opening_balances(&add_default_accounts(&config.accounts));
+            let history = super::run(&config, config.accounts.clone(), 31).unwrap();
+            dec!(-100000.00) - history.last().unwrap().1[MORTGAGE_ACCOUNT]
+        };
+        // A twelfth of a year's interest, against 31 days accrued daily
+        assert_eq!(interest_on_feb_1("monthly_snapshot"), dec!(416.67));
+        assert_eq!(interest_on_feb_1("daily_accrual"), dec!(424.66));
+    }
+
+    #[test]
+    fn test_interest_calculation_annual() {
+        let mut config = create_test_accounts(5); // Mortgage on day 5, salary on day 6
+        // Clear existing interest transaction and add a new one for annual interest in January
+        config.generators = vec![
+            Generator::Mortgage {
+                deduction_amount: dec!(123.45),
+                schedule: Schedule::monthly(5),
+                from: MAIN_ACCOUNT.to_string(),
+                to: MORTGAGE_ACCOUNT.to_string(),
+            }.into(),
+            Generator::Interest {
+                rate: dec!(6.0), // 6% annual rate
+                schedule: Schedule::monthly(15),
+                account: MORTGAGE_ACCOUNT.to_string(),
+                income_account: MORTGAGE_INCOME.to_string(),
+                month: Some(chrono::Month::January), // Annual interest paid in January
+                method: InterestMethod::BalanceOnDay,
+                rate_schedule: vec![],
+                base: None,
+                rate_volatility: Decimal::ZERO,
+            }.into(),
+        ];
+        
+        // Test that interest is paid in January
+        let (next_jan, _) = compute_next_day_balances(
+            &config,
+            &config.accounts.clone().into(),
+            chrono::NaiveDate::from_ymd_opt(2025, 1, 15).unwrap(), // January 15th
+            &State::default(),
+        ).unwrap();
+        
+        // Calculate expected interest: 500000 * (6% / 100) = 500000 * 0.005 = 2500
+        // Note: Still using monthly calculation even for annual payment
+        let expected_interest = dec!(-500000.00) * (dec!(6.0) / dec!(100));
+        assert_eq!(expected_interest, dec!(-30000.00));
+        
+        // Mortgage balance should increase by interest in January
+        assert_eq!(next_jan[MORTGAGE_ACCOUNT], dec!(-500000.00) + expected_interest);
+        
+        // Test that interest is NOT paid in February on the same day
+        let (next_feb, _) = compute_next_day_balances(
+            &config,
+            &config.accounts.clone().into(),
+            chrono::NaiveDate::from_ymd_opt(2025, 2, 15).unwrap(), // February 15th
+            &State::default(),
+        ).unwrap();
+        
+        // Mortgage balance should remain unchanged in February
+        assert_eq!(next_feb[MORTGAGE_ACCOUNT], dec!(-500000.00)); // No interest added
+        
+        // Test that interest is NOT paid in March on the same day
+        let (next_mar, _) = compute_next_day_balances(
+            &config,
+            &config.accounts.clone().into(),
+            chrono::NaiveDate::from_ymd_opt(2025, 3, 15).unwrap(), // March 15th
+            &State::default(),
+        ).unwrap();
+        
+        // Mortgage balance should remain unchanged in March
+        assert_eq!(next_mar[MORTGAGE_ACCOUNT], dec!(-500000.00)); // No interest added
+    }
+
+    #[test]
+    fn test_mortgage_payment_limited_by_available_balance() {
+        let mut config = create_test_accounts_with_main_balance(5, Some(dec!(100.0)));
+        // Set up a scenario where the mortgage payment exceeds the available balance
+        config.generators[0] = Generator::Mortgage {
+            deduction_amount: dec!(500.00), // Try to deduct £500
+            schedule: Schedule::monthly(5),
+            from: MAIN_ACCOUNT.to_string(),
+            to: MORTGAGE_ACCOUNT.to_string(),
+        }.into();
+        
+        let (next, _) = compute_next_day_balances(
+            &config,
+            &config.accounts.clone().into(),
+            chrono::NaiveDate::from_ymd_opt(2025, 1, 5).unwrap(),
+            &State::default(),
+        ).unwrap();
+        
+        // Should only deduct the available £100, leaving balance at zero
+        assert_eq!(next[MAIN_ACCOUNT], dec!(0.00));
+        // Mortgage account should only receive the actual deduction amount
+        let original_mortgage = config.accounts.get(MORTGAGE_ACCOUNT).unwrap();
+        let interest = ((dec!(-500000.00)+dec!(100)) * (dec!(5.0) / dec!(12) / dec!(100))).round_dp(2);
+        assert_eq!(next[MORTGAGE_ACCOUNT], *original_mortgage + dec!(100.00) + interest);
+    }
+
+    #[test]
+    fn test_mortgage_payment_with_negative_balance() {
+        // Set up a scenario where the account already has a negative balance
+        let mut config = create_test_accounts_with_main_balance(5, Some(dec!(-50.00)));
+        config.generators[0] = Generator::Mortgage {
+            deduction_amount: dec!(200.00),
+            schedule: Schedule::monthly(5),
+            from: MAIN_ACCOUNT.to_string(),
+            to: MORTGAGE_ACCOUNT.to_string(),
+        }.into();
+        
+        let (next, _) = compute_next_day_balances(
+            &config,
+            &config.accounts.clone().into(),
+            chrono::NaiveDate::from_ymd_opt(2025, 1, 5).unwrap(),
+            &State::default(),
+        ).unwrap();
+        
+        // Should not deduct anything when balance is already negative
+        assert_eq!(next[MAIN_ACCOUNT], dec!(-50.00)); // No change
+        // Mortgage account should not receive any payment
+        let original_mortgage = config.accounts.get(MORTGAGE_ACCOUNT).unwrap();
+        // work out interest based on original mortgage balance
+        let interest = (dec!(-500000.00) * (dec!(5.0) / dec!(12) / dec!(100))).round_dp(2);
+        assert_eq!(next[MORTGAGE_ACCOUNT], *original_mortgage + interest);
+
+    }
+
+    #[test]
+    fn test_tithe_calculation_basic() {
+        let mut config = create_test_accounts(15); // No mortgage/salary on test day
+        
+        // Add tithe transaction
+        config.generators.push(Generator::Tithe {
+            percentage: dec!(10.0), // 10% tithe
+            basis: TitheBasis::Salary,
+            schedule: Schedule::monthly(10),
+            from: MAIN_ACCOUNT.to_string(),
+            to: CHARITY_EXPENDITURE.to_string(),
+            recipients: Vec::new(),
+            of: None,
+            gift_aid: false,
+            gift_aid_account: GIFT_AID.to_string(),
+            gift_aid_income_account: GIFT_AID_INCOME.to_string(),
+        }.into());
+        
+        // Simulate running for 10 days with salary accumulation
+        let balances = config.accounts.clone();
+        let history = super::run(&config, balances, 10).unwrap();
+        
+        // Get balances on day 10 (when tithe is paid)
+        let day_10_balances = &history[9].1; // 0-indexed, so day 10 is index 9
+        
+        // Salary should be paid on day 6, so by day 10 we should have one salary payment
+        // Tithe should be 10% of £2000 = £200
+        let expected_tithe = dec!(2000.00) * dec!(10.0) / dec!(100);
+        assert_eq!(expected_tithe, dec!(200.00));
+        
+        // Main account should have: initial + salary - tithe
+        assert_eq!(day_10_balances[MAIN_ACCOUNT], dec!(10000.00) + dec!(2000.00) - expected_tithe);
+        
+        // Charity account should have the tithe amount
+        assert_eq!(day_10_balances[CHARITY_EXPENDITURE], expected_tithe);
+    }
+
+    #[test]
+    fn test_tithe_multiple_salaries() {
+        // Create a simple config without mortgage/interest transactions to avoid conflicts
+        let accounts = HashMap::from([
+            (MAIN_ACCOUNT.to_string(), dec!(10000.00)),
+        ]);
+        let accounts_with_defaults = super::add_default_accounts(&accounts);
+        let accounts_with_opening = add_opening_balances(&accounts_with_defaults);
+        
+        let config = Config {
+            generators: vec![
+                Generator::Salary {
+                    amount: dec!(2000.00),
+                    schedule: Schedule::monthly(6),
+                    to: MAIN_ACCOUNT.to_string(),
+                    income_account: SALARY_INCOME.to_string(),
+                    annual_increase_percent: Decimal::ZERO,
+                    increase_month: Month::January,
+                    raises: vec![],
+                    gross_annual: None,
+                    student_loans: vec![],
+                    tax_account: INCOME_TAX.to_string(),
+                    ni_account: NATIONAL_INSURANCE.to_string(),
+                    student_loan_account: STUDENT_LOAN.to_string(),
+                    payroll_giving_percent: Decimal::ZERO,
+                    payroll_giving_account: CHARITY_EXPENDITURE.to_string(),
+                }.into(),
+                Generator::Salary {
+                    amount: dec!(1500.00),
+                    schedule: Schedule::monthly(15),
+                    to: MAIN_ACCOUNT.to_string(),
+                    income_account: SALARY_INCOME.to_string(),
+                    annual_increase_percent: Decimal::ZERO,
+                    increase_month: Month::January,
+                    raises: vec![],
+                    gross_annual: None,
+                    student_loans: vec![],
+                    tax_account: INCOME_TAX.to_string(),
+                    ni_account: NATIONAL_INSURANCE.to_string(),
+                    student_loan_account: STUDENT_LOAN.to_string(),
+                    payroll_giving_percent: Decimal::ZERO,
+                    payroll_giving_account: CHARITY_EXPENDITURE.to_string(),
+                }.into(),
+                Generator::Tithe {
+                    percentage: dec!(10.0), // 10% tithe
+                    basis: TitheBasis::Salary,
+                    schedule: Schedule::monthly(20),
+                    from: MAIN_ACCOUNT.to_string(),
+                    to: CHARITY_EXPENDITURE.to_string(),
+                    recipients: Vec::new(),
+                    of: None,
+                    gift_aid: false,
+                    gift_aid_account: GIFT_AID.to_string(),
+                    gift_aid_income_account: GIFT_AID_INCOME.to_string(),
+                }.into(),
+            ],
+            accounts: accounts_with_opening,
+            currency_symbol: "£".to_string(),
+            start_date: chrono::NaiveDate::from_ymd_opt(2025, 1, 1).unwrap(),
+            ..Default::default()
+        };
+        
+        let balances = config.accounts.clone();
+        let history = super::run(&config, balances, 20).unwrap();
+        
+        // Get balances on day 20
+        let day_20_balances = &history[19].1;
+        
+        // Total salary: £2000 (day 6) + £1500 (day 15) = £3500
+        // Tithe: 10% of £3500 = £350
+        let total_salary = dec!(2000.00) + dec!(1500.00);
+        let expected_tithe = total_salary * dec!(10.0) / dec!(100);
+        assert_eq!(expected_tithe, dec!(350.00));
+        
+        // Main account should have: initial + total_salary - tithe
+        assert_eq!(day_20_balances[MAIN_ACCOUNT], dec!(10000.00) + total_salary - expected_tithe);
+        
+        // Charity account should have the tithe amount
+        assert_eq!(day_20_balances[CHARITY_EXPENDITURE], expected_tithe);
+    }
+
+    #[test]
+    fn test_tithes_accumulate_separately() {
+        let mut config = create_test_accounts(30);
+        
+        // Add multiple tithe transactions, each giving on all the salary since it last paid
+        config.generators.push(Generator::Tithe {
+            percentage: dec!(10.0),
+            basis: TitheBasis::Salary,
+            schedule: Schedule::monthly(10),
+            from: MAIN_ACCOUNT.to_string(),
+            to: CHARITY_EXPENDITURE.to_string(),
+            recipients: Vec::new(),
+            of: None,
+            gift_aid: false,
+            gift_aid_account: GIFT_AID.to_string(),
+            gift_aid_income_account: GIFT_AID_INCOME.to_string(),
+        }.into());
+        
+        config.generators.push(Generator::Salary {
+            amount: dec!(1000.00),
+            schedule: Schedule::monthly(15),
+            to: MAIN_ACCOUNT.to_string(),
+            income_account: SALARY_INCOME.to_string(),
+            annual_increase_percent: Decimal::ZERO,
+            increase_month: Month::January,
+            raises: vec![],
+            gross_annual: None,
+            student_loans: vec![],
+            tax_account: INCOME_TAX.to_string(),
+            ni_account: NATIONAL_INSURANCE.to_string(),
+            student_loan_account: STUDENT_LOAN.to_string(),
+            payroll_giving_percent: Decimal::ZERO,
+            payroll_giving_account: CHARITY_EXPENDITURE.to_string(),
+        }.into());
+        
+        config.generators.push(Generator::Tithe {
+            percentage: dec!(10.0),
+            basis: TitheBasis::Salary,
+            schedule: Schedule::monthly(20),
+            from: MAIN_ACCOUNT.to_string(),
+            to: CHARITY_EXPENDITURE.to_string(),
+            recipients: Vec::new(),
+            of: None,
+            gift_aid: false,
+            gift_aid_account: GIFT_AID.to_string(),
+            gift_aid_income_account: GIFT_AID_INCOME.to_string(),
+        }.into());
+        
+        let balances = config.accounts.clone();
+        let history = super::run(&config, balances, 20).unwrap();
+        
+        // Check day 10 - should tithe on first salary only (£2000 from day 6)
+        let day_10_balances = &history[9].1;
+        let first_tithe = dec!(2000.00) * dec!(10.0) / dec!(100);
+        assert_eq!(day_10_balances[CHARITY_EXPENDITURE], first_tithe);
+        
+        // Check day 20 - the first tithe paying doesn't stop the second giving on both salaries (£3000)
+        let day_20_balances = &history[19].1;
+        let second_tithe = dec!(3000.00) * dec!(10.0) / dec!(100);
+        let total_tithe = first_tithe + second_tithe;
+        assert_eq!(day_20_balances[CHARITY_EXPENDITURE], total_tithe);
+        
+        // Main account should reflect both tithes
+        assert_eq!(day_20_balances[MAIN_ACCOUNT], 
+                   dec!(10000.00) + dec!(2000.00) + dec!(1000.00) - total_tithe);
+    }
+
+    #[test]
+    fn test_tithe_of_income_sources() {
+        let yaml = r#"
+start_date: 2025-01-01
+generators:
+  - type: salary
+    name: Day job
+    amount: 2000
+    day: 5
+  - type: salary
+    name: Freelance
+    amount: 500
+    day: 6
+    income_account: freelance_income
+  - type: rent
+    amount: 800
+    day: 7
+  - type: tithe
+    name: Church
+    percentage: 10
+    day: 8
+  - type: tithe
+    name: Freelance giving
+    percentage: 5
+    day: 8
+    of: [freelance, rental_income]
+    to: other_giving
+accounts:
+  main: 1000
+"#;
+        let config: Config = serde_yaml::from_str(yaml).unwrap();
+        let history = run(&config, all_accounts(&config), 8).unwrap();
+        let balances = &history[7].1;
+        assert_eq!(balances[CHARITY_EXPENDITURE], dec!(250.00));
+        assert_eq!(balances["other_giving"], dec!(65.00));
+    }
+
+    #[test]
+    fn test_percentage_transfers() {
+        let yaml = r#"
+start_date: 2025-01-01
+generators:
+  - type: salary
+    amount: 2000
+    day: 28
+  - type: salary
+    name: Freelance
+    amount: 500
+    day: 15
+    income_account: freelance_income
+  - type: transfer
+    name: Save a fifth
+    percentage: 20
+    day: 1
+    to: savings
+  - type: transfer
+    name: Freelance tax pot
+    percentage: 30
+    of: {income: [freelance]}
+    day: 1
+    to: tax_pot
+  - type: transfer
+    name: Sweep a tenth
+    percentage: 10
+    of: {balance: main}
+    day: 10
+    to: savings
+accounts:
+  main: 1000
+  savings: 0
+  tax_pot: 0
+"#;
+        let config: Config = serde_yaml::from_str(yaml).unwrap();
+        let history = run(&config, all_accounts(&config), 40).unwrap();
+        let on = |day: usize, account: &str| history[day - 1].1[account];
+        // Nothing was earned in December
+        assert_eq!(on(1, "savings"), Decimal::ZERO);
+        // 10% of the £1000 in main on 10 January
+        assert_eq!(on(10, "savings"), dec!(100.00));
+        // A fifth of January's £2500, and 30% of its freelance £500, on 1 February
+        assert_eq!(on(32, "savings"), dec!(600.00));
+        assert_eq!(on(32, "tax_pot"), dec!(150.00));
+        assert!(validate_config(yaml).is_empty());
+        let shown = show_parameter(&config, "save a fifth", dec!(25));
+        assert_eq!(shown, "25.00%");
+        let adjusted = with_parameter(&config, "save a fifth", dec!(25)).unwrap();
+        assert!(matches!(adjusted.generators[2].generator, Generator::Transfer { percentage: Some(p), .. } if p == dec!(25)));
+    }
+
+    #[test]
+    fn test_tithe_with_zero_salary() {
+        let mut config = create_test_accounts(20); // No salary on tithe day
+        
+        // Remove the default salary transaction
+        config.generators = vec![
+            Generator::Tithe {
+                percentage: dec!(10.0),
+                basis: TitheBasis::Salary,
+                schedule: Schedule::monthly(10),
+                from: MAIN_ACCOUNT.to_string(),
+                to: CHARITY_EXPENDITURE.to_string(),
+                recipients: Vec::new(),
+                of: None,
+                gift_aid: false,
+                gift_aid_account: GIFT_AID.to_string(),
+                gift_aid_income_account: GIFT_AID_INCOME.to_string(),
+            }.into()
+        ];
+        
+        let balances = config.accounts.clone();
+        let history = super::run(&config, balances, 10).unwrap();
+        
+        // Get balances on day 10
+        let day_10_balances = &history[9].1;
+        
+        // No salary, so no tithe should be paid
+        assert_eq!(day_10_balances[CHARITY_EXPENDITURE], dec!(0.00));
+        assert_eq!(day_10_balances[MAIN_ACCOUNT], dec!(10000.00)); // No change
+    }
+
+    #[test]
+    fn test_config_parsing_with_tithe() {
+        let yaml = r#"
+generators:
+  - type: tithe
+    percentage: 10.0
+    day: 15
+    from: main
+    to: charity_expenditure
+accounts:
+  main: 5000.00
+currency_symbol: "£"
+start_date: "2025-01-01"
+"#;
+        let config: Config = serde_yaml::from_str(yaml).expect("Failed to parse YAML");
+        assert_eq!(config.generators.len(), 1);
+        
+        if let Generator::Tithe { percentage, schedule, from, to, .. } = &config.generators[0].generator {
+            assert_eq!(*percentage, dec!(10.0));
+            assert_eq!(*schedule, Schedule::monthly(15));
+            assert_eq!(from, "main");
+            assert_eq!(to, "charity_expenditure");
+        } else {
+            panic!("Expected Tithe transaction");
+        }
+    }
+
+    #[test]
+    fn test_tithe_with_different_percentage() {
+        let mut config = create_test_accounts(15);
+        
+        config.generators.push(Generator::Tithe {
+            percentage: dec!(5.0), // 5% tithe
+            basis: TitheBasis::Salary,
+            schedule: Schedule::monthly(10),
+            from: MAIN_ACCOUNT.to_string(),
+            to: CHARITY_EXPENDITURE.to_string(),
+            recipients: Vec::new(),
+            of: None,
+            gift_aid: false,
+            gift_aid_account: GIFT_AID.to_string(),
+            gift_aid_income_account: GIFT_AID_INCOME.to_string(),
+        }.into());
+        
+        let balances = config.accounts.clone();
+        let history = super::run(&config, balances, 10).unwrap();
+        
+        let day_10_balances = &history[9].1;
+        
+        // Tithe should be 5% of £2000 = £100
+        let expected_tithe = dec!(2000.00) * dec!(5.0) / dec!(100);
+        assert_eq!(expected_tithe, dec!(100.00));
+        
+        assert_eq!(day_10_balances[CHARITY_EXPENDITURE], expected_tithe);
+        assert_eq!(day_10_balances[MAIN_ACCOUNT], dec!(10000.00) + dec!(2000.00) - expected_tithe);
+    }
+
+    #[test]
+    fn test_payments_rolled_off_weekends_and_holidays() {
+        let yaml = r#"
+generators:
+  - type: salary
+    amount: 2000.00
+    day: 25
+    adjust: previous_business_day
+  - type: transfer
+    amount: 100.00
+    day: 25
+    to: bills
+    adjust: next_business_day
+accounts:
+  main: 0.00
+  bills: 0.00
+holidays: ["2025-06-25"]
+"#;
+        let mut config: Config = serde_yaml::from_str(yaml).expect("Failed to parse YAML");
+        config.accounts = add_opening_balances(&add_default_accounts(&config.accounts));
+        let history = super::run(&config, config.accounts.clone(), 200).unwrap();
+        let on = |m, d| {
+            let date = chrono::NaiveDate::from_ymd_opt(2025, m, d).unwrap();
+            history.iter().find(|(day, _)| *day == date).unwrap().1.clone()
+        };
+        // 25 May 2025 is a Sunday followed by the spring bank holiday
+        assert_eq!(on(5, 23)[SALARY_INCOME], dec!(-10000.00));
+        assert_eq!(on(5, 26)["bills"], dec!(400.00));
+        assert_eq!(on(5, 27)["bills"], dec!(500.00));
+        // 25 June 2025 is a custom holiday
+        assert_eq!(on(6, 24)[SALARY_INCOME], dec!(-12000.00));
+        assert_eq!(on(6, 25)["bills"], dec!(500.00));
+        assert_eq!(on(6, 26)["bills"], dec!(600.00));
+    }
+
+    #[test]
+    fn test_us_holiday_calendar() {
+        let yaml = r#"
+generators:
+  - type: transfer
+    amount: 100.00
+    day: 4
+    to: bills
+    adjust: next_business_day
+accounts:
+  main: 1000.00
+  bills: 0.00
+holiday_calendar: us
+"#;
+        let mut config: Config = serde_yaml::from_str(yaml).expect("Failed to parse YAML");
+        config.accounts = add_opening_balances(&add_default_accounts(&config.accounts));
+        let history = super::run(&config, config.accounts.clone(), 200).unwrap();
+        let bills = |m, d| {
+            let date = chrono::NaiveDate::from_ymd_opt(2025, m, d).unwrap();
+            history.iter().find(|(day, _)| *day == date).unwrap().1["bills"]
+        };
+        // Independence Day 2025 is a Friday, so July's transfer waits until Monday
+        assert_eq!(bills(6, 4), dec!(600.00));
+        assert_eq!(bills(7, 4), dec!(600.00));
+        assert_eq!(bills(7, 7), dec!(700.00));
+    }
+
+    #[test]
+    fn test_expenses_by_category() {
+        let yaml = r#"
+generators:
+  - type: expense
+    amount: 60.00
+    day: 3
+    category: utilities
+  - type: expense
+    amount: 15.99
+    day: 12
+    category: subscriptions
+  - type: expense
+    amount: 25.00
+    day: 20
+    category: utilities
+accounts:
+  main: 1000.00
+"#;
+        let mut config: Config = serde_yaml::from_str(yaml).expect("Failed to parse YAML");
+        assert_eq!(
+            categories(&config.generators),
+            vec![
+                ("subscriptions".to_string(), "subscriptions_expenditure".to_string()),
+                ("utilities".to_string(), "utilities_expenditure".to_string()),
+            ]
+        );
+        let accounts = add_generator_accounts(&add_default_accounts(&config.accounts), &config.generators);
+        config.accounts = add_opening_balances(&accounts);
+        let history = super::run(&config, config.accounts.clone(), 58).unwrap();
+        let final_balances = &history.last().unwrap().1;
+        assert_eq!(final_balances["utilities_expenditure"], dec!(170.00));
+        assert_eq!(final_balances["subscriptions_expenditure"], dec!(31.98));
+        assert_eq!(final_balances[MAIN_ACCOUNT], dec!(1000.00) - dec!(201.98));
+
+        let accounts = ["utilities_expenditure".to_string()];
+        let months = report::monthly_changes(&history, &config.accounts.clone().into(), &accounts);
+        assert_eq!(months, vec![((2025, 1), vec![dec!(85.00)]), ((2025, 2), vec![dec!(85.00)])]);
+    }
+
+    #[test]
+    fn test_subscription_audit() {
+        let yaml = r#"
+generators:
+  - type: expense
+    name: Streaming
+    amount: 10.99
+    day: 3
+    category: subscriptions
+  - type: expense
+    name: Gym
+    amount: 35.00
+    day: 1
+    category: health
+    subscription: true
+  - type: expense
+    name: Newspaper
+    amount: 4.50
+    schedule:
+      frequency: weekly
+      weekday: sat
+    category: subscriptions
+  - type: expense
+    name: Electricity
+    amount: 80.00
+    day: 5
+    category: utilities
+accounts:
+  main: 5000.00
+"#;
+        let mut config: Config = serde_yaml::from_str(yaml).expect("Failed to parse YAML");
+        let accounts = add_generator_accounts(&add_default_accounts(&config.accounts), &config.generators);
+        config.accounts = add_opening_balances(&accounts);
+        let history = super::run(&config, config.accounts.clone(), 30).unwrap();
+        let audit = subscription_audit(&config, &config.accounts.clone().into(), 30, &history).unwrap();
+        let names: Vec<&str> = audit.iter().map(|cost| cost.name.as_str()).collect();
+        assert_eq!(names, vec!["Gym", "Newspaper", "Streaming"]);
+        assert_eq!(audit[0].annual_cost, dec!(420.00));
+        assert_eq!(audit[1].annual_cost, dec!(234.00));
+        assert_eq!(audit[2].annual_cost, dec!(131.88));
+        // The gym's first payment is on 1 February, outside the horizon
+        assert_eq!(audit[0].cancel_impact, dec!(0.00));
+        // Four Saturdays in January after the 1st
+        assert_eq!(audit[1].cancel_impact, dec!(18.00));
+        assert_eq!(audit[2].cancel_impact, dec!(10.99));
+    }
+
+    #[test]
+    fn test_account_inventory() {
+        let yaml = r#"
+generators:
+  - type: transfer
+    amount: 50.00
+    day: 10
+    to: savings
+accounts:
+  main: 1000.00
+  savings: 200.00
+account_details:
+  savings:
+    institution: Nationwide
+    sort_code: 07-00-93
+    last4: "1234"
+    notes: Easy access
+  premium_bonds:
+    institution: NS&I
+    url: https://www.nsandi.com
+"#;
+        let mut config: Config = serde_yaml::from_str(yaml).expect("Failed to parse YAML");
+        let balances: balances::Balances = add_opening_balances(&add_default_accounts(&config.accounts)).into();
+        config.accounts = balances.to_map();
+        let history = super::run(&config, balances.clone(), 10).unwrap();
+        let inventory = account_inventory(&config, &balances, &history);
+        let savings = inventory.iter().find(|account| account.name == "savings").unwrap();
+        assert_eq!((savings.opening, savings.closing), (dec!(200.00), dec!(250.00)));
+        assert_eq!(savings.details.reference(), "Nationwide 07-00-93 ****1234");
+        assert_eq!(savings.details.notes.as_deref(), Some("Easy access"));
+        // Accounts with details but no balance are still listed
+        let bonds = inventory.iter().find(|account| account.name == "premium_bonds").unwrap();
+        assert_eq!((bonds.opening, bonds.closing), (Decimal::ZERO, Decimal::ZERO));
+        assert_eq!(bonds.details.url.as_deref(), Some("https://www.nsandi.com"));
+    }
+
+    #[test]
+    fn test_assumptions() {
+        let yaml = r#"
+generators:
+  - type: interest
+    rate: 4.5
+    day: 1
+    rate_schedule:
+      - from: 2027-03-01
+        rate: 6.5
+accounts:
+  main: 0.00
+seed: 7
+"#;
+        let config: Config = serde_yaml::from_str(yaml).expect("Failed to parse YAML");
+        assert_eq!(
+            assumptions(&config),
+            vec!["interest on mortgage at 4.5%, 6.5% from 2027-03-01", "uncertain income sampled with seed 7"]
+        );
+    }
+
+    #[test]
+    fn test_sweep_overpayment_capped_with_overflow() {
+        let yaml = r#"
+generators:
+  - type: salary
+    amount: 3000.00
+    day: 1
+  - type: overpayment
+    sweep_above: 1000.00
+    day: 2
+    overflow_to: savings
+accounts:
+  main: 1000.00
+  savings: 0.00
+  mortgage: -50000.00
+"#;
+        let mut config: Config = serde_yaml::from_str(yaml).expect("Failed to parse YAML");
+        config.accounts = add_opening_balances(&add_default_accounts(&config.accounts));
+        let (history, state) = run_with_state(&config, config.accounts.clone(), 90).unwrap();
+        let on = |m, d| {
+            let date = chrono::NaiveDate::from_ymd_opt(2025, m, d).unwrap();
+            history.iter().find(|(day, _)| *day == date).unwrap().1.clone()
+        };
+        // Limit for 2025 is 10% of £50,000
+        assert_eq!(on(2, 2)[MORTGAGE_ACCOUNT], dec!(-47000.00));
+        assert_eq!(on(2, 2)[MAIN_ACCOUNT], dec!(1000.00));
+        assert_eq!(on(3, 2)[MORTGAGE_ACCOUNT], dec!(-45000.00));
+        assert_eq!(on(3, 2)["savings"], dec!(1000.00));
+        assert_eq!(on(3, 2)[MAIN_ACCOUNT], dec!(1000.00));
+        assert_eq!(state.overpayments[&1].total, dec!(5000.00));
+    }
+
+    #[test]
+    fn test_overpayment_brings_payoff_forward() {
+        let yaml = r#"
+generators:
+  - type: salary
+    amount: 2000.00
+    day: 1
+  - type: mortgage
+    deduction_amount: 1000.00
+    deduction_day: 2
+  - type: overpayment
+    amount: 500.00
+    day: 2
+    annual_cap_percent: 100
+accounts:
+  main: 0.00
+  mortgage: -3000.00
+"#;
+        let mut config: Config = serde_yaml::from_str(yaml).expect("Failed to parse YAML");
+        config.accounts = add_opening_balances(&add_default_accounts(&config.accounts));
+        let (history, state) = run_with_state(&config, config.accounts.clone(), 150).unwrap();
+        let impact = overpayment_impact(&config, &config.accounts.clone().into(), 150, &history, &state).unwrap();
+        let date = |m, d| chrono::NaiveDate::from_ymd_opt(2025, m, d).unwrap();
+        assert_eq!(
+            impact,
+            vec![report::OverpaymentImpact {
+                account: MORTGAGE_ACCOUNT.to_string(),
+                overpaid: dec!(1000.00),
+                payoff_with: Some(date(3, 2)),
+                payoff_without: Some(date(4, 2)),
+            }]
+        );
+    }
+
+    #[test]
+    fn test_liability_payoffs() {
+        let yaml = r#"
+generators:
+  - type: salary
+    amount: 2000.00
+    day: 1
+  - type: interest
+    rate: 12.0
+    day: 1
+    account: mortgage
+  - type: mortgage
+    deduction_amount: 1500.00
+    deduction_day: 2
+  - type: interest
+    rate: 3.0
+    day: 1
+    account: savings
+accounts:
+  main: 0.00
+  savings: 1000.00
+  mortgage: -3000.00
+"#;
+        let mut config: Config = serde_yaml::from_str(yaml).expect("Failed to parse YAML");
+        config.accounts = add_opening_balances(&add_default_accounts(&config.accounts));
+        let (history, state) = run_with_state(&config, config.accounts.clone(), 150).unwrap();
+        // 1% a month: £30 on 1 Feb, then £15.30 on 1 Mar, and the payment on 2 Mar leaves £45.30,
+        // which is interest of £0.45 on 1 Apr and cleared on 2 Apr. Savings aren't a liability.
+        assert_eq!(
+            liability_payoffs(&config, &config.accounts.clone().into(), &history, &state),
+            vec![report::LiabilityPayoff {
+                account: MORTGAGE_ACCOUNT.to_string(),
+                paid_off: Some(chrono::NaiveDate::from_ymd_opt(2025, 4, 2).unwrap()),
+                total_interest: dec!(45.75),
+                remaining_months: Some(3),
+            }]
+        );
+    }
+
+    #[test]
+    fn test_multiple_mortgages() {
+        let yaml = r#"
+generators:
+  - type: salary
+    amount: 3000.00
+    day: 1
+  - type: interest
+    rate: 12.0
+    day: 1
+    account: home_mortgage
+  - type: mortgage
+    deduction_amount: 1000.00
+    deduction_day: 2
+    to: home_mortgage
+  - type: interest
+    rate: 6.0
+    day: 1
+    account: flat_mortgage
+  - type: mortgage
+    deduction_amount: 500.00
+    deduction_day: 2
+    to: flat_mortgage
+accounts:
+  main: 0.00
+  home_mortgage: -10000.00
+  flat_mortgage: -20000.00
+"#;
+        let mut config: Config = serde_yaml::from_str(yaml).expect("Failed to parse YAML");
+        config.accounts = add_opening_balances(&add_default_accounts(&config.accounts));
+        let (history, state) = run_with_state(&config, config.accounts.clone(), 60).unwrap();
+        assert_eq!(liability_accounts(&config, &config.accounts.clone().into()), vec!["flat_mortgage", "home_mortgage"]);
+
+        let home = report::amortization(&history, &config.accounts.clone().into(), "home_mortgage", &interest_charged(&config, &state, "home_mortgage"));
+        let flat = report::amortization(&history, &config.accounts.clone().into(), "flat_mortgage", &interest_charged(&config, &state, "flat_mortgage"));
+        let february = |rows: &[report::AmortizationRow]| *rows.iter().find(|row| row.period == (2025, 2)).unwrap();
+        assert_eq!(
+            february(&home),
+            report::AmortizationRow {
+                period: (2025, 2),
+                opening: dec!(10000.00),
+                interest: dec!(100.00),
+                paid: dec!(1000.00),
+                closing: dec!(9100.00),
+            }
+        );
+        assert_eq!(
+            february(&flat),
+            report::AmortizationRow {
+                period: (2025, 2),
+                opening: dec!(20000.00),
+                interest: dec!(100.00),
+                paid: dec!(500.00),
+                closing: dec!(19600.00),
+            }
+        );
+    }
+
+    #[test]
+    fn test_roles_rename_special_accounts() {
+        let yaml = r#"
+generators:
+  - type: salary
+    amount: 2000.00
+    day: 5
+  - type: salary
+    amount: 500.00
+    day: 5
+    income_account: freelance_income
+  - type: tithe
+    percentage: 10
+    day: 10
+roles:
+  main: current_account
+  salary_income: employer
+  charity_expenditure: giving
+accounts:
+  current_account: 100.00
+"#;
+        let mut config: Config = serde_yaml::from_str(yaml).expect("Failed to parse YAML");
+        config.resolve_roles();
+        assert_eq!(config.role(MAIN_ACCOUNT), "current_account");
+        assert_eq!(config.role(MORTGAGE_ACCOUNT), MORTGAGE_ACCOUNT);
+        let balances = add_generator_accounts(&config.accounts, &config.generators);
+        config.accounts = add_opening_balances(&balances);
+        let history = super::run(&config, config.accounts.clone(), 10).unwrap();
+        let last = &history.last().unwrap().1;
+        assert_eq!(last["employer"], dec!(-2000.00));
+        assert_eq!(last["freelance_income"], dec!(-500.00));
+        assert_eq!(last["giving"], dec!(250.00));
+        assert_eq!(last["current_account"], dec!(2350.00));
+        assert!(!last.contains_key(MAIN_ACCOUNT));
+    }
+
+    #[test]
+    fn test_remortgage_switches_rate_and_payment() {
+        let yaml = r#"
+generators:
+  - type: interest
+    rate: 6.0
+    day: 1
+  - type: mortgage
+    deduction_amount: 1000.00
+    deduction_day: 2
+  - type: remortgage
+    date: 2025-03-15
+    rate: 3.0
+    payment: 800.00
+    fee: 999.00
+accounts:
+  main: 10000.00
+  mortgage: -100000.00
+"#;
+        let mut config: Config = serde_yaml::from_str(yaml).expect("Failed to parse YAML");
+        let balances = add_generator_accounts(&add_default_accounts(&config.accounts), &config.generators);
+        config.accounts = add_opening_balances(&balances);
+        let history = super::run(&config, config.accounts.clone(), 95).unwrap();
+        let on = |m, d| {
+            let date = chrono::NaiveDate::from_ymd_opt(2025, m, d).unwrap();
+            history.iter().find(|(day, _)| *day == date).unwrap().1.clone()
+        };
+        assert_eq!(on(3, 2)[MORTGAGE_ACCOUNT], dec!(-97987.48));
+        // The arrangement fee is added to the balance on the day of the switch
+        assert_eq!(on(3, 15)[MORTGAGE_ACCOUNT], dec!(-98986.48));
+        assert_eq!(on(3, 15)[DEBT_FEES], dec!(999.00));
+        // Then interest is charged at 3% and the payment drops to £800
+        assert_eq!(on(4, 1)[MORTGAGE_ACCOUNT], dec!(-99233.95));
+        assert_eq!(on(4, 2)[MORTGAGE_ACCOUNT], dec!(-98433.95));
+        assert_eq!(assumptions(&config)[1], "mortgage remortgaged on 2025-03-15 at 3%, paying 800");
+    }
+
+    #[test]
+    fn test_impact_of_removing_generators() {
+        let yaml = r#"
+generators:
+  - type: salary
+    amount: 1000.00
+    day: 1
+  - type: mortgage
+    deduction_amount: 1000.00
+    deduction_day: 2
+  - type: expense
+    name: Netflix
+    amount: 10.99
+    day: 3
+    category: subscriptions
+  - type: expense
+    name: Gym
+    amount: 389.01
+    day: 3
+    category: health
+accounts:
+  main: 400.00
+  mortgage: -2500.00
+"#;
+        let config: Config = serde_yaml::from_str(yaml).expect("Failed to parse YAML");
+        let balances: balances::Balances = add_opening_balances(&add_generator_accounts(&add_default_accounts(&config.accounts), &config.generators)).into();
+        assert_eq!(without_generators(&config, &["Spotify".to_string()]).unwrap_err(), vec!["Spotify"]);
+
+        let without = without_generators(&config, &["netflix".to_string(), "GYM".to_string()]).unwrap();
+        assert_eq!(without.generators.len(), 2);
+        let date = |m, d| chrono::NaiveDate::from_ymd_opt(2025, m, d).unwrap();
+        let before = key_metrics(&config, &balances, &super::run(&config, balances.clone(), 150).unwrap());
+        let after = key_metrics(&without, &balances, &super::run(&without, balances.clone(), 150).unwrap());
+        // The expenses leave only £600 a month for the mortgage, so it takes a month longer to clear
+        assert_eq!(
+            before,
+            vec![
+                ("main at horizon".to_string(), report::Metric::Amount(dec!(-100.00))),
+                ("Net worth at horizon".to_string(), report::Metric::Amount(dec!(-100.00))),
+                ("mortgage paid off".to_string(), report::Metric::Date(Some(date(5, 2)))),
+            ]
+        );
+        assert_eq!(
+            after,
+            vec![
+                ("main at horizon".to_string(), report::Metric::Amount(dec!(1900.00))),
+                ("Net worth at horizon".to_string(), report::Metric::Amount(dec!(1900.00))),
+                ("mortgage paid off".to_string(), report::Metric::Date(Some(date(4, 2)))),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_generator_active_window() {
+        let yaml = r#"
+generators:
+  - type: transfer
+    amount: 10.00
+    day: 1
+    to: savings
+    start_date: 2025-03-01
+    end_date: 2025-05-01
+accounts:
+  main: 100.00
+  savings: 0.00
+"#;
+        let mut config: Config = serde_yaml::from_str(yaml).expect("Failed to parse YAML");
+        config.accounts = add_opening_balances(&add_default_accounts(&config.accounts));
+        let history = super::run(&config, config.accounts.clone(), 200).unwrap();
+        // Paid on 1 March, April and May only
+        assert_eq!(history.last().unwrap().1["savings"], dec!(30.00));
+    }
+
+    #[test]
+    fn test_child_costs() {
+        let yaml = r#"
+generators: []
+children:
+  - name: Sam
+    birth_date: 2024-03-10
+    inflation: 10
+    costs:
+      - name: nursery
+        from_age: 1
+        to_age: 3
+        amount: 1000.00
+        day: 1
+accounts:
+  main: 50000.00
+"#;
+        let mut config: Config = serde_yaml::from_str(yaml).expect("Failed to parse YAML");
+        config.expand_children();
+        let balances = add_generator_accounts(&add_default_accounts(&config.accounts), &config.generators);
+        config.accounts = add_opening_balances(&balances);
+        let history = super::run(&config, config.accounts.clone(), 500).unwrap();
+        let on = |y, m, d| {
+            let date = chrono::NaiveDate::from_ymd_opt(y, m, d).unwrap();
+            history.iter().find(|(day, _)| *day == date).unwrap().1["children_expenditure"]
+        };
+        // Nursery starts at age one, then costs 10% more from age two
+        assert_eq!(on(2025, 3, 31), dec!(0.00));
+        assert_eq!(on(2025, 4, 1), dec!(1000.00));
+        assert_eq!(on(2026, 3, 31), dec!(12000.00));
+        assert_eq!(on(2026, 4, 1), dec!(13100.00));
+    }
+
+    #[test]
+    fn test_salary_growth() {
+        let yaml = r#"
+generators:
+  - type: salary
+    amount: 2000.00
+    day: 28
+    annual_increase_percent: 5
+    increase_month: april
+    raises:
+      - date: 2026-06-01
+        amount: 3000.00
+accounts:
+  main: 0.00
+"#;
+        let mut config: Config = serde_yaml::from_str(yaml).expect("Failed to parse YAML");
+        config.accounts = add_opening_balances(&add_default_accounts(&config.accounts));
+        let history = super::run(&config, config.accounts.clone(), 730).unwrap();
+        let paid_in = |y, m| {
+            let date = chrono::NaiveDate::from_ymd_opt(y, m, 28).unwrap();
+            let before = date.pred_opt().unwrap();
+            let balance = |day| history.iter().find(|(d, _)| *d == day).unwrap().1[MAIN_ACCOUNT];
+            balance(date) - balance(before)
+        };
+        assert_eq!(paid_in(2025, 3), dec!(2000.00));
+        assert_eq!(paid_in(2025, 4), dec!(2100.00));
+        assert_eq!(paid_in(2026, 4), dec!(2205.00));
+        assert_eq!(paid_in(2026, 6), dec!(3000.00));
+    }
+
+    #[test]
+    fn test_gross_salary_deductions() {
+        let yaml = r#"
+generators:
+  - type: salary
+    gross_annual: 62000
+    student_loans: [plan2]
+    day: 28
+accounts:
+  main: 0.00
+"#;
+        let mut config: Config = serde_yaml::from_str(yaml).expect("Failed to parse YAML");
+        let balances = add_generator_accounts(&add_default_accounts(&config.accounts), &config.generators);
+        config.accounts = add_opening_balances(&balances);
+        let history = super::run(&config, config.accounts.clone(), 30).unwrap();
+        let last = &history.last().unwrap().1;
+        // A twelfth of 62,000 less a twelfth of each year's deductions, with
+        // the 2024/25 Plan 2 threshold of 27,295
+        assert_eq!(last[SALARY_INCOME], dec!(-5166.67));
+        assert_eq!(last[INCOME_TAX], dec!(1019.33));
+        assert_eq!(last[NATIONAL_INSURANCE], dec!(270.88));
+        assert_eq!(last[STUDENT_LOAN], dec!(260.29));
+        assert_eq!(last[MAIN_ACCOUNT], dec!(3616.17));
+    }
+
+    #[test]
+    fn test_university_plans() {
+        let yaml = r#"
+generators: []
+start_date: 2025-01-01
+children:
+  - name: Sam
+    birth_date: 2010-05-01
+    inflation: 0
+    university:
+      termly_amount: 4000
+  - name: Jo
+    birth_date: 2015-05-01
+accounts:
+  main: 0.00
+"#;
+        let config: Config = serde_yaml::from_str(yaml).expect("Failed to parse YAML");
+        let plans = university_plans(&config);
+        // Nine terms from September 2028; the last, in April 2031, needs £36,000 saved over 75 months
+        assert_eq!(
+            plans,
+            vec![report::UniversityPlan {
+                child: "Sam".to_string(),
+                first_payment: chrono::NaiveDate::from_ymd_opt(2028, 9, 1).unwrap(),
+                total_cost: dec!(36000),
+                monthly_saving: dec!(480.00),
+            }]
+        );
+    }
+
+    #[test]
+    fn test_energy_bill_follows_price_cap() {
+        let yaml = r#"
+generators:
+  - type: energy
+    standing_charge: 0.50
+    unit_rate: 0.25
+    usage: [300, 280, 250, 200, 150, 120, 100, 100, 130, 180, 250, 300]
+    tariff_changes:
+      - from: 2025-04-01
+        unit_rate: 0.20
+    day: 10
+start_date: 2025-01-01
+accounts:
+  main: 1000.00
+"#;
+        let mut config: Config = serde_yaml::from_str(yaml).expect("Failed to parse YAML");
+        config.accounts = add_opening_balances(&add_generator_accounts(&add_default_accounts(&config.accounts), &config.generators));
+        let history = super::run(&config, config.accounts.clone(), 150).unwrap();
+        let on = |m, d| {
+            let date = chrono::NaiveDate::from_ymd_opt(2025, m, d).unwrap();
+            history.iter().find(|(day, _)| *day == date).unwrap().1["energy_expenditure"]
+        };
+        // December's bill: 31 days at 50p plus 300kWh at 25p
+        assert_eq!(on(1, 10), dec!(90.50));
+        // Then January and February, 28 days at 50p plus 280kWh at 25p
+        assert_eq!(on(3, 10), dec!(90.50) + dec!(90.50) + dec!(84.00));
+        // April is the first month at the lower rate, billed in May: 30 days at 50p plus 200kWh at 20p
+        assert_eq!(on(5, 10) - on(4, 10), dec!(55.00));
+    }
+
+    #[test]
+    fn test_investment_with_isa_allowance() {
+        let yaml = r#"
+generators:
+  - type: investment
+    amount: 8000
+    day: 10
+    to: isa
+    annual_return: 12
+    annual_cap: 20000
+    overflow_to: savings
+start_date: 2025-01-01
+accounts:
+  main: 50000.00
+  isa: 0.00
+  savings: 0.00
+"#;
+        let mut config: Config = serde_yaml::from_str(yaml).expect("Failed to parse YAML");
+        config.accounts = add_opening_balances(&add_generator_accounts(&add_default_accounts(&config.accounts), &config.generators));
+        let history = super::run(&config, config.accounts.clone(), 120).unwrap();
+        let on = |m, d, account: &str| {
+            let date = chrono::NaiveDate::from_ymd_opt(2025, m, d).unwrap();
+            history.iter().find(|(day, _)| *day == date).unwrap().1[account]
+        };
+        assert_eq!(on(1, 10, "isa"), dec!(8000));
+        // A month's growth at 12% a year is about 0.95%
+        assert_eq!(on(2, 10, "investment_growth"), dec!(-75.91));
+        // Only £4,000 of the 2024/25 allowance is left by March; the rest goes to savings
+        assert_eq!(on(3, 10, "savings"), dec!(4000));
+        assert_eq!(on(3, 10, "main"), dec!(26000));
+        // The new tax year's allowance is available in April
+        assert_eq!(on(4, 10, "savings"), dec!(4000));
+        assert_eq!(on(4, 10, "main"), dec!(18000));
+    }
+
+    #[test]
+    fn test_investment_capped_by_tax_year_allowance() {
+        let yaml = r#"
+generators:
+  - type: investment
+    amount: 8000
+    day: 10
+    to: isa
+    allowance: isa
+    overflow_to: savings
+tax:
+  years:
+    2025:
+      isa_allowance: 10000
+start_date: 2025-01-01
+accounts:
+  main: 50000.00
+  isa: 0.00
+  savings: 0.00
+"#;
+        let config: Config = serde_yaml::from_str(yaml).expect("Failed to parse YAML");
+        let history = super::run(&config, all_accounts(&config), 150).unwrap();
+        let on = |m, d, account: &str| {
+            let date = chrono::NaiveDate::from_ymd_opt(2025, m, d).unwrap();
+            history.iter().find(|(day, _)| *day == date).unwrap().1[account]
+        };
+        // The built-in £20,000 allowance for 2024/25 runs out in March
+        assert_eq!(on(3, 10, "isa"), dec!(20000));
+        assert_eq!(on(3, 10, "savings"), dec!(4000));
+        // The config lowers the allowance from 2025/26
+        assert_eq!(on(5, 10, "isa"), dec!(30000));
+        assert_eq!(on(5, 10, "savings"), dec!(10000));
+    }
+
+    #[test]
+    fn test_expense_with_mid_contract_price_rise() {
+        let yaml = r#"
+generators:
+  - type: expense
+    name: Broadband
+    amount: 40
+    day: 1
+    category: bills
+    price_rise:
+      plus: 3.9
+      month: april
+start_date: 2025-01-01
+inflation_indices:
+  cpi: 3.1
+accounts:
+  main: 1000.00
+"#;
+        let mut config: Config = serde_yaml::from_str(yaml).expect("Failed to parse YAML");
+        config.accounts = add_opening_balances(&add_generator_accounts(&add_default_accounts(&config.accounts), &config.generators));
+        let history = super::run(&config, config.accounts.clone(), 120).unwrap();
+        let on = |m, d| {
+            let date = chrono::NaiveDate::from_ymd_opt(2025, m, d).unwrap();
+            history.iter().find(|(day, _)| *day == date).unwrap().1["bills_expenditure"]
+        };
+        // £40 in February and March, then 7% more from April
+        assert_eq!(on(3, 1), dec!(80));
+        assert_eq!(on(4, 1) - on(3, 1), dec!(42.80));
+    }
+
+    #[test]
+    fn test_cashback_on_categories() {
+        let yaml = r#"
+generators:
+  - type: expense
+    amount: 400
+    day: 5
+    category: groceries
+  - type: expense
+    amount: 100
+    day: 5
+    category: fuel
+  - type: expense
+    amount: 1000
+    day: 6
+    category: rent
+  - type: cashback
+    percentage: 1.5
+    categories: [groceries, fuel]
+    day: 1
+    annual_cap: 10
+start_date: 2025-01-01
+accounts:
+  main: 5000.00
+"#;
+        let mut config: Config = serde_yaml::from_str(yaml).expect("Failed to parse YAML");
+        config.accounts = add_opening_balances(&add_generator_accounts(&add_default_accounts(&config.accounts), &config.generators));
+        let history = super::run(&config, config.accounts.clone(), 100).unwrap();
+        let on = |m, d| {
+            let date = chrono::NaiveDate::from_ymd_opt(2025, m, d).unwrap();
+            history.iter().find(|(day, _)| *day == date).unwrap().1["cashback_income"]
+        };
+        // 1.5% of January's £500 on groceries and fuel, not rent
+        assert_eq!(on(2, 1), dec!(-7.50));
+        // Only £2.50 of the year's £10 cap is left in March
+        assert_eq!(on(3, 1), dec!(-10.00));
+        assert_eq!(on(4, 1), dec!(-10.00));
+    }
+
+    #[test]
+    fn test_inflation_of_expenses_and_salary() {
+        let yaml = r#"
+generators:
+  - type: salary
+    amount: 2000
+    day: 25
+  - type: expense
+    amount: 100
+    day: 5
+    category: groceries
+  - type: expense
+    amount: 100
+    day: 5
+    category: insurance
+    index: cpi
+  - type: expense
+    amount: 100
+    day: 5
+    category: phone
+    inflation: 0
+start_date: 2025-01-01
+inflation_rate: 5
+inflation_indices:
+  cpi: 2
+accounts:
+  main: 1000.00
+"#;
+        let mut config: Config = serde_yaml::from_str(yaml).expect("Failed to parse YAML");
+        config.accounts = add_opening_balances(&add_generator_accounts(&add_default_accounts(&config.accounts), &config.generators));
+        let history = super::run(&config, config.accounts.clone(), 400).unwrap();
+        let on = |y, m, d, account: &str| {
+            let date = chrono::NaiveDate::from_ymd_opt(y, m, d).unwrap();
+            history.iter().find(|(day, _)| *day == date).unwrap().1[account]
+        };
+        let paid = |account: &str| on(2026, 1, 5, account) - on(2025, 12, 31, account);
+        assert_eq!(paid("groceries_expenditure"), dec!(105.00));
+        assert_eq!(paid("insurance_expenditure"), dec!(102.00));
+        assert_eq!(paid("phone_expenditure"), dec!(100));
+        assert_eq!(on(2025, 12, 5, "groceries_expenditure") - on(2025, 11, 30, "groceries_expenditure"), dec!(100));
+        // Salary rises with inflation in its increase month, January by default
+        assert_eq!(on(2026, 1, 31, "salary_income") - on(2025, 12, 31, "salary_income"), dec!(-2100.00));
+
+        // An index that isn't defined is found before the run, and stops it rather than panicking
+        let typo = yaml.replace("    index: cpi", "    index: cpl");
+        assert_eq!(
+            validate_config(&typo),
+            vec![validate::Problem::new(Some(14), "expense (generator 3): index follows cpl, which is not one of the inflation_indices")]
+        );
+        let mut config: Config = serde_yaml::from_str(&typo).expect("Failed to parse YAML");
+        config.accounts = add_opening_balances(&add_generator_accounts(&add_default_accounts(&config.accounts), &config.generators));
+        assert_eq!(missing_references(&config, &all_accounts(&config)).len(), 1);
+        assert_eq!(super::run(&config, config.accounts.clone(), 10).unwrap_err(), "Inflation index 'cpl' not found in inflation_indices");
+    }
+
+    #[test]
+    fn test_transfer_only_if_balance_stays_above() {
+        let yaml = r#"
+generators:
+  - type: salary
+    amount: 1500
+    day: 1
+  - type: expense
+    amount: 1200
+    day: 2
+    category: bills
+  - type: transfer
+    amount: 500
+    day: 3
+    to: savings
+    only_if_balance_above: 2000
+  - type: transfer
+    amount: 300
+    day: 4
+    from: savings
+    only_if_balance_below: 1800
+    balance_account: main
+  - type: expense
+    amount: 2000
+    day: 20
+    category: holiday
+    only_if_balance_above: 0
+start_date: 2025-01-01
+accounts:
+  main: 4000.00
+  savings: 1000.00
+"#;
+        let mut config: Config = serde_yaml::from_str(yaml).expect("Failed to parse YAML");
+        config.accounts = add_opening_balances(&add_generator_accounts(&add_default_accounts(&config.accounts), &config.generators));
+        let history = super::run(&config, config.accounts.clone(), 100).unwrap();
+        let on = |m, d, account: &str| {
+            let date = chrono::NaiveDate::from_ymd_opt(2025, m, d).unwrap();
+            history.iter().find(|(day, _)| *day == date).unwrap().1[account]
+        };
+        // January: saving 500 leaves 2300, but a 300 top-up would take main to 2600
+        assert_eq!(on(1, 4, "savings"), dec!(1500));
+        assert_eq!(on(1, 4, "main"), dec!(2300));
+        assert_eq!(on(1, 20, "main"), dec!(300));
+        // February: saving would leave 100, and the top-up keeps main under 1800
+        assert_eq!(on(2, 4, "savings"), dec!(1200));
+        assert_eq!(on(2, 4, "main"), dec!(900));
+        // The holiday isn't paid again while it would make main overdrawn
+        assert_eq!(on(4, 10, "holiday_expenditure"), dec!(2000));
+    }
+
+    #[test]
+    fn test_saye_matures_into_shares() {
+        let yaml = r#"
+generators:
+  - type: saye
+    name: Sharesave
+    amount: 250
+    day: 1
+    start: 2025-02-01
+    term_months: 12
+    option_price: 2
+    share_price: 3
+    scenarios:
+      - name: crash
+        share_price: 1.5
+    account: sharesave
+start_date: 2025-01-01
+accounts:
+  main: 5000.00
+  sharesave: 0.00
+"#;
+        let mut config: Config = serde_yaml::from_str(yaml).expect("Failed to parse YAML");
+        config.accounts = add_opening_balances(&add_generator_accounts(&add_default_accounts(&config.accounts), &config.generators));
+        let history = super::run(&config, config.accounts.clone(), 400).unwrap();
+        let on = |y, m, d, account: &str| {
+            let date = chrono::NaiveDate::from_ymd_opt(y, m, d).unwrap();
+            history.iter().find(|(day, _)| *day == date).unwrap().1[account]
+        };
+        assert_eq!(on(2026, 1, 31, "sharesave"), dec!(3000));
+        // 1,500 shares bought at £2 and sold at £3, under the capital gains allowance
+        assert_eq!(on(2026, 2, 1, "sharesave"), dec!(0));
+        assert_eq!(on(2026, 2, 1, "main"), dec!(5000) + dec!(1500.00));
+        assert_eq!(on(2026, 2, 1, "capital_gains_tax"), dec!(0));
+
+        let outcomes = share_scheme_outcomes(&config);
+        assert_eq!(outcomes.len(), 2);
+        assert_eq!((outcomes[0].scenario.as_str(), outcomes[0].maturity.gain), ("expected", dec!(1500.00)));
+        assert_eq!((outcomes[1].scenario.as_str(), outcomes[1].maturity.shares), ("crash", dec!(0)));
+    }
+
+    #[test]
+    fn test_volatile_asset_drawdown() {
+        let yaml = r#"
+generators:
+  - type: volatile_asset
+    account: crypto
+    amount: 100
+    day: 1
+    volatility: 80
+    drawdowns:
+      - date: 2025-03-15
+        percent: 40
+start_date: 2025-01-01
+accounts:
+  main: 1000.00
+  crypto: 1000.00
+"#;
+        let mut config: Config = serde_yaml::from_str(yaml).expect("Failed to parse YAML");
+        config.accounts = add_opening_balances(&add_generator_accounts(&add_default_accounts(&config.accounts), &config.generators));
+        let history = super::run(&config, config.accounts.clone(), 100).unwrap();
+        let on = |m, d| {
+            let date = chrono::NaiveDate::from_ymd_opt(2025, m, d).unwrap();
+            history.iter().find(|(day, _)| *day == date).unwrap().1["crypto"]
+        };
+        // With no seed and no mean return the value only changes by purchases and the drawdown
+        assert_eq!(on(3, 14), dec!(1200));
+        assert_eq!(on(3, 15), dec!(720.00));
+        assert_eq!(on(4, 1), dec!(820.00));
+
+        // Sampled returns move the value every day
+        config.seed = Some(1);
+        let history = super::run(&config, config.accounts.clone(), 10).unwrap();
+        assert_ne!(history[3].1["crypto"], history[4].1["crypto"]);
+    }
+
+    #[test]
+    fn test_sweep_to_target_and_back_to_floor() {
+        let yaml = r#"
+generators:
+  - type: salary
+    amount: 2000
+    day: 1
+  - type: expense
+    amount: 3500
+    day: 10
+    start_date: 2025-03-01
+    end_date: 2025-03-31
+    category: car
+  - type: sweep
+    day: 28
+    savings: savings
+    target: 1500
+    floor: 500
+start_date: 2025-01-01
+accounts:
+  main: 2500.00
+  savings: 700.00
+"#;
+        let mut config: Config = serde_yaml::from_str(yaml).expect("Failed to parse YAML");
+        config.accounts = add_opening_balances(&add_generator_accounts(&add_default_accounts(&config.accounts), &config.generators));
+        let history = super::run(&config, config.accounts.clone(), 100).unwrap();
+        let on = |m, d, account: &str| {
+            let date = chrono::NaiveDate::from_ymd_opt(2025, m, d).unwrap();
+            history.iter().find(|(day, _)| *day == date).unwrap().1[account]
+        };
+        assert_eq!((on(1, 28, "main"), on(1, 28, "savings")), (dec!(1500), dec!(1700)));
+        assert_eq!((on(2, 28, "main"), on(2, 28, "savings")), (dec!(1500), dec!(3700)));
+        // The car bill leaves main at 0 in March, so it is topped back up to 500
+        assert_eq!((on(3, 28, "main"), on(3, 28, "savings")), (dec!(500), dec!(3200)));
+    }
+
+    #[test]
+    fn test_tithe_split_between_recipients() {
+        let yaml = r#"
+generators:
+  - type: salary
+    amount: 3000
+    day: 1
+  - type: tithe
+    name: Giving
+    percentage: 10
+    day: 2
+    recipients:
+      - name: Church
+        account: church_giving
+        percentage: 50
+      - name: Food bank
+        account: food_bank_giving
+        amount: 50
+start_date: 2025-01-01
+accounts:
+  main: 0.00
+"#;
+        let mut config: Config = serde_yaml::from_str(yaml).expect("Failed to parse YAML");
+        config.accounts = add_opening_balances(&add_generator_accounts(&add_default_accounts(&config.accounts), &config.generators));
+        let history = super::run(&config, config.accounts.clone(), 40).unwrap();
+        let (_, last) = history.last().unwrap();
+        // £300 a month: £50 to the food bank first, then half of the £250 left to the church
+        assert_eq!(last["food_bank_giving"], dec!(50));
+        assert_eq!(last["church_giving"], dec!(125.00));
+        assert_eq!(last["charity_expenditure"], dec!(125.00));
+        assert_eq!(
+            giving_accounts(&config),
+            vec![
+                ("Church".to_string(), "church_giving".to_string()),
+                ("Food bank".to_string(), "food_bank_giving".to_string()),
+                ("Giving".to_string(), "charity_expenditure".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_overdraft_costs_and_hard_limit() {
+        let yaml = r#"
+generators:
+  - type: expense
+    amount: 700
+    day: 10
+    start_date: 2025-01-01
+    end_date: 2025-01-31
+    category: car
+  - type: salary
+    amount: 1000
+    day: 20
+overdrafts:
+  main:
+    limit: 500
+    rate: 36.5
+    daily_fee: 0.50
+    monthly_fee: 5
+    hard_limit: true
+start_date: 2025-01-01
+accounts:
+  main: 0.00
+"#;
+        let mut config: Config = serde_yaml::from_str(yaml).expect("Failed to parse YAML");
+        config.accounts = add_opening_balances(&add_overdraft_accounts(
+            &add_generator_accounts(&add_default_accounts(&config.accounts), &config.generators),
+            &config.overdrafts,
+        ));
+        let (history, state) = super::run_with_state(&config, config.accounts.clone(), 40).unwrap();
+        let on = |m, d, account: &str| {
+            let date = chrono::NaiveDate::from_ymd_opt(2025, m, d).unwrap();
+            history.iter().find(|(day, _)| *day == date).unwrap().1[account]
+        };
+        // Overdrawn by 700 from the 10th to the 19th: 50p a day in fees
+        assert_eq!(on(1, 19, "debt_fees"), dec!(5.00));
+        assert_eq!(on(1, 20, "main"), dec!(295.00));
+        // 10 days at 10p a day per £100 on the balance, which the fees add to, charged with the monthly fee on 1 February
+        assert_eq!(on(2, 1, "overdraft_interest"), dec!(7.02));
+        assert_eq!(on(2, 1, "debt_fees"), dec!(10.00));
+        assert_eq!(state.overdraft_breaches, vec![("main".to_string(), chrono::NaiveDate::from_ymd_opt(2025, 1, 10).unwrap(), dec!(-700))]);
+    }
+
+    #[test]
+    fn test_payroll_giving_before_tax() {
+        let yaml = r#"
+generators:
+  - type: salary
+    name: Job
+    gross_annual: 62000
+    payroll_giving_percent: 10
+    day: 28
+start_date: 2025-01-01
+accounts:
+  main: 0.00
+"#;
+        let mut config: Config = serde_yaml::from_str(yaml).expect("Failed to parse YAML");
+        config.accounts = add_opening_balances(&add_generator_accounts(&add_default_accounts(&config.accounts), &config.generators));
+        let history = super::run(&config, config.accounts.clone(), 31).unwrap();
+        let (_, last) = history.last().unwrap();
+        // £6,200 a year given: the taxed income falls to £55,800, so 20% of 37,700 plus 40% of 5,530
+        assert_eq!(last["charity_expenditure"], dec!(516.67));
+        assert_eq!(last["income_tax"], dec!(812.67));
+        // NI is still on the full £62,000
+        assert_eq!(last["national_insurance"], dec!(270.88));
+
+        // The charity gets £6,200 either way; payroll saves 40%, Gift Aid costs 80% less 20% reclaimed
+        let costs = payroll_giving_comparison(&config);
+        assert_eq!(
+            costs,
+            vec![report::PayrollGivingCost {
+                salary: "Job".to_string(),
+                to_charity: dec!(6200.00),
+                payroll: dec!(3720.00),
+                gift_aid: dec!(3720.00),
+            }]
+        );
+    }
+
+    #[test]
+    fn test_savings_goal() {
+        let yaml = r#"
+generators:
+  - type: transfer
+    amount: 1000
+    day: 1
+    to: house_deposit
+goals:
+  - account: house_deposit
+    target: 5000
+    by: 2025-06-30
+  - name: Bigger deposit
+    account: house_deposit
+    target: 8000
+    by: 2025-06-30
+start_date: 2025-01-01
+accounts:
+  main: 20000.00
+  house_deposit: 0.00
+"#;
+        let mut config: Config = serde_yaml::from_str(yaml).expect("Failed to parse YAML");
+        config.accounts = add_opening_balances(&add_generator_accounts(&add_default_accounts(&config.accounts), &config.generators));
+        let history = super::run(&config, config.accounts.clone(), 300).unwrap();
+        let progress: Vec<goals::Progress> = config.goals.iter().map(|goal| goals::progress(&history, goal)).collect();
+        // £1,000 a month from February: £5,000 on 1 June, £8,000 on 1 September
+        assert!(progress[0].met());
+        assert_eq!(progress[0].reached, chrono::NaiveDate::from_ymd_opt(2025, 6, 1));
+        assert!(!progress[1].met());
+        assert_eq!(progress[1].reached, chrono::NaiveDate::from_ymd_opt(2025, 9, 1));
+        assert_eq!(progress[1].shortfall(), dec!(3000));
+    }
+
+    #[test]
+    fn test_start_date_given() {
+        assert!(start_date_given("start_date: 2025-03-01\naccounts: {}\n"));
+        assert!(!start_date_given("generators: []\naccounts: {}\n"));
+    }
+
+    #[test]
+    fn test_solve_for_amount() {
+        let yaml = r#"
+start_date: 2025-01-01
+generators:
+  - type: transfer
+    name: Saving
+    amount: 0
+    day: 1
+    from: main
+    to: savings
+  - type: mortgage
+    deduction_amount: 500.00
+    deduction_day: 2
+  - type: overpayment
+    name: Overpay
+    amount: 0
+    day: 3
+    annual_cap_percent: 100
+  - type: expense
+    name: Rent
+    amount: 10
+    day: 3
+    category: housing
+accounts:
+  main: 100000.00
+  savings: 0.00
+  mortgage: -5000.00
+"#;
+        let mut config: Config = serde_yaml::from_str(yaml).expect("Failed to parse YAML");
+        config.accounts = add_opening_balances(&add_generator_accounts(&add_default_accounts(&config.accounts), &config.generators));
+        let balances: balances::Balances = config.accounts.clone().into();
+        let date = |m, d| chrono::NaiveDate::from_ymd_opt(2025, m, d).unwrap();
+        let goal = |account: &str, target, by| goals::Goal { account: account.to_string(), target, by, name: None, from: None, actuals: None };
+
+        // Eleven transfers from February to December
+        let found = solve_for(&config, &balances, "saving", &goal("savings", dec!(3000), date(12, 31)), dec!(10000));
+        assert_eq!(found, Ok(Some(dec!(272.73))));
+        // Six mortgage payments leave £2000 to overpay in six overpayments
+        let found = solve_for(&config, &balances, "Overpay", &goal("mortgage", Decimal::ZERO, date(6, 30)), dec!(10000));
+        assert_eq!(found, Ok(Some(dec!(333.34))));
+        let found = solve_for(&config, &balances, "Overpay", &goal("mortgage", Decimal::ZERO, date(6, 30)), dec!(300));
+        assert_eq!(found, Ok(None));
+        assert!(solve_for(&config, &balances, "Spotify", &goal("savings", dec!(1), date(6, 30)), dec!(10)).is_err());
+        assert!(solve_for(&config, &balances, "Rent", &goal("nowhere", dec!(1), date(6, 30)), dec!(10)).is_err());
+    }
+
+    #[test]
+    fn test_numeric_limits() {
+        assert!(check_horizon(MAX_HORIZON_DAYS).is_ok());
+        assert!(check_horizon(MAX_HORIZON_DAYS + 1).is_err());
+
+        let date = chrono::NaiveDate::from_ymd_opt(2025, 2, 1).unwrap();
+        let mut balances = balances::Balances::from_iter([
+            ("isa".to_string(), MAX_BALANCE),
+            (INVESTMENT_GROWTH.to_string(), -MAX_BALANCE),
+        ]);
+        assert_eq!(check_balance_limits(&balances, date), Ok(()));
+        post(&mut balances, INVESTMENT_GROWTH, "isa", dec!(0.01));
+        assert_eq!(
+            check_balance_limits(&balances, date),
+            Err(format!(
+                "Balance of investment_growth on 2025-02-01 is -1000000000000000000.01, beyond the {MAX_BALANCE} the forecast can hold precisely; check its growth rates and the horizon"
+            ))
+        );
+
+        // The forecast stops with the error rather than panicking
+        let yaml = r#"
+start_date: 2025-01-01
+generators:
+  - type: salary
+    amount: 2000.00
+    day: 26
+accounts:
+  main: 999999999999999000.00
+"#;
+        let mut config: Config = serde_yaml::from_str(yaml).expect("Failed to parse YAML");
+        config.accounts = add_opening_balances(&add_generator_accounts(&add_default_accounts(&config.accounts), &config.generators));
+        let error = run(&config, config.accounts.clone(), 60).unwrap_err();
+        assert!(error.starts_with("Balance of main on 2025-01-26 is 1000000000000001000,"), "{error}");
+    }
+
+    #[test]
+    fn test_scenarios() {
+        let yaml = r#"
+start_date: 2025-01-01
+generators:
+  - type: salary
+    name: Salary
+    amount: 2000.00
+    day: 26
+  - type: overpayment
+    name: Overpay
+    amount: 100
+    day: 2
+    annual_cap_percent: 100
+accounts:
+  main: 1000.00
+  mortgage: -10000.00
+scenarios:
+  - name: Redundancy in June
+    generators:
+      salary: {end_date: 2025-06-30}
+  - name: Bigger overpayment
+    generators:
+      Overpay: {amount: 200}
+    accounts:
+      main: 2000
+"#;
+        let config: Config = serde_yaml::from_str(yaml).expect("Failed to parse YAML");
+        let scenarios = scenario_configs(yaml, &config).unwrap();
+        assert_eq!(scenarios.iter().map(|(name, _)| name.as_str()).collect::<Vec<_>>(), vec!["Redundancy in June", "Bigger overpayment"]);
+        let date = |m, d| chrono::NaiveDate::from_ymd_opt(2025, m, d).unwrap();
+        let on = |config: &Config, m, d, account: &str| {
+            let history = super::run(config, all_accounts(config), 364).unwrap();
+            history.iter().find(|(day, _)| *day == date(m, d)).unwrap().1[account]
+        };
+        // Twelve overpayments from main, and twelve salaries less the six after June
+        assert_eq!(on(&config, 12, 31, "main"), dec!(23800.00));
+        assert_eq!(on(&scenarios[0].1, 12, 31, "main"), dec!(11800.00));
+        assert_eq!(on(&scenarios[1].1, 12, 31, "mortgage"), dec!(-7600.00));
+        assert_eq!(on(&scenarios[1].1, 12, 31, "main"), dec!(23600.00));
+        assert!(scenarios.iter().all(|(_, varied)| varied.scenarios.is_empty()));
+
+        let mut config = config;
+        config.scenarios[0].remove = vec!["Bonus".to_string()];
+        assert_eq!(scenario_configs(yaml, &config).unwrap_err(), "Scenario Redundancy in June names no generator called: Bonus");
+    }
+
+    #[test]
+    fn test_monte_carlo_bands() {
+        let yaml = r#"
+start_date: 2025-01-01
+generators:
+  - type: investment
+    amount: 100
+    day: 1
+    to: isa
+    annual_return: 5
+    return_volatility: 15
+  - type: expense
+    amount: 50
+    day: 10
+    category: repairs
+    variation: 40
+  - type: interest
+    rate: 4
+    rate_volatility: 1
+    day: 28
+    account: savings
+    income_account: savings_interest
+accounts:
+  main: 10000.00
+  isa: 10000.00
+  savings: 5000.00
+"#;
+        let mut config: Config = serde_yaml::from_str(yaml).expect("Failed to parse YAML");
+        let balances = all_accounts(&config);
+        let bands = simulate(&config, &balances, 3 * 365, 50).unwrap();
+        assert_eq!(bands.iter().map(|(account, _)| account.as_str()).collect::<Vec<_>>(), vec!["isa", "main", "savings"]);
+        for (account, bands) in &bands {
+            assert_eq!(bands.len(), 36, "{account}");
+            let (_, last) = bands.last().unwrap();
+            assert!(last.p10 < last.p50 && last.p50 < last.p90, "{account}: {last:?}");
+        }
+
+        // Without any volatility every run is the same
+        for entry in &mut config.generators {
+            match &mut entry.generator {
+                Generator::Investment { return_volatility, .. } => *return_volatility = Decimal::ZERO,
+                Generator::Expense { variation, .. } => *variation = Decimal::ZERO,
+                Generator::Interest { rate_volatility, .. } => *rate_volatility = Decimal::ZERO,
+                _ => {}
+            }
+        }
+        let expected = super::run(&config, balances.clone(), 3 * 365).unwrap();
+        for (account, bands) in simulate(&config, &balances, 3 * 365, 5).unwrap() {
+            let (date, last) = bands.last().unwrap();
+            let balance = expected.iter().find(|(day, _)| day == date).unwrap().1[&account];
+            assert_eq!((last.p10, last.p50, last.p90), (balance, balance, balance), "{account}");
+        }
+    }
+
+    #[test]
+    fn test_sensitivity() {
+        let yaml = r#"
+start_date: 2025-01-01
+generators:
+  - type: mortgage
+    deduction_amount: 500.00
+    deduction_day: 2
+  - type: overpayment
+    name: Overpay
+    amount: 0
+    day: 3
+    annual_cap_percent: 100
+accounts:
+  main: 10000.00
+  mortgage: -3000.00
+"#;
+        let config: Config = serde_yaml::from_str(yaml).expect("Failed to parse YAML");
+        let balances = all_accounts(&config);
+        let date = |m, d| chrono::NaiveDate::from_ymd_opt(2025, m, d).unwrap();
+        let rows = sensitivity(&config, &balances, 200, "overpay", (Decimal::ZERO, dec!(500), dec!(250))).unwrap();
+        let paid_off: Vec<_> = rows.iter().map(|row| (row.value, row.metrics[2].1)).collect();
+        assert_eq!(
+            paid_off,
+            vec![
+                (dec!(0), report::Metric::Date(Some(date(6, 2)))),
+                (dec!(250), report::Metric::Date(Some(date(4, 3)))),
+                (dec!(500), report::Metric::Date(Some(date(3, 3)))),
+            ]
+        );
+        assert!(rows.iter().all(|row| row.lowest_main == dec!(7000.00)));
+        assert_eq!(
+            report::sensitivity_csv(&rows, "Overpay", "main"),
+            vec!["Overpay,Lowest main,main at horizon,Net worth at horizon", "0,7000,7000,7000", "250,7000,7000,7000", "500,7000,7000,7000"]
+        );
+        assert!(sensitivity(&config, &balances, 200, "overpay", (dec!(5), dec!(1), dec!(1))).is_err());
+        assert!(sensitivity(&config, &balances, 200, "overpay", (dec!(1), dec!(5), Decimal::ZERO)).is_err());
+    }
+
+    #[test]
+    fn test_matrix() {
+        let yaml = r#"
+start_date: 2025-01-01
+generators:
+  - type: mortgage
+    deduction_amount: 500.00
+    deduction_day: 2
+  - type: overpayment
+    name: Overpay
+    amount: 0
+    day: 3
+    annual_cap_percent: 100
+  - type: expense
+    name: Holiday
+    amount: 0
+    day: 10
+    category: holidays
+accounts:
+  main: 10000.00
+  mortgage: -3000.00
+"#;
+        let config: Config = serde_yaml::from_str(yaml).expect("Failed to parse YAML");
+        let balances = all_accounts(&config);
+        let vary = [parse_vary("overpay=0:500:500").unwrap(), parse_vary("Holiday=0:100:100").unwrap()];
+        assert_eq!(vary[1], Vary { generator: "Holiday".to_string(), from: dec!(0), to: dec!(100), step: dec!(100) });
+        assert!(parse_vary("overpay=0:500").is_err());
+
+        let grid = matrix(&config, &balances, 200, &vary, MatrixMetric::Payoff, None).unwrap();
+        assert_eq!(grid.metric, "mortgage paid off");
+        assert_eq!(
+            report::matrix_csv(&grid),
+            vec!["overpay \\ Holiday,£0.00,£100.00", "£0.00,2025-06-02,2025-06-02", "£500.00,2025-03-03,2025-03-03"]
+        );
+        let grid = matrix(&config, &balances, 200, &vary, MatrixMetric::LowestCash, None).unwrap();
+        assert_eq!(report::matrix_csv(&grid)[1..], ["£0.00,7000,6300", "£500.00,7000,6300"]);
+        let html = report::matrix_html(&grid, true, "£");
+        assert!(html.contains("hsl(120, 70%, 75%)\">£7000.00</td>"), "{html}");
+        assert!(html.contains("hsl(0, 70%, 75%)\">£6300.00</td>"), "{html}");
+
+        let grid = matrix(&config, &balances, 200, &vary[..1], MatrixMetric::CashAtHorizon, None).unwrap();
+        assert_eq!(report::matrix_csv(&grid), vec!["overpay,main at horizon", "£0.00,7000", "£500.00,7000"]);
+        assert!(matrix(&config, &balances, 200, &vary, MatrixMetric::Payoff, Some("main")).is_err());
+        assert!(matrix(&config, &balances, 200, &[], MatrixMetric::Payoff, None).is_err());
+    }
+
+    #[test]
+    fn test_calendar_postings() {
+        let yaml = r#"
+start_date: 2025-01-01
+generators:
+  - type: interest
+    rate: 5.0
+    day: 1
+    account: mortgage
+    income_account: mortgage_income
+  - type: transfer
+    name: Save
+    amount: 100
+    day: 15
+    to: savings
+accounts:
+  main: 1000.00
+  savings: 0
+  mortgage: -10000.00
+"#;
+        let config: Config = serde_yaml::from_str(yaml).expect("Failed to parse YAML");
+        let (_, state) = run_with_state(&config, all_accounts(&config), 40).unwrap();
+        assert!(state.journal.iter().any(|posting| posting.generator == "interest (generator 1)"));
+        let payments = calendar_postings(&config, &state.journal);
+        let dates: Vec<_> = payments.iter().map(|posting| (posting.generator.as_str(), posting.date)).collect();
+        assert_eq!(dates, vec![("Save", chrono::NaiveDate::from_ymd_opt(2025, 1, 15).unwrap())]);
+    }
+
+    #[test]
+    fn test_year_totals() {
+        let yaml = r#"
+start_date: 2025-01-01
+generators:
+  - type: salary
+    amount: 2000.00
+    day: 28
+  - type: tithe
+    percentage: 10
+    day: 1
+  - type: interest
+    rate: 12.0
+    day: 1
+    account: mortgage
+    income_account: mortgage_income
+accounts:
+  main: 1000.00
+  mortgage: -10000.00
+"#;
+        let config: Config = serde_yaml::from_str(yaml).expect("Failed to parse YAML");
+        let (_, state) = run_with_state(&config, all_accounts(&config), 120).unwrap();
+        let tax_years = year_totals(&config, &state.journal, |date| tax::uk::tax_year(date).to_string());
+        // Paid on 28 January to 28 March, then 28 April, and tithed on the 1st of
+        // the month after; interest at 1% a month, compounding
+        assert_eq!(
+            tax_years,
+            vec![
+                report::YearTotals {
+                    year: "2024".to_string(),
+                    gross_salary: dec!(6000),
+                    net_salary: dec!(6000),
+                    interest_paid: dec!(303.01),
+                    given: dec!(600),
+                    ..Default::default()
+                },
+                report::YearTotals {
+                    year: "2025".to_string(),
+                    gross_salary: dec!(2000),
+                    net_salary: dec!(2000),
+                    interest_paid: dec!(103.03),
+                    given: dec!(200),
+                    ..Default::default()
+                },
+            ]
+        );
+        let calendar_years = year_totals(&config, &state.journal, |date| date.year().to_string());
+        assert_eq!(calendar_years.len(), 1);
+        assert_eq!(calendar_years[0].interest_paid, dec!(406.04));
+    }
+
+    #[test]
+    fn test_gift_aid_tithe() {
+        let yaml = r#"
+start_date: 2025-01-01
+generators:
+  - type: salary
+    amount: 2000.00
+    day: 28
+  - type: tithe
+    percentage: 10
+    day: 1
+    gift_aid: true
+accounts:
+  main: 1000.00
+"#;
+        let mut config: Config = serde_yaml::from_str(yaml).expect("Failed to parse YAML");
+        config.accounts = add_opening_balances(&add_generator_accounts(&add_default_accounts(&config.accounts), &config.generators));
+        let (history, state) = run_with_state(&config, config.accounts.clone(), 120).unwrap();
+        let (_, last) = history.last().unwrap();
+        // The uplift is tracked on the side, and only the tithes themselves leave main
+        assert_eq!(last[CHARITY_EXPENDITURE], dec!(800));
+        assert_eq!((last[GIFT_AID], last[GIFT_AID_INCOME]), (dec!(200), dec!(-200)));
+        assert_eq!(last[MAIN_ACCOUNT], dec!(1000) + dec!(8000) - dec!(800));
+        let tax_years = year_totals(&config, &state.journal, |date| tax::uk::tax_year(date).to_string());
+        assert_eq!((tax_years[0].given, tax_years[0].gift_aid, tax_years[0].gross_given()), (dec!(600), dec!(150), dec!(750)));
+        assert_eq!(tax_years[1].gross_given(), dec!(250));
     }
-    if !new_balances.contains_key(CHARITY_EXPENDITURE) {
-        new_balances.insert(CHARITY_EXPENDITURE.to_string(), Decimal::ZERO);
+
+    #[test]
+    fn test_plot_accounts() {
+        let yaml = r#"
+generators:
+  - type: mortgage
+    deduction_amount: 500.00
+    deduction_day: 2
+accounts:
+  main: 1000.00
+  savings: 0
+  mortgage: -3000.00
+"#;
+        let mut config: Config = serde_yaml::from_str(yaml).expect("Failed to parse YAML");
+        let balances = all_accounts(&config);
+        // Assets before liabilities
+        assert_eq!(plot_accounts(&config, &balances), Ok(vec!["main".to_string(), "savings".to_string(), "mortgage".to_string()]));
+        config.plot_accounts = Some(vec!["savings".to_string(), "main".to_string()]);
+        assert_eq!(plot_accounts(&config, &balances), Ok(vec!["savings".to_string(), "main".to_string()]));
+        config.plot_accounts = Some(vec!["main".to_string(), "pension".to_string()]);
+        assert_eq!(plot_accounts(&config, &balances), Err("No account to plot called: pension".to_string()));
+
+        // Accounts nested in groups are charted after their groups, and a group can be charted on its own
+        let yaml = yaml.replace("  savings: 0", "  savings:isa: 0\n  savings:easy_access: 0");
+        let mut config: Config = serde_yaml::from_str(&yaml).expect("Failed to parse YAML");
+        let balances = all_accounts(&config);
+        let plotted = plot_accounts(&config, &balances).unwrap();
+        assert_eq!(plotted, ["main", "savings", "savings:easy_access", "savings:isa", "mortgage"]);
+        config.plot_accounts = Some(vec!["savings".to_string()]);
+        assert_eq!(plot_accounts(&config, &balances), Ok(vec!["savings".to_string()]));
     }
-    new_balances
-}
 
-fn compute_next_day_balances(
-    config: &Config,
-    balances: &std::collections::HashMap<String, Decimal>,
-    date: chrono::NaiveDate,
-    total_salary_since_last_tithe: Decimal,
-) -> (std::collections::HashMap<String, Decimal>, Decimal) {
-    let mut new_balances = balances.clone();
-    let mut salary_accumulator = total_salary_since_last_tithe;
+    #[test]
+    fn test_account_types() {
+        let yaml = r#"
+generators:
+  - type: interest
+    rate: 5.0
+    day: 1
+    account: mortgage
+    income_account: savings
+  - type: salary
+    name: Salary
+    amount: 2000
+    day: 28
+    income_account: bonus
+accounts:
+  main: 1000.00
+  savings: 0
+  bonus: 0
+  mortgage: -3000.00
+account_types:
+  savings: asset
+  bonus: liability
+  pension: asset
+"#;
+        let config: Config = serde_yaml::from_str(yaml).expect("Failed to parse YAML");
+        let balances = all_accounts(&config);
+        assert_eq!(
+            check_account_types(&config, &balances),
+            Err(vec![
+                "account_types names no account called pension".to_string(),
+                "interest (generator 1): income_account is savings, declared as asset, but must be income or expense".to_string(),
+                "Salary: income_account is bonus, declared as liability, but must be income".to_string(),
+            ])
+        );
+        // Declared types decide the reporting group over what generators imply
+        let names = ledger_account_names(&config, &balances);
+        assert_eq!(names["bonus"], "Liabilities:bonus");
+        assert_eq!(names["savings"], "Assets:savings");
 
-    // For each transaction, apply its effect to the relevant accounts
-    for transaction in &config.generators {
-        match transaction {
-            Generator::Mortgage { deduction_amount, deduction_day, from, to } => {
-                if date.day() == *deduction_day {
-                    let from_balance = *new_balances.get(from).expect("From account not found in balances");
-                    let to_balance = *new_balances.get(to).expect("to account not found in balances");
-                    assert!(to_balance <= Decimal::ZERO, "Mortgage account must be negative; is {to_balance}");
-                    let actual_deduction = (*deduction_amount).min(-to_balance).min(from_balance).max(Decimal::ZERO);
-                    assert!(actual_deduction <= *deduction_amount);
-                    assert!(actual_deduction >= Decimal::ZERO, "Mortgage deduction amount must be non-negative; is {actual_deduction}");
-                    *new_balances.get_mut(from).expect("From account not found in balances") -= actual_deduction;
-                    *new_balances.get_mut(to).expect("To account not found in balances") += actual_deduction;
-                }
-            }
-            Generator::Interest { rate, day, account, income_account, month } => {
-                let should_pay_interest = match month {
-                    Some(specific_month) => date.day() == *day && Month::try_from(date.month() as u8).unwrap() == *specific_month,
-                    None => date.day() == *day, // Monthly payment
-                };
-                
-                if should_pay_interest && *rate != Decimal::ZERO {
-                    let current_balance = *new_balances.get(account).unwrap();
-                    let interest_exact = match month {
-                        Some(_) => current_balance * (*rate / dec!(100)), // Annual interest
-                        None => current_balance * (*rate / dec!(12) / dec!(100)), // Monthly interest
-                    };
-                    // round interest to 2 decimal places
-                    let interest = interest_exact.round_dp(2);
-                    *new_balances.get_mut(account).expect("Account not found for interest") += interest;
-                    *new_balances.get_mut(income_account).expect("Income account not found for interest") -= interest;
-                }
-            }
-            Generator::Salary { amount, day, to } => {
-                if date.day() == *day {
-                    *new_balances.get_mut(to).expect("Salary 'to' account not found") += *amount;
-                    *new_balances.get_mut(SALARY_INCOME).expect("salary_income not found for salary") -= *amount;
-                    // Accumulate salary for tithe calculation
-                    salary_accumulator += *amount;
-                }
-            }
-            Generator::Transfer { amount, day, from, to } => {
-                if date.day() == *day {
-                    *new_balances.get_mut(from).expect("Transfer 'from' account not found") -= *amount;
-                    *new_balances.get_mut(to).expect("Transfer 'to' account not found") += *amount;
-                }
-            }
-            Generator::Tithe { percentage, day, from, to } => {
-                if date.day() == *day {
-                    // Calculate tithe amount as percentage of accumulated salary
-                    let tithe_amount = (salary_accumulator * *percentage / dec!(100)).round_dp(2);
-                    if tithe_amount > Decimal::ZERO {
-                        *new_balances.get_mut(from).expect("Tithe 'from' account not found") -= tithe_amount;
-                        *new_balances.get_mut(to).expect("Tithe 'to' account not found") += tithe_amount;
-                        // Reset salary accumulator after tithe is paid
-                        salary_accumulator = Decimal::ZERO;
-                    }
-                }
-            }
-        }
+        let mut config = config;
+        config.account_types = [("main", ledger::AccountType::Assets), ("mortgage", ledger::AccountType::Liabilities), ("bonus", ledger::AccountType::Income)]
+            .map(|(account, kind)| (account.to_string(), kind))
+            .into();
+        assert_eq!(check_account_types(&config, &balances), Ok(()));
+        assert_eq!(missing_references(&config, &balances), Vec::new());
+
+        // A typo in a generator's wiring is found before the forecast runs
+        let yaml = yaml.replace("    account: mortgage", "    account: mortgaeg");
+        let config: Config = serde_yaml::from_str(&yaml).expect("Failed to parse YAML");
+        assert_eq!(
+            missing_references(&config, &all_accounts(&config)),
+            vec![(0, "account", "interest (generator 1): account is mortgaeg, which is not an account".to_string())]
+        );
     }
 
-    // assert balances sum to zero
-    let total_balance: Decimal = new_balances.values().sum();
-    if total_balance != Decimal::ZERO {
-        // print all balances
-        for (name, balance) in &new_balances {
-            print_balance_named(name, date, *balance, &config.currency_symbol);
-        }
-        panic!("Error: Balances do not sum to zero on {date}: {total_balance}");
+    #[test]
+    fn test_horizon() {
+        let yaml = "\
+start_date: 2025-01-01
+generators:
+  - type: mortgage
+    deduction_amount: 200
+    deduction_day: 10
+accounts:
+  main: 10000
+  mortgage: -1000
+";
+        let mut config: Config = serde_yaml::from_str(yaml).unwrap();
+        let balances = all_accounts(&config);
+        assert_eq!(horizon_days(&config), Ok(DEFAULT_HORIZON_DAYS));
+        config.end_date = chrono::NaiveDate::from_ymd_opt(2025, 3, 1);
+        assert_eq!(horizon_days(&config), Ok(59));
+        assert_eq!(run(&config, balances.clone(), 59).unwrap().last().unwrap().0, config.end_date.unwrap());
+        config.years = Some(1);
+        assert!(horizon_days(&config).is_err());
+        (config.end_date, config.months) = (None, Some(6));
+        assert_eq!(horizon_days(&config), Ok(546));
+        config.years = Some(101);
+        assert!(horizon_days(&config).is_err());
+
+        // The fifth payment, on 10 May, clears the mortgage
+        assert_eq!(paid_off_days(&Config { years: None, months: None, ..config.clone() }, &balances, "mortgage", 0), Ok(129));
+        let short = Config { years: None, months: Some(3), ..config.clone() };
+        assert_eq!(paid_off_days(&short, &balances, "mortgage", 90), Err("mortgage is not paid off by 2025-04-01".to_string()));
+        assert!(paid_off_days(&config, &balances, "main", 90).is_err());
+        assert!(paid_off_days(&config, &balances, "loan", 90).is_err());
     }
-    (new_balances, salary_accumulator)
-}
 
+    #[test]
+    fn test_splice_actuals() {
+        let file = std::env::temp_dir().join(format!("cash-forecast-statement-{}.csv", std::process::id()));
+        fs::write(&file, "Date,Description,Amount,Balance\n2025-01-02,TESCO,-40.00,960.00\n2025-01-04,REFUND,5.00,1000.00\n").unwrap();
+        let yaml = format!(
+            "
+start_date: 2025-01-01
+generators:
+  - type: expense
+    amount: 100
+    day: 3
+    category: groceries
+accounts:
+  main: 1000
+actuals:
+  - file: {}
+    account: main
+    columns: {{balance: Balance}}
+    rules:
+      - {{contains: tesco, account: groceries_expenditure}}
+",
+            file.display()
+        );
+        let mut config: Config = serde_yaml::from_str(&yaml).unwrap();
+        let history = splice_actuals(&mut config, None).unwrap();
+        fs::remove_file(&file).unwrap();
+        // The expense due on the 3rd is in the past, and the statement has it as 40 at Tesco instead
+        let main: Vec<Decimal> = history.iter().map(|(_, balances)| balances["main"]).collect();
+        assert_eq!(main, vec![dec!(960.00), dec!(960.00), dec!(1000.00)]);
+        assert_eq!(history[2].1["groceries_expenditure"], dec!(40.00));
+        assert_eq!(config.start_date, chrono::NaiveDate::from_ymd_opt(2025, 1, 4).unwrap());
+        assert_eq!(config.accounts["main"], dec!(1000.00));
+        // The balance it ends the day with is 35 more than the rows alone give
+        assert_eq!(history[2].1[OPENING_BALANCES], dec!(-1035.00));
+        let forecast = run(&config, all_accounts(&config), 30).unwrap();
+        assert_eq!(forecast[0].0, chrono::NaiveDate::from_ymd_opt(2025, 1, 5).unwrap());
+        // Only the expense on 3 February is forecast
+        assert_eq!(forecast.last().unwrap().1["main"], dec!(900.00));
+        assert!(assumptions(&config)[0].starts_with("actual balances up to 2025-01-04 from "));
+    }
 
+    #[test]
+    fn test_resume_from_snapshot() {
+        let yaml = "
+start_date: 2025-01-01
+generators:
+  - type: salary
+    amount: 2000
+    day: 25
+  - type: tithe
+    percentage: 10
+    day: 8
+  - type: interest
+    rate: 4.0
+    schedule:
+      frequency: quarterly
+      month: january
+      day: 31
+    account: main
+accounts:
+  main: 1000.00
+";
+        let config: Config = serde_yaml::from_str(yaml).unwrap();
+        let (straight, _) = run_with_state(&config, all_accounts(&config), 120).unwrap();
+        // Mid-month, with a tithe owed on January's salary and interest accruing since January's payment
+        let (history, state) = run_with_state(&config, all_accounts(&config), 45).unwrap();
+        let (date, balances) = history.last().unwrap();
+        let saved = snapshot::Snapshot::new(*date, generator_labels(&config), balances, state).to_yaml().unwrap();
 
-fn print_balance_named(name: &str, date: chrono::NaiveDate, balance: Decimal, currency_symbol: &str) {
-    println!(
-        "{name}: {date} {symbol}{v:.2}",
-        name = name,
-        date = date.format("%Y-%m-%d"),
-        symbol = currency_symbol,
-        v = balance
-    );
-}
+        let mut resumed: Config = serde_yaml::from_str(yaml).unwrap();
+        resume(&mut resumed, snapshot::read(&saved).unwrap()).unwrap();
+        assert_eq!(resumed.start_date, chrono::NaiveDate::from_ymd_opt(2025, 2, 15).unwrap());
+        assert_eq!(run(&resumed, all_accounts(&resumed), 75).unwrap(), straight[45..].to_vec());
 
-fn create_mortgage_plots(
-    history: &[(chrono::NaiveDate, std::collections::HashMap<String, Decimal>)],
-    currency_symbol: &str,
-) {
-    // Extract dates and mortgage balances
-    let mut csv_lines = vec!["Date,Balance".to_string()];
-    
-    for (date, balances) in history {
-        if let Some(mortgage_balance) = balances.get(MORTGAGE_ACCOUNT) {
-            csv_lines.push(format!("{},{}", date.format("%Y-%m-%d"), mortgage_balance));
-        }
+        let mut changed: Config = serde_yaml::from_str(&yaml.replace("  - type: tithe", "  - type: expense\n    amount: 5\n    day: 1\n    category: books\n  - type: tithe")).unwrap();
+        assert_eq!(
+            resume(&mut changed, snapshot::read(&saved).unwrap()),
+            Err("The snapshot was taken with generators salary (generator 1), tithe (generator 2), interest (generator 3), \
+                 but the config has salary (generator 1), expense (generator 2), tithe (generator 3), interest (generator 4)"
+                .to_string())
+        );
     }
-    
-    // Create CSV file
-    if let Err(e) = std::fs::write("/tmp/mortgage_balance.csv", csv_lines.join("\n")) {
-        eprintln!("Error creating CSV file: {}", e);
-    } else {
-        println!("Mortgage balance CSV data saved to '/tmp/mortgage_balance.csv'");
+
+    #[test]
+    fn test_multi_currency() {
+        let yaml = "
+start_date: 2025-01-01
+generators:
+  - type: transfer
+    amount: 170
+    day: 15
+    from: main
+    to: euro_savings
+accounts:
+  main: 1000
+  euro_savings: 2000
+account_details:
+  euro_savings: {currency: EUR}
+exchange_rates:
+  - {currency: EUR, from: 2025-01-01, rate: 0.85}
+  - {currency: EUR, from: 2025-02-01, rate: 0.80}
+";
+        let config: Config = serde_yaml::from_str(yaml).unwrap();
+        let balances = all_accounts(&config);
+        // The euros are worth 1700 in pounds at the start
+        assert_eq!(balances[OPENING_BALANCES], dec!(-2700));
+        let (history, state) = run_with_state(&config, balances.clone(), 59).unwrap();
+        let last = &history.last().unwrap().1;
+        assert_eq!(last["main"], dec!(660));
+        assert_eq!(last["euro_savings"], dec!(2412.50));
+        assert_eq!(state.journal[1].received, Some(dec!(212.50)));
+        // The 2200 euros held when the rate fell lost 110 in pounds
+        assert_eq!(last[EXCHANGE_GAINS], dec!(110.00));
+        // Net worth counts the euros at the rate on the last day
+        assert_eq!(key_metrics(&config, &balances, &history)[1].1, report::Metric::Amount(dec!(2590.00)));
+        assert_eq!(assumptions(&config)[0], "exchange rates in GBP: EUR at 0.85 from 2025-01-01, EUR at 0.8 from 2025-02-01");
+
+        let unpriced = yaml.replace("{currency: EUR}", "{currency: CHF}");
+        assert_eq!(check_currencies(&serde_yaml::from_str(&unpriced).unwrap()), Err(vec!["exchange_rates gives no rate for CHF, the currency of euro_savings".to_string()]));
+        assert_eq!(
+            validate_config(&unpriced),
+            vec![validate::Problem::new(Some(14), "exchange_rates gives no rate for CHF, the currency of euro_savings")]
+        );
     }
-    
-    // Create HTML plot with Chart.js
-    create_html_chart(&csv_lines, currency_symbol);
-}
 
-fn create_html_chart(csv_lines: &[String], currency_symbol: &str) {
-    // Skip header and extract data for JavaScript
-    let data_lines: Vec<&str> = csv_lines.iter().skip(1).map(|s| s.as_str()).collect();
-    
-    let mut dates = Vec::new();
-    let mut balances = Vec::new();
-    
-    for line in data_lines {
-        if let Some((date, balance)) = line.split_once(',') {
-            dates.push(format!("'{}'", date));
-            balances.push(balance.to_string());
-        }
+    #[test]
+    fn test_rounding_rules() {
+        let yaml = "
+start_date: 2025-01-01
+generators:
+  - type: interest
+    rate: 3
+    day: 31
+    account: main
+  - type: interest
+    rate: 3
+    day: 31
+    account: savings
+accounts:
+  main: 1002
+  savings: 1003.6
+";
+        let interest = |yaml: &str| {
+            let config: Config = serde_yaml::from_str(yaml).unwrap();
+            let history = super::run(&config, all_accounts(&config), 31).unwrap();
+            let last = &history.last().unwrap().1;
+            (last["main"] - dec!(1002), last["savings"] - dec!(1003.6))
+        };
+        // 2.505 and 2.509 a month, halves going to the even penny by default
+        assert_eq!(interest(yaml), (dec!(2.50), dec!(2.51)));
+        let rounded = format!("{yaml}rounding:\n  strategy: half_up\n  accounts:\n    savings: {{strategy: truncate}}\n");
+        assert_eq!(interest(&rounded), (dec!(2.51), dec!(2.50)));
+        let whole = format!("{yaml}rounding: {{places: 0}}\n");
+        assert_eq!(interest(&whole), (dec!(3), dec!(3)));
     }
-    
-    let html_content = format!(
-        r#"<!DOCTYPE html>
-<html>
-<head>
-    <title>Mortgage Balance Over Time</title>
-    <script src="https://cdn.jsdelivr.net/npm/chart.js"></script>
-    <style>
-        body {{ font-family: Arial, sans-serif; margin: 20px; }}
-        .chart-container {{ width: 90%; height: 400px; margin: 0 auto; }}
-        h1 {{ text-align: center; }}
-    </style>
-</head>
-<body>
-    <h1>Mortgage Balance Over Time</h1>
-    <div class="chart-container">
-        <canvas id="mortgageChart"></canvas>
-    </div>
-    
-    <script>
-        const ctx = document.getElementById('mortgageChart').getContext('2d');
-        const chart = new Chart(ctx, {{
-            type: 'line',
-            data: {{
-                labels: [{}],
-                datasets: [{{
-                    label: 'Mortgage Balance ({})',
-                    data: [{}],
-                    borderColor: 'rgb(75, 192, 192)',
-                    backgroundColor: 'rgba(75, 192, 192, 0.2)',
-                    tension: 0.1
-                }}]
-            }},
-            options: {{
-                responsive: true,
-                maintainAspectRatio: false,
-                scales: {{
-                    y: {{
-                        beginAtZero: false,
-                        title: {{
-                            display: true,
-                            text: 'Balance ({})'
-                        }}
-                    }},
-                    x: {{
-                        title: {{
-                            display: true,
-                            text: 'Date'
-                        }},
-                        ticks: {{
-                            maxTicksLimit: 10
-                        }}
-                    }}
-                }}
-            }}
-        }});
-    </script>
-</body>
-</html>"#,
-        dates.join(", "),
-        currency_symbol,
-        balances.join(", "),
-        currency_symbol
-    );
-    
-    if let Err(e) = std::fs::write("/tmp/mortgage_balance.html", html_content) {
-        eprintln!("Error creating HTML file: {}", e);
-    } else {
-        println!("Mortgage balance HTML chart saved to '/tmp/mortgage_balance.html'");
+
+    #[test]
+    fn test_generator_descriptions() {
+        let yaml = "
+start_date: 2025-01-01
+generators:
+  - type: transfer
+    description: Rainy day fund
+    amount: 100
+    day: 5
+    from: main
+    to: savings
+  - type: transfer
+    name: Holiday
+    description: Flights to Lisbon
+    amount: 50
+    day: 5
+    from: main
+    to: savngs
+accounts:
+  main: 1000
+  savings: 0
+";
+        let config: Config = serde_yaml::from_str(yaml).unwrap();
+        assert_eq!(config.generators[0].title(0), "transfer (generator 1) \"Rainy day fund\"");
+        assert_eq!(config.generators[1].title(1), "Holiday \"Flights to Lisbon\"");
+        assert!(validate_config(yaml).iter().any(|problem| problem.message == "Holiday \"Flights to Lisbon\": to is savngs, which is not an account"));
+
+        let fixed: Config = serde_yaml::from_str(&yaml.replace("savngs", "savings")).unwrap();
+        let (_, state) = run_with_state(&fixed, all_accounts(&fixed), 5).unwrap();
+        // The label still identifies the generator; the description is carried alongside it
+        assert_eq!(state.journal[0].generator, "transfer (generator 1)");
+        assert_eq!(state.journal[0].note.as_deref(), Some("Rainy day fund"));
+        assert_eq!(
+            posting_markers(&state.journal, "savings", "£"),
+            vec![(
+                "2025-01-05".to_string(),
+                "transfer (generator 1) \"Rainy day fund\" (transfer): main -> savings £100.00\nHoliday \"Flights to Lisbon\" (transfer): main -> savings £50.00"
+                    .to_string()
+            )]
+        );
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use rust_decimal_macros::dec;
-    use std::collections::HashMap;
+    #[test]
+    fn test_variables() {
+        let yaml = "\
+start_date: 2025-01-01
+variables:
+  net_salary: 3200
+  giving: net_salary * 0.1
+generators:
+  - type: salary
+    name: Salary
+    amount: net_salary
+    day: 25
+  - type: transfer
+    name: Giving
+    amount: giving
+    day: 26
+    from: main
+    to: charity
+accounts:
+  main: 1000
+  charity: 0
+scenarios:
+  - name: Pay cut
+    variables: {net_salary: 2800}
+";
+        let config = read_config(yaml).unwrap();
+        let amounts = |config: &Config| -> Vec<Decimal> {
+            config
+                .generators
+                .iter()
+                .map(|entry| match &entry.generator {
+                    Generator::Salary { amount, .. } | Generator::Transfer { amount, .. } => *amount,
+                    _ => panic!("Expected a salary or transfer"),
+                })
+                .collect()
+        };
+        assert_eq!(amounts(&config), vec![dec!(3200), dec!(320)]);
+        // The scenario's pay cut carries through to the giving worked out from it
+        let scenarios = scenario_configs(yaml, &config).unwrap();
+        assert_eq!(amounts(&scenarios[0].1), vec![dec!(2800), dec!(280)]);
+        assert!(validate_config(yaml).is_empty());
 
-    fn create_test_accounts_with_main_balance(mortgage_deduction_day: u32, main_balance: Option<Decimal>) -> Config {
-        let main_balance = main_balance.unwrap_or(dec!(10000.00));
-        let accounts = HashMap::from([
-            (MAIN_ACCOUNT.to_string(), main_balance),
-            (MORTGAGE_ACCOUNT.to_string(), dec!(-500000.00)),
-        ]);
-        let accounts_with_defaults = super::add_default_accounts(&accounts);
-        let accounts_with_opening = add_opening_balances(&accounts_with_defaults);
-        Config {
-            generators: vec![
-                Generator::Mortgage {
-                    deduction_amount: dec!(123.45),
-                    deduction_day: mortgage_deduction_day,
-                    from: MAIN_ACCOUNT.to_string(),
-                    to: MORTGAGE_ACCOUNT.to_string(),
-                },
-                Generator::Interest {
-                    rate: dec!(5.0), // 5% annual interest rate
-                    day: mortgage_deduction_day,
-                    account: MORTGAGE_ACCOUNT.to_string(),
-                    income_account: MORTGAGE_INCOME.to_string(),
-                    month: None, // Monthly interest
-                },
-                Generator::Salary {
-                    amount: dec!(2000.00),
-                    day: 6,
-                    to: MAIN_ACCOUNT.to_string()
-                },
-            ],
-            accounts: accounts_with_opening,
-            currency_symbol: "£".to_string(),
-            start_date: chrono::NaiveDate::from_ymd_opt(2025, 1, 1).unwrap(),
-        }
+        let typo = yaml.replace("net_salary * 0.1", "net_salary * rate");
+        assert_eq!(
+            validate_config(&typo),
+            vec![validate::Problem::new(Some(2), "variable giving: 'net_salary * rate' can't be worked out: rate is not a variable")]
+        );
     }
 
-    // For backward compatibility, keep the original function
-    fn create_test_accounts(mortgage_deduction_day: u32) -> Config {
-        create_test_accounts_with_main_balance(mortgage_deduction_day, None)
+    #[test]
+    fn test_history_sampling() {
+        let yaml = |history: &str| {
+            format!(
+                "
+start_date: 2025-01-01
+history: {history}
+generators:
+  - {{type: salary, name: Salary, amount: 2000, day: 25}}
+  - {{type: transfer, name: Save, amount: 50, schedule: {{frequency: weekly, weekday: fri}}, from: main, to: savings}}
+accounts:
+  main: 1000
+  savings: 0
+"
+            )
+        };
+        let daily: Config = serde_yaml::from_str(&yaml("daily")).unwrap();
+        let month_end: Config = serde_yaml::from_str(&yaml("month_end")).unwrap();
+        let history = run(&daily, all_accounts(&daily), 75).unwrap();
+        let sampled = run(&month_end, all_accounts(&month_end), 75).unwrap();
+        let dates: Vec<String> = sampled.iter().map(|(date, _)| date.to_string()).collect();
+        assert_eq!(dates, ["2025-01-31", "2025-02-28", "2025-03-17"]);
+        // Only which days are kept differs, not the balances on them
+        assert!(sampled.iter().all(|day| history.contains(day)));
+        assert_eq!(run_with_state(&daily, all_accounts(&daily), 75).unwrap().1, run_with_state(&month_end, all_accounts(&month_end), 75).unwrap().1);
     }
 
     #[test]
-    fn test_config_parsing() {
+    fn test_skipped_months() {
         let yaml = r#"
 generators:
   - type: mortgage
-    deduction_amount: 123.45
+    deduction_amount: 500.00
     deduction_day: 1
-  - type: interest
-    rate: 5.0
+    skip: [2025-03, 2025-04]
+  - type: expense
+    amount: 100
     day: 1
-    account: mortgage
-    income_account: mortgage_income
-  - type: salary
-    amount: 2000.00
-    day: 6
+    category: council_tax
+    skip: [february, march]
+start_date: 2025-01-01
 accounts:
-  main: 10000.00
-  mortgage: -500000.00
-currency_symbol: "£"
-start_date: "2025-01-01"
+  main: 5000.00
+  mortgage: -100000.00
 "#;
-        let original_config: Config = serde_yaml::from_str(yaml).expect("Failed to parse YAML");
-        let mut config = original_config;
-        config.accounts = add_default_accounts(&config.accounts);
-        config.accounts = add_opening_balances(&config.accounts);   
-        
-        let expected = create_test_accounts(1);
-        assert_eq!(
-            config, expected,
-            "Config parsed from YAML does not match expected.\nParsed: {:#?}\nExpected: {:#?}",
-            config, expected
+        let mut config: Config = serde_yaml::from_str(yaml).expect("Failed to parse YAML");
+        config.accounts = add_opening_balances(&add_generator_accounts(&add_default_accounts(&config.accounts), &config.generators));
+        let history = super::run(&config, config.accounts.clone(), 400).unwrap();
+        let on = |y, m, account: &str| {
+            let date = chrono::NaiveDate::from_ymd_opt(y, m, 2).unwrap();
+            history.iter().find(|(day, _)| *day == date).unwrap().1[account]
+        };
+        // A payment holiday in March and April, then payments again from May
+        assert_eq!(on(2025, 2, "mortgage"), dec!(-99500));
+        assert_eq!(on(2025, 4, "mortgage"), dec!(-99500));
+        assert_eq!(on(2025, 5, "mortgage"), dec!(-99000));
+        // No council tax in February or March, this year or next
+        assert_eq!(on(2025, 4, "council_tax_expenditure"), dec!(100));
+        assert_eq!(on(2026, 1, "council_tax_expenditure"), dec!(1000));
+        assert_eq!(on(2026, 2, "council_tax_expenditure"), dec!(1000));
+    }
+
+    #[test]
+    fn test_random_expense() {
+        let yaml = r#"
+generators:
+  - type: random_expense
+    name: Car repair
+    min: 100
+    max: 1500
+    times_per_year: 1.5
+    day: 1
+    category: car
+start_date: 2025-01-01
+inflation_rate: 0
+accounts:
+  main: 50000.00
+"#;
+        let mut config: Config = serde_yaml::from_str(yaml).expect("Failed to parse YAML");
+        config.accounts = add_opening_balances(&add_generator_accounts(&add_default_accounts(&config.accounts), &config.generators));
+        let charges = |config: &Config| -> Vec<Decimal> {
+            let history = super::run(config, config.accounts.clone(), 3650).unwrap();
+            history.windows(2).map(|days| days[1].1["car_expenditure"] - days[0].1["car_expenditure"]).filter(|amount| !amount.is_zero()).collect()
+        };
+        // Without a seed, the expected cost of 1.5 repairs a year at £800 is set aside each month
+        let expected = charges(&config);
+        assert_eq!(expected.len(), 119);
+        assert!(expected.iter().all(|amount| *amount == dec!(100)));
+        // With one, repairs come at random and cost between the limits
+        config.seed = Some(7);
+        let sampled = charges(&config);
+        assert!((5..40).contains(&sampled.len()), "{} repairs", sampled.len());
+        assert!(sampled.iter().all(|amount| (dec!(100)..=dec!(3000)).contains(amount)));
+        assert_eq!(charges(&config), sampled);
+
+        let backwards = yaml.replace("max: 1500", "max: 50");
+        assert_eq!(validate_config(&backwards), vec![validate::Problem::new(Some(6), "Car repair: max is 50, which is below min of 100")]);
+    }
+
+    #[test]
+    fn test_phased_expense() {
+        let yaml = r#"
+generators:
+  - type: phased_expense
+    name: Childcare
+    birth_date: 2023-06-15
+    day: 1
+    phases:
+      - {name: nursery, from_age: 1, amount: 1100}
+      - {name: free hours, from_age: 3, amount: 600}
+      - {name: wraparound, from: 2027-09-01, amount: 250}
+      - {from_age: 11, amount: 0}
+start_date: 2025-01-01
+inflation_rate: 0
+accounts:
+  main: 500000.00
+"#;
+        let mut config: Config = serde_yaml::from_str(yaml).expect("Failed to parse YAML");
+        config.accounts = add_opening_balances(&add_generator_accounts(&add_default_accounts(&config.accounts), &config.generators));
+        let (history, state) = run_with_state(&config, config.accounts.clone(), 365 * 10).unwrap();
+        let paid = |y, m| {
+            let date = chrono::NaiveDate::from_ymd_opt(y, m, 1).unwrap();
+            state.journal.iter().find(|posting| posting.date == date).map(|posting| (posting.description.as_str(), posting.amount))
+        };
+        assert_eq!(paid(2025, 2), Some(("nursery", dec!(1100))));
+        assert_eq!(paid(2026, 7), Some(("free hours", dec!(600))));
+        assert_eq!(paid(2027, 9), Some(("wraparound", dec!(250))));
+        assert_eq!(paid(2034, 7), None);
+        // 17 months of nursery, 14 of free hours and 82 of wraparound care
+        assert_eq!(history.last().unwrap().1["children_expenditure"], dec!(1100) * dec!(17) + dec!(600) * dec!(14) + dec!(250) * dec!(82));
+
+        let unborn = yaml.replace("    birth_date: 2023-06-15\n", "");
+        assert_eq!(
+            validate_config(&unborn),
+            vec![
+                validate::Problem::new(Some(6), "Childcare: phase 1 starts at age 1, but the generator has no birth_date"),
+                validate::Problem::new(Some(6), "Childcare: phase 2 starts at age 3, but the generator has no birth_date"),
+                validate::Problem::new(Some(6), "Childcare: phase 4 starts at age 11, but the generator has no birth_date"),
+            ]
         );
-        let account = config.accounts.get(MAIN_ACCOUNT).unwrap();
-        assert_eq!(*account, dec!(10000.00));
-        assert_eq!(config.currency_symbol, "£");
     }
 
     #[test]
-    fn test_compute_next_day_balances_no_deduction() {
-        let mortgage_deduction_day = 2;
-        let test_day = 5;
-        let next = make_accounts_for_day(mortgage_deduction_day, test_day);
-        assert_eq!(next[MAIN_ACCOUNT], dec!(10000.00));
+    fn test_dividend_and_rent_taxed() {
+        let yaml = r#"
+generators:
+  - type: salary
+    gross_annual: 30000
+    day: 28
+  - type: dividend
+    amount: 625
+    schedule: {frequency: quarterly, month: january, day: 10}
+    growth_percent: 4
+    taxed: true
+  - type: rent
+    amount: 1000
+    day: 5
+    growth_percent: 3
+    taxed: true
+start_date: 2025-01-01
+inflation_rate: 0
+accounts:
+  main: 0.00
+"#;
+        let mut config: Config = serde_yaml::from_str(yaml).expect("Failed to parse YAML");
+        config.accounts = add_opening_balances(&add_generator_accounts(&add_default_accounts(&config.accounts), &config.generators));
+        let (history, state) = run_with_state(&config, config.accounts.clone(), 400).unwrap();
+        let posted = |description: &str| -> Vec<(String, Decimal)> {
+            state.journal.iter().filter(|posting| posting.description == description).map(|posting| (posting.date.to_string(), posting.amount)).collect()
+        };
+        // £2,500 a year of dividends on top of a £30,000 salary: £2,000 over the allowance at 8.75%
+        assert_eq!(posted("dividend")[..2], [("2025-01-10".to_string(), dec!(625)), ("2025-04-10".to_string(), dec!(625))]);
+        assert_eq!(posted("dividend tax")[0], ("2025-01-10".to_string(), dec!(43.75)));
+        // Grown by 4% after a year
+        assert_eq!(posted("dividend").last(), Some(&("2026-01-10".to_string(), dec!(650.00))));
+        // £12,000 of rent a year, £11,000 over the allowance at 20%: £2,200 a year
+        assert_eq!(posted("property income tax")[0], ("2025-01-05".to_string(), dec!(183.33)));
+        assert_eq!(posted("rent").last(), Some(&("2026-02-05".to_string(), dec!(1030.00))));
+        // Each kind of income has its own account
+        let last = &history.last().unwrap().1;
+        assert_eq!(last[DIVIDEND_INCOME], dec!(-3150.00));
+        assert_eq!(last[RENTAL_INCOME], dec!(-14060.00));
     }
 
-    fn make_accounts_for_day(mortgage_deduction_day: u32, test_day: u32) -> HashMap<String, Decimal> {
-        let config = create_test_accounts(mortgage_deduction_day);
-        let (next, _) = compute_next_day_balances(
-            &config,
-            &config.accounts,
-            chrono::NaiveDate::from_ymd_opt(2025, 1, test_day).unwrap(),
-            Decimal::ZERO,
-        );
-        next
-    }
-    
     #[test]
-    fn test_compute_next_day_balances_with_deduction() {
-        let next = make_accounts_for_day(3, 3);
-        assert_eq!(next[MAIN_ACCOUNT], dec!(10000.00) - dec!(123.45));
+    fn test_envelopes() {
+        let yaml = r#"
+start_date: 2025-01-01
+generators:
+  - {type: envelope, name: Food, amount: 400, day: 2, to: groceries}
+  - {type: envelope, name: Fun, amount: 100, day: 2, to: fun, leftover: reset}
+  - {type: expense, name: Shop, amount: 350, day: 20, category: food, from: groceries}
+  - {type: expense, name: Cinema, amount: 60, day: 20, category: leisure, from: fun}
+accounts:
+  main: 1000.00
+"#;
+        let mut config: Config = serde_yaml::from_str(yaml).expect("Failed to parse YAML");
+        config.accounts = add_opening_balances(&add_generator_accounts(&add_default_accounts(&config.accounts), &config.generators));
+        let (history, state) = run_with_state(&config, config.accounts.clone(), 58).unwrap();
+        let (_, last) = history.last().unwrap();
+        // Food rolls £50 a month over; fun hands back its £40 before February's £100
+        assert_eq!((last["groceries"], last["fun"]), (dec!(100), dec!(40)));
+        assert_eq!(last[MAIN_ACCOUNT], dec!(1000) - dec!(800) - dec!(200) + dec!(40));
+        let usages = envelope::usage(&envelopes(&config), &state.journal, &history);
+        assert_eq!(usages.len(), 4);
+        assert_eq!((usages[2].envelope.as_str(), usages[2].allocated, usages[2].spent, usages[2].left), ("groceries", dec!(400), dec!(350), dec!(100)));
+        assert_eq!(usages[3].used(), Some(dec!(60.0)));
     }
 
     #[test]
-    fn test_compute_next_day_balances_with_salary() {
-        let next= make_accounts_for_day(5, 6);
-        assert_eq!(next[MAIN_ACCOUNT], dec!(10000.00) + dec!(2000.00));
+    fn test_savings_interest() {
+        let yaml = r#"
+start_date: 2025-01-01
+generators:
+  - type: salary
+    gross_annual: 60000
+    day: 28
+  - type: savings_interest
+    tiers: [{upto: 5000, rate: 5}, {rate: 2}]
+    day: 1
+    account: savings
+accounts:
+  main: 0.00
+  savings: 40000.00
+"#;
+        let mut config: Config = serde_yaml::from_str(yaml).expect("Failed to parse YAML");
+        config.accounts = add_opening_balances(&add_generator_accounts(&add_default_accounts(&config.accounts), &config.generators));
+        let (history, state) = run_with_state(&config, config.accounts.clone(), 365).unwrap();
+        let paid: Vec<Decimal> = state.journal.iter().filter(|posting| posting.to == "savings").map(|posting| posting.amount).collect();
+        // 5% of the first 5,000 and 2% of the 35,000 above it, a twelfth at a time
+        assert_eq!(paid[0], dec!(79.17));
+        assert_eq!(paid.len(), 12);
+        assert_eq!(history.last().unwrap().1[SAVINGS_INTEREST], -paid.iter().sum::<Decimal>());
+        // Paid on 1 February, March and April before the tax year ends on 5 April, and
+        // set against the £500 allowance of a higher rate taxpayer
+        let years = savings_allowance_use(&config, &state.journal).unwrap();
+        assert_eq!(years.len(), 2);
+        assert_eq!((years[0].year.as_str(), years[0].interest, years[0].allowance), ("2024/25", paid[..3].iter().sum(), dec!(500)));
+        assert_eq!(years[1].interest, paid[3..].iter().sum::<Decimal>());
+        assert!(years[1].interest > dec!(500));
+        assert_eq!(years[1].taxable(), years[1].interest - dec!(500));
+
+        let backwards = yaml.replace("[{upto: 5000, rate: 5}, {rate: 2}]", "[{rate: 2}, {upto: 5000, rate: 5}]");
+        assert_eq!(validate_config(&backwards), vec![validate::Problem::new(Some(8), "savings_interest (generator 2): tier 2 comes after a tier with no upto")]);
     }
 
     #[test]
-    fn test_compute_next_day_balances_with_salary_and_mortgage_same_day() {
-        let mut config = create_test_accounts(7);
-        config.generators.push(Generator::Salary {
-            amount: dec!(1500.00),
-            day: 7,
-            to: MAIN_ACCOUNT.to_string()
-        });
-        let (next, _) = compute_next_day_balances(
-            &config,
-            &config.accounts,
-            chrono::NaiveDate::from_ymd_opt(2025, 1, 7).unwrap(),
-            Decimal::ZERO,
-        );
-        assert_eq!(next[MAIN_ACCOUNT], dec!(10000.00) + dec!(1500.00) - dec!(123.45));
+    fn test_regular_saver_matures() {
+        let yaml = r#"
+generators:
+  - type: regular_saver
+    name: Regular saver
+    monthly_cap: 200
+    amount: 250
+    rate: 6
+    day: 1
+    start: 2025-02-01
+    account: regular_saver
+start_date: 2025-01-01
+accounts:
+  main: 5000.00
+"#;
+        let mut config: Config = serde_yaml::from_str(yaml).expect("Failed to parse YAML");
+        config.accounts = add_opening_balances(&add_generator_accounts(&add_default_accounts(&config.accounts), &config.generators));
+        let history = super::run(&config, config.accounts.clone(), 400).unwrap();
+        let on = |y, m, d, account: &str| {
+            let date = chrono::NaiveDate::from_ymd_opt(y, m, d).unwrap();
+            history.iter().find(|(day, _)| *day == date).unwrap().1[account]
+        };
+        // Deposits are held to the cap
+        assert_eq!(on(2025, 3, 1, "regular_saver"), dec!(400));
+        assert_eq!(on(2026, 1, 31, "regular_saver"), dec!(2400));
+        // A year of daily balances at 6%, all paid out with the savings at maturity
+        assert_eq!(on(2026, 2, 1, "regular_saver"), dec!(0));
+        assert_eq!(on(2026, 2, 1, "savings_interest"), dec!(-78.54));
+        assert_eq!(on(2026, 2, 1, "main"), dec!(5000) + dec!(78.54));
+        assert_eq!(on(2026, 2, 5, "main"), dec!(5078.54));
+
+        let problems = validate_config(yaml);
+        assert_eq!(problems.len(), 1);
+        assert!(problems[0].message.contains("amount is 250, which is above the monthly_cap of 200"));
+
+        // Paid in weekly, the cap still holds for each month as a whole
+        let weekly = yaml.replace("    amount: 250\n", "    amount: 60\n").replace("    day: 1\n", "    schedule: friday\n");
+        let mut config: Config = serde_yaml::from_str(&weekly).expect("Failed to parse YAML");
+        config.accounts = add_opening_balances(&add_generator_accounts(&add_default_accounts(&config.accounts), &config.generators));
+        let history = super::run(&config, config.accounts.clone(), 90).unwrap();
+        let on = |m, d| history.iter().find(|(day, _)| *day == chrono::NaiveDate::from_ymd_opt(2025, m, d).unwrap()).unwrap().1["regular_saver"];
+        // Four Fridays in February: 60, 60, 60 and the 20 left under the cap
+        assert_eq!(on(2, 28), dec!(200));
+        assert_eq!(on(3, 31), dec!(400));
+        assert!(validate_config(&weekly).is_empty());
     }
 
     #[test]
-    fn test_compute_next_day_balances_with_salary_not_on_salary_day() {
-        let mut config = create_test_accounts(10);
-        config.generators.push(Generator::Salary {
-            amount: dec!(1000.00),
-            day: 15,
-            to: MAIN_ACCOUNT.to_string()
-        });
-        let (next, _) = compute_next_day_balances(
-            &config,
-            &config.accounts,
-            chrono::NaiveDate::from_ymd_opt(2025, 1, 15).unwrap(),
-            Decimal::ZERO,
+    fn test_backed_by_covers_shortfalls() {
+        let yaml = r#"
+generators:
+  - type: mortgage
+    name: Mortgage
+    deduction_amount: 500
+    deduction_day: 1
+  - type: expense
+    name: Car repair
+    amount: 300
+    day: 10
+    category: transport
+start_date: 2024-12-31
+accounts:
+  main: 100.00
+  mortgage: -100000.00
+  savings: 350.00
+  emergency: 1000.00
+account_details:
+  main:
+    backed_by: savings
+  savings:
+    backed_by: emergency
+    floor: 100
+"#;
+        let mut config: Config = serde_yaml::from_str(yaml).expect("Failed to parse YAML");
+        config.accounts = add_opening_balances(&add_generator_accounts(&add_default_accounts(&config.accounts), &config.generators));
+        let (history, state) = run_with_state(&config, config.accounts.clone(), 15).unwrap();
+        let balances = &history.last().unwrap().1;
+        // The mortgage is paid in full, with savings kept at its floor and emergency making up the rest
+        assert_eq!(balances["mortgage"], dec!(-99500));
+        assert_eq!((balances["main"], balances["savings"], balances["emergency"]), (dec!(0), dec!(100), dec!(550)));
+        let covers: Vec<(&str, &str, &str, Decimal)> = state
+            .journal
+            .iter()
+            .filter(|posting| posting.description == "cover shortfall")
+            .map(|posting| (posting.generator.as_str(), posting.from.as_str(), posting.to.as_str(), posting.amount))
+            .collect();
+        assert_eq!(
+            covers,
+            vec![
+                ("Mortgage", "emergency", "savings", dec!(150)),
+                ("Mortgage", "savings", "main", dec!(400)),
+                ("Car repair", "emergency", "savings", dec!(300)),
+                ("Car repair", "savings", "main", dec!(300)),
+            ]
         );
-        assert_eq!(next[MAIN_ACCOUNT], dec!(10000.00) + dec!(1000.00));
-    }
 
-    
-    #[test]
-    fn test_compute_next_day_balances_with_salary_none() {
-        let next = make_accounts_for_day(20, 20);
-        assert_eq!(next[MAIN_ACCOUNT], dec!(10000.00) - dec!(123.45));
+        let looped = yaml.replace("    floor: 100", "    floor: 100\n  emergency:\n    backed_by: main").replace("backed_by: savings", "backed_by: savigns");
+        let problems: Vec<String> = validate_config(&looped).into_iter().map(|problem| problem.message).collect();
+        assert_eq!(problems, vec!["main: backed_by is savigns, which is not an account"]);
+        let looped = yaml.replace("    floor: 100", "    floor: 100\n  emergency:\n    backed_by: main");
+        let problems: Vec<String> = validate_config(&looped).into_iter().map(|problem| problem.message).collect();
+        assert_eq!(problems, vec!["Accounts back each other in a loop: emergency, main, savings"]);
     }
 
     #[test]
-    fn test_config_parsing_with_salary() {
+    fn test_on_insufficient_funds() {
         let yaml = r#"
 generators:
   - type: mortgage
-    deduction_amount: 123.45
+    name: Mortgage
+    deduction_amount: 500
     deduction_day: 1
-    from: main
-    to: mortgage
-  - type: salary
-    amount: 2500.00
-    day: 28
+  - type: transfer
+    name: Save
+    amount: 300
+    day: 1
+    to: savings
+start_date: 2024-12-31
 accounts:
-  main: 10000.00
-currency_symbol: "£"
-start_date: "2025-01-01"
+  main: 400.00
+  mortgage: -100000.00
+  savings: 0.00
 "#;
-        let config: Config = serde_yaml::from_str(yaml).expect("Failed to parse YAML");
-        assert_eq!(config.generators.len(), 2);
-        let account = config.accounts.get(MAIN_ACCOUNT).unwrap();
-        assert_eq!(*account, dec!(10000.00));
-    }
+        let run = |mortgage: &str, save: &str| {
+            let yaml = yaml.replace("    deduction_day: 1", &format!("    deduction_day: 1\n{mortgage}")).replace("    to: savings", &format!("    to: savings\n{save}"));
+            let mut config: Config = serde_yaml::from_str(&yaml).expect("Failed to parse YAML");
+            config.accounts = add_opening_balances(&add_generator_accounts(&add_default_accounts(&config.accounts), &config.generators));
+            let history = super::run(&config, config.accounts.clone(), 5)?;
+            let balances = &history.last().unwrap().1;
+            Ok::<_, String>((balances["mortgage"], balances["main"], balances["savings"]))
+        };
+        // By default the mortgage pays what it can, and the transfer overdraws
+        assert_eq!(run("", ""), Ok((dec!(-99600), dec!(-300), dec!(300))));
+        assert_eq!(run("    on_insufficient_funds: skip", "    on_insufficient_funds: skip"), Ok((dec!(-100000), dec!(100), dec!(300))));
+        assert_eq!(run("    on_insufficient_funds: overdraw", "    on_insufficient_funds: partial"), Ok((dec!(-99500), dec!(-100), dec!(0))));
+        assert_eq!(run("", "    on_insufficient_funds: error"), Err("Save: can't pay 300 out of main on 2025-01-01, which has only 0 available".to_string()));
 
-    #[test]
-    fn test_run_balances_consistency() {
-        let config = create_test_accounts(1);
-        println!("Config: {:#?}", config);
-        let balances = config.accounts.clone();
-        let days = 30; // Run for 30 days
-        let history = super::run(&config, balances, days);
-        let final_balances = history.last().expect("History should not be empty").1.clone();
-        // The sum of all balances should be zero (by design)
-        let total: Decimal = final_balances.values().copied().sum();
-        assert_eq!(total, Decimal::ZERO);
-        assert_eq!(final_balances[MAIN_ACCOUNT], dec!(10000.00) + dec!(2000.00));
-        assert_eq!(history.len(), days as usize, "History should have one entry per day");
+        let problems = validate_config(&yaml.replace("    deduction_day: 1", "    deduction_day: 1\n  - type: salary\n    amount: 100\n    day: 1\n    on_insufficient_funds: error"));
+        assert_eq!(problems.len(), 1);
+        assert!(problems[0].message.ends_with("salary (generator 2): on_insufficient_funds is set, but it has no from account to pay out of"));
     }
 
     #[test]
-    fn test_run_final_balances_after_salary_and_mortgage() {
-        let config = create_test_accounts(1);
-        let balances = config.accounts.clone();
-        let days = 6; // On day 6, salary is paid
-        let history = super::run(&config, balances, days);
-        let final_balances = &history.last().unwrap().1;
-        // Salary should be added on day 6
-        assert_eq!(final_balances[MAIN_ACCOUNT], dec!(10000.00) + dec!(2000.00));
+    fn test_reference_rates() {
+        let yaml = r#"
+generators:
+  - type: interest
+    base: boe_base
+    margin: 1
+    day: 1
+  - type: savings_interest
+    tiers: [{base: boe_base, margin: -1}]
+    day: 1
+    account: savings
+start_date: 2024-12-31
+accounts:
+  mortgage: -12000.00
+  savings: 12000.00
+reference_rates:
+  boe_base:
+    - {from: 2024-12-01, rate: 5.0}
+    - {from: 2025-02-01, rate: 2.0}
+"#;
+        let mut config: Config = serde_yaml::from_str(yaml).expect("Failed to parse YAML");
+        config.accounts = add_opening_balances(&add_generator_accounts(&add_default_accounts(&config.accounts), &config.generators));
+        let history = super::run(&config, config.accounts.clone(), 40).unwrap();
+        let balances = &history.last().unwrap().1;
+        // 6% then 3% on the mortgage, and 4% then 1% on the savings
+        assert_eq!((balances["mortgage"], balances["savings"]), (dec!(-12090.15), dec!(12050.03)));
+        assert_eq!(assumptions(&config)[..2], ["boe_base at 5% from 2024-12-01, 2% from 2025-02-01", "interest on mortgage at 1% over boe_base"]);
+        assert!(validate_config(yaml).is_empty());
+
+        let problems = validate_config(&yaml.replace("base: boe_base\n", "base: boe\n").replace("2024-12-01", "2025-01-01"));
+        let problems: Vec<String> = problems.iter().map(|problem| problem.to_string()).collect();
+        assert_eq!(
+            problems,
+            vec![
+                "line 4: interest (generator 1): base is boe, which is not in reference_rates",
+                "line 15: Reference rate boe_base starts on 2025-01-01, after the forecast starts on 2024-12-31",
+            ]
+        );
     }
 
     #[test]
-    fn test_run_mortgage_deduction_applied() {
-        let config = create_test_accounts(3);
-        let balances = config.accounts.clone();
-        let days = 3; // On day 3, mortgage is deducted
-        let history = super::run(&config, balances, days);
-        let final_balances = &history.last().unwrap().1;
-        // Mortgage should be deducted on day 3
-        assert_eq!(final_balances[MAIN_ACCOUNT], dec!(10000.00) - dec!(123.45));
+    fn test_council_tax() {
+        let yaml = r#"
+generators:
+  - type: council_tax
+    annual_amount: 1850
+    day: 1
+    annual_increase_percent: 4.99
+start_date: 2025-01-15
+accounts:
+  main: 5000.00
+"#;
+        let mut config: Config = serde_yaml::from_str(yaml).expect("Failed to parse YAML");
+        config.accounts = add_opening_balances(&add_generator_accounts(&add_default_accounts(&config.accounts), &config.generators));
+        let history = super::run(&config, config.accounts.clone(), 400).unwrap();
+        let paid = |y, m, d| history.iter().find(|(date, _)| *date == chrono::NaiveDate::from_ymd_opt(y, m, d).unwrap()).unwrap().1[COUNCIL_TAX_EXPENDITURE];
+        // Nothing is collected in February and March
+        assert_eq!(paid(2025, 3, 31), dec!(0));
+        // 1942.32 for the year from April 2025, in ten instalments, the first making up the rounding
+        assert_eq!(paid(2025, 4, 1), dec!(194.25));
+        assert_eq!(paid(2025, 5, 1), dec!(388.48));
+        assert_eq!(paid(2026, 1, 1), dec!(1942.32));
+        assert_eq!(paid(2026, 2, 1), dec!(1942.32));
+        assert!(assumptions(&config).contains(&"council tax rising 4.99% each April".to_string()));
+
+        let problems = validate_config(&yaml.replace("    day: 1\n", "    day: 1\n    instalments: 13\n"));
+        assert_eq!(problems.len(), 1);
+        assert_eq!(problems[0].to_string(), "line 6: council_tax (generator 1): instalments is 13, which isn't from 1 to 12");
     }
 
     #[test]
-    fn test_run_balances_sum_to_zero_each_day() {
-        let config = create_test_accounts(1);
-        let balances = config.accounts.clone();
-        let days = 15;
-        let history = super::run(&config, balances, days);
-        for (date, balances) in history {
-            let total: Decimal = balances.values().copied().sum();
-            assert_eq!(total, Decimal::ZERO, "Balances do not sum to zero on {date}");
-        }
+    fn test_overrides() {
+        let yaml = r#"
+generators:
+  - type: expense
+    name: energy_bill
+    amount: 200
+    day: 1
+    category: energy
+start_date: 2025-01-15
+accounts:
+  main: 1000.00
+overrides:
+  - {generator: Energy_Bill, date: 2025-03-01, amount: 243.17}
+  - {generator: energy_bill, date: 2025-03-02, amount: 1000}
+"#;
+        let mut config: Config = serde_yaml::from_str(yaml).expect("Failed to parse YAML");
+        config.accounts = add_opening_balances(&add_generator_accounts(&add_default_accounts(&config.accounts), &config.generators));
+        let (history, state) = run_with_state(&config, config.accounts.clone(), 90).unwrap();
+        let paid: Vec<(String, Decimal)> = state.journal.iter().map(|posting| (posting.date.to_string(), posting.amount)).collect();
+        // Only the March bill is overridden; nothing is paid on the 2nd for the second override to replace
+        let bill = |date: &str, amount| (date.to_string(), amount);
+        assert_eq!(paid, vec![bill("2025-02-01", dec!(200)), bill("2025-03-01", dec!(243.17)), bill("2025-04-01", dec!(200))]);
+        assert_eq!(history.last().unwrap().1["main"], dec!(356.83));
+        assert!(validate_config(yaml).is_empty());
+
+        let problems = validate_config(&yaml.replace("generator: energy_bill", "generator: gas"));
+        assert_eq!(problems.len(), 1);
+        assert_eq!(problems[0].to_string(), "line 13: Override 2: generator is gas, which is not the name of a generator");
     }
 
     #[test]
-    fn test_run_salary_paid_on_correct_day() {
-        let config = create_test_accounts(15);
-        let balances = config.accounts.clone();
-        let days = 10;
-        let history = super::run(&config, balances, days);
-        // Salary is paid on day 6, so check balance before and after
-        // get the salary day from config
-        assert!(config.generators.len() > 2, "Config should have at least three transactions");
-        assert!(matches!(config.generators[2], Generator::Salary { .. }), "Third transaction should be a Salary transaction");
-        let salary_day = if let Generator::Salary { day, .. } = &config.generators[2] {
-            *day
-        } else {
-            panic!("Expected third transaction to be a Salary transaction");
+    fn test_override_net_pay() {
+        let yaml = r#"
+generators:
+  - type: salary
+    name: pay
+    gross_annual: 60000
+    day: 28
+  - type: tithe
+    percentage: 10
+    day: 1
+start_date: 2025-01-01
+accounts:
+  main: 0.00
+overrides:
+  - {generator: pay, date: 2025-01-28, amount: 3100}
+"#;
+        let mut config: Config = serde_yaml::from_str(yaml).expect("Failed to parse YAML");
+        config.accounts = add_opening_balances(&add_generator_accounts(&add_default_accounts(&config.accounts), &config.generators));
+        let (_, state) = run_with_state(&config, config.accounts.clone(), 60).unwrap();
+        let paid = |description: &str| -> Vec<Decimal> {
+            state.journal.iter().filter(|posting| posting.description == description).map(|posting| posting.amount).collect()
         };
-        assert_eq!(salary_day, 6, "Salary day should be 6");
-        let before = &history[salary_day as usize - 3]; // day 5
-        let after = &history[salary_day as usize - 2];        
-        assert_eq!(before.0, chrono::NaiveDate::from_ymd_opt(2025, 1, 5).unwrap());
-        assert_eq!(after.0, chrono::NaiveDate::from_ymd_opt(2025, 1, 6).unwrap());
-        // Check salary was paid correctly
-        let before_salary  = &before.1[MAIN_ACCOUNT]; // day 5
-        let after_salary = &after.1[MAIN_ACCOUNT];  // day 6
-        // print history
-        println!("History: {:#?}", history);
-        assert_eq!(
-            *after_salary,
-            *before_salary + dec!(2000.00),
-            "Salary not paid correctly on salary day"
-        );
-    }    
+        // The override is what lands in the bank; the deductions are worked out from the gross as usual
+        let net_pay = paid("net pay");
+        assert_eq!(net_pay[0], dec!(3100));
+        assert_ne!(net_pay[1], dec!(3100));
+        let income_tax = paid("income tax");
+        assert_eq!(income_tax[0], income_tax[1]);
+        assert_ne!(income_tax[0], dec!(3100));
+        // And it's the overridden pay that's tithed
+        assert_eq!(paid("tithe")[0], dec!(310));
+    }
 
+    #[test]
+    fn test_unbalanced_accounts() {
+        let yaml = "start_date: 2025-01-01\ngenerators: []\naccounts:\n  main: 100.00\n";
+        let config: Config = serde_yaml::from_str(yaml).expect("Failed to parse YAML");
+        // Without the opening balance to offset it, the first day's balances don't add up
+        assert_eq!(super::run(&config, config.accounts.clone(), 5).unwrap_err(), "Balances do not sum to zero on 2025-01-02: 100");
+    }
 
     #[test]
-    fn test_run_multiple_transactions_same_day() {
-        let mut config = create_test_accounts(3);
-        config.generators.push(Generator::Salary {
-            amount: dec!(500.00),
-            day: 3,
-            to: MAIN_ACCOUNT.to_string(),
-        });
-        let balances = config.accounts.clone();
-        let days = 3;
-        let history = super::run(&config, balances, days);
-        let final_balances = &history.last().unwrap().1;
-        // On day 3, both mortgage and salary should be applied
-        assert_eq!(final_balances[MAIN_ACCOUNT], dec!(10000.00) - dec!(123.45) + dec!(500.00));
+    fn test_run_checked() {
+        let yaml = r#"
+generators:
+  - type: transfer
+    amount: 300
+    day: 1
+    to: card
+start_date: 2025-01-01
+accounts:
+  main: 1000.00
+  card: -500.00
+"#;
+        let mut config: Config = serde_yaml::from_str(yaml).expect("Failed to parse YAML");
+        config.accounts = add_opening_balances(&add_generator_accounts(&add_default_accounts(&config.accounts), &config.generators));
+        let balances = balances::Balances::from(config.accounts.clone());
+        let (history, _) = run_checked(&config, &balances, 20, false).unwrap();
+        assert_eq!(history.len(), 20);
+        // The card is paid into credit on the second payment
+        let violation = run_checked(&config, &balances, 70, true).unwrap_err();
+        assert_eq!(violation, "Invariant broken on 2025-03-01: liability card is in credit at 100");
+        config.account_details.insert("card".to_string(), AccountDetails { allow_credit: true, ..Default::default() });
+        assert!(run_checked(&config, &balances, 70, true).is_ok());
     }
 
     #[test]
-    fn test_run_salary_to_different_account() {
-        let mut config = create_test_accounts(6);
-        // Add a new account and pay salary to it
-        let alt_account = "alt_account";
-        let mut accounts = config.accounts.clone();
-        accounts.insert(alt_account.to_string(), dec!(0.00));
-        config.accounts = accounts;
-        config.generators[2] = Generator::Salary {  // Fix: index 2 is the Salary transaction
-            amount: dec!(2000.00),
-            day: 6,
-            to: alt_account.to_string(),
-        };
-        
-        let balances = config.accounts.clone();
-        let days = 6;
-        let history = super::run(&config, balances, days);
-        let final_balances = &history.last().unwrap().1;
-        
-        assert_eq!(final_balances[alt_account], dec!(2000.00));
-        assert_eq!(final_balances[MAIN_ACCOUNT], dec!(10000.00) -dec!(123.45)); // mortgage deducted
+    fn test_forecast_args() {
+        let cli = Cli::parse_from(["cashflow", "--months", "6", "-vv", "--check", "serve"]);
+        assert_eq!(forecast_args(&cli), ["--months", "6", "-vv", "--check"]);
+        let cli = Cli::parse_from(["cashflow", "--quiet", "serve"]);
+        assert_eq!(forecast_args(&cli), ["--quiet"]);
     }
 
     #[test]
-    fn test_transfer_between_accounts() {
-        let mut config = create_test_accounts(10); // No mortgage/salary on test day
-        let savings_account = "savings";
-        
-        // Add savings account
-        config.accounts.insert(savings_account.to_string(), dec!(0.00));
-        
-        // Add transfer transaction
-        config.generators.push(Generator::Transfer {
-            amount: dec!(500.00),
-            day: 5,
-            from: MAIN_ACCOUNT.to_string(),
-            to: savings_account.to_string(),
-        });
-        
-        let (next, _) = compute_next_day_balances(
-            &config,
-            &config.accounts,
-            chrono::NaiveDate::from_ymd_opt(2025, 1, 5).unwrap(),
-            Decimal::ZERO,
-        );
-        
-        assert_eq!(next[MAIN_ACCOUNT], dec!(10000.00) - dec!(500.00));
-        assert_eq!(next[savings_account], dec!(500.00));
+    fn test_balance_checks() {
+        let yaml = r#"
+generators:
+  - type: expense
+    amount: 100
+    day: 1
+    category: food
+start_date: 2025-01-01
+accounts:
+  main: 1000.00
+checks:
+  - {date: 2025-03-01, account: main, equals: 750, tolerance: 50}
+  - {date: 2025-02-01, account: main, equals: 950}
+  - date: 2025-02-01
+    account: savngs
+    equals: 0
+"#;
+        let problems = validate_config(yaml);
+        assert_eq!(problems.len(), 1);
+        assert_eq!(problems[0].to_string(), "line 13: Check 3: account is savngs, which is not an account");
+
+        let yaml = yaml.split("  - date").next().unwrap();
+        let mut config: Config = serde_yaml::from_str(yaml).expect("Failed to parse YAML");
+        config.accounts = add_opening_balances(&add_generator_accounts(&add_default_accounts(&config.accounts), &config.generators));
+        let history = super::run(&config, config.accounts.clone(), 90).unwrap();
+        let outcomes = checks::outcomes(&history, &config.checks);
+        let results: Vec<(Option<Decimal>, bool)> = outcomes.iter().map(|outcome| (outcome.balance, outcome.passed())).collect();
+        assert_eq!(results, vec![(Some(dec!(900)), false), (Some(dec!(800)), true)]);
     }
 
     #[test]
-    fn test_transfer_not_on_transfer_day() {
-        let mut config = create_test_accounts(10);
-        let savings_account = "savings";
-        
-        config.accounts.insert(savings_account.to_string(), dec!(0.00));
-        config.generators.push(Generator::Transfer {
-            amount: dec!(500.00),
-            day: 7,
-            from: MAIN_ACCOUNT.to_string(),
-            to: savings_account.to_string(),
-        });
-        
-        let (next, _) = compute_next_day_balances(
-            &config,
-            &config.accounts,
-            chrono::NaiveDate::from_ymd_opt(2025, 1, 5).unwrap(), // Not transfer day
-            Decimal::ZERO,
+    fn test_workbook_sheets() {
+        let yaml = r#"
+generators:
+  - type: expense
+    name: Food
+    amount: 100
+    day: 2
+    category: food
+start_date: 2025-01-01
+accounts:
+  main: 1000.00
+"#;
+        let mut config: Config = serde_yaml::from_str(yaml).expect("Failed to parse YAML");
+        config.accounts = add_opening_balances(&add_generator_accounts(&add_default_accounts(&config.accounts), &config.generators));
+        let balances = balances::Balances::from(config.accounts.clone());
+        let (history, state) = run_with_state(&config, balances.clone(), 3).unwrap();
+        let sheets = workbook_sheets(&config, &balances, &history, &state.journal);
+        let names: Vec<&str> = sheets.iter().map(|sheet| sheet.name.as_str()).take(3).collect();
+        assert_eq!(names, ["Monthly summary", "Transactions", "main"]);
+        let date = |d| chrono::NaiveDate::from_ymd_opt(2025, 1, d).unwrap();
+        use xlsx::Cell;
+        assert_eq!(
+            sheets[1].rows[1],
+            vec![
+                Cell::Date(date(2)),
+                Cell::Text("Food".to_string()),
+                Cell::Text("expense".to_string()),
+                Cell::Text("main".to_string()),
+                Cell::Text("food_expenditure".to_string()),
+                Cell::Money(dec!(100)),
+                Cell::Empty,
+            ]
         );
-        
-        // No transfer should occur
-        assert_eq!(next[MAIN_ACCOUNT], dec!(10000.00));
-        assert_eq!(next[savings_account], dec!(0.00));
+        assert_eq!(sheets[2].rows[2], vec![Cell::Date(date(2)), Cell::Money(dec!(900)), Cell::Formula("B3-B2".to_string())]);
+        assert_eq!(sheets[0].rows[1][..5], [
+            Cell::Text("2025-01".to_string()),
+            Cell::Text("main".to_string()),
+            Cell::Money(dec!(0)),
+            Cell::Money(dec!(100)),
+            Cell::Formula("C2-D2".to_string()),
+        ]);
     }
 
     #[test]
-    fn test_multiple_transfers_same_day() {
-        let mut config = create_test_accounts(10);
-        let savings_account = "savings";
-        let investment_account = "investments";
-        
-        config.accounts.insert(savings_account.to_string(), dec!(0.00));
-        config.accounts.insert(investment_account.to_string(), dec!(0.00));
-        
-        config.generators.push(Generator::Transfer {
-            amount: dec!(300.00),
-            day: 5,
-            from: MAIN_ACCOUNT.to_string(),
-            to: savings_account.to_string(),
-        });
-        
-        config.generators.push(Generator::Transfer {
-            amount: dec!(200.00),
-            day: 5,
-            from: MAIN_ACCOUNT.to_string(),
-            to: investment_account.to_string(),
-        });
-        
-        let (next, _) = compute_next_day_balances(
-            &config,
-            &config.accounts,
-            chrono::NaiveDate::from_ymd_opt(2025, 1, 5).unwrap(),
-            Decimal::ZERO,
+    fn test_parquet_columns() {
+        let date = |d| chrono::NaiveDate::from_ymd_opt(2025, 1, d).unwrap();
+        let day = |main, savings| balances::Balances::from_iter([("main".to_string(), main), ("savings".to_string(), savings)]);
+        let history = vec![(date(2), day(dec!(100), dec!(0))), (date(3), day(dec!(60), dec!(40.5)))];
+        let columns = history_columns(&["savings".to_string(), "main".to_string()], &history);
+        assert_eq!(columns[0], ("date", parquet::Column::Date(vec![date(2), date(2), date(3), date(3)])));
+        assert_eq!(columns[1].1, parquet::Column::Text(["savings", "main", "savings", "main"].map(str::to_string).to_vec()));
+        assert_eq!(columns[2].1, parquet::Column::Number(vec![0.0, 100.0, 40.5, 60.0]));
+
+        let posting = journal::Posting {
+            date: date(3),
+            generator: "Save".to_string(),
+            description: "transfer".to_string(),
+            from: "main".to_string(),
+            to: "savings".to_string(),
+            amount: dec!(40.5),
+            received: None,
+            note: None,
+        };
+        let columns = journal_columns(&[posting]);
+        let names: Vec<&str> = columns.iter().map(|(name, _)| *name).collect();
+        assert_eq!(names, ["date", "generator", "description", "from", "to", "amount", "note"]);
+        assert_eq!(columns[6].1, parquet::Column::OptionalText(vec![None]));
+    }
+
+    #[test]
+    fn test_seasonal_expense() {
+        let yaml = r#"
+generators:
+  - type: seasonal_expense
+    amounts: {jan: 220, feb: 210, december: 200, aug: 90}
+    day: 5
+    category: heating
+start_date: 2025-01-01
+inflation_rate: 0
+accounts:
+  main: 5000.00
+"#;
+        let mut config: Config = serde_yaml::from_str(yaml).expect("Failed to parse YAML");
+        config.accounts = add_opening_balances(&add_generator_accounts(&add_default_accounts(&config.accounts), &config.generators));
+        let history = super::run(&config, config.accounts.clone(), 365).unwrap();
+        let paid: Vec<(u32, Decimal)> = history
+            .windows(2)
+            .map(|days| (days[1].0.month(), days[1].1["heating_expenditure"] - days[0].1["heating_expenditure"]))
+            .filter(|(_, amount)| !amount.is_zero())
+            .collect();
+        // Nothing is paid in the months left out
+        assert_eq!(paid, [(1, dec!(220)), (2, dec!(210)), (8, dec!(90)), (12, dec!(200))]);
+    }
+
+    #[test]
+    fn test_validate_config() {
+        let yaml = "\
+start_date: 2025-01-01
+inflaton_rate: 3
+generators:
+  - type: salary
+    name: Job
+    amount: 2000
+    day: 0
+    annual_increase_percent: -2
+  - type: salary
+    name: Job
+    amount: 2500
+    day: 28
+    start_date: 2025-06-01
+  - type: transfer
+    amount: 10
+    schedule: {frequency: weekly, weekday: fri, every: 0}
+    to: savngs
+  - type: expense
+    amount: 10
+    day: 1
+    category: food
+    form: main
+accounts:
+  main: 100
+  savings: 0
+  main: 200
+";
+        let problems: Vec<String> = validate_config(yaml).iter().map(ToString::to_string).collect();
+        assert_eq!(
+            problems,
+            vec![
+                "line 26: account main is listed more than once",
+                "line 22: expense (generator 4): unknown field form",
+                "line 2: unknown field inflaton_rate",
+                "line 7: Job: day 0 is not a day of the month (1 to 31)",
+                "line 8: Job: annual_increase_percent is -2, which is negative",
+                "line 17: transfer (generator 3): to is savngs, which is not an account",
+                "line 16: transfer (generator 3): every must be at least 1 week",
+                "line 9: Job: generators 1 and 2 both run from 2025-06-01",
+            ]
         );
-        
-        assert_eq!(next[MAIN_ACCOUNT], dec!(10000.00) - dec!(300.00) - dec!(200.00));
-        assert_eq!(next[savings_account], dec!(300.00));
-        assert_eq!(next[investment_account], dec!(200.00));
+        // A generator that can't be read is reported without hiding the others
+        let yaml = "\
+generators:
+  - type: expense
+    amount: x
+    day: 1
+    category: food
+  - type: transfer
+    amount: 10
+    day: 1
+    to: main
+    colour: red
+accounts:
+  main: 100
+";
+        let problems: Vec<String> = validate_config(yaml).iter().map(ToString::to_string).collect();
+        assert_eq!(problems.len(), 2);
+        assert!(problems[0].starts_with("line 2: expense (generator 1): invalid value: string \"x\""));
+        assert_eq!(problems[1], "line 10: transfer (generator 2): unknown field colour");
+        assert!(validate_config(&fs::read_to_string("config.yaml").unwrap()).is_empty());
     }
 
     #[test]
-    fn test_transfer_with_salary_and_mortgage_same_day() {
-        let mut config = create_test_accounts(7); // Mortgage and salary on day 7
-        let savings_account = "savings";
-        
-        config.accounts.insert(savings_account.to_string(), dec!(0.00));
-        
-        // Change existing transactions to day 7
-        config.generators[0] = Generator::Mortgage {
-            deduction_amount: dec!(123.45),
-            deduction_day: 7,
-            from: MAIN_ACCOUNT.to_string(),
-            to: MORTGAGE_ACCOUNT.to_string(),
-        };
-        config.generators[1] = Generator::Salary {
-            amount: dec!(2000.00),
-            day: 7,
-            to: MAIN_ACCOUNT.to_string(),
+    fn test_assumption_register() {
+        let yaml = r#"
+generators:
+  - type: salary
+    name: Salary
+    amount: 3000
+    day: 26
+    confidence: high
+  - type: expense
+    amount: 300
+    day: 1
+    category: holidays
+    variation: 15
+    confidence: low
+  - type: investment
+    name: Pension
+    amount: 200
+    day: 1
+    to: pension
+    annual_return: 5
+    confidence: low
+  - type: expense
+    name: Gym
+    amount: 40
+    day: 1
+    category: leisure
+    variation: 5
+    confidence: medium
+  - type: transfer
+    amount: 100
+    day: 1
+    to: savings
+accounts:
+  main: 0.00
+"#;
+        let config: Config = serde_yaml::from_str(yaml).expect("Failed to parse YAML");
+        let register = assumption_register(&config);
+        let entry = |confidence, generator: &str, spread: Option<&str>| report::Assumption {
+            confidence,
+            generator: generator.to_string(),
+            spread: spread.map(str::to_string),
         };
-        
-        // Add transfer on same day
-        config.generators.push(Generator::Transfer {
-            amount: dec!(500.00),
-            day: 7,
-            from: MAIN_ACCOUNT.to_string(),
-            to: savings_account.to_string(),
-        });
-        
-        let (next, _) = compute_next_day_balances(
-            &config,
-            &config.accounts,
-            chrono::NaiveDate::from_ymd_opt(2025, 1, 7).unwrap(),
-            Decimal::ZERO,
+        use monte_carlo::Confidence::{High, Low, Medium};
+        assert_eq!(
+            register,
+            vec![
+                entry(Low, "expense (generator 2)", Some("amount ±30%")),
+                entry(Low, "Pension", Some("return ±10 points")),
+                entry(Medium, "Gym", Some("amount ±5%")),
+                entry(High, "Salary", None),
+            ]
         );
-        
-        // Main account: start + salary - mortgage - transfer
-        assert_eq!(next[MAIN_ACCOUNT], dec!(10000.00) + dec!(2000.00) - dec!(123.45) - dec!(500.00));
-        assert_eq!(next[savings_account], dec!(500.00));
+        // Only the simulation is widened; the forecast itself is unchanged
+        let widened = widen_low_confidence(&config);
+        assert!(matches!(widened.generators[3].generator, Generator::Expense { variation, .. } if variation == dec!(5)));
+        assert!(matches!(config.generators[1].generator, Generator::Expense { variation, .. } if variation == dec!(15)));
     }
 
     #[test]
-    fn test_config_parsing_with_transfer() {
+    fn test_journal_explains_balances() {
         let yaml = r#"
 generators:
+  - type: salary
+    name: Salary
+    gross_annual: 40000
+    day: 28
+  - type: rent
+    amount: 900
+    day: 1
+    to: main
+    late_probability: 0.5
+    late_days: {min: 4, max: 4}
   - type: transfer
-    amount: 250.00
-    day: 10
-    from: main
+    name: Save
+    amount: 100
+    day: 15
     to: savings
+  - type: interest
+    rate: 3
+    day: 1
+    account: savings
+start_date: 2025-01-01
 accounts:
-  main: 5000.00
+  main: 1000.00
   savings: 0.00
-currency_symbol: "£"
-start_date: "2025-01-01"
 "#;
         let config: Config = serde_yaml::from_str(yaml).expect("Failed to parse YAML");
-        assert_eq!(config.generators.len(), 1);
-        
-        if let Generator::Transfer { amount, day, from, to } = &config.generators[0] {
-            assert_eq!(*amount, dec!(250.00));
-            assert_eq!(*day, 10);
-            assert_eq!(from, "main");
-            assert_eq!(to, "savings");
-        } else {
-            panic!("Expected Transfer transaction");
+        let opening = all_accounts(&config);
+        let (history, state) = run_with_state(&config, opening.clone(), 90).unwrap();
+        // Replaying the journal over the opening balances gives the closing ones
+        let mut replayed = opening.clone();
+        for posting in &state.journal {
+            post(&mut replayed, &posting.from, &posting.to, posting.amount);
         }
+        assert_eq!(replayed, history.last().unwrap().1);
+        // Executing the journal as a plan gives every day's balances
+        let end = history.last().unwrap().0;
+        assert_eq!(journal::execute(&opening, config.start_date, end, &state.journal), Ok(history.clone()));
+        let on = |m, d| chrono::NaiveDate::from_ymd_opt(2025, m, d).unwrap();
+        let posted = |date, generator: &str| -> Vec<(String, Decimal)> {
+            state
+                .journal
+                .iter()
+                .filter(|posting| posting.date == date && posting.generator == generator)
+                .map(|posting| (posting.description.clone(), posting.amount))
+                .collect()
+        };
+        assert_eq!(
+            posted(on(1, 28), "Salary").iter().map(|(description, _)| description.as_str()).collect::<Vec<_>>(),
+            vec!["income tax", "national insurance", "net pay"]
+        );
+        assert_eq!(posted(on(1, 15), "Save"), vec![("transfer".to_string(), dec!(100))]);
+        // Half of February's rent is expected on time and half four days late
+        assert_eq!(posted(on(2, 1), "rent (generator 2)"), vec![("rent".to_string(), dec!(450.00))]);
+        assert_eq!(posted(on(2, 5), "rent (generator 2)"), vec![("late rent".to_string(), dec!(450.00))]);
     }
 
     #[test]
-    fn test_interest_calculation() {
-        let mut config = create_test_accounts(5); // Mortgage on day 5, salary on day 6
-        // Clear existing interest transaction and add a new one for day 10
-        config.generators = vec![
-            Generator::Mortgage {
-                deduction_amount: dec!(123.45),
-                deduction_day: 5,
-                from: MAIN_ACCOUNT.to_string(),
-                to: MORTGAGE_ACCOUNT.to_string(),
-            },
-            Generator::Interest {
-                rate: dec!(6.0), // 6% annual rate
-                day: 10,
-                account: MORTGAGE_ACCOUNT.to_string(),
-                income_account: MORTGAGE_INCOME.to_string(),
-                month: None, // Monthly interest
-            },
-        ];
-        
-        let (next, _) = compute_next_day_balances(
-            &config,
-            &config.accounts,
-            chrono::NaiveDate::from_ymd_opt(2025, 1, 10).unwrap(),
-            Decimal::ZERO,
-        );
-        
-        // Calculate expected interest: 500000 * (6% / 12 / 100) = 500000 * 0.005 = 2500
-        let expected_interest = dec!(-500000.00) * (dec!(6.0) / dec!(12) / dec!(100));
-        assert_eq!(expected_interest, dec!(-2500.00));
-        
-        // Mortgage balance should increase by interest
-        assert_eq!(next[MORTGAGE_ACCOUNT], dec!(-500000.00) + expected_interest);
-        
+    fn test_ledger_account_names() {
+        let yaml = r#"
+generators:
+  - type: salary
+    gross_annual: 40000
+    day: 28
+  - type: rent
+    amount: 900
+    day: 1
+  - type: expense
+    amount: 50
+    day: 1
+    category: food
+  - type: interest
+    rate: 4
+    day: 1
+    account: mortgage
+  - type: interest
+    rate: 3
+    day: 1
+    account: savings
+    income_account: savings_interest
+accounts:
+  main: 1000.00
+  savings: 500.00
+  mortgage: -90000.00
+"#;
+        let config: Config = serde_yaml::from_str(yaml).expect("Failed to parse YAML");
+        let names = ledger_account_names(&config, &all_accounts(&config));
+        let name = |account: &str| names[account].as_str();
+        assert_eq!(name("main"), "Assets:main");
+        assert_eq!(name("mortgage"), "Liabilities:mortgage");
+        assert_eq!(name(SALARY_INCOME), "Income:salary_income");
+        assert_eq!(name(INCOME_TAX), "Expenses:income_tax");
+        assert_eq!(name("food_expenditure"), "Expenses:food_expenditure");
+        // Mortgage interest is a cost; savings interest is income
+        assert_eq!(name(MORTGAGE_INCOME), "Expenses:mortgage_income");
+        assert_eq!(name("savings_interest"), "Income:savings_interest");
+        assert_eq!(name(OPENING_BALANCES), "Equity:Opening Balances");
+        assert_eq!(name(CHARITY_EXPENDITURE), "Expenses:charity_expenditure");
+        assert_eq!(name(RENTAL_INCOME), "Income:rental_income");
     }
 
     #[test]
-    fn test_interest_calculation_annual() {
-        let mut config = create_test_accounts(5); // Mortgage on day 5, salary on day 6
-        // Clear existing interest transaction and add a new one for annual interest in January
-        config.generators = vec![
-            Generator::Mortgage {
-                deduction_amount: dec!(123.45),
-                deduction_day: 5,
-                from: MAIN_ACCOUNT.to_string(),
-                to: MORTGAGE_ACCOUNT.to_string(),
-            },
-            Generator::Interest {
-                rate: dec!(6.0), // 6% annual rate
-                day: 15,
-                account: MORTGAGE_ACCOUNT.to_string(),
-                income_account: MORTGAGE_INCOME.to_string(),
-                month: Some(chrono::Month::January), // Annual interest paid in January
-            },
-        ];
-        
-        // Test that interest is paid in January
-        let (next_jan, _) = compute_next_day_balances(
-            &config,
-            &config.accounts,
-            chrono::NaiveDate::from_ymd_opt(2025, 1, 15).unwrap(), // January 15th
-            Decimal::ZERO,
-        );
-        
-        // Calculate expected interest: 500000 * (6% / 100) = 500000 * 0.005 = 2500
-        // Note: Still using monthly calculation even for annual payment
-        let expected_interest = dec!(-500000.00) * (dec!(6.0) / dec!(100));
-        assert_eq!(expected_interest, dec!(-30000.00));
-        
-        // Mortgage balance should increase by interest in January
-        assert_eq!(next_jan[MORTGAGE_ACCOUNT], dec!(-500000.00) + expected_interest);
-        
-        // Test that interest is NOT paid in February on the same day
-        let (next_feb, _) = compute_next_day_balances(
-            &config,
-            &config.accounts,
-            chrono::NaiveDate::from_ymd_opt(2025, 2, 15).unwrap(), // February 15th
-            Decimal::ZERO,
-        );
-        
-        // Mortgage balance should remain unchanged in February
-        assert_eq!(next_feb[MORTGAGE_ACCOUNT], dec!(-500000.00)); // No interest added
-        
-        // Test that interest is NOT paid in March on the same day
-        let (next_mar, _) = compute_next_day_balances(
-            &config,
-            &config.accounts,
-            chrono::NaiveDate::from_ymd_opt(2025, 3, 15).unwrap(), // March 15th
-            Decimal::ZERO,
-        );
-        
-        // Mortgage balance should remain unchanged in March
-        assert_eq!(next_mar[MORTGAGE_ACCOUNT], dec!(-500000.00)); // No interest added
+    fn test_account_order() {
+        let yaml = r#"
+generators:
+  - type: interest
+    rate: 4
+    day: 1
+    account: mortgage
+accounts:
+  savings: 500.00
+  mortgage: -90000.00
+  main: 1000.00
+  car_loan: -4000.00
+"#;
+        let config: Config = serde_yaml::from_str(yaml).expect("Failed to parse YAML");
+        let balances = all_accounts(&config);
+        let order = account_order(&config, &balances);
+        let position = |account: &str| order.iter().position(|other| other == account).unwrap();
+        assert_eq!(order[..4], ["main", "savings", "car_loan", "mortgage"]);
+        assert!(position(SALARY_INCOME) < position(CHARITY_EXPENDITURE) && position(SALARY_INCOME) < position(MORTGAGE_INCOME));
+        // Only a config with rent or annual events has accounts for them
+        assert!(!order.contains(&RENTAL_INCOME.to_string()) && !order.contains(&EVENTS_EXPENDITURE.to_string()));
+        assert!(!order.contains(&OPENING_BALANCES.to_string()));
+        assert_eq!(plot_accounts(&config, &balances), Ok(order[..4].to_vec()));
     }
 
     #[test]
-    fn test_mortgage_payment_limited_by_available_balance() {
-        let mut config = create_test_accounts_with_main_balance(5, Some(dec!(100.0)));
-        // Set up a scenario where the mortgage payment exceeds the available balance
-        config.generators[0] = Generator::Mortgage {
-            deduction_amount: dec!(500.00), // Try to deduct £500
-            deduction_day: 5,
-            from: MAIN_ACCOUNT.to_string(),
-            to: MORTGAGE_ACCOUNT.to_string(),
+    fn test_rerun_matches_full_run() {
+        let yaml = r#"
+generators:
+  - type: salary
+    name: Salary
+    amount: 3000
+    day: 26
+  - type: expense
+    name: Holidays
+    amount: 900
+    schedule: {frequency: annually, month: july, day: 1}
+    category: holidays
+    variation: 30
+  - type: overpayment
+    name: Overpay
+    amount: 100
+    day: 10
+    to: mortgage
+    annual_cap_percent: 100
+    start_date: 2026-03-01
+  - type: interest
+    name: Mortgage interest
+    rate: 4
+    day: 1
+    account: mortgage
+  - type: rent
+    name: Rent
+    amount: 700
+    day: 1
+    to: main
+    late_probability: 0.2
+    late_days: {min: 2, max: 9}
+seed: 11
+start_date: 2025-01-01
+accounts:
+  main: 1000.00
+  mortgage: -50000.00
+"#;
+        let config: Config = serde_yaml::from_str(yaml).expect("Failed to parse YAML");
+        let days = 900;
+        let baseline = Baseline::new(&config, all_accounts(&config), days).unwrap();
+        assert_eq!(baseline.history, run(&config, all_accounts(&config), days).unwrap());
+        let date = |y, m, d| chrono::NaiveDate::from_ymd_opt(y, m, d).unwrap();
+        let check = |changed: &Config, affected| {
+            assert_eq!(baseline.first_affected(changed), affected);
+            assert_eq!(baseline.rerun(changed).unwrap(), run(changed, all_accounts(changed), days).unwrap());
         };
-        
-        let (next, _) = compute_next_day_balances(
-            &config,
-            &config.accounts,
-            chrono::NaiveDate::from_ymd_opt(2025, 1, 5).unwrap(),
-            Decimal::ZERO,
-        );
-        
-        // Should only deduct the available £100, leaving balance at zero
-        assert_eq!(next[MAIN_ACCOUNT], dec!(0.00));
-        // Mortgage account should only receive the actual deduction amount
-        let original_mortgage = config.accounts.get(MORTGAGE_ACCOUNT).unwrap();
-        let interest = ((dec!(-500000.00)+dec!(100)) * (dec!(5.0) / dec!(12) / dec!(100))).round_dp(2);
-        assert_eq!(next[MORTGAGE_ACCOUNT], *original_mortgage + dec!(100.00) + interest);
+        check(&config, None);
+        // Picked up from the first overpayment, in March 2026
+        check(&with_parameter(&config, "Overpay", dec!(400)).unwrap(), Some(date(2026, 3, 10)));
+        // The holidays are first due in July, and the random draws before then are unchanged
+        check(&with_parameter(&config, "Holidays", dec!(1500)).unwrap(), Some(date(2025, 7, 1)));
+        // Interest accrues from the start
+        check(&with_parameter(&config, "Mortgage interest", dec!(5)).unwrap(), Some(date(2025, 1, 2)));
+        let mut moved = config.clone();
+        moved.generators[2].start_date = Some(date(2025, 6, 1));
+        check(&moved, Some(date(2025, 6, 1)));
+        let mut seeded = config.clone();
+        seeded.seed = Some(12);
+        check(&seeded, Some(date(2025, 1, 2)));
+
+        // A history of month ends is picked up from as a daily one is
+        let sampled = Config { history: Sampling::MonthEnd, ..config.clone() };
+        let baseline = Baseline::new(&sampled, all_accounts(&sampled), days).unwrap();
+        let changed = with_parameter(&sampled, "Overpay", dec!(400)).unwrap();
+        assert_eq!(baseline.rerun(&changed).unwrap(), run(&changed, all_accounts(&changed), days).unwrap());
     }
 
     #[test]
-    fn test_mortgage_payment_with_negative_balance() {
-        // Set up a scenario where the account already has a negative balance
-        let mut config = create_test_accounts_with_main_balance(5, Some(dec!(-50.00)));
-        config.generators[0] = Generator::Mortgage {
-            deduction_amount: dec!(200.00),
-            deduction_day: 5,
-            from: MAIN_ACCOUNT.to_string(),
-            to: MORTGAGE_ACCOUNT.to_string(),
+    fn test_tithe_on_income_basis() {
+        let yaml = |basis: &str| format!(r#"
+generators:
+  - type: salary
+    amount: 2000.00
+    day: 5
+  - type: rent
+    amount: 1000.00
+    day: 6
+  - type: tithe
+    percentage: 10
+    basis: {basis}
+    day: 10
+accounts:
+  main: 0.00
+"#);
+        let tithe_paid = |basis| {
+            let mut config: Config = serde_yaml::from_str(&yaml(basis)).expect("Failed to parse YAML");
+            config.accounts = add_opening_balances(&add_generator_accounts(&add_default_accounts(&config.accounts), &config.generators));
+            let history = super::run(&config, config.accounts.clone(), 10).unwrap();
+            history.last().unwrap().1[CHARITY_EXPENDITURE]
         };
-        
-        let (next, _) = compute_next_day_balances(
-            &config,
-            &config.accounts,
-            chrono::NaiveDate::from_ymd_opt(2025, 1, 5).unwrap(),
-            Decimal::ZERO,
-        );
-        
-        // Should not deduct anything when balance is already negative
-        assert_eq!(next[MAIN_ACCOUNT], dec!(-50.00)); // No change
-        // Mortgage account should not receive any payment
-        let original_mortgage = config.accounts.get(MORTGAGE_ACCOUNT).unwrap();
-        // work out interest based on original mortgage balance
-        let interest = (dec!(-500000.00) * (dec!(5.0) / dec!(12) / dec!(100))).round_dp(2);
-        assert_eq!(next[MORTGAGE_ACCOUNT], *original_mortgage + interest);
+        assert_eq!(tithe_paid("salary"), dec!(200.00));
+        assert_eq!(tithe_paid("income"), dec!(300.00));
+    }
+
+    #[test]
+    fn test_giving_comparison() {
+        let yaml = r#"
+generators:
+  - type: salary
+    amount: 3000.00
+    day: 1
+  - type: tithe
+    percentage: 10
+    day: 1
+  - type: mortgage
+    deduction_amount: 2000.00
+    deduction_day: 2
+accounts:
+  main: 0.00
+  mortgage: -4000.00
+tithe_comparison:
+  gift_aid: true
+  higher_rate_taxpayer: true
+  strategies:
+    - name: Fixed 1500
+      generators:
+        - type: transfer
+          amount: 1500.00
+          day: 1
+          to: charity_expenditure
+"#;
+        let mut config: Config = serde_yaml::from_str(yaml).expect("Failed to parse YAML");
+        config.accounts = add_opening_balances(&add_default_accounts(&config.accounts));
+        let comparison = config.tithe_comparison.clone().unwrap();
+        let outcomes = giving_comparison(&config, &comparison, &config.accounts.clone().into(), 90).unwrap();
+        let date = |m, d| chrono::NaiveDate::from_ymd_opt(2025, m, d).unwrap();
+
+        assert_eq!(outcomes[0].name, "current");
+        assert_eq!(outcomes[0].given, dec!(900.00));
+        assert_eq!(outcomes[0].gift_aid, dec!(225.00));
+        assert_eq!(outcomes[0].donor_relief, dec!(225.00));
+        assert_eq!(outcomes[0].final_balance, dec!(4100.00));
+        assert_eq!(outcomes[0].payoff_dates, vec![(MORTGAGE_ACCOUNT.to_string(), Some(date(3, 2)))]);
 
+        // Giving more leaves less to pay the mortgage, which isn't cleared within the horizon
+        assert_eq!(outcomes[1].name, "Fixed 1500");
+        assert_eq!(outcomes[1].given, dec!(4500.00));
+        assert_eq!(outcomes[1].final_balance, dec!(1500.00));
+        assert_eq!(outcomes[1].payoff_dates, vec![(MORTGAGE_ACCOUNT.to_string(), None)]);
     }
 
     #[test]
-    fn test_tithe_calculation_basic() {
-        let mut config = create_test_accounts(15); // No mortgage/salary on test day
-        
-        // Add tithe transaction
-        config.generators.push(Generator::Tithe {
-            percentage: dec!(10.0), // 10% tithe
-            day: 10,
-            from: MAIN_ACCOUNT.to_string(),
-            to: CHARITY_EXPENDITURE.to_string(),
-        });
-        
-        // Simulate running for 10 days with salary accumulation
-        let balances = config.accounts.clone();
-        let history = super::run(&config, balances, 10);
-        
-        // Get balances on day 10 (when tithe is paid)
-        let day_10_balances = &history[9].1; // 0-indexed, so day 10 is index 9
-        
-        // Salary should be paid on day 6, so by day 10 we should have one salary payment
-        // Tithe should be 10% of £2000 = £200
-        let expected_tithe = dec!(2000.00) * dec!(10.0) / dec!(100);
-        assert_eq!(expected_tithe, dec!(200.00));
-        
-        // Main account should have: initial + salary - tithe
-        assert_eq!(day_10_balances[MAIN_ACCOUNT], dec!(10000.00) + dec!(2000.00) - expected_tithe);
-        
-        // Charity account should have the tithe amount
-        assert_eq!(day_10_balances[CHARITY_EXPENDITURE], expected_tithe);
+    fn test_loan_amortizes_to_zero() {
+        let yaml = r#"
+generators:
+  - type: loan
+    account: car_loan
+    principal: 10000.00
+    rate: 6.0
+    term_months: 12
+    day: 15
+accounts:
+  main: 12000.00
+  car_loan: -10000.00
+"#;
+        let mut config: Config = serde_yaml::from_str(yaml).expect("Failed to parse YAML");
+        let accounts = add_generator_accounts(&add_default_accounts(&config.accounts), &config.generators);
+        config.accounts = add_opening_balances(&accounts);
+        let history = super::run(&config, config.accounts.clone(), 400).unwrap();
+        let on = |y, m, d| {
+            let date = chrono::NaiveDate::from_ymd_opt(y, m, d).unwrap();
+            history.iter().find(|(day, _)| *day == date).unwrap().1.clone()
+        };
+        // First payment of £860.66 is £50 interest and £810.66 principal
+        assert_eq!(on(2025, 1, 15)["car_loan"], dec!(-10000.00) + dec!(810.66));
+        assert_eq!(on(2025, 1, 15)[LOAN_INTEREST], dec!(50.00));
+        assert_eq!(on(2025, 1, 15)[MAIN_ACCOUNT], dec!(12000.00) - dec!(860.66));
+        // Paid off after twelve payments and nothing taken after that
+        assert_eq!(on(2025, 12, 15)["car_loan"], dec!(0.00));
+        assert_eq!(on(2025, 12, 15)[LOAN_INTEREST], dec!(327.96));
+        assert_eq!(on(2026, 2, 1)[MAIN_ACCOUNT], dec!(12000.00) - dec!(10327.96));
+    }
+
+    fn card_config(repayment: &str) -> Config {
+        let yaml = format!(r#"
+generators:
+  - type: expense
+    amount: 500.00
+    day: 5
+    category: shopping
+    from: card
+  - type: credit_card
+    account: card
+    statement_day: 20
+    payment_delay_days: 15
+    repayment: {repayment}
+    rate: 24.0
+accounts:
+  main: 5000.00
+  card: 0.00
+"#);
+        let mut config: Config = serde_yaml::from_str(&yaml).expect("Failed to parse YAML");
+        let accounts = add_generator_accounts(&add_default_accounts(&config.accounts), &config.generators);
+        config.accounts = add_opening_balances(&accounts);
+        config
     }
 
     #[test]
-    fn test_tithe_multiple_salaries() {
-        // Create a simple config without mortgage/interest transactions to avoid conflicts
-        let accounts = HashMap::from([
-            (MAIN_ACCOUNT.to_string(), dec!(10000.00)),
-        ]);
-        let accounts_with_defaults = super::add_default_accounts(&accounts);
-        let accounts_with_opening = add_opening_balances(&accounts_with_defaults);
-        
-        let config = Config {
-            generators: vec![
-                Generator::Salary {
-                    amount: dec!(2000.00),
-                    day: 6,
-                    to: MAIN_ACCOUNT.to_string(),
-                },
-                Generator::Salary {
-                    amount: dec!(1500.00),
-                    day: 15,
-                    to: MAIN_ACCOUNT.to_string(),
-                },
-                Generator::Tithe {
-                    percentage: dec!(10.0), // 10% tithe
-                    day: 20,
-                    from: MAIN_ACCOUNT.to_string(),
-                    to: CHARITY_EXPENDITURE.to_string(),
-                },
-            ],
-            accounts: accounts_with_opening,
-            currency_symbol: "£".to_string(),
-            start_date: chrono::NaiveDate::from_ymd_opt(2025, 1, 1).unwrap(),
+    fn test_credit_card_paid_in_full() {
+        let config = card_config("full");
+        let history = super::run(&config, config.accounts.clone(), 70).unwrap();
+        let on = |m, d| {
+            let date = chrono::NaiveDate::from_ymd_opt(2025, m, d).unwrap();
+            history.iter().find(|(day, _)| *day == date).unwrap().1.clone()
         };
-        
-        let balances = config.accounts.clone();
-        let history = super::run(&config, balances, 20);
-        
-        // Get balances on day 20
-        let day_20_balances = &history[19].1;
-        
-        // Total salary: £2000 (day 6) + £1500 (day 15) = £3500
-        // Tithe: 10% of £3500 = £350
-        let total_salary = dec!(2000.00) + dec!(1500.00);
-        let expected_tithe = total_salary * dec!(10.0) / dec!(100);
-        assert_eq!(expected_tithe, dec!(350.00));
-        
-        // Main account should have: initial + total_salary - tithe
-        assert_eq!(day_20_balances[MAIN_ACCOUNT], dec!(10000.00) + total_salary - expected_tithe);
-        
-        // Charity account should have the tithe amount
-        assert_eq!(day_20_balances[CHARITY_EXPENDITURE], expected_tithe);
+        assert_eq!(on(1, 5)["card"], dec!(-500.00));
+        // January's statement is paid 15 days after it is cut
+        assert_eq!(on(2, 3)[MAIN_ACCOUNT], dec!(5000.00));
+        assert_eq!(on(2, 4)[MAIN_ACCOUNT], dec!(4500.00));
+        assert_eq!(on(2, 4)["card"], dec!(0.00));
+        assert_eq!(on(3, 8)[MAIN_ACCOUNT], dec!(4000.00));
+        assert_eq!(on(3, 11)[CARD_INTEREST], dec!(0.00));
     }
 
     #[test]
-    fn test_tithe_resets_salary_accumulator() {
-        let mut config = create_test_accounts(30);
-        
-        // Add multiple tithe transactions
-        config.generators.push(Generator::Tithe {
-            percentage: dec!(10.0),
-            day: 10,
-            from: MAIN_ACCOUNT.to_string(),
-            to: CHARITY_EXPENDITURE.to_string(),
-        });
-        
-        config.generators.push(Generator::Salary {
-            amount: dec!(1000.00),
-            day: 15,
-            to: MAIN_ACCOUNT.to_string(),
-        });
-        
-        config.generators.push(Generator::Tithe {
-            percentage: dec!(10.0),
-            day: 20,
-            from: MAIN_ACCOUNT.to_string(),
-            to: CHARITY_EXPENDITURE.to_string(),
-        });
-        
-        let balances = config.accounts.clone();
-        let history = super::run(&config, balances, 20);
-        
-        // Check day 10 - should tithe on first salary only (£2000 from day 6)
-        let day_10_balances = &history[9].1;
-        let first_tithe = dec!(2000.00) * dec!(10.0) / dec!(100);
-        assert_eq!(day_10_balances[CHARITY_EXPENDITURE], first_tithe);
-        
-        // Check day 20 - should tithe only on salary from day 15 (£1000)
-        let day_20_balances = &history[19].1;
-        let second_tithe = dec!(1000.00) * dec!(10.0) / dec!(100);
-        let total_tithe = first_tithe + second_tithe;
-        assert_eq!(day_20_balances[CHARITY_EXPENDITURE], total_tithe);
-        
-        // Main account should reflect both tithes
-        assert_eq!(day_20_balances[MAIN_ACCOUNT], 
-                   dec!(10000.00) + dec!(2000.00) + dec!(1000.00) - total_tithe);
+    fn test_credit_card_minimum_payment_carries_interest() {
+        let config = card_config("minimum");
+        let history = super::run(&config, config.accounts.clone(), 70).unwrap();
+        let on = |m, d| {
+            let date = chrono::NaiveDate::from_ymd_opt(2025, m, d).unwrap();
+            history.iter().find(|(day, _)| *day == date).unwrap().1.clone()
+        };
+        // Minimum of 3% of £500
+        assert_eq!(on(2, 4)[MAIN_ACCOUNT], dec!(4985.00));
+        // February's statement charges a month's interest on the £485 carried
+        assert_eq!(on(2, 20)[CARD_INTEREST], dec!(9.70));
+        assert_eq!(on(2, 20)["card"], dec!(-500.00) - dec!(485.00) - dec!(9.70));
+        // ...and 3% of that is due in March
+        assert_eq!(on(3, 7)[MAIN_ACCOUNT], dec!(4985.00) - dec!(29.84));
     }
 
     #[test]
-    fn test_tithe_with_zero_salary() {
-        let mut config = create_test_accounts(20); // No salary on tithe day
-        
-        // Remove the default salary transaction
-        config.generators = vec![
-            Generator::Tithe {
-                percentage: dec!(10.0),
-                day: 10,
-                from: MAIN_ACCOUNT.to_string(),
-                to: CHARITY_EXPENDITURE.to_string(),
-            }
-        ];
-        
-        let balances = config.accounts.clone();
-        let history = super::run(&config, balances, 10);
-        
-        // Get balances on day 10
-        let day_10_balances = &history[9].1;
-        
-        // No salary, so no tithe should be paid
-        assert_eq!(day_10_balances[CHARITY_EXPENDITURE], dec!(0.00));
-        assert_eq!(day_10_balances[MAIN_ACCOUNT], dec!(10000.00)); // No change
+    fn test_loan_partial_payment_allocation_order() {
+        let yaml = r#"
+generators:
+  - type: loan
+    name: Personal loan
+    account: loan
+    principal: 1200.00
+    rate: 12.0
+    term_months: 12
+    day: 10
+    fee: 5.00
+    allocation: [principal, interest, fees]
+accounts:
+  main: 50.00
+  loan: -1200.00
+"#;
+        let mut config: Config = serde_yaml::from_str(yaml).expect("Failed to parse YAML");
+        let accounts = add_generator_accounts(&add_default_accounts(&config.accounts), &config.generators);
+        config.accounts = add_opening_balances(&accounts);
+        let (history, state) = run_with_state(&config, config.accounts.clone(), 10).unwrap();
+        let final_balances = &history.last().unwrap().1;
+        // Only £50 of the £106.62 payment plus £5 fee can be paid, and it all goes to principal
+        let paid = state.debts[&0].paid;
+        assert_eq!(paid, debt::Allocation { fees: dec!(0), interest: dec!(0), principal: dec!(50.00) });
+        assert_eq!((state.debts[&0].fees, state.debts[&0].interest), (dec!(5.00), dec!(12.00)));
+        assert_eq!(final_balances["loan"], dec!(-1200.00) - dec!(12.00) - dec!(5.00) + dec!(50.00));
+        assert_eq!(final_balances[DEBT_FEES], dec!(5.00));
+        assert_eq!(final_balances[MAIN_ACCOUNT], dec!(0.00));
+        assert_eq!(debt_allocations(&config, &state), vec![("Personal loan".to_string(), paid)]);
+
+        // Told to overdraw, it pays everything due
+        let mut config: Config = serde_yaml::from_str(&yaml.replace("    day: 10", "    day: 10\n    on_insufficient_funds: overdraw")).unwrap();
+        config.accounts = add_opening_balances(&accounts);
+        let (history, state) = run_with_state(&config, config.accounts.clone(), 10).unwrap();
+        assert_eq!(state.debts[&0].paid, debt::Allocation { fees: dec!(5.00), interest: dec!(12.00), principal: dec!(94.62) });
+        assert_eq!(history.last().unwrap().1[MAIN_ACCOUNT], dec!(50.00) - dec!(111.62));
     }
 
     #[test]
-    fn test_config_parsing_with_tithe() {
+    fn test_annual_events_charged_in_their_months() {
         let yaml = r#"
 generators:
-  - type: tithe
-    percentage: 10.0
-    day: 15
-    from: main
-    to: charity_expenditure
+  - type: annual_events
+    events:
+      - name: Summer holiday
+        month: august
+        amount: 2500.00
+      - name: Christmas
+        month: december
+        amount: 800.00
+        day: 10
+      - name: Birthdays
+        month: march
+        amount: 150.00
 accounts:
   main: 5000.00
-currency_symbol: "£"
-start_date: "2025-01-01"
 "#;
-        let config: Config = serde_yaml::from_str(yaml).expect("Failed to parse YAML");
-        assert_eq!(config.generators.len(), 1);
-        
-        if let Generator::Tithe { percentage, day, from, to } = &config.generators[0] {
-            assert_eq!(*percentage, dec!(10.0));
-            assert_eq!(*day, 15);
-            assert_eq!(from, "main");
-            assert_eq!(to, "charity_expenditure");
-        } else {
-            panic!("Expected Tithe transaction");
+        let mut config: Config = serde_yaml::from_str(yaml).expect("Failed to parse YAML");
+        config.accounts = add_opening_balances(&add_generator_accounts(&add_default_accounts(&config.accounts), &config.generators));
+        let history = super::run(&config, config.accounts.clone(), 365).unwrap();
+        let on = |m, d| {
+            let date = chrono::NaiveDate::from_ymd_opt(2025, m, d).unwrap();
+            history.iter().find(|(day, _)| *day == date).unwrap().1.clone()
+        };
+        assert_eq!(on(2, 28)[EVENTS_EXPENDITURE], dec!(0.00));
+        assert_eq!(on(3, 1)[EVENTS_EXPENDITURE], dec!(150.00));
+        assert_eq!(on(8, 1)[EVENTS_EXPENDITURE], dec!(2650.00));
+        assert_eq!(on(12, 9)[EVENTS_EXPENDITURE], dec!(2650.00));
+        assert_eq!(on(12, 10)[EVENTS_EXPENDITURE], dec!(3450.00));
+        assert_eq!(on(12, 31)[MAIN_ACCOUNT], dec!(5000.00) - dec!(3450.00));
+    }
+
+    fn rent_config(void_probability: Decimal, late_probability: Decimal, late_days: DelayRange, seed: Option<u64>) -> Config {
+        let accounts = HashMap::from([(MAIN_ACCOUNT.to_string(), dec!(0.00))]);
+        let generators: Vec<GeneratorEntry> = vec![Generator::Rent {
+                amount: dec!(1000.00),
+                schedule: Schedule::monthly(5),
+                to: MAIN_ACCOUNT.to_string(),
+                income_account: RENTAL_INCOME.to_string(),
+                void_probability,
+                late_probability,
+                late_days,
+                growth_percent: Decimal::ZERO,
+                taxed: false,
+                tax_account: INCOME_TAX.to_string(),
+                other_income: None,
+            }.into()];
+        Config {
+            accounts: add_opening_balances(&add_generator_accounts(&add_default_accounts(&accounts), &generators)),
+            generators,
+            seed,
+            ..Default::default()
         }
     }
 
     #[test]
-    fn test_tithe_with_different_percentage() {
-        let mut config = create_test_accounts(15);
-        
-        config.generators.push(Generator::Tithe {
-            percentage: dec!(5.0), // 5% tithe
-            day: 10,
-            from: MAIN_ACCOUNT.to_string(),
-            to: CHARITY_EXPENDITURE.to_string(),
-        });
-        
-        let balances = config.accounts.clone();
-        let history = super::run(&config, balances, 10);
-        
-        let day_10_balances = &history[9].1;
-        
-        // Tithe should be 5% of £2000 = £100
-        let expected_tithe = dec!(2000.00) * dec!(5.0) / dec!(100);
-        assert_eq!(expected_tithe, dec!(100.00));
-        
-        assert_eq!(day_10_balances[CHARITY_EXPENDITURE], expected_tithe);
-        assert_eq!(day_10_balances[MAIN_ACCOUNT], dec!(10000.00) + dec!(2000.00) - expected_tithe);
+    fn test_rent_expected_value_with_void_and_arrears() {
+        let config = rent_config(dec!(0.1), dec!(0.2), DelayRange { min: 10, max: 20 }, None);
+        let history = super::run(&config, config.accounts.clone(), 25).unwrap();
+        // 90% of rent is expected to be received, a fifth of that 15 days late
+        assert_eq!(history[3].1[MAIN_ACCOUNT], dec!(720.00)); // 5 January
+        assert_eq!(history[17].1[MAIN_ACCOUNT], dec!(720.00)); // 19 January
+        assert_eq!(history[18].1[MAIN_ACCOUNT], dec!(900.00)); // 20 January
+        assert_eq!(history[18].1[RENTAL_INCOME], dec!(-900.00));
+    }
+
+    #[test]
+    fn test_rent_sampled_with_seed() {
+        let always_void = rent_config(dec!(1), dec!(0), DelayRange::default(), Some(1));
+        let history = super::run(&always_void, always_void.accounts.clone(), 90).unwrap();
+        assert_eq!(history.last().unwrap().1[MAIN_ACCOUNT], dec!(0.00));
+
+        let always_late = rent_config(dec!(0), dec!(1), DelayRange { min: 3, max: 3 }, Some(1));
+        let history = super::run(&always_late, always_late.accounts.clone(), 10).unwrap();
+        assert_eq!(history[5].1[MAIN_ACCOUNT], dec!(0.00)); // 7 January
+        assert_eq!(history[6].1[MAIN_ACCOUNT], dec!(1000.00)); // 8 January
+
+        // The same seed gives the same path, and every payment is whole
+        let mixed = rent_config(dec!(0.3), dec!(0.3), DelayRange { min: 1, max: 30 }, Some(42));
+        let first = super::run(&mixed, mixed.accounts.clone(), 730).unwrap();
+        let second = super::run(&mixed, mixed.accounts.clone(), 730).unwrap();
+        assert_eq!(first, second);
+        let received = first.last().unwrap().1[MAIN_ACCOUNT];
+        assert_eq!(received % dec!(1000), Decimal::ZERO);
+        assert!(received < dec!(24000.00));
     }
 
     #[test]
@@ -1194,87 +9842,64 @@ start_date: "2025-01-01"
         let accounts = HashMap::from([
             (MAIN_ACCOUNT.to_string(), dec!(50000.00)),
             (savings_account.to_string(), dec!(10000.00)), // Initial savings balance
+            ("interest_income".to_string(), dec!(0.00)),
         ]);
         let accounts_with_defaults = super::add_default_accounts(&accounts);
         let accounts_with_opening = add_opening_balances(&accounts_with_defaults);
         
-        let mut config = Config {
+        let config = Config {
             generators: vec![
-                // Transfer money into savings account during the year (March 1st)
+                // Transfer money into savings account during the year (September 1st)
                 Generator::Transfer {
                     amount: dec!(5000.00),
-                    day: 1,
+                    schedule: Schedule::Annually { month: chrono::Month::September, day: DayOfMonth::Day(1) },
                     from: MAIN_ACCOUNT.to_string(),
                     to: savings_account.to_string(),
-                },
-                // Annual interest paid on June 30th at 4% rate
+                    percentage: None,
+                    of: None,
+                }.into(),
+                // Annual interest paid on June 30th at 4% rate on the average balance over the year
                 Generator::Interest {
                     rate: dec!(4.0), // 4% annual rate
-                    day: 30,
+                    schedule: Schedule::monthly(30),
                     account: savings_account.to_string(),
                     income_account: "interest_income".to_string(),
                     month: Some(chrono::Month::June), // Annual interest paid in June
-                },
+                    method: InterestMethod::AverageDailyBalance,
+                    rate_schedule: vec![],
+                    base: None,
+                    rate_volatility: Decimal::ZERO,
+                }.into(),
             ],
             accounts: accounts_with_opening,
             currency_symbol: "£".to_string(),
-            start_date: chrono::NaiveDate::from_ymd_opt(2025, 1, 1).unwrap(),
+            // Simulate July 2024 to July 2025
+            start_date: chrono::NaiveDate::from_ymd_opt(2024, 6, 30).unwrap(),
+            ..Default::default()
+        };
+        let history = super::run(&config, config.accounts.clone(), 395).unwrap();
+        let on = |y, m, d| {
+            let date = chrono::NaiveDate::from_ymd_opt(y, m, d).unwrap();
+            history.iter().find(|(day, _)| *day == date).unwrap().1.clone()
         };
         
-        // Add the interest income account
-        config.accounts.insert("interest_income".to_string(), dec!(0.00));
-        
-        // Test before the deposit (February)
-        let (balances_feb, _) = compute_next_day_balances(
-            &config,
-            &config.accounts,
-            chrono::NaiveDate::from_ymd_opt(2025, 2, 15).unwrap(),
-            Decimal::ZERO,
-        );
-        
-        // Should still have original balance
-        assert_eq!(balances_feb[savings_account], dec!(10000.00));
-        
-        // Test after the deposit (March 1st)
-        let (balances_mar, _) = compute_next_day_balances(
-            &config,
-            &balances_feb,
-            chrono::NaiveDate::from_ymd_opt(2025, 3, 1).unwrap(),
-            Decimal::ZERO,
-        );
-        
-        // Should have original balance plus deposit
-        assert_eq!(balances_mar[savings_account], dec!(15000.00));
-        
-        // Test annual interest payment on June 30th - should be calculated on the current balance
-        let (balances_jun, _) = compute_next_day_balances(
-            &config,
-            &balances_mar,
-            chrono::NaiveDate::from_ymd_opt(2025, 6, 30).unwrap(),
-            Decimal::ZERO,
-        );
+        // Before and after the deposit
+        assert_eq!(on(2024, 8, 31)[savings_account], dec!(10000.00));
+        assert_eq!(on(2024, 9, 1)[savings_account], dec!(15000.00));
         
-        let expected_interest = dec!(15000.00) * (dec!(4.0) / dec!(100)) * dec!(10) / dec!(12) +
-            dec!(10000) * dec!(4.0) * dec!(2) / dec!(12) / dec!(100);
-        let expected_interest_pence = expected_interest.round_dp(2);
-        assert_eq!(expected_interest_pence, dec!(566.67));
+        // 62 days at £10,000 (July and August) and 303 days at £15,000
+        let average_balance = (dec!(10000.00) * dec!(62) + dec!(15000.00) * dec!(303)) / dec!(365);
+        let expected_interest = (average_balance * dec!(4.0) / dec!(100)).round_dp(2);
+        assert_eq!(expected_interest, dec!(566.03));
         
         // Savings balance should increase by interest
-        assert_eq!(balances_jun[savings_account], dec!(15000.00) + expected_interest);
+        assert_eq!(on(2025, 6, 30)[savings_account], dec!(15000.00) + expected_interest);
         
         // Interest income account should receive the interest (as negative since it's income)
-        assert_eq!(balances_jun["interest_income"], -expected_interest);
-        
-        // Test that no interest is paid in other months (e.g., July)
-        let (balances_jul, _) = compute_next_day_balances(
-            &config,
-            &balances_jun,
-            chrono::NaiveDate::from_ymd_opt(2025, 7, 30).unwrap(),
-            Decimal::ZERO,
-        );
+        assert_eq!(on(2025, 6, 30)["interest_income"], -expected_interest);
         
         // Savings balance should remain unchanged in July
-        assert_eq!(balances_jul[savings_account], dec!(15000.00) + expected_interest);
+        assert_eq!(on(2025, 7, 30)[savings_account], dec!(15000.00) + expected_interest);
     }
 }
 
@@ -0,0 +1,139 @@
+use chrono::NaiveDate;
+use rand::rngs::StdRng;
+use rand::Rng;
+use rust_decimal::prelude::{FromPrimitive, ToPrimitive};
+use rust_decimal::Decimal;
+use serde::Deserialize;
+
+use crate::balances::Balances;
+
+/// How sure the config's author is of a generator's figures
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Confidence {
+    Low,
+    Medium,
+    High,
+}
+
+/// Spread to sample a low-confidence figure with: twice its own, or
+/// `fallback` if it has none
+pub fn widen(spread: Decimal, fallback: Decimal) -> Decimal {
+    if spread.is_zero() { fallback } else { spread * Decimal::TWO }
+}
+
+/// A standard normal sample, by the Box-Muller transform of two uniform ones
+pub fn standard_normal(rng: &mut StdRng) -> f64 {
+    let (u1, u2): (f64, f64) = (rng.gen_range(f64::EPSILON..1.0), rng.gen_range(0.0..1.0));
+    (-2.0 * u1.ln()).sqrt() * (2.0 * std::f64::consts::PI * u2).cos()
+}
+
+/// A sample from a normal distribution around `mean` with standard deviation
+/// `sd`, or `mean` itself without an `rng`
+pub fn sample(mean: Decimal, sd: Decimal, rng: Option<&mut StdRng>) -> Decimal {
+    match rng {
+        Some(rng) if !sd.is_zero() => {
+            let deviation = sd.to_f64().unwrap_or(0.0) * standard_normal(rng);
+            mean + Decimal::from_f64(deviation).unwrap_or_default().round_dp(10)
+        }
+        _ => mean,
+    }
+}
+
+/// How many times a random event happens in a period it's expected to happen
+/// `mean` times in, drawn from a Poisson distribution by multiplying uniform
+/// samples until the product drops below e^-mean
+pub fn occurrences(mean: f64, rng: &mut StdRng) -> u32 {
+    let limit = (-mean).exp();
+    let mut count = 0;
+    let mut product: f64 = rng.gen_range(0.0..1.0);
+    while product > limit {
+        count += 1;
+        product *= rng.gen_range(0.0..1.0);
+    }
+    count
+}
+
+/// An amount drawn uniformly between `low` and `high`
+pub fn uniform(low: Decimal, high: Decimal, rng: &mut StdRng) -> Decimal {
+    low + (high - low) * Decimal::from_f64(rng.gen_range(0.0..=1.0)).unwrap_or_default()
+}
+
+/// The spread of an account's balance across simulation runs on a day
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Bands {
+    pub p10: Decimal,
+    pub p50: Decimal,
+    pub p90: Decimal,
+}
+
+/// The `percent`th percentile of `sorted`, taking the nearest value below
+fn percentile(sorted: &[Decimal], percent: usize) -> Decimal {
+    sorted[(sorted.len() - 1) * percent / 100]
+}
+
+/// P10, P50 and P90 of `account`'s balance across `runs` on each of `dates`.
+/// Every run must cover the same days.
+pub fn bands(runs: &[Vec<(NaiveDate, Balances)>], account: &str, dates: &[NaiveDate]) -> Vec<(NaiveDate, Bands)> {
+    dates
+        .iter()
+        .map(|date| {
+            let mut balances: Vec<Decimal> = runs
+                .iter()
+                .filter_map(|history| history.iter().find(|(day, _)| day == date))
+                .map(|(_, balances)| balances.get(account).copied().unwrap_or_default())
+                .collect();
+            balances.sort();
+            let bands = if balances.is_empty() {
+                Bands { p10: Decimal::ZERO, p50: Decimal::ZERO, p90: Decimal::ZERO }
+            } else {
+                Bands { p10: percentile(&balances, 10), p50: percentile(&balances, 50), p90: percentile(&balances, 90) }
+            };
+            (*date, bands)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+    use rust_decimal_macros::dec;
+
+    #[test]
+    fn test_sample() {
+        assert_eq!(sample(dec!(5), dec!(2), None), dec!(5));
+        let mut rng = StdRng::seed_from_u64(3);
+        let samples: Vec<f64> = (0..10000).map(|_| sample(dec!(5), dec!(2), Some(&mut rng)).to_f64().unwrap()).collect();
+        let mean = samples.iter().sum::<f64>() / samples.len() as f64;
+        let sd = (samples.iter().map(|s| (s - mean).powi(2)).sum::<f64>() / samples.len() as f64).sqrt();
+        assert!((mean - 5.0).abs() < 0.1, "mean {mean}");
+        assert!((sd - 2.0).abs() < 0.1, "sd {sd}");
+    }
+
+    #[test]
+    fn test_occurrences() {
+        let mut rng = StdRng::seed_from_u64(5);
+        let counts: Vec<u32> = (0..10000).map(|_| occurrences(0.125, &mut rng)).collect();
+        let mean = counts.iter().sum::<u32>() as f64 / counts.len() as f64;
+        assert!((mean - 0.125).abs() < 0.01, "mean {mean}");
+        assert!(counts.iter().any(|&count| count > 1));
+        assert_eq!(occurrences(0.0, &mut rng), 0);
+        let amounts: Vec<Decimal> = (0..1000).map(|_| uniform(dec!(100), dec!(1500), &mut rng)).collect();
+        assert!(amounts.iter().all(|amount| (dec!(100)..=dec!(1500)).contains(amount)));
+    }
+
+    #[test]
+    fn test_widen() {
+        assert_eq!(widen(dec!(15), dec!(10)), dec!(30));
+        assert_eq!(widen(Decimal::ZERO, dec!(10)), dec!(10));
+    }
+
+    #[test]
+    fn test_bands() {
+        let date = NaiveDate::from_ymd_opt(2025, 6, 1).unwrap();
+        let runs: Vec<Vec<(NaiveDate, Balances)>> =
+            (1..=11).rev().map(|n| vec![(date, Balances::from_iter([("main".to_string(), Decimal::from(n * 100))]))]).collect();
+        assert_eq!(bands(&runs, "main", &[date]), vec![(date, Bands { p10: dec!(200), p50: dec!(600), p90: dec!(1000) })]);
+    }
+}
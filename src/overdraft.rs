@@ -0,0 +1,89 @@
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+use serde::{Deserialize, Serialize};
+
+use crate::rounding::Rounding;
+
+/// What it costs for an account to go overdrawn
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct Overdraft {
+    // Arranged overdraft limit, as a positive amount
+    #[serde(default)]
+    pub limit: Decimal,
+    // Annual percentage interest on the overdrawn balance, accrued daily and charged on the 1st of the next month
+    #[serde(default)]
+    pub rate: Decimal,
+    // Charged for each day the account ends overdrawn
+    #[serde(default)]
+    pub daily_fee: Decimal,
+    // Charged for each month in which the account was overdrawn at all
+    #[serde(default)]
+    pub monthly_fee: Decimal,
+    // Flag the day the balance goes past the arranged limit
+    #[serde(default)]
+    pub hard_limit: bool,
+    #[serde(default = "crate::default_overdraft_interest")]
+    pub interest_account: String,
+    #[serde(default = "crate::default_debt_fees")]
+    pub fee_account: String,
+}
+
+/// How an account has used its overdraft so far this month
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Usage {
+    accrued: Decimal,
+    overdrawn_days: u32,
+    over_limit: bool,
+}
+
+impl Usage {
+    /// Note the balance at the end of a day, returning the day's fee and
+    /// whether the balance has just gone past the limit
+    pub fn record(&mut self, overdraft: &Overdraft, balance: Decimal) -> (Decimal, bool) {
+        let breached = balance < -overdraft.limit;
+        let newly_breached = breached && !self.over_limit;
+        self.over_limit = breached;
+        if balance >= Decimal::ZERO {
+            return (Decimal::ZERO, newly_breached);
+        }
+        self.accrued += -balance * overdraft.rate / dec!(100) / dec!(365);
+        self.overdrawn_days += 1;
+        (overdraft.daily_fee, newly_breached)
+    }
+
+    /// Interest and monthly fee for the month just ended, starting a new month
+    pub fn settle(&mut self, overdraft: &Overdraft, rounding: Rounding) -> (Decimal, Decimal) {
+        let interest = rounding.round(self.accrued);
+        let fee = if self.overdrawn_days > 0 { overdraft.monthly_fee } else { Decimal::ZERO };
+        self.accrued = Decimal::ZERO;
+        self.overdrawn_days = 0;
+        (interest, fee)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_usage_charges_and_breaches() {
+        let overdraft = Overdraft {
+            limit: dec!(500),
+            rate: dec!(36.5),
+            daily_fee: dec!(1),
+            monthly_fee: dec!(5),
+            hard_limit: true,
+            interest_account: "overdraft_interest".to_string(),
+            fee_account: "debt_fees".to_string(),
+        };
+        let mut usage = Usage::default();
+        assert_eq!(usage.record(&overdraft, dec!(100)), (Decimal::ZERO, false));
+        assert_eq!(usage.record(&overdraft, dec!(-400)), (dec!(1), false));
+        assert_eq!(usage.record(&overdraft, dec!(-600)), (dec!(1), true));
+        // Still past the limit, so not flagged again until it comes back within it
+        assert_eq!(usage.record(&overdraft, dec!(-600)), (dec!(1), false));
+        // 10p a day per £100 overdrawn
+        assert_eq!(usage.settle(&overdraft, Rounding::default()), (dec!(1.60), dec!(5)));
+        assert_eq!(usage.settle(&overdraft, Rounding::default()), (Decimal::ZERO, Decimal::ZERO));
+    }
+}
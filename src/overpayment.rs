@@ -0,0 +1,63 @@
+use chrono::{Datelike, NaiveDate};
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+use serde::{Deserialize, Serialize};
+
+use crate::rounding::Rounding;
+
+/// How much can still be overpaid on a mortgage in the current calendar year.
+/// Lenders typically allow a percentage of the balance outstanding at the
+/// start of the year before early repayment charges apply.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Allowance {
+    year: i32,
+    remaining: Decimal,
+    // Everything overpaid so far, across all years
+    pub total: Decimal,
+}
+
+impl Allowance {
+    /// Use up to `amount` of the allowance on `date`, returning how much was
+    /// used. The first overpayment in a year sets that year's limit to
+    /// `cap_percent` of `owed`.
+    pub fn take(&mut self, date: NaiveDate, amount: Decimal, owed: Decimal, cap_percent: Decimal, rounding: Rounding) -> Decimal {
+        if date.year() != self.year {
+            self.year = date.year();
+            self.remaining = rounding.round(owed * cap_percent / dec!(100));
+        }
+        let taken = amount.min(self.remaining).min(owed).max(Decimal::ZERO);
+        self.remaining -= taken;
+        self.total += taken;
+        taken
+    }
+
+    /// Return `amount` taken but never paid to the allowance
+    pub fn give_back(&mut self, amount: Decimal) {
+        self.remaining += amount;
+        self.total -= amount;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_allowance_resets_each_year() {
+        let date = |y, m| NaiveDate::from_ymd_opt(y, m, 1).unwrap();
+        let mut allowance = Allowance::default();
+        assert_eq!(allowance.take(date(2025, 1), dec!(6000), dec!(100000), dec!(10), Rounding::default()), dec!(6000));
+        assert_eq!(allowance.take(date(2025, 6), dec!(6000), dec!(94000), dec!(10), Rounding::default()), dec!(4000));
+        assert_eq!(allowance.take(date(2025, 7), dec!(6000), dec!(90000), dec!(10), Rounding::default()), dec!(0));
+        // A new year's limit is based on the balance then
+        assert_eq!(allowance.take(date(2026, 1), dec!(20000), dec!(90000), dec!(10), Rounding::default()), dec!(9000));
+        assert_eq!(allowance.total, dec!(19000));
+    }
+
+    #[test]
+    fn test_allowance_never_exceeds_balance() {
+        let date = NaiveDate::from_ymd_opt(2025, 1, 1).unwrap();
+        let mut allowance = Allowance::default();
+        assert_eq!(allowance.take(date, dec!(500), dec!(300), dec!(100), Rounding::default()), dec!(300));
+    }
+}
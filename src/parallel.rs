@@ -0,0 +1,44 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// How many forecasts are run at once: one for each core
+pub fn threads() -> usize {
+    std::thread::available_parallelism().map_or(1, |threads| threads.get())
+}
+
+/// `f` applied to each of `items`, spread across `threads()` threads, with the
+/// results in the order of the items. Each thread takes the next item not yet
+/// started, so a few slow items don't hold up the rest, and since each result
+/// depends only on its item they come out the same however the work is shared.
+pub fn map<T: Sync, R: Send>(items: &[T], f: impl Fn(&T) -> R + Sync) -> Vec<R> {
+    let next = AtomicUsize::new(0);
+    let mut results: Vec<(usize, R)> = std::thread::scope(|scope| {
+        let workers: Vec<_> = (0..threads().min(items.len()))
+            .map(|_| {
+                scope.spawn(|| {
+                    let mut done = Vec::new();
+                    loop {
+                        let index = next.fetch_add(1, Ordering::Relaxed);
+                        let Some(item) = items.get(index) else { break done };
+                        done.push((index, f(item)));
+                    }
+                })
+            })
+            .collect();
+        // A panic in a forecast, such as a missing account, is passed on as it was raised
+        workers.into_iter().flat_map(|worker| worker.join().unwrap_or_else(|panic| std::panic::resume_unwind(panic))).collect()
+    });
+    results.sort_by_key(|(index, _)| *index);
+    results.into_iter().map(|(_, result)| result).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_map() {
+        let items: Vec<u64> = (0..100).collect();
+        assert_eq!(map(&items, |n| n * n), items.iter().map(|n| n * n).collect::<Vec<_>>());
+        assert_eq!(map(&[] as &[u64], |n| *n), Vec::<u64>::new());
+    }
+}
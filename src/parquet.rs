@@ -0,0 +1,300 @@
+use chrono::NaiveDate;
+
+/// Marks the start and end of a Parquet file
+const MAGIC: &[u8] = b"PAR1";
+
+/// A column of a table, one value per row
+#[derive(Debug, Clone, PartialEq)]
+pub enum Column {
+    /// Stored as days since 1970, typed as dates
+    Date(Vec<NaiveDate>),
+    Text(Vec<String>),
+    /// Text that may be missing, stored as nulls
+    OptionalText(Vec<Option<String>>),
+    Number(Vec<f64>),
+}
+
+impl Column {
+    fn len(&self) -> usize {
+        match self {
+            Column::Date(values) => values.len(),
+            Column::Text(values) => values.len(),
+            Column::OptionalText(values) => values.len(),
+            Column::Number(values) => values.len(),
+        }
+    }
+
+    /// Physical type, repetition and converted type in the schema
+    fn schema(&self) -> (i32, i32, Option<i32>) {
+        const INT32: i32 = 1;
+        const DOUBLE: i32 = 5;
+        const BYTE_ARRAY: i32 = 6;
+        const REQUIRED: i32 = 0;
+        const OPTIONAL: i32 = 1;
+        const UTF8: i32 = 0;
+        const DATE: i32 = 6;
+        match self {
+            Column::Date(_) => (INT32, REQUIRED, Some(DATE)),
+            Column::Text(_) => (BYTE_ARRAY, REQUIRED, Some(UTF8)),
+            Column::OptionalText(_) => (BYTE_ARRAY, OPTIONAL, Some(UTF8)),
+            Column::Number(_) => (DOUBLE, REQUIRED, None),
+        }
+    }
+
+    /// The column's values in plain encoding, preceded by definition levels if it's optional
+    fn page(&self) -> Vec<u8> {
+        let text = |bytes: &mut Vec<u8>, value: &str| {
+            bytes.extend((value.len() as u32).to_le_bytes());
+            bytes.extend(value.as_bytes());
+        };
+        let mut bytes = Vec::new();
+        match self {
+            Column::Date(values) => {
+                let epoch = NaiveDate::from_ymd_opt(1970, 1, 1).unwrap();
+                values.iter().for_each(|date| bytes.extend(((*date - epoch).num_days() as i32).to_le_bytes()));
+            }
+            Column::Text(values) => values.iter().for_each(|value| text(&mut bytes, value)),
+            Column::OptionalText(values) => {
+                let levels = definition_levels(values.iter().map(Option::is_some));
+                bytes.extend((levels.len() as u32).to_le_bytes());
+                bytes.extend(levels);
+                values.iter().flatten().for_each(|value| text(&mut bytes, value));
+            }
+            Column::Number(values) => values.iter().for_each(|value| bytes.extend(value.to_le_bytes())),
+        }
+        bytes
+    }
+}
+
+/// Whether each value is present, as runs in the RLE encoding with a bit width of 1
+fn definition_levels(present: impl Iterator<Item = bool>) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    let mut runs: Vec<(bool, u64)> = Vec::new();
+    for level in present {
+        match runs.last_mut() {
+            Some((last, count)) if *last == level => *count += 1,
+            _ => runs.push((level, 1)),
+        }
+    }
+    for (level, count) in runs {
+        varint(&mut bytes, count << 1);
+        bytes.push(u8::from(level));
+    }
+    bytes
+}
+
+fn varint(bytes: &mut Vec<u8>, mut value: u64) {
+    while value >= 0x80 {
+        bytes.push((value as u8) | 0x80);
+        value >>= 7;
+    }
+    bytes.push(value as u8);
+}
+
+/// Writes structs in Thrift's compact protocol, which Parquet's metadata is kept in
+#[derive(Default)]
+struct Thrift {
+    bytes: Vec<u8>,
+    // Id of the last field written in each struct being written, innermost last
+    last: Vec<i16>,
+}
+
+impl Thrift {
+    const I32: u8 = 5;
+    const I64: u8 = 6;
+    const BINARY: u8 = 8;
+    const LIST: u8 = 9;
+    const STRUCT: u8 = 12;
+
+    /// A field's header: the change in id from the last field packed in with
+    /// its type where that's from 1 to 15, or else the type then the id itself
+    fn field(&mut self, id: i16, kind: u8) {
+        let last = self.last.last_mut().expect("Thrift field written outside a struct");
+        let delta = i32::from(id) - i32::from(*last);
+        *last = id;
+        if (1..=15).contains(&delta) {
+            self.bytes.push(((delta as u8) << 4) | kind);
+        } else {
+            self.bytes.push(kind);
+            self.int(id.into());
+        }
+    }
+
+    fn int(&mut self, value: i64) {
+        varint(&mut self.bytes, ((value << 1) ^ (value >> 63)) as u64);
+    }
+
+    fn i32(&mut self, id: i16, value: i32) {
+        self.field(id, Self::I32);
+        self.int(value.into());
+    }
+
+    fn i64(&mut self, id: i16, value: i64) {
+        self.field(id, Self::I64);
+        self.int(value);
+    }
+
+    fn string(&mut self, id: i16, value: &str) {
+        self.field(id, Self::BINARY);
+        varint(&mut self.bytes, value.len() as u64);
+        self.bytes.extend(value.as_bytes());
+    }
+
+    fn list(&mut self, id: i16, kind: u8, len: usize) {
+        self.field(id, Self::LIST);
+        if len < 15 {
+            self.bytes.push(((len as u8) << 4) | kind);
+        } else {
+            self.bytes.push(0xf0 | kind);
+            varint(&mut self.bytes, len as u64);
+        }
+    }
+
+    fn i32_list(&mut self, id: i16, values: &[i32]) {
+        self.list(id, Self::I32, values.len());
+        values.iter().for_each(|value| self.int((*value).into()));
+    }
+
+    fn string_list(&mut self, id: i16, values: &[&str]) {
+        self.list(id, Self::BINARY, values.len());
+        for value in values {
+            varint(&mut self.bytes, value.len() as u64);
+            self.bytes.extend(value.as_bytes());
+        }
+    }
+
+    /// Start a struct, as field `id` of the one being written, or as an element of a list if None
+    fn begin(&mut self, id: Option<i16>) {
+        if let Some(id) = id {
+            self.field(id, Self::STRUCT);
+        }
+        self.last.push(0);
+    }
+
+    fn end(&mut self) {
+        self.bytes.push(0);
+        self.last.pop();
+    }
+}
+
+/// `columns`, named and all the same length, as a Parquet file with one row
+/// group and a page of uncompressed, plainly encoded values for each column
+pub fn table(columns: &[(&str, Column)], created_by: &str) -> Vec<u8> {
+    const DATA_PAGE: i32 = 0;
+    const PLAIN: i32 = 0;
+    const RLE: i32 = 3;
+    const UNCOMPRESSED: i32 = 0;
+    let rows = columns.first().map_or(0, |(_, column)| column.len());
+    assert!(columns.iter().all(|(_, column)| column.len() == rows), "Parquet columns must all have the same number of rows");
+
+    let mut file = MAGIC.to_vec();
+    // Offset and size of each column's chunk
+    let mut chunks = Vec::new();
+    for (_, column) in columns {
+        let page = column.page();
+        let mut header = Thrift::default();
+        header.begin(None);
+        header.i32(1, DATA_PAGE);
+        header.i32(2, page.len() as i32);
+        header.i32(3, page.len() as i32);
+        header.begin(Some(5));
+        header.i32(1, rows as i32);
+        header.i32(2, PLAIN);
+        header.i32(3, RLE);
+        header.i32(4, RLE);
+        header.end();
+        header.end();
+        chunks.push((file.len() as i64, (header.bytes.len() + page.len()) as i64));
+        file.extend(header.bytes);
+        file.extend(page);
+    }
+
+    let mut footer = Thrift::default();
+    footer.begin(None);
+    footer.i32(1, 1);
+    footer.list(2, Thrift::STRUCT, columns.len() + 1);
+    footer.begin(None);
+    footer.string(4, "schema");
+    footer.i32(5, columns.len() as i32);
+    footer.end();
+    for (name, column) in columns {
+        let (physical, repetition, converted) = column.schema();
+        footer.begin(None);
+        footer.i32(1, physical);
+        footer.i32(3, repetition);
+        footer.string(4, name);
+        if let Some(converted) = converted {
+            footer.i32(6, converted);
+        }
+        footer.end();
+    }
+    footer.i64(3, rows as i64);
+    footer.list(4, Thrift::STRUCT, 1);
+    footer.begin(None);
+    footer.list(1, Thrift::STRUCT, columns.len());
+    for ((name, column), (offset, size)) in columns.iter().zip(&chunks) {
+        footer.begin(None);
+        footer.i64(2, *offset);
+        footer.begin(Some(3));
+        footer.i32(1, column.schema().0);
+        footer.i32_list(2, &[PLAIN, RLE]);
+        footer.string_list(3, &[name]);
+        footer.i32(4, UNCOMPRESSED);
+        footer.i64(5, rows as i64);
+        footer.i64(6, *size);
+        footer.i64(7, *size);
+        footer.i64(9, *offset);
+        footer.end();
+        footer.end();
+    }
+    footer.i64(2, chunks.iter().map(|(_, size)| size).sum());
+    footer.i64(3, rows as i64);
+    footer.end();
+    footer.string(6, created_by);
+    footer.end();
+
+    let length = footer.bytes.len() as u32;
+    file.extend(footer.bytes);
+    file.extend(length.to_le_bytes());
+    file.extend(MAGIC);
+    file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_table() {
+        let mut bytes = Vec::new();
+        varint(&mut bytes, 300);
+        assert_eq!(bytes, [0xac, 0x02]);
+        // Runs of two present, one missing and one present
+        assert_eq!(definition_levels([true, true, false, true].into_iter()), [4, 1, 2, 0, 2, 1]);
+
+        let mut thrift = Thrift::default();
+        thrift.begin(None);
+        thrift.i32(1, -1);
+        thrift.string(4, "a");
+        thrift.end();
+        assert_eq!(thrift.bytes, [0x15, 0x01, 0x38, 0x01, b'a', 0x00]);
+        // Fields more than 15 apart, or going back, take the long form: the
+        // type, then the id as a zigzag varint, as in the Thrift compact spec
+        let mut thrift = Thrift::default();
+        thrift.begin(None);
+        thrift.i32(1, 1);
+        thrift.i32(17, 2);
+        thrift.i32(3, 3);
+        thrift.end();
+        assert_eq!(thrift.bytes, [0x15, 0x02, 0x05, 0x22, 0x04, 0x05, 0x06, 0x06, 0x00]);
+
+        let date = NaiveDate::from_ymd_opt(1970, 1, 3).unwrap();
+        let note = Column::OptionalText(vec![None, Some("hi".to_string())]);
+        assert_eq!(note.page(), [4, 0, 0, 0, 2, 0, 2, 1, 2, 0, 0, 0, b'h', b'i']);
+        let file = table(&[("date", Column::Date(vec![date, date])), ("note", note)], "cashflow");
+        assert_eq!((&file[..4], &file[file.len() - 4..]), (MAGIC, MAGIC));
+        // The first page holds the two dates, two days after 1970 began, and the footer ends with what wrote the file
+        assert!(file.windows(8).any(|window| window == [2, 0, 0, 0, 2, 0, 0, 0]));
+        assert!(file[..file.len() - 8].ends_with(b"cashflow\x00"));
+    }
+}
@@ -0,0 +1,1715 @@
+use chrono::{Datelike, NaiveDate};
+use rust_decimal::Decimal;
+use std::collections::{BTreeMap, HashMap};
+
+use crate::balances::Balances;
+
+/// Net change in each of `accounts` over every calendar month covered by
+/// `history`, measured from `opening` balances. Months are `(year, month)`.
+pub fn monthly_changes(
+    history: &[(NaiveDate, Balances)],
+    opening: &Balances,
+    accounts: &[String],
+) -> Vec<((i32, u32), Vec<Decimal>)> {
+    let balance_of = |balances: &Balances, account: &String| {
+        balances.get(account).copied().unwrap_or(Decimal::ZERO)
+    };
+    let mut rows = Vec::new();
+    let mut previous: Vec<Decimal> = accounts.iter().map(|a| balance_of(opening, a)).collect();
+    for (i, (date, balances)) in history.iter().enumerate() {
+        let month_ends = history
+            .get(i + 1)
+            .is_none_or(|(next, _)| next.month() != date.month() || next.year() != date.year());
+        if month_ends {
+            let current: Vec<Decimal> = accounts.iter().map(|a| balance_of(balances, a)).collect();
+            let changes = current.iter().zip(&previous).map(|(c, p)| c - p).collect();
+            rows.push(((date.year(), date.month()), changes));
+            previous = current;
+        }
+    }
+    rows
+}
+
+/// Money into and out of an account, or all the accounts of a type, over a
+/// month, and the lowest it fell to on the way
+#[derive(Debug, Clone, PartialEq)]
+pub struct Flows {
+    pub name: String,
+    pub inflows: Decimal,
+    pub outflows: Decimal,
+    pub closing: Decimal,
+    pub lowest: Decimal,
+    // The first day of the month the balance was at its lowest
+    pub lowest_on: NaiveDate,
+}
+
+impl Flows {
+    pub fn net(&self) -> Decimal {
+        self.inflows - self.outflows
+    }
+}
+
+/// A month's flows for each account with anything in it, and totalled by type
+#[derive(Debug, Clone, PartialEq)]
+pub struct MonthSummary {
+    // (year, month)
+    pub month: (i32, u32),
+    pub accounts: Vec<Flows>,
+    pub types: Vec<Flows>,
+}
+
+/// Each calendar month's inflows, outflows, closing and lowest balance for
+/// the accounts in `types`, which pairs each with its type (e.g. "Assets"),
+/// leaving out accounts with nothing in them and no movement that month
+pub fn monthly_summary(
+    history: &[(NaiveDate, Balances)],
+    postings: &[crate::journal::Posting],
+    types: &[(String, String)],
+) -> Vec<MonthSummary> {
+    let month_of = |date: NaiveDate| (date.year(), date.month());
+    // Money in and out of each account in each month
+    let mut flows = HashMap::<_, (Decimal, Decimal)>::new();
+    for posting in postings {
+        flows.entry((month_of(posting.date), posting.to.as_str())).or_default().0 += posting.amount;
+        flows.entry((month_of(posting.date), posting.from.as_str())).or_default().1 += posting.amount;
+    }
+    // Lowest balance so far this month of each account, and of each type's total, with its day
+    let mut account_lows = HashMap::<&str, (Decimal, NaiveDate)>::new();
+    let mut type_lows = HashMap::<&str, (Decimal, NaiveDate)>::new();
+    let lower = |lows: &mut HashMap<_, (Decimal, NaiveDate)>, name, balance: Decimal, date: NaiveDate| {
+        let low = lows.entry(name).or_insert((balance, date));
+        if balance < low.0 {
+            *low = (balance, date);
+        }
+    };
+    let mut summaries = Vec::new();
+    for (i, (date, balances)) in history.iter().enumerate() {
+        let mut totals = HashMap::<&str, Decimal>::new();
+        for (account, kind) in types {
+            let balance = balances.get(account).copied().unwrap_or_default();
+            *totals.entry(kind).or_default() += balance;
+            lower(&mut account_lows, account.as_str(), balance, *date);
+        }
+        for (kind, total) in totals {
+            lower(&mut type_lows, kind, total, *date);
+        }
+        if history.get(i + 1).is_some_and(|(next, _)| month_of(*next) == month_of(*date)) {
+            continue;
+        }
+        let mut summary = MonthSummary { month: month_of(*date), accounts: Vec::new(), types: Vec::new() };
+        for (account, kind) in types {
+            let (inflows, outflows) = flows.get(&(summary.month, account.as_str())).copied().unwrap_or_default();
+            let closing = balances.get(account).copied().unwrap_or_default();
+            let total = match summary.types.iter_mut().find(|total| total.name == *kind) {
+                Some(total) => total,
+                None => {
+                    let (lowest, lowest_on) = type_lows[kind.as_str()];
+                    let zero = Decimal::ZERO;
+                    summary.types.push(Flows { name: kind.clone(), inflows: zero, outflows: zero, closing: zero, lowest, lowest_on });
+                    summary.types.last_mut().unwrap()
+                }
+            };
+            total.inflows += inflows;
+            total.outflows += outflows;
+            total.closing += closing;
+            if !(inflows.is_zero() && outflows.is_zero() && closing.is_zero()) {
+                let (lowest, lowest_on) = account_lows[account.as_str()];
+                summary.accounts.push(Flows { name: account.clone(), inflows, outflows, closing, lowest, lowest_on });
+            }
+        }
+        summaries.push(summary);
+        account_lows.clear();
+        type_lows.clear();
+    }
+    summaries
+}
+
+/// Print each month's flows, account by account and then totalled by type
+pub fn print_monthly_summary(summaries: &[MonthSummary], currency_symbol: &str) {
+    if summaries.is_empty() {
+        return;
+    }
+    let width = summaries
+        .iter()
+        .flat_map(|summary| summary.accounts.iter().map(|flows| flows.name.len()).chain(summary.types.iter().map(|flows| flows.name.len() + 6)))
+        .max()
+        .unwrap_or(0)
+        .max(7);
+    let show = |amount: Decimal| format!("{currency_symbol}{amount:.2}");
+    println!("\nMonthly summary:");
+    println!("{:<8} {:<width$} {:>14} {:>14} {:>14} {:>14} {:>14} {:<10}", "Month", "Account", "In", "Out", "Net", "Closing", "Lowest", "On");
+    for summary in summaries {
+        let (year, month) = summary.month;
+        let mut label = format!("{year}-{month:02}");
+        let totals = summary.types.iter().map(|flows| (format!("Total {}", flows.name), flows));
+        for (name, flows) in summary.accounts.iter().map(|flows| (flows.name.clone(), flows)).chain(totals) {
+            println!(
+                "{label:<8} {name:<width$} {:>14} {:>14} {:>14} {:>14} {:>14} {}",
+                show(flows.inflows),
+                show(flows.outflows),
+                show(flows.net()),
+                show(flows.closing),
+                show(flows.lowest),
+                flows.lowest_on
+            );
+            label.clear();
+        }
+    }
+}
+
+/// The monthly summary as an HTML table, folded away as it's long
+pub fn monthly_summary_html(summaries: &[MonthSummary], currency_symbol: &str) -> String {
+    if summaries.is_empty() {
+        return String::new();
+    }
+    let mut html = String::from(
+        "<details>\n<summary>Monthly summary</summary>\n<table>\n<tr><th>Month</th><th>Account</th><th>In</th><th>Out</th><th>Net</th><th>Closing</th><th>Lowest</th><th>On</th></tr>\n",
+    );
+    for summary in summaries {
+        let (year, month) = summary.month;
+        let totals = summary.types.iter().map(|flows| (format!("<b>Total {}</b>", html_escape(&flows.name)), flows));
+        for (name, flows) in summary.accounts.iter().map(|flows| (html_escape(&flows.name), flows)).chain(totals) {
+            html.push_str(&format!(
+                "<tr><td>{year}-{month:02}</td><td>{name}</td><td>{currency_symbol}{:.2}</td><td>{currency_symbol}{:.2}</td><td>{currency_symbol}{:.2}</td><td>{currency_symbol}{:.2}</td><td>{currency_symbol}{:.2}</td><td>{}</td></tr>\n",
+                flows.inflows,
+                flows.outflows,
+                flows.net(),
+                flows.closing,
+                flows.lowest,
+                flows.lowest_on,
+            ));
+        }
+    }
+    html.push_str("</table>\n</details>");
+    html
+}
+
+/// Pay, tax, interest and giving over a calendar or tax year, as positive amounts
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct YearTotals {
+    // e.g. "2025", or "2025/26" for a tax year
+    pub year: String,
+    pub gross_salary: Decimal,
+    pub income_tax: Decimal,
+    pub national_insurance: Decimal,
+    pub net_salary: Decimal,
+    pub interest_paid: Decimal,
+    pub interest_earned: Decimal,
+    // Tithes and other giving from net pay, which gift aid can be claimed on
+    pub given: Decimal,
+    // Reclaimed by the charities on the giving made under Gift Aid
+    pub gift_aid: Decimal,
+    pub payroll_giving: Decimal,
+}
+
+impl YearTotals {
+    /// What the charities received from the giving from net pay, with Gift Aid,
+    /// as self-assessment asks for it
+    pub fn gross_given(&self) -> Decimal {
+        self.given + self.gift_aid
+    }
+
+    fn columns(&self) -> [Decimal; 10] {
+        [
+            self.gross_salary,
+            self.income_tax,
+            self.national_insurance,
+            self.net_salary,
+            self.interest_paid,
+            self.interest_earned,
+            self.given,
+            self.gift_aid,
+            self.gross_given(),
+            self.payroll_giving,
+        ]
+    }
+}
+
+const YEAR_TOTAL_HEADINGS: [&str; 10] = [
+    "Gross salary",
+    "Income tax",
+    "NI",
+    "Net salary",
+    "Interest paid",
+    "Interest earned",
+    "Given",
+    "Gift Aid",
+    "Gross given",
+    "Payroll giving",
+];
+
+/// Print a row of totals for each year, under `title`
+pub fn print_year_totals(title: &str, totals: &[YearTotals], currency_symbol: &str) {
+    if totals.is_empty() {
+        return;
+    }
+    println!("\n{title}:");
+    let headings: Vec<String> = YEAR_TOTAL_HEADINGS.iter().map(|heading| format!("{heading:>15}")).collect();
+    println!("{:<8} {}", "Year", headings.join(" "));
+    for year in totals {
+        let amounts: Vec<String> = year.columns().iter().map(|amount| format!("{:>15}", format!("{currency_symbol}{amount:.2}"))).collect();
+        println!("{:<8} {}", year.year, amounts.join(" "));
+    }
+}
+
+/// The totals as an HTML table
+pub fn year_totals_html(totals: &[YearTotals], currency_symbol: &str) -> String {
+    if totals.is_empty() {
+        return String::new();
+    }
+    let headings: String = YEAR_TOTAL_HEADINGS.iter().map(|heading| format!("<th>{heading}</th>")).collect();
+    let mut html = format!("<table>\n<tr><th>Year</th>{headings}</tr>\n");
+    for year in totals {
+        let amounts: String = year.columns().iter().map(|amount| format!("<td>{currency_symbol}{amount:.2}</td>")).collect();
+        html.push_str(&format!("<tr><td>{}</td>{amounts}</tr>\n", html_escape(&year.year)));
+    }
+    html.push_str("</table>");
+    html
+}
+
+/// Interest earned over a tax year, and the Personal Savings Allowance it's set against
+#[derive(Debug, Clone, PartialEq)]
+pub struct SavingsAllowanceUse {
+    // e.g. "2025/26"
+    pub year: String,
+    pub interest: Decimal,
+    pub allowance: Decimal,
+}
+
+impl SavingsAllowanceUse {
+    /// Interest above the allowance, on which income tax is due
+    pub fn taxable(&self) -> Decimal {
+        (self.interest - self.allowance).max(Decimal::ZERO)
+    }
+}
+
+/// Print each tax year's interest against the Personal Savings Allowance
+pub fn print_savings_allowance(years: &[SavingsAllowanceUse], currency_symbol: &str) {
+    if years.is_empty() {
+        return;
+    }
+    let show = |amount: Decimal| format!("{currency_symbol}{amount:.2}");
+    println!("\nSavings interest against the Personal Savings Allowance:");
+    println!("{:<8} {:>14} {:>14} {:>14}", "Year", "Interest", "Allowance", "Taxable");
+    for year in years {
+        println!("{:<8} {:>14} {:>14} {:>14}", year.year, show(year.interest), show(year.allowance), show(year.taxable()));
+    }
+}
+
+/// What's owned and owed on a day, with debts as negative amounts
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct NetWorth {
+    pub date: NaiveDate,
+    pub assets: Decimal,
+    pub liabilities: Decimal,
+}
+
+impl NetWorth {
+    pub fn net(&self) -> Decimal {
+        self.assets + self.liabilities
+    }
+}
+
+/// The total of the `assets` and of the `liabilities` on each day of `history`
+pub fn net_worth(history: &[(NaiveDate, Balances)], assets: &[String], liabilities: &[String]) -> Vec<NetWorth> {
+    let total = |balances: &Balances, accounts: &[String]| -> Decimal {
+        accounts.iter().filter_map(|account| balances.get(account)).sum()
+    };
+    history
+        .iter()
+        .map(|(date, balances)| NetWorth { date: *date, assets: total(balances, assets), liabilities: total(balances, liabilities) })
+        .collect()
+}
+
+/// Net worth as CSV lines, header first
+pub fn net_worth_csv(worth: &[NetWorth]) -> Vec<String> {
+    std::iter::once("Date,Assets,Liabilities,Net worth".to_string())
+        .chain(worth.iter().map(|day| format!("{},{},{},{}", day.date.format("%Y-%m-%d"), day.assets, day.liabilities, day.net())))
+        .collect()
+}
+
+/// An account's balance at the end of a month as an earlier run forecast it
+/// and as it turned out
+#[derive(Debug, Clone, PartialEq)]
+pub struct Variance {
+    // Last day of the month with an actual balance
+    pub date: NaiveDate,
+    pub account: String,
+    pub forecast: Decimal,
+    pub actual: Decimal,
+}
+
+impl Variance {
+    /// How far the account ended up above the forecast
+    pub fn difference(&self) -> Decimal {
+        self.actual - self.forecast
+    }
+}
+
+/// Each of `accounts` at the end of each month of `actual` that `forecast`
+/// also covers, by month and then account
+pub fn variances(forecast: &[(NaiveDate, Balances)], actual: &[(NaiveDate, Balances)], accounts: &[String]) -> Vec<Variance> {
+    let forecast: HashMap<NaiveDate, &Balances> = forecast.iter().map(|(date, balances)| (*date, balances)).collect();
+    let month_ends = actual.iter().enumerate().filter(|(i, (date, _))| actual.get(i + 1).is_none_or(|(next, _)| next.month() != date.month() || next.year() != date.year()));
+    let mut variances = Vec::new();
+    for (_, (date, balances)) in month_ends {
+        for account in accounts {
+            if let (Some(forecast), Some(actual)) = (forecast.get(date).and_then(|balances| balances.get(account)), balances.get(account)) {
+                variances.push(Variance { date: *date, account: account.clone(), forecast: *forecast, actual: *actual });
+            }
+        }
+    }
+    variances
+}
+
+/// Print each account's month end balance as forecast and as it was
+pub fn print_variances(variances: &[Variance], currency_symbol: &str) {
+    println!("\nForecast against actual:");
+    if variances.is_empty() {
+        println!("  The earlier forecast covers none of the days the statements do");
+        return;
+    }
+    println!("{:<12} {:<20} {:>14} {:>14} {:>14}", "Date", "Account", "Forecast", "Actual", "Difference");
+    for variance in variances {
+        let show = |amount: Decimal| format!("{currency_symbol}{amount:.2}");
+        println!(
+            "{:<12} {:<20} {:>14} {:>14} {:>14}",
+            variance.date.format("%Y-%m-%d").to_string(),
+            variance.account,
+            show(variance.forecast),
+            show(variance.actual),
+            show(variance.difference())
+        );
+    }
+}
+
+/// Print what the assets and liabilities held in each currency come to on
+/// `date`, in that currency and in the base currency, and their total
+pub fn print_currency_totals(date: NaiveDate, totals: &[crate::currency::CurrencyTotal], base: &str, currency_symbol: &str) {
+    println!("\nBalances by currency on {date}:");
+    println!("{:<10} {:>16} {:>16}", "Currency", "Total", format!("In {base}"));
+    for total in totals {
+        let own = if total.currency == base { format!("{currency_symbol}{:.2}", total.total) } else { format!("{} {:.2}", total.currency, total.total) };
+        println!("{:<10} {own:>16} {:>16}", total.currency, format!("{currency_symbol}{:.2}", total.in_base));
+    }
+    let consolidated: Decimal = totals.iter().map(|total| total.in_base).sum();
+    println!("{:<10} {:>16} {:>16}", format!("Total in {base}"), "", format!("{currency_symbol}{consolidated:.2}"));
+}
+
+/// The variances as an HTML table
+pub fn variances_html(variances: &[Variance], currency_symbol: &str) -> String {
+    let mut html = "<table>\n<tr><th>Date</th><th>Account</th><th>Forecast</th><th>Actual</th><th>Difference</th></tr>\n".to_string();
+    for variance in variances {
+        html.push_str(&format!(
+            "<tr><td>{}</td><td>{}</td><td>{currency_symbol}{:.2}</td><td>{currency_symbol}{:.2}</td><td>{currency_symbol}{:.2}</td></tr>\n",
+            variance.date.format("%Y-%m-%d"),
+            html_escape(&variance.account),
+            variance.forecast,
+            variance.actual,
+            variance.difference()
+        ));
+    }
+    html.push_str("</table>");
+    html
+}
+
+/// Print how much went to each spending category in each month
+pub fn print_category_summary(
+    history: &[(NaiveDate, Balances)],
+    opening: &Balances,
+    categories: &[(String, String)],
+    currency_symbol: &str,
+) {
+    if categories.is_empty() {
+        return;
+    }
+    let accounts: Vec<String> = categories.iter().map(|(_, account)| account.clone()).collect();
+    let width = categories.iter().map(|(name, _)| name.len()).max().unwrap_or(0).max(12);
+    println!("\nSpending by category:");
+    print!("{:<8}", "Month");
+    for (name, _) in categories {
+        print!(" {name:>width$}");
+    }
+    println!();
+    for ((year, month), changes) in monthly_changes(history, opening, &accounts) {
+        print!("{year}-{month:02}");
+        for change in changes {
+            let amount = format!("{currency_symbol}{change:.2}");
+            print!(" {amount:>width$}");
+        }
+        println!();
+    }
+}
+
+/// Print how much was given to each recipient in each calendar year, with the total
+pub fn print_giving_summary(
+    history: &[(NaiveDate, Balances)],
+    opening: &Balances,
+    recipients: &[(String, String)],
+    currency_symbol: &str,
+) {
+    if recipients.is_empty() {
+        return;
+    }
+    let accounts: Vec<String> = recipients.iter().map(|(_, account)| account.clone()).collect();
+    let width = recipients.iter().map(|(name, _)| name.len()).max().unwrap_or(0).max(12);
+    let mut years: Vec<(i32, Vec<Decimal>)> = Vec::new();
+    for ((year, _), changes) in monthly_changes(history, opening, &accounts) {
+        match years.last_mut() {
+            Some((last, totals)) if *last == year => totals.iter_mut().zip(changes).for_each(|(total, change)| *total += change),
+            _ => years.push((year, changes)),
+        }
+    }
+    println!("\nGiving by year:");
+    print!("{:<6}", "Year");
+    for (name, _) in recipients {
+        print!(" {name:>width$}");
+    }
+    println!(" {:>width$}", "Total");
+    for (year, given) in years {
+        print!("{year:<6}");
+        for amount in &given {
+            let amount = format!("{currency_symbol}{amount:.2}");
+            print!(" {amount:>width$}");
+        }
+        let total = format!("{currency_symbol}{:.2}", given.iter().sum::<Decimal>());
+        println!(" {total:>width$}");
+    }
+}
+
+/// Print each envelope's allocation, spending and what's left, month by month
+pub fn print_envelope_usage(usages: &[crate::envelope::Usage], currency_symbol: &str) {
+    if usages.is_empty() {
+        return;
+    }
+    let width = usages.iter().map(|usage| usage.envelope.len()).max().unwrap_or(0).max(8);
+    let show = |amount: Decimal| format!("{currency_symbol}{amount:.2}");
+    println!("\nEnvelopes:");
+    println!("{:<8} {:<width$} {:>14} {:>14} {:>14} {:>8}", "Month", "Envelope", "Allocated", "Spent", "Left", "Used");
+    for usage in usages {
+        let (year, month) = usage.month;
+        let used = usage.used().map_or("-".to_string(), |used| format!("{used:.1}%"));
+        println!(
+            "{:<8} {:<width$} {:>14} {:>14} {:>14} {used:>8}",
+            format!("{year}-{month:02}"),
+            usage.envelope,
+            show(usage.allocated),
+            show(usage.spent),
+            show(usage.left)
+        );
+    }
+}
+
+/// One line of the subscription audit
+#[derive(Debug, Clone, PartialEq)]
+pub struct SubscriptionCost {
+    pub name: String,
+    pub annual_cost: Decimal,
+    // How much more the paying account holds at the end of the forecast without it
+    pub cancel_impact: Decimal,
+}
+
+/// Print each posting under its day: the generator, what it's for, and the
+/// accounts it moves money between
+pub fn print_journal(journal: &[crate::journal::Posting], currency_symbol: &str) {
+    let mut day = None;
+    for posting in journal {
+        if day != Some(posting.date) {
+            day = Some(posting.date);
+            println!("\nTransactions on {}:", posting.date);
+        }
+        println!(
+            "  {} ({}): {} -> {} {currency_symbol}{:.2}",
+            posting.source(), posting.description, posting.from, posting.to, posting.amount
+        );
+    }
+}
+
+/// Print subscriptions with their annual cost and the effect of cancelling each
+pub fn print_subscription_audit(costs: &[SubscriptionCost], currency_symbol: &str) {
+    if costs.is_empty() {
+        return;
+    }
+    let width = costs.iter().map(|cost| cost.name.len()).max().unwrap_or(0).max(7);
+    println!("\nSubscription audit:");
+    println!("{:<width$} {:>14} {:>16}", "Service", "Annual cost", "Cancel impact");
+    for cost in costs {
+        let annual = format!("{currency_symbol}{:.2}", cost.annual_cost);
+        let impact = format!("{currency_symbol}{:.2}", cost.cancel_impact);
+        println!("{:<width$} {annual:>14} {impact:>16}", cost.name);
+    }
+    let total: Decimal = costs.iter().map(|cost| cost.annual_cost).sum();
+    let total = format!("{currency_symbol}{total:.2}");
+    println!("{:<width$} {total:>14}", "Total");
+}
+
+/// Print how each debt's payments were split between fees, interest and principal
+pub fn print_debt_allocations(debts: &[(String, crate::debt::Allocation)], currency_symbol: &str) {
+    if debts.is_empty() {
+        return;
+    }
+    let width = debts.iter().map(|(name, _)| name.len()).max().unwrap_or(0).max(4);
+    println!("\nDebt payment allocation:");
+    println!("{:<width$} {:>14} {:>14} {:>14} {:>14}", "Debt", "Fees", "Interest", "Principal", "Total");
+    for (name, paid) in debts {
+        let cells = [paid.fees, paid.interest, paid.principal, paid.total()].map(|v| format!("{currency_symbol}{v:.2}"));
+        println!("{name:<width$} {:>14} {:>14} {:>14} {:>14}", cells[0], cells[1], cells[2], cells[3]);
+    }
+}
+
+/// The first day each account that opens in debt is back to zero or above,
+/// or None if it never is within `history`. Accounts in `skip` are left out.
+pub fn payoff_dates(
+    history: &[(NaiveDate, Balances)],
+    opening: &Balances,
+    skip: &[&str],
+) -> Vec<(String, Option<NaiveDate>)> {
+    let mut debts: Vec<&String> = opening
+        .iter()
+        .filter(|(account, balance)| **balance < Decimal::ZERO && !skip.contains(&account.as_str()))
+        .map(|(account, _)| account)
+        .collect();
+    debts.sort();
+    debts
+        .into_iter()
+        .map(|account| {
+            let paid_off = history
+                .iter()
+                .find(|(_, balances)| balances.get(account).is_some_and(|balance| *balance >= Decimal::ZERO))
+                .map(|(date, _)| *date);
+            (account.clone(), paid_off)
+        })
+        .collect()
+}
+
+/// One giving strategy's results over the forecast
+#[derive(Debug, Clone, PartialEq)]
+pub struct GivingOutcome {
+    pub name: String,
+    pub given: Decimal,
+    // Reclaimed by the charity under Gift Aid
+    pub gift_aid: Decimal,
+    // Reclaimed by the donor as higher rate relief
+    pub donor_relief: Decimal,
+    // Main account balance at the end of the forecast
+    pub final_balance: Decimal,
+    pub payoff_dates: Vec<(String, Option<NaiveDate>)>,
+}
+
+/// Print giving strategies side by side with when each debt is paid off under them
+pub fn print_giving_comparison(outcomes: &[GivingOutcome], currency_symbol: &str) {
+    let Some(first) = outcomes.first() else {
+        return;
+    };
+    let width = outcomes.iter().map(|outcome| outcome.name.len()).max().unwrap_or(0).max(8);
+    println!("\nGiving strategies:");
+    print!("{:<width$} {:>14} {:>14} {:>14} {:>14}", "Strategy", "Given", "Gift Aid", "Donor relief", "Main at end");
+    for (account, _) in &first.payoff_dates {
+        print!(" {:>12}", format!("{account} paid"));
+    }
+    println!();
+    for outcome in outcomes {
+        let cells = [outcome.given, outcome.gift_aid, outcome.donor_relief, outcome.final_balance]
+            .map(|v| format!("{currency_symbol}{v:.2}"));
+        print!("{:<width$} {:>14} {:>14} {:>14} {:>14}", outcome.name, cells[0], cells[1], cells[2], cells[3]);
+        for (_, date) in &outcome.payoff_dates {
+            let date = date.map_or("never".to_string(), |date| date.to_string());
+            print!(" {date:>12}");
+        }
+        println!();
+    }
+}
+
+/// When an interest-bearing debt is cleared and what it costs until then
+#[derive(Debug, Clone, PartialEq)]
+pub struct LiabilityPayoff {
+    pub account: String,
+    pub paid_off: Option<NaiveDate>,
+    // Interest charged over the forecast, as a positive amount
+    pub total_interest: Decimal,
+    // Whole months from the start of the forecast until it's cleared
+    pub remaining_months: Option<u32>,
+}
+
+/// Whole calendar months from `from` to `to`, not counting a final part month
+pub fn months_between(from: NaiveDate, to: NaiveDate) -> u32 {
+    let months = (to.year() - from.year()) * 12 + to.month() as i32 - from.month() as i32;
+    let months = if to.day() < from.day() { months - 1 } else { months };
+    months.max(0) as u32
+}
+
+fn format_term(months: Option<u32>) -> String {
+    match months {
+        Some(months) => format!("{}y {}m", months / 12, months % 12),
+        None => "beyond horizon".to_string(),
+    }
+}
+
+/// Print when each interest-bearing debt is paid off and the interest it costs
+pub fn print_liability_payoffs(payoffs: &[LiabilityPayoff], currency_symbol: &str) {
+    if payoffs.is_empty() {
+        return;
+    }
+    let width = payoffs.iter().map(|payoff| payoff.account.len()).max().unwrap_or(0).max(7);
+    println!("\nDebt payoff:");
+    println!("{:<width$} {:>14} {:>16} {:>16}", "Account", "Paid off", "Remaining term", "Total interest");
+    for payoff in payoffs {
+        let paid_off = payoff.paid_off.map_or("never".to_string(), |date| date.to_string());
+        let interest = format!("{currency_symbol}{:.2}", payoff.total_interest);
+        println!("{:<width$} {paid_off:>14} {:>16} {interest:>16}", payoff.account, format_term(payoff.remaining_months));
+    }
+}
+
+/// The payoff report as an HTML table
+pub fn liability_payoffs_html(payoffs: &[LiabilityPayoff], currency_symbol: &str) -> String {
+    if payoffs.is_empty() {
+        return String::new();
+    }
+    let mut html = String::from("<table>\n<tr><th>Account</th><th>Paid off</th><th>Remaining term</th><th>Total interest</th></tr>\n");
+    for payoff in payoffs {
+        html.push_str(&format!(
+            "<tr><td>{}</td><td>{}</td><td>{}</td><td>{currency_symbol}{:.2}</td></tr>\n",
+            html_escape(&payoff.account),
+            payoff.paid_off.map_or("never".to_string(), |date| date.to_string()),
+            format_term(payoff.remaining_months),
+            payoff.total_interest,
+        ));
+    }
+    html.push_str("</table>");
+    html
+}
+
+/// A period of a debt's amortization schedule, with amounts owed as positive numbers
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AmortizationRow {
+    // (year, month), or (year, 12) for a row covering a whole year
+    pub period: (i32, u32),
+    pub opening: Decimal,
+    pub interest: Decimal,
+    pub paid: Decimal,
+    pub closing: Decimal,
+}
+
+/// Month by month, the interest charged on `account` and what was paid off it.
+/// `interest` is what was charged in each (year, month).
+pub fn amortization(
+    history: &[(NaiveDate, Balances)],
+    opening: &Balances,
+    account: &str,
+    interest: &BTreeMap<(i32, u32), Decimal>,
+) -> Vec<AmortizationRow> {
+    let mut owed = -opening.get(account).copied().unwrap_or_default();
+    monthly_changes(history, opening, &[account.to_string()])
+        .into_iter()
+        .map(|(period, changes)| {
+            let interest = interest.get(&period).copied().unwrap_or_default();
+            let closing = owed - changes[0];
+            let row = AmortizationRow { period, opening: owed, interest, paid: owed + interest - closing, closing };
+            owed = closing;
+            row
+        })
+        .collect()
+}
+
+/// Roll a monthly schedule up into one row per year
+pub fn yearly(rows: &[AmortizationRow]) -> Vec<AmortizationRow> {
+    let mut years: Vec<AmortizationRow> = Vec::new();
+    for row in rows {
+        match years.last_mut() {
+            Some(year) if year.period.0 == row.period.0 => {
+                year.interest += row.interest;
+                year.paid += row.paid;
+                year.closing = row.closing;
+            }
+            _ => years.push(AmortizationRow { period: (row.period.0, 12), ..*row }),
+        }
+    }
+    years
+}
+
+/// A monthly schedule as CSV lines, header first
+pub fn amortization_csv(rows: &[AmortizationRow]) -> Vec<String> {
+    let mut lines = vec!["Month,Opening,Interest,Paid,Closing".to_string()];
+    for row in rows {
+        let (year, month) = row.period;
+        lines.push(format!("{year}-{month:02},{},{},{},{}", row.opening, row.interest, row.paid, row.closing));
+    }
+    lines
+}
+
+/// A yearly schedule as an HTML table
+pub fn amortization_html(rows: &[AmortizationRow], currency_symbol: &str) -> String {
+    let mut html = String::from("<table>\n<tr><th>Year</th><th>Opening</th><th>Interest</th><th>Paid</th><th>Closing</th></tr>\n");
+    for row in rows {
+        let cells = [row.opening, row.interest, row.paid, row.closing].map(|v| format!("<td>{currency_symbol}{v:.2}</td>"));
+        html.push_str(&format!("<tr><td>{}</td>{}</tr>\n", row.period.0, cells.concat()));
+    }
+    html.push_str("</table>");
+    html
+}
+
+/// A payment off a debt, with amounts owed as positive numbers
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PaymentRow {
+    pub date: NaiveDate,
+    pub payment: Decimal,
+    // Interest charged since the last payment, up to and including this one's day
+    pub interest: Decimal,
+    // Anything else added to the debt since the last payment, such as fees or new borrowing
+    pub added: Decimal,
+    pub balance: Decimal,
+    pub cumulative_interest: Decimal,
+}
+
+impl PaymentRow {
+    /// What the payment took off the debt once the interest is paid; below
+    /// zero when it didn't cover the interest
+    pub fn principal(&self) -> Decimal {
+        self.payment - self.interest
+    }
+}
+
+/// A row for each day something is paid off `account`, working through
+/// `postings` from its `opening` balance. Postings described as interest are
+/// the interest charged; anything else taking the balance down is a payment.
+pub fn payment_schedule(postings: &[crate::journal::Posting], opening: &Balances, account: &str) -> Vec<PaymentRow> {
+    let mut rows: Vec<PaymentRow> = Vec::new();
+    let mut owed = -opening.get(account).copied().unwrap_or_default();
+    let (mut interest, mut added, mut cumulative_interest) = (Decimal::ZERO, Decimal::ZERO, Decimal::ZERO);
+    let mut paid: Option<(NaiveDate, Decimal)> = None;
+    for posting in postings.iter().filter(|posting| posting.from == account || posting.to == account) {
+        if let Some((date, payment)) = paid
+            && date != posting.date
+        {
+            rows.push(PaymentRow { date, payment, interest, added, balance: owed, cumulative_interest });
+            (interest, added, paid) = (Decimal::ZERO, Decimal::ZERO, None);
+        }
+        let into = if posting.to == account { posting.received() } else { Decimal::ZERO };
+        let out = if posting.from == account { posting.amount } else { Decimal::ZERO };
+        // What the posting added to the debt
+        let increase = out - into;
+        owed += increase;
+        if posting.description.ends_with("interest") {
+            interest += increase;
+            cumulative_interest += increase;
+        } else if increase < Decimal::ZERO {
+            let (_, payment) = paid.get_or_insert((posting.date, Decimal::ZERO));
+            *payment -= increase;
+        } else {
+            added += increase;
+        }
+    }
+    if let Some((date, payment)) = paid {
+        rows.push(PaymentRow { date, payment, interest, added, balance: owed, cumulative_interest });
+    }
+    rows
+}
+
+/// A payment schedule as CSV lines, header first
+pub fn payment_schedule_csv(rows: &[PaymentRow]) -> Vec<String> {
+    let mut lines = vec!["Date,Payment,Interest,Principal,Added,Balance,Cumulative interest".to_string()];
+    for row in rows {
+        lines.push(format!(
+            "{},{},{},{},{},{},{}",
+            row.date,
+            row.payment,
+            row.interest,
+            row.principal(),
+            row.added,
+            row.balance,
+            row.cumulative_interest
+        ));
+    }
+    lines
+}
+
+/// A payment schedule as an HTML table
+pub fn payment_schedule_html(rows: &[PaymentRow], currency_symbol: &str) -> String {
+    let mut html = String::from(
+        "<table>\n<tr><th>Date</th><th>Payment</th><th>Interest</th><th>Principal</th><th>Added</th><th>Balance</th><th>Cumulative interest</th></tr>\n",
+    );
+    for row in rows {
+        let cells = [row.payment, row.interest, row.principal(), row.added, row.balance, row.cumulative_interest]
+            .map(|v| format!("<td>{currency_symbol}{v:.2}</td>"));
+        html.push_str(&format!("<tr><td>{}</td>{}</tr>\n", row.date, cells.concat()));
+    }
+    html.push_str("</table>");
+    html
+}
+
+/// How overpaying a debt changes when it's cleared
+#[derive(Debug, Clone, PartialEq)]
+pub struct OverpaymentImpact {
+    pub account: String,
+    pub overpaid: Decimal,
+    pub payoff_with: Option<NaiveDate>,
+    pub payoff_without: Option<NaiveDate>,
+}
+
+/// Print each overpaid account's payoff date with and without overpayments
+pub fn print_overpayment_impact(impacts: &[OverpaymentImpact], currency_symbol: &str) {
+    if impacts.is_empty() {
+        return;
+    }
+    let width = impacts.iter().map(|impact| impact.account.len()).max().unwrap_or(0).max(7);
+    let date = |date: Option<NaiveDate>| date.map_or("never".to_string(), |date| date.to_string());
+    println!("\nOverpayments:");
+    println!("{:<width$} {:>14} {:>16} {:>16}", "Account", "Overpaid", "Paid off", "Without");
+    for impact in impacts {
+        let overpaid = format!("{currency_symbol}{:.2}", impact.overpaid);
+        println!(
+            "{:<width$} {overpaid:>14} {:>16} {:>16}",
+            impact.account,
+            date(impact.payoff_with),
+            date(impact.payoff_without)
+        );
+    }
+}
+
+/// A child's university costs and what to save towards them
+#[derive(Debug, Clone, PartialEq)]
+pub struct UniversityPlan {
+    pub child: String,
+    pub first_payment: NaiveDate,
+    pub total_cost: Decimal,
+    pub monthly_saving: Decimal,
+}
+
+/// Print each child's university costs with the monthly saving needed from now
+pub fn print_university_plans(plans: &[UniversityPlan], currency_symbol: &str) {
+    if plans.is_empty() {
+        return;
+    }
+    let width = plans.iter().map(|plan| plan.child.len()).max().unwrap_or(0).max(5);
+    println!("\nUniversity costs:");
+    println!("{:<width$} {:>14} {:>14} {:>16}", "Child", "First payment", "Total cost", "Save monthly");
+    for plan in plans {
+        let total = format!("{currency_symbol}{:.2}", plan.total_cost);
+        let saving = format!("{currency_symbol}{:.2}", plan.monthly_saving);
+        println!("{:<width$} {:>14} {total:>14} {saving:>16}", plan.child, plan.first_payment.to_string());
+    }
+}
+
+/// How a save-as-you-earn contract turns out at one share price
+#[derive(Debug, Clone, PartialEq)]
+pub struct ShareSchemeOutcome {
+    pub scheme: String,
+    pub scenario: String,
+    pub matures: NaiveDate,
+    pub savings: Decimal,
+    pub share_price: Decimal,
+    pub maturity: crate::share_scheme::Maturity,
+}
+
+/// Print the gain and tax from each share scheme under each share price scenario
+pub fn print_share_scheme_outcomes(outcomes: &[ShareSchemeOutcome], currency_symbol: &str) {
+    if outcomes.is_empty() {
+        return;
+    }
+    let width = outcomes.iter().map(|outcome| outcome.scheme.len() + outcome.scenario.len() + 3).max().unwrap_or(0).max(6);
+    println!("\nShare schemes at maturity:");
+    println!("{:<width$} {:>12} {:>12} {:>12} {:>12} {:>12}", "Scheme", "Matures", "Savings", "Share price", "Gain", "Tax");
+    for outcome in outcomes {
+        let money = |amount: Decimal| format!("{currency_symbol}{amount:.2}");
+        println!(
+            "{:<width$} {:>12} {:>12} {:>12} {:>12} {:>12}",
+            format!("{} ({})", outcome.scheme, outcome.scenario),
+            outcome.matures.to_string(),
+            money(outcome.savings),
+            money(outcome.share_price),
+            money(outcome.maturity.gain),
+            money(outcome.maturity.tax),
+        );
+    }
+}
+
+/// Print each day an account went past its hard overdraft limit
+pub fn print_overdraft_breaches(
+    breaches: &[(String, NaiveDate, Decimal)],
+    overdrafts: &HashMap<String, crate::overdraft::Overdraft>,
+    currency_symbol: &str,
+) {
+    if breaches.is_empty() {
+        return;
+    }
+    println!("\nOverdraft limit breaches:");
+    for (account, date, balance) in breaches {
+        let limit = overdrafts.get(account).map_or(Decimal::ZERO, |overdraft| overdraft.limit);
+        println!("{date} {account} at {currency_symbol}{balance:.2}, past its {currency_symbol}{limit:.2} limit");
+    }
+}
+
+/// Print each run of days an account broke an alert rule
+pub fn print_alerts(breaches: &[crate::alerts::Breach], currency_symbol: &str) {
+    if breaches.is_empty() {
+        return;
+    }
+    println!("\nWarnings:");
+    for breach in breaches {
+        let until = breach.to.map_or("the end of the forecast".to_string(), |to| to.to_string());
+        let after = if breach.causes.is_empty() { String::new() } else { format!(", after {}", breach.causes.join(", ")) };
+        println!(
+            "{} {} {} until {until}, reaching {currency_symbol}{:.2}{after}",
+            breach.from, breach.account, breach.rule, breach.worst
+        );
+    }
+}
+
+/// The alert breaches as an HTML table
+pub fn alerts_html(breaches: &[crate::alerts::Breach], currency_symbol: &str) -> String {
+    if breaches.is_empty() {
+        return String::new();
+    }
+    let mut html =
+        String::from("<table>\n<tr><th>From</th><th>To</th><th>Account</th><th>Warning</th><th>Worst balance</th><th>After</th></tr>\n");
+    for breach in breaches {
+        html.push_str(&format!(
+            "<tr><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{currency_symbol}{:.2}</td><td>{}</td></tr>\n",
+            breach.from,
+            breach.to.map_or("end".to_string(), |to| to.to_string()),
+            html_escape(&breach.account),
+            html_escape(&breach.rule),
+            breach.worst,
+            html_escape(&breach.causes.join(", ")),
+        ));
+    }
+    html.push_str("</table>");
+    html
+}
+
+/// What it costs a salary earner to give the same amount to charity through
+/// payroll or from net pay with Gift Aid
+#[derive(Debug, Clone, PartialEq)]
+pub struct PayrollGivingCost {
+    pub salary: String,
+    // What the charity receives in a year, including any Gift Aid
+    pub to_charity: Decimal,
+    // Net cost to the donor of each route, after tax relief
+    pub payroll: Decimal,
+    pub gift_aid: Decimal,
+}
+
+/// Print the net cost of payroll giving against Gift Aid for each salary giving through payroll
+pub fn print_payroll_giving(costs: &[PayrollGivingCost], currency_symbol: &str) {
+    if costs.is_empty() {
+        return;
+    }
+    let width = costs.iter().map(|cost| cost.salary.len()).max().unwrap_or(0).max(6);
+    println!("\nPayroll giving against Gift Aid, per year:");
+    println!("{:<width$} {:>14} {:>14} {:>14} {:>14}", "Salary", "To charity", "Payroll cost", "Gift Aid cost", "Difference");
+    for cost in costs {
+        let money = |amount: Decimal| format!("{currency_symbol}{amount:.2}");
+        println!(
+            "{:<width$} {:>14} {:>14} {:>14} {:>14}",
+            cost.salary,
+            money(cost.to_charity),
+            money(cost.payroll),
+            money(cost.gift_aid),
+            money(cost.gift_aid - cost.payroll),
+        );
+    }
+}
+
+/// Print whether each savings goal is met, when it's reached, and any shortfall
+pub fn print_goals(goals: &[crate::goals::Progress], currency_symbol: &str) {
+    if goals.is_empty() {
+        return;
+    }
+    let width = goals.iter().map(|progress| progress.goal.label().len()).max().unwrap_or(0).max(4);
+    println!("\nGoals:");
+    println!("{:<width$} {:>12} {:>12} {:>6} {:>12} {:>12}", "Goal", "Target", "By", "Met", "Reached", "Shortfall");
+    for progress in goals {
+        println!(
+            "{:<width$} {:>12} {:>12} {:>6} {:>12} {:>12}",
+            progress.goal.label(),
+            format!("{currency_symbol}{:.2}", progress.goal.target),
+            progress.goal.by.to_string(),
+            if progress.met() { "yes" } else { "no" },
+            progress.reached.map_or("never".to_string(), |date| date.to_string()),
+            format!("{currency_symbol}{:.2}", progress.shortfall()),
+        );
+    }
+}
+
+/// Print whether the forecast passes each balance check
+pub fn print_checks(outcomes: &[crate::checks::Outcome], currency_symbol: &str) {
+    if outcomes.is_empty() {
+        return;
+    }
+    println!("\nChecks:");
+    for outcome in outcomes {
+        let check = &outcome.check;
+        let expected = format!("expected {currency_symbol}{:.2} ± {currency_symbol}{:.2}", check.equals, check.tolerance);
+        match (outcome.balance, outcome.difference()) {
+            (Some(balance), Some(difference)) => println!(
+                "{} {} {}: {currency_symbol}{balance:.2}, {expected}, off by {currency_symbol}{difference:.2}",
+                if outcome.passed() { "PASS" } else { "FAIL" },
+                check.date,
+                check.account,
+            ),
+            _ => println!("FAIL {} {}: no forecast balance that day, {expected}", check.date, check.account),
+        }
+    }
+    let passed = outcomes.iter().filter(|outcome| outcome.passed()).count();
+    println!("{passed} of {} checks passed", outcomes.len());
+}
+
+/// The balance checks as an HTML table
+pub fn checks_html(outcomes: &[crate::checks::Outcome], currency_symbol: &str) -> String {
+    if outcomes.is_empty() {
+        return String::new();
+    }
+    let mut html = String::from(
+        "<table>\n<tr><th>Date</th><th>Account</th><th>Expected</th><th>Tolerance</th><th>Forecast</th><th>Difference</th><th>Result</th></tr>\n",
+    );
+    let amount = |amount: Option<Decimal>| amount.map_or("-".to_string(), |amount| format!("{currency_symbol}{amount:.2}"));
+    for outcome in outcomes {
+        html.push_str(&format!(
+            "<tr><td>{}</td><td>{}</td><td>{currency_symbol}{:.2}</td><td>{currency_symbol}{:.2}</td><td>{}</td><td>{}</td><td>{}</td></tr>\n",
+            outcome.check.date,
+            html_escape(&outcome.check.account),
+            outcome.check.equals,
+            outcome.check.tolerance,
+            amount(outcome.balance),
+            amount(outcome.difference()),
+            if outcome.passed() { "pass" } else { "fail" },
+        ));
+    }
+    html.push_str("</table>");
+    html
+}
+
+/// Print whether each goal's forecast keeps up with its planned path, and
+/// where it's furthest behind
+pub fn print_plans(plans: &[(crate::goals::Goal, Vec<crate::goals::PlanRow>)], currency_symbol: &str) {
+    if plans.is_empty() {
+        return;
+    }
+    println!("\nGoals against plan:");
+    for (goal, rows) in plans {
+        let behind: Vec<(chrono::NaiveDate, Decimal)> = rows.iter().filter_map(|row| row.behind().map(|gap| (row.date, gap))).collect();
+        match (behind.first(), behind.iter().max_by_key(|(_, gap)| *gap)) {
+            (Some((first, _)), Some((furthest, gap))) => println!(
+                "{}: behind plan from {first}, furthest on {furthest} by {currency_symbol}{gap:.2}",
+                goal.label()
+            ),
+            _ => println!("{}: on plan throughout", goal.label()),
+        }
+    }
+}
+
+/// Print what `solve` found for the generator called `generator`, already
+/// formatted as an amount or rate, or that nothing up to `max` meets the goal
+pub fn print_solution(goal: &crate::goals::Goal, generator: &str, found: Option<String>, max: &str, currency_symbol: &str) {
+    let aim = if goal.target.is_zero() {
+        format!("{} cleared by {}", goal.account, goal.by)
+    } else {
+        format!("{} at {currency_symbol}{:.2} by {}", goal.account, goal.target, goal.by)
+    };
+    match found {
+        Some(value) => println!("{generator} needs {value} for {aim}"),
+        None => println!("{generator} can't get {aim} with anything up to {max}"),
+    }
+}
+
+/// Print the 10th, 50th and 90th percentile of `account`'s balance across
+/// simulation runs on each date
+pub fn print_bands(account: &str, bands: &[(NaiveDate, crate::monte_carlo::Bands)], currency_symbol: &str) {
+    println!("\n{account} across runs:");
+    println!("{:<12} {:>16} {:>16} {:>16}", "Date", "P10", "P50", "P90");
+    for (date, bands) in bands {
+        let amount = |amount: Decimal| format!("{currency_symbol}{amount:.2}");
+        println!("{:<12} {:>16} {:>16} {:>16}", date.to_string(), amount(bands.p10), amount(bands.p50), amount(bands.p90));
+    }
+}
+
+/// The savings goals as an HTML table
+pub fn goals_html(goals: &[crate::goals::Progress], currency_symbol: &str) -> String {
+    if goals.is_empty() {
+        return String::new();
+    }
+    let mut html = String::from("<table>\n<tr><th>Goal</th><th>Target</th><th>By</th><th>Met</th><th>Reached</th><th>Shortfall</th></tr>\n");
+    for progress in goals {
+        html.push_str(&format!(
+            "<tr><td>{}</td><td>{currency_symbol}{:.2}</td><td>{}</td><td>{}</td><td>{}</td><td>{currency_symbol}{:.2}</td></tr>\n",
+            html_escape(progress.goal.label()),
+            progress.goal.target,
+            progress.goal.by,
+            if progress.met() { "yes" } else { "no" },
+            progress.reached.map_or("never".to_string(), |date| date.to_string()),
+            progress.shortfall(),
+        ));
+    }
+    html.push_str("</table>");
+    html
+}
+
+/// A generator's entry in the assumption register
+#[derive(Debug, Clone, PartialEq)]
+pub struct Assumption {
+    pub confidence: crate::monte_carlo::Confidence,
+    pub generator: String,
+    // How widely Monte Carlo varies its figures, if at all
+    pub spread: Option<String>,
+}
+
+fn confidence_heading(confidence: crate::monte_carlo::Confidence) -> &'static str {
+    match confidence {
+        crate::monte_carlo::Confidence::Low => "Low confidence (guesses)",
+        crate::monte_carlo::Confidence::Medium => "Medium confidence",
+        crate::monte_carlo::Confidence::High => "High confidence",
+    }
+}
+
+/// Print the generators tagged with a confidence, grouped least sure first
+pub fn print_assumption_register(register: &[Assumption]) {
+    if register.is_empty() {
+        return;
+    }
+    println!("\nAssumption register:");
+    let width = register.iter().map(|assumption| assumption.generator.len()).max().unwrap_or(0);
+    let mut heading = None;
+    for assumption in register {
+        if heading != Some(assumption.confidence) {
+            heading = Some(assumption.confidence);
+            println!("{}:", confidence_heading(assumption.confidence));
+        }
+        let spread = assumption.spread.as_deref().unwrap_or("not varied");
+        println!("  {:<width$}  Monte Carlo: {spread}", assumption.generator);
+    }
+}
+
+pub fn assumption_register_html(register: &[Assumption]) -> String {
+    if register.is_empty() {
+        return String::new();
+    }
+    let mut html = String::from("<table>\n<tr><th>Confidence</th><th>Generator</th><th>Monte Carlo spread</th></tr>\n");
+    for assumption in register {
+        html.push_str(&format!(
+            "<tr><td>{}</td><td>{}</td><td>{}</td></tr>\n",
+            confidence_heading(assumption.confidence),
+            html_escape(&assumption.generator),
+            html_escape(assumption.spread.as_deref().unwrap_or("not varied")),
+        ));
+    }
+    html.push_str("</table>");
+    html
+}
+
+/// A headline figure from a forecast
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Metric {
+    Amount(Decimal),
+    // A milestone such as a payoff date, or None if it isn't reached
+    Date(Option<NaiveDate>),
+}
+
+/// Print each metric before and after a change to the forecast, with the difference
+pub fn print_impact(before: &[(String, Metric)], after: &[(String, Metric)], currency_symbol: &str) {
+    let width = before.iter().map(|(name, _)| name.len()).max().unwrap_or(0).max(6);
+    let show = |metric: &Metric| match metric {
+        Metric::Amount(amount) => format!("{currency_symbol}{amount:.2}"),
+        Metric::Date(date) => date.map_or("never".to_string(), |date| date.to_string()),
+    };
+    println!("{:<width$} {:>16} {:>16} {:>16}", "Metric", "Before", "After", "Change");
+    for (name, old) in before {
+        let Some((_, new)) = after.iter().find(|(other, _)| other == name) else {
+            continue;
+        };
+        let change = match (old, new) {
+            (Metric::Amount(old), Metric::Amount(new)) => format!("{currency_symbol}{:+.2}", new - old),
+            (Metric::Date(Some(old)), Metric::Date(Some(new))) => format!("{:+} days", (*new - *old).num_days()),
+            _ => String::new(),
+        };
+        println!("{name:<width$} {:>16} {:>16} {change:>16}", show(old), show(new));
+    }
+}
+
+/// The key figures with a generator's amount or rate set to `value`
+#[derive(Debug, Clone, PartialEq)]
+pub struct SensitivityRow {
+    pub value: Decimal,
+    pub lowest_main: Decimal,
+    pub metrics: Vec<(String, Metric)>,
+}
+
+/// Print a line of key figures for each value tried, with `show` formatting the values
+pub fn print_sensitivity(rows: &[SensitivityRow], main: &str, show: impl Fn(Decimal) -> String, currency_symbol: &str) {
+    let Some(first) = rows.first() else {
+        return;
+    };
+    let lowest = format!("Lowest {main}");
+    let mut headings = vec!["Value", lowest.as_str()];
+    headings.extend(first.metrics.iter().map(|(name, _)| name.as_str()));
+    let widths: Vec<usize> = headings.iter().map(|heading| heading.len().max(12)).collect();
+    let line = |cells: Vec<String>| {
+        let cells: Vec<String> = cells.iter().zip(&widths).map(|(cell, width)| format!("{cell:>width$}")).collect();
+        println!("{}", cells.join(" "));
+    };
+    line(headings.iter().map(|heading| heading.to_string()).collect());
+    for row in rows {
+        let mut cells = vec![show(row.value), format!("{currency_symbol}{:.2}", row.lowest_main)];
+        cells.extend(row.metrics.iter().map(|(_, metric)| match metric {
+            Metric::Amount(amount) => format!("{currency_symbol}{amount:.2}"),
+            Metric::Date(date) => date.map_or("never".to_string(), |date| date.to_string()),
+        }));
+        line(cells);
+    }
+}
+
+/// The value and amounts of each row as CSV, for charting how the figures
+/// change with the value; dates are left out
+pub fn sensitivity_csv(rows: &[SensitivityRow], parameter: &str, main: &str) -> Vec<String> {
+    let amounts = |row: &SensitivityRow| -> Vec<(String, Decimal)> {
+        let mut amounts = vec![(format!("Lowest {main}"), row.lowest_main)];
+        amounts.extend(row.metrics.iter().filter_map(|(name, metric)| match metric {
+            Metric::Amount(amount) => Some((name.clone(), *amount)),
+            Metric::Date(_) => None,
+        }));
+        amounts
+    };
+    let Some(first) = rows.first() else {
+        return Vec::new();
+    };
+    let mut lines = vec![std::iter::once(parameter.to_string()).chain(amounts(first).into_iter().map(|(name, _)| name)).collect::<Vec<_>>().join(",")];
+    for row in rows {
+        lines.push(std::iter::once(row.value.to_string()).chain(amounts(row).into_iter().map(|(_, amount)| amount.to_string())).collect::<Vec<_>>().join(","));
+    }
+    lines
+}
+
+/// A figure for every combination of values of one or two generators
+#[derive(Debug, Clone, PartialEq)]
+pub struct Matrix {
+    pub metric: String,
+    // The generator varied down the rows, and its values as shown
+    pub rows: (String, Vec<String>),
+    // The generator varied across the columns, or with one generator an unnamed single column
+    pub columns: (String, Vec<String>),
+    pub cells: Vec<Vec<Metric>>,
+}
+
+fn show_metric(metric: &Metric, currency_symbol: &str) -> String {
+    match metric {
+        Metric::Amount(amount) => format!("{currency_symbol}{amount:.2}"),
+        Metric::Date(date) => date.map_or("never".to_string(), |date| date.to_string()),
+    }
+}
+
+/// Print the grid with each row's value and then its figures
+pub fn print_matrix(matrix: &Matrix, currency_symbol: &str) {
+    println!("\n{}:", matrix.metric);
+    let corner = if matrix.columns.0.is_empty() { matrix.rows.0.clone() } else { format!("{} \\ {}", matrix.rows.0, matrix.columns.0) };
+    let width = matrix.rows.1.iter().map(String::len).chain([corner.len()]).max().unwrap_or(0);
+    let columns: Vec<String> = matrix.columns.1.iter().map(|column| format!("{column:>14}")).collect();
+    println!("{corner:<width$} {}", columns.join(" "));
+    for (row, cells) in matrix.rows.1.iter().zip(&matrix.cells) {
+        let cells: Vec<String> = cells.iter().map(|cell| format!("{:>14}", show_metric(cell, currency_symbol))).collect();
+        println!("{row:<width$} {}", cells.join(" "));
+    }
+}
+
+/// The grid as CSV, raw values with the row generator's down the first column
+pub fn matrix_csv(matrix: &Matrix) -> Vec<String> {
+    let corner = if matrix.columns.0.is_empty() { matrix.rows.0.clone() } else { format!("{} \\ {}", matrix.rows.0, matrix.columns.0) };
+    let mut lines = vec![std::iter::once(corner).chain(matrix.columns.1.iter().cloned()).collect::<Vec<_>>().join(",")];
+    for (row, cells) in matrix.rows.1.iter().zip(&matrix.cells) {
+        let cells = cells.iter().map(|cell| match cell {
+            Metric::Amount(amount) => amount.to_string(),
+            Metric::Date(date) => date.map_or("never".to_string(), |date| date.to_string()),
+        });
+        lines.push(std::iter::once(row.clone()).chain(cells).collect::<Vec<_>>().join(","));
+    }
+    lines
+}
+
+/// The grid as an HTML table with each cell shaded from red for the worst
+/// figure to green for the best; a date is better earlier unless
+/// `higher_is_better`, and never is the worst
+pub fn matrix_html(matrix: &Matrix, higher_is_better: bool, currency_symbol: &str) -> String {
+    let score = |metric: &Metric| -> Option<Decimal> {
+        match metric {
+            Metric::Amount(amount) => Some(*amount),
+            Metric::Date(date) => date.map(|date| Decimal::from(date.num_days_from_ce())),
+        }
+    };
+    let scores: Vec<Decimal> = matrix.cells.iter().flatten().filter_map(score).collect();
+    let (low, high) = (scores.iter().min().copied().unwrap_or_default(), scores.iter().max().copied().unwrap_or_default());
+    // 0 for the worst through 1 for the best
+    let goodness = |metric: &Metric| -> Decimal {
+        let Some(value) = score(metric) else {
+            return Decimal::ZERO;
+        };
+        let fraction = if high == low { Decimal::ONE } else { (value - low) / (high - low) };
+        if higher_is_better { fraction } else { Decimal::ONE - fraction }
+    };
+    let corner = if matrix.columns.0.is_empty() { matrix.rows.0.clone() } else { format!("{} \\ {}", matrix.rows.0, matrix.columns.0) };
+    let mut html = format!("<table>\n<tr><th>{}</th>", html_escape(&corner));
+    for column in &matrix.columns.1 {
+        html.push_str(&format!("<th>{}</th>", html_escape(column)));
+    }
+    html.push_str("</tr>\n");
+    for (row, cells) in matrix.rows.1.iter().zip(&matrix.cells) {
+        html.push_str(&format!("<tr><th>{}</th>", html_escape(row)));
+        for cell in cells {
+            let hue = (goodness(cell) * Decimal::from(120)).round();
+            html.push_str(&format!(
+                "<td style=\"background: hsl({hue}, 70%, 75%)\">{}</td>",
+                html_escape(&show_metric(cell, currency_symbol))
+            ));
+        }
+        html.push_str("</tr>\n");
+    }
+    html.push_str("</table>");
+    html
+}
+
+/// An account's line in the inventory
+#[derive(Debug, Clone, PartialEq)]
+pub struct AccountSummary {
+    pub name: String,
+    pub opening: Decimal,
+    pub closing: Decimal,
+    pub details: crate::AccountDetails,
+}
+
+/// Print every account with its balances and where it's held
+pub fn print_account_inventory(accounts: &[AccountSummary], currency_symbol: &str) {
+    if accounts.is_empty() {
+        return;
+    }
+    let width = accounts.iter().map(|account| account.name.len()).max().unwrap_or(0).max(7);
+    println!("\nAccounts:");
+    println!("{:<width$} {:>14} {:>14}  Held at", "Account", "Opening", "Closing");
+    for account in accounts {
+        // An account held in another currency shows its amounts in that currency
+        let symbol = account.details.currency.as_ref().map_or(currency_symbol.to_string(), |currency| format!("{currency} "));
+        let opening = format!("{symbol}{:.2}", account.opening);
+        let closing = format!("{symbol}{:.2}", account.closing);
+        println!("{:<width$} {opening:>14} {closing:>14}  {}", account.name, account.details.reference());
+        for extra in [&account.details.url, &account.details.notes].into_iter().flatten() {
+            println!("{:<width$} {extra}", "");
+        }
+    }
+}
+
+/// Print nested accounts as a tree, each with the total of everything under it
+pub fn print_account_tree(nodes: &[crate::hierarchy::Node], currency_symbol: &str) {
+    if nodes.is_empty() {
+        return;
+    }
+    let width = nodes.iter().map(|node| 2 * node.depth + node.name().len()).max().unwrap_or(0).max(7);
+    println!("\nAccounts by group:");
+    println!("{:<width$} {:>14} {:>14} {:>14}", "Account", "Opening", "Closing", "Change");
+    for node in nodes {
+        let name = format!("{}{}", "  ".repeat(node.depth), node.name());
+        let [opening, closing, change] = [node.opening, node.closing, node.closing - node.opening].map(|v| format!("{currency_symbol}{v:.2}"));
+        println!("{name:<width$} {opening:>14} {closing:>14} {change:>14}");
+    }
+}
+
+/// Nested accounts as an HTML table, indented to show what's under what
+pub fn account_tree_html(nodes: &[crate::hierarchy::Node], currency_symbol: &str) -> String {
+    if nodes.is_empty() {
+        return String::new();
+    }
+    let mut html = String::from("<table>\n<tr><th>Account</th><th>Opening</th><th>Closing</th><th>Change</th></tr>\n");
+    for node in nodes {
+        let cells = [node.opening, node.closing, node.closing - node.opening].map(|v| format!("<td>{currency_symbol}{v:.2}</td>"));
+        html.push_str(&format!(
+            "<tr><td style=\"padding-left: {}em\" title=\"{}\">{}</td>{}</tr>\n",
+            node.depth * 2,
+            html_escape(&node.account),
+            html_escape(node.name()),
+            cells.concat()
+        ));
+    }
+    html.push_str("</table>");
+    html
+}
+
+/// The inventory as CSV lines, header first
+pub fn account_inventory_csv(accounts: &[AccountSummary]) -> Vec<String> {
+    let mut lines = vec!["Account,Opening,Closing,Institution,Sort code,Last 4,URL,Notes".to_string()];
+    for account in accounts {
+        let details = &account.details;
+        let text = [&details.institution, &details.sort_code, &details.last4, &details.url, &details.notes]
+            .map(|field| csv_field(field.as_deref().unwrap_or("")));
+        lines.push(format!("{},{},{},{}", csv_field(&account.name), account.opening, account.closing, text.join(",")));
+    }
+    lines
+}
+
+/// The inventory as an HTML table
+pub fn account_inventory_html(accounts: &[AccountSummary], currency_symbol: &str) -> String {
+    let mut html = String::from("<table>\n<tr><th>Account</th><th>Opening</th><th>Closing</th><th>Held at</th><th>Notes</th></tr>\n");
+    for account in accounts {
+        let details = &account.details;
+        let symbol = details.currency.as_ref().map_or(currency_symbol.to_string(), |currency| format!("{currency} "));
+        let held_at = match &details.url {
+            Some(url) => format!("<a href=\"{}\">{}</a>", html_escape(url), html_escape(&details.reference())),
+            None => html_escape(&details.reference()),
+        };
+        html.push_str(&format!(
+            "<tr><td>{}</td><td>{symbol}{:.2}</td><td>{symbol}{:.2}</td><td>{held_at}</td><td>{}</td></tr>\n",
+            html_escape(&account.name),
+            account.opening,
+            account.closing,
+            html_escape(details.notes.as_deref().unwrap_or("")),
+        ));
+    }
+    html.push_str("</table>");
+    html
+}
+
+/// Quote a CSV field if it contains anything that would break the row
+fn csv_field(text: &str) -> String {
+    if text.contains([',', '"', '\n']) {
+        format!("\"{}\"", text.replace('"', "\"\""))
+    } else {
+        text.to_string()
+    }
+}
+
+pub fn html_escape(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal_macros::dec;
+
+    #[test]
+    fn test_monthly_changes() {
+        let day = |m, d| NaiveDate::from_ymd_opt(2025, m, d).unwrap();
+        let balances = |v: Decimal| Balances::from_iter([("bills".to_string(), v)]);
+        let history = vec![
+            (day(1, 30), balances(dec!(10))),
+            (day(1, 31), balances(dec!(25))),
+            (day(2, 1), balances(dec!(25))),
+            (day(2, 2), balances(dec!(40))),
+        ];
+        let opening = balances(dec!(5));
+        let rows = monthly_changes(&history, &opening, &["bills".to_string()]);
+        assert_eq!(rows, vec![((2025, 1), vec![dec!(20)]), ((2025, 2), vec![dec!(15)])]);
+    }
+
+    #[test]
+    fn test_monthly_summary() {
+        let day = |m, d| NaiveDate::from_ymd_opt(2025, m, d).unwrap();
+        let posting = |date, from: &str, to: &str, amount| crate::journal::Posting {
+            date,
+            generator: "Test".to_string(),
+            description: "test".to_string(),
+            from: from.to_string(),
+            to: to.to_string(),
+            amount,
+            received: None,
+            note: None,
+        };
+        let postings = [
+            posting(day(1, 28), "salary_income", "main", dec!(2000)),
+            posting(day(1, 29), "main", "savings", dec!(500)),
+            posting(day(2, 1), "main", "savings", dec!(100)),
+        ];
+        let balances = |main, savings, salary| {
+            Balances::from_iter([("main".to_string(), main), ("savings".to_string(), savings), ("salary_income".to_string(), salary), ("unused".to_string(), Decimal::ZERO)])
+        };
+        // Main dips mid-January, before the salary
+        let history = vec![
+            (day(1, 15), balances(dec!(100), dec!(0), dec!(0))),
+            (day(1, 31), balances(dec!(1500), dec!(500), dec!(-2000))),
+            (day(2, 1), balances(dec!(1400), dec!(600), dec!(-2000))),
+        ];
+        let types: Vec<(String, String)> = [("main", "Assets"), ("savings", "Assets"), ("unused", "Assets"), ("salary_income", "Income")]
+            .map(|(account, kind)| (account.to_string(), kind.to_string()))
+            .to_vec();
+        let summaries = monthly_summary(&history, &postings, &types);
+        let flows = |name: &str, inflows, outflows, closing, (lowest, lowest_on)| Flows {
+            name: name.to_string(),
+            inflows,
+            outflows,
+            closing,
+            lowest,
+            lowest_on,
+        };
+        assert_eq!(
+            summaries[0],
+            MonthSummary {
+                month: (2025, 1),
+                accounts: vec![
+                    flows("main", dec!(2000), dec!(500), dec!(1500), (dec!(100), day(1, 15))),
+                    flows("savings", dec!(500), dec!(0), dec!(500), (dec!(0), day(1, 15))),
+                    flows("salary_income", dec!(0), dec!(2000), dec!(-2000), (dec!(-2000), day(1, 31))),
+                ],
+                types: vec![
+                    flows("Assets", dec!(2500), dec!(500), dec!(2000), (dec!(100), day(1, 15))),
+                    flows("Income", dec!(0), dec!(2000), dec!(-2000), (dec!(-2000), day(1, 31))),
+                ],
+            }
+        );
+        assert_eq!(summaries[1].month, (2025, 2));
+        assert_eq!(summaries[1].accounts[0].net(), dec!(-100));
+        assert_eq!(summaries[1].accounts[2], flows("salary_income", dec!(0), dec!(0), dec!(-2000), (dec!(-2000), day(2, 1))));
+    }
+
+    #[test]
+    fn test_net_worth() {
+        let day = |d| NaiveDate::from_ymd_opt(2025, 1, d).unwrap();
+        let balances = |main, mortgage| {
+            Balances::from_iter([("main".to_string(), main), ("mortgage".to_string(), mortgage), ("salary_income".to_string(), dec!(-5000))])
+        };
+        let history = vec![(day(1), balances(dec!(1000), dec!(-9000))), (day(2), balances(dec!(500), dec!(-8500)))];
+        let worth = net_worth(&history, &["main".to_string()], &["mortgage".to_string()]);
+        assert_eq!(worth[1], NetWorth { date: day(2), assets: dec!(500), liabilities: dec!(-8500) });
+        assert_eq!(
+            net_worth_csv(&worth),
+            vec!["Date,Assets,Liabilities,Net worth", "2025-01-01,1000,-9000,-8000", "2025-01-02,500,-8500,-8000"]
+        );
+    }
+
+    #[test]
+    fn test_variances() {
+        let day = |m, d| NaiveDate::from_ymd_opt(2025, m, d).unwrap();
+        let balances = |main| Balances::from_iter([("main".to_string(), main)]);
+        let forecast = vec![(day(1, 31), balances(dec!(1000))), (day(2, 27), balances(dec!(1200))), (day(2, 28), balances(dec!(1500)))];
+        // The statements run to 27 February
+        let actual = vec![(day(1, 30), balances(dec!(900))), (day(1, 31), balances(dec!(950))), (day(2, 27), balances(dec!(1100)))];
+        let variances = variances(&forecast, &actual, &["main".to_string(), "savings".to_string()]);
+        let variance = |date, forecast, actual| Variance { date, account: "main".to_string(), forecast, actual };
+        assert_eq!(variances, vec![variance(day(1, 31), dec!(1000), dec!(950)), variance(day(2, 27), dec!(1200), dec!(1100))]);
+        assert_eq!(variances[0].difference(), dec!(-50));
+        assert!(variances_html(&variances, "£").contains("<td>£1200.00</td><td>£1100.00</td><td>£-100.00</td>"));
+    }
+
+    #[test]
+    fn test_payoff_dates() {
+        let day = |d| NaiveDate::from_ymd_opt(2025, 1, d).unwrap();
+        let balances = |loan: Decimal, card: Decimal| {
+            Balances::from_iter([("loan".to_string(), loan), ("card".to_string(), card), ("opening_balances".to_string(), dec!(-1))])
+        };
+        let history = vec![
+            (day(1), balances(dec!(-20), dec!(-5))),
+            (day(2), balances(dec!(-10), dec!(0))),
+            (day(3), balances(dec!(0), dec!(-5))),
+        ];
+        let opening = balances(dec!(-30), dec!(-5));
+        assert_eq!(
+            payoff_dates(&history, &opening, &["opening_balances"]),
+            vec![("card".to_string(), Some(day(2))), ("loan".to_string(), Some(day(3)))]
+        );
+        assert_eq!(payoff_dates(&history[..2], &opening, &["opening_balances"])[1], ("loan".to_string(), None));
+    }
+
+    #[test]
+    fn test_account_inventory_exports() {
+        let accounts = vec![AccountSummary {
+            name: "savings".to_string(),
+            opening: dec!(100),
+            closing: dec!(150.50),
+            details: crate::AccountDetails {
+                institution: Some("Nationwide".to_string()),
+                sort_code: Some("07-00-93".to_string()),
+                last4: Some("1234".to_string()),
+                url: Some("https://example.com/?a=1&b=2".to_string()),
+                notes: Some("Rainy day fund, \"do not touch\"".to_string()),
+                currency: None,
+                backed_by: None,
+                floor: None,
+                allow_credit: false,
+            },
+        }];
+        assert_eq!(accounts[0].details.reference(), "Nationwide 07-00-93 ****1234");
+        let csv = account_inventory_csv(&accounts);
+        assert_eq!(
+            csv[1],
+            r#"savings,100,150.50,Nationwide,07-00-93,1234,https://example.com/?a=1&b=2,"Rainy day fund, ""do not touch""""#
+        );
+        let html = account_inventory_html(&accounts, "£");
+        assert!(html.contains(r#"<a href="https://example.com/?a=1&amp;b=2">Nationwide 07-00-93 ****1234</a>"#));
+        assert!(html.contains("<td>£150.50</td>"));
+        assert!(html.contains("Rainy day fund, &quot;do not touch&quot;"));
+    }
+
+    #[test]
+    fn test_months_between() {
+        let date = |y, m, d| NaiveDate::from_ymd_opt(y, m, d).unwrap();
+        assert_eq!(months_between(date(2025, 1, 15), date(2025, 1, 31)), 0);
+        assert_eq!(months_between(date(2025, 1, 15), date(2025, 2, 14)), 0);
+        assert_eq!(months_between(date(2025, 1, 15), date(2025, 2, 15)), 1);
+        assert_eq!(months_between(date(2025, 1, 1), date(2037, 4, 1)), 147);
+        assert_eq!(format_term(Some(147)), "12y 3m");
+        assert_eq!(format_term(None), "beyond horizon");
+    }
+
+    #[test]
+    fn test_amortization() {
+        let day = |y, m, d| NaiveDate::from_ymd_opt(y, m, d).unwrap();
+        let balances = |v: Decimal| Balances::from_iter([("loan".to_string(), v)]);
+        let history = vec![
+            (day(2025, 12, 1), balances(dec!(-1010))),
+            (day(2025, 12, 2), balances(dec!(-810))),
+            (day(2026, 1, 1), balances(dec!(-818.10))),
+            (day(2026, 1, 2), balances(dec!(-618.10))),
+        ];
+        let interest = BTreeMap::from([((2025, 12), dec!(10)), ((2026, 1), dec!(8.10))]);
+        let rows = amortization(&history, &balances(dec!(-1000)), "loan", &interest);
+        assert_eq!(
+            rows,
+            vec![
+                AmortizationRow { period: (2025, 12), opening: dec!(1000), interest: dec!(10), paid: dec!(200), closing: dec!(810) },
+                AmortizationRow {
+                    period: (2026, 1),
+                    opening: dec!(810),
+                    interest: dec!(8.10),
+                    paid: dec!(200.00),
+                    closing: dec!(618.10),
+                },
+            ]
+        );
+        assert_eq!(amortization_csv(&rows)[1], "2025-12,1000,10,200,810");
+        let two_months_one_year: Vec<AmortizationRow> =
+            rows.iter().map(|row| AmortizationRow { period: (2026, row.period.1), ..*row }).collect();
+        assert_eq!(
+            yearly(&two_months_one_year),
+            vec![AmortizationRow { period: (2026, 12), opening: dec!(1000), interest: dec!(18.10), paid: dec!(400.00), closing: dec!(618.10) }]
+        );
+    }
+
+    #[test]
+    fn test_payment_schedule() {
+        let day = |m, d| NaiveDate::from_ymd_opt(2025, m, d).unwrap();
+        let posting = |date, description: &str, from: &str, to: &str, amount| crate::journal::Posting {
+            date,
+            generator: "Loan".to_string(),
+            description: description.to_string(),
+            from: from.to_string(),
+            to: to.to_string(),
+            amount,
+            received: None,
+            note: None,
+        };
+        let postings = [
+            posting(day(1, 1), "interest", "loan", "loan_interest", dec!(10)),
+            posting(day(1, 1), "repayment", "main", "loan", dec!(200)),
+            posting(day(1, 15), "late fee", "loan", "debt_fees", dec!(12)),
+            posting(day(1, 20), "salary", "salary_income", "main", dec!(2000)),
+            // Interest on a debt from an interest generator comes in from its income account as a negative amount
+            posting(day(2, 1), "interest", "mortgage_income", "loan", dec!(-8.22)),
+            posting(day(2, 1), "repayment", "main", "loan", dec!(5)),
+            posting(day(2, 1), "overpayment", "main", "loan", dec!(95)),
+        ];
+        let rows = payment_schedule(&postings, &Balances::from_iter([("loan".to_string(), dec!(-1000))]), "loan");
+        let row = |date, payment, interest, added, balance, cumulative_interest| PaymentRow { date, payment, interest, added, balance, cumulative_interest };
+        assert_eq!(
+            rows,
+            vec![
+                row(day(1, 1), dec!(200), dec!(10), dec!(0), dec!(810), dec!(10)),
+                row(day(2, 1), dec!(100), dec!(8.22), dec!(12), dec!(730.22), dec!(18.22)),
+            ]
+        );
+        assert_eq!(rows[1].principal(), dec!(91.78));
+        assert_eq!(payment_schedule_csv(&rows)[2], "2025-02-01,100,8.22,91.78,12,730.22,18.22");
+    }
+}
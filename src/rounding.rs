@@ -0,0 +1,132 @@
+use rust_decimal::{Decimal, RoundingStrategy};
+use serde::Deserialize;
+use std::collections::HashMap;
+
+/// How an amount is brought to a whole number of minor units, such as pence
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Strategy {
+    /// Halves go away from zero, so 0.125 becomes 0.13
+    HalfUp,
+    /// Halves go to the even digit, so 0.125 becomes 0.12 and 0.135 becomes 0.14
+    #[default]
+    HalfEven,
+    /// Anything past the last minor unit is dropped, so 0.129 becomes 0.12
+    Truncate,
+}
+
+/// The strategy and number of decimal places amounts are rounded to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Rounding {
+    pub strategy: Strategy,
+    pub places: u32,
+}
+
+impl Default for Rounding {
+    fn default() -> Self {
+        Rounding { strategy: Strategy::default(), places: 2 }
+    }
+}
+
+impl Rounding {
+    pub fn round(self, amount: Decimal) -> Decimal {
+        let strategy = match self.strategy {
+            Strategy::HalfUp => RoundingStrategy::MidpointAwayFromZero,
+            Strategy::HalfEven => RoundingStrategy::MidpointNearestEven,
+            Strategy::Truncate => RoundingStrategy::ToZero,
+        };
+        amount.round_dp_with_strategy(self.places, strategy)
+    }
+}
+
+/// A different strategy or precision for a currency or an account, taking
+/// whatever it leaves out from the rounding it overrides
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+pub struct Override {
+    #[serde(default)]
+    pub strategy: Option<Strategy>,
+    #[serde(default)]
+    pub places: Option<u32>,
+}
+
+impl Override {
+    fn apply(&self, rounding: Rounding) -> Rounding {
+        Rounding { strategy: self.strategy.unwrap_or(rounding.strategy), places: self.places.unwrap_or(rounding.places) }
+    }
+}
+
+/// How every amount the generators work out is rounded: one strategy and
+/// precision for everything, overridden for a currency, e.g. `JPY: {places: 0}`,
+/// and again for an account, e.g. `mortgage: {strategy: half_up}`
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct Rules {
+    #[serde(default)]
+    pub strategy: Strategy,
+    #[serde(default = "default_places")]
+    pub places: u32,
+    #[serde(default)]
+    pub currencies: HashMap<String, Override>,
+    #[serde(default)]
+    pub accounts: HashMap<String, Override>,
+}
+
+fn default_places() -> u32 {
+    2
+}
+
+impl Default for Rules {
+    fn default() -> Self {
+        Rules { strategy: Strategy::default(), places: default_places(), currencies: HashMap::new(), accounts: HashMap::new() }
+    }
+}
+
+impl Rules {
+    /// Rounding for amounts in `currency`
+    pub fn for_currency(&self, currency: &str) -> Rounding {
+        let rounding = Rounding { strategy: self.strategy, places: self.places };
+        self.currencies.get(currency).map_or(rounding, |rule| rule.apply(rounding))
+    }
+
+    /// Rounding for amounts paid to or worked out on `account`, held in `currency`
+    pub fn for_account(&self, account: &str, currency: &str) -> Rounding {
+        let rounding = self.for_currency(currency);
+        self.accounts.get(account).map_or(rounding, |rule| rule.apply(rounding))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal_macros::dec;
+
+    #[test]
+    fn test_round() {
+        let rounding = |strategy| Rounding { strategy, places: 2 };
+        assert_eq!(rounding(Strategy::HalfEven).round(dec!(0.125)), dec!(0.12));
+        assert_eq!(rounding(Strategy::HalfEven).round(dec!(0.135)), dec!(0.14));
+        assert_eq!(rounding(Strategy::HalfUp).round(dec!(0.125)), dec!(0.13));
+        assert_eq!(rounding(Strategy::HalfUp).round(dec!(-0.125)), dec!(-0.13));
+        assert_eq!(rounding(Strategy::Truncate).round(dec!(0.129)), dec!(0.12));
+        assert_eq!(rounding(Strategy::Truncate).round(dec!(-0.129)), dec!(-0.12));
+        assert_eq!(Rounding { strategy: Strategy::HalfUp, places: 0 }.round(dec!(150.5)), dec!(151));
+    }
+
+    #[test]
+    fn test_rules() {
+        let rules: Rules = serde_yaml::from_str(
+            "
+strategy: half_up
+currencies:
+  JPY: {places: 0}
+accounts:
+  mortgage: {strategy: truncate}
+  yen_savings: {strategy: half_even}
+",
+        )
+        .unwrap();
+        assert_eq!(rules.for_account("main", "GBP"), Rounding { strategy: Strategy::HalfUp, places: 2 });
+        assert_eq!(rules.for_account("mortgage", "GBP"), Rounding { strategy: Strategy::Truncate, places: 2 });
+        assert_eq!(rules.for_account("yen_savings", "JPY"), Rounding { strategy: Strategy::HalfEven, places: 0 });
+        assert_eq!(Rules::default().for_account("main", "GBP"), Rounding::default());
+    }
+}
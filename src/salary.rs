@@ -0,0 +1,72 @@
+use chrono::{Datelike, Month, NaiveDate};
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+use serde::Deserialize;
+
+use crate::rounding::Rounding;
+
+/// A change of salary to a new amount per payment from a given date
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct Raise {
+    pub date: NaiveDate,
+    pub amount: Decimal,
+}
+
+/// The salary payment due on `date`: the latest raise's amount (or `amount`
+/// before any raise), increased by `annual_increase_percent` on the first of
+/// `increase_month` each year since the forecast started or the raise took effect
+pub fn amount_on(
+    amount: Decimal,
+    annual_increase_percent: Decimal,
+    increase_month: Month,
+    raises: &[Raise],
+    start: NaiveDate,
+    date: NaiveDate,
+    rounding: Rounding,
+) -> Decimal {
+    let (base, since) = raises
+        .iter()
+        .filter(|raise| raise.date <= date)
+        .max_by_key(|raise| raise.date)
+        .map_or((amount, start), |raise| (raise.amount, raise.date));
+    if annual_increase_percent.is_zero() {
+        return base;
+    }
+    let increases = (since.year()..=date.year())
+        .filter_map(|year| NaiveDate::from_ymd_opt(year, increase_month.number_from_month(), 1))
+        .filter(|increase| *increase > since && *increase <= date)
+        .count();
+    let growth = (0..increases).fold(Decimal::ONE, |acc, _| acc * (Decimal::ONE + annual_increase_percent / dec!(100)));
+    rounding.round(base * growth)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(y: i32, m: u32, d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(y, m, d).unwrap()
+    }
+
+    #[test]
+    fn test_annual_increases() {
+        let start = date(2025, 1, 1);
+        let pay = |on| amount_on(dec!(2000), dec!(3), Month::April, &[], start, on, Rounding::default());
+        assert_eq!(pay(date(2025, 3, 31)), dec!(2000));
+        assert_eq!(pay(date(2025, 4, 1)), dec!(2060.00));
+        assert_eq!(pay(date(2026, 3, 31)), dec!(2060.00));
+        assert_eq!(pay(date(2026, 4, 1)), dec!(2121.80));
+        assert_eq!(amount_on(dec!(2000), Decimal::ZERO, Month::April, &[], start, date(2030, 1, 1), Rounding::default()), dec!(2000));
+    }
+
+    #[test]
+    fn test_raise_resets_base() {
+        let start = date(2025, 1, 1);
+        let raises = [Raise { date: date(2025, 9, 1), amount: dec!(2500) }];
+        let pay = |on| amount_on(dec!(2000), dec!(10), Month::January, &raises, start, on, Rounding::default());
+        assert_eq!(pay(date(2025, 8, 31)), dec!(2000));
+        assert_eq!(pay(date(2025, 9, 1)), dec!(2500));
+        // Increases count from the raise
+        assert_eq!(pay(date(2026, 1, 1)), dec!(2750.00));
+    }
+}
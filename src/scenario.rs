@@ -0,0 +1,160 @@
+use rust_decimal::Decimal;
+use serde::Deserialize;
+use serde_yaml::{Mapping, Value};
+use std::collections::HashMap;
+
+/// A variation on the config, forecast alongside it
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct Scenario {
+    pub name: String,
+    // Top-level settings to replace, e.g. `inflation_rate: 4.0`
+    #[serde(default)]
+    pub set: Mapping,
+    // Fields to replace in generators, keyed by generator name (not case sensitive),
+    // e.g. `Salary: {end_date: 2025-06-30}` for redundancy in June
+    #[serde(default)]
+    pub generators: HashMap<String, Mapping>,
+    // Names of generators to leave out
+    #[serde(default)]
+    pub remove: Vec<String>,
+    // Extra generators, written as in `generators`
+    #[serde(default)]
+    pub add: Vec<Value>,
+    // Opening balances to replace or add
+    #[serde(default)]
+    pub accounts: HashMap<String, Decimal>,
+    // Variables to replace or add, e.g. `net_salary: 2800`, changing every amount worked out from them
+    #[serde(default)]
+    pub variables: Mapping,
+}
+
+fn named(generator: &Value, name: &str) -> bool {
+    generator.get("name").and_then(Value::as_str).is_some_and(|n| n.eq_ignore_ascii_case(name))
+}
+
+/// The config document `base` with `scenario`'s changes made, and without
+/// its scenarios. Fails naming any generator the scenario refers to that
+/// isn't in the config.
+pub fn apply(base: &Value, scenario: &Scenario) -> Result<Value, String> {
+    let mut config = base.clone();
+    let document = config.as_mapping_mut().ok_or("Config is not a mapping")?;
+    document.remove("scenarios");
+    for (key, value) in &scenario.set {
+        document.insert(key.clone(), value.clone());
+    }
+    let accounts = document
+        .entry(Value::from("accounts"))
+        .or_insert_with(|| Value::Mapping(Mapping::new()))
+        .as_mapping_mut()
+        .ok_or("Config accounts are not a mapping")?;
+    for (account, balance) in &scenario.accounts {
+        // Written as a string so the balance is read back exactly
+        accounts.insert(Value::from(account.as_str()), Value::from(balance.to_string()));
+    }
+    if !scenario.variables.is_empty() {
+        let variables = document
+            .entry(Value::from("variables"))
+            .or_insert_with(|| Value::Mapping(Mapping::new()))
+            .as_mapping_mut()
+            .ok_or("Config variables are not a mapping")?;
+        for (name, value) in &scenario.variables {
+            variables.insert(name.clone(), value.clone());
+        }
+    }
+    let generators = document
+        .entry(Value::from("generators"))
+        .or_insert_with(|| Value::Sequence(Vec::new()))
+        .as_sequence_mut()
+        .ok_or("Config generators are not a list")?;
+    let mut missing: Vec<&str> = scenario
+        .generators
+        .keys()
+        .chain(&scenario.remove)
+        .filter(|name| !generators.iter().any(|generator| named(generator, name)))
+        .map(String::as_str)
+        .collect();
+    if !missing.is_empty() {
+        missing.sort();
+        return Err(format!("Scenario {} names no generator called: {}", scenario.name, missing.join(", ")));
+    }
+    generators.retain(|generator| !scenario.remove.iter().any(|name| named(generator, name)));
+    for (name, fields) in &scenario.generators {
+        for generator in generators.iter_mut().filter(|generator| named(generator, name)) {
+            if let Some(generator) = generator.as_mapping_mut() {
+                for (key, value) in fields {
+                    generator.insert(key.clone(), value.clone());
+                }
+            }
+        }
+    }
+    generators.extend(scenario.add.iter().cloned());
+    Ok(config)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_apply() {
+        let base: Value = serde_yaml::from_str(
+            r#"
+generators:
+  - type: salary
+    name: Salary
+    amount: 2000
+    day: 26
+  - type: expense
+    name: Gym
+    amount: 40
+    day: 1
+accounts:
+  main: 100
+scenarios:
+  - name: Redundancy
+"#,
+        )
+        .unwrap();
+        let scenario: Scenario = serde_yaml::from_str(
+            r#"
+name: Redundancy
+set:
+  inflation_rate: 4.0
+generators:
+  salary: {end_date: 2025-06-30}
+remove: [Gym]
+add:
+  - type: expense
+    name: Job hunting
+    amount: 25
+    day: 1
+accounts:
+  savings: 5000
+"#,
+        )
+        .unwrap();
+        let expected: Value = serde_yaml::from_str(
+            r#"
+generators:
+  - type: salary
+    name: Salary
+    amount: 2000
+    day: 26
+    end_date: 2025-06-30
+  - type: expense
+    name: Job hunting
+    amount: 25
+    day: 1
+accounts:
+  main: 100
+  savings: "5000"
+inflation_rate: 4.0
+"#,
+        )
+        .unwrap();
+        assert_eq!(apply(&base, &scenario), Ok(expected));
+
+        let scenario = Scenario { remove: vec!["Netflix".to_string()], ..scenario };
+        assert_eq!(apply(&base, &scenario), Err("Scenario Redundancy names no generator called: Netflix".to_string()));
+    }
+}
@@ -0,0 +1,371 @@
+use chrono::{Datelike, Month, NaiveDate, Weekday};
+use rust_decimal::Decimal;
+use serde::Deserialize;
+
+/// A day within a month. Numbered days past the end of a short month are
+/// clamped to its last day, so `31` fires on the 30th in April and the 28th
+/// (or 29th) in February.
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(untagged)]
+pub enum DayOfMonth {
+    Day(u32),
+    Named(NamedDay),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NamedDay {
+    /// The last calendar day of the month
+    Last,
+    /// The last Monday to Friday of the month
+    LastWorkingDay,
+}
+
+impl From<u32> for DayOfMonth {
+    fn from(day: u32) -> Self {
+        DayOfMonth::Day(day)
+    }
+}
+
+impl DayOfMonth {
+    /// The actual day this resolves to in the given month
+    pub fn resolve(&self, year: i32, month: u32) -> u32 {
+        let last = days_in_month(year, month);
+        match self {
+            DayOfMonth::Day(day) => (*day).min(last),
+            DayOfMonth::Named(NamedDay::Last) => last,
+            DayOfMonth::Named(NamedDay::LastWorkingDay) => {
+                let mut date = NaiveDate::from_ymd_opt(year, month, last).unwrap();
+                while date.weekday().num_days_from_monday() >= 5 {
+                    date = date.pred_opt().unwrap();
+                }
+                date.day()
+            }
+        }
+    }
+
+    /// Why this can't be a day of the month, if it can't
+    pub fn problem(&self) -> Option<String> {
+        match self {
+            DayOfMonth::Day(day) if *day == 0 || *day > 31 => Some(format!("day {day} is not a day of the month (1 to 31)")),
+            _ => None,
+        }
+    }
+
+    /// Is `date` the day this resolves to in its month?
+    pub fn matches(&self, date: NaiveDate) -> bool {
+        date.day() == self.resolve(date.year(), date.month())
+    }
+}
+
+/// Number of days in the given month
+pub fn days_in_month(year: i32, month: u32) -> u32 {
+    let (next_year, next_month) = if month == 12 { (year + 1, 1) } else { (year, month + 1) };
+    NaiveDate::from_ymd_opt(next_year, next_month, 1)
+        .unwrap()
+        .pred_opt()
+        .unwrap()
+        .day()
+}
+
+/// When a generator fires. A bare day in the config (`day: 26` or
+/// `day: last_working_day`) is shorthand for a monthly schedule on that day,
+/// and a bare weekday (`day: friday`) for a weekly one; anything else is given
+/// as a map tagged with `frequency`, where `fortnightly` is weekly every 2 weeks.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(from = "ScheduleSpec")]
+pub enum Schedule {
+    /// Fires on `day` of every month
+    Monthly { day: DayOfMonth },
+    /// Fires on `weekday` every `every` weeks, counting from the first such
+    /// weekday on or after `from` (or the simulation start if not given)
+    Weekly {
+        every: u32,
+        weekday: Weekday,
+        from: Option<NaiveDate>,
+    },
+    /// Fires on `day` every three months, in `month` and every third month after it
+    Quarterly { month: Month, day: DayOfMonth },
+    /// Fires once a year on `day` of `month`
+    Annually { month: Month, day: DayOfMonth },
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum ScheduleSpec {
+    Day(DayOfMonth),
+    Weekday(Weekday),
+    Full(FullSpec),
+}
+
+#[derive(Deserialize)]
+#[serde(tag = "frequency", rename_all = "lowercase")]
+enum FullSpec {
+    Monthly {
+        day: DayOfMonth,
+    },
+    Weekly {
+        weekday: Weekday,
+        #[serde(default = "default_every")]
+        every: u32,
+        from: Option<NaiveDate>,
+    },
+    /// Weekly every other week
+    Fortnightly {
+        weekday: Weekday,
+        from: Option<NaiveDate>,
+    },
+    Quarterly {
+        #[serde(default = "default_quarter_month")]
+        month: Month,
+        day: DayOfMonth,
+    },
+    Annually {
+        month: Month,
+        day: DayOfMonth,
+    },
+}
+
+fn default_every() -> u32 {
+    1
+}
+
+fn default_quarter_month() -> Month {
+    Month::January
+}
+
+impl From<ScheduleSpec> for Schedule {
+    fn from(spec: ScheduleSpec) -> Self {
+        match spec {
+            ScheduleSpec::Day(day) => Schedule::Monthly { day },
+            ScheduleSpec::Weekday(weekday) => Schedule::Weekly { every: 1, weekday, from: None },
+            ScheduleSpec::Full(FullSpec::Monthly { day }) => Schedule::Monthly { day },
+            ScheduleSpec::Full(FullSpec::Weekly { weekday, every, from }) => Schedule::Weekly { every, weekday, from },
+            ScheduleSpec::Full(FullSpec::Fortnightly { weekday, from }) => Schedule::Weekly { every: 2, weekday, from },
+            ScheduleSpec::Full(FullSpec::Quarterly { month, day }) => Schedule::Quarterly { month, day },
+            ScheduleSpec::Full(FullSpec::Annually { month, day }) => Schedule::Annually { month, day },
+        }
+    }
+}
+
+impl Schedule {
+    /// A monthly schedule on a numbered day
+    #[cfg(test)]
+    pub fn monthly(day: u32) -> Self {
+        Schedule::Monthly { day: DayOfMonth::Day(day) }
+    }
+
+    /// Does this schedule fire on `date`? `origin` is the simulation start date,
+    /// used to anchor multi-week schedules that don't give their own `from`.
+    pub fn fires_on(&self, date: NaiveDate, origin: NaiveDate) -> bool {
+        match self {
+            Schedule::Monthly { day } => day.matches(date),
+            Schedule::Weekly { every, weekday, from } => {
+                if date.weekday() != *weekday {
+                    return false;
+                }
+                let anchor = from.unwrap_or(origin);
+                let offset = (weekday.num_days_from_monday() + 7 - anchor.weekday().num_days_from_monday()) % 7;
+                let first = anchor + chrono::Duration::days(offset as i64);
+                if date < first {
+                    return false;
+                }
+                let weeks = (date - first).num_days() / 7;
+                weeks % (*every).max(1) as i64 == 0
+            }
+            Schedule::Quarterly { month, day } => {
+                let months_since = (date.month0() + 12 - month.number_from_month() + 1) % 12;
+                day.matches(date) && months_since.is_multiple_of(3)
+            }
+            Schedule::Annually { month, day } => {
+                day.matches(date) && date.month() == month.number_from_month()
+            }
+        }
+    }
+
+    /// What makes the schedule impossible to follow, if anything: a numbered
+    /// day that no month has, or a weekly schedule firing every 0 weeks
+    pub fn problem(&self) -> Option<String> {
+        match self {
+            Schedule::Monthly { day } | Schedule::Quarterly { day, .. } | Schedule::Annually { day, .. } => day.problem(),
+            Schedule::Weekly { every: 0, .. } => Some("every must be at least 1 week".to_string()),
+            Schedule::Weekly { .. } => None,
+        }
+    }
+
+    /// How many times a year this schedule fires, used to turn annual rates
+    /// into a per-payment fraction
+    pub fn periods_per_year(&self) -> Decimal {
+        match self {
+            Schedule::Monthly { .. } => Decimal::from(12),
+            Schedule::Weekly { every, .. } => Decimal::from(52) / Decimal::from((*every).max(1)),
+            Schedule::Quarterly { .. } => Decimal::from(4),
+            Schedule::Annually { .. } => Decimal::ONE,
+        }
+    }
+}
+
+/// A month a generator doesn't run in: a single month written `2026-08`, or
+/// a month of every year written by name, e.g. `february`
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(try_from = "String")]
+pub enum SkippedMonth {
+    Once { year: i32, month: u32 },
+    Yearly(Month),
+}
+
+impl TryFrom<String> for SkippedMonth {
+    type Error = String;
+
+    fn try_from(text: String) -> Result<Self, String> {
+        if let Some((year, month)) = text.split_once('-')
+            && let (Ok(year), Ok(month)) = (year.parse(), month.parse())
+            && (1..=12).contains(&month)
+        {
+            return Ok(SkippedMonth::Once { year, month });
+        }
+        text.parse().map(SkippedMonth::Yearly).map_err(|_| format!("'{text}' is neither a month like 2026-08 nor the name of a month"))
+    }
+}
+
+impl SkippedMonth {
+    /// Is `date` in this month?
+    pub fn contains(&self, date: NaiveDate) -> bool {
+        match self {
+            SkippedMonth::Once { year, month } => (date.year(), date.month()) == (*year, *month),
+            SkippedMonth::Yearly(month) => date.month() == month.number_from_month(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(y: i32, m: u32, d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(y, m, d).unwrap()
+    }
+
+    #[test]
+    fn test_parse_bare_day_is_monthly() {
+        let schedule: Schedule = serde_yaml::from_str("26").unwrap();
+        assert_eq!(schedule, Schedule::monthly(26));
+    }
+
+    #[test]
+    fn test_parse_bare_weekday_is_weekly() {
+        let schedule: Schedule = serde_yaml::from_str("friday").unwrap();
+        assert_eq!(schedule, Schedule::Weekly { every: 1, weekday: Weekday::Fri, from: None });
+        let schedule: Schedule = serde_yaml::from_str("last_working_day").unwrap();
+        assert_eq!(schedule, Schedule::Monthly { day: DayOfMonth::Named(NamedDay::LastWorkingDay) });
+        let fortnightly: Schedule = serde_yaml::from_str("{frequency: fortnightly, weekday: tue, from: 2025-01-07}").unwrap();
+        assert_eq!(fortnightly, Schedule::Weekly { every: 2, weekday: Weekday::Tue, from: NaiveDate::from_ymd_opt(2025, 1, 7) });
+    }
+
+    #[test]
+    fn test_parse_tagged_schedules() {
+        let weekly: Schedule = serde_yaml::from_str("{frequency: weekly, weekday: fri, every: 4}").unwrap();
+        assert_eq!(weekly, Schedule::Weekly { every: 4, weekday: Weekday::Fri, from: None });
+        let quarterly: Schedule = serde_yaml::from_str("{frequency: quarterly, day: 1}").unwrap();
+        assert_eq!(quarterly, Schedule::Quarterly { month: Month::January, day: DayOfMonth::Day(1) });
+        let annual: Schedule = serde_yaml::from_str("{frequency: annually, month: march, day: 15}").unwrap();
+        assert_eq!(annual, Schedule::Annually { month: Month::March, day: DayOfMonth::Day(15) });
+    }
+
+    #[test]
+    fn test_problem() {
+        assert_eq!(Schedule::monthly(31).problem(), None);
+        assert_eq!(Schedule::monthly(0).problem(), Some("day 0 is not a day of the month (1 to 31)".to_string()));
+        let annual: Schedule = serde_yaml::from_str("{frequency: annually, month: may, day: 32}").unwrap();
+        assert_eq!(annual.problem(), Some("day 32 is not a day of the month (1 to 31)".to_string()));
+        let weekly: Schedule = serde_yaml::from_str("{frequency: weekly, weekday: fri, every: 0}").unwrap();
+        assert_eq!(weekly.problem(), Some("every must be at least 1 week".to_string()));
+    }
+
+    #[test]
+    fn test_weekly_fires_every_matching_weekday() {
+        let schedule = Schedule::Weekly { every: 1, weekday: Weekday::Fri, from: None };
+        let origin = date(2025, 1, 1); // a Wednesday
+        let fired: Vec<NaiveDate> = origin
+            .iter_days()
+            .take(21)
+            .filter(|d| schedule.fires_on(*d, origin))
+            .collect();
+        assert_eq!(fired, vec![date(2025, 1, 3), date(2025, 1, 10), date(2025, 1, 17)]);
+    }
+
+    #[test]
+    fn test_four_weekly_counts_from_anchor() {
+        let schedule = Schedule::Weekly { every: 4, weekday: Weekday::Thu, from: Some(date(2025, 1, 9)) };
+        let origin = date(2025, 1, 1);
+        assert!(!schedule.fires_on(date(2025, 1, 2), origin), "before the anchor");
+        assert!(schedule.fires_on(date(2025, 1, 9), origin));
+        assert!(!schedule.fires_on(date(2025, 1, 16), origin));
+        assert!(schedule.fires_on(date(2025, 2, 6), origin));
+        assert_eq!(schedule.periods_per_year(), Decimal::from(13));
+    }
+
+    #[test]
+    fn test_quarterly_fires_every_third_month() {
+        let schedule = Schedule::Quarterly { month: Month::February, day: DayOfMonth::Day(10) };
+        let origin = date(2025, 1, 1);
+        let months: Vec<u32> = (1..=12)
+            .filter(|m| schedule.fires_on(date(2025, *m, 10), origin))
+            .collect();
+        assert_eq!(months, vec![2, 5, 8, 11]);
+        assert!(!schedule.fires_on(date(2025, 2, 11), origin));
+    }
+
+    #[test]
+    fn test_annual_fires_once_a_year() {
+        let schedule = Schedule::Annually { month: Month::March, day: DayOfMonth::Day(15) };
+        let origin = date(2025, 1, 1);
+        let fired = origin
+            .iter_days()
+            .take(365 * 2)
+            .filter(|d| schedule.fires_on(*d, origin))
+            .count();
+        assert_eq!(fired, 2);
+    }
+
+    #[test]
+    fn test_parse_named_days() {
+        let schedule: Schedule = serde_yaml::from_str("last_working_day").unwrap();
+        assert_eq!(schedule, Schedule::Monthly { day: DayOfMonth::Named(NamedDay::LastWorkingDay) });
+        let annual: Schedule = serde_yaml::from_str("{frequency: annually, month: feb, day: last}").unwrap();
+        assert_eq!(annual, Schedule::Annually { month: Month::February, day: DayOfMonth::Named(NamedDay::Last) });
+    }
+
+    #[test]
+    fn test_day_31_clamps_to_month_end() {
+        let schedule = Schedule::monthly(31);
+        let origin = date(2025, 1, 1);
+        assert!(schedule.fires_on(date(2025, 2, 28), origin));
+        assert!(schedule.fires_on(date(2024, 2, 29), origin));
+        assert!(!schedule.fires_on(date(2024, 2, 28), origin));
+        assert!(schedule.fires_on(date(2025, 4, 30), origin));
+        assert!(schedule.fires_on(date(2025, 5, 31), origin));
+        assert!(!schedule.fires_on(date(2025, 5, 30), origin));
+    }
+
+    #[test]
+    fn test_last_working_day_skips_weekends() {
+        let day = DayOfMonth::Named(NamedDay::LastWorkingDay);
+        // 31 May 2025 is a Saturday
+        assert_eq!(day.resolve(2025, 5), 30);
+        // 31 August 2025 is a Sunday
+        assert_eq!(day.resolve(2025, 8), 29);
+        // 30 June 2025 is a Monday
+        assert_eq!(day.resolve(2025, 6), 30);
+        assert_eq!(DayOfMonth::Named(NamedDay::Last).resolve(2025, 2), 28);
+    }
+
+    #[test]
+    fn test_skipped_months() {
+        let skip: Vec<SkippedMonth> = serde_yaml::from_str("[2026-08, February, mar]").unwrap();
+        assert_eq!(skip, [SkippedMonth::Once { year: 2026, month: 8 }, SkippedMonth::Yearly(Month::February), SkippedMonth::Yearly(Month::March)]);
+        assert!(skip[0].contains(date(2026, 8, 31)) && !skip[0].contains(date(2027, 8, 1)));
+        assert!(skip[1].contains(date(2025, 2, 1)) && skip[1].contains(date(2031, 2, 28)) && !skip[1].contains(date(2025, 3, 1)));
+        assert!(serde_yaml::from_str::<SkippedMonth>("2026-13").unwrap_err().to_string().contains("'2026-13' is neither a month like 2026-08"));
+    }
+}
@@ -0,0 +1,215 @@
+use serde_json::{Value, json};
+use std::io::{ErrorKind, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::path::PathBuf;
+use std::time::{Duration, Instant, SystemTime};
+
+/// How often the watched files are checked for changes
+const WATCH_INTERVAL: Duration = Duration::from_secs(1);
+
+/// What a run of the forecast produced, as it's served
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Report {
+    // Raised on every run, so open pages can tell when to reload
+    pub version: u64,
+    // The text report, or why the forecast couldn't be run
+    pub text: String,
+    // Names of the HTML pages the run saved in /tmp, e.g. `balances.html`
+    pub pages: Vec<String>,
+    // The forecast as a JSON document, if it ran
+    pub document: Option<Value>,
+}
+
+/// Names of the HTML pages a run's `output` says it saved in /tmp
+pub fn pages(output: &str) -> Vec<String> {
+    output
+        .lines()
+        .filter_map(|line| line.split("saved to '/tmp/").nth(1)?.strip_suffix('\''))
+        .filter(|page| page.ends_with(".html") && !page.contains('/'))
+        .map(str::to_string)
+        .collect()
+}
+
+/// `html` with a script that reloads it once a later run than `version` is being served
+fn with_reload(html: &str, version: u64) -> String {
+    let script = format!(
+        "<script>setInterval(() => fetch('/api/version').then(r => r.text()).then(v => {{ if (v != '{version}') location.reload(); }}), 2000);</script>\n"
+    );
+    match html.rfind("</body>") {
+        Some(end) => format!("{}{script}{}", &html[..end], &html[end..]),
+        None => format!("{html}{script}"),
+    }
+}
+
+/// Each account with its opening and closing balance, from the forecast's JSON document
+fn accounts(document: &Value) -> Value {
+    let last = document["days"].as_array().and_then(|days| days.last());
+    let accounts = document["accounts"].as_array().cloned().unwrap_or_default();
+    Value::Array(
+        accounts
+            .iter()
+            .filter_map(Value::as_str)
+            .map(|account| {
+                json!({
+                    "name": account,
+                    "opening": document["opening_balances"][account],
+                    "closing": last.map_or(Value::Null, |day| day["balances"][account].clone()),
+                })
+            })
+            .collect(),
+    )
+}
+
+/// A response's status, content type and body
+#[derive(Debug, Clone, PartialEq)]
+pub struct Response {
+    pub status: u16,
+    pub content_type: &'static str,
+    pub body: String,
+}
+
+impl Response {
+    fn new(status: u16, content_type: &'static str, body: String) -> Self {
+        Response { status, content_type, body }
+    }
+
+    fn not_found() -> Self {
+        Response::new(404, "text/plain; charset=utf-8", "Not found".to_string())
+    }
+}
+
+/// The response to a GET of `path`, reading a page of the report with `read_page`
+pub fn respond(report: &Report, path: &str, read_page: &dyn Fn(&str) -> Option<String>) -> Response {
+    let path = path.split('?').next().unwrap_or_default();
+    let api = |body: Option<Value>| match body {
+        Some(body) => Response::new(200, "application/json", body.to_string()),
+        None => Response::new(503, "text/plain; charset=utf-8", report.text.clone()),
+    };
+    match path {
+        "/" => {
+            let links: String = report
+                .pages
+                .iter()
+                .map(|page| format!("<li><a href=\"/{page}\">{}</a></li>\n", crate::report::html_escape(page)))
+                .collect();
+            let html = format!(
+                "<!DOCTYPE html>\n<html>\n<head><title>Cash forecast</title></head>\n<body style=\"font-family: Arial, sans-serif\">\n\
+                 <h1>Cash forecast</h1>\n<ul>\n{links}</ul>\n<pre>{}</pre>\n</body>\n</html>\n",
+                crate::report::html_escape(&report.text)
+            );
+            Response::new(200, "text/html; charset=utf-8", with_reload(&html, report.version))
+        }
+        "/api/version" => Response::new(200, "text/plain; charset=utf-8", report.version.to_string()),
+        "/api/history" => api(report.document.as_ref().map(|document| document["days"].clone())),
+        "/api/accounts" => api(report.document.as_ref().map(accounts)),
+        _ => match path.strip_prefix('/').filter(|page| report.pages.iter().any(|p| p == page)).and_then(read_page) {
+            Some(html) => Response::new(200, "text/html; charset=utf-8", with_reload(&html, report.version)),
+            None => Response::not_found(),
+        },
+    }
+}
+
+/// Answer one request on `stream`
+fn answer(mut stream: TcpStream, report: &Report) -> std::io::Result<()> {
+    stream.set_nonblocking(false)?;
+    stream.set_read_timeout(Some(Duration::from_secs(5)))?;
+    let mut request = Vec::new();
+    let mut buffer = [0; 4096];
+    while !request.windows(4).any(|window| window == b"\r\n\r\n") && request.len() < 65536 {
+        let read = stream.read(&mut buffer)?;
+        if read == 0 {
+            break;
+        }
+        request.extend_from_slice(&buffer[..read]);
+    }
+    let request = String::from_utf8_lossy(&request);
+    let mut words = request.lines().next().unwrap_or_default().split_whitespace();
+    let response = match (words.next(), words.next()) {
+        (Some("GET"), Some(path)) => respond(report, path, &|page| std::fs::read_to_string(format!("/tmp/{page}")).ok()),
+        _ => Response::new(405, "text/plain; charset=utf-8", "Only GET is supported".to_string()),
+    };
+    let reason = match response.status {
+        200 => "OK",
+        404 => "Not Found",
+        405 => "Method Not Allowed",
+        _ => "Service Unavailable",
+    };
+    write!(
+        stream,
+        "HTTP/1.1 {} {reason}\r\nContent-Type: {}\r\nContent-Length: {}\r\nCache-Control: no-store\r\nConnection: close\r\n\r\n{}",
+        response.status,
+        response.content_type,
+        response.body.len(),
+        response.body
+    )
+}
+
+/// When each of `files` was last changed, so a change to any can be seen
+fn modified(files: &[PathBuf]) -> Vec<Option<SystemTime>> {
+    files.iter().map(|file| std::fs::metadata(file).and_then(|metadata| metadata.modified()).ok()).collect()
+}
+
+/// Serve the report `run` makes on localhost at `port`, running it again
+/// whenever any of the files `watched` lists changes
+pub fn serve(port: u16, mut run: impl FnMut(u64) -> Report, watched: impl Fn() -> Vec<PathBuf>) -> std::io::Result<()> {
+    let listener = TcpListener::bind(("127.0.0.1", port))?;
+    listener.set_nonblocking(true)?;
+    let mut files = watched();
+    let mut seen = modified(&files);
+    let mut report = run(1);
+    println!("Serving the forecast at http://localhost:{port}/ (Ctrl-C to stop)");
+    let mut checked = Instant::now();
+    loop {
+        match listener.accept() {
+            Ok((stream, _)) => {
+                if let Err(e) = answer(stream, &report) {
+                    eprintln!("Error answering a request: {e}");
+                }
+            }
+            Err(e) if e.kind() == ErrorKind::WouldBlock => std::thread::sleep(Duration::from_millis(50)),
+            Err(e) => return Err(e),
+        }
+        if checked.elapsed() >= WATCH_INTERVAL {
+            checked = Instant::now();
+            let now = modified(&files);
+            if now != seen {
+                println!("Config changed; running the forecast again");
+                report = run(report.version + 1);
+                files = watched();
+                seen = modified(&files);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_respond() {
+        let output = "Balances Over Time chart saved to '/tmp/balances.html'\nJournal saved to '/tmp/journal.csv'\nx saved to '/tmp/../etc/passwd.html'\n";
+        let document = json!({
+            "accounts": ["main", "savings"],
+            "opening_balances": {"main": "100", "savings": "0"},
+            "days": [{"date": "2025-01-02", "balances": {"main": "60", "savings": "40"}}],
+        });
+        let report = Report { version: 3, text: "Balances on 2025-01-02".to_string(), pages: pages(output), document: Some(document) };
+        assert_eq!(report.pages, vec!["balances.html"]);
+        let read = |page: &str| Some(format!("<html><body>{page}</body></html>"));
+
+        let index = respond(&report, "/", &read);
+        assert!(index.body.contains("<a href=\"/balances.html\">") && index.body.contains("<pre>Balances on 2025-01-02</pre>"));
+        assert_eq!(respond(&report, "/balances.html", &read).body, format!("<html><body>balances.html{}</body></html>", with_reload("", 3)));
+        assert_eq!(respond(&report, "/secret.html", &read), Response::not_found());
+        assert_eq!(respond(&report, "/api/version", &read).body, "3");
+        assert_eq!(respond(&report, "/api/history?x=1", &read).body, r#"[{"balances":{"main":"60","savings":"40"},"date":"2025-01-02"}]"#);
+        assert_eq!(
+            respond(&report, "/api/accounts", &read).body,
+            r#"[{"closing":"60","name":"main","opening":"100"},{"closing":"40","name":"savings","opening":"0"}]"#
+        );
+
+        let failed = Report { document: None, text: "YAML parsing error".to_string(), ..report };
+        assert_eq!(respond(&failed, "/api/history", &read), Response::new(503, "text/plain; charset=utf-8", "YAML parsing error".to_string()));
+    }
+}
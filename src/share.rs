@@ -0,0 +1,93 @@
+use chrono::{Datelike, NaiveDate};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+
+use crate::giving::{self, Income, TitheBasis};
+
+/// What a percentage transfer moves a percentage of, given in the config as
+/// `{balance: main}` or `{income: [salary]}`
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(untagged)]
+pub enum ShareOf {
+    /// An account's balance when the transfer is made
+    Balance { balance: String },
+    /// Income received in the previous calendar month from these generators,
+    /// generator types or income accounts, as a tithe's `of` names them
+    Income { income: Vec<String> },
+}
+
+/// Whether a percentage transfer of `of` counts `income`; one without `of`
+/// counts every salary and rent
+pub fn counts(of: Option<&ShareOf>, income: &Income) -> bool {
+    match of {
+        Some(ShareOf::Balance { .. }) => false,
+        Some(ShareOf::Income { income: names }) => giving::counts(Some(names), TitheBasis::Income, income),
+        None => giving::counts(None, TitheBasis::Income, income),
+    }
+}
+
+/// Income received in the calendar month of the latest payment, and in the month before
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct MonthlyIncome {
+    month: Option<(i32, u32)>,
+    this_month: Decimal,
+    last_month: Decimal,
+}
+
+impl MonthlyIncome {
+    /// Move on to the month of `date`
+    fn roll(&mut self, date: NaiveDate) {
+        let month = (date.year(), date.month());
+        let previous = if date.month() == 1 { (date.year() - 1, 12) } else { (date.year(), date.month() - 1) };
+        if self.month == Some(month) {
+            return;
+        }
+        self.last_month = if self.month == Some(previous) { self.this_month } else { Decimal::ZERO };
+        self.this_month = Decimal::ZERO;
+        self.month = Some(month);
+    }
+
+    pub fn add(&mut self, date: NaiveDate, amount: Decimal) {
+        self.roll(date);
+        self.this_month += amount;
+    }
+
+    /// Income received in the calendar month before the one `date` is in
+    pub fn last_month(&mut self, date: NaiveDate) -> Decimal {
+        self.roll(date);
+        self.last_month
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal_macros::dec;
+
+    #[test]
+    fn test_monthly_income() {
+        let date = |m, d| NaiveDate::from_ymd_opt(2025, m, d).unwrap();
+        let mut income = MonthlyIncome::default();
+        income.add(date(1, 28), dec!(2000));
+        assert_eq!(income.last_month(date(1, 31)), Decimal::ZERO);
+        income.add(date(2, 3), dec!(300));
+        assert_eq!(income.last_month(date(2, 5)), dec!(2000));
+        assert_eq!(income.last_month(date(3, 1)), dec!(300));
+        // Nothing came in in March
+        assert_eq!(income.last_month(date(4, 1)), Decimal::ZERO);
+    }
+
+    #[test]
+    fn test_counts() {
+        let freelance = Income { generator: "Freelance", kind: "salary", account: "freelance_income" };
+        let rent = Income { generator: "rent (generator 2)", kind: "rent", account: "rental_income" };
+        assert!(counts(None, &rent));
+        assert!(counts(Some(&ShareOf::Income { income: vec!["freelance".to_string()] }), &freelance));
+        assert!(!counts(Some(&ShareOf::Income { income: vec!["freelance".to_string()] }), &rent));
+        assert!(!counts(Some(&ShareOf::Balance { balance: "main".to_string() }), &freelance));
+        let of: ShareOf = serde_yaml::from_str("{balance: main}").unwrap();
+        assert_eq!(of, ShareOf::Balance { balance: "main".to_string() });
+        let of: ShareOf = serde_yaml::from_str("{income: [salary]}").unwrap();
+        assert_eq!(of, ShareOf::Income { income: vec!["salary".to_string()] });
+    }
+}
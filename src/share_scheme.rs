@@ -0,0 +1,65 @@
+use chrono::NaiveDate;
+use rust_decimal::Decimal;
+use serde::Deserialize;
+
+use crate::rounding::Rounding;
+use crate::tax::Jurisdiction;
+
+/// A possible share price when a save-as-you-earn contract matures
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct ShareScenario {
+    pub name: String,
+    pub share_price: Decimal,
+}
+
+/// What happens to a save-as-you-earn contract's savings at maturity
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Maturity {
+    // Whole shares bought at the option price; none if the option is under water
+    pub shares: Decimal,
+    // Profit from selling the shares straight away
+    pub gain: Decimal,
+    // Capital gains tax on that profit
+    pub tax: Decimal,
+}
+
+/// The outcome of `savings` maturing with the shares at `share_price`. The
+/// option is exercised only if the shares are worth more than `option_price`,
+/// and the shares are then sold at once, on `date`. There's no income tax or
+/// National Insurance on the gain, but capital gains tax is due above the allowance.
+pub fn maturity(
+    savings: Decimal,
+    option_price: Decimal,
+    share_price: Decimal,
+    jurisdiction: &dyn Jurisdiction,
+    date: NaiveDate,
+    rounding: Rounding,
+) -> Maturity {
+    if share_price <= option_price || option_price <= Decimal::ZERO {
+        return Maturity::default();
+    }
+    let shares = (savings / option_price).floor();
+    let gain = rounding.round(shares * (share_price - option_price));
+    Maturity { shares, gain, tax: jurisdiction.capital_gains_tax(date, gain) }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal_macros::dec;
+
+    #[test]
+    fn test_maturity() {
+        let settings = crate::tax::TaxSettings::default();
+        let rules = settings.jurisdiction();
+        let date = NaiveDate::from_ymd_opt(2026, 1, 1).unwrap();
+        // £9,000 saved buys 4,500 shares at £2; at £3 that's £4,500 profit, £1,500 over the allowance
+        assert_eq!(
+            maturity(dec!(9000), dec!(2), dec!(3), rules.as_ref(), date, Rounding::default()),
+            Maturity { shares: dec!(4500), gain: dec!(4500.00), tax: dec!(360.00) }
+        );
+        assert_eq!(maturity(dec!(9000), dec!(2.30), dec!(2.50), rules.as_ref(), date, Rounding::default()).shares, dec!(3913));
+        // Under water, so the savings are taken back as cash
+        assert_eq!(maturity(dec!(9000), dec!(2), dec!(1.80), rules.as_ref(), date, Rounding::default()), Maturity::default());
+    }
+}
@@ -0,0 +1,91 @@
+use chrono::NaiveDate;
+use rust_decimal::Decimal;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+use crate::balances::Balances;
+
+/// Version of the snapshot's layout, raised whenever the engine's state
+/// changes shape, so an old snapshot isn't read as something it isn't
+pub const SCHEMA_VERSION: u32 = 1;
+
+/// A forecast's balances and engine state at the end of a day, to carry on
+/// from in a later run. Written as YAML, so balances can be trued up by hand
+/// before resuming.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Snapshot<S> {
+    pub schema_version: u32,
+    pub date: NaiveDate,
+    // Label of each generator in order; the state is kept by generator index,
+    // so it only means the same thing to a config with the same generators
+    pub generators: Vec<String>,
+    pub balances: BTreeMap<String, Decimal>,
+    pub state: S,
+}
+
+impl<S> Snapshot<S> {
+    pub fn new(date: NaiveDate, generators: Vec<String>, balances: &Balances, state: S) -> Self {
+        Snapshot {
+            schema_version: SCHEMA_VERSION,
+            date,
+            generators,
+            balances: balances.iter().map(|(account, balance)| (account.clone(), *balance)).collect(),
+            state,
+        }
+    }
+
+    /// Check the snapshot was taken with the generators labelled `generators`
+    pub fn check(&self, generators: &[String]) -> Result<(), String> {
+        if self.generators != generators {
+            return Err(format!(
+                "The snapshot was taken with generators {}, but the config has {}",
+                self.generators.join(", "),
+                generators.join(", ")
+            ));
+        }
+        Ok(())
+    }
+}
+
+impl<S: Serialize> Snapshot<S> {
+    pub fn to_yaml(&self) -> Result<String, String> {
+        serde_yaml::to_string(self).map_err(|e| e.to_string())
+    }
+}
+
+/// A snapshot saved by an earlier run
+pub fn read<S: DeserializeOwned>(text: &str) -> Result<Snapshot<S>, String> {
+    let version: serde_yaml::Value = serde_yaml::from_str(text).map_err(|e| e.to_string())?;
+    match version.get("schema_version").and_then(serde_yaml::Value::as_u64) {
+        Some(version) if version == SCHEMA_VERSION as u64 => {}
+        Some(version) => return Err(format!("Schema version {version} can't be read; this version reads {SCHEMA_VERSION}")),
+        None => return Err("No schema_version; not a snapshot".to_string()),
+    }
+    serde_yaml::from_str(text).map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal_macros::dec;
+
+    #[test]
+    fn test_read() {
+        let date = NaiveDate::from_ymd_opt(2025, 3, 31).unwrap();
+        let balances = Balances::from_iter([("main".to_string(), dec!(1234.56))]);
+        let state = BTreeMap::from([((2025, 3), dec!(1.25))]);
+        let snapshot = Snapshot::new(date, vec!["Salary".to_string()], &balances, state);
+        let yaml = snapshot.to_yaml().unwrap();
+        assert_eq!(read::<BTreeMap<(i32, u32), Decimal>>(&yaml), Ok(snapshot.clone()));
+
+        assert_eq!(snapshot.check(&["Salary".to_string()]), Ok(()));
+        assert_eq!(
+            snapshot.check(&["Salary".to_string(), "Rent".to_string()]),
+            Err("The snapshot was taken with generators Salary, but the config has Salary, Rent".to_string())
+        );
+        let newer = yaml.replace("schema_version: 1", "schema_version: 2");
+        assert_eq!(read::<BTreeMap<(i32, u32), Decimal>>(&newer), Err("Schema version 2 can't be read; this version reads 1".to_string()));
+        assert_eq!(read::<BTreeMap<(i32, u32), Decimal>>("main: 100"), Err("No schema_version; not a snapshot".to_string()));
+    }
+}
@@ -0,0 +1,56 @@
+use rust_decimal::Decimal;
+
+/// Smallest value in `low..=high`, to the nearest hundredth, for which
+/// `meets` holds, assuming that once a value meets the target every larger
+/// one does too. None if even `high` falls short. Each round tries `ways`
+/// values spread evenly across what's left at once, narrowing it to the gap
+/// between two of them, so with one way it's a bisection.
+pub fn search(low: Decimal, high: Decimal, ways: usize, meets: impl Fn(Decimal) -> bool + Sync) -> Option<Decimal> {
+    match crate::parallel::map(&[low, high], |value| meets(*value))[..] {
+        [true, _] => return Some(low),
+        [_, false] => return None,
+        _ => {}
+    }
+    // Search over whole hundredths so every run tries a value that could be typed into the config
+    let hundredths = |value: Decimal| (value * Decimal::ONE_HUNDRED).floor();
+    let (mut failing, mut meeting) = (hundredths(low), hundredths(high).max(hundredths(low) + Decimal::ONE));
+    let parts = Decimal::from(ways.max(1) + 1);
+    while meeting - failing > Decimal::ONE {
+        let mut tries: Vec<Decimal> =
+            (1..=ways.max(1)).map(|way| failing + ((meeting - failing) * Decimal::from(way) / parts).floor()).filter(|value| *value > failing && *value < meeting).collect();
+        tries.dedup();
+        let met = crate::parallel::map(&tries, |value| meets(*value / Decimal::ONE_HUNDRED));
+        let first = met.iter().position(|met| *met).unwrap_or(tries.len());
+        if let Some(value) = tries.get(first) {
+            meeting = *value;
+        }
+        if let Some(value) = first.checked_sub(1).map(|before| tries[before]) {
+            failing = value;
+        }
+    }
+    Some((meeting / Decimal::ONE_HUNDRED).min(high))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal_macros::dec;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    #[test]
+    fn test_search() {
+        let runs = AtomicUsize::new(0);
+        let found = search(Decimal::ZERO, dec!(10000), 1, |value| {
+            runs.fetch_add(1, Ordering::Relaxed);
+            value * dec!(12) >= dec!(5000)
+        });
+        assert_eq!(found, Some(dec!(416.67)));
+        assert!(runs.into_inner() <= 22);
+        // Trying several values a round finds the same one in fewer rounds
+        for ways in [2, 3, 8] {
+            assert_eq!(search(Decimal::ZERO, dec!(10000), ways, |value| value * dec!(12) >= dec!(5000)), Some(dec!(416.67)));
+        }
+        assert_eq!(search(Decimal::ZERO, dec!(100), 4, |value| value >= dec!(150)), None);
+        assert_eq!(search(dec!(5), dec!(100), 4, |_| true), Some(dec!(5)));
+    }
+}
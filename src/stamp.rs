@@ -0,0 +1,105 @@
+use chrono::{NaiveDate, NaiveDateTime};
+
+use crate::report::html_escape;
+
+/// What produced an output: when, from which config and engine, over what
+/// horizon and on what assumptions, so stale copies can be recognised
+#[derive(Debug, Clone, PartialEq)]
+pub struct RunStamp {
+    pub run_at: NaiveDateTime,
+    // Date the run is pinned to, shown instead of `run_at` so output doesn't change from day to day
+    pub as_of: Option<NaiveDate>,
+    pub config_file: String,
+    pub config_hash: String,
+    pub version: String,
+    pub start: NaiveDate,
+    pub end: NaiveDate,
+    pub assumptions: Vec<String>,
+}
+
+impl RunStamp {
+    pub fn lines(&self) -> Vec<String> {
+        let generated = match self.as_of {
+            Some(as_of) => format!("as of {as_of}"),
+            None => self.run_at.format("%Y-%m-%d %H:%M").to_string(),
+        };
+        let mut lines = vec![
+            format!("Generated {generated} by cash-forecast {}", self.version),
+            format!("Config {} (hash {})", self.config_file, self.config_hash),
+            format!("Forecast from {} to {}", self.start, self.end),
+        ];
+        lines.extend(self.assumptions.iter().map(|assumption| format!("Assumes {assumption}")));
+        lines
+    }
+
+    /// Comment lines to put above a CSV header
+    pub fn csv(&self) -> Vec<String> {
+        self.lines().iter().map(|line| format!("# {line}")).collect()
+    }
+
+    /// A block to put at the top of an HTML page's body
+    pub fn html(&self) -> String {
+        let lines: Vec<String> = self.lines().iter().map(|line| html_escape(line)).collect();
+        format!("<div class=\"stamp\">{}</div>", lines.join("<br>\n"))
+    }
+}
+
+/// 64-bit FNV-1a hash of the config text, in hex. Stable across builds and
+/// platforms, unlike the standard library's hasher.
+pub fn config_hash(text: &str) -> String {
+    let hash = text.bytes().fold(0xcbf29ce484222325u64, |hash, byte| {
+        (hash ^ byte as u64).wrapping_mul(0x100000001b3)
+    });
+    format!("{hash:016x}")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn stamp() -> RunStamp {
+        let date = |y, m, d| NaiveDate::from_ymd_opt(y, m, d).unwrap();
+        RunStamp {
+            run_at: date(2025, 3, 4).and_hms_opt(9, 30, 0).unwrap(),
+            as_of: None,
+            config_file: "config.yaml".to_string(),
+            config_hash: config_hash("a"),
+            version: "0.1.0".to_string(),
+            start: date(2025, 1, 1),
+            end: date(2026, 1, 1),
+            assumptions: vec!["interest on mortgage at 5.0%".to_string()],
+        }
+    }
+
+    #[test]
+    fn test_config_hash() {
+        assert_eq!(config_hash(""), "cbf29ce484222325");
+        assert_eq!(config_hash("a"), "af63dc4c8601ec8c");
+    }
+
+    #[test]
+    fn test_stamp_formats() {
+        let stamp = stamp();
+        assert_eq!(
+            stamp.csv(),
+            vec![
+                "# Generated 2025-03-04 09:30 by cash-forecast 0.1.0",
+                "# Config config.yaml (hash af63dc4c8601ec8c)",
+                "# Forecast from 2025-01-01 to 2026-01-01",
+                "# Assumes interest on mortgage at 5.0%",
+            ]
+        );
+        assert!(stamp.html().starts_with("<div class=\"stamp\">Generated 2025-03-04 09:30"));
+    }
+
+    #[test]
+    fn test_as_of_hides_run_time() {
+        let pinned = |hour| RunStamp {
+            run_at: NaiveDate::from_ymd_opt(2025, 3, 4).unwrap().and_hms_opt(hour, 0, 0).unwrap(),
+            as_of: NaiveDate::from_ymd_opt(2025, 3, 1),
+            ..stamp()
+        };
+        assert_eq!(pinned(9).lines()[0], "Generated as of 2025-03-01 by cash-forecast 0.1.0");
+        assert_eq!(pinned(9).lines(), pinned(17).lines());
+    }
+}
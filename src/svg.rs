@@ -0,0 +1,203 @@
+use crate::report::html_escape;
+
+/// A line on a chart
+#[derive(Debug, Clone, PartialEq)]
+pub struct Series {
+    pub label: String,
+    // As "r, g, b"
+    pub colour: String,
+    pub dashed: bool,
+    // One for each x label; None leaves a gap in the line
+    pub values: Vec<Option<f64>>,
+    // Points marked with a dot, by x label, each with a tooltip
+    pub markers: Vec<(usize, String)>,
+}
+
+const WIDTH: f64 = 1000.0;
+const HEIGHT: f64 = 420.0;
+const LEFT: f64 = 90.0;
+const RIGHT: f64 = 20.0;
+const BOTTOM: f64 = 55.0;
+const LEGEND_ROW: f64 = 20.0;
+
+/// A round step between axis ticks that gives about `ticks` ticks over `range`
+fn nice_step(range: f64, ticks: f64) -> f64 {
+    let rough = range / ticks;
+    let magnitude = 10f64.powf(rough.log10().floor());
+    let step = [1.0, 2.0, 5.0, 10.0].into_iter().map(|m| m * magnitude).find(|step| *step >= rough).unwrap_or(10.0 * magnitude);
+    if step > 0.0 { step } else { 1.0 }
+}
+
+/// An SVG path drawing `points`, lifting the pen over the gaps
+fn path(points: &[Option<(f64, f64)>]) -> String {
+    let mut commands = Vec::new();
+    let mut drawing = false;
+    for point in points {
+        match point {
+            Some((x, y)) => {
+                commands.push(format!("{}{x:.1},{y:.1}", if drawing { "L" } else { "M" }));
+                drawing = true;
+            }
+            None => drawing = false,
+        }
+    }
+    commands.join(" ")
+}
+
+/// A line chart of `series` against `x_labels` as a self-contained SVG
+/// element, with a legend above and the axes titled
+pub fn line_chart(x_labels: &[String], series: &[Series], y_title: &str, x_title: &str) -> String {
+    // Legend entries run across the top, wrapping onto more rows as needed
+    let mut legend = Vec::new();
+    let (mut x, mut row) = (LEFT, 0.0);
+    for line in series {
+        let width = 40.0 + 7.0 * line.label.chars().count() as f64;
+        if x + width > WIDTH - RIGHT && x > LEFT {
+            x = LEFT;
+            row += 1.0;
+        }
+        let y = 14.0 + row * LEGEND_ROW;
+        let dash = if line.dashed { " stroke-dasharray=\"6 6\"" } else { "" };
+        legend.push(format!(
+            "<line x1=\"{x}\" y1=\"{}\" x2=\"{}\" y2=\"{}\" stroke=\"rgb({})\" stroke-width=\"3\"{dash}/><text x=\"{}\" y=\"{}\">{}</text>",
+            y - 4.0,
+            x + 24.0,
+            y - 4.0,
+            line.colour,
+            x + 30.0,
+            y,
+            html_escape(&line.label)
+        ));
+        x += width;
+    }
+    let top = 20.0 + (row + 1.0) * LEGEND_ROW;
+    let (plot_width, plot_height) = (WIDTH - LEFT - RIGHT, HEIGHT - top - BOTTOM);
+
+    let values: Vec<f64> = series.iter().flat_map(|line| line.values.iter().flatten().copied()).collect();
+    let (mut low, mut high) = values.iter().fold((f64::INFINITY, f64::NEG_INFINITY), |(low, high), value| (low.min(*value), high.max(*value)));
+    if values.is_empty() {
+        (low, high) = (0.0, 1.0);
+    } else if low == high {
+        (low, high) = (low - 1.0, high + 1.0);
+    }
+    let step = nice_step(high - low, 5.0);
+    let (low, high) = ((low / step).floor() * step, (high / step).ceil() * step);
+    let scale_x = |i: usize| LEFT + if x_labels.len() > 1 { plot_width * i as f64 / (x_labels.len() - 1) as f64 } else { 0.0 };
+    let scale_y = |value: f64| top + plot_height * (high - value) / (high - low);
+
+    let mut svg = format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" viewBox=\"0 0 {WIDTH} {HEIGHT}\" width=\"100%\" font-family=\"Arial, sans-serif\" font-size=\"12\">\n"
+    );
+    svg.push_str(&legend.join("\n"));
+    svg.push('\n');
+    // Gridlines and labels up the y axis
+    let mut tick = low;
+    while tick <= high + step / 2.0 {
+        let y = scale_y(tick);
+        let label = if step >= 1.0 { format!("{tick:.0}") } else { format!("{tick:.2}") };
+        svg.push_str(&format!(
+            "<line x1=\"{LEFT}\" y1=\"{y:.1}\" x2=\"{}\" y2=\"{y:.1}\" stroke=\"#ddd\"/><text x=\"{}\" y=\"{:.1}\" text-anchor=\"end\">{label}</text>\n",
+            WIDTH - RIGHT,
+            LEFT - 6.0,
+            y + 4.0
+        ));
+        tick += step;
+    }
+    // Up to ten labels along the x axis
+    let every = x_labels.len().div_ceil(10).max(1);
+    for (i, label) in x_labels.iter().enumerate().step_by(every) {
+        svg.push_str(&format!(
+            "<line x1=\"{x:.1}\" y1=\"{}\" x2=\"{x:.1}\" y2=\"{}\" stroke=\"#999\"/><text x=\"{x:.1}\" y=\"{}\" text-anchor=\"middle\">{}</text>\n",
+            top + plot_height,
+            top + plot_height + 5.0,
+            top + plot_height + 18.0,
+            html_escape(label),
+            x = scale_x(i)
+        ));
+    }
+    svg.push_str(&format!(
+        "<rect x=\"{LEFT}\" y=\"{top}\" width=\"{plot_width}\" height=\"{plot_height}\" fill=\"none\" stroke=\"#999\"/>\n\
+         <text x=\"{}\" y=\"{}\" text-anchor=\"middle\">{}</text>\n\
+         <text transform=\"translate(16 {}) rotate(-90)\" text-anchor=\"middle\">{}</text>\n",
+        LEFT + plot_width / 2.0,
+        HEIGHT - 10.0,
+        html_escape(x_title),
+        top + plot_height / 2.0,
+        html_escape(y_title)
+    ));
+    for line in series {
+        let points: Vec<Option<(f64, f64)>> =
+            line.values.iter().enumerate().map(|(i, value)| value.map(|value| (scale_x(i), scale_y(value)))).collect();
+        let dash = if line.dashed { " stroke-dasharray=\"6 6\"" } else { "" };
+        svg.push_str(&format!(
+            "<path d=\"{}\" fill=\"none\" stroke=\"rgb({})\" stroke-width=\"1.5\"{dash}><title>{}</title></path>\n",
+            path(&points),
+            line.colour,
+            html_escape(&line.label)
+        ));
+        for (i, text) in &line.markers {
+            if let Some(Some(value)) = line.values.get(*i) {
+                svg.push_str(&format!(
+                    "<circle cx=\"{:.1}\" cy=\"{:.1}\" r=\"3\" fill=\"rgb({})\"><title>{}</title></circle>\n",
+                    scale_x(*i),
+                    scale_y(*value),
+                    line.colour,
+                    html_escape(text)
+                ));
+            }
+        }
+    }
+    svg.push_str("</svg>");
+    svg
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_nice_step() {
+        assert_eq!(nice_step(1000.0, 5.0), 200.0);
+        assert_eq!(nice_step(90000.0, 5.0), 20000.0);
+        assert_eq!(nice_step(3.0, 5.0), 1.0);
+    }
+
+    #[test]
+    fn test_path() {
+        let points = [Some((0.0, 1.0)), Some((2.0, 3.0)), None, Some((4.0, 5.0)), Some((6.0, 7.0))];
+        assert_eq!(path(&points), "M0.0,1.0 L2.0,3.0 M4.0,5.0 L6.0,7.0");
+    }
+
+    #[test]
+    fn test_line_chart() {
+        let labels: Vec<String> = ["2025-01-01", "2025-01-02", "2025-01-03"].map(String::from).to_vec();
+        let series = [
+            Series {
+                label: "main (£)".to_string(),
+                colour: "54, 162, 235".to_string(),
+                dashed: false,
+                values: vec![Some(0.0), Some(50.0), Some(100.0)],
+                markers: vec![(2, "Salary \"Day job\": net pay".to_string())],
+            },
+            Series {
+                label: "Target <£100>".to_string(),
+                colour: "255, 99, 132".to_string(),
+                dashed: true,
+                values: vec![Some(100.0), None, None],
+                markers: vec![(1, "Nothing drawn in a gap".to_string())],
+            },
+        ];
+        let svg = line_chart(&labels, &series, "Balance (£)", "Date");
+        assert!(svg.starts_with("<svg") && svg.ends_with("</svg>"));
+        // Nothing is fetched; the only URL is the SVG namespace
+        assert_eq!(svg.matches("http").count(), 1);
+        assert!(svg.contains("Target &lt;£100&gt;"));
+        assert!(svg.contains("stroke-dasharray=\"6 6\"><title>Target"));
+        // The y axis runs from 0 at the bottom of the plot to 100 at the top
+        let top = 20.0 + LEGEND_ROW;
+        let bottom = HEIGHT - BOTTOM;
+        assert!(svg.contains(&format!("d=\"M{LEFT:.1},{bottom:.1} L{:.1},{:.1} L{:.1},{top:.1}\"", LEFT + 445.0, (top + bottom) / 2.0, WIDTH - RIGHT)));
+        assert!(svg.contains(&format!("<circle cx=\"{:.1}\" cy=\"{top:.1}\" r=\"3\" fill=\"rgb(54, 162, 235)\"><title>Salary &quot;Day job&quot;: net pay</title>", WIDTH - RIGHT)));
+        assert!(!svg.contains("Nothing drawn"));
+    }
+}
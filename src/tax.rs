@@ -0,0 +1,195 @@
+use chrono::NaiveDate;
+use rust_decimal::Decimal;
+use serde::Deserialize;
+use serde_yaml::{Mapping, Value};
+use std::collections::BTreeMap;
+
+pub mod uk;
+
+pub use uk::StudentLoanPlan;
+
+/// Country whose tax rules apply to salaries, gains, dividends and rent
+#[derive(Debug, Clone, Copy, PartialEq, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Country {
+    #[default]
+    Uk,
+}
+
+/// A yearly limit on tax-free saving
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Allowance {
+    Isa,
+    Pension,
+}
+
+/// What's taken from a year's gross pay before it's paid
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Deductions {
+    pub income_tax: Decimal,
+    pub national_insurance: Decimal,
+    pub student_loan: Decimal,
+}
+
+/// A country's rules for taxing pay and gains. Rules can change from one tax
+/// year to the next, so each calculation is for the tax year `date` is in.
+pub trait Jurisdiction {
+    /// The tax year `date` falls in, named by the calendar year it starts in
+    fn tax_year(&self, date: NaiveDate) -> i32;
+
+    /// Annual deductions from a gross annual salary of `gross`
+    fn deductions(&self, date: NaiveDate, gross: Decimal, plans: &[StudentLoanPlan]) -> Deductions;
+
+    /// Tax on a capital gain of `gain`, assuming no other gains in the tax year
+    fn capital_gains_tax(&self, date: NaiveDate, gain: Decimal) -> Decimal;
+
+    /// Income tax saved by giving `giving` a year out of gross pay
+    fn payroll_giving_relief(&self, date: NaiveDate, gross: Decimal, giving: Decimal) -> Decimal;
+
+    /// Income tax a donor reclaims on donations worth `grossed_up` to the charity
+    fn gift_aid_relief(&self, date: NaiveDate, gross: Decimal, grossed_up: Decimal) -> Decimal;
+
+    /// Tax on a year's `dividends` received on top of `other_income` a year
+    fn dividend_tax(&self, date: NaiveDate, other_income: Decimal, dividends: Decimal) -> Decimal;
+
+    /// Income tax on a year's `rent` from property on top of `other_income` a year
+    fn property_income_tax(&self, date: NaiveDate, other_income: Decimal, rent: Decimal) -> Decimal;
+
+    /// Savings interest that's tax free in a year with `income` of other taxable income
+    fn savings_allowance(&self, date: NaiveDate, income: Decimal) -> Decimal;
+
+    /// Most that can be saved under `allowance` in the tax year
+    fn allowance(&self, date: NaiveDate, allowance: Allowance) -> Decimal;
+}
+
+/// Tax settings as written in the config
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+struct TaxConfig {
+    country: Country,
+    // YAML files of rules by tax year, e.g. `2026: {personal_allowance: 13000}`, used
+    // over the built-in rules; later files take precedence
+    files: Vec<String>,
+    // Changes to the rules from the start of each tax year given
+    years: BTreeMap<i32, Mapping>,
+    // Anything else changes the rules for every year, e.g. `personal_allowance: 13000`
+    #[serde(flatten)]
+    overrides: Mapping,
+}
+
+/// The country and its rules for each tax year they change in: the built-in
+/// rules, then any files, then the changes in the config
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(try_from = "TaxConfig")]
+pub struct TaxSettings {
+    pub country: Country,
+    pub years: BTreeMap<i32, uk::TaxRules>,
+}
+
+impl Default for TaxSettings {
+    fn default() -> Self {
+        TaxConfig::default().try_into().expect("Built-in tax rules are valid")
+    }
+}
+
+/// `changes` made to `rules`, field by field
+fn merge(rules: &mut Mapping, changes: &Mapping) {
+    for (key, value) in changes {
+        rules.insert(key.clone(), value.clone());
+    }
+}
+
+impl TryFrom<TaxConfig> for TaxSettings {
+    type Error = String;
+
+    fn try_from(config: TaxConfig) -> Result<Self, String> {
+        let built_in = match config.country {
+            Country::Uk => uk::DATA,
+        };
+        let mut data: BTreeMap<i32, Mapping> = BTreeMap::new();
+        for (year, yaml) in built_in {
+            data.insert(year, serde_yaml::from_str(yaml).map_err(|e| format!("Built-in tax rules for {year}: {e}"))?);
+        }
+        for file in &config.files {
+            let yaml = std::fs::read_to_string(file).map_err(|e| format!("Can't read tax rules from {file}: {e}"))?;
+            let years: BTreeMap<i32, Mapping> = serde_yaml::from_str(&yaml).map_err(|e| format!("Tax rules in {file}: {e}"))?;
+            for (year, rules) in years {
+                merge(data.entry(year).or_default(), &rules);
+            }
+        }
+        // Each year's rules carry on from the year before, with that year's changes
+        let mut years = BTreeMap::new();
+        let mut rules = Mapping::new();
+        let starts: std::collections::BTreeSet<i32> = data.keys().chain(config.years.keys()).copied().collect();
+        for year in starts {
+            if let Some(changes) = data.get(&year) {
+                merge(&mut rules, changes);
+            }
+            let mut in_force = rules.clone();
+            merge(&mut in_force, &config.overrides);
+            for (_, changes) in config.years.range(..=year) {
+                merge(&mut in_force, changes);
+            }
+            let parsed = serde_yaml::from_value(Value::Mapping(in_force)).map_err(|e| format!("Tax rules for {year}: {e}"))?;
+            years.insert(year, parsed);
+        }
+        Ok(TaxSettings { country: config.country, years })
+    }
+}
+
+impl TaxSettings {
+    pub fn jurisdiction(&self) -> Box<dyn Jurisdiction + '_> {
+        match self.country {
+            Country::Uk => Box::new(uk::Uk { years: &self.years }),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal_macros::dec;
+
+    #[test]
+    fn test_settings() {
+        let settings: TaxSettings = serde_yaml::from_str(
+            r#"
+country: uk
+personal_allowance: 10000
+years:
+  2026:
+    capital_gains_rate: 28
+  2027:
+    personal_allowance: 11000
+"#,
+        )
+        .unwrap();
+        assert_eq!(settings.years.keys().copied().collect::<Vec<_>>(), vec![2024, 2025, 2026, 2027]);
+        let date = |y| NaiveDate::from_ymd_opt(y, 6, 1).unwrap();
+        let jurisdiction = settings.jurisdiction();
+        assert_eq!(jurisdiction.capital_gains_tax(date(2025), dec!(13000)), dec!(2400.00));
+        assert_eq!(jurisdiction.capital_gains_tax(date(2030), dec!(13000)), dec!(2800.00));
+        // The allowance set for every year applies until the change in 2027
+        assert_eq!(settings.years[&2024].personal_allowance, dec!(10000));
+        assert_eq!(settings.years[&2026].personal_allowance, dec!(10000));
+        assert_eq!(settings.years[&2027].personal_allowance, dec!(11000));
+        // Student loan thresholds follow the built-in rules for each year
+        assert_eq!(settings.years[&2024].student_loans[1].threshold, dec!(27295));
+        assert_eq!(settings.years[&2027].student_loans[1].threshold, dec!(28470));
+    }
+
+    #[test]
+    fn test_rules_from_file() {
+        let path = std::env::temp_dir().join("cash_forecast_test_tax_rules.yaml");
+        std::fs::write(&path, "2026:\n  isa_allowance: 10000\n").unwrap();
+        let settings: TaxSettings = serde_yaml::from_str(&format!("files: [{}]\n", path.display())).unwrap();
+        let jurisdiction = settings.jurisdiction();
+        let date = |y| NaiveDate::from_ymd_opt(y, 6, 1).unwrap();
+        assert_eq!(jurisdiction.allowance(date(2025), Allowance::Isa), dec!(20000));
+        assert_eq!(jurisdiction.allowance(date(2026), Allowance::Isa), dec!(10000));
+        assert_eq!(jurisdiction.allowance(date(2026), Allowance::Pension), dec!(60000));
+        let missing: Result<TaxSettings, _> = serde_yaml::from_str("files: [/nonexistent/rules.yaml]\n");
+        assert!(missing.unwrap_err().to_string().contains("Can't read tax rules from /nonexistent/rules.yaml"));
+    }
+}
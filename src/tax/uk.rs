@@ -0,0 +1,338 @@
+use chrono::{Datelike, NaiveDate};
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+use serde::Deserialize;
+use std::collections::BTreeMap;
+
+use super::{Allowance, Deductions, Jurisdiction};
+
+/// Income-contingent student loan repayment plans
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StudentLoanPlan {
+    Plan1,
+    Plan2,
+    Plan4,
+    Plan5,
+    Postgraduate,
+}
+
+/// A rate of income tax on taxable income (after the personal allowance) up to `upto`
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct TaxBand {
+    // Top of the band in taxable income; None for the top band
+    pub upto: Option<Decimal>,
+    pub rate: Decimal,
+}
+
+/// Employee Class 1 National Insurance
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct NationalInsurance {
+    pub primary_threshold: Decimal,
+    pub upper_earnings_limit: Decimal,
+    pub main_rate: Decimal,
+    pub upper_rate: Decimal,
+}
+
+/// Repayments on a student loan plan: `rate` percent of income over `threshold`
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct StudentLoanRule {
+    pub plan: StudentLoanPlan,
+    pub threshold: Decimal,
+    pub rate: Decimal,
+}
+
+/// UK income tax, National Insurance, student loan, capital gains, dividend,
+/// property income and savings allowance rules for a tax year
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct TaxRules {
+    pub personal_allowance: Decimal,
+    // Income above which the allowance is reduced by £1 for every £2
+    pub allowance_taper_threshold: Decimal,
+    pub bands: Vec<TaxBand>,
+    pub national_insurance: NationalInsurance,
+    pub student_loans: Vec<StudentLoanRule>,
+    // Capital gains tax annual exempt amount, and the rate on gains above it
+    // (24% for a higher rate taxpayer, 18% at basic rate)
+    pub capital_gains_allowance: Decimal,
+    pub capital_gains_rate: Decimal,
+    // Dividends taxed at nothing each year, and the rates on dividends falling in each income tax band
+    pub dividend_allowance: Decimal,
+    pub dividend_rates: Vec<Decimal>,
+    // Rent that's tax free each year when no expenses are claimed against it
+    pub property_allowance: Decimal,
+    // Savings interest taxed at nothing each year, the Personal Savings
+    // Allowance, by the band the taxpayer's income reaches
+    pub savings_allowances: Vec<Decimal>,
+    // Most that can be paid into ISAs, and into pensions with tax relief, in the tax year
+    pub isa_allowance: Decimal,
+    pub pension_annual_allowance: Decimal,
+}
+
+/// The built-in rules, by the tax year they start in
+pub const DATA: [(i32, &str); 2] = [(2024, include_str!("uk/2024.yaml")), (2025, include_str!("uk/2025.yaml"))];
+
+impl Default for TaxRules {
+    /// The latest built-in rules
+    fn default() -> Self {
+        serde_yaml::from_str(DATA[DATA.len() - 1].1).expect("Built-in tax rules are valid")
+    }
+}
+
+/// The UK tax year `date` falls in, named by the calendar year it starts in
+/// on 6 April
+pub fn tax_year(date: NaiveDate) -> i32 {
+    if (date.month(), date.day()) >= (4, 6) { date.year() } else { date.year() - 1 }
+}
+
+/// UK rules: for each tax year, the rules of the latest year in `years` that
+/// has started, or the earliest rules for years before any of them
+pub struct Uk<'a> {
+    pub years: &'a BTreeMap<i32, TaxRules>,
+}
+
+impl Uk<'_> {
+    fn rules(&self, date: NaiveDate) -> &TaxRules {
+        self.years
+            .range(..=tax_year(date))
+            .next_back()
+            .or_else(|| self.years.first_key_value())
+            .map(|(_, rules)| rules)
+            .expect("Tax rules for at least one year")
+    }
+}
+
+impl Jurisdiction for Uk<'_> {
+    fn tax_year(&self, date: NaiveDate) -> i32 {
+        tax_year(date)
+    }
+
+    fn deductions(&self, date: NaiveDate, gross: Decimal, plans: &[StudentLoanPlan]) -> Deductions {
+        deductions(self.rules(date), gross, plans)
+    }
+
+    fn capital_gains_tax(&self, date: NaiveDate, gain: Decimal) -> Decimal {
+        capital_gains_tax(self.rules(date), gain)
+    }
+
+    fn payroll_giving_relief(&self, date: NaiveDate, gross: Decimal, giving: Decimal) -> Decimal {
+        payroll_giving_relief(self.rules(date), gross, giving)
+    }
+
+    fn gift_aid_relief(&self, date: NaiveDate, gross: Decimal, grossed_up: Decimal) -> Decimal {
+        gift_aid_relief(self.rules(date), gross, grossed_up)
+    }
+
+    fn dividend_tax(&self, date: NaiveDate, other_income: Decimal, dividends: Decimal) -> Decimal {
+        dividend_tax(self.rules(date), other_income, dividends)
+    }
+
+    fn property_income_tax(&self, date: NaiveDate, other_income: Decimal, rent: Decimal) -> Decimal {
+        property_income_tax(self.rules(date), other_income, rent)
+    }
+
+    fn savings_allowance(&self, date: NaiveDate, income: Decimal) -> Decimal {
+        savings_allowance(self.rules(date), income)
+    }
+
+    fn allowance(&self, date: NaiveDate, allowance: Allowance) -> Decimal {
+        let rules = self.rules(date);
+        match allowance {
+            Allowance::Isa => rules.isa_allowance,
+            Allowance::Pension => rules.pension_annual_allowance,
+        }
+    }
+}
+
+/// Annual deductions from a gross annual salary of `gross`
+pub fn deductions(rules: &TaxRules, gross: Decimal, plans: &[StudentLoanPlan]) -> Deductions {
+    let taper = ((gross - rules.allowance_taper_threshold) / dec!(2)).max(Decimal::ZERO);
+    let allowance = (rules.personal_allowance - taper).max(Decimal::ZERO);
+    let taxable = (gross - allowance).max(Decimal::ZERO);
+    let mut income_tax = Decimal::ZERO;
+    let mut lower = Decimal::ZERO;
+    for band in &rules.bands {
+        let upper = band.upto.map_or(taxable, |upto| upto.min(taxable));
+        if upper > lower {
+            income_tax += (upper - lower) * band.rate / dec!(100);
+        }
+        lower = lower.max(upper);
+    }
+
+    let ni = &rules.national_insurance;
+    let main_band = gross.min(ni.upper_earnings_limit) - ni.primary_threshold;
+    let upper_band = gross - ni.upper_earnings_limit;
+    let national_insurance = main_band.max(Decimal::ZERO) * ni.main_rate / dec!(100)
+        + upper_band.max(Decimal::ZERO) * ni.upper_rate / dec!(100);
+
+    let student_loan: Decimal = rules
+        .student_loans
+        .iter()
+        .filter(|rule| plans.contains(&rule.plan))
+        .map(|rule| (gross - rule.threshold).max(Decimal::ZERO) * rule.rate / dec!(100))
+        .sum();
+
+    Deductions {
+        income_tax: income_tax.round_dp(2),
+        national_insurance: national_insurance.round_dp(2),
+        student_loan: student_loan.round_dp(2),
+    }
+}
+
+/// Capital gains tax on `gain`, assuming no other gains in the tax year
+pub fn capital_gains_tax(rules: &TaxRules, gain: Decimal) -> Decimal {
+    ((gain - rules.capital_gains_allowance).max(Decimal::ZERO) * rules.capital_gains_rate / dec!(100)).round_dp(2)
+}
+
+/// Tax on a year's `dividends` received on top of `other_income`. Dividends
+/// are the top slice of income: they use whatever personal allowance the
+/// other income leaves, then the dividend allowance is taxed at nothing
+/// (though it still fills the band it falls in), and the rest is taxed at the
+/// dividend rate of each band it falls in.
+pub fn dividend_tax(rules: &TaxRules, other_income: Decimal, dividends: Decimal) -> Decimal {
+    let taper = ((other_income + dividends - rules.allowance_taper_threshold) / dec!(2)).max(Decimal::ZERO);
+    let allowance = (rules.personal_allowance - taper).max(Decimal::ZERO);
+    let taxable_other = (other_income - allowance).max(Decimal::ZERO);
+    let taxable = (dividends - (allowance - other_income).max(Decimal::ZERO)).max(Decimal::ZERO);
+    // Taxable income, counted from the bottom of the basic rate band, that's taxed as dividends
+    let (bottom, top) = (taxable_other + rules.dividend_allowance.min(taxable), taxable_other + taxable);
+    let mut tax = Decimal::ZERO;
+    let mut lower = Decimal::ZERO;
+    for (band, rate) in rules.bands.iter().zip(&rules.dividend_rates) {
+        let upper = band.upto.map_or(top, |upto| upto.min(top));
+        let taxed = upper - lower.max(bottom);
+        if taxed > Decimal::ZERO {
+            tax += taxed * rate / dec!(100);
+        }
+        lower = lower.max(upper);
+    }
+    tax.round_dp(2)
+}
+
+/// Income tax on a year's `rent` on top of `other_income`, once the property
+/// allowance is taken off it
+pub fn property_income_tax(rules: &TaxRules, other_income: Decimal, rent: Decimal) -> Decimal {
+    let profit = (rent - rules.property_allowance).max(Decimal::ZERO);
+    deductions(rules, other_income + profit, &[]).income_tax - deductions(rules, other_income, &[]).income_tax
+}
+
+/// The Personal Savings Allowance of a taxpayer with `income` a year: the
+/// allowance of the highest band their taxable income reaches
+pub fn savings_allowance(rules: &TaxRules, income: Decimal) -> Decimal {
+    let taper = ((income - rules.allowance_taper_threshold) / dec!(2)).max(Decimal::ZERO);
+    let taxable = income - (rules.personal_allowance - taper).max(Decimal::ZERO);
+    let band = rules.bands.iter().position(|band| band.upto.is_none_or(|upto| taxable <= upto)).unwrap_or(rules.bands.len());
+    rules.savings_allowances.get(band).copied().unwrap_or_default()
+}
+
+/// Income tax saved by giving `giving` a year through payroll, which comes
+/// out of gross pay before tax but not before National Insurance
+pub fn payroll_giving_relief(rules: &TaxRules, gross: Decimal, giving: Decimal) -> Decimal {
+    deductions(rules, gross, &[]).income_tax - deductions(rules, gross - giving, &[]).income_tax
+}
+
+/// Income tax a donor reclaims on Gift Aid donations worth `grossed_up` to
+/// the charity. The basic rate band and the allowance taper threshold are
+/// extended by the grossed-up amount, so only relief above basic rate is due.
+pub fn gift_aid_relief(rules: &TaxRules, gross: Decimal, grossed_up: Decimal) -> Decimal {
+    let mut extended = rules.clone();
+    extended.allowance_taper_threshold += grossed_up;
+    if let Some(upto) = extended.bands.first_mut().and_then(|band| band.upto.as_mut()) {
+        *upto += grossed_up;
+    }
+    deductions(rules, gross, &[]).income_tax - deductions(&extended, gross, &[]).income_tax
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_higher_rate_taxpayer() {
+        let deductions = deductions(&TaxRules::default(), dec!(62000), &[StudentLoanPlan::Plan2]);
+        // 20% of 37,700 plus 40% of the 11,730 above it
+        assert_eq!(deductions.income_tax, dec!(12232.00));
+        // 8% of 37,700 plus 2% of the 11,730 above the upper earnings limit
+        assert_eq!(deductions.national_insurance, dec!(3250.60));
+        assert_eq!(deductions.student_loan, dec!(3017.70));
+    }
+
+    #[test]
+    fn test_allowance_tapered_away() {
+        let deductions = deductions(&TaxRules::default(), dec!(130000), &[]);
+        // No allowance: 20% of 37,700, 40% of 87,440 and 45% of 4,860
+        assert_eq!(deductions.income_tax, dec!(44703.00));
+        assert_eq!(deductions.student_loan, Decimal::ZERO);
+    }
+
+    #[test]
+    fn test_giving_relief() {
+        let rules = TaxRules::default();
+        // £1,000 through payroll saves 40% for a higher rate taxpayer, 20% at basic rate
+        assert_eq!(payroll_giving_relief(&rules, dec!(62000), dec!(1000)), dec!(400.00));
+        assert_eq!(payroll_giving_relief(&rules, dec!(30000), dec!(1000)), dec!(200.00));
+        // With Gift Aid the charity reclaims the basic rate; the donor gets back only the difference
+        assert_eq!(gift_aid_relief(&rules, dec!(62000), dec!(1000)), dec!(200.00));
+        assert_eq!(gift_aid_relief(&rules, dec!(30000), dec!(1000)), dec!(0.00));
+    }
+
+    #[test]
+    fn test_dividend_and_property_income_tax() {
+        let rules = TaxRules::default();
+        // The first £500 is free; the rest is at 8.75% within the basic rate band
+        assert_eq!(dividend_tax(&rules, dec!(30000), dec!(2500)), dec!(175.00));
+        // Unused personal allowance covers dividends first
+        assert_eq!(dividend_tax(&rules, dec!(0), dec!(13070)), dec!(0.00));
+        // Other income uses 17,430 of the 37,700 basic rate band; after the free 500,
+        // 19,770 fills the rest at 8.75% and the last 9,730 is at 33.75%
+        assert_eq!(dividend_tax(&rules, dec!(30000), dec!(30000)), dec!(5013.75));
+        assert_eq!(dividend_tax(&rules, dec!(60000), Decimal::ZERO), Decimal::ZERO);
+        // Rent above the £1,000 allowance is taxed at the marginal rate
+        assert_eq!(property_income_tax(&rules, dec!(30000), dec!(11000)), dec!(2000.00));
+        assert_eq!(property_income_tax(&rules, dec!(60000), dec!(11000)), dec!(4000.00));
+        assert_eq!(property_income_tax(&rules, dec!(5000), dec!(800)), dec!(0.00));
+    }
+
+    #[test]
+    fn test_savings_allowance() {
+        let rules = TaxRules::default();
+        assert_eq!(savings_allowance(&rules, dec!(0)), dec!(1000));
+        assert_eq!(savings_allowance(&rules, dec!(50270)), dec!(1000));
+        assert_eq!(savings_allowance(&rules, dec!(50271)), dec!(500));
+        // With the personal allowance tapered away, the additional rate starts at 125,140
+        assert_eq!(savings_allowance(&rules, dec!(125140)), dec!(500));
+        assert_eq!(savings_allowance(&rules, dec!(125141)), dec!(0));
+    }
+
+    #[test]
+    fn test_below_thresholds() {
+        let deductions = deductions(&TaxRules::default(), dec!(12000), &[StudentLoanPlan::Plan1, StudentLoanPlan::Postgraduate]);
+        assert_eq!(deductions, Deductions::default());
+    }
+
+    #[test]
+    fn test_rules_by_tax_year() {
+        let date = |y, m, d| NaiveDate::from_ymd_opt(y, m, d).unwrap();
+        let years = BTreeMap::from([
+            (2025, TaxRules::default()),
+            (2027, TaxRules { personal_allowance: dec!(13570), isa_allowance: dec!(25000), ..TaxRules::default() }),
+        ]);
+        let uk = Uk { years: &years };
+        let tax = |date| uk.deductions(date, dec!(30000), &[]).income_tax;
+        assert_eq!(tax(date(2020, 1, 1)), dec!(3486.00));
+        assert_eq!(tax(date(2027, 4, 5)), dec!(3486.00));
+        assert_eq!(tax(date(2027, 4, 6)), dec!(3286.00));
+        assert_eq!(tax(date(2035, 1, 1)), dec!(3286.00));
+        assert_eq!(uk.allowance(date(2028, 1, 1), Allowance::Isa), dec!(25000));
+        assert_eq!(uk.tax_year(date(2027, 4, 5)), 2026);
+    }
+
+    #[test]
+    fn test_built_in_rules() {
+        let rules: Vec<TaxRules> = DATA.iter().map(|(_, yaml)| serde_yaml::from_str(yaml).unwrap()).collect();
+        let plan2 = |rules: &TaxRules| rules.student_loans.iter().find(|rule| rule.plan == StudentLoanPlan::Plan2).unwrap().threshold;
+        assert_eq!(plan2(&rules[0]), dec!(27295));
+        assert_eq!(plan2(&rules[1]), dec!(28470));
+        assert_eq!(TaxRules::default(), rules[1]);
+    }
+}
@@ -0,0 +1,152 @@
+use serde::de::DeserializeOwned;
+use serde_yaml::{Mapping, Value};
+
+/// Something wrong with the config, at a line of it if known
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Problem {
+    // Numbered from 1
+    pub line: Option<usize>,
+    pub message: String,
+}
+
+impl Problem {
+    pub fn new(line: Option<usize>, message: impl Into<String>) -> Self {
+        Problem { line, message: message.into() }
+    }
+}
+
+impl std::fmt::Display for Problem {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self.line {
+            Some(line) => write!(f, "line {line}: {}", self.message),
+            None => write!(f, "{}", self.message),
+        }
+    }
+}
+
+/// Keys of `mapping` that `T` ignores. Each key's value in turn is swapped
+/// for a list holding an empty list, which no field of a config type reads;
+/// if `T` can still be read, the key can't be one of its fields.
+pub fn unknown_keys<T: DeserializeOwned>(mapping: &Mapping) -> Vec<String> {
+    if serde_yaml::from_value::<T>(Value::Mapping(mapping.clone())).is_err() {
+        return Vec::new();
+    }
+    let probe = Value::Sequence(vec![Value::Sequence(Vec::new())]);
+    mapping
+        .keys()
+        .filter(|key| {
+            let mut probed = mapping.clone();
+            probed.insert((*key).clone(), probe.clone());
+            serde_yaml::from_value::<T>(Value::Mapping(probed)).is_ok()
+        })
+        .filter_map(|key| key.as_str().map(str::to_string))
+        .collect()
+}
+
+fn indent(line: &str) -> usize {
+    line.len() - line.trim_start().len()
+}
+
+/// Whether `line` is content rather than blank or a comment
+fn content(line: &str) -> bool {
+    !line.trim().is_empty() && !line.trim_start().starts_with('#')
+}
+
+/// Index of the line holding top-level `key`, and of the line after its block
+fn section(lines: &[&str], key: &str) -> Option<(usize, usize)> {
+    let start = lines.iter().position(|line| indent(line) == 0 && line.split_once(':').is_some_and(|(name, _)| name.trim() == key))?;
+    let end = lines.iter().enumerate().skip(start + 1).find(|(_, line)| content(line) && indent(line) == 0).map_or(lines.len(), |(i, _)| i);
+    Some((start, end))
+}
+
+/// Line number of top-level `key`
+pub fn section_line(yaml: &str, key: &str) -> Option<usize> {
+    let lines: Vec<&str> = yaml.lines().collect();
+    section(&lines, key).map(|(start, _)| start + 1)
+}
+
+/// Line number of each item in the top-level list `key`, e.g. each generator
+pub fn item_lines(yaml: &str, key: &str) -> Vec<usize> {
+    let lines: Vec<&str> = yaml.lines().collect();
+    let Some((start, end)) = section(&lines, key) else {
+        return Vec::new();
+    };
+    let items: Vec<usize> = (start + 1..end).filter(|i| lines[*i].trim_start().starts_with("- ") || lines[*i].trim() == "-").collect();
+    let Some(depth) = items.iter().map(|i| indent(lines[*i])).min() else {
+        return Vec::new();
+    };
+    items.into_iter().filter(|i| indent(lines[*i]) == depth).map(|i| i + 1).collect()
+}
+
+/// Line number of the first line at or after line `from` that sets `key`
+pub fn key_line(yaml: &str, from: usize, key: &str) -> Option<usize> {
+    yaml.lines().enumerate().skip(from.saturating_sub(1)).find_map(|(i, line)| {
+        let line = line.trim_start().trim_start_matches("- ");
+        line.split_once(':').is_some_and(|(name, _)| name.trim() == key).then_some(i + 1)
+    })
+}
+
+/// Keys given more than once in the top-level mapping `key`, e.g. accounts,
+/// with the line of each repeat
+pub fn duplicate_keys(yaml: &str, key: &str) -> Vec<(usize, String)> {
+    let lines: Vec<&str> = yaml.lines().collect();
+    let Some((start, end)) = section(&lines, key) else {
+        return Vec::new();
+    };
+    let entries: Vec<(usize, &str)> = (start + 1..end).filter(|i| content(lines[*i])).map(|i| (i, lines[i])).collect();
+    let Some(depth) = entries.iter().map(|(_, line)| indent(line)).min() else {
+        return Vec::new();
+    };
+    let mut seen = Vec::new();
+    let mut duplicates = Vec::new();
+    for (i, line) in entries.into_iter().filter(|(_, line)| indent(line) == depth) {
+        if let Some((name, _)) = line.split_once(':') {
+            let name = name.trim().trim_matches(['"', '\'']).to_string();
+            if seen.contains(&name) {
+                duplicates.push((i + 1, name));
+            } else {
+                seen.push(name);
+            }
+        }
+    }
+    duplicates
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::giving::Recipient;
+
+    #[test]
+    fn test_unknown_keys() {
+        let mapping: Mapping = serde_yaml::from_str("{name: Oxfam, account: oxfam, percentgae: 50, amount: 10}").unwrap();
+        assert_eq!(unknown_keys::<Recipient>(&mapping), vec!["percentgae"]);
+        // Nothing can be said until the fields it has are right
+        let mapping: Mapping = serde_yaml::from_str("{name: Oxfam, colour: red}").unwrap();
+        assert!(unknown_keys::<Recipient>(&mapping).is_empty());
+    }
+
+    #[test]
+    fn test_lines() {
+        let yaml = "\
+start_date: 2025-01-01
+generators:
+  # Pay
+  - type: salary
+    amount: 2000
+  - type: expense
+    schedule:
+      - not an item
+accounts:
+  main: 100
+  savings: 0
+  main: 200
+";
+        assert_eq!(section_line(yaml, "accounts"), Some(9));
+        assert_eq!(item_lines(yaml, "generators"), vec![4, 6]);
+        assert_eq!(key_line(yaml, 6, "schedule"), Some(7));
+        assert_eq!(key_line(yaml, 6, "type"), Some(6));
+        assert_eq!(duplicate_keys(yaml, "accounts"), vec![(12, "main".to_string())]);
+        assert!(duplicate_keys(yaml, "goals").is_empty());
+    }
+}
@@ -0,0 +1,245 @@
+use rust_decimal::Decimal;
+use serde_yaml::{Mapping, Value};
+use std::collections::HashMap;
+
+/// Settings that are always text, so a generator named `rent` stays so
+/// alongside a variable called `rent`
+const TEXT_KEYS: [&str; 3] = ["name", "description", "type"];
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Number(Decimal),
+    Name(String),
+    Operator(char),
+}
+
+/// The tokens of `text`, or None if it has anything an expression can't
+fn tokens(text: &str) -> Option<Vec<Token>> {
+    let mut tokens = Vec::new();
+    let mut chars = text.chars().peekable();
+    while let Some(&c) = chars.peek() {
+        if c.is_whitespace() {
+            chars.next();
+        } else if c.is_ascii_digit() || c == '.' {
+            let mut number = String::new();
+            while let Some(c) = chars.next_if(|c| c.is_ascii_digit() || *c == '.') {
+                number.push(c);
+            }
+            tokens.push(Token::Number(number.parse().ok()?));
+        } else if c.is_ascii_alphabetic() || c == '_' {
+            let mut name = String::new();
+            while let Some(c) = chars.next_if(|c| c.is_ascii_alphanumeric() || *c == '_') {
+                name.push(c);
+            }
+            tokens.push(Token::Name(name));
+        } else if "+-*/()".contains(c) {
+            chars.next();
+            tokens.push(Token::Operator(c));
+        } else {
+            return None;
+        }
+    }
+    Some(tokens)
+}
+
+/// Works out an expression from its tokens by recursive descent, with `*` and
+/// `/` binding tighter than `+` and `-`
+struct Parser<'a> {
+    tokens: &'a [Token],
+    position: usize,
+    variables: &'a HashMap<String, Decimal>,
+}
+
+impl Parser<'_> {
+    fn next_if(&mut self, operators: &str) -> Option<char> {
+        match self.tokens.get(self.position) {
+            Some(Token::Operator(c)) if operators.contains(*c) => {
+                self.position += 1;
+                Some(*c)
+            }
+            _ => None,
+        }
+    }
+
+    fn expression(&mut self) -> Result<Decimal, String> {
+        let mut value = self.term()?;
+        while let Some(operator) = self.next_if("+-") {
+            let term = self.term()?;
+            value = if operator == '+' { value.checked_add(term) } else { value.checked_sub(term) }.ok_or("the result is too large")?;
+        }
+        Ok(value)
+    }
+
+    fn term(&mut self) -> Result<Decimal, String> {
+        let mut value = self.factor()?;
+        while let Some(operator) = self.next_if("*/") {
+            let factor = self.factor()?;
+            value = match operator {
+                '*' => value.checked_mul(factor).ok_or("the result is too large")?,
+                _ if factor.is_zero() => return Err("it divides by zero".to_string()),
+                _ => value.checked_div(factor).ok_or("the result is too large")?,
+            };
+        }
+        Ok(value)
+    }
+
+    fn factor(&mut self) -> Result<Decimal, String> {
+        if self.next_if("-").is_some() {
+            return Ok(-self.factor()?);
+        }
+        if self.next_if("(").is_some() {
+            let value = self.expression()?;
+            return self.next_if(")").map(|_| value).ok_or("a bracket isn't closed".to_string());
+        }
+        let token = self.tokens.get(self.position).ok_or("it ends too soon")?;
+        self.position += 1;
+        match token {
+            Token::Number(number) => Ok(*number),
+            Token::Name(name) => self.variables.get(name).copied().ok_or_else(|| format!("{name} is not a variable")),
+            Token::Operator(c) => Err(format!("{c} is out of place")),
+        }
+    }
+}
+
+/// The value of `text` if it's an arithmetic expression using `variables`,
+/// e.g. `net_salary * 0.1`. Text that names no variable is left as text, so
+/// account names, dates and the like are never mistaken for expressions.
+pub fn evaluate(text: &str, variables: &HashMap<String, Decimal>) -> Result<Option<Decimal>, String> {
+    let Some(tokens) = tokens(text) else { return Ok(None) };
+    if !tokens.iter().any(|token| matches!(token, Token::Name(name) if variables.contains_key(name))) {
+        return Ok(None);
+    }
+    let mut parser = Parser { tokens: &tokens, position: 0, variables };
+    let value = parser.expression().and_then(|value| match tokens.get(parser.position) {
+        None => Ok(value),
+        Some(_) => Err("it has more after the end of the expression".to_string()),
+    });
+    value.map(Some).map_err(|e| format!("'{text}' can't be worked out: {e}"))
+}
+
+/// `value` as YAML: a whole number as a number, so it can fill in a day or a
+/// count, and otherwise as a string, so it's read back exactly
+fn number(value: Decimal) -> Value {
+    let value = value.normalize();
+    match i64::try_from(value) {
+        Ok(whole) if value.scale() == 0 => Value::from(whole),
+        _ => Value::from(value.to_string()),
+    }
+}
+
+/// Every string in `value` that's an expression using `variables` replaced by its result
+fn substitute(value: &mut Value, variables: &HashMap<String, Decimal>) -> Result<(), String> {
+    match value {
+        Value::String(text) => {
+            if let Some(result) = evaluate(text, variables)? {
+                *value = number(result);
+            }
+        }
+        Value::Sequence(items) => {
+            for item in items {
+                substitute(item, variables)?;
+            }
+        }
+        Value::Mapping(mapping) => {
+            for (key, item) in mapping.iter_mut() {
+                if !key.as_str().is_some_and(|key| TEXT_KEYS.contains(&key)) {
+                    substitute(item, variables)?;
+                }
+            }
+        }
+        Value::Tagged(tagged) => substitute(&mut tagged.value, variables)?,
+        _ => {}
+    }
+    Ok(())
+}
+
+/// Work out the config document's `variables`, each a number or an
+/// expression of those before it, e.g. `{net_salary: 3200, tithe: "net_salary
+/// * 0.1"}`, and put their values in place of every expression that uses them
+pub fn resolve(document: &mut Value) -> Result<(), String> {
+    let Some(Value::Mapping(defined)) = document.get("variables").cloned() else {
+        return Ok(());
+    };
+    let mut variables = HashMap::new();
+    let mut values = Mapping::new();
+    for (name, value) in defined {
+        let name = name.as_str().ok_or("variables must be named")?.to_string();
+        let value = match &value {
+            Value::Number(number) => number.to_string().parse().ok(),
+            Value::String(text) => match text.parse() {
+                Ok(number) => Some(number),
+                Err(_) => evaluate(text, &variables).map_err(|e| format!("variable {name}: {e}"))?,
+            },
+            _ => None,
+        };
+        let value: Decimal = value.ok_or_else(|| format!("variable {name} is neither a number nor an expression of the variables before it"))?;
+        if document.get("accounts").and_then(|accounts| accounts.get(&name)).is_some() {
+            return Err(format!("variable {name} has the same name as an account"));
+        }
+        values.insert(Value::from(name.as_str()), number(value));
+        variables.insert(name, value);
+    }
+    let mapping = document.as_mapping_mut().ok_or("Config is not a mapping")?;
+    for (key, value) in mapping.iter_mut() {
+        if key.as_str() != Some("variables") {
+            substitute(value, &variables)?;
+        }
+    }
+    mapping.insert(Value::from("variables"), Value::Mapping(values));
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal_macros::dec;
+
+    #[test]
+    fn test_evaluate() {
+        let variables = HashMap::from([("net_salary".to_string(), dec!(3200)), ("rent".to_string(), dec!(1200))]);
+        let evaluate = |text| evaluate(text, &variables);
+        assert_eq!(evaluate("net_salary * 0.1"), Ok(Some(dec!(320.00))));
+        assert_eq!(evaluate("(net_salary - rent) / 4 + 2 * -1"), Ok(Some(dec!(498))));
+        assert_eq!(evaluate("rent"), Ok(Some(dec!(1200))));
+        // Text without a variable isn't an expression
+        for text in ["main", "2025-01-01", "last_working_day", "Rent & bills", "1200 / 12"] {
+            assert_eq!(evaluate(text), Ok(None));
+        }
+        assert_eq!(evaluate("net_salary * bonus"), Err("'net_salary * bonus' can't be worked out: bonus is not a variable".to_string()));
+        assert_eq!(evaluate("rent / (1 - 1)"), Err("'rent / (1 - 1)' can't be worked out: it divides by zero".to_string()));
+        assert_eq!(evaluate("(rent"), Err("'(rent' can't be worked out: a bracket isn't closed".to_string()));
+    }
+
+    #[test]
+    fn test_resolve() {
+        let mut document: Value = serde_yaml::from_str(
+            "
+variables:
+  net_salary: 3200
+  tithe: net_salary * 0.1 + 0.5
+  payday: 25
+generators:
+  - {type: salary, name: net_salary, amount: net_salary, day: payday, to: main}
+  - {type: transfer, amount: tithe, day: payday, from: main, to: charity}
+accounts:
+  main: 1000
+",
+        )
+        .unwrap();
+        resolve(&mut document).unwrap();
+        assert_eq!(document["variables"]["tithe"], Value::from("320.5"));
+        assert_eq!(document["generators"][0]["amount"], Value::from(3200));
+        assert_eq!(document["generators"][0]["name"], Value::from("net_salary"));
+        assert_eq!(document["generators"][1]["amount"], Value::from("320.5"));
+        assert_eq!(document["generators"][1]["day"], Value::from(25));
+        assert_eq!(document["generators"][1]["to"], Value::from("charity"));
+
+        let mut clash: Value = serde_yaml::from_str("variables: {main: 5}\naccounts: {main: 1000}\n").unwrap();
+        assert_eq!(resolve(&mut clash), Err("variable main has the same name as an account".to_string()));
+        let mut backwards: Value = serde_yaml::from_str("variables: {tithe: net_salary * 0.1, net_salary: 3200}\n").unwrap();
+        assert_eq!(
+            resolve(&mut backwards),
+            Err("variable tithe is neither a number nor an expression of the variables before it".to_string())
+        );
+    }
+}
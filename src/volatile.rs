@@ -0,0 +1,69 @@
+use chrono::NaiveDate;
+use rand::rngs::StdRng;
+use rust_decimal::prelude::{FromPrimitive, ToPrimitive};
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+use serde::Deserialize;
+
+use crate::{investment, monte_carlo};
+
+/// A sudden fall in value on a given day, for stress testing
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct Drawdown {
+    pub date: NaiveDate,
+    // Percentage of the value lost
+    pub percent: Decimal,
+}
+
+/// Fraction a volatile holding changes by in a day. Returns are normally
+/// distributed around `mean_return` a year with `volatility` percent a year
+/// standard deviation when sampled; without an `rng` they are the mean.
+pub fn daily_return(mean_return: Decimal, volatility: Decimal, rng: Option<&mut StdRng>) -> Decimal {
+    let mean = investment::growth_rate(mean_return, dec!(365));
+    let Some(rng) = rng else {
+        return mean;
+    };
+    let z = monte_carlo::standard_normal(rng);
+    let deviation = volatility.to_f64().unwrap_or(0.0) / 100.0 / 365f64.sqrt() * z;
+    (mean + Decimal::from_f64(deviation).unwrap_or_default().round_dp(10)).max(-Decimal::ONE)
+}
+
+/// Fraction of the value lost on `date` to any drawdowns on that day
+pub fn drawdown_on(drawdowns: &[Drawdown], date: NaiveDate) -> Decimal {
+    let kept = drawdowns
+        .iter()
+        .filter(|drawdown| drawdown.date == date)
+        .fold(Decimal::ONE, |kept, drawdown| kept * (Decimal::ONE - drawdown.percent / dec!(100)));
+    Decimal::ONE - kept
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+
+    #[test]
+    fn test_daily_return() {
+        let expected = daily_return(dec!(10), dec!(60), None);
+        let year = (0..365).fold(Decimal::ONE, |value, _| value * (Decimal::ONE + expected));
+        assert_eq!(year.round_dp(4), dec!(1.1000));
+        // Sampled returns vary around the mean with roughly the volatility asked for
+        let mut rng = StdRng::seed_from_u64(7);
+        let samples: Vec<f64> = (0..10000).map(|_| daily_return(dec!(10), dec!(60), Some(&mut rng)).to_f64().unwrap()).collect();
+        let mean = samples.iter().sum::<f64>() / samples.len() as f64;
+        let sd = (samples.iter().map(|s| (s - mean).powi(2)).sum::<f64>() / samples.len() as f64).sqrt();
+        assert!((sd * 365f64.sqrt() - 0.60).abs() < 0.03, "annualised volatility {}", sd * 365f64.sqrt());
+    }
+
+    #[test]
+    fn test_drawdowns_compound_on_the_day() {
+        let date = |d| NaiveDate::from_ymd_opt(2025, 3, d).unwrap();
+        let drawdowns = vec![
+            Drawdown { date: date(10), percent: dec!(50) },
+            Drawdown { date: date(10), percent: dec!(20) },
+            Drawdown { date: date(20), percent: dec!(30) },
+        ];
+        assert_eq!(drawdown_on(&drawdowns, date(10)), dec!(0.60));
+        assert_eq!(drawdown_on(&drawdowns, date(11)), Decimal::ZERO);
+    }
+}
@@ -0,0 +1,276 @@
+use chrono::NaiveDate;
+use rust_decimal::Decimal;
+
+use crate::report::html_escape;
+
+/// Longest name Excel allows a sheet
+const MAX_SHEET_NAME: usize = 31;
+
+/// Name Excel keeps for a sheet of its own, which no other sheet may take
+const RESERVED_SHEET_NAME: &str = "History";
+
+/// A cell of a worksheet
+#[derive(Debug, Clone, PartialEq)]
+pub enum Cell {
+    Empty,
+    Text(String),
+    /// Bold text, for column headings
+    Heading(String),
+    Date(NaiveDate),
+    /// An amount, shown in the workbook's currency
+    Money(Decimal),
+    /// A formula worked out by the spreadsheet, such as `B2-C2`, shown in the workbook's currency
+    Formula(String),
+}
+
+/// A worksheet, its rows from the top, each with its cells from the left
+#[derive(Debug, Clone, PartialEq)]
+pub struct Sheet {
+    pub name: String,
+    pub rows: Vec<Vec<Cell>>,
+}
+
+// Indices into the cell formats in the styles part
+const STYLE_HEADING: u32 = 1;
+const STYLE_DATE: u32 = 2;
+const STYLE_MONEY: u32 = 3;
+
+/// Letters naming the column at `index`, counting from 0: A, B, ... Z, AA
+fn column(index: usize) -> String {
+    let mut letters = Vec::new();
+    let mut rest = index + 1;
+    while rest > 0 {
+        letters.push(b'A' + ((rest - 1) % 26) as u8);
+        rest = (rest - 1) / 26;
+    }
+    letters.iter().rev().map(|&letter| letter as char).collect()
+}
+
+/// Days since 30 December 1899, which is how spreadsheets store dates
+fn serial(date: NaiveDate) -> i64 {
+    (date - NaiveDate::from_ymd_opt(1899, 12, 30).unwrap()).num_days()
+}
+
+/// `names` made fit for sheets: without the characters Excel forbids, or an
+/// apostrophe at either end, no longer than it allows, and each different
+/// from the others and from the name Excel keeps for itself
+fn sheet_names(names: &[&str]) -> Vec<String> {
+    let mut fitted: Vec<String> = Vec::new();
+    for name in names {
+        let mut clean: Vec<char> = name.chars().map(|c| if "[]:*?/\\".contains(c) { '_' } else { c }).take(MAX_SHEET_NAME).collect();
+        for end in [0, clean.len().saturating_sub(1)] {
+            if clean.get(end) == Some(&'\'') {
+                clean[end] = '_';
+            }
+        }
+        let clean: String = clean.into_iter().collect();
+        let mut unique = clean.clone();
+        let mut copy = 1;
+        while unique.eq_ignore_ascii_case(RESERVED_SHEET_NAME) || fitted.iter().any(|taken| taken.eq_ignore_ascii_case(&unique)) {
+            copy += 1;
+            let suffix = format!(" ({copy})");
+            unique = format!("{}{suffix}", clean.chars().take(MAX_SHEET_NAME - suffix.len()).collect::<String>());
+        }
+        fitted.push(unique);
+    }
+    fitted
+}
+
+fn worksheet(sheet: &Sheet) -> String {
+    let mut xml = String::from(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\" standalone=\"yes\"?>\n\
+         <worksheet xmlns=\"http://schemas.openxmlformats.org/spreadsheetml/2006/main\">\
+         <sheetViews><sheetView workbookViewId=\"0\"><pane ySplit=\"1\" topLeftCell=\"A2\" activePane=\"bottomLeft\" state=\"frozen\"/></sheetView></sheetViews>\
+         <cols><col min=\"1\" max=\"16\" width=\"16\" customWidth=\"1\"/></cols><sheetData>",
+    );
+    for (row, cells) in sheet.rows.iter().enumerate() {
+        xml.push_str(&format!("<row r=\"{}\">", row + 1));
+        for (index, cell) in cells.iter().enumerate() {
+            let at = format!("{}{}", column(index), row + 1);
+            xml.push_str(&match cell {
+                Cell::Empty => continue,
+                Cell::Text(text) => format!("<c r=\"{at}\" t=\"inlineStr\"><is><t>{}</t></is></c>", html_escape(text)),
+                Cell::Heading(text) => format!("<c r=\"{at}\" s=\"{STYLE_HEADING}\" t=\"inlineStr\"><is><t>{}</t></is></c>", html_escape(text)),
+                Cell::Date(date) => format!("<c r=\"{at}\" s=\"{STYLE_DATE}\"><v>{}</v></c>", serial(*date)),
+                Cell::Money(amount) => format!("<c r=\"{at}\" s=\"{STYLE_MONEY}\"><v>{}</v></c>", amount.normalize()),
+                Cell::Formula(formula) => format!("<c r=\"{at}\" s=\"{STYLE_MONEY}\"><f>{}</f></c>", html_escape(formula)),
+            });
+        }
+        xml.push_str("</row>");
+    }
+    xml.push_str("</sheetData></worksheet>");
+    xml
+}
+
+/// Cell formats: plain, bold headings, dates, and amounts in `currency_symbol`
+fn styles(currency_symbol: &str) -> String {
+    let symbol = html_escape(&currency_symbol.replace('"', ""));
+    format!(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\" standalone=\"yes\"?>\n\
+         <styleSheet xmlns=\"http://schemas.openxmlformats.org/spreadsheetml/2006/main\">\
+         <numFmts count=\"2\"><numFmt numFmtId=\"164\" formatCode=\"yyyy-mm-dd\"/>\
+         <numFmt numFmtId=\"165\" formatCode=\"&quot;{symbol}&quot;#,##0.00;-&quot;{symbol}&quot;#,##0.00\"/></numFmts>\
+         <fonts count=\"2\"><font><sz val=\"11\"/><name val=\"Calibri\"/></font><font><b/><sz val=\"11\"/><name val=\"Calibri\"/></font></fonts>\
+         <fills count=\"2\"><fill><patternFill patternType=\"none\"/></fill><fill><patternFill patternType=\"gray125\"/></fill></fills>\
+         <borders count=\"1\"><border><left/><right/><top/><bottom/><diagonal/></border></borders>\
+         <cellStyleXfs count=\"1\"><xf numFmtId=\"0\" fontId=\"0\" fillId=\"0\" borderId=\"0\"/></cellStyleXfs>\
+         <cellXfs count=\"4\"><xf numFmtId=\"0\" fontId=\"0\" fillId=\"0\" borderId=\"0\" xfId=\"0\"/>\
+         <xf numFmtId=\"0\" fontId=\"1\" fillId=\"0\" borderId=\"0\" xfId=\"0\" applyFont=\"1\"/>\
+         <xf numFmtId=\"164\" fontId=\"0\" fillId=\"0\" borderId=\"0\" xfId=\"0\" applyNumberFormat=\"1\"/>\
+         <xf numFmtId=\"165\" fontId=\"0\" fillId=\"0\" borderId=\"0\" xfId=\"0\" applyNumberFormat=\"1\"/></cellXfs>\
+         <cellStyles count=\"1\"><cellStyle name=\"Normal\" xfId=\"0\" builtinId=\"0\"/></cellStyles></styleSheet>"
+    )
+}
+
+/// `sheets` as an xlsx workbook, with amounts shown in `currency_symbol`.
+/// Formulas are worked out when the workbook is opened.
+pub fn workbook(sheets: &[Sheet], currency_symbol: &str) -> Vec<u8> {
+    let names = sheet_names(&sheets.iter().map(|sheet| sheet.name.as_str()).collect::<Vec<_>>());
+    let overrides: String = (1..=sheets.len())
+        .map(|n| {
+            format!(
+                "<Override PartName=\"/xl/worksheets/sheet{n}.xml\" ContentType=\"application/vnd.openxmlformats-officedocument.spreadsheetml.worksheet+xml\"/>"
+            )
+        })
+        .collect();
+    let content_types = format!(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\" standalone=\"yes\"?>\n\
+         <Types xmlns=\"http://schemas.openxmlformats.org/package/2006/content-types\">\
+         <Default Extension=\"rels\" ContentType=\"application/vnd.openxmlformats-package.relationships+xml\"/>\
+         <Default Extension=\"xml\" ContentType=\"application/xml\"/>\
+         <Override PartName=\"/xl/workbook.xml\" ContentType=\"application/vnd.openxmlformats-officedocument.spreadsheetml.sheet.main+xml\"/>\
+         <Override PartName=\"/xl/styles.xml\" ContentType=\"application/vnd.openxmlformats-officedocument.spreadsheetml.styles+xml\"/>\
+         {overrides}</Types>"
+    );
+    let package_rels = "<?xml version=\"1.0\" encoding=\"UTF-8\" standalone=\"yes\"?>\n\
+         <Relationships xmlns=\"http://schemas.openxmlformats.org/package/2006/relationships\">\
+         <Relationship Id=\"rId1\" Type=\"http://schemas.openxmlformats.org/officeDocument/2006/relationships/officeDocument\" Target=\"xl/workbook.xml\"/>\
+         </Relationships>"
+        .to_string();
+    let sheet_list: String =
+        (1..=sheets.len()).zip(&names).map(|(n, name)| format!("<sheet name=\"{}\" sheetId=\"{n}\" r:id=\"rId{n}\"/>", html_escape(name))).collect();
+    let workbook = format!(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\" standalone=\"yes\"?>\n\
+         <workbook xmlns=\"http://schemas.openxmlformats.org/spreadsheetml/2006/main\" \
+         xmlns:r=\"http://schemas.openxmlformats.org/officeDocument/2006/relationships\">\
+         <sheets>{sheet_list}</sheets><calcPr fullCalcOnLoad=\"1\"/></workbook>"
+    );
+    let sheet_rels: String = (1..=sheets.len())
+        .map(|n| {
+            format!(
+                "<Relationship Id=\"rId{n}\" Type=\"http://schemas.openxmlformats.org/officeDocument/2006/relationships/worksheet\" Target=\"worksheets/sheet{n}.xml\"/>"
+            )
+        })
+        .collect();
+    let workbook_rels = format!(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\" standalone=\"yes\"?>\n\
+         <Relationships xmlns=\"http://schemas.openxmlformats.org/package/2006/relationships\">{sheet_rels}\
+         <Relationship Id=\"rId{}\" Type=\"http://schemas.openxmlformats.org/officeDocument/2006/relationships/styles\" Target=\"styles.xml\"/>\
+         </Relationships>",
+        sheets.len() + 1
+    );
+    let mut files = vec![
+        ("[Content_Types].xml".to_string(), content_types),
+        ("_rels/.rels".to_string(), package_rels),
+        ("xl/workbook.xml".to_string(), workbook),
+        ("xl/_rels/workbook.xml.rels".to_string(), workbook_rels),
+        ("xl/styles.xml".to_string(), styles(currency_symbol)),
+    ];
+    files.extend(sheets.iter().enumerate().map(|(i, sheet)| (format!("xl/worksheets/sheet{}.xml", i + 1), worksheet(sheet))));
+    zip(&files)
+}
+
+/// CRC-32 of `bytes`, as zip files check their contents with
+fn crc32(bytes: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for &byte in bytes {
+        crc ^= u32::from(byte);
+        for _ in 0..8 {
+            crc = if crc & 1 == 1 { (crc >> 1) ^ 0xEDB8_8320 } else { crc >> 1 };
+        }
+    }
+    !crc
+}
+
+/// `files`, as (path, contents), stored uncompressed in a zip archive. Every
+/// file is dated 1 January 1980, so the same files always make the same archive.
+fn zip(files: &[(String, String)]) -> Vec<u8> {
+    const DOS_DATE: u16 = 0x21;
+    let mut archive = Vec::new();
+    let mut directory = Vec::new();
+    for (path, contents) in files {
+        let offset = archive.len() as u32;
+        let (crc, size, name) = (crc32(contents.as_bytes()), contents.len() as u32, path.as_bytes());
+        // Version needed, flags, method (stored), time and date, CRC, sizes, name length, extra length
+        let fields = |header: &mut Vec<u8>| {
+            header.extend(20u16.to_le_bytes());
+            header.extend(0u16.to_le_bytes());
+            header.extend(0u16.to_le_bytes());
+            header.extend(0u16.to_le_bytes());
+            header.extend(DOS_DATE.to_le_bytes());
+            header.extend(crc.to_le_bytes());
+            header.extend(size.to_le_bytes());
+            header.extend(size.to_le_bytes());
+            header.extend((name.len() as u16).to_le_bytes());
+            header.extend(0u16.to_le_bytes());
+        };
+        archive.extend(0x0403_4b50u32.to_le_bytes());
+        fields(&mut archive);
+        archive.extend(name);
+        archive.extend(contents.as_bytes());
+
+        directory.extend(0x0201_4b50u32.to_le_bytes());
+        // Made by
+        directory.extend(20u16.to_le_bytes());
+        fields(&mut directory);
+        // Comment length, disk, internal and external attributes, offset of the local header
+        directory.extend(0u16.to_le_bytes());
+        directory.extend(0u16.to_le_bytes());
+        directory.extend(0u16.to_le_bytes());
+        directory.extend(0u32.to_le_bytes());
+        directory.extend(offset.to_le_bytes());
+        directory.extend(name);
+    }
+    let (directory_offset, directory_size) = (archive.len() as u32, directory.len() as u32);
+    archive.extend(directory);
+    archive.extend(0x0605_4b50u32.to_le_bytes());
+    archive.extend(0u16.to_le_bytes());
+    archive.extend(0u16.to_le_bytes());
+    archive.extend((files.len() as u16).to_le_bytes());
+    archive.extend((files.len() as u16).to_le_bytes());
+    archive.extend(directory_size.to_le_bytes());
+    archive.extend(directory_offset.to_le_bytes());
+    archive.extend(0u16.to_le_bytes());
+    archive
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal_macros::dec;
+
+    #[test]
+    fn test_workbook() {
+        assert_eq!([column(0), column(25), column(26), column(701), column(702)], ["A", "Z", "AA", "ZZ", "AAA"]);
+        assert_eq!(serial(NaiveDate::from_ymd_opt(2025, 1, 1).unwrap()), 45658);
+        assert_eq!(crc32(b"123456789"), 0xCBF4_3926);
+        assert_eq!(sheet_names(&["main", "Main", "a/b", &"x".repeat(40)]), ["main", "Main (2)", "a_b", &"x".repeat(31)]);
+        assert_eq!(sheet_names(&["history", "'quoted'", "it's"]), ["history (2)", "_quoted_", "it's"]);
+
+        let sheet = Sheet {
+            name: "main".to_string(),
+            rows: vec![
+                vec![Cell::Heading("Date".to_string()), Cell::Heading("Balance".to_string())],
+                vec![Cell::Date(NaiveDate::from_ymd_opt(2025, 1, 1).unwrap()), Cell::Money(dec!(12.50)), Cell::Empty, Cell::Formula("B2*2".to_string())],
+            ],
+        };
+        assert!(worksheet(&sheet).contains(
+            "<row r=\"2\"><c r=\"A2\" s=\"2\"><v>45658</v></c><c r=\"B2\" s=\"3\"><v>12.5</v></c><c r=\"D2\" s=\"3\"><f>B2*2</f></c></row>"
+        ));
+        let bytes = workbook(&[sheet], "£");
+        // A zip archive of the parts, ending with its directory of them
+        assert_eq!(bytes[..4], [0x50, 0x4b, 0x03, 0x04]);
+        assert_eq!(bytes[bytes.len() - 22..bytes.len() - 18], [0x50, 0x4b, 0x05, 0x06]);
+        assert_eq!(u16::from_le_bytes([bytes[bytes.len() - 12], bytes[bytes.len() - 11]]), 6);
+    }
+}